@@ -0,0 +1,409 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A target runner that executes test binaries on an Android device over `adb`.
+//!
+//! Configured as, for example:
+//!
+//! ```toml
+//! [target.aarch64-linux-android]
+//! runner = "cargo-nextest adb-runner"
+//! ```
+
+use crate::{output::OutputContext, ExpectedError, Result};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use clap::Args;
+use std::{
+    collections::BTreeSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    process::{Command, Stdio},
+};
+use tracing::debug;
+
+/// Environment variable prefixes that are forwarded to the device.
+const FORWARDED_ENV_PREFIXES: &[&str] = &["NEXTEST_", "CARGO_", "RUST_"];
+
+#[derive(Debug, Args)]
+pub(crate) struct AdbRunnerOpts {
+    /// The path to adb.
+    #[arg(long, env = "ADB", default_value = "adb")]
+    adb: String,
+
+    /// Serial number of the device to run on [default: the only attached device].
+    #[arg(long, short = 's', env = "ANDROID_SERIAL")]
+    serial: Option<String>,
+
+    /// Directory on the device that binaries and extra files are pushed to.
+    #[arg(
+        long,
+        value_name = "DIR",
+        env = "NEXTEST_ADB_REMOTE_DIR",
+        default_value = "/data/local/tmp/nextest"
+    )]
+    remote_dir: String,
+
+    /// Additional files or directories to push into the package directory on the device.
+    ///
+    /// Relative paths are resolved against the package directory, and keep their location
+    /// within it on the device. Other paths are pushed to the top of the package directory.
+    /// Paths are pushed again whenever a file's size or modification time changes.
+    #[arg(long = "push", value_name = "PATH")]
+    push: Vec<Utf8PathBuf>,
+
+    /// Additional environment variables to forward to the device.
+    ///
+    /// Variables starting with NEXTEST_, CARGO_ and RUST_ are always forwarded.
+    #[arg(long = "env", value_name = "NAME")]
+    env: Vec<String>,
+
+    /// The test binary to run.
+    program: Utf8PathBuf,
+
+    /// Arguments to pass to the test binary.
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+impl AdbRunnerOpts {
+    // output is passed in to ensure that the context is initialized.
+    pub(crate) fn exec(self, _output: OutputContext) -> Result<i32> {
+        // Nextest runs tests from their package directory, so that's what the working directory
+        // on the device mirrors.
+        let package_dir = current_dir()?;
+        let remote_program = self.remote_program_path()?;
+        let work_dir = self.remote_work_dir(&package_dir)?;
+
+        // Find out which of the binary and the working directory are already on the device, in a
+        // single round trip.
+        let present = self.existing_remote_paths(&[remote_program.clone(), work_dir.clone()])?;
+        if !present.contains(&remote_program) {
+            self.push_program(&remote_program)?;
+        }
+        if !present.contains(&work_dir) {
+            self.push_work_dir(&package_dir, &work_dir)?;
+        }
+
+        let env: Vec<_> = std::env::vars()
+            .filter(|(name, _)| self.should_forward(name))
+            .map(|(name, value)| {
+                // Point tests at the package directory on the device.
+                let value = if name == "CARGO_MANIFEST_DIR" {
+                    work_dir.clone()
+                } else {
+                    value
+                };
+                (name, value)
+            })
+            .collect();
+        let command = remote_command(&work_dir, &env, &remote_program, &self.args);
+        debug!("running on device: {command}");
+
+        // Output is streamed directly to nextest, and adb's shell protocol propagates the remote
+        // exit code.
+        let mut adb = self.adb_command();
+        adb.args(["shell", command.as_str()]);
+        let status = adb
+            .status()
+            .map_err(|err| ExpectedError::AdbRunnerExecError {
+                command: format!("{adb:?}"),
+                err,
+            })?;
+        Ok(status.code().unwrap_or(1))
+    }
+
+    fn should_forward(&self, name: &str) -> bool {
+        is_valid_env_name(name)
+            && (FORWARDED_ENV_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+                || self.env.iter().any(|env| env == name))
+    }
+
+    fn remote_program_path(&self) -> Result<String> {
+        let file_name = self.program.file_name().unwrap_or("test-binary");
+        let mut hasher = DefaultHasher::new();
+        hash_metadata(&self.program, &mut hasher)?;
+        Ok(format!(
+            "{}/bin/{:016x}-{file_name}",
+            self.remote_dir,
+            hasher.finish()
+        ))
+    }
+
+    /// Returns the package directory on the device.
+    ///
+    /// Each version of the pushed paths gets a directory of its own, which isn't changed once
+    /// it's in place. This way, pushing updated paths never affects tests that are already
+    /// running.
+    fn remote_work_dir(&self, package_dir: &Utf8Path) -> Result<String> {
+        let mut hasher = DefaultHasher::new();
+        package_dir.hash(&mut hasher);
+        for path in &self.push {
+            push_dest(package_dir, path).hash(&mut hasher);
+            hash_metadata(&package_dir.join(path), &mut hasher)?;
+        }
+        let package_name = package_dir.file_name().unwrap_or("package");
+        Ok(format!(
+            "{}/work/{package_name}-{:016x}",
+            self.remote_dir,
+            hasher.finish()
+        ))
+    }
+
+    fn existing_remote_paths(&self, paths: &[String]) -> Result<BTreeSet<String>> {
+        let quoted = shell_words::join(paths);
+        // ls exits non-zero if any path is missing, so the status isn't checked.
+        let output = self.adb_output(&["shell", &format!("ls -d {quoted} 2>/dev/null")])?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().to_owned())
+            .collect())
+    }
+
+    fn push_program(&self, remote_program: &str) -> Result<()> {
+        // Push to a temporary name and rename it into place, so that tests running in parallel
+        // never see a partially-pushed binary.
+        let temp = format!("{remote_program}.{}.tmp", std::process::id());
+        self.shell_checked(&format!(
+            "mkdir -p {}",
+            shell_words::quote(&format!("{}/bin", self.remote_dir))
+        ))?;
+        self.adb_checked(&["push", self.program.as_str(), &temp])?;
+        self.shell_checked(&format!(
+            "chmod 755 {temp} && mv -f {temp} {dest}",
+            temp = shell_words::quote(&temp),
+            dest = shell_words::quote(remote_program),
+        ))
+    }
+
+    fn push_work_dir(&self, package_dir: &Utf8Path, work_dir: &str) -> Result<()> {
+        // Set up the directory under a temporary name and rename it into place, so that tests
+        // never see a partially-pushed directory.
+        let temp = format!("{work_dir}.{}.tmp", std::process::id());
+        self.shell_checked(&format!(
+            "rm -rf {temp} && mkdir -p {temp}",
+            temp = shell_words::quote(&temp)
+        ))?;
+        for path in &self.push {
+            let dest = format!("{temp}/{}", push_dest(package_dir, path));
+            if let Some((parent, _)) = dest.rsplit_once('/') {
+                self.shell_checked(&format!("mkdir -p {}", shell_words::quote(parent)))?;
+            }
+            self.adb_checked(&["push", package_dir.join(path).as_str(), &dest])?;
+        }
+
+        // If another process got there first, its copy is identical: use it. Otherwise, move
+        // this one into place. (If the other process wins the race in between, mv moves the
+        // temporary directory into its copy, so remove it from there.)
+        self.shell_checked(&format!(
+            "if [ -d {dest} ]; then rm -rf {temp}; else mv {temp} {dest} && rm -rf {dest}/{nested}; fi",
+            dest = shell_words::quote(work_dir),
+            temp = shell_words::quote(&temp),
+            nested = shell_words::quote(remote_file_name(&temp)),
+        ))
+    }
+
+    fn shell_checked(&self, command: &str) -> Result<()> {
+        self.adb_checked(&["shell", command])
+    }
+
+    fn adb_checked(&self, args: &[&str]) -> Result<()> {
+        let output = self.adb_output(args)?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(ExpectedError::AdbRunnerCommandFailed {
+                command: format!("{} {}", self.adb, shell_words::join(args)),
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            })
+        }
+    }
+
+    fn adb_output(&self, args: &[&str]) -> Result<std::process::Output> {
+        let mut command = self.adb_command();
+        command.args(args).stdin(Stdio::null());
+        debug!("executing {command:?}");
+        command
+            .output()
+            .map_err(|err| ExpectedError::AdbRunnerExecError {
+                command: format!("{command:?}"),
+                err,
+            })
+    }
+
+    fn adb_command(&self) -> Command {
+        let mut command = Command::new(&self.adb);
+        if let Some(serial) = &self.serial {
+            command.args(["-s", serial.as_str()]);
+        }
+        command
+    }
+}
+
+fn current_dir() -> Result<Utf8PathBuf> {
+    let dir = std::env::current_dir().map_err(|err| ExpectedError::AdbRunnerExecError {
+        command: "getcwd".to_owned(),
+        err,
+    })?;
+    Utf8PathBuf::try_from(dir).map_err(|err| ExpectedError::AdbRunnerExecError {
+        command: "getcwd".to_owned(),
+        err: err.into_io_error(),
+    })
+}
+
+/// Returns where `path` is pushed to, relative to the package directory on the device.
+///
+/// Relative paths that stay within the package directory keep their location, and everything
+/// else is pushed to the top of the package directory.
+fn push_dest(package_dir: &Utf8Path, path: &Utf8Path) -> String {
+    let relative = if path.is_absolute() {
+        path.strip_prefix(package_dir).ok()
+    } else {
+        Some(path)
+    };
+    let components = relative.and_then(|relative| {
+        relative
+            .components()
+            .filter(|component| *component != Utf8Component::CurDir)
+            .map(|component| match component {
+                Utf8Component::Normal(name) => Some(name),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+    });
+    match components {
+        Some(components) if !components.is_empty() => components.join("/"),
+        _ => path.file_name().unwrap_or("push").to_owned(),
+    }
+}
+
+/// Hashes the path, size and modification time of `path`, and of everything under it if it's a
+/// directory.
+///
+/// This is run for every test, and test binaries can be large, so file contents aren't hashed.
+/// The hash changes whenever a file is rebuilt or edited.
+fn hash_metadata(path: &Utf8Path, hasher: &mut DefaultHasher) -> Result<()> {
+    let stat_error = |err| ExpectedError::AdbRunnerExecError {
+        command: format!("stat {path}"),
+        err,
+    };
+    let metadata = path.metadata().map_err(stat_error)?;
+    path.hash(hasher);
+    metadata.len().hash(hasher);
+    metadata.modified().ok().hash(hasher);
+
+    if metadata.is_dir() {
+        let mut entries = path
+            .read_dir_utf8()
+            .map_err(stat_error)?
+            .map(|entry| entry.map(|entry| entry.into_path()))
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(stat_error)?;
+        entries.sort();
+        for entry in entries {
+            hash_metadata(&entry, hasher)?;
+        }
+    }
+    Ok(())
+}
+
+fn remote_file_name(path: &str) -> &str {
+    path.rsplit_once('/').map_or(path, |(_, name)| name)
+}
+
+fn is_valid_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn remote_command(
+    work_dir: &str,
+    env: &[(String, String)],
+    program: &str,
+    args: &[String],
+) -> String {
+    let mut command = format!("cd {} &&", shell_words::quote(work_dir));
+    for (name, value) in env {
+        command.push_str(&format!(" {name}={}", shell_words::quote(value)));
+    }
+    command.push_str(&format!(" {}", shell_words::quote(program)));
+    for arg in args {
+        command.push_str(&format!(" {}", shell_words::quote(arg)));
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_command() {
+        let env = vec![
+            ("NEXTEST_TEST_NAME".to_owned(), "tests::it works".to_owned()),
+            ("RUST_BACKTRACE".to_owned(), "1".to_owned()),
+        ];
+        let args = vec![
+            "--exact".to_owned(),
+            "tests::it works".to_owned(),
+            "--nocapture".to_owned(),
+        ];
+        assert_eq!(
+            remote_command("/data/local/tmp/nextest/work", &env, "/data/bin/t", &args),
+            "cd /data/local/tmp/nextest/work && \
+             NEXTEST_TEST_NAME='tests::it works' RUST_BACKTRACE=1 /data/bin/t \
+             --exact 'tests::it works' --nocapture",
+        );
+    }
+
+    #[test]
+    fn test_push_dest() {
+        let package_dir = Utf8Path::new("/home/me/ws/my-package");
+        for (path, expected) in [
+            ("tests/data", "tests/data"),
+            ("data.json", "data.json"),
+            ("/home/me/ws/my-package/tests/data", "tests/data"),
+            ("/home/me/ws/shared/data", "data"),
+            ("../shared/data", "data"),
+            ("./tests/data", "tests/data"),
+        ] {
+            assert_eq!(
+                push_dest(package_dir, Utf8Path::new(path)),
+                expected,
+                "for path {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_metadata() {
+        let dir = camino_tempfile::Utf8TempDir::new().unwrap();
+        let nested = dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        std::fs::write(nested.join("file"), "a").unwrap();
+
+        let hash = || {
+            let mut hasher = DefaultHasher::new();
+            hash_metadata(dir.path(), &mut hasher).unwrap();
+            hasher.finish()
+        };
+        let before = hash();
+        assert_eq!(before, hash(), "hash is stable");
+
+        // Changing a nested file's size doesn't change the directory's own metadata, but it
+        // does change the hash.
+        std::fs::write(nested.join("file"), "ab").unwrap();
+        assert_ne!(before, hash(), "hash changes with nested files");
+    }
+
+    #[test]
+    fn test_is_valid_env_name() {
+        assert!(is_valid_env_name("RUST_LOG"));
+        assert!(is_valid_env_name("_foo1"));
+        assert!(!is_valid_env_name(""));
+        assert!(!is_valid_env_name("1FOO"));
+        assert!(!is_valid_env_name("FOO-BAR"));
+    }
+}
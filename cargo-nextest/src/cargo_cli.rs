@@ -6,10 +6,12 @@
 use crate::output::OutputContext;
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{ArgAction, Args};
+use guppy::graph::PackageGraph;
+use nextest_runner::list::{FeatureMatrix, FeatureSelection};
 use std::{borrow::Cow, path::PathBuf};
 
 /// Options passed down to cargo.
-#[derive(Debug, Args)]
+#[derive(Clone, Debug, Args)]
 #[command(
     group = clap::ArgGroup::new("cargo-opts").multiple(true),
 )]
@@ -201,6 +203,41 @@ pub(crate) struct CargoOptions {
     unstable_flags: Vec<String>,
 }
 
+impl CargoOptions {
+    /// Returns the name of the Cargo profile that tests are built with.
+    pub(crate) fn cargo_profile_name(&self) -> &str {
+        match &self.cargo_profile {
+            Some(profile) => profile,
+            None if self.release => "release",
+            None => "dev",
+        }
+    }
+
+    /// Returns true if any target selection options were passed in.
+    /// Computes the features affecting each test target in the selected packages.
+    pub(crate) fn compute_feature_matrix(&self, graph: &PackageGraph) -> FeatureMatrix {
+        let selection = FeatureSelection {
+            features: self.features.clone(),
+            all_features: self.all_features,
+            no_default_features: self.no_default_features,
+        };
+        FeatureMatrix::new(graph, &self.packages, &self.exclude, &selection)
+    }
+
+    pub(crate) fn has_target_selection(&self) -> bool {
+        self.lib
+            || !self.bin.is_empty()
+            || self.bins
+            || !self.example.is_empty()
+            || self.examples
+            || !self.test.is_empty()
+            || self.tests
+            || !self.bench.is_empty()
+            || self.benches
+            || self.all_targets
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct CargoCli<'a> {
     cargo_path: Utf8PathBuf,
@@ -423,7 +460,7 @@ impl<'a> CargoCli<'a> {
     }
 }
 
-fn cargo_path() -> Utf8PathBuf {
+pub(crate) fn cargo_path() -> Utf8PathBuf {
     match std::env::var_os("CARGO") {
         Some(cargo_path) => PathBuf::from(cargo_path)
             .try_into()
@@ -3,12 +3,15 @@
 
 use crate::{
     cargo_cli::{CargoCli, CargoOptions},
+    generate_docs::{generate_docs, DocsFormat},
     output::{should_redact, OutputContext, OutputOpts, OutputWriter, StderrStyles},
-    reuse_build::{make_path_mapper, ArchiveFormatOpt, ReuseBuildOpts},
+    remap_matrix::{path_dependent_tests, RemapOutcomes},
+    reuse_build::{make_path_mapper, ArchiveExtractOpts, ArchiveFormatOpt, ReuseBuildOpts},
     version, ExpectedError, Result, ReuseBuildKind,
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{builder::BoolishValueParser, ArgAction, Args, Parser, Subcommand, ValueEnum};
+use globset::{GlobBuilder, GlobMatcher};
 use guppy::graph::PackageGraph;
 use itertools::Itertools;
 use nextest_filtering::{EvalContext, Filterset, FiltersetKind, ParseContext};
@@ -16,31 +19,44 @@ use nextest_metadata::BuildPlatform;
 use nextest_runner::{
     cargo_config::{CargoConfigs, EnvironmentMap, TargetTriple},
     config::{
-        get_num_cpus, ConfigExperimental, EarlyProfile, MaxFail, NextestConfig,
+        get_num_cpus, ConfigExperimental, EarlyProfile, MaxFail, MaxFailScope, NextestConfig,
         NextestVersionConfig, NextestVersionEval, RetryPolicy, TestGroup, TestThreads,
         ToolConfigFile, VersionOnlyConfig,
     },
     double_spawn::DoubleSpawnInfo,
     errors::{TargetTripleError, WriteTestListError},
+    flaky::{FlakyHistory, FlakyTests, FlakyUpdateParams},
     input::InputHandlerKind,
     list::{
-        BinaryList, OutputFormat, RustTestArtifact, SerializableFormat, TestExecuteContext,
-        TestList,
+        BinaryList, CheckedTestList, OutputFormat, RustTestArtifact, SerializableFormat,
+        TestExecuteContext, TestList,
     },
     partition::PartitionerBuilder,
     platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
     redact::Redactor,
     reporter::{
+        diff::{ReportDiff, ReportDiffParams},
         events::{FinalRunStats, RunStatsFailureKind},
-        highlight_end, structured, FinalStatusLevel, ReporterBuilder, StatusLevel,
-        TestOutputDisplay, TestOutputErrorSlice,
+        highlight_end,
+        merge::MergedReport,
+        structured,
+        timings::{TimingReport, TimingReportParams},
+        FinalStatusLevel, GroupBy, ReporterBuilder, StatusLevel, TestOutputDisplay,
+        TestOutputErrorSlice,
+    },
+    reuse_build::{
+        archive_to_file, ArchiveReporter, PathMapper, ReuseBuildInfo, SourceFingerprint,
     },
-    reuse_build::{archive_to_file, ArchiveReporter, PathMapper, ReuseBuildInfo},
     runner::{configure_handle_inheritance, TestRunnerBuilder},
-    show_config::{ShowNextestVersion, ShowTestGroupSettings, ShowTestGroups, ShowTestGroupsMode},
+    show_config::{
+        ShowNextestVersion, ShowTestGroupSettings, ShowTestGroups, ShowTestGroupsMode,
+        ShowTestSettings,
+    },
     signal::SignalHandlerKind,
     target_runner::{PlatformRunner, TargetRunner},
     test_filter::{FilterBound, RunIgnored, TestFilterBuilder, TestFilterPatterns},
+    user_config::UserConfig,
+    warnings::{self, WarningCode},
     write_str::WriteStr,
     RustcCli,
 };
@@ -84,6 +100,8 @@ impl CargoNextestApp {
             #[cfg(unix)]
             // Double-spawned processes should never use coloring.
             NextestSubcommand::DoubleSpawn(_) => OutputContext::color_never_init(),
+            // Runner output is interleaved with test output, so never color it.
+            NextestSubcommand::AdbRunner(_) => OutputContext::color_never_init(),
         }
     }
 
@@ -95,10 +113,17 @@ impl CargoNextestApp {
         output_writer: &mut OutputWriter,
     ) -> Result<i32> {
         match self.subcommand {
-            NextestSubcommand::Nextest(app) => app.exec(cli_args, output, output_writer),
-            NextestSubcommand::Ntr(opts) => opts.exec(cli_args, output, output_writer),
+            NextestSubcommand::Nextest(app) => {
+                let warning_opts = app.common.warning_opts.clone();
+                warning_opts.check(app.exec(cli_args, output, output_writer)?)
+            }
+            NextestSubcommand::Ntr(opts) => {
+                let warning_opts = opts.common.warning_opts.clone();
+                warning_opts.check(opts.exec(cli_args, output, output_writer)?)
+            }
             #[cfg(unix)]
             NextestSubcommand::DoubleSpawn(opts) => opts.exec(output),
+            NextestSubcommand::AdbRunner(opts) => opts.exec(output),
         }
     }
 }
@@ -113,6 +138,9 @@ enum NextestSubcommand {
     #[cfg(unix)]
     #[command(name = nextest_runner::double_spawn::DoubleSpawnInfo::SUBCOMMAND_NAME, hide = true)]
     DoubleSpawn(crate::double_spawn::DoubleSpawnOpts),
+    /// Target runner that executes test binaries on an Android device via adb.
+    #[command(name = "adb-runner", hide = true)]
+    AdbRunner(crate::adb_runner::AdbRunnerOpts),
 }
 
 #[derive(Debug, Args)]
@@ -145,8 +173,10 @@ impl AppOpts {
                 build_filter,
                 message_format,
                 list_type,
+                compact,
                 reuse_build,
-                ..
+                list_with_check,
+                all_features_matrix,
             } => {
                 let base = BaseApp::new(
                     output,
@@ -156,35 +186,36 @@ impl AppOpts {
                     self.common.manifest_path,
                     output_writer,
                 )?;
-                let app = App::new(base, build_filter)?;
-                app.exec_list(message_format, list_type, output_writer)?;
-                Ok(0)
-            }
-            Command::Run(run_opts) => {
-                let base = BaseApp::new(
-                    output,
-                    run_opts.reuse_build,
-                    run_opts.cargo_options,
-                    self.common.config_opts,
-                    self.common.manifest_path,
-                    output_writer,
-                )?;
-                let app = App::new(base, run_opts.build_filter)?;
-                app.exec_run(
-                    run_opts.no_capture,
-                    &run_opts.runner_opts,
-                    &run_opts.reporter_opts,
-                    cli_args,
-                    output_writer,
-                )?;
+                if list_with_check {
+                    base.exec_list_with_check(message_format, output_writer)?;
+                } else if all_features_matrix {
+                    base.exec_feature_matrix(message_format, output_writer)?;
+                } else {
+                    let app = App::new(base, build_filter)?;
+                    app.exec_list(message_format, list_type, compact, output_writer)?;
+                }
                 Ok(0)
             }
+            Command::Run(run_opts) => run_opts.exec(
+                self.common.config_opts,
+                self.common.manifest_path,
+                cli_args,
+                output,
+                output_writer,
+            ),
+            Command::Archive {
+                command: Some(ArchiveCommand::Extract(opts)),
+                ..
+            } => opts.exec(output, output_writer),
             Command::Archive {
+                command: None,
                 cargo_options,
                 archive_file,
                 archive_format,
                 zstd_level,
             } => {
+                let archive_file =
+                    archive_file.expect("--archive-file is required without a subcommand");
                 let app = BaseApp::new(
                     output,
                     ReuseBuildOpts::default(),
@@ -202,8 +233,25 @@ impl AppOpts {
                 output,
                 output_writer,
             ),
-            Command::Self_ { command } => command.exec(self.common.output),
-            Command::Debug { command } => command.exec(self.common.output),
+            Command::Flaky { command } => command.exec(
+                self.common.manifest_path,
+                self.common.config_opts,
+                output,
+                output_writer,
+            ),
+            Command::Report { command } => command.exec(output, output_writer),
+            Command::Miri(opts) => opts.exec(cli_args),
+            Command::Self_ { command } => command.exec(
+                self.common.manifest_path,
+                self.common.config_opts,
+                self.common.output,
+            ),
+            Command::Debug { command } => command.exec(
+                self.common.manifest_path,
+                self.common.config_opts,
+                output,
+                output_writer,
+            ),
         }
     }
 }
@@ -225,9 +273,55 @@ struct CommonOpts {
 
     #[clap(flatten)]
     config_opts: ConfigOpts,
+
+    #[clap(flatten)]
+    warning_opts: WarningOpts,
 }
 
-#[derive(Debug, Args)]
+#[derive(Clone, Debug, Args)]
+#[command(next_help_heading = "Warning options")]
+struct WarningOpts {
+    /// Treat warnings as errors
+    ///
+    /// If nextest produced any warnings, fail with exit code 91 once the command
+    /// otherwise succeeds. Each warning is shown with a code in brackets, for
+    /// example `[unknown-config-keys]`.
+    #[arg(
+        long,
+        global = true,
+        env = "NEXTEST_DENY_WARNINGS",
+        value_parser = BoolishValueParser::new()
+    )]
+    deny_warnings: bool,
+
+    /// Don't treat warnings with this code as errors
+    ///
+    /// Can be specified multiple times.
+    #[arg(long = "allow-warning", global = true, value_name = "CODE")]
+    allow_warnings: Vec<WarningCode>,
+}
+
+impl WarningOpts {
+    /// Turns a successful exit code into an error if denied warnings were
+    /// produced.
+    fn check(&self, exit_code: i32) -> Result<i32> {
+        if !self.deny_warnings || exit_code != 0 {
+            return Ok(exit_code);
+        }
+
+        let codes: Vec<_> = warnings::emitted()
+            .into_iter()
+            .filter(|code| !self.allow_warnings.contains(code))
+            .collect();
+        if codes.is_empty() {
+            Ok(exit_code)
+        } else {
+            Err(ExpectedError::WarningsDenied { codes })
+        }
+    }
+}
+
+#[derive(Clone, Debug, Args)]
 #[command(next_help_heading = "Config options")]
 struct ConfigOpts {
     /// Config file [default: workspace-root/.config/nextest.toml]
@@ -262,14 +356,18 @@ struct ConfigOpts {
     /// Nextest's configuration supports multiple profiles, which can be used to set up different
     /// configurations for different purposes. (For example, a configuration for local runs and one
     /// for CI.) This option selects the profile to use.
+    ///
+    /// This option can be specified multiple times (or as a comma-separated list) to compose
+    /// profiles: settings in later profiles override those in earlier ones.
     #[arg(
         long,
         short = 'P',
         env = "NEXTEST_PROFILE",
         global = true,
+        value_delimiter = ',',
         help_heading = "Config options"
     )]
-    profile: Option<String>,
+    profile: Vec<String>,
 }
 
 impl ConfigOpts {
@@ -341,6 +439,57 @@ enum Command {
         )]
         list_type: ListType,
 
+        /// Omit the reasons tests don't match filters from JSON output
+        ///
+        /// This makes the output smaller and faster to produce for very large test lists. Has no
+        /// effect with `--message-format human`.
+        #[arg(
+            long,
+            help_heading = "Output options",
+            conflicts_with_all = ["list_with_check", "all_features_matrix"],
+        )]
+        compact: bool,
+
+        /// List tests by scanning source code after `cargo check`, without linking test binaries
+        ///
+        /// This is faster than a full listing in large workspaces where linking dominates, but
+        /// inexact: tests generated by macros are missed, tests disabled via `cfg` are included,
+        /// and filters are not supported.
+        #[arg(
+            long,
+            help_heading = "Output options",
+            conflicts_with_all = [
+                "list_type",
+                "run_ignored",
+                "partition",
+                "filterset",
+                "FILTERS",
+                "filters",
+                "binaries-metadata-sources",
+            ],
+        )]
+        list_with_check: bool,
+
+        /// Show the features affecting each test target, and which targets the current feature
+        /// selection adds or removes compared to the default features
+        ///
+        /// This reads Cargo metadata only, without building anything.
+        #[arg(
+            long,
+            help_heading = "Output options",
+            conflicts_with_all = [
+                "list_type",
+                "list_with_check",
+                "run_ignored",
+                "partition",
+                "filterset",
+                "FILTERS",
+                "filters",
+                "binaries-metadata-sources",
+            ],
+        )]
+        all_features_matrix: bool,
+
         #[clap(flatten)]
         reuse_build: ReuseBuildOpts,
     },
@@ -359,7 +508,13 @@ enum Command {
     /// --archive-file`.
     ///
     /// The archive is a tarball compressed with Zstandard (.tar.zst).
+    ///
+    /// Use `cargo nextest archive extract` to unpack an archive without running tests.
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Archive {
+        #[command(subcommand)]
+        command: Option<ArchiveCommand>,
+
         #[clap(flatten)]
         cargo_options: CargoOptions,
 
@@ -367,10 +522,11 @@ enum Command {
         #[arg(
             long,
             name = "archive-file",
+            required = true,
             help_heading = "Archive options",
             value_name = "PATH"
         )]
-        archive_file: Utf8PathBuf,
+        archive_file: Option<Utf8PathBuf>,
 
         /// Archive format
         ///
@@ -406,6 +562,32 @@ enum Command {
         #[clap(subcommand)]
         command: ShowConfigCommand,
     },
+    /// Maintain the list of flaky tests
+    ///
+    /// Nextest records the outcome of each test in every run. These commands use that history to
+    /// maintain a list of flaky tests in `.config/flaky-tests.toml`, which can be checked into the
+    /// repository. Tests in the list are retried according to the `flaky-test-retries` setting.
+    ///
+    /// For more information, see <https://nexte.st/docs/features/flaky-tests>.
+    Flaky {
+        #[clap(subcommand)]
+        command: FlakyCommand,
+    },
+    /// Work with reports produced by test runs
+    ///
+    /// For more information, see <https://nexte.st/docs/ci-features/partitioning>.
+    Report {
+        #[clap(subcommand)]
+        command: ReportCommand,
+    },
+    /// Build and run tests under Miri
+    ///
+    /// Runs `cargo miri nextest` with the remaining arguments, so that `cargo nextest miri run`
+    /// builds tests with Miri's sysroot and runs them under the Miri interpreter. Under Miri,
+    /// nextest uses the `default-miri` profile and does not double-spawn test processes.
+    ///
+    /// For more information, see <https://nexte.st/docs/integrations/miri>.
+    Miri(MiriOpts),
     /// Manage the nextest installation
     #[clap(name = "self")]
     Self_ {
@@ -439,20 +621,11 @@ impl NtrOpts {
         output: OutputContext,
         output_writer: &mut OutputWriter,
     ) -> Result<i32> {
-        let base = BaseApp::new(
-            output,
-            self.run_opts.reuse_build,
-            self.run_opts.cargo_options,
+        self.run_opts.exec(
             self.common.config_opts,
             self.common.manifest_path,
-            output_writer,
-        )?;
-        let app = App::new(base, self.run_opts.build_filter)?;
-        app.exec_run(
-            self.run_opts.no_capture,
-            &self.run_opts.runner_opts,
-            &self.run_opts.reporter_opts,
             cli_args,
+            output,
             output_writer,
         )
     }
@@ -486,6 +659,116 @@ struct RunOpts {
     reuse_build: ReuseBuildOpts,
 }
 
+impl RunOpts {
+    fn exec(
+        self,
+        config_opts: ConfigOpts,
+        manifest_path: Option<Utf8PathBuf>,
+        cli_args: Vec<String>,
+        output: OutputContext,
+        output_writer: &mut OutputWriter,
+    ) -> Result<i32> {
+        if self.reuse_build.workspace_remap.len() > 1 {
+            return self.exec_remap_matrix(
+                config_opts,
+                manifest_path,
+                cli_args,
+                output,
+                output_writer,
+            );
+        }
+
+        let base = BaseApp::new(
+            output,
+            self.reuse_build,
+            self.cargo_options,
+            config_opts,
+            manifest_path,
+            output_writer,
+        )?;
+        let app = App::new(base, self.build_filter)?;
+        app.exec_run(
+            self.no_capture,
+            &self.runner_opts,
+            &self.reporter_opts,
+            cli_args,
+            None,
+            output_writer,
+        )
+    }
+
+    /// Runs tests once for each workspace remap, then reports tests whose results differ between
+    /// remaps.
+    fn exec_remap_matrix(
+        self,
+        config_opts: ConfigOpts,
+        manifest_path: Option<Utf8PathBuf>,
+        cli_args: Vec<String>,
+        output: OutputContext,
+        output_writer: &mut OutputWriter,
+    ) -> Result<i32> {
+        let styles = output.stderr_styles();
+        let mut runs = Vec::with_capacity(self.reuse_build.workspace_remap.len());
+        let mut any_failed = false;
+
+        for remap in &self.reuse_build.workspace_remap {
+            info!(
+                "running tests with workspace remapped to {}",
+                remap.style(styles.bold)
+            );
+            let base = BaseApp::new(
+                output,
+                self.reuse_build.with_workspace_remap(remap),
+                self.cargo_options.clone(),
+                config_opts.clone(),
+                manifest_path.clone(),
+                output_writer,
+            )?;
+            let app = App::new(base, self.build_filter.clone())?;
+            let mut outcomes = RemapOutcomes::default();
+            match app.exec_run(
+                self.no_capture,
+                &self.runner_opts,
+                &self.reporter_opts,
+                cli_args.clone(),
+                Some(&mut outcomes),
+                output_writer,
+            ) {
+                Ok(_) => {}
+                // Keep going: failures are compared across remaps below.
+                Err(ExpectedError::TestRunFailed) => any_failed = true,
+                Err(err) => return Err(err),
+            }
+            runs.push((remap.clone(), outcomes));
+        }
+
+        let path_dependent = path_dependent_tests(&runs);
+        for test in &path_dependent {
+            warn!(
+                "{} {}: passed with {}, failed with {}",
+                test.binary_id.style(styles.bold),
+                test.test_name.style(styles.bold),
+                test.passed.iter().join(", "),
+                test.failed.iter().join(", "),
+            );
+        }
+
+        if !path_dependent.is_empty() {
+            Err(ExpectedError::PathDependentTests {
+                count: path_dependent.len(),
+            })
+        } else if any_failed {
+            Err(ExpectedError::test_run_failed())
+        } else {
+            info!(
+                "test results were identical across {} workspace remaps",
+                runs.len()
+            );
+            Ok(0)
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, ValueEnum)]
 pub(crate) enum PlatformFilterOpts {
     Target,
@@ -544,7 +827,7 @@ impl Default for MessageFormatOpts {
     }
 }
 
-#[derive(Debug, Args)]
+#[derive(Clone, Debug, Args)]
 #[command(next_help_heading = "Filter options")]
 struct TestBuildFilter {
     /// Run ignored tests
@@ -577,6 +860,29 @@ struct TestBuildFilter {
     )]
     filterset: Vec<String>,
 
+    /// Only list and run tests in binaries whose IDs match this glob
+    ///
+    /// Binaries that don't match are skipped before the list phase, so they are never executed to
+    /// find the tests within them. Can be specified multiple times, in which case binaries matching
+    /// any of the globs are included.
+    #[arg(
+        long,
+        value_name = "GLOB",
+        value_parser = parse_binary_id_glob,
+        action(ArgAction::Append)
+    )]
+    only_binaries: Vec<GlobMatcher>,
+
+    /// Number of test binaries to list simultaneously [possible values: integer or "num-cpus"]
+    /// [default: num-cpus]
+    #[arg(
+        long,
+        value_name = "N",
+        env = "NEXTEST_LIST_JOBS",
+        allow_negative_numbers = true
+    )]
+    list_jobs: Option<TestThreads>,
+
     /// Ignore the default filter configured in the profile.
     ///
     /// By default, all filtersets are intersected with the default filter configured in the
@@ -621,13 +927,20 @@ impl TestBuildFilter {
         )?;
 
         let rust_build_meta = binary_list.rust_build_meta.map_paths(&path_mapper);
-        let test_artifacts = RustTestArtifact::from_binary_list(
+        let mut test_artifacts = RustTestArtifact::from_binary_list(
             graph,
             binary_list,
             &rust_build_meta,
             &path_mapper,
             self.platform_filter.into(),
         )?;
+        if !self.only_binaries.is_empty() {
+            test_artifacts.retain(|artifact| {
+                self.only_binaries
+                    .iter()
+                    .any(|glob| glob.is_match(artifact.binary_id.as_str()))
+            });
+        }
         TestList::new(
             ctx,
             test_artifacts,
@@ -641,8 +954,9 @@ impl TestBuildFilter {
             } else {
                 FilterBound::DefaultSet
             },
-            // TODO: do we need to allow customizing this?
-            get_num_cpus(),
+            self.list_jobs
+                .map_or_else(get_num_cpus, |list_jobs| list_jobs.compute()),
+            true,
         )
         .map_err(|err| ExpectedError::CreateTestListError { err })
     }
@@ -776,6 +1090,38 @@ impl From<RunIgnoredOpt> for RunIgnored {
 }
 
 impl CargoOptions {
+    fn compute_checked_test_list(
+        &self,
+        graph: &PackageGraph,
+        manifest_path: Option<&Utf8Path>,
+        output: OutputContext,
+    ) -> Result<CheckedTestList> {
+        let mut cargo_cli = CargoCli::new("check", manifest_path, output);
+
+        cargo_cli.add_args(["--message-format", "json-render-diagnostics"]);
+        // Match the targets that `cargo test` builds by default.
+        if !self.has_target_selection() {
+            cargo_cli.add_arg("--tests");
+        }
+        cargo_cli.add_options(self);
+
+        let expression = cargo_cli.to_expression();
+        let output = expression
+            .stdout_capture()
+            .unchecked()
+            .run()
+            .map_err(|err| ExpectedError::build_exec_failed(cargo_cli.all_args(), err))?;
+        if !output.status.success() {
+            return Err(ExpectedError::build_failed(
+                cargo_cli.all_args(),
+                output.status.code(),
+            ));
+        }
+
+        let test_list = CheckedTestList::from_messages(Cursor::new(output.stdout), graph)?;
+        Ok(test_list)
+    }
+
     fn compute_binary_list(
         &self,
         graph: &PackageGraph,
@@ -818,6 +1164,13 @@ pub struct TestRunnerOpts {
     #[arg(long, name = "no-run")]
     no_run: bool,
 
+    /// Show what would be run, in which order, without running any tests
+    ///
+    /// Tests are listed and filtered, settings and setup scripts are resolved for each test, and
+    /// tests are shown in the order they'd be started in.
+    #[arg(long, conflicts_with = "no-run")]
+    dry_run: bool,
+
     /// Number of tests to run simultaneously [possible values: integer or "num-cpus"]
     /// [default: from profile]
     #[arg(
@@ -863,6 +1216,16 @@ pub struct TestRunnerOpts {
     )]
     max_fail: Option<MaxFail>,
 
+    /// Scope within which failures are counted for fail-fast and max-fail
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "no-run",
+        value_name = "SCOPE",
+        env = "NEXTEST_MAX_FAIL_SCOPE"
+    )]
+    max_fail_scope: Option<MaxFailScopeOpt>,
+
     /// Behavior if there are no tests to run [default: fail]
     #[arg(
         long,
@@ -873,6 +1236,78 @@ pub struct TestRunnerOpts {
         env = "NEXTEST_NO_TESTS"
     )]
     no_tests: Option<NoTestsBehavior>,
+
+    /// Record scheduling decisions to a JSON Lines file, for debugging
+    ///
+    /// Each line records when a test was queued, started, or finished, along with how many test
+    /// threads and test group slots were in use at the time.
+    #[arg(long, value_name = "PATH", conflicts_with = "no-run")]
+    debug_schedule: Option<Utf8PathBuf>,
+
+    /// Listen for control commands on a local socket for the duration of the run
+    ///
+    /// On Unix this is a Unix domain socket, and on Windows a named pipe (e.g.
+    /// `\\.\pipe\nextest`). Clients send `status`, `cancel` or `cancel-test <binary-id>
+    /// <test-name>`, one per line, and receive a line of JSON in response to each.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "no-run",
+        env = "NEXTEST_CONTROL_SOCKET"
+    )]
+    control_socket: Option<Utf8PathBuf>,
+
+    /// Exit with this code if the run succeeded, but some tests only passed after being retried
+    ///
+    /// By default, runs where flaky tests eventually passed exit with code 0. The code must be
+    /// between 2 and 255, and can't be one of the exit codes nextest uses for failures.
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with = "no-run",
+        value_parser = parse_exit_code_flaky,
+        env = "NEXTEST_EXIT_CODE_FLAKY"
+    )]
+    exit_code_flaky: Option<i32>,
+
+    /// Run tests even if they're banned in the configuration
+    ///
+    /// By default, tests that match a `[[profile.<name>.banned]]` entry aren't run, and are
+    /// reported as failing to start.
+    #[arg(long, conflicts_with = "no-run", env = "NEXTEST_ALLOW_BANNED")]
+    allow_banned: bool,
+
+    /// Keep failed tests' temporary directories, output and environment for inspection
+    ///
+    /// For each failed attempt, a directory is created under the profile's store directory,
+    /// and its path is printed alongside the failure.
+    #[arg(long, conflicts_with = "no-run")]
+    keep_failed_workdirs: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum MaxFailScopeOpt {
+    /// Count failures across the whole run, and cancel the run once the limit
+    /// is reached.
+    Run,
+
+    /// Count failures per package, and skip remaining tests in a package once
+    /// its limit is reached.
+    Package,
+
+    /// Count failures per test binary, and skip remaining tests in a binary
+    /// once its limit is reached.
+    Binary,
+}
+
+impl From<MaxFailScopeOpt> for MaxFailScope {
+    fn from(opt: MaxFailScopeOpt) -> Self {
+        match opt {
+            MaxFailScopeOpt::Run => MaxFailScope::Run,
+            MaxFailScopeOpt::Package => MaxFailScope::Package,
+            MaxFailScopeOpt::Binary => MaxFailScope::Binary,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -913,11 +1348,24 @@ impl TestRunnerOpts {
             builder.set_max_fail(MaxFail::from_fail_fast(true));
             debug!("set max fail via from_fail_fast(true)");
         }
+        if let Some(max_fail_scope) = self.max_fail_scope {
+            builder.set_max_fail_scope(max_fail_scope.into());
+        }
 
         if let Some(test_threads) = self.test_threads {
             builder.set_test_threads(test_threads);
         }
 
+        if let Some(debug_schedule) = &self.debug_schedule {
+            builder.set_debug_schedule(debug_schedule.clone());
+        }
+        if let Some(control_socket) = &self.control_socket {
+            builder.set_control_socket(control_socket.clone());
+        }
+
+        builder.set_allow_banned(self.allow_banned);
+        builder.set_keep_failed_workdirs(self.keep_failed_workdirs);
+
         Some(builder)
     }
 }
@@ -984,10 +1432,48 @@ struct ReporterOpts {
     )]
     final_status_level: Option<FinalStatusLevelOpt>,
 
+    /// How to group test results in the output
+    ///
+    /// With `binary`, the results of each test binary are shown together once
+    /// all of its tests are done, under a header with the binary's counts.
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "no-run",
+        value_name = "GROUP",
+        env = "NEXTEST_GROUP_BY"
+    )]
+    group_by: Option<GroupByOpt>,
+
     /// Do not display the progress bar
+    ///
+    /// Implied by `--status-level fail-only`.
     #[arg(long, env = "NEXTEST_HIDE_PROGRESS_BAR", value_parser = BoolishValueParser::new())]
     hide_progress_bar: bool,
 
+    /// Show the test running in each slot below the progress bar
+    ///
+    /// Useful for seeing which tests are holding up a run.
+    #[arg(
+        long,
+        conflicts_with = "hide_progress_bar",
+        env = "NEXTEST_SHOW_SLOTS",
+        value_parser = BoolishValueParser::new()
+    )]
+    show_slots: bool,
+
+    /// Periodically write a JSON summary of the run's progress to this file
+    ///
+    /// The file is atomically replaced every few seconds with the number of tests passed, failed
+    /// and still to run, the tests currently running, and an estimate of the time remaining.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "no-run",
+        env = "NEXTEST_STATUS_FILE"
+    )]
+    status_file: Option<Utf8PathBuf>,
+
     /// Disable handling of input keys from the terminal.
     ///
     /// By default, when running a terminal, nextest accepts the `t` key to dump
@@ -1039,7 +1525,14 @@ impl ReporterOpts {
         if let Some(final_status_level) = self.final_status_level {
             builder.set_final_status_level(final_status_level.into());
         }
+        if let Some(group_by) = self.group_by {
+            builder.set_group_by(group_by.into());
+        }
         builder.set_hide_progress_bar(self.hide_progress_bar);
+        builder.set_show_slots(self.show_slots);
+        if let Some(status_file) = &self.status_file {
+            builder.set_status_file(status_file.clone());
+        }
         builder
     }
 }
@@ -1063,9 +1556,25 @@ impl From<TestOutputDisplayOpt> for TestOutputDisplay {
     }
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum GroupByOpt {
+    None,
+    Binary,
+}
+
+impl From<GroupByOpt> for GroupBy {
+    fn from(opt: GroupByOpt) -> Self {
+        match opt {
+            GroupByOpt::None => GroupBy::None,
+            GroupByOpt::Binary => GroupBy::Binary,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum StatusLevelOpt {
     None,
+    FailOnly,
     Fail,
     Retry,
     Slow,
@@ -1079,6 +1588,7 @@ impl From<StatusLevelOpt> for StatusLevel {
     fn from(opt: StatusLevelOpt) -> Self {
         match opt {
             StatusLevelOpt::None => StatusLevel::None,
+            StatusLevelOpt::FailOnly => StatusLevel::FailOnly,
             StatusLevelOpt::Fail => StatusLevel::Fail,
             StatusLevelOpt::Retry => StatusLevel::Retry,
             StatusLevelOpt::Slow => StatusLevel::Slow,
@@ -1148,6 +1658,7 @@ impl BaseApp {
     ) -> Result<Self> {
         reuse_build.check_experimental(output);
 
+        let source_mismatch = reuse_build.source_mismatch;
         let reuse_build = reuse_build.process(output, writer)?;
 
         // First obtain the Cargo configs.
@@ -1220,7 +1731,13 @@ impl BaseApp {
             });
         }
 
-        let current_version = current_version();
+        source_mismatch.check(
+            &reuse_build,
+            &workspace_root,
+            config_opts.config_file.as_deref(),
+        )?;
+
+        let current_version = current_version();
 
         Ok(Self {
             output,
@@ -1291,10 +1808,13 @@ impl BaseApp {
                 current,
                 tool,
             } => {
-                warn!(
-                    "this repository recommends nextest version {}, but the current version is {}",
-                    required.style(styles.bold),
-                    current.style(styles.bold),
+                warnings::emit(
+                    WarningCode::RecommendedVersionNotMet,
+                    format_args!(
+                        "this repository recommends nextest version {}, but the current version is {}",
+                        required.style(styles.bold),
+                        current.style(styles.bold),
+                    ),
                 );
                 if let Some(tool) = tool {
                     info!(
@@ -1369,10 +1889,13 @@ impl BaseApp {
                 current,
                 tool,
             } => {
-                warn!(
-                    "this repository recommends nextest version {}, but the current version is {}",
-                    required.style(styles.bold),
-                    current.style(styles.bold),
+                warnings::emit(
+                    WarningCode::RecommendedVersionNotMet,
+                    format_args!(
+                        "this repository recommends nextest version {}, but the current version is {}",
+                        required.style(styles.bold),
+                        current.style(styles.bold),
+                    ),
                 );
                 if let Some(tool) = tool {
                     info!(
@@ -1402,25 +1925,44 @@ impl BaseApp {
     fn load_double_spawn(&self) -> &DoubleSpawnInfo {
         self.double_spawn.get_or_init(|| {
             if std::env::var("NEXTEST_EXPERIMENTAL_DOUBLE_SPAWN").is_ok() {
-                warn!(
-                    "double-spawn is no longer experimental: \
-                     NEXTEST_EXPERIMENTAL_DOUBLE_SPAWN does not need to be set"
+                warnings::emit(
+                    WarningCode::ObsoleteEnvVar,
+                    format_args!(
+                        "double-spawn is no longer experimental: \
+                         NEXTEST_EXPERIMENTAL_DOUBLE_SPAWN does not need to be set"
+                    ),
                 );
             }
             if std::env::var("NEXTEST_DOUBLE_SPAWN") == Ok("0".to_owned()) {
                 info!("NEXTEST_DOUBLE_SPAWN=0 set, disabling double-spawn for test processes");
                 DoubleSpawnInfo::disabled()
+            } else if std::env::var_os("MIRI_SYSROOT").is_some() {
+                // Test binaries are run through cargo miri's runner, which does its own setup, so
+                // double-spawning only adds overhead.
+                debug!("running under Miri, disabling double-spawn for test processes");
+                DoubleSpawnInfo::disabled()
             } else {
                 DoubleSpawnInfo::try_enable()
             }
         })
     }
 
-    fn load_runner(&self, build_platforms: &BuildPlatforms) -> &TargetRunner {
-        self.target_runner.get_or_init(|| {
+    /// Returns the Cargo profile tests were built with, or `None` if the build is being reused
+    /// (in which case the profile isn't known).
+    fn cargo_profile_name(&self) -> Option<String> {
+        if self.reuse_build.is_active() {
+            None
+        } else {
+            Some(self.cargo_opts.cargo_profile_name().to_owned())
+        }
+    }
+
+    fn load_runner(&self, build_platforms: &BuildPlatforms) -> Result<&TargetRunner> {
+        self.target_runner.get_or_try_init(|| {
             runner_for_target(
                 &self.cargo_configs,
                 build_platforms,
+                &self.workspace_root,
                 &self.output.stderr_styles(),
             )
         })
@@ -1458,14 +2000,21 @@ impl BaseApp {
             .color
             .should_colorize(supports_color::Stream::Stderr)
         {
-            reporter.colorize();
+            reporter.colorize_with_theme(&self.output.theme);
         }
 
+        let source_fingerprint = SourceFingerprint::compute(
+            &self.workspace_root,
+            self.config_opts.config_file.as_deref(),
+        )
+        .with_nextest_version(&self.current_version);
+
         let mut writer = output_writer.stderr_writer();
         archive_to_file(
             profile,
             &binary_list,
             &self.cargo_metadata_json,
+            &source_fingerprint,
             &self.package_graph,
             // Note that path_mapper is currently a no-op -- we don't support reusing builds for
             // archive creation because it's too confusing.
@@ -1501,24 +2050,120 @@ impl BaseApp {
         Ok(binary_list)
     }
 
+    fn exec_list_with_check(
+        &self,
+        message_format: MessageFormatOpts,
+        output_writer: &mut OutputWriter,
+    ) -> Result<()> {
+        let (version_only_config, _) = self.load_config()?;
+        let test_list = self.cargo_opts.compute_checked_test_list(
+            self.graph(),
+            self.manifest_path.as_deref(),
+            self.output,
+        )?;
+
+        let mut writer = output_writer.stdout_writer();
+        test_list.write(
+            message_format.to_output_format(self.output.verbose),
+            &mut writer,
+            self.output
+                .color
+                .should_colorize(supports_color::Stream::Stdout),
+        )?;
+        writer.write_str_flush().map_err(WriteTestListError::Io)?;
+
+        self.check_version_config_final(version_only_config.nextest_version())?;
+        Ok(())
+    }
+
+    fn exec_feature_matrix(
+        &self,
+        message_format: MessageFormatOpts,
+        output_writer: &mut OutputWriter,
+    ) -> Result<()> {
+        let (version_only_config, _) = self.load_config()?;
+        let matrix = self.cargo_opts.compute_feature_matrix(self.graph());
+
+        let mut writer = output_writer.stdout_writer();
+        matrix.write(
+            message_format.to_output_format(self.output.verbose),
+            &mut writer,
+            self.output
+                .color
+                .should_colorize(supports_color::Stream::Stdout),
+        )?;
+        writer.write_str_flush().map_err(WriteTestListError::Io)?;
+
+        self.check_version_config_final(version_only_config.nextest_version())?;
+        Ok(())
+    }
+
     #[inline]
     fn graph(&self) -> &PackageGraph {
         &self.package_graph
     }
 
+    fn exec_flaky_update(&self, params: &FlakyUpdateParams) -> Result<()> {
+        let (_, config) = self.load_config()?;
+        let profile = self.load_profile(&config)?;
+
+        if !profile.record_flaky_history() {
+            warn!(
+                "profile {} doesn't have `record-flaky-history` enabled, \
+                 so test outcomes may not have been recorded",
+                profile.name(),
+            );
+        }
+        let history = FlakyHistory::load(&profile.store_dir().join(FlakyHistory::FILE_NAME));
+        let path = self.workspace_root.join(FlakyTests::PATH);
+        let mut flaky_tests = FlakyTests::read(&path).map_err(ExpectedError::config_parse_error)?;
+        let summary = flaky_tests.update(&history, params);
+
+        let styles = self.output.stderr_styles();
+        for (binary_id, test_name) in &summary.added {
+            info!(
+                "added flaky test {} {}",
+                binary_id.style(styles.bold),
+                test_name.style(styles.bold),
+            );
+        }
+        for (binary_id, test_name) in &summary.removed {
+            info!(
+                "removed stable test {} {}",
+                binary_id.style(styles.bold),
+                test_name.style(styles.bold),
+            );
+        }
+
+        if summary.added.is_empty() && summary.removed.is_empty() {
+            info!("no changes to flaky tests in {}", path.style(styles.bold));
+        } else {
+            flaky_tests.write(&path)?;
+            info!(
+                "updated {}: {} added, {} removed",
+                path.style(styles.bold),
+                summary.added.len(),
+                summary.removed.len(),
+            );
+        }
+
+        Ok(())
+    }
+
     fn load_profile<'cfg>(&self, config: &'cfg NextestConfig) -> Result<EarlyProfile<'cfg>> {
-        let profile_name = self.config_opts.profile.as_deref().unwrap_or_else(|| {
+        let profile = if self.config_opts.profile.is_empty() {
             // The "official" way to detect a miri environment is with MIRI_SYSROOT.
             // https://github.com/rust-lang/miri/pull/2398#issuecomment-1190747685
-            if std::env::var_os("MIRI_SYSROOT").is_some() {
+            let profile_name = if std::env::var_os("MIRI_SYSROOT").is_some() {
                 NextestConfig::DEFAULT_MIRI_PROFILE
             } else {
                 NextestConfig::DEFAULT_PROFILE
-            }
-        });
-        let profile = config
-            .profile(profile_name)
-            .map_err(ExpectedError::profile_not_found)?;
+            };
+            config.profile(profile_name)
+        } else {
+            config.composed_profile(&self.config_opts.profile)
+        }
+        .map_err(ExpectedError::profile_not_found)?;
         let store_dir = profile.store_dir();
         std::fs::create_dir_all(store_dir).map_err(|err| ExpectedError::StoreDirCreateError {
             store_dir: store_dir.to_owned(),
@@ -1528,6 +2173,43 @@ impl BaseApp {
     }
 }
 
+/// Returns the workspace root, as determined by `cargo locate-project`.
+///
+/// This is much faster than running `cargo metadata`, and is used by commands that only need to
+/// read nextest's configuration.
+fn locate_workspace_root(
+    manifest_path: Option<&Utf8Path>,
+    output: OutputContext,
+) -> Result<Utf8PathBuf> {
+    let mut cargo_cli = CargoCli::new("locate-project", manifest_path, output);
+    cargo_cli.add_args(["--workspace", "--message-format=plain"]);
+    let locate_project_output = cargo_cli
+        .to_expression()
+        .stdout_capture()
+        .unchecked()
+        .run()
+        .map_err(|error| {
+            ExpectedError::cargo_locate_project_exec_failed(cargo_cli.all_args(), error)
+        })?;
+    if !locate_project_output.status.success() {
+        return Err(ExpectedError::cargo_locate_project_failed(
+            cargo_cli.all_args(),
+        ));
+    }
+    let workspace_root = String::from_utf8(locate_project_output.stdout)
+        .map_err(|err| ExpectedError::WorkspaceRootInvalidUtf8 { err })?;
+    // trim_end because the output ends with a newline.
+    let workspace_root = Utf8Path::new(workspace_root.trim_end());
+    // parent() because the output includes Cargo.toml at the end.
+    let workspace_root =
+        workspace_root
+            .parent()
+            .ok_or_else(|| ExpectedError::WorkspaceRootInvalid {
+                workspace_root: workspace_root.to_owned(),
+            })?;
+    Ok(workspace_root.to_owned())
+}
+
 fn current_version() -> Version {
     // This is a test-only, not part of the public API.
     match std::env::var("__NEXTEST_TEST_VERSION") {
@@ -1554,7 +2236,13 @@ struct App {
 fn check_experimental_filtering(_output: OutputContext) {
     const EXPERIMENTAL_ENV: &str = "NEXTEST_EXPERIMENTAL_FILTER_EXPR";
     if std::env::var(EXPERIMENTAL_ENV).is_ok() {
-        warn!("filtersets are no longer experimental: NEXTEST_EXPERIMENTAL_FILTER_EXPR does not need to be set");
+        warnings::emit(
+            WarningCode::ObsoleteEnvVar,
+            format_args!(
+                "filtersets are no longer experimental: \
+                 NEXTEST_EXPERIMENTAL_FILTER_EXPR does not need to be set"
+            ),
+        );
     }
 }
 
@@ -1608,6 +2296,7 @@ impl App {
         &self,
         message_format: MessageFormatOpts,
         list_type: ListType,
+        compact: bool,
         output_writer: &mut OutputWriter,
     ) -> Result<()> {
         let (version_only_config, config) = self.base.load_config()?;
@@ -1634,7 +2323,7 @@ impl App {
                 let double_spawn = self.base.load_double_spawn();
                 let target_runner = self
                     .base
-                    .load_runner(&binary_list.rust_build_meta.build_platforms);
+                    .load_runner(&binary_list.rust_build_meta.build_platforms)?;
                 let ctx = TestExecuteContext {
                     double_spawn,
                     target_runner,
@@ -1647,14 +2336,21 @@ impl App {
                     self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
 
                 let mut writer = output_writer.stdout_writer();
-                test_list.write(
-                    message_format.to_output_format(self.base.output.verbose),
-                    &mut writer,
-                    self.base
-                        .output
-                        .color
-                        .should_colorize(supports_color::Stream::Stdout),
-                )?;
+                match message_format.to_output_format(self.base.output.verbose) {
+                    OutputFormat::Serializable(format) => {
+                        test_list.write_serializable(format, compact, &mut writer)?;
+                    }
+                    output_format => {
+                        test_list.write(
+                            output_format,
+                            &mut writer,
+                            self.base
+                                .output
+                                .color
+                                .should_colorize(supports_color::Stream::Stdout),
+                        )?;
+                    }
+                }
                 writer.write_str_flush().map_err(WriteTestListError::Io)?;
             }
         }
@@ -1689,7 +2385,7 @@ impl App {
         let build_platforms = binary_list.rust_build_meta.build_platforms.clone();
 
         let double_spawn = self.base.load_double_spawn();
-        let target_runner = self.base.load_runner(&build_platforms);
+        let target_runner = self.base.load_runner(&build_platforms)?;
         let ctx = TestExecuteContext {
             double_spawn,
             target_runner,
@@ -1716,12 +2412,61 @@ impl App {
         Ok(())
     }
 
+    fn exec_resolve_settings(&self, test_id: &str, output_writer: &mut OutputWriter) -> Result<()> {
+        let (_, config) = self.base.load_config()?;
+        let profile = self.base.load_profile(&config)?;
+
+        let filter_exprs = self.build_filtering_expressions()?;
+        let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+
+        let binary_list = self.base.build_binary_list()?;
+        let build_platforms = binary_list.rust_build_meta.build_platforms.clone();
+
+        let double_spawn = self.base.load_double_spawn();
+        let target_runner = self.base.load_runner(&build_platforms)?;
+        let ctx = TestExecuteContext {
+            double_spawn,
+            target_runner,
+        };
+        let profile = profile.apply_build_platforms(&build_platforms);
+        let ecx = profile.filterset_ecx();
+
+        let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
+
+        // Test IDs are displayed as "<binary-id> <test-name>".
+        let test_instance = test_id.split_once(' ').and_then(|(binary_id, test_name)| {
+            test_list.iter_tests().find(|instance| {
+                instance.suite_info.binary_id.as_str() == binary_id && instance.name == test_name
+            })
+        });
+        let Some(test_instance) = test_instance else {
+            return Err(ExpectedError::DebugTestNotFound {
+                test_id: test_id.to_owned(),
+            });
+        };
+
+        let mut writer = output_writer.stdout_writer();
+        ShowTestSettings::new(&profile, test_instance)
+            .write_human(
+                &mut writer,
+                self.base
+                    .output
+                    .color
+                    .should_colorize(supports_color::Stream::Stdout),
+            )
+            .map_err(WriteTestListError::Io)?;
+        writer.write_str_flush().map_err(WriteTestListError::Io)?;
+
+        Ok(())
+    }
+
     fn exec_run(
         &self,
         no_capture: bool,
         runner_opts: &TestRunnerOpts,
         reporter_opts: &ReporterOpts,
         cli_args: Vec<String>,
+        mut outcomes: Option<&mut RemapOutcomes>,
         output_writer: &mut OutputWriter,
     ) -> Result<i32> {
         let (version_only_config, config) = self.base.load_config()?;
@@ -1769,17 +2514,37 @@ impl App {
         let binary_list = self.base.build_binary_list()?;
         let build_platforms = &binary_list.rust_build_meta.build_platforms.clone();
         let double_spawn = self.base.load_double_spawn();
-        let target_runner = self.base.load_runner(build_platforms);
+        let target_runner = self.base.load_runner(build_platforms)?;
         let ctx = TestExecuteContext {
             double_spawn,
             target_runner,
         };
 
-        let profile = profile.apply_build_platforms(build_platforms);
+        let mut profile = profile.apply_build_platforms(build_platforms);
+        profile.set_cargo_profile(self.base.cargo_profile_name());
         let ecx = profile.filterset_ecx();
 
         let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
 
+        if runner_opts.dry_run {
+            // --dry-run conflicts with --no-run, so a builder is always returned here.
+            if let Some(runner_builder) = runner_opts.to_builder(cap_strat) {
+                let dry_run = runner_builder.dry_run(&test_list, &profile);
+                let mut writer = output_writer.stdout_writer();
+                dry_run
+                    .write_human(
+                        &mut writer,
+                        self.base
+                            .output
+                            .color
+                            .should_colorize(supports_color::Stream::Stdout),
+                    )
+                    .map_err(WriteTestListError::Io)?;
+                writer.write_str_flush().map_err(WriteTestListError::Io)?;
+            }
+            return Ok(0);
+        }
+
         let output = output_writer.reporter_output();
         let should_colorize = self
             .base
@@ -1816,13 +2581,32 @@ impl App {
         )?;
 
         // Make the reporter.
-        let mut reporter = reporter_opts
-            .to_builder(no_capture, should_colorize)
+        let mut reporter_builder = reporter_opts.to_builder(no_capture, should_colorize);
+        reporter_builder
             .set_verbose(self.base.output.verbose)
-            .build(&test_list, &profile, output, structured_reporter);
+            .set_theme(self.base.output.theme);
+        if let Some(symbols) = self.base.output.symbols {
+            reporter_builder.set_symbols(symbols);
+        }
+        let user_config = UserConfig::from_default_location()?;
+        reporter_builder
+            .set_duration_format(user_config.ui().duration_format)
+            .set_thousands_separator(user_config.ui().thousands_separator);
+        // The final summary in the user config only applies if the profile
+        // doesn't set one.
+        if profile.final_summary().is_none() {
+            if let Some(final_summary) = user_config.reporter().final_summary {
+                reporter_builder.set_final_summary(final_summary);
+            }
+        }
+        let mut reporter =
+            reporter_builder.build(&test_list, &profile, output, structured_reporter);
 
         configure_handle_inheritance(no_capture)?;
         let run_stats = runner.try_execute(|event| {
+            if let Some(outcomes) = outcomes.as_deref_mut() {
+                outcomes.record(&event);
+            }
             // Write and flush the event.
             reporter.report_event(event)
         })?;
@@ -1831,7 +2615,21 @@ impl App {
             .check_version_config_final(version_only_config.nextest_version())?;
 
         match run_stats.summarize_final() {
-            FinalRunStats::Success => Ok(0),
+            FinalRunStats::Success => match runner_opts.exit_code_flaky {
+                Some(exit_code) if run_stats.is_flaky_success() => {
+                    warn!(
+                        "{} {} passed only after being retried, exiting with code {exit_code}",
+                        run_stats.flaky,
+                        if run_stats.flaky == 1 {
+                            "test"
+                        } else {
+                            "tests"
+                        },
+                    );
+                    Ok(exit_code)
+                }
+                _ => Ok(0),
+            },
             FinalRunStats::NoTestsRun => match runner_opts.no_tests {
                 Some(NoTestsBehavior::Pass) => Ok(0),
                 Some(NoTestsBehavior::Warn) => {
@@ -1853,6 +2651,16 @@ impl App {
     }
 }
 
+#[derive(Debug, Subcommand)]
+enum ArchiveCommand {
+    /// Extract an archive without running tests
+    ///
+    /// This command unpacks an archive to a directory, and prints out a `cargo nextest run`
+    /// command that runs the tests within it. The standard library directories stored in the
+    /// archive are remapped in the extracted binaries metadata.
+    Extract(ArchiveExtractOpts),
+}
+
 #[derive(Debug, Subcommand)]
 enum ShowConfigCommand {
     /// Show version-related configuration.
@@ -1888,35 +2696,8 @@ impl ShowConfigCommand {
     ) -> Result<i32> {
         match self {
             Self::Version {} => {
-                let mut cargo_cli =
-                    CargoCli::new("locate-project", manifest_path.as_deref(), output);
-                cargo_cli.add_args(["--workspace", "--message-format=plain"]);
-                let locate_project_output = cargo_cli
-                    .to_expression()
-                    .stdout_capture()
-                    .unchecked()
-                    .run()
-                    .map_err(|error| {
-                        ExpectedError::cargo_locate_project_exec_failed(cargo_cli.all_args(), error)
-                    })?;
-                if !locate_project_output.status.success() {
-                    return Err(ExpectedError::cargo_locate_project_failed(
-                        cargo_cli.all_args(),
-                    ));
-                }
-                let workspace_root = String::from_utf8(locate_project_output.stdout)
-                    .map_err(|err| ExpectedError::WorkspaceRootInvalidUtf8 { err })?;
-                // trim_end because the output ends with a newline.
-                let workspace_root = Utf8Path::new(workspace_root.trim_end());
-                // parent() because the output includes Cargo.toml at the end.
-                let workspace_root =
-                    workspace_root
-                        .parent()
-                        .ok_or_else(|| ExpectedError::WorkspaceRootInvalid {
-                            workspace_root: workspace_root.to_owned(),
-                        })?;
-
-                let config = config_opts.make_version_only_config(workspace_root)?;
+                let workspace_root = locate_workspace_root(manifest_path.as_deref(), output)?;
+                let config = config_opts.make_version_only_config(&workspace_root)?;
                 let current_version = current_version();
 
                 let show = ShowNextestVersion::new(
@@ -1980,6 +2761,294 @@ impl ShowConfigCommand {
     }
 }
 
+#[derive(Debug, Subcommand)]
+enum FlakyCommand {
+    /// Update the list of flaky tests based on recent runs
+    ///
+    /// Adds tests whose flake score (the fraction of recent runs in which they passed only after
+    /// being retried) exceeds the threshold, and removes tests that have passed on their first
+    /// attempt for the last several runs.
+    Update {
+        /// Add tests with a flake score above this threshold (between 0 and 1)
+        #[arg(long, default_value_t = 0.05, value_parser = parse_flake_threshold, value_name = "SCORE")]
+        threshold: f64,
+
+        /// Only add tests with at least this many recorded runs
+        #[arg(long, default_value_t = 10, value_name = "N")]
+        min_runs: usize,
+
+        /// Remove tests that passed on their first attempt for this many consecutive runs
+        #[arg(long, default_value_t = 20, value_name = "N")]
+        stable_runs: usize,
+
+        #[clap(flatten)]
+        cargo_options: Box<CargoOptions>,
+    },
+}
+
+impl FlakyCommand {
+    fn exec(
+        self,
+        manifest_path: Option<Utf8PathBuf>,
+        config_opts: ConfigOpts,
+        output: OutputContext,
+        output_writer: &mut OutputWriter,
+    ) -> Result<i32> {
+        match self {
+            Self::Update {
+                threshold,
+                min_runs,
+                stable_runs,
+                cargo_options,
+            } => {
+                let base = BaseApp::new(
+                    output,
+                    ReuseBuildOpts::default(),
+                    *cargo_options,
+                    config_opts,
+                    manifest_path,
+                    output_writer,
+                )?;
+                base.exec_flaky_update(&FlakyUpdateParams {
+                    threshold,
+                    min_runs,
+                    stable_runs,
+                })?;
+                Ok(0)
+            }
+        }
+    }
+}
+
+fn parse_binary_id_glob(s: &str) -> std::result::Result<GlobMatcher, String> {
+    let glob = GlobBuilder::new(s)
+        // Match the glob syntax used by filtersets: only allow escapes via [].
+        .backslash_escape(false)
+        .empty_alternates(true)
+        .build()
+        .map_err(|err| format!("invalid glob: {err}"))?;
+    Ok(glob.compile_matcher())
+}
+
+fn parse_exit_code_flaky(s: &str) -> std::result::Result<i32, String> {
+    let exit_code: i32 = s.parse().map_err(|err| format!("{err}"))?;
+    if !(2..=255).contains(&exit_code) {
+        // 1 is used for unexpected errors.
+        Err(format!(
+            "exit code must be between 2 and 255, found {exit_code}"
+        ))
+    } else if nextest_metadata::NextestExitCode::ALL.contains(&exit_code) {
+        Err(format!(
+            "exit code {exit_code} is already used by nextest (see \
+             https://docs.rs/nextest-metadata/latest/nextest_metadata/enum.NextestExitCode.html)"
+        ))
+    } else {
+        Ok(exit_code)
+    }
+}
+
+fn parse_flake_threshold(s: &str) -> std::result::Result<f64, String> {
+    let threshold: f64 = s.parse().map_err(|err| format!("{err}"))?;
+    if (0.0..=1.0).contains(&threshold) {
+        Ok(threshold)
+    } else {
+        Err(format!(
+            "threshold must be between 0 and 1, found {threshold}"
+        ))
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum ReportCommand {
+    /// Merge JUnit reports from partitioned runs into a single report
+    ///
+    /// Writes the merged JUnit report to `junit.xml` within the output directory, along with a
+    /// `summary.json` containing counts, failed and flaky tests, and the slowest tests across all
+    /// shards.
+    Merge {
+        /// The JUnit reports to merge, one per shard
+        #[arg(required = true, value_name = "REPORTS")]
+        files: Vec<Utf8PathBuf>,
+
+        /// The directory to write the merged report to
+        #[arg(short = 'o', long, value_name = "DIR")]
+        output_dir: Utf8PathBuf,
+    },
+
+    /// Compare the JUnit reports of two runs
+    ///
+    /// Lists tests that are newly failing or newly passing, tests that got slower, and changes to
+    /// the output of tests that fail in both runs.
+    Diff {
+        /// The JUnit report of the old run
+        #[arg(value_name = "OLD")]
+        old: Utf8PathBuf,
+
+        /// The JUnit report of the new run
+        #[arg(value_name = "NEW")]
+        new: Utf8PathBuf,
+
+        /// Report tests that got slower by more than this percentage
+        #[arg(long, default_value_t = 50.0, value_parser = parse_regression_threshold, value_name = "PERCENT")]
+        threshold: f64,
+
+        /// Output format
+        #[arg(short = 'T', long, value_enum, default_value_t, value_name = "FMT")]
+        message_format: MessageFormatOpts,
+    },
+
+    /// Summarize where the time went in a run, from its JUnit report
+    ///
+    /// Shows the slowest tests and binaries, the test whose attempts took longest in total, and a
+    /// graph of how many tests were running over the course of the run.
+    Timings {
+        /// The JUnit report of the run
+        #[arg(value_name = "REPORT")]
+        report: Utf8PathBuf,
+
+        /// The number of slowest tests and binaries to show
+        #[arg(long, default_value_t = 10, value_name = "N")]
+        top: usize,
+
+        /// The number of intervals to split the run into for the parallelism graph
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u16).range(1..), value_name = "N")]
+        intervals: u16,
+
+        /// Output format
+        #[arg(short = 'T', long, value_enum, default_value_t, value_name = "FMT")]
+        message_format: MessageFormatOpts,
+    },
+}
+
+impl ReportCommand {
+    fn exec(self, output: OutputContext, output_writer: &mut OutputWriter) -> Result<i32> {
+        match self {
+            Self::Merge { files, output_dir } => {
+                let report = MergedReport::read(&files)?;
+                report.write(&output_dir)?;
+
+                let styles = output.stderr_styles();
+                let summary = report.summary();
+                info!(
+                    "merged {} reports into {}: {} tests, {} passed ({} flaky), {} failed, {} skipped",
+                    summary.shard_count,
+                    output_dir.style(styles.bold),
+                    summary.test_count,
+                    summary.passed,
+                    summary.flaky_tests.len(),
+                    summary.failed,
+                    summary.skipped,
+                );
+                Ok(0)
+            }
+            Self::Diff {
+                old,
+                new,
+                threshold,
+                message_format,
+            } => {
+                let params = ReportDiffParams {
+                    threshold: threshold / 100.0,
+                };
+                let diff = ReportDiff::read(&old, &new, params)?;
+
+                let mut writer = output_writer.stdout_writer();
+                diff.write(
+                    message_format.to_output_format(output.verbose),
+                    &mut writer,
+                    output.color.should_colorize(supports_color::Stream::Stdout),
+                )?;
+                writer.write_str_flush().map_err(WriteTestListError::Io)?;
+                Ok(0)
+            }
+            Self::Timings {
+                report,
+                top,
+                intervals,
+                message_format,
+            } => {
+                let params = TimingReportParams {
+                    top,
+                    intervals: intervals.into(),
+                };
+                let timings = TimingReport::read(&report, params)?;
+
+                let mut writer = output_writer.stdout_writer();
+                timings.write(
+                    message_format.to_output_format(output.verbose),
+                    &mut writer,
+                    output.color.should_colorize(supports_color::Stream::Stdout),
+                )?;
+                writer.write_str_flush().map_err(WriteTestListError::Io)?;
+                Ok(0)
+            }
+        }
+    }
+}
+
+fn parse_regression_threshold(s: &str) -> std::result::Result<f64, String> {
+    let threshold: f64 = s.parse().map_err(|err| format!("{err}"))?;
+    if threshold >= 0.0 {
+        Ok(threshold)
+    } else {
+        Err(format!("threshold must not be negative, found {threshold}"))
+    }
+}
+
+#[derive(Debug, Args)]
+struct MiriOpts {
+    /// The nextest command to run under Miri (for example `run` or `list`), and its arguments
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        value_name = "COMMAND"
+    )]
+    args: Vec<String>,
+}
+
+impl MiriOpts {
+    fn exec(self, cli_args: Vec<String>) -> Result<i32> {
+        // cargo miri sets MIRI_SYSROOT before invoking nextest, so this also prevents an infinite
+        // loop of nextest and cargo miri invoking each other.
+        if std::env::var_os("MIRI_SYSROOT").is_some() {
+            return Err(ExpectedError::MiriAlreadyActive);
+        }
+
+        let mut command = std::process::Command::new(crate::cargo_cli::cargo_path());
+        command.args(miri_args(&cli_args, self.args.len()));
+        debug!("executing {command:?}");
+
+        // cargo miri runs this binary again with the same arguments, minus `miri`, and the exit
+        // code is passed through.
+        let status = command
+            .status()
+            .map_err(|err| ExpectedError::MiriExecFailed {
+                command: format!("{command:?}"),
+                err,
+            })?;
+        Ok(status.code().unwrap_or(1))
+    }
+}
+
+/// Converts the arguments for `cargo nextest [OPTIONS] miri COMMAND...` into arguments for
+/// `cargo miri nextest [OPTIONS] COMMAND...`.
+///
+/// `command_len` is the number of arguments that make up the command, which always come last.
+fn miri_args(cli_args: &[String], command_len: usize) -> Vec<String> {
+    // The first two arguments are `cargo-nextest nextest`. Global options may appear on either side
+    // of `miri`, so look for the last `miri` before the command.
+    let command_start = cli_args.len() - command_len;
+    let miri_index = cli_args[..command_start]
+        .iter()
+        .rposition(|arg| arg == "miri")
+        .expect("miri subcommand is present in arguments");
+    let mut args = vec!["miri".to_owned(), "nextest".to_owned()];
+    args.extend_from_slice(&cli_args[2..miri_index]);
+    args.extend_from_slice(&cli_args[miri_index + 1..]);
+    args
+}
+
 #[derive(Debug, Subcommand)]
 enum SelfCommand {
     #[clap(hide = true)]
@@ -2005,16 +3074,35 @@ enum SelfCommand {
     )]
     Update {
         /// Version or version range to download
-        #[arg(long, default_value = "latest")]
-        version: String,
+        ///
+        /// Defaults to the `pin` range in the `nextest-version` section of the workspace's
+        /// nextest config if one is set, and to the latest version otherwise.
+        #[arg(long)]
+        version: Option<String>,
+
+        /// Release channel to update from
+        #[arg(long, value_enum, default_value_t = UpdateChannelOpt::Stable)]
+        channel: UpdateChannelOpt,
 
         /// Check for updates rather than downloading them
         ///
         /// If no update is available, exits with code 0. If an update is available, exits with code
-        /// 80 (UPDATE_AVAILABLE).
+        /// 80 (UPDATE_AVAILABLE). If the current version doesn't meet the version required by the
+        /// workspace's nextest config, exits with code 92 (REQUIRED_VERSION_NOT_MET).
         #[arg(short = 'n', long)]
         check: bool,
 
+        /// Output format for --check
+        ///
+        /// With `json`, the result of the check is written to standard output as a JSON object.
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = UpdateMessageFormat::Human,
+            requires = "check"
+        )]
+        message_format: UpdateMessageFormat,
+
         /// Do not prompt for confirmation
         #[arg(short = 'y', long, conflicts_with = "check")]
         yes: bool,
@@ -2026,9 +3114,79 @@ enum SelfCommand {
         /// URL or path to fetch releases.json from
         #[arg(long)]
         releases_url: Option<String>,
+
+        /// Base URL or local directory of a mirror to update from
+        ///
+        /// The mirror must contain a copy of releases.json, as well as the release archives for
+        /// the platforms being updated. Archives are looked up by file name, and verified against
+        /// the checksums in releases.json.
+        #[arg(long, value_name = "URL_OR_DIR", conflicts_with = "releases_url")]
+        mirror: Option<String>,
+
+        /// Install from a local .tar.gz archive, without fetching release information
+        ///
+        /// The archive must have the same layout as nextest's release archives. Pass in
+        /// --archive-sha256 to verify the archive's checksum.
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = ["version", "check", "releases_url", "mirror"],
+        )]
+        archive: Option<Utf8PathBuf>,
+
+        /// Expected SHA-256 checksum of the archive passed in with --archive, as a hex string
+        #[arg(long, value_name = "HEX", requires = "archive")]
+        archive_sha256: Option<String>,
+    },
+
+    /// Generate man pages or Markdown reference docs for nextest's command-line interface
+    ///
+    /// The docs cover `cargo nextest` and all of its subcommands, including hidden ones, and are
+    /// generated from the same definitions as `--help`, so they always match this version of
+    /// nextest.
+    GenerateDocs {
+        /// The format to generate docs in
+        #[arg(long, value_enum)]
+        format: DocsFormat,
+
+        /// Directory to write docs to
+        ///
+        /// Man pages are written out as one file per command, for example
+        /// `cargo-nextest-run.1`, and require this option. The Markdown reference is written out
+        /// to `cargo-nextest.md` in this directory, or to standard output if this isn't specified.
+        #[arg(short = 'o', long, value_name = "DIR", required_if_eq("format", "man"))]
+        output_dir: Option<Utf8PathBuf>,
     },
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum UpdateChannelOpt {
+    /// Stable releases only
+    Stable,
+
+    /// Stable releases and pre-releases
+    Beta,
+}
+
+#[cfg(feature = "self-update")]
+impl From<UpdateChannelOpt> for nextest_runner::update::UpdateChannel {
+    fn from(opt: UpdateChannelOpt) -> Self {
+        match opt {
+            UpdateChannelOpt::Stable => Self::Stable,
+            UpdateChannelOpt::Beta => Self::Beta,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub(crate) enum UpdateMessageFormat {
+    /// Human-readable output
+    Human,
+
+    /// A JSON object on standard output
+    Json,
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum SetupSource {
     User,
@@ -2038,7 +3196,12 @@ enum SetupSource {
 
 impl SelfCommand {
     #[cfg_attr(not(feature = "self-update"), expect(unused_variables))]
-    fn exec(self, output: OutputOpts) -> Result<i32> {
+    fn exec(
+        self,
+        manifest_path: Option<Utf8PathBuf>,
+        config_opts: ConfigOpts,
+        output: OutputOpts,
+    ) -> Result<i32> {
         let output = output.init();
 
         match self {
@@ -2048,19 +3211,45 @@ impl SelfCommand {
             }
             Self::Update {
                 version,
+                channel,
                 check,
+                message_format,
                 yes,
                 force,
                 releases_url,
+                mirror,
+                archive,
+                archive_sha256,
             } => {
                 cfg_if::cfg_if! {
                     if #[cfg(feature = "self-update")] {
+                        // The workspace's config is optional: self-updates also work outside a
+                        // workspace.
+                        let nextest_version =
+                            match locate_workspace_root(manifest_path.as_deref(), output) {
+                                Ok(workspace_root) => config_opts
+                                    .make_version_only_config(&workspace_root)?
+                                    .nextest_version()
+                                    .clone(),
+                                Err(error) => {
+                                    debug!("not reading nextest config for self-update: {error}");
+                                    NextestVersionConfig::default()
+                                }
+                            };
                         crate::update::perform_update(
-                            &version,
-                            check,
-                            yes,
-                            force,
-                            releases_url,
+                            crate::update::UpdateOpts {
+                                version,
+                                channel: channel.into(),
+                                check,
+                                message_format,
+                                yes,
+                                force,
+                                releases_url,
+                                mirror,
+                                archive,
+                                archive_sha256,
+                                nextest_version,
+                            },
                             output,
                         )
                     } else {
@@ -2070,6 +3259,10 @@ impl SelfCommand {
                     }
                 }
             }
+            Self::GenerateDocs { format, output_dir } => {
+                generate_docs(format, output_dir.as_deref())?;
+                Ok(0)
+            }
         }
     }
 }
@@ -2111,12 +3304,39 @@ enum DebugCommand {
         #[arg(long, value_name = "KEY=VALUE")]
         config: Vec<String>,
     },
+
+    /// Show the settings nextest would use for a test, and where each setting came from.
+    ///
+    /// Settings are resolved for the selected profile, taking per-test overrides into account.
+    ResolveSettings {
+        /// The test to show settings for, in the form "<binary-id> <test-name>".
+        #[arg(value_name = "TEST_ID")]
+        test_id: String,
+
+        #[clap(flatten)]
+        cargo_options: Box<CargoOptions>,
+
+        #[clap(flatten)]
+        build_filter: TestBuildFilter,
+
+        #[clap(flatten)]
+        reuse_build: Box<ReuseBuildOpts>,
+    },
+
+    /// Print a JSON Schema for nextest's repository config.
+    ///
+    /// Editors can use the schema to validate and complete `.config/nextest.toml`.
+    ConfigSchema,
 }
 
 impl DebugCommand {
-    fn exec(self, output: OutputOpts) -> Result<i32> {
-        let _ = output.init();
-
+    fn exec(
+        self,
+        manifest_path: Option<Utf8PathBuf>,
+        config_opts: ConfigOpts,
+        output: OutputContext,
+        output_writer: &mut OutputWriter,
+    ) -> Result<i32> {
         match self {
             DebugCommand::Extract {
                 stdout,
@@ -2180,6 +3400,26 @@ impl DebugCommand {
                     println!("no target triple found");
                 }
             }
+            DebugCommand::ResolveSettings {
+                test_id,
+                cargo_options,
+                build_filter,
+                reuse_build,
+            } => {
+                let base = BaseApp::new(
+                    output,
+                    *reuse_build,
+                    *cargo_options,
+                    config_opts,
+                    manifest_path,
+                    output_writer,
+                )?;
+                let app = App::new(base, build_filter)?;
+                app.exec_resolve_settings(&test_id, output_writer)?;
+            }
+            DebugCommand::ConfigSchema => {
+                print!("{}", NextestConfig::JSON_SCHEMA);
+            }
         }
 
         Ok(0)
@@ -2322,10 +3562,14 @@ fn discover_target_triple(
 fn runner_for_target(
     cargo_configs: &CargoConfigs,
     build_platforms: &BuildPlatforms,
+    workspace_root: &Utf8Path,
     styles: &StderrStyles,
-) -> TargetRunner {
+) -> Result<TargetRunner> {
     match TargetRunner::new(cargo_configs, build_platforms) {
         Ok(runner) => {
+            // Resolve placeholders and check that runners exist before logging them, so that
+            // misconfigured runners are reported once rather than for every test.
+            let runner = runner.resolve(build_platforms, workspace_root)?;
             if build_platforms.target.is_some() {
                 if let Some(runner) = runner.target() {
                     log_platform_runner("for the target platform, ", runner, styles);
@@ -2340,11 +3584,11 @@ fn runner_for_target(
                     log_platform_runner("", runner, styles);
                 }
             }
-            runner
+            Ok(runner)
         }
         Err(err) => {
             warn_on_err("target runner", &err, styles);
-            TargetRunner::empty()
+            Ok(TargetRunner::empty())
         }
     }
 }
@@ -2390,17 +3634,29 @@ mod tests {
             "cargo nextest list --list-type binaries-only",
             "cargo nextest list --list-type full",
             "cargo nextest list --message-format json-pretty",
+            "cargo nextest list --list-with-check",
+            "cargo nextest list --list-with-check --workspace --message-format json",
+            "cargo nextest list --all-features-matrix",
+            "cargo nextest list --all-features-matrix -p foo -F bar --message-format json",
+            "cargo nextest list --compact --message-format json",
             "cargo nextest run --failure-output never",
             "cargo nextest run --success-output=immediate",
             "cargo nextest run --status-level=all",
             "cargo nextest run --no-capture",
             "cargo nextest run --nocapture",
             "cargo nextest run --no-run",
+            "cargo nextest run --debug-schedule schedule.jsonl",
+            "cargo nextest run --retries 2 --exit-code-flaky 3",
             "cargo nextest run --final-status-level flaky",
             // retry is an alias for flaky -- ensure that it parses
             "cargo nextest run --final-status-level retry",
             "NEXTEST_HIDE_PROGRESS_BAR=1 cargo nextest run",
             "NEXTEST_HIDE_PROGRESS_BAR=true cargo nextest run",
+            "cargo nextest run --error-format json",
+            "cargo nextest --error-format=json list",
+            "cargo nextest run --profile ci --profile asan",
+            "cargo nextest run -P ci,asan",
+            "NEXTEST_PROFILE=ci,asan cargo nextest run",
             // ---
             // Cargo options
             // ---
@@ -2413,9 +3669,12 @@ mod tests {
             "cargo nextest run --binaries-metadata=foo --target-dir-remap=bar",
             "cargo nextest list --cargo-metadata path",
             "cargo nextest run --cargo-metadata=path --workspace-remap remapped-path",
+            "cargo nextest run --cargo-metadata=path --workspace-remap a --workspace-remap b",
             "cargo nextest archive --archive-file my-archive.tar.zst --zstd-level -1",
             "cargo nextest archive --archive-file my-archive.foo --archive-format tar-zst",
             "cargo nextest archive --archive-file my-archive.foo --archive-format tar-zstd",
+            "cargo nextest archive extract --archive-file my-archive.tar.zst --extract-to my-path",
+            "cargo nextest archive extract --archive-file my-archive.tar.zst --extract-to my-path --extract-overwrite --workspace-remap foo",
             "cargo nextest list --archive-file my-archive.tar.zst",
             "cargo nextest list --archive-file my-archive.tar.zst --archive-format tar-zst",
             "cargo nextest list --archive-file my-archive.tar.zst --extract-to my-path",
@@ -2430,6 +3689,8 @@ mod tests {
             "cargo nextest run --filterset 'test(bar)' --package=my-package test-filter",
             "cargo nextest run --filter-expr 'test(bar)' --package=my-package test-filter",
             "cargo nextest list -E 'deps(foo)' --ignore-default-filter",
+            "cargo nextest run --only-binaries 'my-crate::*'",
+            "cargo nextest list --only-binaries my-crate --only-binaries 'other-crate::bin/{a,b}'",
             // ---
             // Test binary arguments
             // ---
@@ -2440,9 +3701,129 @@ mod tests {
             // Test negative cargo build jobs
             "cargo nextest run --build-jobs -1",
             "cargo nextest run --build-jobs 1",
+            // Test binaries listed in parallel
+            "cargo nextest list --list-jobs 4",
+            "cargo nextest run --list-jobs num-cpus",
+            "cargo nextest list --list-jobs -1",
+            // ---
+            // Miri
+            // ---
+            "cargo nextest miri run",
+            "cargo nextest -P ci miri list -E 'test(foo)' -- --exact",
+            // ---
+            // Report merging
+            // ---
+            "cargo nextest report merge shard-1.xml shard-2.xml -o merged",
+            "cargo nextest report merge shard-1.xml --output-dir merged",
+            "cargo nextest report diff old.xml new.xml",
+            "cargo nextest report diff old.xml new.xml --threshold 20 -T json",
+            // ---
+            // Docs generation
+            // ---
+            "cargo nextest self generate-docs --format markdown",
+            "cargo nextest self generate-docs --format markdown -o docs",
+            "cargo nextest self generate-docs --format man --output-dir man",
+            // ---
+            // Self-updates
+            // ---
+            "cargo nextest self update --channel beta",
+            "cargo nextest self update --version ~0.9.80 --check --message-format json",
+            "cargo nextest self update --mirror https://mirror.example.com/nextest",
+            "cargo nextest self update --archive nextest.tar.gz --archive-sha256 abcd -y",
         ];
 
         let invalid: &[(&'static str, ErrorKind)] = &[
+            // ---
+            // Miri requires a command
+            // ---
+            ("cargo nextest miri", MissingRequiredArgument),
+            // ---
+            // Report merging requires reports and an output directory
+            // ---
+            (
+                "cargo nextest report merge -o merged",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest report merge shard-1.xml",
+                MissingRequiredArgument,
+            ),
+            ("cargo nextest report diff old.xml", MissingRequiredArgument),
+            // ---
+            // Archives require an archive file, and extracting requires a destination
+            // ---
+            ("cargo nextest archive", MissingRequiredArgument),
+            (
+                "cargo nextest archive extract --archive-file my-archive.tar.zst",
+                MissingRequiredArgument,
+            ),
+            // ---
+            // Man pages require an output directory
+            // ---
+            (
+                "cargo nextest self generate-docs --format man",
+                MissingRequiredArgument,
+            ),
+            // ---
+            // JSON output is only supported for update checks
+            // ---
+            (
+                "cargo nextest self update --message-format json",
+                MissingRequiredArgument,
+            ),
+            ("cargo nextest self update --channel nightly", InvalidValue),
+            ("cargo nextest run --error-format xml", InvalidValue),
+            (
+                "cargo nextest self update --archive nextest.tar.gz --check",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest self update --mirror /srv/nextest --releases-url releases.json",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest self update --archive-sha256 abcd",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest report diff old.xml new.xml --threshold=-5",
+                ValueValidation,
+            ),
+            // ---
+            // --list-with-check and these options conflict
+            // ---
+            (
+                "cargo nextest list --list-with-check -E 'test(foo)'",
+                ArgumentConflict,
+            ),
+            ("cargo nextest list --list-with-check foo", ArgumentConflict),
+            (
+                "cargo nextest list --list-with-check --list-type binaries-only",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest list --list-with-check --archive-file my-archive.tar.zst",
+                ArgumentConflict,
+            ),
+            // ---
+            // --all-features-matrix and these options conflict
+            // ---
+            (
+                "cargo nextest list --all-features-matrix --list-with-check",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest list --all-features-matrix -E 'test(foo)'",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest list --compact --list-with-check",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest list --compact --all-features-matrix",
+                ArgumentConflict,
+            ),
             // ---
             // --no-capture and these options conflict
             // ---
@@ -2469,6 +3850,23 @@ mod tests {
                 ArgumentConflict,
             ),
             ("cargo nextest run --no-run --max-fail=3", ArgumentConflict),
+            (
+                "cargo nextest run --no-run --debug-schedule schedule.jsonl",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --exit-code-flaky 3",
+                ArgumentConflict,
+            ),
+            ("cargo nextest run --exit-code-flaky 0", ValueValidation),
+            ("cargo nextest run --exit-code-flaky 256", ValueValidation),
+            ("cargo nextest run --exit-code-flaky 1", ValueValidation),
+            ("cargo nextest run --exit-code-flaky 100", ValueValidation),
+            ("cargo nextest run --exit-code-flaky 4", ValueValidation),
+            (
+                "cargo nextest run --only-binaries 'my-crate[a'",
+                ValueValidation,
+            ),
             (
                 "cargo nextest run --no-run --failure-output immediate",
                 ArgumentConflict,
@@ -2567,6 +3965,8 @@ mod tests {
             // Test threads must be a number
             ("cargo nextest run --jobs -twenty", UnknownArgument),
             ("cargo nextest run --build-jobs -inf1", UnknownArgument),
+            ("cargo nextest list --list-jobs 0", ValueValidation),
+            ("cargo nextest list --list-jobs four", ValueValidation),
         ];
 
         // Unset all NEXTEST_ env vars because they can conflict with the try_parse_from below.
@@ -2624,6 +4024,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_miri_args() {
+        let cases: &[(&str, &str)] = &[
+            ("cargo-nextest nextest miri run", "miri nextest run"),
+            (
+                "cargo-nextest nextest -P ci miri run -E 'test(miri)' -- --exact",
+                "miri nextest -P ci run -E 'test(miri)' -- --exact",
+            ),
+            (
+                "cargo-nextest nextest --profile miri miri list",
+                "miri nextest --profile miri list",
+            ),
+            (
+                "cargo-nextest nextest miri -P ci list",
+                "miri nextest -P ci list",
+            ),
+        ];
+
+        for &(input, expected) in cases {
+            let cli_args = shell_words::split(input).expect("valid command line");
+            let app = CargoNextestApp::try_parse_from(&cli_args)
+                .unwrap_or_else(|error| panic!("{input} should parse: {error}"));
+            let NextestSubcommand::Nextest(app) = app.subcommand else {
+                panic!("{input} should parse as cargo nextest");
+            };
+            let Command::Miri(opts) = app.command else {
+                panic!("{input} should parse as cargo nextest miri");
+            };
+            assert_eq!(
+                shell_words::join(miri_args(&cli_args, opts.args.len())),
+                expected,
+                "for input {input}"
+            );
+        }
+    }
+
     #[derive(Debug, Parser)]
     struct TestCli {
         #[structopt(flatten)]
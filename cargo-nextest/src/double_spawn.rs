@@ -4,7 +4,7 @@
 use crate::{output::OutputContext, ExpectedError, Result};
 use camino::Utf8PathBuf;
 use clap::Args;
-use nextest_runner::double_spawn::double_spawn_child_init;
+use nextest_runner::double_spawn::{double_spawn_child_init, double_spawn_child_pre_exec};
 use std::os::unix::process::CommandExt;
 
 #[derive(Debug, Args)]
@@ -27,6 +27,8 @@ impl DoubleSpawnOpts {
             }
         })?;
         let mut command = std::process::Command::new(&self.program);
+        double_spawn_child_pre_exec(&mut command)
+            .map_err(|err| ExpectedError::DoubleSpawnPreExecError { err })?;
         // Note: exec only returns an error -- in the success case it never returns.
         let err = command.args(args).exec();
         Err(ExpectedError::DoubleSpawnExecError { command, err })
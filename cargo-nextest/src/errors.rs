@@ -1,12 +1,17 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{output::StderrStyles, ExtractOutputFormat};
+use crate::{
+    output::{capture_log_messages, StderrStyles},
+    ExtractOutputFormat,
+};
 use camino::Utf8PathBuf;
 use itertools::Itertools;
 use nextest_filtering::errors::FiltersetParseErrors;
 use nextest_metadata::NextestExitCode;
-use nextest_runner::{errors::*, redact::Redactor};
+use nextest_runner::{
+    errors::*, redact::Redactor, reuse_build::SourceFingerprintMismatch, warnings::WarningCode,
+};
 use owo_colors::OwoColorize;
 use semver::Version;
 use std::{error::Error, string::FromUtf8Error};
@@ -63,11 +68,31 @@ pub enum ExpectedError {
         path: Utf8PathBuf,
         reuse_build_kind: ReuseBuildKind,
     },
+    #[error("source fingerprint mismatch")]
+    SourceFingerprintMismatch {
+        mismatches: Vec<SourceFingerprintMismatch>,
+        nextest_version: Option<String>,
+    },
     #[error("profile not found")]
     ProfileNotFound {
         #[from]
         err: ProfileNotFound,
     },
+    #[error("failed to write flaky tests")]
+    FlakyTestsWriteError {
+        #[from]
+        err: FlakyTestsWriteError,
+    },
+    #[error("failed to process JUnit reports")]
+    JunitReportError {
+        #[from]
+        err: JunitReportError,
+    },
+    #[error("failed to read user config")]
+    UserConfigError {
+        #[from]
+        err: UserConfigError,
+    },
     #[error("failed to create store directory")]
     StoreDirCreateError {
         store_dir: Utf8PathBuf,
@@ -79,6 +104,11 @@ pub enum ExpectedError {
         #[from]
         err: Box<CargoConfigError>,
     },
+    #[error("target runner error")]
+    TargetRunnerError {
+        #[from]
+        err: TargetRunnerError,
+    },
     #[error("config parse error")]
     ConfigParseError {
         #[from]
@@ -124,6 +154,12 @@ pub enum ExpectedError {
         #[source]
         err: Box<ArchiveExtractError>,
     },
+    #[error("error writing archive metadata")]
+    ArchiveMetadataWriteError {
+        path: Utf8PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
     #[error("path mapper construct error")]
     PathMapperConstructError {
         arg_name: &'static str,
@@ -196,6 +232,10 @@ pub enum ExpectedError {
     SetupScriptFailed,
     #[error("test run failed")]
     TestRunFailed,
+    #[error("test results depend on the workspace path")]
+    PathDependentTests { count: usize },
+    #[error("multiple workspace remaps are only supported by `cargo nextest run`")]
+    MultipleWorkspaceRemapsUnsupported,
     #[error("no tests to run")]
     NoTestsRun {
         /// The no-tests-run error was chosen because it was the default (we show a hint in this
@@ -214,6 +254,12 @@ pub enum ExpectedError {
         #[from]
         err: UpdateError,
     },
+    #[cfg(feature = "self-update")]
+    #[error("failed to write update check result")]
+    UpdateCheckWriteError {
+        #[source]
+        err: std::io::Error,
+    },
     #[error("error reading prompt")]
     DialoguerError {
         #[source]
@@ -230,6 +276,8 @@ pub enum ExpectedError {
         current: Version,
         tool: Option<String>,
     },
+    #[error("warnings denied")]
+    WarningsDenied { codes: Vec<WarningCode> },
     #[error("experimental feature not enabled")]
     ExperimentalFeatureNotEnabled {
         name: &'static str,
@@ -250,12 +298,37 @@ pub enum ExpectedError {
         #[source]
         err: shell_words::ParseError,
     },
+    #[error("double-spawn pre-exec hook error")]
+    DoubleSpawnPreExecError {
+        #[source]
+        err: PreExecHookError,
+    },
     #[error("double-spawn execution error")]
     DoubleSpawnExecError {
         command: std::process::Command,
         #[source]
         err: std::io::Error,
     },
+    #[error("adb execution error")]
+    AdbRunnerExecError {
+        command: String,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("adb command failed")]
+    AdbRunnerCommandFailed {
+        command: String,
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+    #[error("cargo miri exec failed")]
+    MiriExecFailed {
+        command: String,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("already running under Miri")]
+    MiriAlreadyActive,
     #[error("message format version is not valid")]
     InvalidMessageFormatVersion {
         #[from]
@@ -268,12 +341,21 @@ pub enum ExpectedError {
         #[source]
         err: std::io::Error,
     },
+    #[error("test not found")]
+    DebugTestNotFound { test_id: String },
     #[error("extract write error")]
     DebugExtractWriteError {
         format: ExtractOutputFormat,
         #[source]
         err: std::io::Error,
     },
+    #[error("error writing generated docs")]
+    GenerateDocsWriteError {
+        /// The path being written to, or `None` for standard output.
+        path: Option<Utf8PathBuf>,
+        #[source]
+        err: std::io::Error,
+    },
 }
 
 impl ExpectedError {
@@ -395,14 +477,20 @@ impl ExpectedError {
             | Self::GetCurrentExeFailed { .. }
             | Self::ProfileNotFound { .. }
             | Self::StoreDirCreateError { .. }
+            | Self::FlakyTestsWriteError { .. }
+            | Self::JunitReportError { .. }
+            | Self::UserConfigError { .. }
             | Self::RootManifestNotFound { .. }
+            | Self::SourceFingerprintMismatch { .. }
             | Self::CargoConfigError { .. }
+            | Self::TargetRunnerError { .. }
             | Self::TestFilterBuilderError { .. }
             | Self::UnknownHostPlatform { .. }
             | Self::TargetTripleError { .. }
             | Self::MetadataMaterializeError { .. }
             | Self::UnknownArchiveFormat { .. }
             | Self::ArchiveExtractError { .. }
+            | Self::ArchiveMetadataWriteError { .. }
             | Self::RustBuildMetaParseError { .. }
             | Self::PathMapperConstructError { .. }
             | Self::TestRunnerBuildError { .. }
@@ -413,7 +501,13 @@ impl ExpectedError {
             | Self::SignalHandlerSetupError { .. }
             | Self::ShowTestGroupsError { .. }
             | Self::InvalidMessageFormatVersion { .. }
-            | Self::DebugExtractReadError { .. } => NextestExitCode::SETUP_ERROR,
+            | Self::AdbRunnerExecError { .. }
+            | Self::AdbRunnerCommandFailed { .. }
+            | Self::MiriExecFailed { .. }
+            | Self::MiriAlreadyActive
+            | Self::MultipleWorkspaceRemapsUnsupported
+            | Self::DebugExtractReadError { .. }
+            | Self::DebugTestNotFound { .. } => NextestExitCode::SETUP_ERROR,
             Self::ConfigParseError { err } => {
                 // Experimental features not being enabled are their own error.
                 match err.kind() {
@@ -424,11 +518,12 @@ impl ExpectedError {
                 }
             }
             Self::RequiredVersionNotMet { .. } => NextestExitCode::REQUIRED_VERSION_NOT_MET,
+            Self::WarningsDenied { .. } => NextestExitCode::WARNINGS_DENIED,
             #[cfg(feature = "self-update")]
             Self::UpdateVersionParseError { .. } => NextestExitCode::SETUP_ERROR,
-            Self::DoubleSpawnParseArgsError { .. } | Self::DoubleSpawnExecError { .. } => {
-                NextestExitCode::DOUBLE_SPAWN_ERROR
-            }
+            Self::DoubleSpawnParseArgsError { .. }
+            | Self::DoubleSpawnPreExecError { .. }
+            | Self::DoubleSpawnExecError { .. } => NextestExitCode::DOUBLE_SPAWN_ERROR,
             Self::FromMessagesError { .. } | Self::CreateTestListError { .. } => {
                 NextestExitCode::TEST_LIST_CREATION_FAILED
             }
@@ -436,7 +531,9 @@ impl ExpectedError {
                 NextestExitCode::BUILD_FAILED
             }
             Self::SetupScriptFailed => NextestExitCode::SETUP_SCRIPT_FAILED,
-            Self::TestRunFailed => NextestExitCode::TEST_RUN_FAILED,
+            Self::TestRunFailed | Self::PathDependentTests { .. } => {
+                NextestExitCode::TEST_RUN_FAILED
+            }
             Self::NoTestsRun { .. } => NextestExitCode::NO_TESTS_RUN,
             Self::ArchiveCreateError { .. } => NextestExitCode::ARCHIVE_CREATION_FAILED,
             Self::WriteTestListError { .. }
@@ -444,7 +541,10 @@ impl ExpectedError {
             // TestRunnerExecuteErrors isn't _quite_ a WRITE_OUTPUT_ERROR, but
             // we keep this for backwards compatibility.
             | Self::TestRunnerExecuteErrors { .. }
-            | Self::DebugExtractWriteError { .. } => NextestExitCode::WRITE_OUTPUT_ERROR,
+            | Self::DebugExtractWriteError { .. }
+            | Self::GenerateDocsWriteError { .. } => NextestExitCode::WRITE_OUTPUT_ERROR,
+            #[cfg(feature = "self-update")]
+            Self::UpdateCheckWriteError { .. } => NextestExitCode::WRITE_OUTPUT_ERROR,
             #[cfg(feature = "self-update")]
             Self::UpdateError { .. } => NextestExitCode::UPDATE_ERROR,
             Self::ExperimentalFeatureNotEnabled { .. } => {
@@ -454,9 +554,165 @@ impl ExpectedError {
         }
     }
 
+    /// Returns a short, stable code identifying the kind of error.
+    ///
+    /// This is used in JSON error output.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::SetCurrentDirFailed { .. } => "set-current-dir-failed",
+            Self::GetCurrentExeFailed { .. } => "get-current-exe-failed",
+            Self::CargoMetadataExecFailed { .. } => "cargo-metadata-exec-failed",
+            Self::CargoMetadataFailed { .. } => "cargo-metadata-failed",
+            Self::CargoLocateProjectExecFailed { .. } => "cargo-locate-project-exec-failed",
+            Self::CargoLocateProjectFailed { .. } => "cargo-locate-project-failed",
+            Self::WorkspaceRootInvalidUtf8 { .. } => "workspace-root-invalid-utf8",
+            Self::WorkspaceRootInvalid { .. } => "workspace-root-invalid",
+            Self::RootManifestNotFound { .. } => "root-manifest-not-found",
+            Self::SourceFingerprintMismatch { .. } => "source-fingerprint-mismatch",
+            Self::ProfileNotFound { .. } => "profile-not-found",
+            Self::FlakyTestsWriteError { .. } => "flaky-tests-write-error",
+            Self::JunitReportError { .. } => "junit-report-error",
+            Self::UserConfigError { .. } => "user-config-error",
+            Self::StoreDirCreateError { .. } => "store-dir-create-error",
+            Self::CargoConfigError { .. } => "cargo-config-error",
+            Self::TargetRunnerError { .. } => "target-runner-error",
+            Self::ConfigParseError { .. } => "config-parse-error",
+            Self::TestFilterBuilderError { .. } => "test-filter-builder-error",
+            Self::UnknownHostPlatform { .. } => "unknown-host-platform",
+            Self::TargetTripleError { .. } => "target-triple-error",
+            Self::MetadataMaterializeError { .. } => "metadata-materialize-error",
+            Self::UnknownArchiveFormat { .. } => "unknown-archive-format",
+            Self::ArchiveCreateError { .. } => "archive-create-error",
+            Self::ArchiveExtractError { .. } => "archive-extract-error",
+            Self::ArchiveMetadataWriteError { .. } => "archive-metadata-write-error",
+            Self::PathMapperConstructError { .. } => "path-mapper-construct-error",
+            Self::CargoMetadataParseError { .. } => "cargo-metadata-parse-error",
+            Self::RustBuildMetaParseError { .. } => "rust-build-meta-parse-error",
+            Self::FromMessagesError { .. } => "from-messages-error",
+            Self::CreateTestListError { .. } => "create-test-list-error",
+            Self::BuildExecFailed { .. } => "build-exec-failed",
+            Self::BuildFailed { .. } => "build-failed",
+            Self::TestRunnerBuildError { .. } => "test-runner-build-error",
+            Self::WriteTestListError { .. } => "write-test-list-error",
+            Self::WriteEventError { .. } => "write-event-error",
+            Self::TestRunnerExecuteErrors { .. } => "test-runner-execute-errors",
+            Self::ConfigureHandleInheritanceError { .. } => "configure-handle-inheritance-error",
+            Self::ShowTestGroupsError { .. } => "show-test-groups-error",
+            Self::SetupScriptFailed => "setup-script-failed",
+            Self::TestRunFailed => "test-run-failed",
+            Self::PathDependentTests { .. } => "path-dependent-tests",
+            Self::MultipleWorkspaceRemapsUnsupported => "multiple-workspace-remaps-unsupported",
+            Self::NoTestsRun { .. } => "no-tests-run",
+            #[cfg(feature = "self-update")]
+            Self::UpdateVersionParseError { .. } => "update-version-parse-error",
+            #[cfg(feature = "self-update")]
+            Self::UpdateError { .. } => "update-error",
+            #[cfg(feature = "self-update")]
+            Self::UpdateCheckWriteError { .. } => "update-check-write-error",
+            Self::DialoguerError { .. } => "dialoguer-error",
+            Self::SignalHandlerSetupError { .. } => "signal-handler-setup-error",
+            Self::RequiredVersionNotMet { .. } => "required-version-not-met",
+            Self::WarningsDenied { .. } => "warnings-denied",
+            Self::ExperimentalFeatureNotEnabled { .. } => "experimental-feature-not-enabled",
+            Self::FiltersetParseError { .. } => "filterset-parse-error",
+            Self::TestBinaryArgsParseError { .. } => "test-binary-args-parse-error",
+            Self::DoubleSpawnParseArgsError { .. } => "double-spawn-parse-args-error",
+            Self::DoubleSpawnPreExecError { .. } => "double-spawn-pre-exec-error",
+            Self::DoubleSpawnExecError { .. } => "double-spawn-exec-error",
+            Self::AdbRunnerExecError { .. } => "adb-runner-exec-error",
+            Self::AdbRunnerCommandFailed { .. } => "adb-runner-command-failed",
+            Self::MiriExecFailed { .. } => "miri-exec-failed",
+            Self::MiriAlreadyActive => "miri-already-active",
+            Self::InvalidMessageFormatVersion { .. } => "invalid-message-format-version",
+            Self::DebugExtractReadError { .. } => "debug-extract-read-error",
+            Self::DebugTestNotFound { .. } => "debug-test-not-found",
+            Self::DebugExtractWriteError { .. } => "debug-extract-write-error",
+            Self::GenerateDocsWriteError { .. } => "generate-docs-write-error",
+        }
+    }
+
     /// Displays this error to stderr.
     pub fn display_to_stderr(&self, styles: &StderrStyles) {
-        let mut next_error = match &self {
+        let mut next_error = self.display_message(styles);
+        while let Some(err) = next_error {
+            error!(target: "cargo_nextest::no_heading", "\nCaused by:\n  {}", err);
+            next_error = err.source();
+        }
+    }
+
+    /// Displays this error to stderr as a single line of JSON.
+    ///
+    /// The JSON object contains the error's code and exit code, the message
+    /// that would be displayed in human-readable output, the chain of
+    /// underlying causes, and any diagnostics with source spans in the format
+    /// produced by [`miette::JSONReportHandler`].
+    pub fn display_json_to_stderr(&self) {
+        let mut next_error = None;
+        let log_messages = capture_log_messages(|| {
+            next_error = self.display_message(&StderrStyles::default());
+        });
+
+        let (messages, notes): (Vec<_>, Vec<_>) = log_messages
+            .into_iter()
+            .partition(|(level, _)| *level == Level::ERROR);
+
+        let mut causes = Vec::new();
+        while let Some(err) = next_error {
+            causes.push(err.to_string());
+            next_error = err.source();
+        }
+
+        let diagnostics: Vec<_> = self
+            .miette_reports()
+            .into_iter()
+            .filter_map(|report| {
+                let mut json = String::new();
+                miette::JSONReportHandler::new()
+                    .render_report(&mut json, &*report)
+                    .ok()?;
+                serde_json::from_str::<serde_json::Value>(&json).ok()
+            })
+            .collect();
+
+        let json = serde_json::json!({
+            "code": self.error_code(),
+            "exit-code": self.process_exit_code(),
+            "message": messages.into_iter().map(|(_, message)| message).join("\n"),
+            "notes": notes.into_iter().map(|(_, message)| message).collect::<Vec<_>>(),
+            "causes": causes,
+            "diagnostics": diagnostics,
+        });
+        eprintln!("{json}");
+    }
+
+    /// Returns miette reports with source spans for this error, if any.
+    fn miette_reports(&self) -> Vec<miette::Report> {
+        match self {
+            Self::ConfigParseError { err } => match err.kind() {
+                ConfigParseErrorKind::CompileErrors(errors) => errors
+                    .iter()
+                    .flat_map(|compile_error| compile_error.kind.reports())
+                    .collect(),
+                _ => Vec::new(),
+            },
+            Self::TargetTripleError { err } => err.source_report().into_iter().collect(),
+            Self::FiltersetParseError { all_errors } => all_errors
+                .iter()
+                .flat_map(|errors| {
+                    errors.errors.iter().map(|single_error| {
+                        miette::Report::new(single_error.clone())
+                            .with_source_code(errors.input.to_owned())
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Logs the message for this error, returning the underlying cause if
+    /// there is one.
+    fn display_message(&self, styles: &StderrStyles) -> Option<&dyn Error> {
+        match &self {
             Self::SetCurrentDirFailed { error } => {
                 error!("could not change to requested directory");
                 Some(error as &dyn Error)
@@ -520,6 +776,35 @@ impl ExpectedError {
                 );
                 None
             }
+            Self::SourceFingerprintMismatch {
+                mismatches,
+                nextest_version,
+            } => {
+                let version_str = match nextest_version {
+                    Some(version) => {
+                        format!("\n  (archive was created by cargo-nextest {version})")
+                    }
+                    None => String::new(),
+                };
+                error!(
+                    "archive was built from a different source tree than the current workspace:\n{}{version_str}\n\
+                     (hint: pass in --source-mismatch warn or ignore to run anyway)",
+                    mismatches.iter().map(|m| format!("  - {m}")).join("\n"),
+                );
+                None
+            }
+            Self::FlakyTestsWriteError { err } => {
+                error!("{}", err);
+                err.source()
+            }
+            Self::JunitReportError { err } => {
+                error!("{}", err);
+                err.source()
+            }
+            Self::UserConfigError { err } => {
+                error!("{}", err);
+                err.source()
+            }
             Self::StoreDirCreateError { store_dir, err } => {
                 error!(
                     "failed to create store dir at `{}`",
@@ -531,6 +816,10 @@ impl ExpectedError {
                 error!("{}", err);
                 err.source()
             }
+            Self::TargetRunnerError { err } => {
+                error!("{}", err);
+                err.source()
+            }
             Self::ConfigParseError { err } => {
                 match err.kind() {
                     ConfigParseErrorKind::CompileErrors(errors) => {
@@ -560,6 +849,15 @@ impl ExpectedError {
                                         index.style(styles.bold)
                                     )
                                 }
+                                ConfigCompileSection::Banned(index) => {
+                                    let banned =
+                                        format!("profile.{}.banned", compile_error.profile_name);
+                                    format!(
+                                        "{} at index {}",
+                                        banned.style(styles.bold),
+                                        index.style(styles.bold)
+                                    )
+                                }
                             };
                             error!(
                                 "for config file `{}`{}, failed to parse {}",
@@ -698,6 +996,13 @@ impl ExpectedError {
                 );
                 Some(err as &dyn Error)
             }
+            Self::ArchiveMetadataWriteError { path, err } => {
+                error!(
+                    "error writing archive metadata to `{}`",
+                    path.style(styles.bold)
+                );
+                Some(err as &dyn Error)
+            }
             Self::RustBuildMetaParseError { err } => {
                 error!("error parsing Rust build metadata");
                 Some(err as &dyn Error)
@@ -774,6 +1079,21 @@ impl ExpectedError {
                 error!("test run failed");
                 None
             }
+            Self::PathDependentTests { count } => {
+                error!(
+                    "{} {} had different results depending on the workspace path",
+                    count.style(styles.bold),
+                    if *count == 1 { "test" } else { "tests" },
+                );
+                None
+            }
+            Self::MultipleWorkspaceRemapsUnsupported => {
+                error!(
+                    "--workspace-remap can only be specified multiple times \
+                     with `cargo nextest run`"
+                );
+                None
+            }
             Self::NoTestsRun { is_default } => {
                 let hint_str = if *is_default {
                     "\n(hint: use `--no-tests` to customize)"
@@ -812,6 +1132,17 @@ impl ExpectedError {
                 );
                 None
             }
+            Self::WarningsDenied { codes } => {
+                error!(
+                    "warnings were produced and --deny-warnings is set: {}",
+                    codes.iter().map(|code| code.style(styles.bold)).join(", "),
+                );
+                info!(
+                    target: "cargo_nextest::no_heading",
+                    "(hint: pass in --allow-warning <CODE> to allow a specific kind of warning)",
+                );
+                None
+            }
             #[cfg(feature = "self-update")]
             Self::UpdateVersionParseError { err } => {
                 error!("failed to parse --version");
@@ -825,6 +1156,11 @@ impl ExpectedError {
                 );
                 Some(err as &dyn Error)
             }
+            #[cfg(feature = "self-update")]
+            Self::UpdateCheckWriteError { err } => {
+                error!("error writing update check result to standard output");
+                Some(err as &dyn Error)
+            }
             Self::DialoguerError { err } => {
                 error!("error reading input prompt");
                 Some(err as &dyn Error)
@@ -863,10 +1199,40 @@ impl ExpectedError {
                 error!("[double-spawn] failed to parse arguments `{args}`");
                 Some(err as &dyn Error)
             }
+            Self::DoubleSpawnPreExecError { err } => {
+                error!("[double-spawn] {err}");
+                err.source()
+            }
             Self::DoubleSpawnExecError { command, err } => {
                 error!("[double-spawn] failed to exec `{command:?}`");
                 Some(err as &dyn Error)
             }
+            Self::AdbRunnerExecError { command, err } => {
+                error!("[adb-runner] failed to execute `{command}`");
+                Some(err as &dyn Error)
+            }
+            Self::AdbRunnerCommandFailed {
+                command,
+                status,
+                stderr,
+            } => {
+                error!(
+                    "[adb-runner] `{command}` failed with {status}:\n{}",
+                    stderr.trim_end()
+                );
+                None
+            }
+            Self::MiriExecFailed { command, err } => {
+                error!("failed to execute `{}`", command.style(styles.bold));
+                Some(err as &dyn Error)
+            }
+            Self::MiriAlreadyActive => {
+                error!(
+                    "`cargo nextest miri` cannot be run under Miri \
+                     (hint: use `cargo miri nextest` or `cargo nextest miri`, but not both)"
+                );
+                None
+            }
             Self::InvalidMessageFormatVersion { err } => {
                 error!("error parsing message format version");
                 Some(err as &dyn Error)
@@ -875,15 +1241,24 @@ impl ExpectedError {
                 error!("error reading {kind} file `{}`", path.style(styles.bold),);
                 Some(err as &dyn Error)
             }
+            Self::DebugTestNotFound { test_id } => {
+                error!(
+                    "test `{}` not found\n(hint: test IDs are of the form `<binary-id> <test-name>`)",
+                    test_id.style(styles.bold),
+                );
+                None
+            }
             Self::DebugExtractWriteError { format, err } => {
                 error!("error writing {format} output");
                 Some(err as &dyn Error)
             }
-        };
-
-        while let Some(err) = next_error {
-            error!(target: "cargo_nextest::no_heading", "\nCaused by:\n  {}", err);
-            next_error = err.source();
+            Self::GenerateDocsWriteError { path, err } => {
+                match path {
+                    Some(path) => error!("error writing docs to `{}`", path.style(styles.bold)),
+                    None => error!("error writing docs to standard output"),
+                }
+                Some(err as &dyn Error)
+            }
         }
     }
 }
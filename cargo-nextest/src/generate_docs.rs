@@ -0,0 +1,449 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Rendering of man pages and Markdown reference docs from the clap definitions.
+
+use crate::{CargoNextestApp, ExpectedError, Result};
+use camino::Utf8Path;
+use clap::{builder::PossibleValue, Arg, Command, CommandFactory, ValueEnum};
+use itertools::Itertools;
+use std::io::Write;
+use swrite::{swrite, swriteln, SWrite};
+
+/// The format to generate reference documentation in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub(crate) enum DocsFormat {
+    /// One man page per command, e.g. `cargo-nextest-run.1`.
+    Man,
+
+    /// A single Markdown document covering every command.
+    Markdown,
+}
+
+/// The file name the Markdown reference is written to with `--output-dir`.
+const MARKDOWN_FILE_NAME: &str = "cargo-nextest.md";
+
+/// Generates documentation for `cargo nextest` and all its subcommands.
+///
+/// Man pages are written out to `output_dir`, which is required. The Markdown reference is written
+/// to `output_dir` if specified, and to standard output otherwise.
+pub(crate) fn generate_docs(format: DocsFormat, output_dir: Option<&Utf8Path>) -> Result<()> {
+    let root = nextest_command();
+    let commands = all_commands(&root);
+
+    match format {
+        DocsFormat::Man => {
+            let output_dir = output_dir.expect("clap requires --output-dir for man pages");
+            create_dir(output_dir)?;
+            for command in commands {
+                let path = output_dir.join(format!("{}.1", page_name(command)));
+                write_file(&path, &render_man(command))?;
+            }
+        }
+        DocsFormat::Markdown => {
+            let markdown = render_markdown(&commands);
+            match output_dir {
+                Some(output_dir) => {
+                    create_dir(output_dir)?;
+                    write_file(&output_dir.join(MARKDOWN_FILE_NAME), &markdown)?;
+                }
+                None => std::io::stdout()
+                    .write_all(markdown.as_bytes())
+                    .map_err(|err| ExpectedError::GenerateDocsWriteError { path: None, err })?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn create_dir(dir: &Utf8Path) -> Result<()> {
+    std::fs::create_dir_all(dir).map_err(|err| ExpectedError::GenerateDocsWriteError {
+        path: Some(dir.to_owned()),
+        err,
+    })
+}
+
+fn write_file(path: &Utf8Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents).map_err(|err| ExpectedError::GenerateDocsWriteError {
+        path: Some(path.to_owned()),
+        err,
+    })
+}
+
+/// Returns the fully built `cargo nextest` command.
+fn nextest_command() -> Command {
+    let mut app = CargoNextestApp::command();
+    // Building the command propagates bin names (e.g. `cargo nextest run`) and global arguments to
+    // subcommands.
+    app.build();
+    app.find_subcommand("nextest")
+        .expect("nextest subcommand is defined")
+        .clone()
+}
+
+/// Returns `command` and all its subcommands, depth-first.
+///
+/// Hidden subcommands are included, since packagers and integrators may need to look them up.
+/// clap's generated `help` subcommands are not.
+fn all_commands(command: &Command) -> Vec<&Command> {
+    let mut commands = vec![command];
+    for subcommand in command.get_subcommands() {
+        if subcommand.get_name() != "help" {
+            commands.extend(all_commands(subcommand));
+        }
+    }
+    commands
+}
+
+fn bin_name(command: &Command) -> &str {
+    command.get_bin_name().unwrap_or_else(|| command.get_name())
+}
+
+/// The man page name for a command: `cargo nextest run` becomes `cargo-nextest-run`.
+fn page_name(command: &Command) -> String {
+    bin_name(command).replace(' ', "-")
+}
+
+fn usage(command: &Command) -> String {
+    let usage = command.clone().render_usage().to_string();
+    match usage.strip_prefix("Usage: ") {
+        Some(usage) => usage.to_owned(),
+        None => usage,
+    }
+}
+
+fn visible_args(command: &Command) -> impl Iterator<Item = &Arg> {
+    command.get_arguments().filter(|arg| !arg.is_hide_set())
+}
+
+/// Returns a command's arguments grouped by help heading, in the order they're shown in `--help`.
+fn args_by_heading(command: &Command) -> Vec<(&str, Vec<&Arg>)> {
+    let mut groups: Vec<(&str, Vec<&Arg>)> = Vec::new();
+    let positionals = visible_args(command).filter(|arg| arg.is_positional());
+    let options = visible_args(command).filter(|arg| !arg.is_positional());
+    for arg in positionals.chain(options) {
+        let heading = match arg.get_help_heading() {
+            Some(heading) => heading,
+            None if arg.is_positional() => "Arguments",
+            None => "Options",
+        };
+        match groups.iter_mut().find(|(h, _)| *h == heading) {
+            Some((_, args)) => args.push(arg),
+            None => groups.push((heading, vec![arg])),
+        }
+    }
+    groups
+}
+
+/// Returns the flag spec for an argument, e.g. `-j, --test-threads <THREADS>`.
+fn arg_spec(arg: &Arg) -> String {
+    let value = arg
+        .get_value_names()
+        .map(|names| names.iter().map(|name| format!("<{name}>")).join(" "))
+        .unwrap_or_else(|| format!("<{}>", arg.get_id().as_str().to_uppercase()));
+
+    if arg.is_positional() {
+        return value;
+    }
+
+    let mut spec = String::new();
+    if let Some(short) = arg.get_short() {
+        swrite!(spec, "-{short}");
+    }
+    if let Some(long) = arg.get_long() {
+        if !spec.is_empty() {
+            spec.push_str(", ");
+        }
+        swrite!(spec, "--{long}");
+    }
+    if arg.get_action().takes_values() {
+        swrite!(spec, " {value}");
+    }
+    spec
+}
+
+fn arg_help(arg: &Arg) -> String {
+    arg.get_long_help()
+        .or_else(|| arg.get_help())
+        .map(|help| help.to_string())
+        .unwrap_or_default()
+}
+
+/// Returns notes shown after an argument's help: possible values, defaults and environment
+/// variables.
+fn arg_notes(arg: &Arg) -> Vec<String> {
+    let mut notes = Vec::new();
+
+    let possible_values: Vec<PossibleValue> = arg
+        .get_possible_values()
+        .into_iter()
+        .filter(|value| !value.is_hide_set())
+        .collect();
+    // Boolean flags have the possible values "true" and "false", which aren't worth listing.
+    if arg.get_action().takes_values() && !possible_values.is_empty() {
+        notes.push(format!(
+            "Possible values: {}",
+            possible_values
+                .iter()
+                .map(|value| value.get_name())
+                .join(", ")
+        ));
+    }
+
+    let defaults = arg.get_default_values();
+    if arg.get_action().takes_values() && !defaults.is_empty() {
+        notes.push(format!(
+            "Default: {}",
+            defaults
+                .iter()
+                .map(|value| value.to_string_lossy())
+                .join(", ")
+        ));
+    }
+
+    if let Some(env) = arg.get_env() {
+        notes.push(format!("Environment variable: {}", env.to_string_lossy()));
+    }
+
+    notes
+}
+
+fn command_about(command: &Command) -> String {
+    command
+        .get_about()
+        .map(|about| about.to_string())
+        .unwrap_or_default()
+}
+
+fn command_long_about(command: &Command) -> String {
+    command
+        .get_long_about()
+        .or_else(|| command.get_about())
+        .map(|about| about.to_string())
+        .unwrap_or_default()
+}
+
+fn documented_subcommands(command: &Command) -> impl Iterator<Item = &Command> {
+    command
+        .get_subcommands()
+        .filter(|subcommand| subcommand.get_name() != "help")
+}
+
+// ---
+// Man pages
+// ---
+
+fn render_man(command: &Command) -> String {
+    let mut out = String::new();
+    let name = page_name(command);
+
+    swriteln!(
+        out,
+        ".TH {} 1 \"\" \"cargo-nextest {}\"",
+        roff_escape(&name.to_uppercase()),
+        env!("CARGO_PKG_VERSION"),
+    );
+
+    out.push_str(".SH NAME\n");
+    let about = command_about(command);
+    if about.is_empty() {
+        swriteln!(out, "{}", roff_escape(&name));
+    } else {
+        swriteln!(out, "{} \\- {}", roff_escape(&name), roff_escape(&about));
+    }
+
+    out.push_str(".SH SYNOPSIS\n");
+    swriteln!(out, "\\fB{}\\fR", roff_escape(&usage(command)));
+
+    let long_about = command_long_about(command);
+    if !long_about.is_empty() {
+        out.push_str(".SH DESCRIPTION\n");
+        write_roff_paragraphs(&mut out, &long_about);
+    }
+
+    for (heading, args) in args_by_heading(command) {
+        swriteln!(out, ".SH {}", roff_escape(&heading.to_uppercase()));
+        for arg in args {
+            out.push_str(".TP\n");
+            swriteln!(out, "\\fB{}\\fR", roff_escape(&arg_spec(arg)));
+            write_roff_paragraphs(&mut out, &arg_help(arg));
+            for note in arg_notes(arg) {
+                out.push_str(".IP\n");
+                swriteln!(out, "{}", roff_line(&note));
+            }
+        }
+    }
+
+    let subcommands: Vec<_> = documented_subcommands(command).collect();
+    if !subcommands.is_empty() {
+        out.push_str(".SH SUBCOMMANDS\n");
+        for subcommand in subcommands {
+            out.push_str(".TP\n");
+            swriteln!(out, "\\fB{}\\fR(1)", roff_escape(&page_name(subcommand)));
+            write_roff_paragraphs(&mut out, &command_about(subcommand));
+        }
+    }
+
+    out.push_str(".SH SEE ALSO\n");
+    out.push_str("https://nexte.st\n");
+
+    out
+}
+
+/// Writes text out as roff, with blank lines starting new paragraphs.
+fn write_roff_paragraphs(out: &mut String, text: &str) {
+    for (index, paragraph) in text.trim().split("\n\n").enumerate() {
+        if index > 0 {
+            out.push_str(".IP\n");
+        }
+        for line in paragraph.lines() {
+            swriteln!(out, "{}", roff_line(line.trim_end()));
+        }
+    }
+}
+
+/// Escapes a line of text, so that lines starting with control characters aren't treated as
+/// requests.
+fn roff_line(line: &str) -> String {
+    let escaped = roff_escape(line);
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{escaped}")
+    } else {
+        escaped
+    }
+}
+
+fn roff_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\e"),
+            '-' => out.push_str("\\-"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ---
+// Markdown
+// ---
+
+fn render_markdown(commands: &[&Command]) -> String {
+    let mut out = String::new();
+
+    out.push_str("# Command-line reference\n\n");
+    swriteln!(
+        out,
+        "<!-- Generated by `cargo nextest self generate-docs --format markdown` (cargo-nextest {}). -->",
+        env!("CARGO_PKG_VERSION"),
+    );
+
+    for command in commands {
+        swriteln!(out, "\n## `{}`\n", bin_name(command));
+
+        let long_about = command_long_about(command);
+        if !long_about.is_empty() {
+            swriteln!(out, "{}\n", long_about.trim());
+        }
+
+        swriteln!(out, "```text\n{}\n```", usage(command));
+
+        let subcommands: Vec<_> = documented_subcommands(command).collect();
+        if !subcommands.is_empty() {
+            out.push_str("\n**Subcommands:**\n\n");
+            for subcommand in subcommands {
+                let about = command_about(subcommand);
+                if about.is_empty() {
+                    swriteln!(out, "- `{}`", subcommand.get_name());
+                } else {
+                    swriteln!(out, "- `{}`: {}", subcommand.get_name(), about.trim());
+                }
+            }
+        }
+
+        for (heading, args) in args_by_heading(command) {
+            swriteln!(out, "\n**{heading}:**\n");
+            for arg in args {
+                swriteln!(out, "`{}`", arg_spec(arg));
+                let help = arg_help(arg);
+                let notes = arg_notes(arg);
+                let mut lines = help.trim().lines().chain(notes.iter().map(|note| &**note));
+                if let Some(first) = lines.next() {
+                    swriteln!(out, ": {first}");
+                    for line in lines {
+                        if line.is_empty() {
+                            out.push('\n');
+                        } else {
+                            swriteln!(out, "    {line}");
+                        }
+                    }
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_commands() {
+        let root = nextest_command();
+        let names: Vec<_> = all_commands(&root).into_iter().map(bin_name).collect();
+        assert_eq!(names[0], "cargo nextest");
+        for expected in [
+            "cargo nextest run",
+            "cargo nextest list",
+            "cargo nextest self update",
+            "cargo nextest self generate-docs",
+            // Hidden commands are included.
+            "cargo nextest debug extract",
+        ] {
+            assert!(names.contains(&expected), "{expected} in {names:?}");
+        }
+        assert!(
+            !names.iter().any(|name| name.ends_with(" help")),
+            "help subcommands are skipped: {names:?}"
+        );
+    }
+
+    #[test]
+    fn test_render_man() {
+        let root = nextest_command();
+        let run = root.find_subcommand("run").unwrap();
+        assert_eq!(page_name(run), "cargo-nextest-run");
+
+        let page = render_man(run);
+        assert!(
+            page.starts_with(".TH CARGO\\-NEXTEST\\-RUN 1 "),
+            "title header: {page}"
+        );
+        assert!(page.contains("\\fB\\-\\-no\\-capture\\fR"), "{page}");
+        assert!(page.contains("\n.SH RUNNER OPTIONS\n"), "{page}");
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let root = nextest_command();
+        let markdown = render_markdown(&all_commands(&root));
+        assert!(
+            markdown.contains("\n## `cargo nextest run`\n"),
+            "{markdown}"
+        );
+        assert!(markdown.contains("\n`--no-capture`\n"), "{markdown}");
+        assert!(markdown.contains("- `run`: "), "{markdown}");
+    }
+
+    #[test]
+    fn test_roff_line() {
+        assert_eq!(roff_line("--foo \\bar"), "\\-\\-foo \\ebar");
+        assert_eq!(roff_line(".TH is a request"), "\\&.TH is a request");
+        assert_eq!(roff_line("'quoted"), "\\&'quoted");
+    }
+}
@@ -13,13 +13,16 @@
 
 #![warn(missing_docs)]
 
+mod adb_runner;
 mod cargo_cli;
 mod dispatch;
 #[cfg(unix)]
 mod double_spawn;
 mod errors;
+mod generate_docs;
 mod helpers;
 mod output;
+mod remap_matrix;
 mod reuse_build;
 #[cfg(feature = "self-update")]
 mod update;
@@ -2,24 +2,32 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::ExpectedError;
 use clap::{Args, ValueEnum};
 use miette::{GraphicalTheme, MietteHandlerOpts, ThemeStyles};
-use nextest_runner::{reporter::ReporterStderr, write_str::WriteStr};
+use nextest_runner::{
+    reporter::ReporterStderr,
+    theme::Theme,
+    user_config::{Symbols, UserConfig},
+    write_str::WriteStr,
+};
 use owo_colors::{style, OwoColorize, Style};
 use std::{
     fmt,
     io::{self, BufWriter, Stderr, Stdout, Write},
     marker::PhantomData,
+    sync::{Arc, Mutex},
 };
+use swrite::{swrite, SWrite};
 use tracing::{
     field::{Field, Visit},
     level_filters::LevelFilter,
-    Event, Level, Subscriber,
+    warn, Event, Level, Subscriber,
 };
 use tracing_subscriber::{
     filter::Targets,
     fmt::{format, FmtContext, FormatEvent, FormatFields},
-    layer::SubscriberExt,
+    layer::{Context, SubscriberExt},
     registry::LookupSpan,
     util::SubscriberInitExt,
     Layer,
@@ -72,15 +80,48 @@ pub(crate) struct OutputOpts {
         env = "CARGO_TERM_COLOR"
     )]
     pub(crate) color: Color,
+
+    /// Format for fatal errors: human, json
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        hide_possible_values = true,
+        global = true,
+        value_name = "FORMAT",
+        env = "NEXTEST_ERROR_FORMAT"
+    )]
+    pub(crate) error_format: ErrorFormat,
 }
 
 impl OutputOpts {
     pub(crate) fn init(self) -> OutputContext {
-        let OutputOpts { verbose, color } = self;
+        let OutputOpts {
+            verbose,
+            color,
+            error_format,
+        } = self;
+
+        // The theme and symbols are read before the logger is initialized, so
+        // report errors reading the user config afterwards. Commands that need
+        // the rest of the user config read it again and fail on errors.
+        let (theme, symbols, user_config_error) = match UserConfig::from_default_location() {
+            Ok(user_config) => (*user_config.theme(), user_config.ui().symbols, None),
+            Err(error) => (Theme::default(), None, Some(error)),
+        };
 
-        color.init();
+        color.init(theme, symbols);
+        if let Some(error) = user_config_error {
+            warn!("{error}, using the default color theme");
+        }
 
-        OutputContext { verbose, color }
+        OutputContext {
+            verbose,
+            color,
+            error_format,
+            theme,
+            symbols,
+        }
     }
 }
 
@@ -89,16 +130,22 @@ impl OutputOpts {
 pub struct OutputContext {
     pub(crate) verbose: bool,
     pub(crate) color: Color,
+    pub(crate) error_format: ErrorFormat,
+    pub(crate) theme: Theme,
+    pub(crate) symbols: Option<Symbols>,
 }
 
 impl OutputContext {
     // color_never_init is only used for double-spawning, which only exists on Unix platforms.
     #[cfg(unix)]
     pub(crate) fn color_never_init() -> Self {
-        Color::Never.init();
+        Color::Never.init(Theme::default(), None);
         Self {
             verbose: false,
             color: Color::Never,
+            error_format: ErrorFormat::Human,
+            theme: Theme::default(),
+            symbols: None,
         }
     }
 
@@ -107,11 +154,31 @@ impl OutputContext {
         let mut styles = StderrStyles::default();
 
         if self.color.should_colorize(supports_color::Stream::Stderr) {
-            styles.colorize();
+            styles.colorize(&self.theme);
         }
 
         styles
     }
+
+    /// Displays a fatal error to stderr, in the format requested by
+    /// `--error-format`.
+    pub fn display_error(&self, error: &ExpectedError) {
+        match self.error_format {
+            ErrorFormat::Human => error.display_to_stderr(&self.stderr_styles()),
+            ErrorFormat::Json => error.display_json_to_stderr(),
+        }
+    }
+}
+
+/// The format in which fatal errors are displayed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ErrorFormat {
+    /// Human-readable output, with diagnostics rendered graphically.
+    #[default]
+    Human,
+
+    /// A single line of JSON, for tools that wrap nextest.
+    Json,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
@@ -202,12 +269,61 @@ impl Visit for MessageVisitor<'_, '_> {
     }
 }
 
+/// Runs `f`, collecting the messages it logs on the current thread instead of
+/// writing them to stderr.
+///
+/// Error messages without a heading are skipped: these are miette reports,
+/// which callers render separately.
+pub(crate) fn capture_log_messages(f: impl FnOnce()) -> Vec<(Level, String)> {
+    let collector = MessageCollector::default();
+    let subscriber = tracing_subscriber::registry().with(collector.clone());
+    tracing::subscriber::with_default(subscriber, f);
+
+    let messages = collector.messages.lock().unwrap();
+    messages.clone()
+}
+
+#[derive(Clone, Default)]
+struct MessageCollector {
+    messages: Arc<Mutex<Vec<(Level, String)>>>,
+}
+
+impl<S: Subscriber> Layer<S> for MessageCollector {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if metadata.target() == "cargo_nextest::no_heading" && *metadata.level() == Level::ERROR {
+            return;
+        }
+
+        let mut visitor = CollectMessageVisitor {
+            message: String::new(),
+        };
+        event.record(&mut visitor);
+        self.messages
+            .lock()
+            .unwrap()
+            .push((*metadata.level(), visitor.message));
+    }
+}
+
+struct CollectMessageVisitor {
+    message: String,
+}
+
+impl Visit for CollectMessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == MESSAGE_FIELD {
+            swrite!(self.message, "{:?}", value);
+        }
+    }
+}
+
 impl Color {
-    pub(crate) fn init(self) {
+    pub(crate) fn init(self, theme: Theme, symbols: Option<Symbols>) {
         // Pass the styles in as a stylesheet to ensure we use the latest supports-color here.
         let mut log_styles = LogStyles::default();
         if self.should_colorize(supports_color::Stream::Stderr) {
-            log_styles.colorize();
+            log_styles.colorize(&theme);
         }
 
         INIT_LOGGER.call_once(|| {
@@ -245,8 +361,8 @@ impl Color {
             miette::set_hook(Box::new(move |_| {
                 let theme_styles = if self.should_colorize(supports_color::Stream::Stderr) {
                     ThemeStyles {
-                        error: style().red().bold(),
-                        warning: style().yellow().bold(),
+                        error: theme.fail(),
+                        warning: theme.skip(),
                         advice: style().bright_cyan().bold(),
                         help: style().cyan(),
                         link: style().cyan().underline().bold(),
@@ -256,8 +372,12 @@ impl Color {
                 } else {
                     ThemeStyles::none()
                 };
-                let mut graphical_theme = if supports_unicode::on(supports_unicode::Stream::Stderr)
-                {
+                let use_unicode = match symbols {
+                    Some(Symbols::Unicode) => true,
+                    Some(Symbols::Ascii) => false,
+                    None => supports_unicode::on(supports_unicode::Stream::Stderr),
+                };
+                let mut graphical_theme = if use_unicode {
                     GraphicalTheme::unicode()
                 } else {
                     GraphicalTheme::ascii()
@@ -299,11 +419,11 @@ struct LogStyles {
 }
 
 impl LogStyles {
-    fn colorize(&mut self) {
-        self.error = style().red().bold();
-        self.warning = style().yellow().bold();
-        self.info = style().bold();
-        self.debug = style().bold();
+    fn colorize(&mut self, theme: &Theme) {
+        self.error = theme.fail();
+        self.warning = theme.skip();
+        self.info = theme.emphasis();
+        self.debug = theme.emphasis();
         self.trace = style().dimmed();
     }
 }
@@ -315,9 +435,12 @@ pub struct StderrStyles {
 }
 
 impl StderrStyles {
-    fn colorize(&mut self) {
-        self.bold = style().bold();
-        self.warning_text = style().yellow();
+    fn colorize(&mut self, theme: &Theme) {
+        self.bold = theme.emphasis();
+        self.warning_text = match theme.skip {
+            Some(_) => theme.skip(),
+            None => style().yellow(),
+        };
     }
 }
 
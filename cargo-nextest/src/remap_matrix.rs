@@ -0,0 +1,133 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for running the same build against several workspace remaps, and comparing the results.
+//!
+//! This is used to check that tests don't depend on the absolute path of the workspace, which is
+//! important for archives that are meant to be extracted and run elsewhere.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use nextest_metadata::RustBinaryId;
+use nextest_runner::reporter::events::{TestEvent, TestEventKind};
+use std::collections::BTreeMap;
+
+/// The outcomes of tests in a single run.
+#[derive(Debug, Default)]
+pub(crate) struct RemapOutcomes {
+    // (binary ID, test name) -> whether the test passed (possibly after retries).
+    outcomes: BTreeMap<(RustBinaryId, String), bool>,
+}
+
+impl RemapOutcomes {
+    /// Records the outcome of the test in `event`, if it's a test that finished.
+    pub(crate) fn record(&mut self, event: &TestEvent<'_>) {
+        if let TestEventKind::TestFinished {
+            test_instance,
+            run_statuses,
+            ..
+        } = &event.kind
+        {
+            let id = test_instance.id();
+            self.outcomes.insert(
+                (id.binary_id.clone(), id.test_name.to_owned()),
+                run_statuses.last_status().result.is_success(),
+            );
+        }
+    }
+}
+
+/// A test whose outcome differed between workspace remaps.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct PathDependentTest<'a> {
+    pub(crate) binary_id: &'a RustBinaryId,
+    pub(crate) test_name: &'a str,
+    /// Remaps that the test passed with.
+    pub(crate) passed: Vec<&'a Utf8Path>,
+    /// Remaps that the test failed with.
+    pub(crate) failed: Vec<&'a Utf8Path>,
+}
+
+/// Compares the outcomes of each run, and returns the tests that passed with some remaps but failed
+/// with others.
+///
+/// Tests that didn't run in every run (for example, because a run was cancelled) are only compared
+/// across the runs they were part of.
+pub(crate) fn path_dependent_tests(
+    runs: &[(Utf8PathBuf, RemapOutcomes)],
+) -> Vec<PathDependentTest<'_>> {
+    let mut by_test: BTreeMap<&(RustBinaryId, String), PathDependentTest<'_>> = BTreeMap::new();
+    for (remap, outcomes) in runs {
+        for (key, &passed) in &outcomes.outcomes {
+            let test = by_test.entry(key).or_insert_with(|| PathDependentTest {
+                binary_id: &key.0,
+                test_name: &key.1,
+                passed: Vec::new(),
+                failed: Vec::new(),
+            });
+            if passed {
+                test.passed.push(remap);
+            } else {
+                test.failed.push(remap);
+            }
+        }
+    }
+
+    by_test
+        .into_values()
+        .filter(|test| !test.passed.is_empty() && !test.failed.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcomes(entries: &[(&str, &str, bool)]) -> RemapOutcomes {
+        RemapOutcomes {
+            outcomes: entries
+                .iter()
+                .map(|&(binary_id, test_name, passed)| {
+                    ((RustBinaryId::new(binary_id), test_name.to_owned()), passed)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_path_dependent_tests() {
+        let runs = vec![
+            (
+                Utf8PathBuf::from("/a"),
+                outcomes(&[
+                    ("crate", "stable_pass", true),
+                    ("crate", "stable_fail", false),
+                    ("crate", "uses_abs_path", true),
+                    ("crate", "only_in_a", false),
+                ]),
+            ),
+            (
+                Utf8PathBuf::from("/b"),
+                outcomes(&[
+                    ("crate", "stable_pass", true),
+                    ("crate", "stable_fail", false),
+                    ("crate", "uses_abs_path", false),
+                ]),
+            ),
+            (
+                Utf8PathBuf::from("/c"),
+                outcomes(&[("crate", "uses_abs_path", true)]),
+            ),
+        ];
+
+        let binary_id = RustBinaryId::new("crate");
+        assert_eq!(
+            path_dependent_tests(&runs),
+            vec![PathDependentTest {
+                binary_id: &binary_id,
+                test_name: "uses_abs_path",
+                passed: vec![Utf8Path::new("/a"), Utf8Path::new("/c")],
+                failed: vec![Utf8Path::new("/b")],
+            }],
+        );
+    }
+}
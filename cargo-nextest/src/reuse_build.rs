@@ -6,17 +6,20 @@ use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Args, ValueEnum};
 use guppy::graph::PackageGraph;
 use nextest_runner::{
-    errors::PathMapperConstructKind,
+    errors::{PathMapperConstructKind, WriteTestListError},
+    list::{OutputFormat, SerializableFormat},
     redact::Redactor,
     reuse_build::{
         ArchiveFormat, ArchiveReporter, ExtractDestination, MetadataKind, MetadataWithRemap,
-        PathMapper, ReuseBuildInfo, ReusedBinaryList, ReusedCargoMetadata,
+        PathMapper, ReuseBuildInfo, ReusedBinaryList, ReusedCargoMetadata, SourceFingerprint,
+        BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME,
     },
+    warnings::{self, WarningCode},
 };
 use std::io::Write;
 use tracing::warn;
 
-#[derive(Debug, Default, Args)]
+#[derive(Clone, Debug, Default, Args)]
 #[command(
     next_help_heading = "Reuse build options",
     // These groups define data sources for various aspects of reuse-build inputs
@@ -61,6 +64,17 @@ pub(crate) struct ReuseBuildOpts {
     #[arg(long, conflicts_with_all = &["cargo-opts", "extract_to"], requires = "archive_file")]
     pub(crate) persist_extract_tempdir: bool,
 
+    /// What to do if the archive was built from a different source tree
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        requires = "archive_file",
+        value_name = "ACTION",
+        env = "NEXTEST_SOURCE_MISMATCH"
+    )]
+    pub(crate) source_mismatch: SourceMismatchOpt,
+
     /// Path to cargo metadata JSON
     #[arg(
         long,
@@ -71,8 +85,11 @@ pub(crate) struct ReuseBuildOpts {
     pub(crate) cargo_metadata: Option<Utf8PathBuf>,
 
     /// Remapping for the workspace root
+    ///
+    /// With `cargo nextest run`, this can be specified multiple times to run the same tests against
+    /// each remapped workspace in turn, and report tests whose results differ between them.
     #[arg(long, requires = "cargo-metadata-sources", value_name = "PATH")]
-    pub(crate) workspace_remap: Option<Utf8PathBuf>,
+    pub(crate) workspace_remap: Vec<Utf8PathBuf>,
 
     /// Path to binaries-metadata JSON
     #[arg(
@@ -102,7 +119,21 @@ impl ReuseBuildOpts {
     // before calling this method)
     pub(crate) fn check_experimental(&self, _output: OutputContext) {
         if std::env::var(Self::EXPERIMENTAL_ENV).is_ok() {
-            warn!("build reuse is no longer experimental: NEXTEST_EXPERIMENTAL_REUSE_BUILD does not need to be set");
+            warnings::emit(
+                WarningCode::ObsoleteEnvVar,
+                format_args!(
+                    "build reuse is no longer experimental: \
+                     NEXTEST_EXPERIMENTAL_REUSE_BUILD does not need to be set"
+                ),
+            );
+        }
+    }
+
+    /// Returns a copy of these options with the workspace remapped to just `remap`.
+    pub(crate) fn with_workspace_remap(&self, remap: &Utf8Path) -> Self {
+        Self {
+            workspace_remap: vec![remap.to_owned()],
+            ..self.clone()
         }
     }
 
@@ -111,6 +142,12 @@ impl ReuseBuildOpts {
         output: OutputContext,
         output_writer: &mut OutputWriter,
     ) -> Result<ReuseBuildInfo> {
+        let workspace_remap = match self.workspace_remap.as_slice() {
+            [] => None,
+            [remap] => Some(remap),
+            _ => return Err(ExpectedError::MultipleWorkspaceRemapsUnsupported),
+        };
+
         if let Some(archive_file) = &self.archive_file {
             let format = self.archive_format.to_archive_format(archive_file)?;
             // Process this archive.
@@ -124,29 +161,14 @@ impl ReuseBuildOpts {
                 },
             };
 
-            // TODO: make this redactor work.
-            let redactor = Redactor::noop();
-
-            let mut reporter = ArchiveReporter::new(output.verbose, redactor);
-            if output.color.should_colorize(supports_color::Stream::Stderr) {
-                reporter.colorize();
-            }
-
-            let mut writer = output_writer.stderr_writer();
-            return ReuseBuildInfo::extract_archive(
+            return extract_archive(
                 archive_file,
                 format,
                 dest,
-                |event| {
-                    reporter.report_event(event, &mut writer)?;
-                    writer.flush()
-                },
-                self.workspace_remap.as_deref(),
-            )
-            .map_err(|err| ExpectedError::ArchiveExtractError {
-                archive_file: archive_file.clone(),
-                err: Box::new(err),
-            });
+                workspace_remap.map(|remap| remap.as_path()),
+                output,
+                output_writer,
+            );
         }
 
         let cargo_metadata = self
@@ -155,7 +177,7 @@ impl ReuseBuildOpts {
             .map(|path| {
                 Ok(MetadataWithRemap {
                     metadata: ReusedCargoMetadata::materialize(path)?,
-                    remap: self.workspace_remap.clone(),
+                    remap: workspace_remap.cloned(),
                 })
             })
             .transpose()
@@ -177,6 +199,198 @@ impl ReuseBuildOpts {
     }
 }
 
+/// Options for `cargo nextest archive extract`.
+#[derive(Debug, Args)]
+#[command(next_help_heading = "Extract options")]
+pub(crate) struct ArchiveExtractOpts {
+    /// Path to nextest archive
+    #[arg(long, value_name = "PATH")]
+    archive_file: Utf8PathBuf,
+
+    /// Archive format
+    #[arg(long, value_enum, default_value_t, value_name = "FORMAT")]
+    archive_format: ArchiveFormatOpt,
+
+    /// Destination directory to extract archive to
+    #[arg(long, value_name = "DIR")]
+    extract_to: Utf8PathBuf,
+
+    /// Overwrite files in destination directory while extracting archive
+    #[arg(long)]
+    extract_overwrite: bool,
+
+    /// Remapping for the workspace root, included in the printed run command
+    #[arg(long, value_name = "PATH")]
+    workspace_remap: Option<Utf8PathBuf>,
+}
+
+impl ArchiveExtractOpts {
+    pub(crate) fn exec(
+        self,
+        output: OutputContext,
+        output_writer: &mut OutputWriter,
+    ) -> Result<i32> {
+        let format = self.archive_format.to_archive_format(&self.archive_file)?;
+        let dest = ExtractDestination::Destination {
+            dir: self.extract_to.clone(),
+            overwrite: self.extract_overwrite,
+        };
+        let reuse_build = extract_archive(
+            &self.archive_file,
+            format,
+            dest,
+            self.workspace_remap.as_deref(),
+            output,
+            output_writer,
+        )?;
+
+        let binaries_metadata = reuse_build
+            .binaries_metadata
+            .as_ref()
+            .expect("extracted archives always have binaries metadata");
+        let target_dir = binaries_metadata
+            .remap
+            .as_deref()
+            .expect("extracted binaries metadata is always remapped");
+        let dest_dir = target_dir
+            .parent()
+            .expect("extracted target directory is within the destination");
+
+        // Runs with --binaries-metadata don't know about libdirs extracted from the archive, so
+        // rewrite the metadata to point to them.
+        let mut binary_list = (*binaries_metadata.metadata.binary_list).clone();
+        binary_list.rust_build_meta.build_platforms = binary_list
+            .rust_build_meta
+            .build_platforms
+            .map_libdir(&reuse_build.libdir_mapper);
+        let binaries_metadata_path = dest_dir.join(BINARIES_METADATA_FILE_NAME);
+        let json =
+            binary_list.to_string(OutputFormat::Serializable(SerializableFormat::JsonPretty))?;
+        std::fs::write(&binaries_metadata_path, json).map_err(|err| {
+            ExpectedError::ArchiveMetadataWriteError {
+                path: binaries_metadata_path.clone(),
+                err,
+            }
+        })?;
+
+        // Print out the equivalent run command.
+        let mut command = vec![
+            "cargo".to_owned(),
+            "nextest".to_owned(),
+            "run".to_owned(),
+            "--binaries-metadata".to_owned(),
+            binaries_metadata_path.into_string(),
+            "--target-dir-remap".to_owned(),
+            target_dir.to_string(),
+            "--cargo-metadata".to_owned(),
+            dest_dir.join(CARGO_METADATA_FILE_NAME).into_string(),
+        ];
+        if let Some(workspace_remap) = &self.workspace_remap {
+            command.push("--workspace-remap".to_owned());
+            command.push(workspace_remap.to_string());
+        }
+        let mut writer = output_writer.stdout_writer();
+        writeln!(writer, "{}", shell_words::join(&command)).map_err(WriteTestListError::Io)?;
+        writer.flush().map_err(WriteTestListError::Io)?;
+
+        Ok(0)
+    }
+}
+
+fn extract_archive(
+    archive_file: &Utf8Path,
+    format: ArchiveFormat,
+    dest: ExtractDestination,
+    workspace_remap: Option<&Utf8Path>,
+    output: OutputContext,
+    output_writer: &mut OutputWriter,
+) -> Result<ReuseBuildInfo> {
+    // TODO: make this redactor work.
+    let redactor = Redactor::noop();
+
+    let mut reporter = ArchiveReporter::new(output.verbose, redactor);
+    if output.color.should_colorize(supports_color::Stream::Stderr) {
+        reporter.colorize_with_theme(&output.theme);
+    }
+
+    let mut writer = output_writer.stderr_writer();
+    ReuseBuildInfo::extract_archive(
+        archive_file,
+        format,
+        dest,
+        |event| {
+            reporter.report_event(event, &mut writer)?;
+            writer.flush()
+        },
+        workspace_remap,
+    )
+    .map_err(|err| ExpectedError::ArchiveExtractError {
+        archive_file: archive_file.to_owned(),
+        err: Box::new(err),
+    })
+}
+
+/// What to do if an archive's source fingerprint doesn't match the current workspace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum SourceMismatchOpt {
+    /// Print a warning and continue.
+    #[default]
+    Warn,
+
+    /// Exit with an error.
+    Error,
+
+    /// Don't check the source fingerprint.
+    Ignore,
+}
+
+impl SourceMismatchOpt {
+    /// Checks the fingerprint stored in the archive, if any, against the workspace at
+    /// `workspace_root` and the nextest config file in use.
+    pub(crate) fn check(
+        self,
+        reuse_build: &ReuseBuildInfo,
+        workspace_root: &Utf8Path,
+        config_file: Option<&Utf8Path>,
+    ) -> Result<()> {
+        if self == Self::Ignore {
+            return Ok(());
+        }
+        let Some(archived) = reuse_build.source_fingerprint() else {
+            return Ok(());
+        };
+
+        let current = SourceFingerprint::compute(workspace_root, config_file);
+        let mismatches = archived.mismatches(&current);
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+
+        match self {
+            Self::Warn => {
+                warnings::emit(
+                    WarningCode::ArchiveSourceMismatch,
+                    format_args!(
+                        "archive was built from a different source tree than the current workspace:"
+                    ),
+                );
+                for mismatch in &mismatches {
+                    warn!("  - {mismatch}");
+                }
+                if let Some(version) = &archived.nextest_version {
+                    warn!("  (archive was created by cargo-nextest {version})");
+                }
+                Ok(())
+            }
+            Self::Error => Err(ExpectedError::SourceFingerprintMismatch {
+                mismatches,
+                nextest_version: archived.nextest_version.clone(),
+            }),
+            Self::Ignore => unreachable!("handled above"),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
 pub(crate) enum ArchiveFormatOpt {
     Auto,
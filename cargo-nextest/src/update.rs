@@ -1,14 +1,18 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{output::OutputContext, ExpectedError, Result};
-use camino::Utf8PathBuf;
+use crate::{dispatch::UpdateMessageFormat, output::OutputContext, ExpectedError, Result};
+use camino::{Utf8Path, Utf8PathBuf};
 use nextest_metadata::NextestExitCode;
-use nextest_runner::update::{CheckStatus, MuktiBackend, UpdateVersion};
+use nextest_runner::{
+    config::{NextestVersionConfig, NextestVersionEval, NextestVersionReq},
+    update::{CheckStatus, LocalArchiveUpdate, MuktiBackend, UpdateChannel, UpdateVersion},
+    user_config::UserConfig,
+};
 use owo_colors::OwoColorize;
-use semver::Version;
-use std::cmp::Ordering;
-use tracing::info;
+use semver::{Version, VersionReq};
+use std::{cmp::Ordering, io::Write};
+use tracing::{info, warn};
 
 // Returns the smallest version with a `self setup` command.
 fn min_version_with_setup() -> Version {
@@ -22,25 +26,78 @@ fn min_version_with_setup() -> Version {
     }
 }
 
+/// Options for [`perform_update`].
+pub(crate) struct UpdateOpts {
+    /// The version or version range passed in on the command line.
+    pub(crate) version: Option<String>,
+    pub(crate) channel: UpdateChannel,
+    pub(crate) check: bool,
+    pub(crate) message_format: UpdateMessageFormat,
+    pub(crate) yes: bool,
+    pub(crate) force: bool,
+    pub(crate) releases_url: Option<String>,
+    pub(crate) mirror: Option<String>,
+    pub(crate) archive: Option<Utf8PathBuf>,
+    pub(crate) archive_sha256: Option<String>,
+    /// The workspace's nextest version configuration, or the default if not run in a workspace.
+    pub(crate) nextest_version: NextestVersionConfig,
+}
+
 /// Perform an update.
-pub(crate) fn perform_update(
-    version: &str,
-    check: bool,
-    yes: bool,
-    force: bool,
-    releases_url: Option<String>,
-    output: OutputContext,
-) -> Result<i32> {
-    let version = version
-        .parse::<UpdateVersion>()
-        .map_err(|err| ExpectedError::UpdateVersionParseError { err })?;
-    let releases_url =
-        releases_url.unwrap_or_else(|| "https://get.nexte.st/releases.json".to_owned());
+pub(crate) fn perform_update(opts: UpdateOpts, output: OutputContext) -> Result<i32> {
+    let UpdateOpts {
+        version,
+        channel,
+        check,
+        message_format,
+        yes,
+        force,
+        releases_url,
+        mirror,
+        archive,
+        archive_sha256,
+        nextest_version,
+    } = opts;
+
+    // The binary is always present at this path.
+    let mut bin_path_in_archive = Utf8PathBuf::from("cargo-nextest");
+    bin_path_in_archive.set_extension(std::env::consts::EXE_EXTENSION);
+
+    if let Some(archive) = archive {
+        return update_from_archive(archive, archive_sha256, bin_path_in_archive, yes, output);
+    }
+
+    // A version passed in on the command line takes precedence over the pinned range.
+    let version = match (version, &nextest_version.pin) {
+        (Some(version), _) => version
+            .parse::<UpdateVersion>()
+            .map_err(|err| ExpectedError::UpdateVersionParseError { err })?,
+        (None, Some(pin)) => {
+            info!("using version range {pin} pinned in nextest config");
+            UpdateVersion::Req(pin.clone())
+        }
+        (None, None) => UpdateVersion::Req(VersionReq::STAR),
+    };
+    let releases_url = match (&releases_url, &mirror) {
+        (Some(releases_url), _) => releases_url.clone(),
+        (None, Some(mirror)) => {
+            let mirror_path = Utf8Path::new(mirror);
+            if mirror_path.is_dir() {
+                mirror_path.join("releases.json").into_string()
+            } else {
+                format!("{}/releases.json", mirror.trim_end_matches('/'))
+            }
+        }
+        (None, None) => "https://get.nexte.st/releases.json".to_owned(),
+    };
 
     // Configure the backend.
+    let user_config = UserConfig::from_default_location()?;
     let backend = MuktiBackend {
         url: releases_url,
         package_name: "cargo-nextest".to_owned(),
+        mirror,
+        http: user_config.http().clone(),
     };
 
     let current_version: Version = env!("CARGO_PKG_VERSION")
@@ -49,17 +106,35 @@ pub(crate) fn perform_update(
 
     let releases = backend.fetch_releases(current_version.clone())?;
 
-    // The binary is always present at this path.
-    let mut bin_path_in_archive = Utf8PathBuf::from("cargo-nextest");
-    bin_path_in_archive.set_extension(std::env::consts::EXE_EXTENSION);
-
-    let status = releases.check(&version, force, &bin_path_in_archive, |v| {
+    let status = releases.check(&version, channel, force, &bin_path_in_archive, |v| {
         // Use cmp_precedence here to disregard build metadata.
         v.cmp_precedence(&min_version_with_setup()).is_ge()
     })?;
 
     let styles = output.stderr_styles();
 
+    if check {
+        let eval = nextest_version.eval(&current_version, false);
+        if message_format == UpdateMessageFormat::Json {
+            write_check_json(
+                &current_version,
+                &version,
+                channel,
+                &status,
+                &nextest_version,
+                &eval,
+            )?;
+        }
+        if let NextestVersionEval::Error { required, .. } = &eval {
+            warn!(
+                "current version {} does not meet required version {}",
+                current_version.style(styles.bold),
+                required.style(styles.bold),
+            );
+            return Ok(NextestExitCode::REQUIRED_VERSION_NOT_MET);
+        }
+    }
+
     match status {
         CheckStatus::AlreadyOnRequested(version) => {
             info!(
@@ -96,24 +171,7 @@ pub(crate) fn perform_update(
                 return Ok(NextestExitCode::UPDATE_AVAILABLE);
             }
 
-            let should_apply = if yes {
-                true
-            } else {
-                let colorful_theme = dialoguer::theme::ColorfulTheme::default();
-                let confirm = if output.color.should_colorize(supports_color::Stream::Stderr) {
-                    dialoguer::Confirm::with_theme(&colorful_theme)
-                } else {
-                    dialoguer::Confirm::with_theme(&dialoguer::theme::SimpleTheme)
-                };
-                confirm
-                    .with_prompt("proceed?")
-                    .default(true)
-                    .show_default(true)
-                    .interact()
-                    .map_err(|err| ExpectedError::DialoguerError { err })?
-            };
-
-            if should_apply {
+            if should_apply(yes, output)? {
                 ctx.do_update()
                     .map_err(|err| ExpectedError::UpdateError { err })?;
                 info!(
@@ -128,3 +186,105 @@ pub(crate) fn perform_update(
         }
     }
 }
+
+/// Installs nextest from a local archive.
+fn update_from_archive(
+    archive: Utf8PathBuf,
+    sha256: Option<String>,
+    bin_path_in_archive: Utf8PathBuf,
+    yes: bool,
+    output: OutputContext,
+) -> Result<i32> {
+    let styles = output.stderr_styles();
+    let update = LocalArchiveUpdate::new("cargo-nextest", archive, sha256, bin_path_in_archive)?;
+
+    info!(
+        "installing cargo-nextest from archive {}",
+        update.archive_path.style(styles.bold),
+    );
+    if should_apply(yes, output)? {
+        update
+            .do_update()
+            .map_err(|err| ExpectedError::UpdateError { err })?;
+        info!(
+            "cargo-nextest updated from {}",
+            update.archive_path.style(styles.bold)
+        );
+        Ok(0)
+    } else {
+        info!("update cancelled");
+        Ok(NextestExitCode::UPDATE_CANCELED)
+    }
+}
+
+/// Prompts for confirmation, unless `yes` is true.
+fn should_apply(yes: bool, output: OutputContext) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+
+    let colorful_theme = dialoguer::theme::ColorfulTheme::default();
+    let confirm = if output.color.should_colorize(supports_color::Stream::Stderr) {
+        dialoguer::Confirm::with_theme(&colorful_theme)
+    } else {
+        dialoguer::Confirm::with_theme(&dialoguer::theme::SimpleTheme)
+    };
+    confirm
+        .with_prompt("proceed?")
+        .default(true)
+        .show_default(true)
+        .interact()
+        .map_err(|err| ExpectedError::DialoguerError { err })
+}
+
+/// Writes the result of `self update --check` as JSON to standard output.
+fn write_check_json(
+    current_version: &Version,
+    requested: &UpdateVersion,
+    channel: UpdateChannel,
+    status: &CheckStatus<'_>,
+    nextest_version: &NextestVersionConfig,
+    eval: &NextestVersionEval,
+) -> Result<()> {
+    let (status, version) = match status {
+        CheckStatus::AlreadyOnRequested(version) => ("up-to-date", version),
+        CheckStatus::DowngradeNotAllowed { requested, .. } => ("downgrade-not-allowed", requested),
+        CheckStatus::Success(ctx) => {
+            let status = match ctx.version.cmp(current_version) {
+                Ordering::Greater => "update-available",
+                Ordering::Equal => "reinstall-available",
+                Ordering::Less => "downgrade-available",
+            };
+            (status, &ctx.version)
+        }
+    };
+    let requested = match requested {
+        UpdateVersion::Exact(version) => format!("={version}"),
+        UpdateVersion::Req(req) => req.to_string(),
+    };
+
+    let json = serde_json::json!({
+        "current-version": current_version.to_string(),
+        "channel": channel.to_string(),
+        "requested": requested,
+        "status": status,
+        "version": version.to_string(),
+        "required-version": version_req_str(&nextest_version.required),
+        "recommended-version": version_req_str(&nextest_version.recommended),
+        "meets-required": !matches!(eval, NextestVersionEval::Error { .. }),
+        "meets-recommended": matches!(eval, NextestVersionEval::Satisfied),
+    });
+
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &json)
+        .map_err(std::io::Error::from)
+        .and_then(|()| writeln!(stdout))
+        .map_err(|err| ExpectedError::UpdateCheckWriteError { err })
+}
+
+fn version_req_str(req: &NextestVersionReq) -> Option<String> {
+    match req {
+        NextestVersionReq::Version { version, .. } => Some(version.to_string()),
+        NextestVersionReq::None => None,
+    }
+}
@@ -21,7 +21,7 @@ fn main() -> Result<()> {
     match opts.exec(cli_args, output, &mut OutputWriter::default()) {
         Ok(code) => std::process::exit(code),
         Err(error) => {
-            error.display_to_stderr(&output.stderr_styles());
+            output.display_error(&error);
             std::process::exit(error.process_exit_code())
         }
     }
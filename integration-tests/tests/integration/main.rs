@@ -166,6 +166,60 @@ fn test_list_binaries_only() {
     check_list_binaries_output(&output.stdout);
 }
 
+#[test]
+fn test_list_only_binaries() {
+    set_env_vars();
+    let p = TempProject::new().unwrap();
+
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "list",
+            "--workspace",
+            "--all-targets",
+            "--message-format",
+            "json",
+            "--only-binaries",
+            "nextest-tests::{basic,other}",
+        ])
+        .output();
+
+    let result: TestListSummary = serde_json::from_slice(&output.stdout).unwrap();
+    let binary_ids: Vec<_> = result
+        .rust_suites
+        .keys()
+        .map(|binary_id| binary_id.as_str())
+        .collect();
+    assert_eq!(
+        binary_ids,
+        ["nextest-tests::basic", "nextest-tests::other"],
+        "only matching binaries are listed"
+    );
+
+    // An invalid glob is rejected before anything is built.
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "list",
+            "--only-binaries",
+            "nextest-tests::[basic",
+        ])
+        .unchecked(true)
+        .output();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(2),
+        "invalid glob is a usage error\noutput: {output}"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("invalid glob"),
+        "stderr mentions the invalid glob: {stderr}"
+    );
+}
+
 #[test]
 fn test_target_dir() {
     set_env_vars();
@@ -743,6 +797,32 @@ archive.include = [
     }
 }
 
+#[test]
+fn test_run_from_archive_with_glob_includes() {
+    set_env_vars();
+
+    // Excluding d16 means that the recursion limit isn't hit.
+    let config = r#"
+[profile.default]
+archive.include = [
+    { path = "application-data/**/*.txt", relative-to = "target", exclude = ["application-data/**/d16"], on-missing = "error" },
+]"#;
+    let (_p1, archive_file) =
+        create_archive(config, false, "archive_glob_includes").expect("archive succeeded");
+    let (_p2, extracted_target) = run_archive(&archive_file);
+
+    let contents = std::fs::read_to_string(extracted_target.join(INCLUDED_PATH))
+        .expect("extra file written to archive");
+    assert_eq!(contents, "a test string");
+
+    for path in [EXCLUDED_PATH, TOP_LEVEL_FILE] {
+        _ = extracted_target
+            .join(path)
+            .symlink_metadata()
+            .map(|_| panic!("file {path} must not be included in the archive"));
+    }
+}
+
 #[test]
 fn test_run_from_archive_with_missing_includes() {
     set_env_vars();
@@ -756,6 +836,74 @@ archive.include = [
         .expect_err("archive should have failed");
 }
 
+#[test]
+fn test_archive_extract() {
+    set_env_vars();
+
+    let (_p1, archive_file) =
+        create_archive("", false, "archive_no_includes").expect("archive succeeded");
+
+    let p2 = TempProject::new().unwrap();
+    let extract_to = p2.workspace_root().join("extract_to");
+    std::fs::create_dir_all(&extract_to).unwrap();
+    let extract = |overwrite: bool| {
+        let mut cli = CargoNextestCli::for_test();
+        cli.args([
+            "archive",
+            "extract",
+            "--archive-file",
+            archive_file.as_str(),
+            "--extract-to",
+            extract_to.as_str(),
+            "--workspace-remap",
+            p2.workspace_root().as_str(),
+        ]);
+        if overwrite {
+            cli.args(["--extract-overwrite"]);
+        }
+        cli.unchecked(true).output()
+    };
+
+    let output = extract(false);
+    assert!(
+        output.exit_status.success(),
+        "extract succeeded\noutput: {output}"
+    );
+
+    // The printed command runs the tests in the extracted archive.
+    let stdout = output.stdout_as_str();
+    let command: Vec<_> = stdout.split_whitespace().collect();
+    assert_eq!(
+        command[..3],
+        ["cargo", "nextest", "run"],
+        "printed a run command: {stdout}"
+    );
+    // for_test already passes in "nextest".
+    let output = CargoNextestCli::for_test()
+        .args(command[2..].iter().copied())
+        .unchecked(true)
+        .output();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+    check_run_output(&output.stderr, RunProperty::Relocated as u64);
+
+    // Extracting again over the same directory requires --extract-overwrite.
+    let output = extract(false);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::SETUP_ERROR),
+        "extracting over an existing archive fails\noutput: {output}"
+    );
+    let output = extract(true);
+    assert!(
+        output.exit_status.success(),
+        "extract with --extract-overwrite succeeded\noutput: {output}"
+    );
+}
+
 const APP_DATA_DIR: &str = "application-data";
 // The default limit is 16, so anything at depth 17 (under d16) is excluded.
 const DIR_TREE: &str = "application-data/d1/d2/d3/d4/d5/d6/d7/d8/d9/d10/d11/d12/d13/d14/d15/d16";
@@ -972,6 +1120,80 @@ fn test_show_config_test_groups() {
     insta::assert_snapshot!(with_termination_all_output.stdout_as_str());
 }
 
+#[test]
+fn test_debug_resolve_settings() {
+    set_env_vars();
+    let p = TempProject::new().unwrap();
+
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "debug",
+            "resolve-settings",
+            "nextest-tests::basic test_flaky_mod_6",
+            "--profile=with-retries",
+        ])
+        .output();
+
+    let stdout = output.stdout_as_str();
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some("test: nextest-tests::basic test_flaky_mod_6 (profile with-retries, target platform)"),
+        "header shows the test and profile\n{output}"
+    );
+    let setting = |key: &str| {
+        stdout
+            .lines()
+            .find(|line| line.trim_start().starts_with(&format!("{key} ")))
+            .unwrap_or_else(|| panic!("{key} is shown\n{output}"))
+            .to_owned()
+    };
+    assert!(
+        setting("retries").ends_with(
+            "(from override 1 in profile with-retries with filter 'test(=test_flaky_mod_6)')"
+        ),
+        "retries come from the first matching override\n{output}"
+    );
+    assert!(
+        setting("threads-required").contains("(from override 1 in profile with-retries"),
+        "threads-required comes from the first matching override\n{output}"
+    );
+    assert!(
+        setting("test-group").starts_with("  test-group")
+            && setting("test-group").contains("= flaky  (from override 2 in profile with-retries"),
+        "test-group comes from the second matching override\n{output}"
+    );
+    assert!(
+        setting("slow-timeout").ends_with("(from profile with-retries)"),
+        "settings without overrides come from the profile\n{output}"
+    );
+
+    // A test that doesn't exist is an error.
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "debug",
+            "resolve-settings",
+            "nextest-tests::basic test_does_not_exist",
+        ])
+        .unchecked(true)
+        .output();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::SETUP_ERROR),
+        "unknown test is a setup error\n{output}"
+    );
+    assert!(
+        output
+            .stderr_as_str()
+            .contains("test `nextest-tests::basic test_does_not_exist` not found"),
+        "error names the test\n{output}"
+    );
+}
+
 #[test]
 fn test_list_with_default_filter() {
     set_env_vars();
@@ -1377,6 +1599,126 @@ fn test_setup_script_error() {
     );
 }
 
+#[test]
+fn test_error_format_json() {
+    set_env_vars();
+    let p = TempProject::new().unwrap();
+
+    let json_error = |extra_args: &[&str], expected_exit_code: i32| {
+        let output = CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                p.manifest_path().as_str(),
+                "--error-format",
+                "json",
+                "list",
+            ])
+            .args(extra_args.iter().copied())
+            .unchecked(true)
+            .output();
+        assert_eq!(
+            output.exit_status.code(),
+            Some(expected_exit_code),
+            "exit code matches\noutput: {output}"
+        );
+        let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+        let last_line = stderr.lines().last().expect("stderr is not empty");
+        serde_json::from_str::<serde_json::Value>(last_line)
+            .unwrap_or_else(|err| panic!("last line of stderr is JSON ({err}): {last_line}"))
+    };
+
+    // A filterset error includes diagnostics with source spans.
+    let json = json_error(&["-E", "test(foo"], NextestExitCode::INVALID_FILTERSET);
+    assert_eq!(json["code"], "filterset-parse-error");
+    assert_eq!(json["exit-code"], NextestExitCode::INVALID_FILTERSET);
+    let diagnostics = json["diagnostics"].as_array().unwrap();
+    assert!(!diagnostics.is_empty(), "diagnostics are reported: {json}");
+
+    // Errors without source spans still report a message.
+    let json = json_error(
+        &["--profile", "does-not-exist"],
+        NextestExitCode::SETUP_ERROR,
+    );
+    assert_eq!(json["code"], "profile-not-found");
+    assert!(
+        json["message"].as_str().unwrap().contains("does-not-exist"),
+        "message names the profile: {json}"
+    );
+    assert_eq!(json["diagnostics"], serde_json::json!([]));
+
+    // An unknown error format is rejected.
+    let output = CargoNextestCli::for_test()
+        .args(["--error-format", "xml", "list"])
+        .unchecked(true)
+        .output();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(2),
+        "unknown error format is a usage error\noutput: {output}"
+    );
+}
+
+#[test]
+fn test_run_dry_run() {
+    set_env_vars();
+    let p = TempProject::new().unwrap();
+
+    // If the setup script were run, this would make it fail.
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "run",
+            "--profile=with-retries",
+            "--test-threads=2",
+            "-E",
+            "binary_id(nextest-tests::basic) & \
+             (test(test_flaky_mod) | test(=test_success) | test(=test_cargo_env_vars))",
+            "--dry-run",
+        ])
+        .env("__NEXTEST_SETUP_SCRIPT_ERROR", "1")
+        .output();
+
+    let stdout = output.stdout_as_str();
+    assert!(
+        stdout.starts_with("dry run for profile with-retries with 2 test threads\n"),
+        "header is shown\n{output}"
+    );
+
+    let tests: Vec<_> = stdout
+        .lines()
+        .filter(|line| line.contains("nextest-tests::basic"))
+        .collect();
+    let script = if cfg!(windows) {
+        "my-script-windows"
+    } else {
+        "my-script-unix"
+    };
+    assert_eq!(
+        tests,
+        [
+            format!("  1. nextest-tests::basic test_cargo_env_vars (retries: 2; setup scripts: {script})"),
+            "  2. nextest-tests::basic test_flaky_mod_4 (group: flaky; retries: 4)".to_owned(),
+            "  3. nextest-tests::basic test_flaky_mod_6 (group: flaky; threads: 2; retries: 5)"
+                .to_owned(),
+            "  4. nextest-tests::basic test_success (retries: 2)".to_owned(),
+        ],
+        "tests are listed in start order, with their settings\n{output}"
+    );
+    assert!(
+        stdout.contains(&format!("  {script}: required by 1 test\n")),
+        "setup script is listed\n{output}"
+    );
+    assert!(
+        stdout.contains("\n4 tests would be run, "),
+        "summary is shown\n{output}"
+    );
+    assert!(
+        !output.stderr_as_str().contains("PASS"),
+        "no tests were run\n{output}"
+    );
+}
+
 #[test]
 fn test_target_arg() {
     set_env_vars();
@@ -1,6 +1,7 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::RustBinaryId;
 use std::{error, fmt};
 
 /// An error that occurs while running a `cargo nextest` command.
@@ -54,3 +55,104 @@ impl error::Error for CommandError {
         }
     }
 }
+
+/// An error that occurs while parsing a [`RustBinaryId`].
+///
+/// Returned by [`RustBinaryId::parse`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RustBinaryIdParseError {
+    /// The binary ID was empty.
+    Empty,
+
+    /// The binary ID contained whitespace.
+    ContainsWhitespace {
+        /// The binary ID.
+        id: String,
+    },
+
+    /// One of the components of the binary ID (package name, kind, or binary name) was empty.
+    EmptyComponent {
+        /// The binary ID.
+        id: String,
+    },
+
+    /// A component of the binary ID contained a `:` or `/` separator where one isn't allowed.
+    InvalidSeparator {
+        /// The binary ID.
+        id: String,
+    },
+
+    /// The binary ID is valid, but isn't in the canonical form that nextest generates.
+    NonCanonical {
+        /// The binary ID.
+        id: String,
+
+        /// The canonical form of the binary ID.
+        canonical: RustBinaryId,
+    },
+}
+
+impl fmt::Display for RustBinaryIdParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "binary ID is empty"),
+            Self::ContainsWhitespace { id } => {
+                write!(f, "binary ID `{id}` contains whitespace")
+            }
+            Self::EmptyComponent { id } => {
+                write!(f, "binary ID `{id}` has an empty component")
+            }
+            Self::InvalidSeparator { id } => {
+                write!(f, "binary ID `{id}` has an unexpected `:` or `/`")
+            }
+            Self::NonCanonical { id, canonical } => {
+                write!(
+                    f,
+                    "binary ID `{id}` is not in canonical form (expected `{canonical}`)"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for RustBinaryIdParseError {}
+
+/// An error that occurs while parsing a [`RustTestId`](crate::RustTestId).
+///
+/// Returned by [`RustTestId::parse`](crate::RustTestId::parse).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RustTestIdParseError {
+    /// The test ID didn't contain a test name after the binary ID.
+    MissingTestName {
+        /// The test ID.
+        id: String,
+    },
+
+    /// The binary ID portion of the test ID was invalid.
+    BinaryId(RustBinaryIdParseError),
+}
+
+impl fmt::Display for RustTestIdParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingTestName { id } => {
+                write!(
+                    f,
+                    "test ID `{id}` is missing a test name (expected `<binary-id> <test-name>`)"
+                )
+            }
+            Self::BinaryId(_) => write!(f, "error parsing binary ID in test ID"),
+        }
+    }
+}
+
+impl error::Error for RustTestIdParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::MissingTestName { .. } => None,
+            Self::BinaryId(err) => Some(err),
+        }
+    }
+}
@@ -72,10 +72,41 @@ impl NextestExitCode {
     /// *Since nextest 0.9.55*.
     pub const REQUIRED_VERSION_NOT_MET: i32 = 92;
 
+    /// Nextest produced warnings, and `--deny-warnings` was passed in.
+    ///
+    /// *Since nextest 0.9.89*.
+    pub const WARNINGS_DENIED: i32 = 91;
+
     /// The current version of nextest is older than the minimum recommended version.
     ///
     /// This advisory exit code is only produced by `cargo nextest show-config version`.
     ///
     /// *Since nextest 0.9.55*.
     pub const RECOMMENDED_VERSION_NOT_MET: i32 = 10;
+
+    /// All of the exit codes above, in ascending order.
+    ///
+    /// *Since nextest 0.9.89*.
+    pub const ALL: &'static [i32] = &[
+        Self::NO_TESTS_RUN,
+        Self::RECOMMENDED_VERSION_NOT_MET,
+        Self::DOUBLE_SPAWN_ERROR,
+        Self::UPDATE_AVAILABLE,
+        Self::UPDATE_DOWNGRADE_NOT_PERFORMED,
+        Self::UPDATE_CANCELED,
+        Self::UPDATE_ERROR,
+        Self::WARNINGS_DENIED,
+        Self::REQUIRED_VERSION_NOT_MET,
+        Self::SELF_UPDATE_UNAVAILABLE,
+        Self::INVALID_FILTERSET,
+        Self::EXPERIMENTAL_FEATURE_NOT_ENABLED,
+        Self::SETUP_ERROR,
+        Self::TEST_RUN_FAILED,
+        Self::BUILD_FAILED,
+        Self::CARGO_METADATA_FAILED,
+        Self::ARCHIVE_CREATION_FAILED,
+        Self::TEST_LIST_CREATION_FAILED,
+        Self::SETUP_SCRIPT_FAILED,
+        Self::WRITE_OUTPUT_ERROR,
+    ];
 }
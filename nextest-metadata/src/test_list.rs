@@ -1,7 +1,7 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::CommandError;
+use crate::{CommandError, RustBinaryIdParseError, RustTestIdParseError};
 use camino::{Utf8Path, Utf8PathBuf};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
@@ -342,6 +342,96 @@ impl RustBinaryId {
         Self(id.into())
     }
 
+    /// Parses a binary ID, checking that it is in the canonical form produced by
+    /// [`Self::from_parts`].
+    ///
+    /// Unlike [`Self::new`], which accepts any string, this rejects IDs that nextest would never
+    /// generate. In particular, a kind of `lib` or `proc-macro` is never written out, and
+    /// integration tests (kind `test`) are written as `package_name::target_name`. For those
+    /// inputs, the returned error contains the canonical form of the ID.
+    ///
+    /// If parsing succeeds, [`Self::as_str`] returns the input unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nextest_metadata::{RustBinaryId, RustBinaryIdParseError};
+    ///
+    /// assert_eq!(
+    ///     RustBinaryId::parse("foo-lib::bin/foo_bin"),
+    ///     Ok(RustBinaryId::new("foo-lib::bin/foo_bin")),
+    /// );
+    ///
+    /// // Integration tests don't have their kind written out.
+    /// assert_eq!(
+    ///     RustBinaryId::parse("foo-lib::test/foo_test"),
+    ///     Err(RustBinaryIdParseError::NonCanonical {
+    ///         id: "foo-lib::test/foo_test".to_owned(),
+    ///         canonical: RustBinaryId::new("foo-lib::foo_test"),
+    ///     }),
+    /// );
+    /// ```
+    pub fn parse(id: &str) -> Result<Self, RustBinaryIdParseError> {
+        if id.is_empty() {
+            return Err(RustBinaryIdParseError::Empty);
+        }
+        if id.contains(char::is_whitespace) {
+            // Whitespace separates the binary ID from the test name in test IDs.
+            return Err(RustBinaryIdParseError::ContainsWhitespace { id: id.to_owned() });
+        }
+
+        let id = RustBinaryId::new(id);
+        let components = id.components();
+        let empty_component = || RustBinaryIdParseError::EmptyComponent {
+            id: id.as_str().to_owned(),
+        };
+        if components.package_name.is_empty() {
+            return Err(empty_component());
+        }
+        if components.package_name.contains([':', '/']) {
+            return Err(RustBinaryIdParseError::InvalidSeparator {
+                id: id.as_str().to_owned(),
+            });
+        }
+        match components.binary_name_and_kind {
+            RustBinaryIdNameAndKind::None => {}
+            RustBinaryIdNameAndKind::NameOnly { binary_name } => {
+                if binary_name.is_empty() {
+                    return Err(empty_component());
+                }
+                if binary_name.contains(':') {
+                    return Err(RustBinaryIdParseError::InvalidSeparator {
+                        id: id.as_str().to_owned(),
+                    });
+                }
+            }
+            RustBinaryIdNameAndKind::NameAndKind { kind, binary_name } => {
+                if kind.is_empty() || binary_name.is_empty() {
+                    return Err(empty_component());
+                }
+                if kind.contains(':') || binary_name.contains([':', '/']) {
+                    return Err(RustBinaryIdParseError::InvalidSeparator {
+                        id: id.as_str().to_owned(),
+                    });
+                }
+                let kind = RustTestBinaryKind::new(kind.to_owned());
+                if kind == RustTestBinaryKind::LIB
+                    || kind == RustTestBinaryKind::PROC_MACRO
+                    || kind == RustTestBinaryKind::TEST
+                {
+                    let canonical =
+                        RustBinaryId::from_parts(components.package_name, &kind, binary_name);
+                    return Err(RustBinaryIdParseError::NonCanonical {
+                        id: id.as_str().to_owned(),
+                        canonical,
+                    });
+                }
+            }
+        }
+
+        Ok(id)
+    }
+
     /// Returns the identifier as a string.
     #[inline]
     pub fn as_str(&self) -> &str {
@@ -460,6 +550,65 @@ pub enum RustBinaryIdNameAndKind<'a> {
     },
 }
 
+/// A fully-qualified identifier for a test: the [`RustBinaryId`] of the binary it is in, and the
+/// name of the test within that binary.
+///
+/// This is written as the binary ID, a single space, and then the test name: for example,
+/// `foo-lib::bin/foo_bin tests::my_test`. Nextest uses this format wherever it identifies a test
+/// across binaries, and it is part of nextest's stable API.
+///
+/// Test IDs are ordered by binary ID (see [`RustBinaryIdComponents`]), then by test name. This
+/// matches the order in which nextest lists tests.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RustTestId {
+    /// The binary ID.
+    pub binary_id: RustBinaryId,
+
+    /// The name of the test within the binary.
+    pub test_name: String,
+}
+
+impl RustTestId {
+    /// Creates a new `RustTestId` from a binary ID and a test name.
+    pub fn new(binary_id: RustBinaryId, test_name: impl Into<String>) -> Self {
+        Self {
+            binary_id,
+            test_name: test_name.into(),
+        }
+    }
+
+    /// Parses a test ID of the form `binary_id test_name`.
+    ///
+    /// The binary ID is parsed with [`RustBinaryId::parse`]. Binary IDs never contain whitespace,
+    /// so everything after the first space is the test name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nextest_metadata::{RustBinaryId, RustTestId};
+    ///
+    /// let id = RustTestId::parse("foo-lib::foo_test tests::my_test").unwrap();
+    /// assert_eq!(id.binary_id, RustBinaryId::new("foo-lib::foo_test"));
+    /// assert_eq!(id.test_name, "tests::my_test");
+    /// assert_eq!(id.to_string(), "foo-lib::foo_test tests::my_test");
+    /// ```
+    pub fn parse(id: &str) -> Result<Self, RustTestIdParseError> {
+        let (binary_id, test_name) = match id.split_once(' ') {
+            Some((binary_id, test_name)) if !test_name.is_empty() => (binary_id, test_name),
+            _ => return Err(RustTestIdParseError::MissingTestName { id: id.to_owned() }),
+        };
+        let binary_id = RustBinaryId::parse(binary_id).map_err(RustTestIdParseError::BinaryId)?;
+
+        Ok(Self::new(binary_id, test_name))
+    }
+}
+
+impl fmt::Display for RustTestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.binary_id, self.test_name)
+    }
+}
+
 /// Rust metadata used for builds and test runs.
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Default)]
 #[serde(rename_all = "kebab-case")]
@@ -736,12 +885,32 @@ pub struct RustTestCaseSummary {
     /// Ignored tests, if run, are executed with the `--ignored` argument.
     pub ignored: bool,
 
+    /// The reason this test is ignored, if the test binary reported one.
+    ///
+    /// This is obtained from test binaries that support listing tests with
+    /// `--format json`, such as libtest, and is `None` for other binaries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_reason: Option<String>,
+
     /// Whether the test matches the provided test filter.
     ///
     /// Only tests that match the filter are run.
     pub filter_match: FilterMatch,
 }
 
+impl RustTestCaseSummary {
+    /// Returns the reason this test is ignored, if it was skipped for being
+    /// ignored and the test binary reported a reason.
+    pub fn skipped_ignore_reason(&self) -> Option<&str> {
+        match self.filter_match {
+            FilterMatch::Mismatch {
+                reason: MismatchReason::Ignored,
+            } => self.ignore_reason.as_deref(),
+            _ => None,
+        }
+    }
+}
+
 /// An enum describing whether a test matches a filter.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", tag = "status")]
@@ -752,10 +921,18 @@ pub enum FilterMatch {
     /// This test does not match this filter.
     Mismatch {
         /// Describes the reason this filter isn't matched.
+        ///
+        /// Compact test lists (`cargo nextest list --compact`) omit this field,
+        /// in which case it is deserialized as [`MismatchReason::Unknown`].
+        #[serde(default = "unknown_mismatch_reason")]
         reason: MismatchReason,
     },
 }
 
+fn unknown_mismatch_reason() -> MismatchReason {
+    MismatchReason::Unknown
+}
+
 impl FilterMatch {
     /// Returns true if the filter doesn't match.
     pub fn is_match(&self) -> bool {
@@ -784,6 +961,18 @@ pub enum MismatchReason {
     ///
     /// This is the lowest-priority reason for skipping a test.
     DefaultFilter,
+
+    /// This test wasn't run because the package or binary it belongs to was
+    /// cancelled after reaching `--max-fail` with a `--max-fail-scope`.
+    ///
+    /// Unlike the other reasons, this is only produced while running tests.
+    ScopeCancelled,
+
+    /// The reason was not recorded.
+    ///
+    /// This is produced when deserializing a compact test list, which omits
+    /// mismatch reasons.
+    Unknown,
 }
 
 impl fmt::Display for MismatchReason {
@@ -798,6 +987,10 @@ impl fmt::Display for MismatchReason {
             MismatchReason::DefaultFilter => {
                 write!(f, "is filtered out by the profile's default-filter")
             }
+            MismatchReason::ScopeCancelled => {
+                write!(f, "belongs to a package or binary cancelled by max-fail")
+            }
+            MismatchReason::Unknown => write!(f, "does not match filters (reason unknown)"),
         }
     }
 }
@@ -847,6 +1040,98 @@ mod tests {
         );
     }
 
+    #[test_case("foo", Ok(()); "package only")]
+    #[test_case("foo-lib::foo_test", Ok(()); "integration test")]
+    #[test_case("foo::bin/foo-bin", Ok(()); "bin")]
+    #[test_case("foo::custom-build/build-script-build", Ok(()); "build script")]
+    #[test_case("", Err(RustBinaryIdParseError::Empty); "empty")]
+    #[test_case("foo::bin/my bin", Err(RustBinaryIdParseError::ContainsWhitespace {
+        id: "foo::bin/my bin".to_owned(),
+    }); "whitespace")]
+    #[test_case("::foo", Err(RustBinaryIdParseError::EmptyComponent {
+        id: "::foo".to_owned(),
+    }); "empty package")]
+    #[test_case("foo::bin/", Err(RustBinaryIdParseError::EmptyComponent {
+        id: "foo::bin/".to_owned(),
+    }); "empty binary name")]
+    #[test_case("foo::bin/a::b", Err(RustBinaryIdParseError::InvalidSeparator {
+        id: "foo::bin/a::b".to_owned(),
+    }); "extra separator")]
+    #[test_case("foo::lib/foo", Err(RustBinaryIdParseError::NonCanonical {
+        id: "foo::lib/foo".to_owned(),
+        canonical: RustBinaryId::new("foo"),
+    }); "lib kind")]
+    #[test_case("foo::test/foo_test", Err(RustBinaryIdParseError::NonCanonical {
+        id: "foo::test/foo_test".to_owned(),
+        canonical: RustBinaryId::new("foo::foo_test"),
+    }); "test kind")]
+    fn test_binary_id_parse(input: &str, expected: Result<(), RustBinaryIdParseError>) {
+        let actual = RustBinaryId::parse(input);
+        match expected {
+            Ok(()) => assert_eq!(actual.expect("parse succeeded").as_str(), input),
+            Err(error) => assert_eq!(actual, Err(error)),
+        }
+    }
+
+    #[test]
+    fn test_test_id_parse() {
+        let id = RustTestId::parse("foo::bin/foo_bin tests::with space").unwrap();
+        assert_eq!(id.binary_id, RustBinaryId::new("foo::bin/foo_bin"));
+        assert_eq!(id.test_name, "tests::with space");
+        assert_eq!(id.to_string(), "foo::bin/foo_bin tests::with space");
+
+        assert_eq!(
+            RustTestId::parse("foo"),
+            Err(RustTestIdParseError::MissingTestName {
+                id: "foo".to_owned()
+            })
+        );
+        assert_eq!(
+            RustTestId::parse("foo "),
+            Err(RustTestIdParseError::MissingTestName {
+                id: "foo ".to_owned()
+            })
+        );
+        assert_eq!(
+            RustTestId::parse("::foo test"),
+            Err(RustTestIdParseError::BinaryId(
+                RustBinaryIdParseError::EmptyComponent {
+                    id: "::foo".to_owned()
+                }
+            ))
+        );
+
+        // Test IDs are ordered by binary ID first.
+        let a = RustTestId::new(RustBinaryId::new("foo::name"), "z");
+        let b = RustTestId::new(RustBinaryId::new("foo::bin/name"), "a");
+        assert!(a < b, "{a} sorts before {b}");
+    }
+
+    #[test_case(r#"{ "status": "matches" }"#, FilterMatch::Matches; "matches")]
+    #[test_case(r#"{ "status": "mismatch", "reason": "ignored" }"#, FilterMatch::Mismatch {
+        reason: MismatchReason::Ignored,
+    }; "mismatch with reason")]
+    #[test_case(r#"{ "status": "mismatch" }"#, FilterMatch::Mismatch {
+        reason: MismatchReason::Unknown,
+    }; "compact mismatch")]
+    fn test_deserialize_filter_match(input: &str, expected: FilterMatch) {
+        let filter_match: FilterMatch =
+            serde_json::from_str(input).expect("input deserialized correctly");
+        assert_eq!(filter_match, expected);
+    }
+
+    #[test]
+    fn test_deserialize_filter_match_invalid_reason() {
+        let error = serde_json::from_str::<FilterMatch>(
+            r#"{ "status": "mismatch", "reason": "not-a-reason" }"#,
+        )
+        .expect_err("unknown reason is rejected");
+        assert!(
+            error.to_string().contains("unknown variant `not-a-reason`"),
+            "{error}"
+        );
+    }
+
     #[test]
     fn test_binary_id_ord() {
         let empty = RustBinaryId::new("");
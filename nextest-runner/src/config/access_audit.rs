@@ -0,0 +1,233 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{de::Error, Deserialize};
+use std::{fmt, net::IpAddr, str::FromStr};
+
+/// Auditing of tests' network and file system access, set via the `access-audit` setting.
+///
+/// Auditing is only supported on Linux, where tests are run under `strace`. On other platforms,
+/// tests are run without auditing and a warning is printed.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AccessAudit {
+    /// Tests are run without auditing.
+    #[default]
+    Off,
+
+    /// The network destinations and files each test attempt accesses are written out to a report
+    /// in the store directory.
+    Record,
+
+    /// Like [`Self::Record`], and tests that contact hosts not allowed by the profile's
+    /// [`AccessPolicy`] fail.
+    Enforce,
+}
+
+impl AccessAudit {
+    /// Returns true if tests are audited.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Self::Off => false,
+            Self::Record | Self::Enforce => true,
+        }
+    }
+}
+
+/// The hosts tests are allowed to contact, set via `[profile.<name>.access-policy]`.
+///
+/// This is only checked for tests run with `access-audit = "enforce"`. Loopback addresses are
+/// always allowed.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AccessPolicy {
+    #[serde(default)]
+    allowed_hosts: Vec<HostPattern>,
+}
+
+impl AccessPolicy {
+    /// Returns true if tests are allowed to contact this address.
+    pub fn allows(&self, addr: IpAddr) -> bool {
+        // Dual-stack sockets report IPv4 destinations as IPv4-mapped IPv6 addresses.
+        let addr = addr.to_canonical();
+        addr.is_loopback()
+            || addr.is_unspecified()
+            || self
+                .allowed_hosts
+                .iter()
+                .any(|pattern| pattern.contains(addr))
+    }
+}
+
+/// An IP address or network in an [`AccessPolicy`], for example `10.1.2.3` or `10.0.0.0/8`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HostPattern {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl HostPattern {
+    /// Returns true if this pattern matches the address.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                prefix_matches(&network.octets(), &addr.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                prefix_matches(&network.octets(), &addr.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(network: &[u8], addr: &[u8], prefix_len: u8) -> bool {
+    let mut remaining = usize::from(prefix_len);
+    for (network, addr) in network.iter().zip(addr) {
+        if remaining == 0 {
+            break;
+        }
+        let bits = remaining.min(8);
+        let mask = !0u8 << (8 - bits);
+        if network & mask != addr & mask {
+            return false;
+        }
+        remaining -= bits;
+    }
+    true
+}
+
+impl FromStr for HostPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("invalid IP address `{addr}`"))?;
+        let max_len = match network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => match prefix_len.parse::<u8>() {
+                Ok(prefix_len) if prefix_len <= max_len => prefix_len,
+                _ => {
+                    return Err(format!(
+                        "invalid prefix length `{prefix_len}` (must be 0-{max_len})"
+                    ))
+                }
+            },
+            None => max_len,
+        };
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl fmt::Display for HostPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+impl<'de> Deserialize<'de> for HostPattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test]
+    fn access_policy_allows() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            access-audit = "record"
+
+            [profile.ci]
+            access-audit = "enforce"
+
+            [profile.ci.access-policy]
+            allowed-hosts = ["10.0.0.0/8", "192.168.1.5", "2001:db8::/32"]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        )
+        .unwrap();
+
+        let default_profile = config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(default_profile.access_audit(), AccessAudit::Record);
+        let policy = default_profile.access_policy();
+        assert!(policy.allows("127.0.0.1".parse().unwrap()));
+        assert!(policy.allows("::1".parse().unwrap()));
+        assert!(!policy.allows("10.1.2.3".parse().unwrap()));
+
+        let ci_profile = config
+            .profile("ci")
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(ci_profile.access_audit(), AccessAudit::Enforce);
+        let policy = ci_profile.access_policy();
+        assert!(policy.allows("10.1.2.3".parse().unwrap()));
+        assert!(policy.allows("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(policy.allows("192.168.1.5".parse().unwrap()));
+        assert!(!policy.allows("192.168.1.6".parse().unwrap()));
+        assert!(policy.allows("2001:db8:1::1".parse().unwrap()));
+        assert!(!policy.allows("2001:db9::1".parse().unwrap()));
+        assert!(!policy.allows("93.184.216.34".parse().unwrap()));
+    }
+
+    #[test_case("10.0.0.0/8", "10.0.0.0/8" ; "ipv4 network")]
+    #[test_case("10.1.2.3", "10.1.2.3/32" ; "ipv4 address")]
+    #[test_case("172.16.0.0/12", "172.16.0.0/12" ; "ipv4 partial octet")]
+    #[test_case("::1", "::1/128" ; "ipv6 address")]
+    #[test_case("0.0.0.0/0", "0.0.0.0/0" ; "everything")]
+    fn parse_host_pattern(input: &str, expected: &str) {
+        let pattern: HostPattern = input.parse().expect("valid host pattern");
+        assert_eq!(pattern.to_string(), expected);
+    }
+
+    #[test_case("example.com", "invalid IP address `example.com`" ; "hostname")]
+    #[test_case("10.0.0.0/33", "invalid prefix length `33` (must be 0-32)" ; "prefix too long")]
+    #[test_case("::/x", "invalid prefix length `x` (must be 0-128)" ; "prefix not a number")]
+    fn parse_host_pattern_invalid(input: &str, expected: &str) {
+        let error = input
+            .parse::<HostPattern>()
+            .expect_err("invalid host pattern");
+        assert_eq!(error, expected);
+    }
+
+    #[test]
+    fn host_pattern_contains() {
+        let pattern: HostPattern = "172.16.0.0/12".parse().unwrap();
+        assert!(pattern.contains("172.31.255.255".parse().unwrap()));
+        assert!(!pattern.contains("172.32.0.0".parse().unwrap()));
+        assert!(!pattern.contains("::1".parse().unwrap()));
+    }
+}
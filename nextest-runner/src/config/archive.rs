@@ -4,9 +4,16 @@
 use super::TrackDefault;
 use crate::config::helpers::deserialize_relative_path;
 use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
-use serde::{de::Unexpected, Deserialize};
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use serde::{
+    de::{Error, Unexpected},
+    Deserialize,
+};
 use std::fmt;
 
+/// Characters that mark a path component as a glob pattern.
+const GLOB_METACHARS: &[char] = &['*', '?', '[', '{'];
+
 /// Configuration for archives.
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
@@ -26,13 +33,18 @@ pub struct ArchiveConfig {
 pub struct ArchiveInclude {
     // We only allow well-formed relative paths within the target directory here. It's possible we
     // can relax this in the future, but better safe than sorry for now.
-    #[serde(deserialize_with = "deserialize_relative_path")]
+    //
+    // The path may contain glob patterns, in which case the components before the first pattern
+    // are recursed into, and files within them that match the path are archived.
+    #[serde(deserialize_with = "deserialize_include_path")]
     path: Utf8PathBuf,
     relative_to: ArchiveRelativeTo,
     #[serde(default = "default_depth")]
     depth: TrackDefault<RecursionDepth>,
     #[serde(default = "default_on_missing")]
     on_missing: ArchiveIncludeOnMissing,
+    #[serde(default, deserialize_with = "deserialize_exclude")]
+    exclude: Vec<String>,
 }
 
 impl ArchiveInclude {
@@ -47,16 +59,132 @@ impl ArchiveInclude {
     }
 
     /// Join the path with the given target dir.
+    ///
+    /// The returned path may contain glob patterns.
     pub fn join_path(&self, target_dir: &Utf8Path) -> Utf8PathBuf {
         match self.relative_to {
             ArchiveRelativeTo::Target => join_rel_path(target_dir, &self.path),
         }
     }
 
-    /// What to do when the path is missing.
+    /// Join the leading components of the path that don't contain glob patterns with the given
+    /// target dir.
+    ///
+    /// This is the directory (or file) that is recursed into to find files to archive. For paths
+    /// without glob patterns, this is the same as [`Self::join_path`].
+    pub fn join_base_path(&self, target_dir: &Utf8Path) -> Utf8PathBuf {
+        let base: Utf8PathBuf = self
+            .path
+            .components()
+            .take_while(|component| !component.as_str().contains(GLOB_METACHARS))
+            .collect();
+        match self.relative_to {
+            ArchiveRelativeTo::Target => join_rel_path(target_dir, &base),
+        }
+    }
+
+    /// Returns true if the path contains glob patterns.
+    pub fn is_glob(&self) -> bool {
+        self.path.as_str().contains(GLOB_METACHARS)
+    }
+
+    /// What to do when the path is missing, or if it's a glob, when it matches no files.
     pub fn on_missing(&self) -> ArchiveIncludeOnMissing {
         self.on_missing
     }
+
+    /// The glob patterns for paths to exclude from the archive.
+    pub fn exclude(&self) -> &[String] {
+        &self.exclude
+    }
+
+    /// Returns the filter for paths within this include.
+    pub(crate) fn filter(&self) -> ArchiveIncludeFilter {
+        let glob = self.is_glob().then(|| {
+            build_glob(&normalize_glob_path(&self.path))
+                .expect("path was validated during deserialization")
+                .compile_matcher()
+        });
+        let mut exclude = GlobSetBuilder::new();
+        for pattern in &self.exclude {
+            exclude.add(build_glob(pattern).expect("exclude was validated during deserialization"));
+        }
+        let exclude = exclude
+            .build()
+            .expect("exclude was validated during deserialization");
+
+        ArchiveIncludeFilter { glob, exclude }
+    }
+}
+
+/// Determines which paths within an [`ArchiveInclude`] are archived.
+///
+/// Paths passed in are relative to the include's `relative-to` directory, and use `/` as the
+/// separator.
+#[derive(Clone, Debug)]
+pub(crate) struct ArchiveIncludeFilter {
+    glob: Option<GlobMatcher>,
+    exclude: GlobSet,
+}
+
+impl ArchiveIncludeFilter {
+    /// Returns true if the file or directory at `rel_path` is excluded.
+    ///
+    /// Excluded directories aren't recursed into.
+    pub(crate) fn is_excluded(&self, rel_path: &Utf8Path) -> bool {
+        self.exclude.is_match(rel_path)
+    }
+
+    /// Returns true if the file at `rel_path` matches the include's path.
+    pub(crate) fn is_match(&self, rel_path: &Utf8Path) -> bool {
+        self.glob
+            .as_ref()
+            .map_or(true, |glob| glob.is_match(rel_path))
+    }
+}
+
+fn build_glob(pattern: &str) -> Result<Glob, globset::Error> {
+    GlobBuilder::new(pattern)
+        // `*` doesn't match across directories, while `**` does.
+        .literal_separator(true)
+        // Match the glob syntax used elsewhere in nextest: only allow escapes via [].
+        .backslash_escape(false)
+        .build()
+}
+
+/// Converts `path` to the form that [`ArchiveIncludeFilter`] matches against.
+fn normalize_glob_path(path: &Utf8Path) -> String {
+    path.components()
+        .filter(|component| *component != Utf8Component::CurDir)
+        .map(|component| component.as_str())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn deserialize_include_path<'de, D>(deserializer: D) -> Result<Utf8PathBuf, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let path = deserialize_relative_path(deserializer)?;
+    if let Err(error) = build_glob(&normalize_glob_path(&path)) {
+        return Err(D::Error::custom(format!("invalid glob `{path}`: {error}")));
+    }
+    Ok(path)
+}
+
+fn deserialize_exclude<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let patterns = Vec::<String>::deserialize(deserializer)?;
+    for pattern in &patterns {
+        if let Err(error) = build_glob(pattern) {
+            return Err(D::Error::custom(format!(
+                "invalid exclude glob `{pattern}`: {error}"
+            )));
+        }
+    }
+    Ok(patterns)
 }
 
 fn default_depth() -> TrackDefault<RecursionDepth> {
@@ -268,6 +396,11 @@ mod tests {
             archive.include = []
 
             [profile.profile3]
+
+            [profile.profile4]
+            archive.include = [
+                { path = "fixtures/**", relative-to = "target", exclude = ["fixtures/**/*.large"] },
+            ]
         "#};
 
         let workspace_dir = tempdir().unwrap();
@@ -290,12 +423,14 @@ mod tests {
                     relative_to: ArchiveRelativeTo::Target,
                     depth: default_depth(),
                     on_missing: ArchiveIncludeOnMissing::Warn,
+                    exclude: vec![],
                 },
                 ArchiveInclude {
                     path: "bar".into(),
                     relative_to: ArchiveRelativeTo::Target,
                     depth: TrackDefault::with_deserialized_value(RecursionDepth::Finite(1)),
                     on_missing: ArchiveIncludeOnMissing::Error,
+                    exclude: vec![],
                 },
             ],
         };
@@ -322,6 +457,7 @@ mod tests {
                     relative_to: ArchiveRelativeTo::Target,
                     depth: TrackDefault::with_deserialized_value(RecursionDepth::ZERO),
                     on_missing: ArchiveIncludeOnMissing::Ignore,
+                    exclude: vec![],
                 }],
             },
             "profile1 matches"
@@ -346,6 +482,24 @@ mod tests {
             &default_config,
             "profile3 matches"
         );
+
+        assert_eq!(
+            config
+                .profile("profile4")
+                .expect("profile exists")
+                .apply_build_platforms(&build_platforms())
+                .archive_config(),
+            &ArchiveConfig {
+                include: vec![ArchiveInclude {
+                    path: "fixtures/**".into(),
+                    relative_to: ArchiveRelativeTo::Target,
+                    depth: default_depth(),
+                    on_missing: ArchiveIncludeOnMissing::Warn,
+                    exclude: vec!["fixtures/**/*.large".to_owned()],
+                }],
+            },
+            "profile4 matches"
+        );
     }
 
     #[test_case(
@@ -418,6 +572,24 @@ mod tests {
         "#},
         r#"invalid type: integer `42`, expected a string: "ignore", "warn", or "error""#
         ; "invalid on-missing type")]
+    #[test_case(
+        indoc!{r#"
+            [profile.default]
+            archive.include = [
+                { path = "foo/[a", relative-to = "target" }
+            ]
+        "#},
+        r"invalid glob `foo/[a`"
+        ; "invalid glob")]
+    #[test_case(
+        indoc!{r#"
+            [profile.default]
+            archive.include = [
+                { path = "foo", relative-to = "target", exclude = ["foo/{a"] }
+            ]
+        "#},
+        r"invalid exclude glob `foo/{a`"
+        ; "invalid exclude glob")]
     fn parse_invalid(config_contents: &str, expected_message: &str) {
         let workspace_dir = tempdir().unwrap();
         let workspace_path: &Utf8Path = workspace_dir.path();
@@ -453,6 +625,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_include_filter() {
+        let include = ArchiveInclude {
+            path: "./fixtures/*/data/**".into(),
+            relative_to: ArchiveRelativeTo::Target,
+            depth: default_depth(),
+            on_missing: ArchiveIncludeOnMissing::Warn,
+            exclude: vec!["fixtures/**/*.large".to_owned(), "fixtures/skip".to_owned()],
+        };
+        assert!(include.is_glob());
+        assert_eq!(
+            include.join_path(Utf8Path::new("target")),
+            "target/fixtures/*/data/**"
+        );
+        assert_eq!(
+            include.join_base_path(Utf8Path::new("target")),
+            "target/fixtures"
+        );
+
+        let filter = include.filter();
+        assert!(filter.is_match(Utf8Path::new("fixtures/a/data/foo.txt")));
+        assert!(filter.is_match(Utf8Path::new("fixtures/a/data/nested/foo.txt")));
+        assert!(
+            !filter.is_match(Utf8Path::new("fixtures/a/b/data/foo.txt")),
+            "* doesn't match across directories"
+        );
+        assert!(!filter.is_match(Utf8Path::new("fixtures/a/other/foo.txt")));
+
+        assert!(filter.is_excluded(Utf8Path::new("fixtures/a/data/foo.large")));
+        assert!(filter.is_excluded(Utf8Path::new("fixtures/skip")));
+        assert!(!filter.is_excluded(Utf8Path::new("fixtures/a/data/foo.txt")));
+
+        let include = ArchiveInclude {
+            path: "foo/bar".into(),
+            relative_to: ArchiveRelativeTo::Target,
+            depth: default_depth(),
+            on_missing: ArchiveIncludeOnMissing::Warn,
+            exclude: vec![],
+        };
+        assert!(!include.is_glob());
+        assert_eq!(
+            include.join_base_path(Utf8Path::new("target")),
+            include.join_path(Utf8Path::new("target")),
+        );
+        let filter = include.filter();
+        assert!(filter.is_match(Utf8Path::new("foo/bar/baz")));
+        assert!(!filter.is_excluded(Utf8Path::new("foo/bar/baz")));
+    }
+
     #[test]
     fn test_join_rel_path() {
         let inputs = [
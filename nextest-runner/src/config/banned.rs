@@ -0,0 +1,154 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::{ConfigCompileError, ConfigCompileErrorKind, ConfigCompileSection};
+use guppy::graph::PackageGraph;
+use nextest_filtering::{Filterset, FiltersetKind, ParseContext};
+use serde::Deserialize;
+
+/// Deserialized form of a `[[profile.<name>.banned]]` entry.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(super) struct DeserializedBanned {
+    filter: String,
+    reason: String,
+}
+
+/// Tests that must not be run, along with the reason why.
+#[derive(Clone, Debug)]
+pub(super) struct CompiledBanned {
+    pub(super) expr: Filterset,
+    pub(super) reason: String,
+}
+
+impl CompiledBanned {
+    pub(super) fn new(
+        graph: &PackageGraph,
+        profile_name: &str,
+        index: usize,
+        source: &DeserializedBanned,
+        errors: &mut Vec<ConfigCompileError>,
+    ) -> Option<Self> {
+        let cx = ParseContext {
+            graph,
+            kind: FiltersetKind::Test,
+        };
+
+        match Filterset::parse(source.filter.clone(), &cx) {
+            Ok(expr) => Some(Self {
+                expr,
+                reason: source.reason.clone(),
+            }),
+            Err(err) => {
+                errors.push(ConfigCompileError {
+                    profile_name: profile_name.to_owned(),
+                    section: ConfigCompileSection::Banned(index),
+                    kind: ConfigCompileErrorKind::Parse {
+                        host_parse_error: None,
+                        target_parse_error: None,
+                        filter_parse_errors: vec![err],
+                    },
+                });
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::TestQuery;
+
+    #[test]
+    fn banned_reason() {
+        let config_contents = indoc! {r#"
+            [[profile.default.banned]]
+            filter = "test(/^destructive_/)"
+            reason = "wipes the shared database"
+
+            [[profile.ci.banned]]
+            filter = "test(needs_gpu)"
+            reason = "CI machines don't have GPUs"
+
+            [[profile.ci.banned]]
+            filter = "test(destructive_reset)"
+            reason = "resets the CI cache"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        )
+        .unwrap();
+
+        let binary_query = binary_query(
+            &graph,
+            package_id,
+            "lib",
+            "my-binary",
+            BuildPlatform::Target,
+        );
+        let query = |test_name: &'static str| TestQuery {
+            binary_query: binary_query.to_query(),
+            test_name,
+        };
+
+        let default_profile = config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(
+            default_profile.banned_reason(&query("destructive_reset")),
+            Some("wipes the shared database"),
+        );
+        assert_eq!(default_profile.banned_reason(&query("needs_gpu")), None);
+        assert_eq!(default_profile.banned_reason(&query("safe")), None);
+
+        // Entries from the custom profile come first, followed by the default
+        // profile's entries.
+        let ci_profile = config
+            .profile("ci")
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(
+            ci_profile.banned_reason(&query("needs_gpu")),
+            Some("CI machines don't have GPUs"),
+        );
+        assert_eq!(
+            ci_profile.banned_reason(&query("destructive_reset")),
+            Some("resets the CI cache"),
+        );
+    }
+
+    #[test]
+    fn banned_invalid_filter() {
+        let config_contents = indoc! {r#"
+            [[profile.default.banned]]
+            filter = "test(/^destructive_/"
+            reason = "wipes the shared database"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        );
+        assert!(config.is_err(), "invalid filterset is rejected");
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::ResourceLimit;
+use serde::Deserialize;
+use std::fmt;
+
+/// Cgroup v2 limits that tests are run with, set via the `cgroup-limits` setting.
+///
+/// If any limits are set, each test is run in its own cgroup with these limits. This only has an
+/// effect on Linux.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct CgroupLimits {
+    /// The maximum amount of memory the test can use, in bytes (`memory.max`).
+    #[serde(default)]
+    pub memory_max: Option<ResourceLimit>,
+
+    /// The maximum number of processes and threads the test can have (`pids.max`).
+    #[serde(default)]
+    pub pids_max: Option<ResourceLimit>,
+
+    /// The maximum CPU bandwidth the test can use (`cpu.max`).
+    #[serde(default)]
+    pub cpu_max: Option<CgroupCpuMax>,
+}
+
+impl CgroupLimits {
+    /// Returns true if no limits are set.
+    pub fn is_empty(&self) -> bool {
+        self.memory_max.is_none() && self.pids_max.is_none() && self.cpu_max.is_none()
+    }
+}
+
+impl fmt::Display for CgroupLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(memory_max) = self.memory_max {
+            parts.push(format!("memory-max={memory_max}"));
+        }
+        if let Some(pids_max) = self.pids_max {
+            parts.push(format!("pids-max={pids_max}"));
+        }
+        if let Some(cpu_max) = self.cpu_max {
+            parts.push(format!("cpu-max={cpu_max}"));
+        }
+        if parts.is_empty() {
+            write!(f, "(none)")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// A limit on CPU bandwidth, expressed as a number of CPUs.
+///
+/// Part of [`CgroupLimits`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CgroupCpuMax {
+    quota_micros: u64,
+}
+
+impl CgroupCpuMax {
+    /// The period over which the quota is measured, in microseconds.
+    ///
+    /// This is the kernel's default period for `cpu.max`.
+    pub const PERIOD_MICROS: u64 = 100_000;
+
+    // The kernel requires quotas to be at least 1ms.
+    const MIN_QUOTA_MICROS: u64 = 1000;
+
+    /// Returns the quota within each [`Self::PERIOD_MICROS`] period, in microseconds.
+    pub fn quota_micros(&self) -> u64 {
+        self.quota_micros
+    }
+}
+
+impl fmt::Display for CgroupCpuMax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.quota_micros as f64 / Self::PERIOD_MICROS as f64
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for CgroupCpuMax {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl serde::de::Visitor<'_> for V {
+            type Value = CgroupCpuMax;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "a number of CPUs, at least 0.01")
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                let quota_micros = v * CgroupCpuMax::PERIOD_MICROS as f64;
+                if !quota_micros.is_finite() || quota_micros < CgroupCpuMax::MIN_QUOTA_MICROS as f64
+                {
+                    return Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Float(v),
+                        &self,
+                    ));
+                }
+                Ok(CgroupCpuMax {
+                    quota_micros: quota_micros.round() as u64,
+                })
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v <= 0 {
+                    return Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Signed(v),
+                        &self,
+                    ));
+                }
+                self.visit_f64(v as f64)
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                self.visit_f64(v as f64)
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            cgroup-limits = { memory-max = 1073741824, pids-max = "unlimited", cpu-max = 1.5 }
+        "#},
+        Some(CgroupLimits {
+            memory_max: Some(ResourceLimit::Value(1 << 30)),
+            pids_max: Some(ResourceLimit::Unlimited),
+            cpu_max: Some(CgroupCpuMax { quota_micros: 150_000 }),
+        })
+
+        ; "all limits"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            cgroup-limits = { cpu-max = 2 }
+        "#},
+        Some(CgroupLimits {
+            cpu_max: Some(CgroupCpuMax { quota_micros: 200_000 }),
+            ..Default::default()
+        })
+
+        ; "integer cpu-max"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Some(CgroupLimits::default())
+
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            cgroup-limits = { cpu-max = 0 }
+        "#},
+        None
+
+        ; "zero cpu-max"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            cgroup-limits = { io-max = 1 }
+        "#},
+        None
+
+        ; "unknown limit"
+    )]
+    fn parse_cgroup_limits(config_contents: &str, expected: Option<CgroupLimits>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        );
+        match expected {
+            None => assert!(config.is_err()),
+            Some(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.cgroup_limits(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn display_cgroup_limits() {
+        let limits = CgroupLimits {
+            memory_max: Some(ResourceLimit::Value(1024)),
+            cpu_max: Some(CgroupCpuMax {
+                quota_micros: 50_000,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(limits.to_string(), "memory-max=1024, cpu-max=0.5");
+        assert_eq!(CgroupLimits::default().to_string(), "(none)");
+    }
+}
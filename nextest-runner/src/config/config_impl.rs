@@ -2,20 +2,26 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    ArchiveConfig, CompiledByProfile, CompiledData, CompiledDefaultFilter, ConfigExperimental,
-    CustomTestGroup, DefaultJunitImpl, DeserializedOverride, DeserializedProfileScriptConfig,
-    JunitConfig, JunitImpl, NextestVersionDeserialize, RetryPolicy, ScriptConfig, ScriptId,
-    SettingSource, SetupScripts, SlowTimeout, TestGroup, TestGroupConfig, TestSettings,
-    TestThreads, ThreadsRequired, ToolConfigFile,
+    AccessAudit, AccessPolicy, AdaptiveConcurrency, ArchiveConfig, CgroupLimits, CompiledByProfile,
+    CompiledData, CompiledDefaultFilter, ConfigExperimental, CrashDumps, CustomTestGroup,
+    DefaultJunitImpl, DeserializedBanned, DeserializedOverride, DeserializedProfileScriptConfig,
+    DesktopIsolation, EnvVarPattern, FailureClasses, FinalSummary, JunitConfig, JunitImpl,
+    MaxConcurrentBinaries, MaxFailScope, NetworkAccess, NextestVersionDeserialize, OutputFilter,
+    PreExecHookConfig, ProfileReporterConfig, Redactions, ResourceLimits, RetryPolicy,
+    SchedulingFairness, SchedulingPolicy, ScriptConfig, ScriptId, SettingSource, SetupScripts,
+    SignalConfig, SlowTimeout, SlowTimeoutMultiplier, TestGroup, TestGroupConfig, TestSettings,
+    TestStdin, TestThreads, ThreadsRequired, TmpdirIsolation, ToolConfigFile, WrapperPreset,
 };
 use crate::{
     errors::{
         provided_by_tool, ConfigParseError, ConfigParseErrorKind, ProfileNotFound,
         UnknownConfigScriptError, UnknownTestGroupError,
     },
+    flaky::FlakyTests,
     list::TestList,
     platform::BuildPlatforms,
     reporter::{FinalStatusLevel, StatusLevel, TestOutputDisplay},
+    warnings::{self, WarningCode},
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use config::{
@@ -59,6 +65,7 @@ pub struct NextestConfig {
     workspace_root: Utf8PathBuf,
     inner: NextestConfigImpl,
     compiled: CompiledByProfile,
+    flaky_tests: FlakyTests,
 }
 
 impl NextestConfig {
@@ -71,6 +78,12 @@ impl NextestConfig {
     /// Repository-specific configuration is layered on top of the default config.
     pub const DEFAULT_CONFIG: &'static str = include_str!("../../default-config.toml");
 
+    /// Contains a JSON Schema describing the config file.
+    ///
+    /// The schema is maintained by hand alongside the config types, and is printed by
+    /// `cargo nextest debug config-schema` for use by editors.
+    pub const JSON_SCHEMA: &'static str = include_str!("../../config-schema.json");
+
     /// Environment configuration uses this prefix, plus a _.
     pub const ENVIRONMENT_PREFIX: &'static str = "NEXTEST";
 
@@ -123,9 +136,12 @@ impl NextestConfig {
                     }
                 }
 
-                warn!(
-                    "ignoring unknown configuration keys in config file {config_file}{}:{unknown_str}",
-                    provided_by_tool(tool),
+                warnings::emit(
+                    WarningCode::UnknownConfigKeys,
+                    format_args!(
+                        "ignoring unknown configuration keys in config file {config_file}{}:{unknown_str}",
+                        provided_by_tool(tool),
+                    ),
                 )
             },
         )
@@ -153,10 +169,12 @@ impl NextestConfig {
             experimental,
             &mut unknown_callback,
         )?;
+        let flaky_tests = FlakyTests::read(&workspace_root.join(FlakyTests::PATH))?;
         Ok(Self {
             workspace_root,
             inner,
             compiled,
+            flaky_tests,
         })
     }
 
@@ -190,13 +208,28 @@ impl NextestConfig {
             inner: deserialized.into_config_impl(),
             // The default config has no overrides or special settings.
             compiled: CompiledByProfile::for_default_config(),
+            flaky_tests: FlakyTests::default(),
         }
     }
 
     /// Returns the profile with the given name, or an error if a profile was
     /// specified but not found.
     pub fn profile(&self, name: impl AsRef<str>) -> Result<EarlyProfile<'_>, ProfileNotFound> {
-        self.make_profile(name.as_ref())
+        self.make_profile(&[name.as_ref()])
+    }
+
+    /// Returns a profile composed of the given profiles, or an error if any of
+    /// them weren't found.
+    ///
+    /// Settings in later profiles override those in earlier ones, and settings
+    /// that none of them set are taken from the default profile. The composed
+    /// profile is named after its parts, joined by `+`: for example, `ci+asan`.
+    pub fn composed_profile<S: AsRef<str>>(
+        &self,
+        names: &[S],
+    ) -> Result<EarlyProfile<'_>, ProfileNotFound> {
+        let names: Vec<&str> = names.iter().map(AsRef::as_ref).collect();
+        self.make_profile(&names)
     }
 
     // ---
@@ -376,12 +409,15 @@ impl NextestConfig {
             .filter(|p| p.starts_with("default-") && !NextestConfig::DEFAULT_PROFILES.contains(p))
             .collect();
         if !unknown_default_profiles.is_empty() {
-            warn!(
-                "unknown profiles in the reserved `default-` namespace in config file {}{}:",
-                config_file
-                    .strip_prefix(workspace_root)
-                    .unwrap_or(config_file),
-                provided_by_tool(tool),
+            warnings::emit(
+                WarningCode::UnknownDefaultProfiles,
+                format_args!(
+                    "unknown profiles in the reserved `default-` namespace in config file {}{}:",
+                    config_file
+                        .strip_prefix(workspace_root)
+                        .unwrap_or(config_file),
+                    provided_by_tool(tool),
+                ),
             );
 
             for profile in unknown_default_profiles {
@@ -393,6 +429,51 @@ impl NextestConfig {
         let this_compiled = CompiledByProfile::new(graph, &this_config)
             .map_err(|kind| ConfigParseError::new(config_file, tool, kind))?;
 
+        // Warn about suppress-env patterns that look like they're for nextest's
+        // variables but can't match any of them: these are most likely typos.
+        let unmatched_patterns: BTreeSet<_> = this_config
+            .default_profile()
+            .suppress_env
+            .iter()
+            .chain(
+                this_config
+                    .other_profiles()
+                    .flat_map(|(_, profile)| profile.suppress_env.iter().flatten()),
+            )
+            .chain(
+                this_compiled
+                    .default
+                    .overrides
+                    .iter()
+                    .chain(
+                        this_compiled
+                            .other
+                            .values()
+                            .flat_map(|data| &data.overrides),
+                    )
+                    .flat_map(|override_| override_.data.suppress_env.iter().flatten()),
+            )
+            .filter(|pattern| pattern.is_unknown_nextest_var())
+            .map(|pattern| pattern.as_str())
+            .collect();
+        if !unmatched_patterns.is_empty() {
+            warnings::emit(
+                WarningCode::UnmatchedSuppressEnv,
+                format_args!(
+                    "suppress-env patterns in config file {}{} don't match any environment \
+                     variable nextest sets:",
+                    config_file
+                        .strip_prefix(workspace_root)
+                        .unwrap_or(config_file),
+                    provided_by_tool(tool),
+                ),
+            );
+
+            for pattern in unmatched_patterns {
+                warn!("  {pattern}");
+            }
+        }
+
         // Check that all overrides specify known test groups.
         let mut unknown_group_errors = Vec::new();
         let mut check_test_group = |profile_name: &str, test_group: Option<&TestGroup>| {
@@ -509,26 +590,42 @@ impl NextestConfig {
         Config::builder().add_source(File::from_str(Self::DEFAULT_CONFIG, FileFormat::Toml))
     }
 
-    fn make_profile(&self, name: &str) -> Result<EarlyProfile<'_>, ProfileNotFound> {
-        let custom_profile = self.inner.get_profile(name)?;
-
-        // The profile was found: construct it.
-        let mut store_dir = self.workspace_root.join(&self.inner.store.dir);
-        store_dir.push(name);
+    fn make_profile(&self, names: &[&str]) -> Result<EarlyProfile<'_>, ProfileNotFound> {
+        let mut custom_profiles = Vec::with_capacity(names.len());
+        for name in names {
+            // The default profile is always the base, so it doesn't need to be
+            // added again.
+            if let Some(custom_profile) = self.inner.get_profile(name)? {
+                custom_profiles.push(custom_profile);
+            }
+        }
 
-        // Grab the compiled data as well.
-        let compiled_data = match self.compiled.other.get(name) {
-            Some(data) => data.clone().chain(self.compiled.default.clone()),
-            None => self.compiled.default.clone(),
+        // The profiles were found: construct the profile.
+        let name = if names.is_empty() {
+            NextestConfig::DEFAULT_PROFILE.to_owned()
+        } else {
+            names.join("+")
         };
+        let mut store_dir = self.workspace_root.join(&self.inner.store.dir);
+        store_dir.push(&name);
+
+        // Grab the compiled data as well. Overrides in later profiles take
+        // precedence over those in earlier ones.
+        let mut compiled_data = self.compiled.default.clone();
+        for name in names {
+            if let Some(data) = self.compiled.other.get(*name) {
+                compiled_data = data.clone().chain(compiled_data);
+            }
+        }
 
         Ok(EarlyProfile {
-            name: name.to_owned(),
+            name,
             store_dir,
             default_profile: &self.inner.default_profile,
-            custom_profile,
+            custom_profiles,
             test_groups: &self.inner.test_groups,
             scripts: &self.inner.scripts,
+            flaky_tests: &self.flaky_tests,
             compiled_data,
         })
     }
@@ -583,6 +680,15 @@ pub(crate) struct FinalConfig {
     pub(super) target_eval: bool,
 }
 
+/// Returns the first setting found in `custom_profiles`, with later profiles
+/// taking precedence over earlier ones.
+fn custom_setting<'cfg, T>(
+    custom_profiles: &[&'cfg CustomProfileImpl],
+    f: impl FnMut(&'cfg CustomProfileImpl) -> Option<T>,
+) -> Option<T> {
+    custom_profiles.iter().rev().copied().find_map(f)
+}
+
 /// A nextest profile that can be obtained without identifying the host and
 /// target platforms.
 ///
@@ -591,20 +697,36 @@ pub struct EarlyProfile<'cfg> {
     name: String,
     store_dir: Utf8PathBuf,
     default_profile: &'cfg DefaultProfileImpl,
-    custom_profile: Option<&'cfg CustomProfileImpl>,
+    // In the order they were specified: later profiles take precedence.
+    custom_profiles: Vec<&'cfg CustomProfileImpl>,
     test_groups: &'cfg BTreeMap<CustomTestGroup, TestGroupConfig>,
     // This is ordered because the scripts are used in the order they're defined.
     scripts: &'cfg IndexMap<ScriptId, ScriptConfig>,
+    flaky_tests: &'cfg FlakyTests,
     // Invariant: `compiled_data.default_filter` is always present.
     pub(super) compiled_data: CompiledData<PreBuildPlatform>,
 }
 
 impl<'cfg> EarlyProfile<'cfg> {
+    /// Returns the name of the profile.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Returns the absolute profile-specific store directory.
     pub fn store_dir(&self) -> &Utf8Path {
         &self.store_dir
     }
 
+    /// Returns whether the outcome of each test is recorded to the flaky test
+    /// history at the end of a run.
+    pub fn record_flaky_history(&self) -> bool {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.record_flaky_history
+        })
+        .unwrap_or(self.default_profile.record_flaky_history)
+    }
+
     /// Returns the global test group configuration.
     pub fn test_group_config(&self) -> &'cfg BTreeMap<CustomTestGroup, TestGroupConfig> {
         self.test_groups
@@ -620,6 +742,14 @@ impl<'cfg> EarlyProfile<'cfg> {
     ) -> EvaluatableProfile<'cfg> {
         let compiled_data = self.compiled_data.apply_build_platforms(build_platforms);
 
+        let slow_timeout_multipliers = custom_setting(&self.custom_profiles, |profile| {
+            profile.slow_timeout_multipliers.as_deref()
+        })
+        .unwrap_or(&self.default_profile.slow_timeout_multipliers)
+        .iter()
+        .filter(|multiplier| multiplier.matches_platforms(build_platforms))
+        .collect();
+
         let resolved_default_filter = {
             // Look for the default filter in the first valid override.
             let found_filter = compiled_data
@@ -641,11 +771,14 @@ impl<'cfg> EarlyProfile<'cfg> {
             name: self.name,
             store_dir: self.store_dir,
             default_profile: self.default_profile,
-            custom_profile: self.custom_profile,
+            custom_profiles: self.custom_profiles,
             scripts: self.scripts,
             test_groups: self.test_groups,
+            flaky_tests: self.flaky_tests,
             compiled_data,
             resolved_default_filter,
+            slow_timeout_multipliers,
+            cargo_profile: None,
         }
     }
 }
@@ -658,15 +791,21 @@ pub struct EvaluatableProfile<'cfg> {
     name: String,
     store_dir: Utf8PathBuf,
     default_profile: &'cfg DefaultProfileImpl,
-    custom_profile: Option<&'cfg CustomProfileImpl>,
+    // In the order they were specified: later profiles take precedence.
+    custom_profiles: Vec<&'cfg CustomProfileImpl>,
     test_groups: &'cfg BTreeMap<CustomTestGroup, TestGroupConfig>,
     // This is ordered because the scripts are used in the order they're defined.
     scripts: &'cfg IndexMap<ScriptId, ScriptConfig>,
+    flaky_tests: &'cfg FlakyTests,
     // Invariant: `compiled_data.default_filter` is always present.
     pub(super) compiled_data: CompiledData<FinalConfig>,
     // The default filter that's been resolved after considering overrides (i.e.
     // platforms).
     resolved_default_filter: CompiledDefaultFilter,
+    // Slow timeout multipliers that match the build platforms.
+    slow_timeout_multipliers: Vec<&'cfg SlowTimeoutMultiplier>,
+    // The Cargo profile tests were built with, if known.
+    cargo_profile: Option<String>,
 }
 
 impl<'cfg> EvaluatableProfile<'cfg> {
@@ -704,86 +843,307 @@ impl<'cfg> EvaluatableProfile<'cfg> {
 
     /// Returns the retry count for this profile.
     pub fn retries(&self) -> RetryPolicy {
-        self.custom_profile
-            .and_then(|profile| profile.retries)
+        custom_setting(&self.custom_profiles, |profile| profile.retries)
             .unwrap_or(self.default_profile.retries)
     }
 
+    /// Returns the retry policy for tests listed in the workspace's flaky tests file.
+    pub fn flaky_test_retries(&self) -> RetryPolicy {
+        custom_setting(&self.custom_profiles, |profile| profile.flaky_test_retries)
+            .unwrap_or(self.default_profile.flaky_test_retries)
+    }
+
+    /// Returns whether the outcome of each test is recorded to the flaky test
+    /// history at the end of a run.
+    pub fn record_flaky_history(&self) -> bool {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.record_flaky_history
+        })
+        .unwrap_or(self.default_profile.record_flaky_history)
+    }
+
+    /// Returns the list of flaky tests read from [`FlakyTests::PATH`].
+    pub fn flaky_tests(&self) -> &'cfg FlakyTests {
+        self.flaky_tests
+    }
+
     /// Returns the number of threads to run against for this profile.
     pub fn test_threads(&self) -> TestThreads {
-        self.custom_profile
-            .and_then(|profile| profile.test_threads)
+        custom_setting(&self.custom_profiles, |profile| profile.test_threads)
             .unwrap_or(self.default_profile.test_threads)
     }
 
     /// Returns the number of threads required for each test.
     pub fn threads_required(&self) -> ThreadsRequired {
-        self.custom_profile
-            .and_then(|profile| profile.threads_required)
+        custom_setting(&self.custom_profiles, |profile| profile.threads_required)
             .unwrap_or(self.default_profile.threads_required)
     }
 
     /// Returns extra arguments to be passed to the test binary at runtime.
     pub fn run_extra_args(&self) -> &'cfg [String] {
-        self.custom_profile
-            .and_then(|profile| profile.run_extra_args.as_deref())
-            .unwrap_or(&self.default_profile.run_extra_args)
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.run_extra_args.as_deref()
+        })
+        .unwrap_or(&self.default_profile.run_extra_args)
     }
 
     /// Returns the time after which tests are treated as slow for this profile.
     pub fn slow_timeout(&self) -> SlowTimeout {
-        self.custom_profile
-            .and_then(|profile| profile.slow_timeout)
+        custom_setting(&self.custom_profiles, |profile| profile.slow_timeout)
             .unwrap_or(self.default_profile.slow_timeout)
     }
 
+    /// Sets the Cargo profile that tests were built with, e.g. `dev` or `release`.
+    ///
+    /// This is used to select slow timeout multipliers. If it isn't set, multipliers that specify
+    /// a Cargo profile don't apply.
+    pub fn set_cargo_profile(&mut self, cargo_profile: Option<String>) {
+        self.cargo_profile = cargo_profile;
+    }
+
+    /// Returns the factor that slow timeouts are scaled by, taking into account the build
+    /// platforms and the Cargo profile.
+    ///
+    /// All matching multipliers are applied.
+    pub fn slow_timeout_multiplier(&self) -> f64 {
+        self.slow_timeout_multipliers
+            .iter()
+            .filter_map(|multiplier| multiplier.multiplier_for(self.cargo_profile.as_deref()))
+            .product()
+    }
+
     /// Returns the time after which a child process that hasn't closed its handles is marked as
     /// leaky.
     pub fn leak_timeout(&self) -> Duration {
-        self.custom_profile
-            .and_then(|profile| profile.leak_timeout)
+        custom_setting(&self.custom_profiles, |profile| profile.leak_timeout)
             .unwrap_or(self.default_profile.leak_timeout)
     }
 
     /// Returns the test status level.
     pub fn status_level(&self) -> StatusLevel {
-        self.custom_profile
-            .and_then(|profile| profile.status_level)
+        custom_setting(&self.custom_profiles, |profile| profile.status_level)
             .unwrap_or(self.default_profile.status_level)
     }
 
     /// Returns the test status level at the end of the run.
     pub fn final_status_level(&self) -> FinalStatusLevel {
-        self.custom_profile
-            .and_then(|profile| profile.final_status_level)
+        custom_setting(&self.custom_profiles, |profile| profile.final_status_level)
             .unwrap_or(self.default_profile.final_status_level)
     }
 
     /// Returns the failure output config for this profile.
     pub fn failure_output(&self) -> TestOutputDisplay {
-        self.custom_profile
-            .and_then(|profile| profile.failure_output)
+        custom_setting(&self.custom_profiles, |profile| profile.failure_output)
             .unwrap_or(self.default_profile.failure_output)
     }
 
     /// Returns the failure output config for this profile.
     pub fn success_output(&self) -> TestOutputDisplay {
-        self.custom_profile
-            .and_then(|profile| profile.success_output)
+        custom_setting(&self.custom_profiles, |profile| profile.success_output)
             .unwrap_or(self.default_profile.success_output)
     }
 
     /// Returns the fail-fast config for this profile.
     pub fn fail_fast(&self) -> bool {
-        self.custom_profile
-            .and_then(|profile| profile.fail_fast)
+        custom_setting(&self.custom_profiles, |profile| profile.fail_fast)
             .unwrap_or(self.default_profile.fail_fast)
     }
 
+    /// Returns the max-fail scope for this profile.
+    pub fn max_fail_scope(&self) -> MaxFailScope {
+        custom_setting(&self.custom_profiles, |profile| profile.max_fail_scope)
+            .unwrap_or(self.default_profile.max_fail_scope)
+    }
+
+    /// Returns whether tests are run with an isolated home directory by default.
+    pub fn isolate_home(&self) -> bool {
+        custom_setting(&self.custom_profiles, |profile| profile.isolate_home)
+            .unwrap_or(self.default_profile.isolate_home)
+    }
+
+    /// Returns the desktop session isolation that tests are run with by default.
+    pub fn isolate_desktop(&self) -> DesktopIsolation {
+        custom_setting(&self.custom_profiles, |profile| profile.isolate_desktop)
+            .unwrap_or(self.default_profile.isolate_desktop)
+    }
+
+    /// Returns whether tests are run with per-attempt temporary directories by default.
+    pub fn isolate_tmpdir(&self) -> TmpdirIsolation {
+        custom_setting(&self.custom_profiles, |profile| profile.isolate_tmpdir)
+            .unwrap_or(self.default_profile.isolate_tmpdir)
+    }
+
+    /// Returns whether tests are allowed to access the network by default.
+    pub fn network(&self) -> NetworkAccess {
+        custom_setting(&self.custom_profiles, |profile| profile.network)
+            .unwrap_or(self.default_profile.network)
+    }
+
+    /// Returns whether tests' network and file system access is audited by default.
+    pub fn access_audit(&self) -> AccessAudit {
+        custom_setting(&self.custom_profiles, |profile| profile.access_audit)
+            .unwrap_or(self.default_profile.access_audit)
+    }
+
+    /// Returns the hosts that tests run with `access-audit = "enforce"` are allowed to contact.
+    pub fn access_policy(&self) -> &'cfg AccessPolicy {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.access_policy.as_ref()
+        })
+        .unwrap_or(&self.default_profile.access_policy)
+    }
+
+    /// Returns whether crash dumps are collected from tests that crash by default.
+    pub fn crash_dumps(&self) -> CrashDumps {
+        custom_setting(&self.custom_profiles, |profile| profile.crash_dumps)
+            .unwrap_or(self.default_profile.crash_dumps)
+    }
+
+    /// Returns the command run to produce a backtrace from each collected crash dump, if any.
+    ///
+    /// The `{binary}` and `{dump}` placeholders in arguments are replaced with the paths to the
+    /// test binary and the crash dump respectively.
+    pub fn crash_backtrace(&self) -> &'cfg [String] {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.crash_backtrace.as_deref()
+        })
+        .unwrap_or(&self.default_profile.crash_backtrace)
+    }
+
+    /// Returns the rules for classifying test failures by their output.
+    pub fn failure_classes(&self) -> &'cfg FailureClasses {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.failure_classes.as_ref()
+        })
+        .unwrap_or(&self.default_profile.failure_classes)
+    }
+
+    /// Returns the resource limits that tests are run with by default.
+    pub fn rlimits(&self) -> ResourceLimits {
+        custom_setting(&self.custom_profiles, |profile| profile.rlimits)
+            .unwrap_or(self.default_profile.rlimits)
+    }
+
+    /// Returns the cgroup limits that tests are run with by default.
+    pub fn cgroup_limits(&self) -> CgroupLimits {
+        custom_setting(&self.custom_profiles, |profile| profile.cgroup_limits)
+            .unwrap_or(self.default_profile.cgroup_limits)
+    }
+
+    /// Returns whether tests can acknowledge being stopped by default.
+    pub fn cooperative_cancel(&self) -> bool {
+        custom_setting(&self.custom_profiles, |profile| profile.cooperative_cancel)
+            .unwrap_or(self.default_profile.cooperative_cancel)
+    }
+
+    /// Returns whether key-value annotations are collected from test output by
+    /// default.
+    pub fn annotations(&self) -> bool {
+        custom_setting(&self.custom_profiles, |profile| profile.annotations)
+            .unwrap_or(self.default_profile.annotations)
+    }
+
+    /// Returns whether test binaries are run with libtest's JSON output by
+    /// default.
+    pub fn libtest_json(&self) -> bool {
+        custom_setting(&self.custom_profiles, |profile| profile.libtest_json)
+            .unwrap_or(self.default_profile.libtest_json)
+    }
+
+    /// Returns the patterns for environment variables to remove from each
+    /// test's environment by default.
+    pub fn suppress_env(&self) -> &'cfg [EnvVarPattern] {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.suppress_env.as_deref()
+        })
+        .unwrap_or(&self.default_profile.suppress_env)
+    }
+
+    /// Returns the filters applied to each test's captured output by default.
+    pub fn output_filters(&self) -> &'cfg [OutputFilter] {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.output_filters.as_deref()
+        })
+        .unwrap_or(&self.default_profile.output_filters)
+    }
+
+    /// Returns what tests' standard input is connected to by default.
+    pub fn stdin(&self) -> &'cfg TestStdin {
+        custom_setting(&self.custom_profiles, |profile| profile.stdin.as_ref())
+            .unwrap_or(&self.default_profile.stdin)
+    }
+
+    /// Returns the hooks run in the double-spawned child before each test is executed.
+    pub fn pre_exec(&self) -> &'cfg [PreExecHookConfig] {
+        custom_setting(&self.custom_profiles, |profile| profile.pre_exec.as_deref())
+            .unwrap_or(&self.default_profile.pre_exec)
+    }
+
+    /// Returns how signals received by nextest during a test run are handled.
+    pub fn signals(&self) -> SignalConfig {
+        custom_setting(&self.custom_profiles, |profile| profile.signals)
+            .unwrap_or(self.default_profile.signals)
+    }
+
+    /// Returns the wrapper preset that tests are run with by default.
+    pub fn wrapper(&self) -> WrapperPreset {
+        custom_setting(&self.custom_profiles, |profile| profile.wrapper)
+            .unwrap_or(self.default_profile.wrapper)
+    }
+
+    /// Returns the scheduling policy for this profile.
+    pub fn scheduling(&self) -> SchedulingPolicy {
+        custom_setting(&self.custom_profiles, |profile| profile.scheduling)
+            .unwrap_or(self.default_profile.scheduling)
+    }
+
+    /// Returns how tests from different packages or binaries are interleaved.
+    pub fn fairness(&self) -> SchedulingFairness {
+        custom_setting(&self.custom_profiles, |profile| profile.fairness)
+            .unwrap_or(self.default_profile.fairness)
+    }
+
+    /// Returns the settings for reducing concurrency while the system is under pressure.
+    pub fn adaptive_concurrency(&self) -> AdaptiveConcurrency {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.adaptive_concurrency
+        })
+        .unwrap_or(self.default_profile.adaptive_concurrency)
+    }
+
+    /// Returns the maximum number of test binaries that can have tests running at the same time.
+    pub fn max_concurrent_binaries(&self) -> MaxConcurrentBinaries {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile.max_concurrent_binaries
+        })
+        .unwrap_or(self.default_profile.max_concurrent_binaries)
+    }
+
+    /// Returns the extra sections to show in the final summary, if set in
+    /// this profile or the default profile.
+    pub fn final_summary(&self) -> Option<FinalSummary> {
+        custom_setting(&self.custom_profiles, |profile| {
+            profile
+                .reporter
+                .as_ref()
+                .and_then(|reporter| reporter.final_summary)
+        })
+        .or(self.default_profile.reporter.final_summary)
+    }
+
+    /// Returns the rules for redacting sensitive data from captured output.
+    pub fn redactions(&self) -> Redactions<'cfg> {
+        let custom = self
+            .custom_profiles
+            .iter()
+            .filter_map(|profile| profile.reporter.as_ref())
+            .flat_map(|reporter| &reporter.redact);
+        Redactions::new(self.default_profile.reporter.redact.iter().chain(custom))
+    }
+
     /// Returns the archive configuration for this profile.
     pub fn archive_config(&self) -> &'cfg ArchiveConfig {
-        self.custom_profile
-            .and_then(|profile| profile.archive.as_ref())
+        custom_setting(&self.custom_profiles, |profile| profile.archive.as_ref())
             .unwrap_or(&self.default_profile.archive)
     }
 
@@ -797,6 +1157,19 @@ impl<'cfg> EvaluatableProfile<'cfg> {
         TestSettings::new(self, query)
     }
 
+    /// Returns the reason a test is banned from running, if it matches any
+    /// `[[profile.<name>.banned]]` entries.
+    ///
+    /// If several entries match, the reason from the first one is returned.
+    pub fn banned_reason(&self, query: &TestQuery<'_>) -> Option<&str> {
+        let ecx = self.filterset_ecx();
+        self.compiled_data
+            .banned
+            .iter()
+            .find(|banned| banned.expr.matches_test(query, &ecx))
+            .map(|banned| banned.reason.as_str())
+    }
+
     /// Returns override settings for individual tests, with sources attached.
     pub(crate) fn settings_with_source_for(
         &self,
@@ -809,14 +1182,14 @@ impl<'cfg> EvaluatableProfile<'cfg> {
     pub fn junit(&self) -> Option<JunitConfig<'cfg>> {
         JunitConfig::new(
             self.store_dir(),
-            self.custom_profile.map(|p| &p.junit),
+            self.custom_profiles.iter().map(|p| &p.junit).collect(),
             &self.default_profile.junit,
         )
     }
 
     #[cfg(test)]
     pub(super) fn custom_profile(&self) -> Option<&'cfg CustomProfileImpl> {
-        self.custom_profile
+        self.custom_profiles.last().copied()
     }
 }
 
@@ -914,17 +1287,47 @@ pub(super) struct DefaultProfileImpl {
     threads_required: ThreadsRequired,
     run_extra_args: Vec<String>,
     retries: RetryPolicy,
+    flaky_test_retries: RetryPolicy,
+    record_flaky_history: bool,
     status_level: StatusLevel,
     final_status_level: FinalStatusLevel,
     failure_output: TestOutputDisplay,
     success_output: TestOutputDisplay,
     fail_fast: bool,
+    max_fail_scope: MaxFailScope,
     slow_timeout: SlowTimeout,
+    slow_timeout_multipliers: Vec<SlowTimeoutMultiplier>,
     leak_timeout: Duration,
+    isolate_home: bool,
+    isolate_desktop: DesktopIsolation,
+    isolate_tmpdir: TmpdirIsolation,
+    network: NetworkAccess,
+    access_audit: AccessAudit,
+    access_policy: AccessPolicy,
+    crash_dumps: CrashDumps,
+    crash_backtrace: Vec<String>,
+    rlimits: ResourceLimits,
+    cgroup_limits: CgroupLimits,
+    cooperative_cancel: bool,
+    annotations: bool,
+    libtest_json: bool,
+    failure_classes: FailureClasses,
+    suppress_env: Vec<EnvVarPattern>,
+    stdin: TestStdin,
+    output_filters: Vec<OutputFilter>,
+    wrapper: WrapperPreset,
+    pre_exec: Vec<PreExecHookConfig>,
+    signals: SignalConfig,
+    scheduling: SchedulingPolicy,
+    fairness: SchedulingFairness,
+    max_concurrent_binaries: MaxConcurrentBinaries,
+    adaptive_concurrency: AdaptiveConcurrency,
     overrides: Vec<DeserializedOverride>,
     scripts: Vec<DeserializedProfileScriptConfig>,
+    banned: Vec<DeserializedBanned>,
     junit: DefaultJunitImpl,
     archive: ArchiveConfig,
+    reporter: ProfileReporterConfig,
 }
 
 impl DefaultProfileImpl {
@@ -943,6 +1346,12 @@ impl DefaultProfileImpl {
                 .run_extra_args
                 .expect("run-extra-args present in default profile"),
             retries: p.retries.expect("retries present in default profile"),
+            flaky_test_retries: p
+                .flaky_test_retries
+                .expect("flaky-test-retries present in default profile"),
+            record_flaky_history: p
+                .record_flaky_history
+                .expect("record-flaky-history present in default profile"),
             status_level: p
                 .status_level
                 .expect("status-level present in default profile"),
@@ -956,16 +1365,80 @@ impl DefaultProfileImpl {
                 .success_output
                 .expect("success-output present in default profile"),
             fail_fast: p.fail_fast.expect("fail-fast present in default profile"),
+            max_fail_scope: p
+                .max_fail_scope
+                .expect("max-fail-scope present in default profile"),
             slow_timeout: p
                 .slow_timeout
                 .expect("slow-timeout present in default profile"),
+            slow_timeout_multipliers: p
+                .slow_timeout_multipliers
+                .expect("slow-timeout-multipliers present in default profile"),
             leak_timeout: p
                 .leak_timeout
                 .expect("leak-timeout present in default profile"),
+            isolate_home: p
+                .isolate_home
+                .expect("isolate-home present in default profile"),
+            isolate_desktop: p
+                .isolate_desktop
+                .expect("isolate-desktop present in default profile"),
+            isolate_tmpdir: p
+                .isolate_tmpdir
+                .expect("isolate-tmpdir present in default profile"),
+            network: p.network.expect("network present in default profile"),
+            access_audit: p
+                .access_audit
+                .expect("access-audit present in default profile"),
+            access_policy: p
+                .access_policy
+                .expect("access-policy present in default profile"),
+            crash_dumps: p
+                .crash_dumps
+                .expect("crash-dumps present in default profile"),
+            crash_backtrace: p
+                .crash_backtrace
+                .expect("crash-backtrace present in default profile"),
+            rlimits: p.rlimits.expect("rlimits present in default profile"),
+            cgroup_limits: p
+                .cgroup_limits
+                .expect("cgroup-limits present in default profile"),
+            cooperative_cancel: p
+                .cooperative_cancel
+                .expect("cooperative-cancel present in default profile"),
+            annotations: p
+                .annotations
+                .expect("annotations present in default profile"),
+            libtest_json: p
+                .libtest_json
+                .expect("libtest-json present in default profile"),
+            failure_classes: p
+                .failure_classes
+                .expect("failure-classes present in default profile"),
+            suppress_env: p
+                .suppress_env
+                .expect("suppress-env present in default profile"),
+            stdin: p.stdin.expect("stdin present in default profile"),
+            output_filters: p
+                .output_filters
+                .expect("output-filters present in default profile"),
+            wrapper: p.wrapper.expect("wrapper present in default profile"),
+            pre_exec: p.pre_exec.expect("pre-exec present in default profile"),
+            signals: p.signals.expect("signals present in default profile"),
+            scheduling: p.scheduling.expect("scheduling present in default profile"),
+            fairness: p.fairness.expect("fairness present in default profile"),
+            max_concurrent_binaries: p
+                .max_concurrent_binaries
+                .expect("max-concurrent-binaries present in default profile"),
+            adaptive_concurrency: p
+                .adaptive_concurrency
+                .expect("adaptive-concurrency present in default profile"),
             overrides: p.overrides,
             scripts: p.scripts,
+            banned: p.banned,
             junit: DefaultJunitImpl::for_default_profile(p.junit),
             archive: p.archive.expect("archive present in default profile"),
+            reporter: p.reporter.expect("reporter present in default profile"),
         }
     }
 
@@ -980,6 +1453,10 @@ impl DefaultProfileImpl {
     pub(super) fn setup_scripts(&self) -> &[DeserializedProfileScriptConfig] {
         &self.scripts
     }
+
+    pub(super) fn banned(&self) -> &[DeserializedBanned] {
+        &self.banned
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -990,6 +1467,10 @@ pub(super) struct CustomProfileImpl {
     default_filter: Option<String>,
     #[serde(default, deserialize_with = "super::deserialize_retry_policy")]
     retries: Option<RetryPolicy>,
+    #[serde(default, deserialize_with = "super::deserialize_retry_policy")]
+    flaky_test_retries: Option<RetryPolicy>,
+    #[serde(default)]
+    record_flaky_history: Option<bool>,
     #[serde(default)]
     test_threads: Option<TestThreads>,
     #[serde(default)]
@@ -1006,18 +1487,74 @@ pub(super) struct CustomProfileImpl {
     success_output: Option<TestOutputDisplay>,
     #[serde(default)]
     fail_fast: Option<bool>,
+    #[serde(default)]
+    max_fail_scope: Option<MaxFailScope>,
     #[serde(default, deserialize_with = "super::deserialize_slow_timeout")]
     slow_timeout: Option<SlowTimeout>,
+    #[serde(default)]
+    slow_timeout_multipliers: Option<Vec<SlowTimeoutMultiplier>>,
     #[serde(default, with = "humantime_serde::option")]
     leak_timeout: Option<Duration>,
     #[serde(default)]
+    isolate_home: Option<bool>,
+    #[serde(default)]
+    isolate_desktop: Option<DesktopIsolation>,
+    #[serde(default)]
+    isolate_tmpdir: Option<TmpdirIsolation>,
+    #[serde(default)]
+    network: Option<NetworkAccess>,
+    #[serde(default)]
+    access_audit: Option<AccessAudit>,
+    #[serde(default)]
+    access_policy: Option<AccessPolicy>,
+    #[serde(default)]
+    crash_dumps: Option<CrashDumps>,
+    #[serde(default)]
+    crash_backtrace: Option<Vec<String>>,
+    #[serde(default)]
+    rlimits: Option<ResourceLimits>,
+    #[serde(default)]
+    cgroup_limits: Option<CgroupLimits>,
+    #[serde(default)]
+    cooperative_cancel: Option<bool>,
+    #[serde(default)]
+    annotations: Option<bool>,
+    #[serde(default)]
+    libtest_json: Option<bool>,
+    #[serde(default)]
+    failure_classes: Option<FailureClasses>,
+    #[serde(default)]
+    suppress_env: Option<Vec<EnvVarPattern>>,
+    #[serde(default)]
+    stdin: Option<TestStdin>,
+    #[serde(default)]
+    output_filters: Option<Vec<OutputFilter>>,
+    #[serde(default)]
+    wrapper: Option<WrapperPreset>,
+    #[serde(default)]
+    pre_exec: Option<Vec<PreExecHookConfig>>,
+    #[serde(default)]
+    signals: Option<SignalConfig>,
+    #[serde(default)]
+    scheduling: Option<SchedulingPolicy>,
+    #[serde(default)]
+    fairness: Option<SchedulingFairness>,
+    #[serde(default)]
+    max_concurrent_binaries: Option<MaxConcurrentBinaries>,
+    #[serde(default)]
+    adaptive_concurrency: Option<AdaptiveConcurrency>,
+    #[serde(default)]
     overrides: Vec<DeserializedOverride>,
     #[serde(default)]
     scripts: Vec<DeserializedProfileScriptConfig>,
     #[serde(default)]
+    banned: Vec<DeserializedBanned>,
+    #[serde(default)]
     junit: JunitImpl,
     #[serde(default)]
     archive: Option<ArchiveConfig>,
+    #[serde(default)]
+    reporter: Option<ProfileReporterConfig>,
 }
 
 impl CustomProfileImpl {
@@ -1037,6 +1574,10 @@ impl CustomProfileImpl {
     pub(super) fn scripts(&self) -> &[DeserializedProfileScriptConfig] {
         &self.scripts
     }
+
+    pub(super) fn banned(&self) -> &[DeserializedBanned] {
+        &self.banned
+    }
 }
 
 #[cfg(test)]
@@ -1044,6 +1585,8 @@ mod tests {
     use super::*;
     use crate::config::test_helpers::*;
     use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
 
     #[test]
     fn default_config_is_valid() {
@@ -1053,6 +1596,465 @@ mod tests {
             .expect("default profile should exist");
     }
 
+    #[test]
+    fn json_schema_covers_default_config() {
+        let default_config: toml::Table =
+            toml::from_str(NextestConfig::DEFAULT_CONFIG).expect("default config is valid TOML");
+
+        // Every key in the default config should be described by the schema, so the schema
+        // doesn't fall behind as config keys are added.
+        check_schema_covers(&default_config);
+    }
+
+    #[test]
+    fn json_schema_covers_overrides_scripts_and_test_groups() {
+        // The default config doesn't have overrides, scripts or test groups, so check them
+        // against a config that uses every one of their keys.
+        let config_contents = indoc! {r#"
+            [test-groups.serial]
+            max-threads = 1
+
+            [script.my-script]
+            command = "echo hello"
+            slow-timeout = { period = "60s", terminate-after = 2 }
+            leak-timeout = "1s"
+            capture-stdout = true
+            capture-stderr = true
+            cleanup-timeout = "10s"
+            lazy = true
+            junit = { store-success-output = false, store-failure-output = true }
+
+            [[profile.default.scripts]]
+            platform = { host = "cfg(unix)", target = "cfg(unix)" }
+            filter = "test(my_test)"
+            setup = ["my-script"]
+
+            [[profile.default.overrides]]
+            platform = "cfg(unix)"
+            filter = "test(my_test)"
+            threads-required = 2
+            run-extra-args = ["--test-threads=1"]
+            retries = { backoff = "exponential", count = 3, delay = "1s", jitter = true, max-delay = "10s" }
+            slow-timeout = { period = "60s", terminate-after = 2, grace-period = "5s" }
+            leak-timeout = "1s"
+            isolate-home = true
+            isolate-desktop = "xdg"
+            isolate-tmpdir = "isolated"
+            network = "deny"
+            access-audit = "record"
+            crash-dumps = "collect"
+            rlimits = { nofile = 1024, core = 0, stack = "unlimited", as = "unlimited" }
+            cgroup-limits = { memory-max = 1073741824, pids-max = 100, cpu-max = 1.5 }
+            cooperative-cancel = true
+            annotations = true
+            libtest-json = true
+            tracking-issue = "https://example.com/issues/1"
+            cpu-set = "0"
+            priority = { nice = 5, io = 4 }
+            suppress-env = ["CARGO_PKG_*"]
+            stdin = { file = "input.txt" }
+            output-filters = ["strip-timestamps", { command = "cat" }]
+            wrapper = "valgrind"
+            test-group = "serial"
+            success-output = "final"
+            failure-output = "immediate-final"
+            junit = { store-success-output = true, store-failure-output = false }
+
+            [[profile.default.overrides]]
+            platform = "cfg(windows)"
+            default-filter = "all()"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        // Make sure that the config above is valid and only uses known keys.
+        NextestConfig::from_sources_impl(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &maplit::btreeset! { ConfigExperimental::SetupScripts },
+            |path, _tool, unknown| {
+                panic!("config file {path} has unknown keys: {unknown:?}");
+            },
+        )
+        .expect("config is valid");
+
+        let config: toml::Table = toml::from_str(config_contents).expect("config is valid TOML");
+        check_schema_covers(&config);
+
+        // Conversely, make sure the config above doesn't fall behind the schema.
+        let schema: serde_json::Value =
+            serde_json::from_str(NextestConfig::JSON_SCHEMA).expect("schema is valid JSON");
+        let used = |table: &toml::Value| -> BTreeSet<String> {
+            table
+                .as_table()
+                .expect("value is a table")
+                .keys()
+                .cloned()
+                .collect()
+        };
+        let profile = &config["profile"]["default"];
+        let mut overrides = BTreeSet::new();
+        for item in profile["overrides"].as_array().unwrap() {
+            overrides.extend(used(item));
+        }
+        for (definition, tested) in [
+            (&schema["definitions"]["override"], overrides),
+            (
+                &schema["definitions"]["profile-script"],
+                used(&profile["scripts"][0]),
+            ),
+            (
+                &schema["definitions"]["script"],
+                used(&config["script"]["my-script"]),
+            ),
+            (
+                &schema["properties"]["test-groups"]["additionalProperties"],
+                used(&config["test-groups"]["serial"]),
+            ),
+        ] {
+            let described: BTreeSet<String> = definition["properties"]
+                .as_object()
+                .expect("definition has properties")
+                .keys()
+                .cloned()
+                .collect();
+            assert_eq!(described, tested, "every key in the schema is tested");
+        }
+    }
+
+    /// Checks that every key in `config` is described by the JSON schema.
+    fn check_schema_covers(config: &toml::Table) {
+        let schema: serde_json::Value =
+            serde_json::from_str(NextestConfig::JSON_SCHEMA).expect("schema is valid JSON");
+
+        fn check(
+            schema: &serde_json::Value,
+            node: &serde_json::Value,
+            table: &toml::Table,
+            path: &str,
+        ) {
+            for (key, value) in table {
+                let path = format!("{path}.{key}");
+                let child = lookup(schema, node, key)
+                    .unwrap_or_else(|| panic!("schema doesn't describe `{path}`"));
+                match value {
+                    toml::Value::Table(table) => check(schema, child, table, &path),
+                    toml::Value::Array(items) => {
+                        let child = resolve(schema, child);
+                        for item in items {
+                            if let toml::Value::Table(table) = item {
+                                check(schema, &child["items"], table, &path);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        check(&schema, &schema, config, "");
+    }
+
+    /// Returns the schema for `key` within `node`, trying each branch of a `oneOf`.
+    fn lookup<'a>(
+        schema: &'a serde_json::Value,
+        node: &'a serde_json::Value,
+        key: &str,
+    ) -> Option<&'a serde_json::Value> {
+        let node = resolve(schema, node);
+        if let Some(branches) = node["oneOf"].as_array() {
+            return branches
+                .iter()
+                .find_map(|branch| lookup(schema, branch, key));
+        }
+        node["properties"].get(key).or_else(|| {
+            node["additionalProperties"]
+                .as_object()
+                .map(|_| &node["additionalProperties"])
+        })
+    }
+
+    /// Follows `$ref`s.
+    fn resolve<'a>(
+        schema: &'a serde_json::Value,
+        node: &'a serde_json::Value,
+    ) -> &'a serde_json::Value {
+        match node["$ref"].as_str() {
+            Some(reference) => {
+                let name = reference
+                    .strip_prefix("#/definitions/")
+                    .expect("references are to definitions");
+                resolve(schema, &schema["definitions"][name])
+            }
+            None => node,
+        }
+    }
+
+    #[test]
+    fn json_schema_covers_serde_fields() {
+        use crate::config::{
+            access_audit::AccessPolicy,
+            archive::{ArchiveConfig, ArchiveInclude},
+            banned::DeserializedBanned,
+            cgroup_limits::CgroupLimits,
+            junit::JunitImpl,
+            overrides::{DeserializedJunitOutput, DeserializedOverride},
+            priority::ProcessPriority,
+            reporter::{FinalSummary, ProfileReporterConfig, Redaction},
+            rlimits::ResourceLimits,
+            scheduling::AdaptiveConcurrency,
+            scripts::{DeserializedProfileScriptConfig, ScriptConfig, ScriptJunitConfig},
+            signals::SignalConfig,
+            slow_timeout::SlowTimeout,
+            test_group::TestGroupConfig,
+        };
+
+        // Unlike the tests above, this doesn't depend on an example config being kept up to date:
+        // adding a field to any of these structs without describing it in the schema fails here.
+        let schema: serde_json::Value =
+            serde_json::from_str(NextestConfig::JSON_SCHEMA).expect("schema is valid JSON");
+        let profile = "/definitions/profile/properties";
+        let cases = [
+            (serde_fields::<NextestConfigDeserialize>(), String::new()),
+            (
+                serde_fields::<StoreConfigImpl>(),
+                "/properties/store".to_owned(),
+            ),
+            (
+                serde_fields::<TestGroupConfig>(),
+                "/properties/test-groups/additionalProperties".to_owned(),
+            ),
+            (
+                serde_fields::<ScriptConfig>(),
+                "/definitions/script".to_owned(),
+            ),
+            (
+                serde_fields::<ScriptJunitConfig>(),
+                "/definitions/junit-output".to_owned(),
+            ),
+            (
+                serde_fields::<CustomProfileImpl>(),
+                "/definitions/profile".to_owned(),
+            ),
+            (
+                serde_fields::<DeserializedOverride>(),
+                "/definitions/override".to_owned(),
+            ),
+            (
+                serde_fields::<DeserializedJunitOutput>(),
+                "/definitions/junit-output".to_owned(),
+            ),
+            (
+                serde_fields::<DeserializedProfileScriptConfig>(),
+                "/definitions/profile-script".to_owned(),
+            ),
+            (
+                serde_fields::<DeserializedBanned>(),
+                "/definitions/banned".to_owned(),
+            ),
+            (
+                serde_fields::<SlowTimeout>(),
+                "/definitions/slow-timeout".to_owned(),
+            ),
+            (
+                serde_fields::<ResourceLimits>(),
+                "/definitions/rlimits".to_owned(),
+            ),
+            (
+                serde_fields::<CgroupLimits>(),
+                "/definitions/cgroup-limits".to_owned(),
+            ),
+            (
+                serde_fields::<AdaptiveConcurrency>(),
+                "/definitions/adaptive-concurrency".to_owned(),
+            ),
+            (
+                serde_fields::<ProcessPriority>(),
+                "/definitions/override/properties/priority".to_owned(),
+            ),
+            (
+                serde_fields::<AccessPolicy>(),
+                format!("{profile}/access-policy"),
+            ),
+            (serde_fields::<SignalConfig>(), format!("{profile}/signals")),
+            (serde_fields::<JunitImpl>(), format!("{profile}/junit")),
+            (
+                serde_fields::<ArchiveConfig>(),
+                format!("{profile}/archive"),
+            ),
+            (
+                serde_fields::<ArchiveInclude>(),
+                format!("{profile}/archive/properties/include/items"),
+            ),
+            (
+                serde_fields::<ProfileReporterConfig>(),
+                format!("{profile}/reporter"),
+            ),
+            (
+                serde_fields::<FinalSummary>(),
+                format!("{profile}/reporter/properties/final-summary"),
+            ),
+            (
+                serde_fields::<Redaction>(),
+                format!("{profile}/reporter/properties/redact/items"),
+            ),
+        ];
+
+        for (fields, pointer) in cases {
+            let node = schema
+                .pointer(&pointer)
+                .unwrap_or_else(|| panic!("schema has `{pointer}`"));
+            for field in fields {
+                assert!(
+                    lookup(&schema, node, field).is_some(),
+                    "schema at `{pointer}` doesn't describe field `{field}`"
+                );
+            }
+        }
+    }
+
+    /// Returns the field names that a `Deserialize` struct expects.
+    fn serde_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+        struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+        impl<'de> serde::Deserializer<'de> for FieldNames<'_> {
+            type Error = serde::de::value::Error;
+
+            fn deserialize_any<V: serde::de::Visitor<'de>>(
+                self,
+                _visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                Err(serde::de::Error::custom("expected a struct"))
+            }
+
+            fn deserialize_struct<V: serde::de::Visitor<'de>>(
+                self,
+                _name: &'static str,
+                fields: &'static [&'static str],
+                _visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                *self.0 = fields;
+                Err(serde::de::Error::custom("fields recorded"))
+            }
+
+            serde::forward_to_deserialize_any! {
+                bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+                byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map enum
+                identifier ignored_any
+            }
+        }
+
+        let mut fields: &'static [&'static str] = &[];
+        _ = T::deserialize(FieldNames(&mut fields));
+        assert!(
+            !fields.is_empty(),
+            "{} is deserialized as a struct",
+            std::any::type_name::<T>()
+        );
+        fields
+    }
+
+    #[test]
+    fn composed_profiles() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            retries = 1
+            fail-fast = true
+
+            [profile.ci]
+            retries = 2
+            fail-fast = false
+
+            [profile.ci.junit]
+            path = "junit.xml"
+
+            [[profile.ci.overrides]]
+            filter = "test(slow)"
+            retries = 4
+
+            [profile.asan]
+            retries = 3
+
+            [[profile.asan.overrides]]
+            filter = "test(slow)"
+            retries = 5
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        )
+        .expect("config is valid");
+
+        let profile = config
+            .composed_profile(&["ci", "asan"])
+            .expect("profiles exist")
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.name(), "ci+asan");
+        assert!(profile.store_dir().ends_with("ci+asan"));
+        // Later profiles override earlier ones...
+        assert_eq!(profile.retries(), RetryPolicy::new_without_delay(3));
+        // ...earlier profiles override the default profile...
+        assert!(!profile.fail_fast());
+        assert!(profile
+            .junit()
+            .expect("junit is enabled")
+            .path()
+            .ends_with("ci+asan/junit.xml"));
+        // ...and so do their overrides.
+        let binary_query = binary_query(
+            &graph,
+            package_id,
+            "lib",
+            "my-binary",
+            BuildPlatform::Target,
+        );
+        let query = TestQuery {
+            binary_query: binary_query.to_query(),
+            test_name: "slow",
+        };
+        assert_eq!(
+            profile.settings_for(&query).retries(),
+            RetryPolicy::new_without_delay(5)
+        );
+
+        // The order matters.
+        let profile = config
+            .composed_profile(&["asan", "ci"])
+            .expect("profiles exist")
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.retries(), RetryPolicy::new_without_delay(2));
+        assert_eq!(
+            profile.settings_for(&query).retries(),
+            RetryPolicy::new_without_delay(4)
+        );
+
+        // A single profile is the same as selecting it by name.
+        let profile = config
+            .composed_profile(&["ci"])
+            .expect("profile exists")
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.name(), "ci");
+        assert_eq!(profile.retries(), RetryPolicy::new_without_delay(2));
+
+        let Err(error) = config.composed_profile(&["ci", "missing"]) else {
+            panic!("unknown profile is an error");
+        };
+        assert!(
+            error.to_string().contains("profile `missing"),
+            "error mentions the missing profile: {error}"
+        );
+    }
+
     #[test]
     fn ignored_keys() {
         let config_contents = r#"
@@ -0,0 +1,128 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{de::Error, Deserialize};
+use std::fmt;
+
+/// A set of CPUs that a test is pinned to, set via the `cpu-set` setting.
+///
+/// This is specified as a comma-separated list of CPU indexes and inclusive ranges, for example
+/// `"0-3,8"`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CpuSet {
+    // Sorted and deduplicated.
+    cpus: Vec<usize>,
+}
+
+impl CpuSet {
+    /// The largest CPU index that can be specified.
+    ///
+    /// This matches `CPU_SETSIZE` on Linux.
+    pub const MAX_CPU: usize = 1023;
+
+    /// Parses a CPU set from a string like `"0-3,8"`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut cpus = Vec::new();
+        for part in input.split(',') {
+            let part = part.trim();
+            let (start, end) = match part.split_once('-') {
+                Some((start, end)) => (parse_cpu(start)?, parse_cpu(end)?),
+                None => {
+                    let cpu = parse_cpu(part)?;
+                    (cpu, cpu)
+                }
+            };
+            if start > end {
+                return Err(format!("in range `{part}`, {start} is greater than {end}"));
+            }
+            cpus.extend(start..=end);
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(Self { cpus })
+    }
+
+    /// Returns the CPU indexes in this set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.cpus.iter().copied()
+    }
+}
+
+fn parse_cpu(input: &str) -> Result<usize, String> {
+    let input = input.trim();
+    let cpu: usize = input
+        .parse()
+        .map_err(|_| format!("`{input}` is not a CPU index"))?;
+    if cpu > CpuSet::MAX_CPU {
+        return Err(format!(
+            "CPU index {cpu} is too large (maximum {})",
+            CpuSet::MAX_CPU
+        ));
+    }
+    Ok(cpu)
+}
+
+impl fmt::Display for CpuSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Collapse consecutive CPUs into ranges.
+        let mut first = true;
+        let mut iter = self.cpus.iter().copied().peekable();
+        while let Some(start) = iter.next() {
+            let mut end = start;
+            while iter.peek() == Some(&(end + 1)) {
+                end += 1;
+                iter.next();
+            }
+            if !first {
+                write!(f, ",")?;
+            }
+            first = false;
+            if start == end {
+                write!(f, "{start}")?;
+            } else {
+                write!(f, "{start}-{end}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for CpuSet {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        CpuSet::parse(&s).map_err(|error| D::Error::custom(format!("invalid cpu-set: {error}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("0", Ok(vec![0]); "single")]
+    #[test_case("0-3", Ok(vec![0, 1, 2, 3]); "range")]
+    #[test_case("8, 0-2,1", Ok(vec![0, 1, 2, 8]); "unsorted with overlap")]
+    #[test_case("3-1", Err(()); "reversed range")]
+    #[test_case("", Err(()); "empty")]
+    #[test_case("0,", Err(()); "trailing comma")]
+    #[test_case("a-b", Err(()); "not a number")]
+    #[test_case("1024", Err(()); "too large")]
+    fn parse_cpu_set(input: &str, expected: Result<Vec<usize>, ()>) {
+        let actual = CpuSet::parse(input);
+        match expected {
+            Ok(cpus) => assert_eq!(actual.unwrap().iter().collect::<Vec<_>>(), cpus),
+            Err(()) => {
+                actual.expect_err("parsing should fail");
+            }
+        }
+    }
+
+    #[test]
+    fn display_cpu_set() {
+        let cpu_set = CpuSet::parse("5,0-2,3,7,8").unwrap();
+        assert_eq!(cpu_set.to_string(), "0-3,5,7-8");
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Collection of crash dumps from tests that crash, set via the `crash-dumps` setting.
+///
+/// On Unix, tests are run with core dumps enabled, and core files are looked for according to the
+/// system's core pattern. On Windows, dumps written by Windows Error Reporting's `LocalDumps`
+/// feature are looked for. In both cases, dumps that are found are moved into the store directory.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrashDumps {
+    /// Crash dumps aren't collected, and tests are run with the system's core dump settings.
+    #[default]
+    Off,
+
+    /// Crash dumps of tests that crash are collected into the store directory.
+    Collect,
+}
+
+impl CrashDumps {
+    /// Returns true if crash dumps are collected.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Self::Off => false,
+            Self::Collect => true,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Isolation from the desktop session, set via the `isolate-desktop` setting.
+///
+/// This only has an effect on Unix platforms other than macOS, where desktop integration is
+/// configured through XDG base directories and D-Bus.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DesktopIsolation {
+    /// Tests share the desktop session that nextest is run in.
+    #[default]
+    None,
+
+    /// Each test attempt gets its own `XDG_RUNTIME_DIR`, `XDG_CACHE_HOME` and `XDG_CONFIG_HOME`.
+    Xdg,
+
+    /// In addition to [`Self::Xdg`], each test attempt gets a private D-Bus session bus.
+    XdgDbus,
+}
+
+impl DesktopIsolation {
+    /// Returns true if XDG directories are isolated.
+    pub fn isolates_xdg(self) -> bool {
+        matches!(self, Self::Xdg | Self::XdgDbus)
+    }
+
+    /// Returns true if a private D-Bus session is started.
+    pub fn isolates_dbus(self) -> bool {
+        matches!(self, Self::XdgDbus)
+    }
+}
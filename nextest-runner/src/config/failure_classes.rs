@@ -0,0 +1,146 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_output::ChildOutput;
+use indexmap::IndexMap;
+use regex::bytes::{Regex, RegexBuilder};
+use serde::{de::Error, Deserialize};
+
+/// Rules for classifying test failures by their output, set via the `failure-classes` table.
+///
+/// Each rule maps a class name to a regular expression. A failed test is assigned the first class,
+/// in the order defined, whose regular expression matches its standard output or standard error.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct FailureClasses {
+    classes: IndexMap<String, FailureClassPattern>,
+}
+
+impl FailureClasses {
+    /// Returns true if no failure classes are defined.
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    /// Returns the name of the first class that matches the given output, if any.
+    pub fn classify(&self, output: &ChildOutput) -> Option<&str> {
+        let (first, second) = match output {
+            ChildOutput::Split(output) => (
+                output.stdout.as_ref().map(|stdout| &stdout.buf[..]),
+                output.stderr.as_ref().map(|stderr| &stderr.buf[..]),
+            ),
+            ChildOutput::Combined { output } => (Some(&output.buf[..]), None),
+        };
+        self.classify_impl(first, second)
+    }
+
+    fn classify_impl(&self, stdout: Option<&[u8]>, stderr: Option<&[u8]>) -> Option<&str> {
+        self.classes.iter().find_map(|(name, pattern)| {
+            let matches = stdout.is_some_and(|stdout| pattern.0.is_match(stdout))
+                || stderr.is_some_and(|stderr| pattern.0.is_match(stderr));
+            matches.then_some(name.as_str())
+        })
+    }
+}
+
+/// A compiled regular expression for a failure class.
+#[derive(Clone, Debug)]
+struct FailureClassPattern(Regex);
+
+impl<'de> Deserialize<'de> for FailureClassPattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        // Test output is usually line-oriented, so match ^ and $ at line boundaries.
+        let regex = RegexBuilder::new(&s)
+            .multi_line(true)
+            .build()
+            .map_err(|error| D::Error::custom(format!("invalid regex `{s}`: {error}")))?;
+        Ok(Self(regex))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_and_classify() {
+        let config_contents = indoc! {r#"
+            [profile.custom.failure-classes]
+            infra-flake = 'connection (refused|reset)'
+            panic = '^thread .* panicked at'
+            assertion = 'assertion .*failed'
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        )
+        .expect("config is valid");
+
+        let default_profile = config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        assert!(default_profile.failure_classes().is_empty());
+
+        let profile = config
+            .profile("custom")
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        let classes = profile.failure_classes();
+
+        assert_eq!(
+            classes.classify_impl(None, Some(b"error: connection refused\n".as_slice())),
+            Some("infra-flake")
+        );
+        // Classes are checked in the order they're defined.
+        assert_eq!(
+            classes.classify_impl(
+                None,
+                Some(
+                    b"thread 'x' panicked at src/lib.rs:1:1:\nassertion `left == right` failed\n"
+                        .as_slice()
+                )
+            ),
+            Some("panic")
+        );
+        assert_eq!(
+            classes.classify_impl(Some(b"assertion `left == right` failed".as_slice()), None),
+            Some("assertion")
+        );
+        assert_eq!(
+            classes.classify_impl(Some(b"something else".as_slice()), None),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_invalid_regex() {
+        let config_contents = indoc! {r#"
+            [profile.custom.failure-classes]
+            broken = 'unclosed ('
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        )
+        .expect_err("invalid regex is rejected");
+    }
+}
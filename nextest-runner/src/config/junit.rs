@@ -13,35 +13,55 @@ pub struct JunitConfig<'cfg> {
     report_name: &'cfg str,
     store_success_output: bool,
     store_failure_output: bool,
+    split_by: JunitSplitBy,
 }
 
 impl<'cfg> JunitConfig<'cfg> {
     pub(super) fn new(
         store_dir: &Utf8Path,
-        custom_data: Option<&'cfg JunitImpl>,
+        custom_data: Vec<&'cfg JunitImpl>,
         default_data: &'cfg DefaultJunitImpl,
     ) -> Option<Self> {
-        let path = custom_data
-            .map(|custom| &custom.path)
-            .unwrap_or(&default_data.path)
-            .as_deref();
+        // Custom profiles are in the order they were specified: later ones
+        // take precedence over earlier ones.
+        // The default profile's path only applies to the default profile.
+        let path = if custom_data.is_empty() {
+            default_data.path.as_deref()
+        } else {
+            custom_data
+                .iter()
+                .rev()
+                .find_map(|custom| custom.path.as_deref())
+        };
 
         path.map(|path| {
             let path = store_dir.join(path);
             let report_name = custom_data
-                .and_then(|custom| custom.report_name.as_deref())
+                .iter()
+                .rev()
+                .find_map(|custom| custom.report_name.as_deref())
                 .unwrap_or(&default_data.report_name);
             let store_success_output = custom_data
-                .and_then(|custom| custom.store_success_output)
+                .iter()
+                .rev()
+                .find_map(|custom| custom.store_success_output)
                 .unwrap_or(default_data.store_success_output);
             let store_failure_output = custom_data
-                .and_then(|custom| custom.store_failure_output)
+                .iter()
+                .rev()
+                .find_map(|custom| custom.store_failure_output)
                 .unwrap_or(default_data.store_failure_output);
+            let split_by = custom_data
+                .iter()
+                .rev()
+                .find_map(|custom| custom.split_by)
+                .unwrap_or(default_data.split_by);
             Self {
                 path,
                 report_name,
                 store_success_output,
                 store_failure_output,
+                split_by,
             }
         })
     }
@@ -65,6 +85,29 @@ impl<'cfg> JunitConfig<'cfg> {
     pub fn store_failure_output(&self) -> bool {
         self.store_failure_output
     }
+
+    /// Returns how the JUnit report is split up into separate files.
+    pub fn split_by(&self) -> JunitSplitBy {
+        self.split_by
+    }
+}
+
+/// How a JUnit report is split up into separate files.
+///
+/// Part of [`JunitConfig`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum JunitSplitBy {
+    /// Write a single report to the configured path.
+    #[default]
+    None,
+
+    /// Write a separate report for each test binary.
+    Binary,
+
+    /// Write a separate report for each package.
+    Package,
 }
 
 #[derive(Clone, Debug)]
@@ -73,6 +116,7 @@ pub(super) struct DefaultJunitImpl {
     report_name: String,
     store_success_output: bool,
     store_failure_output: bool,
+    split_by: JunitSplitBy,
 }
 
 impl DefaultJunitImpl {
@@ -89,6 +133,9 @@ impl DefaultJunitImpl {
             store_failure_output: data
                 .store_failure_output
                 .expect("junit.store-failure-output present in default profile"),
+            split_by: data
+                .split_by
+                .expect("junit.split-by present in default profile"),
         }
     }
 }
@@ -104,4 +151,6 @@ pub(super) struct JunitImpl {
     store_success_output: Option<bool>,
     #[serde(default)]
     store_failure_output: Option<bool>,
+    #[serde(default)]
+    split_by: Option<JunitSplitBy>,
 }
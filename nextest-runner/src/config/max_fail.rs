@@ -1,4 +1,5 @@
 use crate::errors::MaxFailParseError;
+use serde::Deserialize;
 use std::{fmt, str::FromStr};
 
 /// Type for the max-fail flag
@@ -55,6 +56,37 @@ impl fmt::Display for MaxFail {
     }
 }
 
+/// The scope within which test failures are counted against [`MaxFail`].
+///
+/// Once the max-fail limit is exceeded within a scope, remaining tests in that
+/// scope are not started. Tests outside that scope continue to run.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MaxFailScope {
+    /// Count failures across the whole run, and cancel the run once the limit
+    /// is exceeded.
+    #[default]
+    Run,
+
+    /// Count failures per package, and only cancel remaining tests in the
+    /// package where the limit was exceeded.
+    Package,
+
+    /// Count failures per test binary, and only cancel remaining tests in the
+    /// binary where the limit was exceeded.
+    Binary,
+}
+
+impl fmt::Display for MaxFailScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Run => write!(f, "run"),
+            Self::Package => write!(f, "package"),
+            Self::Binary => write!(f, "binary"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
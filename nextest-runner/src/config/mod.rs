@@ -19,38 +19,76 @@
 //! as possible -- before the host and target platforms are known. Returning
 //! errors early leads to a better user experience.
 
+mod access_audit;
 mod archive;
+mod banned;
+mod cgroup_limits;
 mod config_impl;
+mod cpu_set;
+mod crash_dumps;
+mod desktop_isolation;
+mod failure_classes;
 mod helpers;
 mod identifier;
 mod junit;
 mod max_fail;
+mod network;
 mod nextest_version;
+mod output_filters;
 mod overrides;
+mod pre_exec;
+mod priority;
+mod reporter;
 mod retry_policy;
+mod rlimits;
+mod scheduling;
 mod scripts;
+mod signals;
 mod slow_timeout;
+mod stdin;
+mod suppress_env;
 mod test_group;
 mod test_threads;
 mod threads_required;
+mod tmpdir_isolation;
 mod tool_config;
 mod track_default;
+mod wrapper;
 
+pub use access_audit::*;
 pub use archive::*;
+use banned::*;
+pub use cgroup_limits::*;
 pub use config_impl::*;
+pub use cpu_set::*;
+pub use crash_dumps::*;
+pub use desktop_isolation::*;
+pub use failure_classes::*;
 pub use identifier::*;
 pub use junit::*;
 pub use max_fail::*;
+pub use network::*;
 pub use nextest_version::*;
+pub use output_filters::*;
 pub use overrides::*;
+pub use pre_exec::*;
+pub use priority::*;
+pub use reporter::*;
 pub use retry_policy::*;
+pub use rlimits::*;
+pub use scheduling::*;
 pub(super) use scripts::*;
+pub use signals::*;
 pub use slow_timeout::*;
+pub use stdin::*;
+pub use suppress_env::*;
 pub use test_group::*;
 pub use test_threads::*;
 pub use threads_required::*;
+pub use tmpdir_isolation::*;
 pub use tool_config::*;
 pub(super) use track_default::*;
+pub use wrapper::*;
 
 #[cfg(test)]
 mod test_helpers;
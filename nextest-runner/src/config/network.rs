@@ -0,0 +1,20 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Network access for tests, set via the `network` setting.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NetworkAccess {
+    /// Tests can access the network.
+    #[default]
+    Allow,
+
+    /// Tests are denied network access.
+    ///
+    /// On Linux, each test is run in its own network namespace, with only a loopback interface. On
+    /// other platforms, proxy environment variables are pointed at an address that refuses
+    /// connections, which only affects clients that honor those variables.
+    Deny,
+}
@@ -6,7 +6,7 @@
 use super::{NextestConfig, ToolConfigFile};
 use crate::errors::{ConfigParseError, ConfigParseErrorKind};
 use camino::Utf8Path;
-use semver::Version;
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Deserializer};
 use std::{borrow::Cow, collections::BTreeSet, fmt, str::FromStr};
 
@@ -167,6 +167,12 @@ pub struct NextestVersionConfig {
     /// This might be lower than [`Self::required`], in which case it is ignored. [`Self::eval`]
     /// checks for required versions before it checks for recommended versions.
     pub recommended: NextestVersionReq,
+
+    /// The range of versions `cargo nextest self update` updates to by default.
+    ///
+    /// Unlike required and recommended versions, this isn't merged across config files: the
+    /// highest-priority config file that specifies a range wins.
+    pub pin: Option<VersionReq>,
 }
 
 impl NextestVersionConfig {
@@ -178,6 +184,10 @@ impl NextestVersionConfig {
         if let Some(v) = v.recommended {
             self.recommended.accumulate(v, v_tool);
         }
+        // Config files are accumulated in increasing order of priority, so later ranges win.
+        if let Some(v) = v.pin {
+            self.pin = Some(v);
+        }
     }
 
     /// Returns whether the given version satisfies the nextest version requirement.
@@ -374,6 +384,9 @@ pub(crate) struct NextestVersionDeserialize {
 
     /// The minimum version of nextest that this repository produces a warning against.
     recommended: Option<Version>,
+
+    /// The range of versions to update to.
+    pin: Option<VersionReq>,
 }
 
 impl<'de> Deserialize<'de> for NextestVersionDeserialize {
@@ -400,6 +413,7 @@ impl<'de> Deserialize<'de> for NextestVersionDeserialize {
                 Ok(NextestVersionDeserialize {
                     required: Some(required),
                     recommended: None,
+                    pin: None,
                 })
             }
 
@@ -413,11 +427,14 @@ impl<'de> Deserialize<'de> for NextestVersionDeserialize {
                     required: Option<Version>,
                     #[serde(default, deserialize_with = "deserialize_version_opt")]
                     recommended: Option<Version>,
+                    #[serde(default, deserialize_with = "deserialize_version_req_opt")]
+                    pin: Option<VersionReq>,
                 }
 
                 let NextestVersionMap {
                     required,
                     recommended,
+                    pin,
                 } = NextestVersionMap::deserialize(serde::de::value::MapAccessDeserializer::new(
                     map,
                 ))?;
@@ -433,6 +450,7 @@ impl<'de> Deserialize<'de> for NextestVersionDeserialize {
                 Ok(NextestVersionDeserialize {
                     required,
                     recommended,
+                    pin,
                 })
             }
         }
@@ -455,6 +473,20 @@ where
     s.map(parse_version::<D::Error>).transpose()
 }
 
+fn deserialize_version_req_opt<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<VersionReq>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    s.map(|s| {
+        s.parse::<VersionReq>()
+            .map_err(|error| serde::de::Error::custom(format!("invalid pin `{s}`: {error}")))
+    })
+    .transpose()
+}
+
 fn parse_version<E>(mut s: String) -> std::result::Result<Version, E>
 where
     E: serde::de::Error,
@@ -490,19 +522,19 @@ mod tests {
         r#"
             nextest-version = "0.9"
         "#,
-        NextestVersionDeserialize { required: Some("0.9.0".parse().unwrap()), recommended: None } ; "basic"
+        NextestVersionDeserialize { required: Some("0.9.0".parse().unwrap()), recommended: None, pin: None } ; "basic"
     )]
     #[test_case(
         r#"
             nextest-version = "0.9.30"
         "#,
-        NextestVersionDeserialize { required: Some("0.9.30".parse().unwrap()), recommended: None } ; "basic with patch"
+        NextestVersionDeserialize { required: Some("0.9.30".parse().unwrap()), recommended: None, pin: None } ; "basic with patch"
     )]
     #[test_case(
         r#"
             nextest-version = { recommended = "0.9.20" }
         "#,
-        NextestVersionDeserialize { required: None, recommended: Some("0.9.20".parse().unwrap()) } ; "with warning"
+        NextestVersionDeserialize { required: None, recommended: Some("0.9.20".parse().unwrap()), pin: None } ; "with warning"
     )]
     #[test_case(
         r#"
@@ -511,8 +543,19 @@ mod tests {
         NextestVersionDeserialize {
             required: Some("0.9.20".parse().unwrap()),
             recommended: Some("0.9.25".parse().unwrap()),
+            pin: None,
         } ; "with error and warning"
     )]
+    #[test_case(
+        r#"
+            nextest-version = { required = "0.9.20", pin = "~0.9.80" }
+        "#,
+        NextestVersionDeserialize {
+            required: Some("0.9.20".parse().unwrap()),
+            recommended: None,
+            pin: Some("~0.9.80".parse().unwrap()),
+        } ; "with pin"
+    )]
     fn test_valid_nextest_version(input: &str, expected: NextestVersionDeserialize) {
         let actual: VersionOnlyDeserialize = toml::from_str(input).unwrap();
         assert_eq!(actual.nextest_version.unwrap(), expected);
@@ -542,6 +585,12 @@ mod tests {
         "#,
         "required version (0.9.20) must not be greater than recommended version (0.9.10)" ; "error greater than warning"
     )]
+    #[test_case(
+        r#"
+            nextest-version = { pin = "not-a-range" }
+        "#,
+        "invalid pin `not-a-range`" ; "invalid pin"
+    )]
     fn test_invalid_nextest_version(input: &str, error_message: &str) {
         let err = toml::from_str::<VersionOnlyDeserialize>(input).unwrap_err();
         assert!(
@@ -557,6 +606,7 @@ mod tests {
             NextestVersionDeserialize {
                 required: Some("0.9.20".parse().unwrap()),
                 recommended: None,
+                pin: Some("~0.9.20".parse().unwrap()),
             },
             Some("tool1"),
         );
@@ -564,6 +614,7 @@ mod tests {
             NextestVersionDeserialize {
                 required: Some("0.9.30".parse().unwrap()),
                 recommended: Some("0.9.35".parse().unwrap()),
+                pin: Some("~0.9.35".parse().unwrap()),
             },
             Some("tool2"),
         );
@@ -573,6 +624,7 @@ mod tests {
                 // This recommended version is ignored since it is less than the last recommended
                 // version.
                 recommended: Some("0.9.25".parse().unwrap()),
+                pin: None,
             },
             Some("tool3"),
         );
@@ -582,6 +634,7 @@ mod tests {
                 // last tool wins.
                 required: Some("0.9.30".parse().unwrap()),
                 recommended: None,
+                pin: None,
             },
             Some("tool4"),
         );
@@ -597,6 +650,8 @@ mod tests {
                     version: "0.9.35".parse().unwrap(),
                     tool: Some("tool2".to_owned()),
                 },
+                // The last pin specified wins, and later files without a pin don't clear it.
+                pin: Some("~0.9.35".parse().unwrap()),
             }
         );
     }
@@ -0,0 +1,213 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::fmt;
+
+/// A filter applied to a test's captured output before it's displayed or stored, set via the
+/// `output-filters` setting.
+///
+/// Filters are applied to standard output and standard error separately, in the order they're
+/// listed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OutputFilter {
+    /// Removes timestamps from the start of each line.
+    StripTimestamps,
+
+    /// Collapses runs of identical lines into a single line, followed by a count of repeats.
+    CollapseRepeatedLines,
+
+    /// Renders lines that are JSON objects, as emitted by structured loggers, as readable text.
+    DecodeJsonLogs,
+
+    /// Pipes the output through an external command, and uses the command's standard output.
+    Command {
+        /// The program to run.
+        program: String,
+
+        /// Arguments to the program.
+        args: Vec<String>,
+    },
+}
+
+impl fmt::Display for OutputFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StripTimestamps => write!(f, "strip-timestamps"),
+            Self::CollapseRepeatedLines => write!(f, "collapse-repeated-lines"),
+            Self::DecodeJsonLogs => write!(f, "decode-json-logs"),
+            Self::Command { program, args } => {
+                write!(
+                    f,
+                    "{}",
+                    shell_words::join(std::iter::once(program).chain(args))
+                )
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OutputFilter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de2> serde::de::Visitor<'de2> for V {
+            type Value = OutputFilter;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "one of \"strip-timestamps\", \"collapse-repeated-lines\" or \
+                     \"decode-json-logs\", or a table with a `command` key"
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                match v {
+                    "strip-timestamps" => Ok(OutputFilter::StripTimestamps),
+                    "collapse-repeated-lines" => Ok(OutputFilter::CollapseRepeatedLines),
+                    "decode-json-logs" => Ok(OutputFilter::DecodeJsonLogs),
+                    _ => Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    )),
+                }
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de2>,
+            {
+                #[derive(Deserialize)]
+                #[serde(deny_unknown_fields)]
+                struct CommandFilter {
+                    #[serde(deserialize_with = "super::deserialize_command")]
+                    command: (String, Vec<String>),
+                }
+
+                let CommandFilter {
+                    command: (program, args),
+                } = CommandFilter::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+                Ok(OutputFilter::Command { program, args })
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Some(Vec::new())
+
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            output-filters = ["decode-json-logs", "strip-timestamps", "collapse-repeated-lines"]
+        "#},
+        Some(vec![
+            OutputFilter::DecodeJsonLogs,
+            OutputFilter::StripTimestamps,
+            OutputFilter::CollapseRepeatedLines,
+        ])
+
+        ; "built-in filters"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            output-filters = [{ command = "jq -R 'fromjson? // .'" }, { command = ["sed", "s/a/b/"] }]
+        "#},
+        Some(vec![
+            OutputFilter::Command {
+                program: "jq".to_owned(),
+                args: vec!["-R".to_owned(), "fromjson? // .".to_owned()],
+            },
+            OutputFilter::Command {
+                program: "sed".to_owned(),
+                args: vec!["s/a/b/".to_owned()],
+            },
+        ])
+
+        ; "commands"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            output-filters = ["strip-colors"]
+        "#},
+        None
+
+        ; "unknown built-in filter"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            output-filters = [{ command = "" }]
+        "#},
+        None
+
+        ; "empty command"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            output-filters = [{ command = "cat", timeout = "1s" }]
+        "#},
+        None
+
+        ; "unknown key"
+    )]
+    fn parse_output_filters(config_contents: &str, expected: Option<Vec<OutputFilter>>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        );
+        match expected {
+            None => assert!(config.is_err()),
+            Some(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.output_filters(), expected.as_slice());
+            }
+        }
+    }
+
+    #[test]
+    fn display_output_filter() {
+        assert_eq!(OutputFilter::DecodeJsonLogs.to_string(), "decode-json-logs");
+        let command = OutputFilter::Command {
+            program: "sed".to_owned(),
+            args: vec!["s/a b/c/".to_owned()],
+        };
+        assert_eq!(command.to_string(), "sed 's/a b/c/'");
+    }
+}
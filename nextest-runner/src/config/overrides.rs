@@ -2,11 +2,16 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    CompiledProfileScripts, DeserializedProfileScriptConfig, EvaluatableProfile, NextestConfig,
-    NextestConfigImpl,
+    CompiledBanned, CompiledProfileScripts, DeserializedBanned, DeserializedProfileScriptConfig,
+    EvaluatableProfile, NextestConfig, NextestConfigImpl,
 };
 use crate::{
-    config::{FinalConfig, PreBuildPlatform, RetryPolicy, SlowTimeout, TestGroup, ThreadsRequired},
+    config::{
+        AccessAudit, CgroupLimits, CpuSet, CrashDumps, DesktopIsolation, EnvVarPattern,
+        FinalConfig, NetworkAccess, OutputFilter, PreBuildPlatform, ProcessPriority,
+        ResourceLimits, RetryPolicy, SlowTimeout, TestGroup, TestStdin, ThreadsRequired,
+        TmpdirIsolation, WrapperPreset,
+    },
     errors::{
         ConfigCompileError, ConfigCompileErrorKind, ConfigCompileSection, ConfigParseErrorKind,
     },
@@ -18,7 +23,7 @@ use nextest_filtering::{CompiledExpr, Filterset, FiltersetKind, ParseContext, Te
 use owo_colors::{OwoColorize, Style};
 use serde::{Deserialize, Deserializer};
 use smol_str::SmolStr;
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, fmt, time::Duration};
 use target_spec::{Platform, TargetSpec};
 
 /// Settings for individual tests.
@@ -34,6 +39,24 @@ pub struct TestSettings<'p, Source = ()> {
     retries: (RetryPolicy, Source),
     slow_timeout: (SlowTimeout, Source),
     leak_timeout: (Duration, Source),
+    isolate_home: (bool, Source),
+    isolate_desktop: (DesktopIsolation, Source),
+    isolate_tmpdir: (TmpdirIsolation, Source),
+    network: (NetworkAccess, Source),
+    access_audit: (AccessAudit, Source),
+    crash_dumps: (CrashDumps, Source),
+    rlimits: (ResourceLimits, Source),
+    cgroup_limits: (CgroupLimits, Source),
+    cooperative_cancel: (bool, Source),
+    annotations: (bool, Source),
+    libtest_json: (bool, Source),
+    tracking_issue: (Option<&'p str>, Source),
+    cpu_set: (Option<&'p CpuSet>, Source),
+    priority: (Option<ProcessPriority>, Source),
+    suppress_env: (&'p [EnvVarPattern], Source),
+    stdin: (&'p TestStdin, Source),
+    output_filters: (&'p [OutputFilter], Source),
+    wrapper: (WrapperPreset, Source),
     test_group: (TestGroup, Source),
     success_output: (TestOutputDisplay, Source),
     failure_output: (TestOutputDisplay, Source),
@@ -43,6 +66,7 @@ pub struct TestSettings<'p, Source = ()> {
 
 pub(crate) trait TrackSource<'p>: Sized {
     fn track_profile<T>(value: T) -> (T, Self);
+    fn track_flaky_tests<T>(value: T) -> (T, Self);
     fn track_override<T>(value: T, source: &'p CompiledOverride<FinalConfig>) -> (T, Self);
 }
 
@@ -51,6 +75,10 @@ impl<'p> TrackSource<'p> for () {
         (value, ())
     }
 
+    fn track_flaky_tests<T>(value: T) -> (T, Self) {
+        (value, ())
+    }
+
     fn track_override<T>(value: T, _source: &'p CompiledOverride<FinalConfig>) -> (T, Self) {
         (value, ())
     }
@@ -59,6 +87,9 @@ impl<'p> TrackSource<'p> for () {
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum SettingSource<'p> {
     Profile,
+    /// The profile's `flaky-test-retries`, because the test is listed in the
+    /// workspace's flaky tests file.
+    FlakyTests,
     Override(&'p CompiledOverride<FinalConfig>),
 }
 
@@ -67,6 +98,10 @@ impl<'p> TrackSource<'p> for SettingSource<'p> {
         (value, SettingSource::Profile)
     }
 
+    fn track_flaky_tests<T>(value: T) -> (T, Self) {
+        (value, SettingSource::FlakyTests)
+    }
+
     fn track_override<T>(value: T, source: &'p CompiledOverride<FinalConfig>) -> (T, Self) {
         (value, SettingSource::Override(source))
     }
@@ -98,6 +133,99 @@ impl<'p> TestSettings<'p> {
         self.leak_timeout.0
     }
 
+    /// Returns whether this test should be run with an isolated home
+    /// directory.
+    pub fn isolate_home(&self) -> bool {
+        self.isolate_home.0
+    }
+
+    /// Returns the desktop session isolation this test is run with.
+    pub fn isolate_desktop(&self) -> DesktopIsolation {
+        self.isolate_desktop.0
+    }
+
+    /// Returns whether this test is run with per-attempt temporary directories.
+    pub fn isolate_tmpdir(&self) -> TmpdirIsolation {
+        self.isolate_tmpdir.0
+    }
+
+    /// Returns whether this test is allowed to access the network.
+    pub fn network(&self) -> NetworkAccess {
+        self.network.0
+    }
+
+    /// Returns whether this test's network and file system access is audited.
+    pub fn access_audit(&self) -> AccessAudit {
+        self.access_audit.0
+    }
+
+    /// Returns whether crash dumps are collected if this test crashes.
+    pub fn crash_dumps(&self) -> CrashDumps {
+        self.crash_dumps.0
+    }
+
+    /// Returns the resource limits this test is run with.
+    pub fn rlimits(&self) -> ResourceLimits {
+        self.rlimits.0
+    }
+
+    /// Returns the cgroup limits this test is run with.
+    pub fn cgroup_limits(&self) -> CgroupLimits {
+        self.cgroup_limits.0
+    }
+
+    /// Returns whether this test can acknowledge being stopped.
+    pub fn cooperative_cancel(&self) -> bool {
+        self.cooperative_cancel.0
+    }
+
+    /// Returns whether key-value annotations are collected from this test's
+    /// output.
+    pub fn annotations(&self) -> bool {
+        self.annotations.0
+    }
+
+    /// Returns whether this test is run with libtest's JSON output.
+    pub fn libtest_json(&self) -> bool {
+        self.libtest_json.0
+    }
+
+    /// Returns the issue tracking known failures of this test, if any.
+    pub fn tracking_issue(&self) -> Option<&'p str> {
+        self.tracking_issue.0
+    }
+
+    /// Returns the set of CPUs this test is pinned to, if any.
+    pub fn cpu_set(&self) -> Option<&'p CpuSet> {
+        self.cpu_set.0
+    }
+
+    /// Returns the OS-level priorities this test is run with, if any.
+    pub fn priority(&self) -> Option<ProcessPriority> {
+        self.priority.0
+    }
+
+    /// Returns the patterns for environment variables to remove from this
+    /// test's environment.
+    pub fn suppress_env(&self) -> &'p [EnvVarPattern] {
+        self.suppress_env.0
+    }
+
+    /// Returns what this test's standard input is connected to.
+    pub fn stdin(&self) -> &'p TestStdin {
+        self.stdin.0
+    }
+
+    /// Returns the filters applied to this test's captured output.
+    pub fn output_filters(&self) -> &'p [OutputFilter] {
+        self.output_filters.0
+    }
+
+    /// Returns the wrapper preset this test is run with.
+    pub fn wrapper(&self) -> WrapperPreset {
+        self.wrapper.0
+    }
+
     /// Returns the test group for this test.
     pub fn test_group(&self) -> &TestGroup {
         &self.test_group.0
@@ -137,6 +265,24 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
         let mut retries = None;
         let mut slow_timeout = None;
         let mut leak_timeout = None;
+        let mut isolate_home = None;
+        let mut isolate_desktop = None;
+        let mut isolate_tmpdir = None;
+        let mut network = None;
+        let mut access_audit = None;
+        let mut crash_dumps = None;
+        let mut rlimits = None;
+        let mut cgroup_limits = None;
+        let mut cooperative_cancel = None;
+        let mut annotations = None;
+        let mut libtest_json = None;
+        let mut tracking_issue = None;
+        let mut cpu_set = None;
+        let mut priority = None;
+        let mut suppress_env = None;
+        let mut stdin = None;
+        let mut output_filters = None;
+        let mut wrapper = None;
         let mut test_group = None;
         let mut success_output = None;
         let mut failure_output = None;
@@ -187,6 +333,96 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
                     leak_timeout = Some(Source::track_override(l, override_));
                 }
             }
+            if isolate_home.is_none() {
+                if let Some(i) = override_.data.isolate_home {
+                    isolate_home = Some(Source::track_override(i, override_));
+                }
+            }
+            if isolate_desktop.is_none() {
+                if let Some(i) = override_.data.isolate_desktop {
+                    isolate_desktop = Some(Source::track_override(i, override_));
+                }
+            }
+            if isolate_tmpdir.is_none() {
+                if let Some(i) = override_.data.isolate_tmpdir {
+                    isolate_tmpdir = Some(Source::track_override(i, override_));
+                }
+            }
+            if network.is_none() {
+                if let Some(n) = override_.data.network {
+                    network = Some(Source::track_override(n, override_));
+                }
+            }
+            if access_audit.is_none() {
+                if let Some(a) = override_.data.access_audit {
+                    access_audit = Some(Source::track_override(a, override_));
+                }
+            }
+            if crash_dumps.is_none() {
+                if let Some(c) = override_.data.crash_dumps {
+                    crash_dumps = Some(Source::track_override(c, override_));
+                }
+            }
+            if rlimits.is_none() {
+                if let Some(r) = override_.data.rlimits {
+                    rlimits = Some(Source::track_override(r, override_));
+                }
+            }
+            if cgroup_limits.is_none() {
+                if let Some(c) = override_.data.cgroup_limits {
+                    cgroup_limits = Some(Source::track_override(c, override_));
+                }
+            }
+            if cooperative_cancel.is_none() {
+                if let Some(c) = override_.data.cooperative_cancel {
+                    cooperative_cancel = Some(Source::track_override(c, override_));
+                }
+            }
+            if annotations.is_none() {
+                if let Some(a) = override_.data.annotations {
+                    annotations = Some(Source::track_override(a, override_));
+                }
+            }
+            if libtest_json.is_none() {
+                if let Some(l) = override_.data.libtest_json {
+                    libtest_json = Some(Source::track_override(l, override_));
+                }
+            }
+            if tracking_issue.is_none() {
+                if let Some(t) = override_.data.tracking_issue.as_deref() {
+                    tracking_issue = Some(Source::track_override(Some(t), override_));
+                }
+            }
+            if cpu_set.is_none() {
+                if let Some(c) = override_.data.cpu_set.as_ref() {
+                    cpu_set = Some(Source::track_override(Some(c), override_));
+                }
+            }
+            if priority.is_none() {
+                if let Some(p) = override_.data.priority {
+                    priority = Some(Source::track_override(Some(p), override_));
+                }
+            }
+            if suppress_env.is_none() {
+                if let Some(s) = override_.data.suppress_env.as_deref() {
+                    suppress_env = Some(Source::track_override(s, override_));
+                }
+            }
+            if stdin.is_none() {
+                if let Some(s) = override_.data.stdin.as_ref() {
+                    stdin = Some(Source::track_override(s, override_));
+                }
+            }
+            if output_filters.is_none() {
+                if let Some(f) = override_.data.output_filters.as_deref() {
+                    output_filters = Some(Source::track_override(f, override_));
+                }
+            }
+            if wrapper.is_none() {
+                if let Some(w) = override_.data.wrapper {
+                    wrapper = Some(Source::track_override(w, override_));
+                }
+            }
             if test_group.is_none() {
                 if let Some(t) = &override_.data.test_group {
                     test_group = Some(Source::track_override(t.clone(), override_));
@@ -219,11 +455,56 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
             threads_required.unwrap_or_else(|| Source::track_profile(profile.threads_required()));
         let run_extra_args =
             run_extra_args.unwrap_or_else(|| Source::track_profile(profile.run_extra_args()));
-        let retries = retries.unwrap_or_else(|| Source::track_profile(profile.retries()));
-        let slow_timeout =
-            slow_timeout.unwrap_or_else(|| Source::track_profile(profile.slow_timeout()));
+        let retries = retries.unwrap_or_else(|| {
+            let flaky_tests = profile.flaky_tests();
+            if flaky_tests.contains(query.binary_query.binary_id.as_str(), query.test_name) {
+                Source::track_flaky_tests(profile.flaky_test_retries())
+            } else {
+                Source::track_profile(profile.retries())
+            }
+        });
+        let slow_timeout = {
+            let (slow_timeout, source) =
+                slow_timeout.unwrap_or_else(|| Source::track_profile(profile.slow_timeout()));
+            (
+                slow_timeout.scale(profile.slow_timeout_multiplier()),
+                source,
+            )
+        };
         let leak_timeout =
             leak_timeout.unwrap_or_else(|| Source::track_profile(profile.leak_timeout()));
+        let isolate_home =
+            isolate_home.unwrap_or_else(|| Source::track_profile(profile.isolate_home()));
+        let isolate_desktop =
+            isolate_desktop.unwrap_or_else(|| Source::track_profile(profile.isolate_desktop()));
+        let isolate_tmpdir =
+            isolate_tmpdir.unwrap_or_else(|| Source::track_profile(profile.isolate_tmpdir()));
+        let network = network.unwrap_or_else(|| Source::track_profile(profile.network()));
+        let access_audit =
+            access_audit.unwrap_or_else(|| Source::track_profile(profile.access_audit()));
+        let crash_dumps =
+            crash_dumps.unwrap_or_else(|| Source::track_profile(profile.crash_dumps()));
+        let rlimits = rlimits.unwrap_or_else(|| Source::track_profile(profile.rlimits()));
+        let cgroup_limits =
+            cgroup_limits.unwrap_or_else(|| Source::track_profile(profile.cgroup_limits()));
+        let cooperative_cancel = cooperative_cancel
+            .unwrap_or_else(|| Source::track_profile(profile.cooperative_cancel()));
+        let annotations =
+            annotations.unwrap_or_else(|| Source::track_profile(profile.annotations()));
+        let libtest_json =
+            libtest_json.unwrap_or_else(|| Source::track_profile(profile.libtest_json()));
+        // Tracking issues can only be set via overrides.
+        let tracking_issue = tracking_issue.unwrap_or_else(|| Source::track_profile(None));
+        // CPU sets can only be set via overrides.
+        let cpu_set = cpu_set.unwrap_or_else(|| Source::track_profile(None));
+        // Priorities can only be set via overrides.
+        let priority = priority.unwrap_or_else(|| Source::track_profile(None));
+        let suppress_env =
+            suppress_env.unwrap_or_else(|| Source::track_profile(profile.suppress_env()));
+        let stdin = stdin.unwrap_or_else(|| Source::track_profile(profile.stdin()));
+        let output_filters =
+            output_filters.unwrap_or_else(|| Source::track_profile(profile.output_filters()));
+        let wrapper = wrapper.unwrap_or_else(|| Source::track_profile(profile.wrapper()));
         let test_group = test_group.unwrap_or_else(|| Source::track_profile(TestGroup::Global));
         let success_output =
             success_output.unwrap_or_else(|| Source::track_profile(profile.success_output()));
@@ -244,6 +525,24 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
             retries,
             slow_timeout,
             leak_timeout,
+            isolate_home,
+            isolate_desktop,
+            isolate_tmpdir,
+            network,
+            access_audit,
+            crash_dumps,
+            rlimits,
+            cgroup_limits,
+            cooperative_cancel,
+            annotations,
+            libtest_json,
+            tracking_issue,
+            cpu_set,
+            priority,
+            suppress_env,
+            stdin,
+            output_filters,
+            wrapper,
             test_group,
             success_output,
             failure_output,
@@ -278,6 +577,86 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
     }
 }
 
+impl<'p> TestSettings<'p, SettingSource<'p>> {
+    /// Returns each setting as its config key, its value formatted for display, and the source of
+    /// the value.
+    pub(crate) fn entries(&self) -> Vec<(&'static str, String, SettingSource<'p>)> {
+        fn debug<T: fmt::Debug, S: Copy>(
+            key: &'static str,
+            setting: &(T, S),
+        ) -> (&'static str, String, S) {
+            (key, format!("{:?}", setting.0), setting.1)
+        }
+
+        fn display<T: fmt::Display, S: Copy>(
+            key: &'static str,
+            setting: &(T, S),
+        ) -> (&'static str, String, S) {
+            (key, setting.0.to_string(), setting.1)
+        }
+
+        fn list<T: fmt::Display, S: Copy>(
+            key: &'static str,
+            setting: &(&[T], S),
+        ) -> (&'static str, String, S) {
+            let value = setting
+                .0
+                .iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>();
+            (key, format!("[{}]", value.join(", ")), setting.1)
+        }
+
+        fn optional<T: fmt::Display, S: Copy>(
+            key: &'static str,
+            setting: &(Option<T>, S),
+        ) -> (&'static str, String, S) {
+            let value = match &setting.0 {
+                Some(value) => value.to_string(),
+                None => "(none)".to_owned(),
+            };
+            (key, value, setting.1)
+        }
+
+        vec![
+            debug("threads-required", &self.threads_required),
+            debug("run-extra-args", &self.run_extra_args),
+            debug("retries", &self.retries),
+            debug("slow-timeout", &self.slow_timeout),
+            debug("leak-timeout", &self.leak_timeout),
+            display("test-group", &self.test_group),
+            debug("isolate-home", &self.isolate_home),
+            debug("isolate-desktop", &self.isolate_desktop),
+            debug("isolate-tmpdir", &self.isolate_tmpdir),
+            debug("network", &self.network),
+            debug("access-audit", &self.access_audit),
+            debug("crash-dumps", &self.crash_dumps),
+            display("rlimits", &self.rlimits),
+            display("cgroup-limits", &self.cgroup_limits),
+            debug("cooperative-cancel", &self.cooperative_cancel),
+            debug("annotations", &self.annotations),
+            debug("libtest-json", &self.libtest_json),
+            optional("tracking-issue", &self.tracking_issue),
+            optional("cpu-set", &self.cpu_set),
+            optional("priority", &self.priority),
+            list("suppress-env", &self.suppress_env),
+            debug("stdin", &self.stdin),
+            list("output-filters", &self.output_filters),
+            debug("wrapper", &self.wrapper),
+            debug("success-output", &self.success_output),
+            debug("failure-output", &self.failure_output),
+            debug(
+                "junit.store-success-output",
+                &self.junit_store_success_output,
+            ),
+            debug(
+                "junit.store-failure-output",
+                &self.junit_store_failure_output,
+            ),
+        ]
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(super) struct CompiledByProfile {
     pub(super) default: CompiledData<PreBuildPlatform>,
@@ -296,6 +675,7 @@ impl CompiledByProfile {
             Some(config.default_profile().default_filter()),
             config.default_profile().overrides(),
             config.default_profile().setup_scripts(),
+            config.default_profile().banned(),
             &mut errors,
         );
         let other: HashMap<_, _> = config
@@ -309,6 +689,7 @@ impl CompiledByProfile {
                         profile.default_filter(),
                         profile.overrides(),
                         profile.scripts(),
+                        profile.banned(),
                         &mut errors,
                     ),
                 )
@@ -333,6 +714,7 @@ impl CompiledByProfile {
                 profile_default_filter: Some(CompiledDefaultFilter::for_default_config()),
                 overrides: vec![],
                 scripts: vec![],
+                banned: vec![],
             },
             other: HashMap::new(),
         }
@@ -410,6 +792,8 @@ pub(super) struct CompiledData<State> {
     pub(super) profile_default_filter: Option<CompiledDefaultFilter>,
     pub(super) overrides: Vec<CompiledOverride<State>>,
     pub(super) scripts: Vec<CompiledProfileScripts<State>>,
+    // Banned tests don't depend on the platform, so they don't carry state.
+    pub(super) banned: Vec<CompiledBanned>,
 }
 
 impl CompiledData<PreBuildPlatform> {
@@ -419,6 +803,7 @@ impl CompiledData<PreBuildPlatform> {
         profile_default_filter: Option<&str>,
         overrides: &[DeserializedOverride],
         scripts: &[DeserializedProfileScriptConfig],
+        banned: &[DeserializedBanned],
         errors: &mut Vec<ConfigCompileError>,
     ) -> Self {
         let profile_default_filter = profile_default_filter.and_then(|filter| {
@@ -461,10 +846,18 @@ impl CompiledData<PreBuildPlatform> {
                 CompiledProfileScripts::new(graph, profile_name, index, source, errors)
             })
             .collect();
+        let banned = banned
+            .iter()
+            .enumerate()
+            .filter_map(|(index, source)| {
+                CompiledBanned::new(graph, profile_name, index, source, errors)
+            })
+            .collect();
         Self {
             profile_default_filter,
             overrides,
             scripts,
+            banned,
         }
     }
 
@@ -475,11 +868,13 @@ impl CompiledData<PreBuildPlatform> {
         }
         self.overrides.extend(other.overrides.into_iter().rev());
         self.scripts.extend(other.scripts.into_iter().rev());
+        self.banned.extend(other.banned.into_iter().rev());
     }
 
     pub(super) fn reverse(&mut self) {
         self.overrides.reverse();
         self.scripts.reverse();
+        self.banned.reverse();
     }
 
     /// Chains this data with another set of data, treating `other` as lower-priority than `self`.
@@ -487,12 +882,15 @@ impl CompiledData<PreBuildPlatform> {
         let profile_default_filter = self.profile_default_filter.or(other.profile_default_filter);
         let mut overrides = self.overrides;
         let mut setup_scripts = self.scripts;
+        let mut banned = self.banned;
         overrides.extend(other.overrides);
         setup_scripts.extend(other.scripts);
+        banned.extend(other.banned);
         Self {
             profile_default_filter,
             overrides,
             scripts: setup_scripts,
+            banned,
         }
     }
 
@@ -515,6 +913,7 @@ impl CompiledData<PreBuildPlatform> {
             profile_default_filter,
             overrides,
             scripts: setup_scripts,
+            banned: self.banned,
         }
     }
 }
@@ -535,7 +934,7 @@ impl<State> CompiledOverride<State> {
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub(crate) struct OverrideId {
     pub(crate) profile_name: SmolStr,
-    index: usize,
+    pub(crate) index: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -548,6 +947,24 @@ pub(super) struct ProfileOverrideData {
     retries: Option<RetryPolicy>,
     slow_timeout: Option<SlowTimeout>,
     leak_timeout: Option<Duration>,
+    isolate_home: Option<bool>,
+    isolate_desktop: Option<DesktopIsolation>,
+    isolate_tmpdir: Option<TmpdirIsolation>,
+    network: Option<NetworkAccess>,
+    access_audit: Option<AccessAudit>,
+    crash_dumps: Option<CrashDumps>,
+    rlimits: Option<ResourceLimits>,
+    cgroup_limits: Option<CgroupLimits>,
+    cooperative_cancel: Option<bool>,
+    annotations: Option<bool>,
+    libtest_json: Option<bool>,
+    tracking_issue: Option<String>,
+    cpu_set: Option<CpuSet>,
+    priority: Option<ProcessPriority>,
+    pub(super) suppress_env: Option<Vec<EnvVarPattern>>,
+    stdin: Option<TestStdin>,
+    output_filters: Option<Vec<OutputFilter>>,
+    wrapper: Option<WrapperPreset>,
     pub(super) test_group: Option<TestGroup>,
     success_output: Option<TestOutputDisplay>,
     failure_output: Option<TestOutputDisplay>,
@@ -630,6 +1047,24 @@ impl CompiledOverride<PreBuildPlatform> {
                         retries: source.retries,
                         slow_timeout: source.slow_timeout,
                         leak_timeout: source.leak_timeout,
+                        isolate_home: source.isolate_home,
+                        isolate_desktop: source.isolate_desktop,
+                        isolate_tmpdir: source.isolate_tmpdir,
+                        network: source.network,
+                        access_audit: source.access_audit,
+                        crash_dumps: source.crash_dumps,
+                        rlimits: source.rlimits,
+                        cgroup_limits: source.cgroup_limits,
+                        cooperative_cancel: source.cooperative_cancel,
+                        annotations: source.annotations,
+                        libtest_json: source.libtest_json,
+                        tracking_issue: source.tracking_issue.clone(),
+                        cpu_set: source.cpu_set.clone(),
+                        priority: source.priority,
+                        suppress_env: source.suppress_env.clone(),
+                        stdin: source.stdin.clone(),
+                        output_filters: source.output_filters.clone(),
+                        wrapper: source.wrapper,
                         test_group: source.test_group.clone(),
                         success_output: source.success_output,
                         failure_output: source.failure_output,
@@ -778,6 +1213,42 @@ pub(super) struct DeserializedOverride {
     #[serde(default, with = "humantime_serde::option")]
     leak_timeout: Option<Duration>,
     #[serde(default)]
+    isolate_home: Option<bool>,
+    #[serde(default)]
+    isolate_desktop: Option<DesktopIsolation>,
+    #[serde(default)]
+    isolate_tmpdir: Option<TmpdirIsolation>,
+    #[serde(default)]
+    network: Option<NetworkAccess>,
+    #[serde(default)]
+    access_audit: Option<AccessAudit>,
+    #[serde(default)]
+    crash_dumps: Option<CrashDumps>,
+    #[serde(default)]
+    rlimits: Option<ResourceLimits>,
+    #[serde(default)]
+    cgroup_limits: Option<CgroupLimits>,
+    #[serde(default)]
+    cooperative_cancel: Option<bool>,
+    #[serde(default)]
+    annotations: Option<bool>,
+    #[serde(default)]
+    libtest_json: Option<bool>,
+    #[serde(default)]
+    tracking_issue: Option<String>,
+    #[serde(default)]
+    cpu_set: Option<CpuSet>,
+    #[serde(default)]
+    priority: Option<ProcessPriority>,
+    #[serde(default)]
+    suppress_env: Option<Vec<EnvVarPattern>>,
+    #[serde(default)]
+    stdin: Option<TestStdin>,
+    #[serde(default)]
+    output_filters: Option<Vec<OutputFilter>>,
+    #[serde(default)]
+    wrapper: Option<WrapperPreset>,
+    #[serde(default)]
     test_group: Option<TestGroup>,
     #[serde(default)]
     success_output: Option<TestOutputDisplay>,
@@ -854,7 +1325,10 @@ impl<'de> Deserialize<'de> for PlatformStrings {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{test_helpers::*, NextestConfig};
+    use crate::{
+        config::{test_helpers::*, IoPriority, NextestConfig, ResourceLimit},
+        flaky::FlakyTests,
+    };
     use camino::Utf8Path;
     use camino_tempfile::tempdir;
     use indoc::indoc;
@@ -878,6 +1352,9 @@ mod tests {
             [[profile.default.overrides]]
             filter = "test(test)"
             threads-required = 8
+            cooperative-cancel = true
+            annotations = true
+            libtest-json = true
             retries = 3
             slow-timeout = "60s"
             leak-timeout = "300ms"
@@ -890,6 +1367,16 @@ mod tests {
             platform = { host = "cfg(unix)" }
             filter = "test(override3)"
             retries = 5
+            tracking-issue = "PROJ-1234"
+            cpu-set = "0-1,4"
+            priority = { nice = 10, io = "idle" }
+            cgroup-limits = { pids-max = 64 }
+            network = "deny"
+            access-audit = "enforce"
+            crash-dumps = "collect"
+            stdin = { file = "fixtures/input.txt" }
+            isolate-tmpdir = "keep-on-failure"
+            output-filters = ["decode-json-logs"]
 
             # Override 4 -- host not matched
             [[profile.default.overrides]]
@@ -947,6 +1434,9 @@ mod tests {
             }
         );
         assert_eq!(overrides.leak_timeout(), Duration::from_millis(300));
+        assert!(overrides.cooperative_cancel());
+        assert!(overrides.annotations());
+        assert!(overrides.libtest_json());
         assert_eq!(overrides.test_group(), &test_group("my-group"));
         assert_eq!(overrides.success_output(), TestOutputDisplay::Never);
         assert_eq!(overrides.failure_output(), TestOutputDisplay::Final);
@@ -1010,6 +1500,34 @@ mod tests {
         };
         let overrides = profile.settings_for(&query);
         assert_eq!(overrides.retries(), RetryPolicy::new_without_delay(5));
+        assert_eq!(overrides.tracking_issue(), Some("PROJ-1234"));
+        assert_eq!(
+            overrides.cpu_set().map(|c| c.iter().collect::<Vec<_>>()),
+            Some(vec![0, 1, 4])
+        );
+        assert_eq!(
+            overrides.priority(),
+            Some(ProcessPriority {
+                nice: Some(10),
+                io: Some(IoPriority::Idle),
+            })
+        );
+        assert_eq!(
+            overrides.cgroup_limits(),
+            CgroupLimits {
+                pids_max: Some(ResourceLimit::Value(64)),
+                ..Default::default()
+            }
+        );
+        assert_eq!(overrides.network(), NetworkAccess::Deny);
+        assert_eq!(overrides.access_audit(), AccessAudit::Enforce);
+        assert_eq!(overrides.crash_dumps(), CrashDumps::Collect);
+        assert_eq!(
+            overrides.stdin(),
+            &TestStdin::File("fixtures/input.txt".into())
+        );
+        assert_eq!(overrides.isolate_tmpdir(), TmpdirIsolation::KeepOnFailure);
+        assert_eq!(overrides.output_filters(), &[OutputFilter::DecodeJsonLogs]);
 
         // This query matches override 5.
         let query = TestQuery {
@@ -1026,6 +1544,19 @@ mod tests {
         };
         let overrides = profile.settings_for(&query);
         assert_eq!(overrides.retries(), RetryPolicy::new_without_delay(0));
+        assert_eq!(overrides.tracking_issue(), None);
+        assert_eq!(overrides.cpu_set(), None);
+        assert_eq!(overrides.priority(), None);
+        assert!(overrides.cgroup_limits().is_empty());
+        assert_eq!(overrides.network(), NetworkAccess::Allow);
+        assert_eq!(overrides.access_audit(), AccessAudit::Off);
+        assert_eq!(overrides.crash_dumps(), CrashDumps::Off);
+        assert_eq!(overrides.stdin(), &TestStdin::Null);
+        assert_eq!(overrides.isolate_tmpdir(), TmpdirIsolation::None);
+        assert!(overrides.output_filters().is_empty());
+        assert!(!overrides.cooperative_cancel());
+        assert!(!overrides.annotations());
+        assert!(!overrides.libtest_json());
     }
 
     #[test_case(
@@ -1203,6 +1734,142 @@ mod tests {
         };
     }
 
+    #[test]
+    fn settings_entries_sources() {
+        let config_contents = indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(other)"
+            retries = 5
+
+            [[profile.default.overrides]]
+            filter = "test(test)"
+            retries = 3
+            test-group = "my-group"
+
+            [test-groups.my-group]
+            max-threads = 2
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config is valid");
+        let profile = nextest_config
+            .profile("default")
+            .expect("valid profile name")
+            .apply_build_platforms(&build_platforms());
+
+        let binary_query =
+            binary_query(&graph, package_id, "lib", "my-binary", BuildPlatform::Host);
+        let query = TestQuery {
+            binary_query: binary_query.to_query(),
+            test_name: "test",
+        };
+        let entries = profile.settings_with_source_for(&query).entries();
+        let entry = |key: &str| {
+            entries
+                .iter()
+                .find(|(entry_key, _, _)| *entry_key == key)
+                .unwrap_or_else(|| panic!("entry {key} not found"))
+        };
+
+        let (_, value, source) = entry("retries");
+        assert_eq!(value, &format!("{:?}", RetryPolicy::new_without_delay(3)));
+        match source {
+            SettingSource::Override(override_) => assert_eq!(override_.id().index, 1),
+            SettingSource::Profile | SettingSource::FlakyTests => {
+                panic!("retries should come from an override")
+            }
+        }
+
+        let (_, value, source) = entry("test-group");
+        assert_eq!(value, "my-group");
+        assert!(matches!(source, SettingSource::Override(_)));
+
+        let (_, _, source) = entry("slow-timeout");
+        assert!(matches!(source, SettingSource::Profile));
+    }
+
+    #[test]
+    fn settings_entries_flaky_tests_source() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            retries = 1
+            flaky-test-retries = 4
+
+            [[profile.default.overrides]]
+            filter = "test(overridden)"
+            retries = 2
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let binary_query =
+            binary_query(&graph, package_id, "lib", "my-binary", BuildPlatform::Host);
+        std::fs::write(
+            workspace_dir.path().join(FlakyTests::PATH),
+            format!(
+                "[tests]\n\"{}\" = [\"flaky\", \"overridden\"]\n",
+                binary_query.to_query().binary_id,
+            ),
+        )
+        .unwrap();
+
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config is valid");
+        let profile = nextest_config
+            .profile("default")
+            .expect("valid profile name")
+            .apply_build_platforms(&build_platforms());
+
+        let retries_for = |test_name| {
+            let query = TestQuery {
+                binary_query: binary_query.to_query(),
+                test_name,
+            };
+            profile
+                .settings_with_source_for(&query)
+                .entries()
+                .into_iter()
+                .find(|(key, _, _)| *key == "retries")
+                .expect("retries entry found")
+        };
+
+        let (_, value, source) = retries_for("flaky");
+        assert_eq!(value, format!("{:?}", RetryPolicy::new_without_delay(4)));
+        assert!(
+            matches!(source, SettingSource::FlakyTests),
+            "listed test's retries come from flaky-test-retries, found {source:?}"
+        );
+
+        let (_, value, source) = retries_for("stable");
+        assert_eq!(value, format!("{:?}", RetryPolicy::new_without_delay(1)));
+        assert!(matches!(source, SettingSource::Profile));
+
+        let (_, value, source) = retries_for("overridden");
+        assert_eq!(value, format!("{:?}", RetryPolicy::new_without_delay(2)));
+        assert!(
+            matches!(source, SettingSource::Override(_)),
+            "overrides take precedence over the flaky tests list"
+        );
+    }
+
     /// Test that `cfg(unix)` works with a custom platform.
     ///
     /// This was broken with older versions of target-spec.
@@ -0,0 +1,174 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A hook to run in the double-spawned child just before a test is executed, set via the
+/// `pre-exec` setting.
+///
+/// Hooks are registered by name with
+/// [`register_pre_exec_hook`](crate::double_spawn::register_pre_exec_hook).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct PreExecHookConfig {
+    /// The name the hook is registered with.
+    pub name: String,
+
+    /// Arguments passed to the hook.
+    pub args: Vec<String>,
+}
+
+impl fmt::Display for PreExecHookConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for arg in &self.args {
+            write!(f, " {}", shell_words::quote(arg))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for PreExecHookConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de2> serde::de::Visitor<'de2> for V {
+            type Value = PreExecHookConfig;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a hook name, or a table with a `name` key and an optional `args` key"
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v.is_empty() {
+                    return Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    ));
+                }
+                Ok(PreExecHookConfig {
+                    name: v.to_owned(),
+                    args: Vec::new(),
+                })
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de2>,
+            {
+                #[derive(Deserialize)]
+                #[serde(deny_unknown_fields)]
+                struct HookTable {
+                    name: String,
+                    #[serde(default)]
+                    args: Vec<String>,
+                }
+
+                let HookTable { name, args } =
+                    HookTable::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+                if name.is_empty() {
+                    return Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(""),
+                        &"a non-empty hook name",
+                    ));
+                }
+                Ok(PreExecHookConfig { name, args })
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Some(vec![])
+
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            pre-exec = ["enter-netns", { name = "drop-privileges", args = ["nobody"] }]
+        "#},
+        Some(vec![
+            PreExecHookConfig { name: "enter-netns".to_owned(), args: vec![] },
+            PreExecHookConfig {
+                name: "drop-privileges".to_owned(),
+                args: vec!["nobody".to_owned()],
+            },
+        ])
+
+        ; "string and table"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            pre-exec = [""]
+        "#},
+        None
+
+        ; "empty name"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            pre-exec = [{ hook = "drop-privileges" }]
+        "#},
+        None
+
+        ; "unknown key"
+    )]
+    fn parse_pre_exec(config_contents: &str, expected: Option<Vec<PreExecHookConfig>>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        );
+        match expected {
+            None => assert!(config.is_err()),
+            Some(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.pre_exec(), expected.as_slice());
+            }
+        }
+    }
+
+    #[test]
+    fn display_pre_exec_hook() {
+        let hook = PreExecHookConfig {
+            name: "drop-privileges".to_owned(),
+            args: vec!["nobody".to_owned(), "a b".to_owned()],
+        };
+        assert_eq!(hook.to_string(), "drop-privileges nobody 'a b'");
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{de::Error, Deserialize};
+use std::fmt;
+
+/// OS-level scheduling priorities that a test is run with, set via the `priority` setting.
+///
+/// This is used to de-prioritize heavyweight tests relative to builds or interactive work.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ProcessPriority {
+    /// The niceness to run the test with, from -20 (highest priority) to 19 (lowest priority).
+    ///
+    /// On Unix, this is an absolute niceness, as set by `setpriority(2)`. On Windows, it's mapped
+    /// to the closest process priority class.
+    #[serde(default, deserialize_with = "deserialize_nice")]
+    pub nice: Option<i32>,
+
+    /// The I/O priority to run the test with. This only has an effect on Linux.
+    #[serde(default)]
+    pub io: Option<IoPriority>,
+}
+
+impl ProcessPriority {
+    /// The lowest niceness, i.e. the highest priority.
+    pub const MIN_NICE: i32 = -20;
+
+    /// The highest niceness, i.e. the lowest priority.
+    pub const MAX_NICE: i32 = 19;
+
+    /// Returns true if no priorities are set.
+    pub fn is_empty(&self) -> bool {
+        self.nice.is_none() && self.io.is_none()
+    }
+}
+
+impl fmt::Display for ProcessPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(nice) = self.nice {
+            parts.push(format!("nice={nice}"));
+        }
+        if let Some(io) = self.io {
+            parts.push(format!("io={io}"));
+        }
+        if parts.is_empty() {
+            write!(f, "(none)")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+fn deserialize_nice<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let nice = i32::deserialize(deserializer)?;
+    if !(ProcessPriority::MIN_NICE..=ProcessPriority::MAX_NICE).contains(&nice) {
+        return Err(D::Error::custom(format!(
+            "nice must be between {} and {}, found {nice}",
+            ProcessPriority::MIN_NICE,
+            ProcessPriority::MAX_NICE,
+        )));
+    }
+    Ok(Some(nice))
+}
+
+/// An I/O scheduling priority, as set by `ioprio_set(2)` on Linux.
+///
+/// Part of [`ProcessPriority`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IoPriority {
+    /// Only perform I/O when no other process needs the disk (`IOPRIO_CLASS_IDLE`).
+    Idle,
+
+    /// Best-effort I/O at the given level, from 0 (highest priority) to 7 (lowest priority)
+    /// (`IOPRIO_CLASS_BE`).
+    BestEffort(u8),
+}
+
+impl IoPriority {
+    /// The lowest-priority best-effort level.
+    pub const MAX_BEST_EFFORT_LEVEL: u8 = 7;
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Idle => write!(f, "idle"),
+            Self::BestEffort(level) => write!(f, "{level}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for IoPriority {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl serde::de::Visitor<'_> for V {
+            type Value = IoPriority;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a best-effort level from 0 to {} or the string \"idle\"",
+                    IoPriority::MAX_BEST_EFFORT_LEVEL
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v == "idle" {
+                    Ok(IoPriority::Idle)
+                } else {
+                    Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    ))
+                }
+            }
+
+            // Note that TOML uses i64, not u64.
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                u8::try_from(v)
+                    .ok()
+                    .filter(|level| *level <= IoPriority::MAX_BEST_EFFORT_LEVEL)
+                    .map(IoPriority::BestEffort)
+                    .ok_or_else(|| {
+                        serde::de::Error::invalid_value(serde::de::Unexpected::Signed(v), &self)
+                    })
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[derive(Debug, Deserialize)]
+    struct Wrapper {
+        priority: ProcessPriority,
+    }
+
+    #[test_case(
+        "priority = {}",
+        Some(ProcessPriority::default())
+        ; "empty"
+    )]
+    #[test_case(
+        r#"priority = { nice = 10, io = "idle" }"#,
+        Some(ProcessPriority { nice: Some(10), io: Some(IoPriority::Idle) })
+        ; "nice and idle"
+    )]
+    #[test_case(
+        "priority = { nice = -20, io = 7 }",
+        Some(ProcessPriority { nice: Some(-20), io: Some(IoPriority::BestEffort(7)) })
+        ; "best effort"
+    )]
+    #[test_case("priority = { nice = 20 }", None; "nice too high")]
+    #[test_case("priority = { io = 8 }", None; "level too high")]
+    #[test_case(r#"priority = { io = "realtime" }"#, None; "unknown class")]
+    #[test_case("priority = { ionice = 3 }", None; "unknown field")]
+    fn parse_priority(input: &str, expected: Option<ProcessPriority>) {
+        let parsed = toml::from_str::<Wrapper>(input).map(|w| w.priority);
+        match expected {
+            Some(expected) => assert_eq!(parsed.expect("parse succeeded"), expected),
+            None => {
+                parsed.expect_err("parse failed");
+            }
+        }
+    }
+
+    #[test]
+    fn display() {
+        let priority = ProcessPriority {
+            nice: Some(10),
+            io: Some(IoPriority::BestEffort(4)),
+        };
+        assert_eq!(priority.to_string(), "nice=10, io=4");
+        assert_eq!(ProcessPriority::default().to_string(), "(none)");
+    }
+}
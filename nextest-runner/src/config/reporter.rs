@@ -0,0 +1,246 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use regex::bytes::Regex;
+use serde::{de::Error, Deserialize};
+
+/// Reporter configuration, set in the `reporter` table of a profile or of the
+/// user config.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ReporterConfig {
+    /// Extra sections to show in the final summary at the end of a run.
+    #[serde(default)]
+    pub final_summary: Option<FinalSummary>,
+}
+
+/// Reporter configuration, set in the `reporter` table of a profile.
+///
+/// This extends [`ReporterConfig`] with settings that can't be set in the user
+/// config.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(super) struct ProfileReporterConfig {
+    #[serde(default)]
+    pub(super) final_summary: Option<FinalSummary>,
+    #[serde(default)]
+    pub(super) redact: Vec<Redaction>,
+}
+
+/// A rule for scrubbing sensitive data out of captured output, set via the
+/// `reporter.redact` list.
+///
+/// Matches of the pattern are replaced before output is displayed, stored in
+/// JUnit reports or kept on disk.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Redaction {
+    #[serde(deserialize_with = "deserialize_redact_pattern")]
+    pattern: Regex,
+    #[serde(default = "default_redact_replace")]
+    replace: String,
+}
+
+impl Redaction {
+    /// The default replacement for matches.
+    pub const DEFAULT_REPLACE: &'static str = "[REDACTED]";
+
+    /// Returns the regular expression to look for.
+    pub fn pattern(&self) -> &Regex {
+        &self.pattern
+    }
+
+    /// Returns what matches are replaced with.
+    ///
+    /// This can refer to capture groups in the pattern, such as `$1` or `$name`.
+    pub fn replace(&self) -> &str {
+        &self.replace
+    }
+}
+
+fn deserialize_redact_pattern<'de, D>(deserializer: D) -> Result<Regex, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    Regex::new(&s).map_err(|error| D::Error::custom(format!("invalid regex `{s}`: {error}")))
+}
+
+fn default_redact_replace() -> String {
+    Redaction::DEFAULT_REPLACE.to_owned()
+}
+
+/// The redaction rules that apply to a profile.
+///
+/// Rules in the default profile always apply, so that a custom profile can't
+/// accidentally turn them off. Rules in custom profiles are applied after them,
+/// in the order the profiles were specified.
+#[derive(Clone, Debug, Default)]
+pub struct Redactions<'cfg> {
+    rules: Vec<&'cfg Redaction>,
+}
+
+impl<'cfg> Redactions<'cfg> {
+    pub(crate) fn new(rules: impl IntoIterator<Item = &'cfg Redaction>) -> Self {
+        Self {
+            rules: rules.into_iter().collect(),
+        }
+    }
+
+    /// Returns true if there are no redaction rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the redaction rules, in the order they're applied.
+    pub fn iter(&self) -> impl Iterator<Item = &'cfg Redaction> + '_ {
+        self.rules.iter().copied()
+    }
+}
+
+/// Extra sections to show in the final summary at the end of a run.
+///
+/// This information is also shown as tests finish, but in large runs it tends
+/// to scroll away.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FinalSummary {
+    /// The number of slowest tests to list, or 0 to not list any.
+    #[serde(default)]
+    pub slowest: usize,
+
+    /// Whether to list tests that were retried, along with how many attempts
+    /// they took.
+    #[serde(default)]
+    pub retries: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_final_summary() {
+        let config_contents = indoc! {r#"
+            [profile.default.reporter]
+            final-summary = { slowest = 5 }
+
+            [profile.ci.reporter]
+            final-summary = { slowest = 10, retries = true }
+
+            [profile.other]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        )
+        .expect("config is valid");
+
+        let final_summary = |name: &str| {
+            config
+                .profile(name)
+                .expect("profile exists")
+                .apply_build_platforms(&build_platforms())
+                .final_summary()
+        };
+        assert_eq!(
+            final_summary("default"),
+            Some(FinalSummary {
+                slowest: 5,
+                retries: false,
+            }),
+        );
+        assert_eq!(
+            final_summary("ci"),
+            Some(FinalSummary {
+                slowest: 10,
+                retries: true,
+            }),
+        );
+        // Profiles that don't set a final summary inherit the default profile's.
+        assert_eq!(final_summary("other"), final_summary("default"));
+    }
+
+    #[test]
+    fn parse_redact() {
+        let config_contents = indoc! {r#"
+            [profile.default.reporter]
+            redact = [{ pattern = 'ghp_[A-Za-z0-9]+' }]
+
+            [profile.ci.reporter]
+            redact = [{ pattern = '(password=)\S+', replace = '${1}***' }]
+
+            [profile.other]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        )
+        .expect("config is valid");
+
+        let redactions = |name: &str| {
+            config
+                .profile(name)
+                .expect("profile exists")
+                .apply_build_platforms(&build_platforms())
+                .redactions()
+                .iter()
+                .map(|redaction| {
+                    (
+                        redaction.pattern().as_str().to_owned(),
+                        redaction.replace().to_owned(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let github_token = (
+            "ghp_[A-Za-z0-9]+".to_owned(),
+            Redaction::DEFAULT_REPLACE.to_owned(),
+        );
+        assert_eq!(redactions("default"), vec![github_token.clone()]);
+        // The default profile's rules are applied first, followed by the
+        // custom profile's.
+        assert_eq!(
+            redactions("ci"),
+            vec![
+                github_token.clone(),
+                ("(password=)\\S+".to_owned(), "${1}***".to_owned()),
+            ],
+        );
+        assert_eq!(redactions("other"), vec![github_token]);
+    }
+
+    #[test]
+    fn parse_invalid_redact() {
+        let config_contents = indoc! {r#"
+            [profile.default.reporter]
+            redact = [{ pattern = '(unclosed' }]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        )
+        .expect_err("invalid regex is rejected");
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::fmt;
+
+/// Resource limits that tests are run with, set via the `rlimits` setting.
+///
+/// Each limit is applied as the soft limit for the test process. This only has an effect on Unix.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceLimits {
+    /// The maximum number of open file descriptors (`RLIMIT_NOFILE`).
+    #[serde(default)]
+    pub nofile: Option<ResourceLimit>,
+
+    /// The maximum size of a core dump, in bytes (`RLIMIT_CORE`).
+    #[serde(default)]
+    pub core: Option<ResourceLimit>,
+
+    /// The maximum size of the main thread's stack, in bytes (`RLIMIT_STACK`).
+    #[serde(default)]
+    pub stack: Option<ResourceLimit>,
+
+    /// The maximum size of the process's virtual memory, in bytes (`RLIMIT_AS`).
+    #[serde(default, rename = "as")]
+    pub address_space: Option<ResourceLimit>,
+}
+
+impl ResourceLimits {
+    /// Returns true if no limits are set.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns the limits that are set, along with their kinds.
+    pub fn iter(&self) -> impl Iterator<Item = (ResourceLimitKind, ResourceLimit)> {
+        [
+            (ResourceLimitKind::Nofile, self.nofile),
+            (ResourceLimitKind::Core, self.core),
+            (ResourceLimitKind::Stack, self.stack),
+            (ResourceLimitKind::AddressSpace, self.address_space),
+        ]
+        .into_iter()
+        .filter_map(|(kind, limit)| limit.map(|limit| (kind, limit)))
+    }
+}
+
+impl fmt::Display for ResourceLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for (kind, limit) in self.iter() {
+            if !first {
+                write!(f, ", ")?;
+            }
+            first = false;
+            write!(f, "{kind}={limit}")?;
+        }
+        if first {
+            write!(f, "(none)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A kind of resource limit.
+///
+/// Part of [`ResourceLimits`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResourceLimitKind {
+    /// `RLIMIT_NOFILE`.
+    Nofile,
+
+    /// `RLIMIT_CORE`.
+    Core,
+
+    /// `RLIMIT_STACK`.
+    Stack,
+
+    /// `RLIMIT_AS`.
+    AddressSpace,
+}
+
+impl fmt::Display for ResourceLimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nofile => write!(f, "nofile"),
+            Self::Core => write!(f, "core"),
+            Self::Stack => write!(f, "stack"),
+            Self::AddressSpace => write!(f, "as"),
+        }
+    }
+}
+
+/// The value of a single resource limit.
+///
+/// Part of [`ResourceLimits`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResourceLimit {
+    /// No limit (`RLIM_INFINITY`).
+    Unlimited,
+
+    /// A fixed limit.
+    Value(u64),
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unlimited => write!(f, "unlimited"),
+            Self::Value(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourceLimit {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl serde::de::Visitor<'_> for V {
+            type Value = ResourceLimit;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a non-negative integer or the string \"unlimited\""
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v == "unlimited" {
+                    Ok(ResourceLimit::Unlimited)
+                } else {
+                    Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    ))
+                }
+            }
+
+            // Note that TOML uses i64, not u64.
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                u64::try_from(v).map(ResourceLimit::Value).map_err(|_| {
+                    serde::de::Error::invalid_value(serde::de::Unexpected::Signed(v), &self)
+                })
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            rlimits = { nofile = 256, core = 0 }
+        "#},
+        Some(ResourceLimits {
+            nofile: Some(ResourceLimit::Value(256)),
+            core: Some(ResourceLimit::Value(0)),
+            ..Default::default()
+        })
+
+        ; "values"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            rlimits = { stack = "unlimited", as = 1073741824 }
+        "#},
+        Some(ResourceLimits {
+            stack: Some(ResourceLimit::Unlimited),
+            address_space: Some(ResourceLimit::Value(1 << 30)),
+            ..Default::default()
+        })
+
+        ; "unlimited and as"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Some(ResourceLimits::default())
+
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            rlimits = { nofile = -1 }
+        "#},
+        None
+
+        ; "negative"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            rlimits = { nproc = 10 }
+        "#},
+        None
+
+        ; "unknown limit"
+    )]
+    fn parse_rlimits(config_contents: &str, expected: Option<ResourceLimits>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        );
+        match expected {
+            None => assert!(config.is_err()),
+            Some(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.rlimits(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn display_rlimits() {
+        let limits = ResourceLimits {
+            nofile: Some(ResourceLimit::Value(256)),
+            address_space: Some(ResourceLimit::Unlimited),
+            ..Default::default()
+        };
+        assert_eq!(limits.to_string(), "nofile=256, as=unlimited");
+        assert_eq!(ResourceLimits::default().to_string(), "(none)");
+    }
+}
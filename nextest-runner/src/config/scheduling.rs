@@ -0,0 +1,220 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::{fmt, num::NonZeroUsize, time::Duration};
+
+/// The order in which tests are started.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchedulingPolicy {
+    /// Start tests in the order they're listed in.
+    #[default]
+    ListOrder,
+
+    /// Start tests that took the longest in previous runs first.
+    ///
+    /// Test durations are recorded in the profile's store directory at the
+    /// end of each run. Tests that have no recorded duration are started
+    /// before all others.
+    LongestFirst,
+}
+
+/// How tests from different packages or binaries are interleaved, set via the
+/// `fairness` setting.
+///
+/// This is applied after [`SchedulingPolicy`] orders tests.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchedulingFairness {
+    /// Don't interleave tests: tests from the same binary are started
+    /// together.
+    #[default]
+    None,
+
+    /// Start tests from each package in turn.
+    RoundRobinPackage,
+
+    /// Start tests from each test binary in turn.
+    RoundRobinBinary,
+}
+
+/// The maximum number of distinct test binaries that can have tests running at
+/// the same time, set via the `max-concurrent-binaries` setting.
+///
+/// This is independent of the number of test threads. Limiting it bounds the
+/// memory and I/O spent on loading large test binaries.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MaxConcurrentBinaries {
+    /// Any number of binaries can have tests running at the same time.
+    #[default]
+    Unlimited,
+
+    /// At most this many binaries can have tests running at the same time.
+    Count(NonZeroUsize),
+}
+
+impl MaxConcurrentBinaries {
+    /// Returns the limit as a count, or `None` if unlimited.
+    pub fn count(self) -> Option<NonZeroUsize> {
+        match self {
+            Self::Unlimited => None,
+            Self::Count(count) => Some(count),
+        }
+    }
+}
+
+impl fmt::Display for MaxConcurrentBinaries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unlimited => write!(f, "unlimited"),
+            Self::Count(count) => write!(f, "{count}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MaxConcurrentBinaries {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl serde::de::Visitor<'_> for V {
+            type Value = MaxConcurrentBinaries;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "a positive integer or the string \"unlimited\"")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v == "unlimited" {
+                    Ok(MaxConcurrentBinaries::Unlimited)
+                } else {
+                    Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    ))
+                }
+            }
+
+            // Note that TOML uses i64, not u64.
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                usize::try_from(v)
+                    .ok()
+                    .and_then(NonZeroUsize::new)
+                    .map(MaxConcurrentBinaries::Count)
+                    .ok_or_else(|| {
+                        serde::de::Error::invalid_value(serde::de::Unexpected::Signed(v), &self)
+                    })
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+/// Settings for reducing the number of tests running at the same time while
+/// the system is under pressure, set via the `adaptive-concurrency` setting.
+///
+/// Adaptive concurrency is enabled if either `max-load` or
+/// `min-available-memory` is set.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct AdaptiveConcurrency {
+    /// The maximum 1-minute load average per logical CPU before concurrency
+    /// is reduced.
+    #[serde(default)]
+    pub max_load: Option<f64>,
+
+    /// The minimum amount of available memory, in bytes, before concurrency
+    /// is reduced.
+    #[serde(default)]
+    pub min_available_memory: Option<u64>,
+
+    /// The number of tests that can always run at the same time, however
+    /// much pressure the system is under. Defaults to 1.
+    #[serde(default)]
+    pub min_threads: Option<NonZeroUsize>,
+
+    /// How often system load and memory are sampled. Defaults to 1 second.
+    #[serde(default, with = "humantime_serde::option")]
+    pub sample_interval: Option<Duration>,
+}
+
+impl AdaptiveConcurrency {
+    /// Returns true if adaptive concurrency is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.max_load.is_some() || self.min_available_memory.is_some()
+    }
+
+    /// Returns the minimum number of tests that can run at the same time.
+    pub fn min_threads(&self) -> usize {
+        self.min_threads.map_or(1, NonZeroUsize::get)
+    }
+
+    /// Returns how often system load and memory are sampled.
+    pub fn sample_interval(&self) -> Duration {
+        self.sample_interval.unwrap_or(Duration::from_secs(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Wrapper {
+        max_concurrent_binaries: MaxConcurrentBinaries,
+    }
+
+    #[test_case(r#"max-concurrent-binaries = "unlimited""#, Some(MaxConcurrentBinaries::Unlimited); "unlimited")]
+    #[test_case("max-concurrent-binaries = 4", Some(MaxConcurrentBinaries::Count(NonZeroUsize::new(4).unwrap())); "count")]
+    #[test_case("max-concurrent-binaries = 0", None; "zero")]
+    #[test_case("max-concurrent-binaries = -1", None; "negative")]
+    #[test_case(r#"max-concurrent-binaries = "num-cpus""#, None; "other string")]
+    fn parse_max_concurrent_binaries(input: &str, expected: Option<MaxConcurrentBinaries>) {
+        let parsed = toml::from_str::<Wrapper>(input).map(|w| w.max_concurrent_binaries);
+        match expected {
+            Some(expected) => assert_eq!(parsed.expect("parse succeeded"), expected),
+            None => {
+                parsed.expect_err("parse failed");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_adaptive_concurrency() {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "kebab-case")]
+        struct Wrapper {
+            adaptive_concurrency: AdaptiveConcurrency,
+        }
+
+        let parsed: Wrapper = toml::from_str("adaptive-concurrency = {}").unwrap();
+        assert!(!parsed.adaptive_concurrency.is_enabled());
+        assert_eq!(parsed.adaptive_concurrency.min_threads(), 1);
+
+        let parsed: Wrapper = toml::from_str(
+            r#"adaptive-concurrency = { max-load = 1.5, min-threads = 2, sample-interval = "500ms" }"#,
+        )
+        .unwrap();
+        let adaptive = parsed.adaptive_concurrency;
+        assert!(adaptive.is_enabled());
+        assert_eq!(adaptive.max_load, Some(1.5));
+        assert_eq!(adaptive.min_available_memory, None);
+        assert_eq!(adaptive.min_threads(), 2);
+        assert_eq!(adaptive.sample_interval(), Duration::from_millis(500));
+
+        toml::from_str::<Wrapper>("adaptive-concurrency = { max-load = 1, unknown = 2 }")
+            .expect_err("unknown fields are rejected");
+    }
+}
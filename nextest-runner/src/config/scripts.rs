@@ -28,7 +28,10 @@ use std::{
     collections::{HashMap, HashSet},
     fmt,
     process::Command,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
@@ -159,6 +162,8 @@ pub(crate) struct SetupScriptCommand {
     command: std::process::Command,
     /// The environment file.
     env_path: Utf8TempPath,
+    /// The file that cleanup commands are written to.
+    cleanup_path: Utf8TempPath,
     /// Double-spawn context.
     double_spawn: Option<DoubleSpawnContext>,
 }
@@ -181,12 +186,19 @@ impl SetupScriptCommand {
             .tempfile()
             .map_err(|error| ChildStartError::TempPath(Arc::new(error)))?
             .into_temp_path();
+        let cleanup_path = camino_tempfile::Builder::new()
+            .prefix("nextest-cleanup")
+            .tempfile()
+            .map_err(|error| ChildStartError::TempPath(Arc::new(error)))?
+            .into_temp_path();
 
         cmd.current_dir(test_list.workspace_root())
             // This environment variable is set to indicate that tests are being run under nextest.
             .env("NEXTEST", "1")
             // Setup scripts can define environment variables which are written out here.
-            .env("NEXTEST_ENV", &env_path);
+            .env("NEXTEST_ENV", &env_path)
+            // Setup scripts can register commands to clean up resources they create here.
+            .env("NEXTEST_CLEANUP", &cleanup_path);
 
         apply_ld_dyld_env(&mut cmd, test_list.updated_dylib_path());
 
@@ -195,6 +207,7 @@ impl SetupScriptCommand {
         Ok(Self {
             command: cmd,
             env_path,
+            cleanup_path,
             double_spawn,
         })
     }
@@ -205,35 +218,93 @@ impl SetupScriptCommand {
         &mut self.command
     }
 
-    pub(crate) fn spawn(self) -> std::io::Result<(tokio::process::Child, Utf8TempPath)> {
+    pub(crate) fn spawn(self) -> std::io::Result<(tokio::process::Child, SetupScriptPaths)> {
         let mut command = tokio::process::Command::from(self.command);
         let res = command.spawn();
         if let Some(ctx) = self.double_spawn {
             ctx.finish();
         }
         let child = res?;
-        Ok((child, self.env_path))
+        Ok((
+            child,
+            SetupScriptPaths {
+                env_path: self.env_path,
+                cleanup_path: self.cleanup_path,
+            },
+        ))
     }
 }
 
+/// Files that a running setup script writes its output to.
+pub(crate) struct SetupScriptPaths {
+    /// The environment file, exposed as `NEXTEST_ENV`.
+    pub(crate) env_path: Utf8TempPath,
+    /// The cleanup file, exposed as `NEXTEST_CLEANUP`.
+    pub(crate) cleanup_path: Utf8TempPath,
+}
+
 /// Data obtained by executing setup scripts. This is used to set up the environment for tests.
-#[derive(Clone, Debug, Default)]
+#[derive(Debug, Default)]
 pub(crate) struct SetupScriptExecuteData<'profile> {
     env_maps: Vec<(SetupScript<'profile>, SetupScriptEnvMap)>,
+    lazy: Vec<LazySetupScript<'profile>>,
+    /// The total number of setup scripts enabled for this run, including lazy ones.
+    total: usize,
+    /// The index of the next lazy script to be started. Setup scripts are run one at a time, so
+    /// this lock is held while a lazy script runs.
+    next_lazy_index: tokio::sync::Mutex<usize>,
 }
 
 impl<'profile> SetupScriptExecuteData<'profile> {
-    pub(crate) fn new() -> Self {
-        Self::default()
+    pub(crate) fn new(total: usize) -> Self {
+        Self {
+            total,
+            ..Self::default()
+        }
     }
 
     pub(crate) fn add_script(&mut self, script: SetupScript<'profile>, env_map: SetupScriptEnvMap) {
         self.env_maps.push((script, env_map));
     }
 
+    /// Adds a lazy setup script, required by `remaining` tests in this run.
+    pub(crate) fn add_lazy_script(&mut self, script: SetupScript<'profile>, remaining: usize) {
+        self.lazy.push(LazySetupScript {
+            script,
+            env_map: tokio::sync::OnceCell::new(),
+            remaining: AtomicUsize::new(remaining),
+        });
+    }
+
+    /// Returns the total number of setup scripts enabled for this run.
+    #[inline]
+    pub(crate) fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the lazy setup scripts enabled for this run.
+    #[inline]
+    pub(crate) fn lazy_scripts(&self) -> &[LazySetupScript<'profile>] {
+        &self.lazy
+    }
+
+    /// Waits for any running lazy setup script to finish, then returns the index for the next one.
+    pub(crate) async fn lock_next_lazy_index(&self) -> tokio::sync::MutexGuard<'_, usize> {
+        self.next_lazy_index.lock().await
+    }
+
     /// Applies the data from setup scripts to the given test instance.
     pub(crate) fn apply(&self, test: &TestQuery<'_>, cx: &EvalContext<'_>, command: &mut Command) {
-        for (script, env_map) in &self.env_maps {
+        let lazy_env_maps = self
+            .lazy
+            .iter()
+            .filter_map(|lazy| Some((&lazy.script, lazy.env_map.get()?.as_ref()?)));
+        for (script, env_map) in self
+            .env_maps
+            .iter()
+            .map(|(script, env_map)| (script, env_map))
+            .chain(lazy_env_maps)
+        {
             if script.is_enabled(test, cx) {
                 for (key, value) in env_map.env_map.iter() {
                     command.env(key, value);
@@ -243,6 +314,28 @@ impl<'profile> SetupScriptExecuteData<'profile> {
     }
 }
 
+/// A setup script with `lazy = true`, started just before the first test that requires it.
+#[derive(Debug)]
+pub(crate) struct LazySetupScript<'profile> {
+    pub(crate) script: SetupScript<'profile>,
+
+    /// The environment map written out by the script, set once the script has finished. This is
+    /// `None` if the script failed or was never started because the run was cancelled.
+    pub(crate) env_map: tokio::sync::OnceCell<Option<SetupScriptEnvMap>>,
+
+    /// The number of tests requiring this script that haven't finished yet.
+    remaining: AtomicUsize,
+}
+
+impl LazySetupScript<'_> {
+    /// Records that a test requiring this script has finished.
+    ///
+    /// Returns true if this was the last such test.
+    pub(crate) fn test_finished(&self) -> bool {
+        self.remaining.fetch_sub(1, Ordering::AcqRel) == 1
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct CompiledProfileScripts<State> {
     pub(super) setup: Vec<ScriptId>,
@@ -450,6 +543,18 @@ pub struct ScriptConfig {
     #[serde(default)]
     pub capture_stderr: bool,
 
+    /// An optional timeout for each cleanup command registered by this script.
+    #[serde(default, with = "humantime_serde::option")]
+    pub cleanup_timeout: Option<Duration>,
+
+    /// Whether to start this script just before the first test that requires it, rather than at
+    /// the beginning of the run.
+    ///
+    /// Lazy scripts have their cleanup commands run as soon as the last test requiring them
+    /// finishes.
+    #[serde(default)]
+    pub lazy: bool,
+
     /// JUnit configuration for this script.
     #[serde(default)]
     pub junit: ScriptJunitConfig,
@@ -540,7 +645,9 @@ where
     deserializer.deserialize_any(ScriptIdVisitor)
 }
 
-fn deserialize_command<'de, D>(deserializer: D) -> Result<(String, Vec<String>), D::Error>
+pub(super) fn deserialize_command<'de, D>(
+    deserializer: D,
+) -> Result<(String, Vec<String>), D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -627,6 +734,7 @@ mod tests {
             leak-timeout = "1s"
             capture-stdout = true
             capture-stderr = true
+            lazy = true
         "#
         };
 
@@ -692,6 +800,10 @@ mod tests {
             "foo",
             "first script should be foo"
         );
+        assert!(
+            !scripts.enabled_scripts.get_index(0).unwrap().1.config.lazy,
+            "scripts aren't lazy by default"
+        );
         assert_eq!(
             scripts.enabled_scripts.get_index(1).unwrap().0.as_str(),
             "bar",
@@ -718,6 +830,10 @@ mod tests {
             "baz",
             "first script should be baz"
         );
+        assert!(
+            scripts.enabled_scripts.get_index(0).unwrap().1.config.lazy,
+            "baz should be lazy"
+        );
 
         // This query matches the baz, foo and tool scripts (but note the order).
         let query = TestQuery {
@@ -779,6 +895,16 @@ mod tests {
 
         ; "slow timeout is not a duration"
     )]
+    #[test_case(
+        indoc! {r#"
+            [script.foo]
+            command = "my-command"
+            lazy = "maybe"
+        "#},
+        r#"invalid type: string "maybe", expected a boolean"#
+
+        ; "lazy is not a boolean"
+    )]
     #[test_case(
         indoc! {r#"
             [script.'@tool:foo']
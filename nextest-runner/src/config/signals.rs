@@ -0,0 +1,147 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::fmt;
+
+/// How signals received by nextest during a test run are handled, set via
+/// `[profile.<name>.signals]`.
+///
+/// Signals not listed in the table keep their default handling. This only has an effect on Unix;
+/// on Windows, only `int` is consulted, and only to determine whether Ctrl-C is ignored.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct SignalConfig {
+    /// How `SIGINT` (and Ctrl-C on Windows) is handled.
+    pub int: SignalAction,
+
+    /// How `SIGTERM` is handled.
+    pub term: SignalAction,
+
+    /// How `SIGHUP` is handled.
+    pub hup: SignalAction,
+
+    /// How `SIGUSR1` is handled.
+    pub usr1: SignalAction,
+
+    /// How `SIGUSR2` is handled.
+    pub usr2: SignalAction,
+}
+
+impl Default for SignalConfig {
+    fn default() -> Self {
+        Self {
+            int: SignalAction::Cancel,
+            term: SignalAction::Cancel,
+            hup: SignalAction::Cancel,
+            usr1: SignalAction::Info,
+            usr2: SignalAction::Cancel,
+        }
+    }
+}
+
+/// The action taken when nextest receives a signal.
+///
+/// Part of [`SignalConfig`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignalAction {
+    /// Cancel the run gracefully, terminating running tests. A second signal kills running tests
+    /// immediately.
+    Cancel,
+
+    /// Send the signal to the process groups of all running tests and setup scripts, and carry
+    /// on with the run.
+    Forward,
+
+    /// Print information about running tests.
+    Info,
+
+    /// Ignore the signal.
+    Ignore,
+}
+
+impl fmt::Display for SignalAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancel => write!(f, "cancel"),
+            Self::Forward => write!(f, "forward"),
+            Self::Info => write!(f, "info"),
+            Self::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Some(SignalConfig::default())
+
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom.signals]
+            usr1 = "forward"
+            hup = "ignore"
+        "#},
+        Some(SignalConfig {
+            usr1: SignalAction::Forward,
+            hup: SignalAction::Ignore,
+            ..SignalConfig::default()
+        })
+
+        ; "partial table"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom.signals]
+            usr1 = "dump"
+        "#},
+        None
+
+        ; "unknown action"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom.signals]
+            quit = "forward"
+        "#},
+        None
+
+        ; "unknown signal"
+    )]
+    fn parse_signals(config_contents: &str, expected: Option<SignalConfig>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        );
+        match expected {
+            None => assert!(config.is_err()),
+            Some(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.signals(), expected);
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::{MaybeTargetSpec, PlatformStrings};
+use crate::platform::BuildPlatforms;
 use serde::{de::IntoDeserializer, Deserialize};
 use std::{fmt, num::NonZeroUsize, time::Duration};
 
@@ -24,6 +26,87 @@ impl SlowTimeout {
         terminate_after: None,
         grace_period: Duration::from_secs(10),
     };
+
+    /// Returns a new `SlowTimeout` with the period scaled by `multiplier`.
+    ///
+    /// The scaled period is capped at the period of [`Self::VERY_LARGE`].
+    pub(crate) fn scale(self, multiplier: f64) -> Self {
+        let period = Duration::try_from_secs_f64(self.period.as_secs_f64() * multiplier)
+            .map_or(Self::VERY_LARGE.period, |period| {
+                period.min(Self::VERY_LARGE.period)
+            });
+        Self { period, ..self }
+    }
+}
+
+/// A multiplier for slow timeouts, applied when the platform and Cargo profile match.
+///
+/// Read from the `slow-timeout-multipliers` profile key.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "DeserializedSlowTimeoutMultiplier")]
+pub(crate) struct SlowTimeoutMultiplier {
+    host_spec: MaybeTargetSpec,
+    target_spec: MaybeTargetSpec,
+    cargo_profile: Option<String>,
+    multiplier: f64,
+}
+
+impl SlowTimeoutMultiplier {
+    /// Returns true if this multiplier applies to the given platforms.
+    pub(super) fn matches_platforms(&self, build_platforms: &BuildPlatforms) -> bool {
+        let target_platform = build_platforms
+            .target
+            .as_ref()
+            .map_or(&build_platforms.host.platform, |target| {
+                &target.triple.platform
+            });
+        self.host_spec.eval(&build_platforms.host.platform)
+            && self.target_spec.eval(target_platform)
+    }
+
+    /// Returns the multiplier if it applies to the given Cargo profile.
+    ///
+    /// Multipliers that specify a Cargo profile never apply if the Cargo profile isn't known.
+    pub(super) fn multiplier_for(&self, cargo_profile: Option<&str>) -> Option<f64> {
+        match &self.cargo_profile {
+            Some(expected) => (cargo_profile == Some(expected.as_str())).then_some(self.multiplier),
+            None => Some(self.multiplier),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DeserializedSlowTimeoutMultiplier {
+    #[serde(default)]
+    platform: PlatformStrings,
+    #[serde(default)]
+    cargo_profile: Option<String>,
+    multiplier: f64,
+}
+
+impl TryFrom<DeserializedSlowTimeoutMultiplier> for SlowTimeoutMultiplier {
+    type Error = String;
+
+    fn try_from(value: DeserializedSlowTimeoutMultiplier) -> Result<Self, Self::Error> {
+        if !value.multiplier.is_finite() || value.multiplier <= 0.0 {
+            return Err(format!(
+                "multiplier must be a positive number, found {}",
+                value.multiplier
+            ));
+        }
+        let host_spec = MaybeTargetSpec::new(value.platform.host.as_deref())
+            .map_err(|error| format!("invalid host platform: {error}"))?;
+        let target_spec = MaybeTargetSpec::new(value.platform.target.as_deref())
+            .map_err(|error| format!("invalid target platform: {error}"))?;
+
+        Ok(Self {
+            host_spec,
+            target_spec,
+            cargo_profile: value.cargo_profile,
+            multiplier: value.multiplier,
+        })
+    }
 }
 
 fn default_grace_period() -> Duration {
@@ -227,4 +310,101 @@ mod tests {
             }
         }
     }
+    #[test]
+    fn slow_timeout_scale_saturates() {
+        let slow_timeout = SlowTimeout {
+            period: Duration::from_secs(60),
+            terminate_after: None,
+            grace_period: Duration::from_secs(10),
+        };
+        assert_eq!(slow_timeout.scale(2.5).period, Duration::from_secs(150));
+        for multiplier in [1e30, f64::MAX] {
+            assert_eq!(
+                slow_timeout.scale(multiplier).period,
+                SlowTimeout::VERY_LARGE.period,
+                "multiplier {multiplier} saturates",
+            );
+        }
+    }
+
+    #[test_case(
+        None,
+        3.0
+
+        ; "no cargo profile"
+    )]
+    #[test_case(
+        Some("release"),
+        1.5
+
+        ; "release profile"
+    )]
+    #[test_case(
+        Some("dev"),
+        3.0
+
+        ; "dev profile"
+    )]
+    fn slow_timeout_multipliers(cargo_profile: Option<&str>, expected: f64) {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            slow-timeout = "60s"
+            slow-timeout-multipliers = [
+                { cargo-profile = "release", multiplier = 0.5 },
+                { platform = { host = "cfg(target_os = \"linux\")" }, multiplier = 3 },
+                { platform = "cfg(windows)", multiplier = 10 },
+            ]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config file should parse");
+
+        let mut profile = nextest_config
+            .profile("default")
+            .expect("default profile should exist")
+            .apply_build_platforms(&build_platforms());
+        profile.set_cargo_profile(cargo_profile.map(|p| p.to_owned()));
+
+        assert_eq!(profile.slow_timeout_multiplier(), expected);
+        assert_eq!(
+            profile
+                .slow_timeout()
+                .scale(profile.slow_timeout_multiplier())
+                .period,
+            Duration::from_secs_f64(60.0 * expected),
+        );
+    }
+
+    #[test]
+    fn slow_timeout_multiplier_invalid() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            slow-timeout-multipliers = [{ multiplier = 0 }]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let err = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect_err("zero multiplier should fail");
+
+        let err_str = format!("{err:?}");
+        assert!(
+            err_str.contains("multiplier must be a positive number"),
+            "expected error string not found: {err_str}",
+        );
+    }
 }
@@ -0,0 +1,155 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use camino::Utf8PathBuf;
+use serde::Deserialize;
+use std::fmt;
+
+/// What a test's standard input is connected to, set via the `stdin` setting.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum TestStdin {
+    /// Standard input is closed: reads return end-of-file immediately.
+    #[default]
+    Null,
+
+    /// Standard input is inherited from nextest.
+    Inherit,
+
+    /// Standard input is read from a file, relative to the workspace root.
+    File(Utf8PathBuf),
+}
+
+impl<'de> Deserialize<'de> for TestStdin {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de2> serde::de::Visitor<'de2> for V {
+            type Value = TestStdin;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "the string \"null\" or \"inherit\", or a table with a `file` key"
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                match v {
+                    "null" => Ok(TestStdin::Null),
+                    "inherit" => Ok(TestStdin::Inherit),
+                    _ => Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    )),
+                }
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de2>,
+            {
+                #[derive(Deserialize)]
+                #[serde(deny_unknown_fields)]
+                struct FileStdin {
+                    file: Utf8PathBuf,
+                }
+
+                let FileStdin { file } =
+                    FileStdin::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+                if file.as_str().is_empty() {
+                    return Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(""),
+                        &"a non-empty file path",
+                    ));
+                }
+                Ok(TestStdin::File(file))
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Some(TestStdin::Null)
+
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            stdin = "inherit"
+        "#},
+        Some(TestStdin::Inherit)
+
+        ; "inherit"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            stdin = { file = "fixtures/input.txt" }
+        "#},
+        Some(TestStdin::File("fixtures/input.txt".into()))
+
+        ; "file"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            stdin = "pipe"
+        "#},
+        None
+
+        ; "unknown string"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            stdin = { path = "input.txt" }
+        "#},
+        None
+
+        ; "unknown key"
+    )]
+    fn parse_stdin(config_contents: &str, expected: Option<TestStdin>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            [],
+            &Default::default(),
+        );
+        match expected {
+            None => assert!(config.is_err()),
+            Some(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.stdin(), &expected);
+            }
+        }
+    }
+}
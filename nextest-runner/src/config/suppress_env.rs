@@ -0,0 +1,187 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{de::Error, Deserialize};
+use std::fmt;
+
+/// Environment variables that nextest sets for tests, along with representative
+/// names for variable families.
+///
+/// Kept in sync with the list at <https://nexte.st/docs/configuration/env-vars>.
+/// Used to warn about [`EnvVarPattern`]s that look like they're meant for
+/// nextest's variables, but can't match any of them.
+const NEXTEST_SET_ENV_VARS: &[&str] = &[
+    "NEXTEST",
+    "NEXTEST_RUN_ID",
+    "NEXTEST_TEST_ID",
+    "NEXTEST_ATTEMPT",
+    "NEXTEST_EXECUTION_MODE",
+    "NEXTEST_TMPDIR",
+    "NEXTEST_CANCEL_ACK_PATH",
+    "NEXTEST_BIN_EXE_",
+    "NEXTEST_LD_LIBRARY_PATH",
+    "NEXTEST_DYLD_FALLBACK_LIBRARY_PATH",
+    "CARGO",
+    "CARGO_MANIFEST_DIR",
+    "CARGO_PKG_VERSION",
+    "CARGO_PKG_VERSION_MAJOR",
+    "CARGO_PKG_VERSION_MINOR",
+    "CARGO_PKG_VERSION_PATCH",
+    "CARGO_PKG_VERSION_PRE",
+    "CARGO_PKG_AUTHORS",
+    "CARGO_PKG_NAME",
+    "CARGO_PKG_DESCRIPTION",
+    "CARGO_PKG_HOMEPAGE",
+    "CARGO_PKG_REPOSITORY",
+    "CARGO_PKG_LICENSE",
+    "CARGO_PKG_LICENSE_FILE",
+    "OUT_DIR",
+    "LD_LIBRARY_PATH",
+    "DYLD_FALLBACK_LIBRARY_PATH",
+    "PATH",
+    "HOME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+];
+
+/// A pattern matching the names of environment variables, used by the
+/// `suppress-env` setting.
+///
+/// `*` matches any sequence of characters, including an empty one. All other
+/// characters must be ASCII letters, digits, `_` or `-` (`-` is allowed since
+/// it can appear in `NEXTEST_BIN_EXE_<name>`).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct EnvVarPattern(String);
+
+impl EnvVarPattern {
+    /// Parses a new pattern.
+    pub fn new(pattern: impl Into<String>) -> Result<Self, String> {
+        let pattern = pattern.into();
+        if pattern.is_empty() {
+            return Err("environment variable pattern must not be empty".to_owned());
+        }
+        if let Some(c) = pattern
+            .chars()
+            .find(|&c| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '*')))
+        {
+            return Err(format!(
+                "invalid character {c:?} in environment variable pattern `{pattern}` \
+                 (expected letters, digits, `_`, `-` or `*`)"
+            ));
+        }
+        Ok(Self(pattern))
+    }
+
+    /// Returns the pattern as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns true if `name` matches this pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        let mut parts = self.0.split('*');
+        // split always returns at least one element.
+        let first = parts.next().expect("at least one part");
+        let Some(mut rest) = name.strip_prefix(first) else {
+            return false;
+        };
+
+        let mut parts = parts.peekable();
+        if parts.peek().is_none() {
+            // No wildcards.
+            return rest.is_empty();
+        }
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                // The last part must match the end of the name.
+                return rest.ends_with(part);
+            }
+            match rest.find(part) {
+                Some(index) => rest = &rest[index + part.len()..],
+                None => return false,
+            }
+        }
+        unreachable!("the loop returns on the last part")
+    }
+
+    /// Returns true if this pattern is in the `NEXTEST` or `CARGO` namespace,
+    /// but doesn't match any variable that nextest sets for tests. Such
+    /// patterns are likely to be typos.
+    pub(super) fn is_unknown_nextest_var(&self) -> bool {
+        if !(self.0.starts_with("NEXTEST") || self.0.starts_with("CARGO")) {
+            return false;
+        }
+        let is_bin_exe =
+            self.0.starts_with("NEXTEST_BIN_EXE_") && self.0.len() > "NEXTEST_BIN_EXE_".len();
+        !is_bin_exe && !NEXTEST_SET_ENV_VARS.iter().any(|var| self.matches(var))
+    }
+}
+
+impl fmt::Display for EnvVarPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for EnvVarPattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Self::new(s).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("NEXTEST", "NEXTEST", true; "exact")]
+    #[test_case("NEXTEST", "NEXTEST_RUN_ID", false; "exact does not match prefix")]
+    #[test_case("NEXTEST_LD_*", "NEXTEST_LD_LIBRARY_PATH", true; "suffix wildcard")]
+    #[test_case("NEXTEST_LD_*", "NEXTEST_LD_", true; "wildcard matches empty")]
+    #[test_case("NEXTEST_LD_*", "NEXTEST_DYLD_LIBRARY_PATH", false; "suffix wildcard mismatch")]
+    #[test_case("*_LIBRARY_PATH", "NEXTEST_DYLD_FALLBACK_LIBRARY_PATH", true; "prefix wildcard")]
+    #[test_case("NEXTEST_*_PATH", "NEXTEST_LD_LIBRARY_PATH", true; "middle wildcard")]
+    #[test_case("NEXTEST_*_PATH", "NEXTEST_PATH", false; "middle wildcard needs both ends")]
+    #[test_case("*A*A*", "AA", true; "multiple wildcards")]
+    #[test_case("*A*A*", "BAB", false; "multiple wildcards mismatch")]
+    #[test_case("*", "ANYTHING", true; "star")]
+    fn test_env_var_pattern_matches(pattern: &str, name: &str, matches: bool) {
+        let pattern = EnvVarPattern::new(pattern).expect("pattern is valid");
+        assert_eq!(pattern.matches(name), matches);
+    }
+
+    #[test]
+    fn test_env_var_pattern_invalid() {
+        EnvVarPattern::new("").expect_err("empty pattern is invalid");
+        EnvVarPattern::new("NEXTEST FOO").expect_err("spaces are invalid");
+        EnvVarPattern::new("NEXTEST_?").expect_err("question marks are invalid");
+    }
+
+    #[test]
+    fn test_env_var_pattern_is_unknown_nextest_var() {
+        for (pattern, expected) in [
+            ("NEXTEST_LD_*", false),
+            ("NEXTEST_EXECUTION_MODE", false),
+            ("NEXTEST_TMPDIR", false),
+            ("NEXTEST_CANCEL_ACK_PATH", false),
+            ("NEXTEST_BIN_EXE_my-binary", false),
+            ("CARGO_PKG_*", false),
+            ("NEXTEST_EXECUTION_MOD", true),
+            ("CARGO_PKG_NAM", true),
+            // Not in the nextest or Cargo namespaces.
+            ("RUST_LOG", false),
+        ] {
+            let pattern = EnvVarPattern(pattern.to_owned());
+            assert_eq!(
+                pattern.is_unknown_nextest_var(),
+                expected,
+                "for pattern {pattern}"
+            );
+        }
+    }
+}
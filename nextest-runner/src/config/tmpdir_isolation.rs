@@ -0,0 +1,32 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Per-attempt temporary directories, set via the `isolate-tmpdir` setting.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TmpdirIsolation {
+    /// Tests share the temporary directory that nextest is run with.
+    #[default]
+    None,
+
+    /// Each test attempt gets its own temporary directory, which is deleted after the attempt
+    /// finishes.
+    Isolated,
+
+    /// Like [`Self::Isolated`], except that the directory is kept if the attempt fails.
+    KeepOnFailure,
+}
+
+impl TmpdirIsolation {
+    /// Returns true if each test attempt gets its own temporary directory.
+    pub fn is_isolated(self) -> bool {
+        matches!(self, Self::Isolated | Self::KeepOnFailure)
+    }
+
+    /// Returns true if the temporary directory is kept when the attempt fails.
+    pub fn keeps_on_failure(self) -> bool {
+        matches!(self, Self::KeepOnFailure)
+    }
+}
@@ -204,7 +204,8 @@ mod tests {
                 recommended: NextestVersionReq::Version {
                     version: "0.9.52".parse().unwrap(),
                     tool: Some("tool1".to_owned())
-                }
+                },
+                pin: None,
             },
         );
 
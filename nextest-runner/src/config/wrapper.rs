@@ -0,0 +1,66 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// A preset for running tests under an error-checking tool, set via the `wrapper` setting.
+///
+/// Besides wrapping the test command (where applicable), nextest scans the output of failing tests
+/// for the tool's reports, and classifies them as memory errors rather than ordinary failures.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WrapperPreset {
+    /// Run tests directly.
+    None,
+
+    /// Run tests under Valgrind's memcheck tool.
+    Valgrind,
+
+    /// Tests are built with a sanitizer, such as AddressSanitizer or ThreadSanitizer.
+    ///
+    /// Sanitizers are compiled into the test binary, so the command isn't wrapped. Only the output
+    /// of failing tests is scanned for sanitizer reports.
+    Sanitizer,
+}
+
+impl WrapperPreset {
+    /// The exit code Valgrind is configured to use if it finds errors.
+    ///
+    /// This is chosen to be distinct from the exit codes used by libtest (101) and by sanitizers
+    /// (1 or 66 by default).
+    pub(crate) const VALGRIND_ERROR_EXIT_CODE: i32 = 97;
+
+    /// Returns the program and arguments to insert before the test binary, if any.
+    pub(crate) fn command_prefix(self) -> &'static [&'static str] {
+        match self {
+            Self::None | Self::Sanitizer => &[],
+            Self::Valgrind => &[
+                "valgrind",
+                "--quiet",
+                // Must match VALGRIND_ERROR_EXIT_CODE.
+                "--error-exitcode=97",
+                "--leak-check=full",
+                "--errors-for-leak-kinds=definite",
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valgrind_error_exit_code() {
+        let arg = format!(
+            "--error-exitcode={}",
+            WrapperPreset::VALGRIND_ERROR_EXIT_CODE
+        );
+        assert!(
+            WrapperPreset::Valgrind
+                .command_prefix()
+                .contains(&arg.as_str()),
+            "valgrind arguments include {arg}"
+        );
+    }
+}
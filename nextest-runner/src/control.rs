@@ -0,0 +1,489 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A local control socket for test runs.
+//!
+//! Similar to signal and input handling, the control socket is read by the runner and used to
+//! control the test run. It's meant for external orchestrators that need to query or stop a run
+//! cleanly without sending signals, which is particularly useful on Windows, where signal semantics
+//! are limited.
+//!
+//! If enabled, nextest listens on a Unix domain socket (on Unix) or a named pipe (on Windows) for
+//! the duration of the run. Clients send commands, one per line, and receive a single line of JSON
+//! in response to each command. The supported commands are:
+//!
+//! * `status`: return the current state of the run.
+//! * `cancel`: cancel the run, as if it were sent a termination signal.
+//! * `cancel-test <binary-id> <test-name>`: terminate a single running test.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::{mpsc, oneshot},
+    task::{JoinHandle, JoinSet},
+};
+use tracing::{debug, warn};
+
+/// The control socket for a test run.
+#[derive(Debug)]
+pub(crate) struct ControlServer {
+    imp: Option<ControlServerImpl>,
+}
+
+#[derive(Debug)]
+struct ControlServerImpl {
+    path: Utf8PathBuf,
+    rx: mpsc::UnboundedReceiver<ControlRequest>,
+    accept_task: JoinHandle<()>,
+}
+
+impl ControlServer {
+    /// Starts listening on the given path.
+    ///
+    /// This must be called from within a Tokio runtime.
+    pub(crate) fn bind(path: &Utf8Path) -> std::io::Result<Self> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let accept_task = imp::spawn_accept_loop(path, tx)?;
+        debug!(%path, "listening for control commands");
+        Ok(Self {
+            imp: Some(ControlServerImpl {
+                path: path.to_owned(),
+                rx,
+                accept_task,
+            }),
+        })
+    }
+
+    /// Creates a new `ControlServer` that doesn't listen for commands.
+    pub(crate) fn noop() -> Self {
+        Self { imp: None }
+    }
+
+    /// Receives a request from a client, or `None` if the control socket isn't enabled.
+    ///
+    /// This is a cancel-safe operation.
+    pub(crate) async fn recv(&mut self) -> Option<ControlRequest> {
+        self.imp.as_mut()?.rx.recv().await
+    }
+}
+
+impl Drop for ControlServerImpl {
+    fn drop(&mut self) {
+        // Aborting the accept loop also drops the tasks serving connected clients.
+        self.accept_task.abort();
+        imp::cleanup(&self.path);
+    }
+}
+
+/// A command received over the control socket, along with a way to respond to it.
+#[derive(Debug)]
+pub(crate) struct ControlRequest {
+    pub(crate) command: ControlCommand,
+    pub(crate) responder: oneshot::Sender<ControlResponse>,
+}
+
+/// A command sent over the control socket.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ControlCommand {
+    /// Return the current state of the run.
+    Status,
+
+    /// Cancel the run.
+    Cancel,
+
+    /// Terminate a single running test.
+    CancelTest {
+        binary_id: String,
+        test_name: String,
+    },
+}
+
+impl ControlCommand {
+    fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (command, rest) = match line.split_once(char::is_whitespace) {
+            Some((command, rest)) => (command, rest.trim()),
+            None => (line, ""),
+        };
+
+        match (command, rest) {
+            ("status", "") => Ok(Self::Status),
+            ("cancel", "") => Ok(Self::Cancel),
+            ("cancel-test", rest) => match rest.split_once(char::is_whitespace) {
+                Some((binary_id, test_name)) if !test_name.trim().is_empty() => {
+                    Ok(Self::CancelTest {
+                        binary_id: binary_id.to_owned(),
+                        test_name: test_name.trim().to_owned(),
+                    })
+                }
+                _ => Err("usage: cancel-test <binary-id> <test-name>".to_owned()),
+            },
+            ("status" | "cancel", _) => Err(format!("`{command}` doesn't take arguments")),
+            _ => Err(format!(
+                "unknown command `{command}` (expected `status`, `cancel` or `cancel-test`)"
+            )),
+        }
+    }
+}
+
+/// The response to a [`ControlCommand`], serialized as a single line of JSON.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "result", rename_all = "kebab-case")]
+pub(crate) enum ControlResponse {
+    /// The command was carried out.
+    Ok {
+        /// A description of what was done.
+        message: String,
+    },
+
+    /// The current state of the run, in response to `status`.
+    #[serde(rename_all = "kebab-case")]
+    Status {
+        run_id: String,
+        initial_run_count: usize,
+        finished_count: usize,
+        running: Vec<String>,
+        cancel_reason: Option<&'static str>,
+    },
+
+    /// The command couldn't be carried out.
+    Error {
+        /// A description of the error.
+        message: String,
+    },
+}
+
+impl ControlResponse {
+    pub(crate) fn ok(message: impl Into<String>) -> Self {
+        Self::Ok {
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn error(message: impl Into<String>) -> Self {
+        Self::Error {
+            message: message.into(),
+        }
+    }
+}
+
+async fn serve_connection<S>(stream: S, tx: mpsc::UnboundedSender<ControlRequest>)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(error) => {
+                debug!("error reading from control client: {error}");
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match ControlCommand::parse(&line) {
+            Ok(command) => {
+                let (responder, response_rx) = oneshot::channel();
+                if tx.send(ControlRequest { command, responder }).is_ok() {
+                    response_rx
+                        .await
+                        .unwrap_or_else(|_| ControlResponse::error("the run is finishing"))
+                } else {
+                    ControlResponse::error("the run is finishing")
+                }
+            }
+            Err(message) => ControlResponse::error(message),
+        };
+
+        let mut json =
+            serde_json::to_string(&response).expect("serializing control response succeeds");
+        json.push('\n');
+        if let Err(error) = writer.write_all(json.as_bytes()).await {
+            debug!("error writing to control client: {error}");
+            break;
+        }
+    }
+}
+
+/// Tracks consecutive errors accepting connections, so that a persistent error doesn't make the
+/// accept loop spin.
+#[derive(Debug, Default)]
+struct AcceptErrors {
+    consecutive: u32,
+}
+
+impl AcceptErrors {
+    /// The number of consecutive errors after which nextest stops accepting connections.
+    const MAX_CONSECUTIVE: u32 = 10;
+
+    /// Records an error, returning how long to wait before accepting again, or `None` if
+    /// accepting should stop.
+    fn record(&mut self) -> Option<Duration> {
+        self.consecutive += 1;
+        if self.consecutive < Self::MAX_CONSECUTIVE {
+            Some(Duration::from_millis(10 << self.consecutive))
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self) {
+        self.consecutive = 0;
+    }
+}
+
+/// Handles an error accepting a connection, returning false if accepting should stop.
+async fn on_accept_error(errors: &mut AcceptErrors, error: std::io::Error) -> bool {
+    match errors.record() {
+        Some(delay) => {
+            warn!("error accepting control connection: {error}");
+            tokio::time::sleep(delay).await;
+            true
+        }
+        None => {
+            warn!(
+                "error accepting control connection, no longer accepting control connections: \
+                 {error}"
+            );
+            false
+        }
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::*;
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use tokio::net::UnixListener;
+
+    pub(super) fn spawn_accept_loop(
+        path: &Utf8Path,
+        tx: mpsc::UnboundedSender<ControlRequest>,
+    ) -> std::io::Result<JoinHandle<()>> {
+        let listener = bind_private(path)?;
+        Ok(tokio::spawn(async move {
+            let mut connections = JoinSet::new();
+            let mut errors = AcceptErrors::default();
+            loop {
+                tokio::select! {
+                    res = listener.accept() => match res {
+                        Ok((stream, _)) => {
+                            errors.reset();
+                            connections.spawn(serve_connection(stream, tx.clone()));
+                        }
+                        Err(error) => {
+                            if !on_accept_error(&mut errors, error).await {
+                                break;
+                            }
+                        }
+                    },
+                    // Reap finished connections.
+                    Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                }
+            }
+            // Keep serving existing connections.
+            while connections.join_next().await.is_some() {}
+        }))
+    }
+
+    /// Binds a socket at `path` that only the current user can connect to.
+    ///
+    /// The socket is bound within a directory that only the current user can access, made
+    /// readable and writable only by the current user, and then linked into place. This way,
+    /// there's no window in which other users can connect to it.
+    fn bind_private(path: &Utf8Path) -> std::io::Result<UnixListener> {
+        let file_name = path.file_name().unwrap_or("control.sock");
+        let temp_dir = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+        std::fs::DirBuilder::new().mode(0o700).create(&temp_dir)?;
+        let temp_path = temp_dir.join(file_name);
+
+        let res = UnixListener::bind(&temp_path).and_then(|listener| {
+            std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o600))?;
+            // Linking fails if the path already exists, which avoids clobbering an existing
+            // file.
+            std::fs::hard_link(&temp_path, path)?;
+            Ok(listener)
+        });
+
+        // The listener keeps working through the link at path.
+        if let Err(error) = std::fs::remove_dir_all(&temp_dir) {
+            debug!(%temp_dir, "error removing temporary control socket directory: {error}");
+        }
+        res
+    }
+
+    pub(super) fn cleanup(path: &Utf8Path) {
+        if let Err(error) = std::fs::remove_file(path) {
+            debug!(%path, "error removing control socket: {error}");
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::*;
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    pub(super) fn spawn_accept_loop(
+        path: &Utf8Path,
+        tx: mpsc::UnboundedSender<ControlRequest>,
+    ) -> std::io::Result<JoinHandle<()>> {
+        // Creating the first instance fails if a pipe with this name already exists.
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(path)?;
+        let path = path.to_owned();
+        Ok(tokio::spawn(async move {
+            let mut connections = JoinSet::new();
+            let mut errors = AcceptErrors::default();
+            loop {
+                tokio::select! {
+                    res = server.connect() => {
+                        if let Err(error) = res {
+                            if on_accept_error(&mut errors, error).await {
+                                continue;
+                            }
+                            break;
+                        }
+                        errors.reset();
+                        // Create the next instance before handing off this one, so clients
+                        // can always connect.
+                        let next = match ServerOptions::new().create(&path) {
+                            Ok(next) => next,
+                            Err(error) => {
+                                warn!("error creating control pipe instance, no longer accepting \
+                                       control connections: {error}");
+                                connections.spawn(serve_connection(server, tx.clone()));
+                                break;
+                            }
+                        };
+                        let connected = std::mem::replace(&mut server, next);
+                        connections.spawn(serve_connection(connected, tx.clone()));
+                    }
+                    // Reap finished connections.
+                    Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                }
+            }
+            // Keep serving existing connections.
+            while connections.join_next().await.is_some() {}
+        }))
+    }
+
+    pub(super) fn cleanup(_path: &Utf8Path) {
+        // Named pipes are removed once all handles to them are closed.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("status", Ok(ControlCommand::Status); "status")]
+    #[test_case("  cancel\r", Ok(ControlCommand::Cancel); "cancel with whitespace")]
+    #[test_case(
+        "cancel-test my-crate::bin/my-bin tests::foo",
+        Ok(ControlCommand::CancelTest {
+            binary_id: "my-crate::bin/my-bin".to_owned(),
+            test_name: "tests::foo".to_owned(),
+        });
+        "cancel-test"
+    )]
+    #[test_case("cancel-test my-crate", Err(()); "cancel-test missing test name")]
+    #[test_case("cancel now", Err(()); "cancel with argument")]
+    #[test_case("stop", Err(()); "unknown command")]
+    fn parse_command(input: &str, expected: Result<ControlCommand, ()>) {
+        assert_eq!(ControlCommand::parse(input).map_err(|_| ()), expected);
+    }
+
+    #[test]
+    fn serialize_response() {
+        let response = ControlResponse::Status {
+            run_id: "run".to_owned(),
+            initial_run_count: 10,
+            finished_count: 4,
+            running: vec!["my-crate tests::foo".to_owned()],
+            cancel_reason: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"result":"status","run-id":"run","initial-run-count":10,"finished-count":4,"running":["my-crate tests::foo"],"cancel-reason":null}"#,
+        );
+        assert_eq!(
+            serde_json::to_string(&ControlResponse::error("oops")).unwrap(),
+            r#"{"result":"error","message":"oops"}"#,
+        );
+    }
+
+    #[test]
+    fn accept_errors_back_off() {
+        let mut errors = AcceptErrors::default();
+        let delays: Vec<_> = std::iter::from_fn(|| errors.record()).collect();
+        assert_eq!(
+            delays.len(),
+            AcceptErrors::MAX_CONSECUTIVE as usize - 1,
+            "accepting stops after repeated errors"
+        );
+        assert!(
+            delays.windows(2).all(|pair| pair[0] < pair[1]),
+            "delays increase: {delays:?}"
+        );
+
+        // A successful connection resets the count.
+        errors.reset();
+        assert_eq!(errors.record(), Some(delays[0]));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_roundtrip() {
+        use tokio::{io::AsyncReadExt, net::UnixStream};
+
+        let dir = camino_tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let mut server = ControlServer::bind(&path).expect("bound control socket");
+
+        let client = tokio::spawn({
+            let path = path.clone();
+            async move {
+                let mut stream = UnixStream::connect(&path).await.unwrap();
+                stream.write_all(b"bogus\nstatus\n").await.unwrap();
+                stream.shutdown().await.unwrap();
+                let mut output = String::new();
+                stream.read_to_string(&mut output).await.unwrap();
+                output
+            }
+        });
+
+        let request = server.recv().await.expect("received request");
+        assert_eq!(request.command, ControlCommand::Status);
+        request.responder.send(ControlResponse::ok("fine")).unwrap();
+
+        let output = client.await.unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 2, "two responses: {output}");
+        assert!(lines[0].starts_with(r#"{"result":"error""#), "{output}");
+        assert_eq!(lines[1], r#"{"result":"ok","message":"fine"}"#);
+
+        // Only the current user can connect to the socket, and nothing else is left behind.
+        use std::os::unix::fs::PermissionsExt;
+        let mode = path.metadata().unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600, "control socket mode is {mode:o}");
+        assert_eq!(
+            dir.path().read_dir_utf8().unwrap().count(),
+            1,
+            "only the control socket is in the directory"
+        );
+
+        drop(server);
+        assert!(!path.exists(), "control socket is removed on drop");
+    }
+}
@@ -26,8 +26,31 @@
 //! 2. In the stub child process, unblock `SIGTSTP`.
 //!
 //! With this approach, the race condition between posix_spawn and `SIGTSTP` no longer exists.
-
-use std::path::Path;
+//!
+//! ## Pre-exec hooks
+//!
+//! Because the stub child runs in its own process before executing the test, it's also a natural
+//! place to perform per-process setup that must not affect nextest itself: for example, dropping
+//! privileges, entering namespaces, or setting resource limits.
+//!
+//! Programs that embed nextest can register such setup steps as *pre-exec hooks* with
+//! [`register_pre_exec_hook`], and users select the hooks to run for a profile via the
+//! `pre-exec` setting. Hooks must be registered at startup, before
+//! [`double_spawn_child_init`] is called, since the stub child is a fresh copy of the current
+//! executable and looks hooks up by name.
+//!
+//! The hooks configured for a test are passed in to the stub child through an environment
+//! variable, and are run in order after `SIGTSTP` is unblocked. If a hook fails, the test isn't
+//! executed.
+
+use crate::{config::PreExecHookConfig, errors::PreExecHookError};
+use once_cell::sync::Lazy;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    process::Command,
+    sync::{Arc, Mutex},
+};
 
 /// Information about double-spawning processes. This determines whether a process will be
 /// double-spawned.
@@ -69,6 +92,84 @@ impl DoubleSpawnInfo {
     pub fn spawn_context(&self) -> Option<DoubleSpawnContext> {
         self.current_exe().map(|_| DoubleSpawnContext::new())
     }
+
+    /// Checks that the given pre-exec hooks can be run.
+    ///
+    /// This returns an error if any hooks are configured but double-spawning is disabled, or if a
+    /// hook isn't registered.
+    pub fn check_pre_exec_hooks(
+        &self,
+        hooks: &[PreExecHookConfig],
+    ) -> Result<(), PreExecHookError> {
+        if hooks.is_empty() {
+            return Ok(());
+        }
+        if self.current_exe().is_none() {
+            return Err(PreExecHookError::DoubleSpawnDisabled);
+        }
+        let registry = PRE_EXEC_HOOKS.lock().unwrap();
+        for hook in hooks {
+            if !registry.contains_key(&hook.name) {
+                return Err(PreExecHookError::NotRegistered {
+                    name: hook.name.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A setup step run in the double-spawned child process, just before it executes a test.
+///
+/// Hooks are registered with [`register_pre_exec_hook`]. This trait is implemented for closures
+/// with the signature `Fn(&[String]) -> std::io::Result<()>`.
+pub trait PreExecHook: Send + Sync {
+    /// Runs the hook with the arguments configured for it.
+    ///
+    /// This is called in the double-spawned child, which is single-threaded at this point. If an
+    /// error is returned, the test is not executed.
+    fn run(&self, args: &[String]) -> std::io::Result<()>;
+}
+
+impl<F> PreExecHook for F
+where
+    F: Fn(&[String]) -> std::io::Result<()> + Send + Sync,
+{
+    fn run(&self, args: &[String]) -> std::io::Result<()> {
+        self(args)
+    }
+}
+
+static PRE_EXEC_HOOKS: Lazy<Mutex<BTreeMap<String, Arc<dyn PreExecHook>>>> =
+    Lazy::new(Default::default);
+
+/// Registers a pre-exec hook under the given name, replacing any hook previously registered with
+/// that name.
+///
+/// This must be called in both the main nextest process and the double-spawned child, so it is
+/// typically called at the start of `main`.
+pub fn register_pre_exec_hook(name: impl Into<String>, hook: impl PreExecHook + 'static) {
+    PRE_EXEC_HOOKS
+        .lock()
+        .unwrap()
+        .insert(name.into(), Arc::new(hook));
+}
+
+/// Returns true if a pre-exec hook is registered under the given name.
+pub fn is_pre_exec_hook_registered(name: &str) -> bool {
+    PRE_EXEC_HOOKS.lock().unwrap().contains_key(name)
+}
+
+/// The environment variable used to pass pre-exec hooks in to the double-spawned child.
+const PRE_EXEC_ENV: &str = "__NEXTEST_DOUBLE_SPAWN_PRE_EXEC";
+
+/// Passes the given pre-exec hooks in to the double-spawned child spawned by `cmd`.
+pub(crate) fn set_pre_exec_hooks(cmd: &mut Command, hooks: &[PreExecHookConfig]) {
+    if hooks.is_empty() {
+        return;
+    }
+    let hooks = serde_json::to_string(hooks).expect("serializing pre-exec hooks succeeds");
+    cmd.env(PRE_EXEC_ENV, hooks);
 }
 
 /// Context to be used before spawning processes and dropped afterwards.
@@ -98,6 +199,34 @@ pub fn double_spawn_child_init() {
     imp::double_spawn_child_init()
 }
 
+/// Runs the pre-exec hooks passed in to the double-spawn child, in order.
+///
+/// `command` is the command the child is about to execute. The environment variable used to pass
+/// in the hooks is removed from it.
+pub fn double_spawn_child_pre_exec(command: &mut Command) -> Result<(), PreExecHookError> {
+    command.env_remove(PRE_EXEC_ENV);
+    let Some(hooks) = std::env::var_os(PRE_EXEC_ENV) else {
+        return Ok(());
+    };
+    let hooks: Vec<PreExecHookConfig> =
+        serde_json::from_str(&hooks.to_string_lossy()).map_err(PreExecHookError::ParseEnv)?;
+
+    for hook in &hooks {
+        let registered = PRE_EXEC_HOOKS.lock().unwrap().get(&hook.name).cloned();
+        let registered = registered.ok_or_else(|| PreExecHookError::NotRegistered {
+            name: hook.name.clone(),
+        })?;
+        registered
+            .run(&hook.args)
+            .map_err(|error| PreExecHookError::Failed {
+                hook: hook.to_string(),
+                error,
+            })?;
+    }
+
+    Ok(())
+}
+
 #[cfg(unix)]
 mod imp {
     use super::*;
@@ -237,3 +366,43 @@ mod imp {
     #[inline]
     pub(super) fn double_spawn_child_init() {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_pre_exec_hooks() {
+        register_pre_exec_hook("test-noop", |_: &[String]| Ok(()));
+        assert!(is_pre_exec_hook_registered("test-noop"));
+
+        let registered = [PreExecHookConfig {
+            name: "test-noop".to_owned(),
+            args: vec![],
+        }];
+        let unregistered = [PreExecHookConfig {
+            name: "test-missing".to_owned(),
+            args: vec![],
+        }];
+
+        let disabled = DoubleSpawnInfo::disabled();
+        disabled
+            .check_pre_exec_hooks(&[])
+            .expect("no hooks is always fine");
+        assert!(matches!(
+            disabled.check_pre_exec_hooks(&registered),
+            Err(PreExecHookError::DoubleSpawnDisabled)
+        ));
+
+        let enabled = DoubleSpawnInfo::try_enable();
+        if enabled.current_exe().is_some() {
+            enabled
+                .check_pre_exec_hooks(&registered)
+                .expect("registered hook is accepted");
+            assert!(matches!(
+                enabled.check_pre_exec_hooks(&unregistered),
+                Err(PreExecHookError::NotRegistered { name }) if name == "test-missing"
+            ));
+        }
+    }
+}
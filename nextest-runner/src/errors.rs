@@ -16,7 +16,7 @@ use config::ConfigError;
 use indent_write::fmt::IndentWriter;
 use itertools::{Either, Itertools};
 use nextest_filtering::errors::FiltersetParseErrors;
-use nextest_metadata::RustBinaryId;
+use nextest_metadata::{PlatformLibdirUnavailable, RustBinaryId};
 use smol_str::SmolStr;
 use std::{
     borrow::Cow,
@@ -98,6 +98,12 @@ pub enum ConfigParseErrorKind {
     /// An error occurred while deserializing the config (version only).
     #[error(transparent)]
     VersionOnlyDeserializeError(Box<serde_path_to_error::Error<toml::de::Error>>),
+    /// An error occurred while reading the list of flaky tests.
+    #[error(transparent)]
+    FlakyTestsReadError(std::io::Error),
+    /// An error occurred while deserializing the list of flaky tests.
+    #[error(transparent)]
+    FlakyTestsDeserializeError(Box<toml::de::Error>),
     /// Errors occurred while compiling configuration strings.
     #[error("error parsing compiled data (destructure this variant for more details)")]
     CompileErrors(Vec<ConfigCompileError>),
@@ -191,6 +197,9 @@ pub enum ConfigCompileSection {
 
     /// `[[profile.<profile-name>.scripts]]` at the corresponding index.
     Script(usize),
+
+    /// `[[profile.<profile-name>.banned]]` at the corresponding index.
+    Banned(usize),
 }
 
 /// The kind of error that occurred while parsing config overrides.
@@ -280,6 +289,45 @@ pub enum ChildStartError {
     #[error("error creating temporary path for setup script")]
     TempPath(#[source] Arc<std::io::Error>),
 
+    /// An error occurred while creating a temporary directory for a test.
+    #[error("error creating temporary directory for test")]
+    TempDir(#[source] Arc<std::io::Error>),
+
+    /// An error occurred while isolating a test from the desktop session.
+    #[error("error isolating test from the desktop session")]
+    IsolateDesktop(#[source] Arc<std::io::Error>),
+
+    /// An error occurred while setting up auditing of a test's network and file system access.
+    #[error("error setting up access audit for test")]
+    AccessAudit(#[source] Arc<std::io::Error>),
+
+    /// The test matched a `[[profile.<name>.banned]]` entry, so it wasn't run.
+    #[error("test is banned from running: {reason} (pass --allow-banned to run it anyway)")]
+    Banned {
+        /// The reason the test is banned, from the configuration.
+        reason: String,
+    },
+
+    /// An error occurred while opening a file to use as a test's standard input.
+    #[error("error opening `{path}` as standard input for test")]
+    StdinFile {
+        /// The path to the file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: Arc<std::io::Error>,
+    },
+
+    /// An error occurred while denying a test network access.
+    ///
+    /// On Linux, this requires unprivileged user namespaces.
+    #[error(
+        "error denying network access to test (`network = \"deny\"` requires \
+         unprivileged user namespaces to be enabled)"
+    )]
+    DenyNetwork(#[source] Arc<std::io::Error>),
+
     /// An error occurred while spawning the child process.
     #[error("error spawning child process")]
     Spawn(#[source] Arc<std::io::Error>),
@@ -325,6 +373,26 @@ pub enum SetupScriptOutputError {
         /// The environment variable name.
         key: String,
     },
+
+    /// An error occurred while reading the setup script cleanup file.
+    #[error("error reading cleanup file `{path}`")]
+    CleanupFileRead {
+        /// The path to the cleanup file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: Arc<std::io::Error>,
+    },
+
+    /// A line in the setup script cleanup file could not be parsed as a command.
+    #[error("line `{line}` in cleanup file `{path}` is not a valid command")]
+    CleanupFileParse {
+        /// The path to the cleanup file.
+        path: Utf8PathBuf,
+        /// The line at issue.
+        line: String,
+    },
 }
 
 /// A list of errors that implements `Error`.
@@ -620,6 +688,29 @@ impl TestThreadsParseError {
     }
 }
 
+/// Error returned while parsing a [`WarningCode`](crate::warnings::WarningCode).
+#[derive(Clone, Debug, Error)]
+#[error(
+    "unknown warning code: {input}\n(hint: known codes are {})",
+    crate::warnings::WarningCode::ALL
+        .iter()
+        .map(|code| code.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+)]
+pub struct UnknownWarningCode {
+    /// The input that failed to parse.
+    pub input: String,
+}
+
+impl UnknownWarningCode {
+    pub(crate) fn new(input: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+        }
+    }
+}
+
 /// An error that occurs while parsing a
 /// [`PartitionerBuilder`](crate::partition::PartitionerBuilder) input.
 #[derive(Clone, Debug, Error)]
@@ -948,6 +1039,10 @@ pub enum CreateTestListError {
     /// Creating a Tokio runtime failed.
     #[error("error creating Tokio runtime")]
     TokioRuntimeCreate(#[source] std::io::Error),
+
+    /// Gathering the list of tests failed for more than one test binary.
+    #[error(transparent)]
+    MultipleBinaries(ErrorList<CreateTestListError>),
 }
 
 impl CreateTestListError {
@@ -1003,6 +1098,69 @@ pub enum TestRunnerBuildError {
     /// An error occurred while setting up signals.
     #[error("error setting up signals")]
     SignalHandlerSetupError(#[from] SignalHandlerSetupError),
+
+    /// An error occurred while creating the file to record scheduling decisions to.
+    #[error("error creating schedule log at `{path}`")]
+    DebugScheduleCreate {
+        /// The path of the schedule log.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// An error occurred while setting up the control socket.
+    #[error("error listening on control socket `{path}`")]
+    ControlSocketBind {
+        /// The path to the control socket.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// The pre-exec hooks configured for the profile can't be run.
+    #[error("error setting up pre-exec hooks")]
+    PreExecHook(#[from] PreExecHookError),
+}
+
+/// An error that occurred while setting up or running a pre-exec hook.
+///
+/// Pre-exec hooks are registered with
+/// [`register_pre_exec_hook`](crate::double_spawn::register_pre_exec_hook).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PreExecHookError {
+    /// Pre-exec hooks were configured, but double-spawning is disabled or unsupported.
+    #[error(
+        "pre-exec hooks are configured, but double-spawning is not available \
+         (pre-exec hooks are only supported on Unix, without NEXTEST_DOUBLE_SPAWN=0)"
+    )]
+    DoubleSpawnDisabled,
+
+    /// A configured pre-exec hook isn't registered.
+    #[error("pre-exec hook `{name}` is not registered")]
+    NotRegistered {
+        /// The name of the hook.
+        name: String,
+    },
+
+    /// The pre-exec hooks passed in to the double-spawned child couldn't be parsed.
+    #[error("error parsing pre-exec hooks passed in via the environment")]
+    ParseEnv(#[source] serde_json::Error),
+
+    /// A pre-exec hook failed.
+    #[error("pre-exec hook `{hook}` failed")]
+    Failed {
+        /// The hook that failed, along with its arguments.
+        hook: String,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
 }
 
 /// Errors that occurred while managing test runner Tokio tasks.
@@ -1588,6 +1746,57 @@ pub enum TargetRunnerError {
         /// The value that was read from the key
         value: String,
     },
+
+    /// The runner binary wasn't found.
+    #[error("runner binary `{binary}` specified by {key} was not found")]
+    BinaryNotFound {
+        /// The source under consideration.
+        key: PlatformRunnerSource,
+
+        /// The runner binary.
+        binary: Utf8PathBuf,
+    },
+
+    /// A runner argument referred to a libdir that isn't available.
+    #[error(
+        "runner specified by {key} uses `{placeholder}`, \
+         but the libdir is unavailable ({})",
+        reason.as_str()
+    )]
+    LibdirUnavailable {
+        /// The source under consideration.
+        key: PlatformRunnerSource,
+
+        /// The placeholder that was used.
+        placeholder: &'static str,
+
+        /// The reason the libdir is unavailable.
+        reason: PlatformLibdirUnavailable,
+    },
+}
+
+/// An error occurred while writing the list of flaky tests.
+#[derive(Debug, Error)]
+#[error("error writing flaky tests to `{path}`")]
+pub struct FlakyTestsWriteError {
+    /// The path that was being written to.
+    pub path: Utf8PathBuf,
+
+    /// The underlying error.
+    #[source]
+    pub error: std::io::Error,
+}
+
+/// An error occurred while writing the manifest of safe test names.
+#[derive(Debug, Error)]
+#[error("error writing test name manifest to `{path}`")]
+pub struct SafeNamesManifestWriteError {
+    /// The path that was being written to.
+    pub path: Utf8PathBuf,
+
+    /// The underlying error.
+    #[source]
+    pub error: std::io::Error,
 }
 
 /// An error that occurred while setting up the signal handler.
@@ -1595,6 +1804,57 @@ pub enum TargetRunnerError {
 #[error("error setting up signal handler")]
 pub struct SignalHandlerSetupError(#[from] std::io::Error);
 
+/// An error that occurs while reading or writing JUnit reports produced by earlier runs.
+///
+/// Returned by [`MergedReport`](crate::reporter::merge::MergedReport) and
+/// [`ReportDiff`](crate::reporter::diff::ReportDiff).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum JunitReportError {
+    /// An error occurred while reading a report.
+    #[error("error reading report from `{path}`")]
+    Read {
+        /// The path that was read.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// An error occurred while parsing a report.
+    #[error("error parsing JUnit XML in `{path}`")]
+    Parse {
+        /// The path of the report.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: quick_xml::Error,
+    },
+
+    /// The report did not have a `<testsuites>` root element.
+    #[error("`{path}` is not a JUnit report: expected root element `testsuites`, found `{found}`")]
+    InvalidRoot {
+        /// The path of the report.
+        path: Utf8PathBuf,
+
+        /// The root element that was found, or an empty string if none was found.
+        found: String,
+    },
+
+    /// An error occurred while writing the merged report.
+    #[error("error writing merged report to `{path}`")]
+    Write {
+        /// The path that was written to.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+}
+
 /// An error occurred while showing test groups.
 #[derive(Debug, Error)]
 pub enum ShowTestGroupsError {
@@ -1613,12 +1873,93 @@ pub enum ShowTestGroupsError {
     },
 }
 
+/// An error that occurred while reading the user config.
+///
+/// Returned by [`UserConfig`](crate::user_config::UserConfig).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum UserConfigError {
+    /// The path to the user config, set via an environment variable, is not valid UTF-8.
+    #[error("user config path is not valid UTF-8")]
+    PathNotUtf8 {
+        /// The underlying error.
+        #[source]
+        error: FromPathBufError,
+    },
+
+    /// An error occurred while reading the user config.
+    #[error("error reading user config from `{path}`")]
+    Read {
+        /// The path that was read.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// An error occurred while parsing the user config.
+    #[error("error parsing user config at `{path}`")]
+    Parse {
+        /// The path that was read.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: toml::de::Error,
+    },
+}
+
 #[cfg(feature = "self-update")]
 mod self_update_errors {
     use super::*;
     use mukti_metadata::ReleaseStatus;
     use semver::{Version, VersionReq};
 
+    /// An error that occurred while building an HTTP client from the user config.
+    ///
+    /// Returned by [`HttpConfig::client`](crate::user_config::HttpConfig::client).
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum HttpClientError {
+        /// The proxy URL is invalid.
+        #[error("invalid proxy `{proxy}`")]
+        InvalidProxy {
+            /// The proxy URL.
+            proxy: String,
+
+            /// The underlying error.
+            #[source]
+            error: reqwest::Error,
+        },
+
+        /// The CA bundle could not be read.
+        #[error("error reading CA bundle from `{path}`")]
+        CaBundleRead {
+            /// The path that was read.
+            path: Utf8PathBuf,
+
+            /// The underlying error.
+            #[source]
+            error: std::io::Error,
+        },
+
+        /// The CA bundle could not be parsed.
+        #[error("error parsing CA bundle at `{path}`")]
+        CaBundleParse {
+            /// The path that was read.
+            path: Utf8PathBuf,
+
+            /// The underlying error.
+            #[source]
+            error: reqwest::Error,
+        },
+
+        /// The client could not be built.
+        #[error("error building HTTP client")]
+        Build(#[source] reqwest::Error),
+    }
+
     /// An error that occurs while performing a self-update.
     ///
     /// Returned by methods in the [`update`](crate::update) module.
@@ -1626,6 +1967,32 @@ mod self_update_errors {
     #[derive(Debug, Error)]
     #[non_exhaustive]
     pub enum UpdateError {
+        /// The HTTP client could not be set up.
+        #[error("failed to set up HTTP client")]
+        HttpClient(#[source] HttpClientError),
+
+        /// A download failed.
+        #[error("failed to download `{url}`")]
+        Download {
+            /// The URL being downloaded.
+            url: String,
+
+            /// The underlying error.
+            #[source]
+            error: reqwest::Error,
+        },
+
+        /// An error occurred while reading a download.
+        #[error("error reading download from `{url}`")]
+        DownloadRead {
+            /// The URL being downloaded.
+            url: String,
+
+            /// The underlying error.
+            #[source]
+            error: std::io::Error,
+        },
+
         /// Failed to read release metadata from a local path on disk.
         #[error("failed to read release metadata from `{path}`")]
         ReadLocalMetadata {
@@ -1693,6 +2060,17 @@ mod self_update_errors {
         #[error("the current executable's path could not be determined")]
         CurrentExe(#[source] std::io::Error),
 
+        /// A local archive could not be read.
+        #[error("failed to read archive from `{path}`")]
+        ReadLocalArchive {
+            /// The path that was read.
+            path: Utf8PathBuf,
+
+            /// The error that occurred.
+            #[source]
+            error: std::io::Error,
+        },
+
         /// A temporary directory could not be created.
         #[error("temporary directory could not be created at `{location}`")]
         TempDirCreate {
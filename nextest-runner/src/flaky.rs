@@ -0,0 +1,445 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tracking and quarantining flaky tests.
+//!
+//! If the profile has `record-flaky-history` enabled, at the end of each run nextest records the
+//! outcome of every test that ran to a [`FlakyHistory`] within the profile's store directory. `cargo nextest flaky update` uses this
+//! history to maintain [`FlakyTests`], a list of flaky tests checked into the repository at
+//! [`FlakyTests::PATH`]. Tests in this list are retried according to the `flaky-test-retries`
+//! profile setting.
+
+use crate::errors::{ConfigParseError, ConfigParseErrorKind, FlakyTestsWriteError};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    io::{self, Write},
+};
+use tracing::{debug, warn};
+
+/// The outcome of a test within a single run, as recorded in [`FlakyHistory`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestOutcome {
+    /// The test passed on its first attempt.
+    Pass,
+
+    /// The test failed at least once, then passed on a retry.
+    Flaky,
+
+    /// The test failed on every attempt.
+    Fail,
+}
+
+impl TestOutcome {
+    /// Returns true if the test eventually passed.
+    fn is_pass(self) -> bool {
+        matches!(self, Self::Pass | Self::Flaky)
+    }
+}
+
+/// Outcomes of tests across recent runs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FlakyHistory {
+    // binary ID -> test name -> outcomes, oldest first.
+    tests: BTreeMap<String, BTreeMap<String, VecDeque<TestOutcome>>>,
+}
+
+impl FlakyHistory {
+    /// The name of the file, within the profile's store directory, that history is stored in.
+    pub const FILE_NAME: &'static str = "flaky-history.json";
+
+    /// The maximum number of runs remembered for each test.
+    pub const MAX_RUNS: usize = 100;
+
+    /// Loads history from `path`.
+    ///
+    /// If the file doesn't exist or can't be read, this returns an empty history.
+    pub fn load(path: &Utf8Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                debug!("no flaky test history found at {path}");
+                return Self::default();
+            }
+            Err(error) => {
+                warn!("error reading flaky test history from {path}: {error}");
+                return Self::default();
+            }
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(history) => history,
+            Err(error) => {
+                warn!("error parsing flaky test history from {path}, ignoring: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes history out to `path`, creating parent directories as needed.
+    pub fn save(&self, path: &Utf8Path) {
+        if let Some(parent) = path.parent() {
+            if let Err(error) = std::fs::create_dir_all(parent) {
+                warn!("error creating directory {parent} for flaky test history: {error}");
+                return;
+            }
+        }
+
+        let file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
+        if let Err(error) = file.write(|f| serde_json::to_writer(f, self)) {
+            warn!("error writing flaky test history to {path}: {error}");
+        }
+    }
+
+    /// Records the outcome of a test, forgetting the oldest outcome if more than
+    /// [`Self::MAX_RUNS`] are stored.
+    pub fn record(&mut self, binary_id: &str, test_name: &str, outcome: TestOutcome) {
+        let outcomes = self
+            .tests
+            .entry(binary_id.to_owned())
+            .or_default()
+            .entry(test_name.to_owned())
+            .or_default();
+        outcomes.push_back(outcome);
+        while outcomes.len() > Self::MAX_RUNS {
+            outcomes.pop_front();
+        }
+    }
+
+    /// Returns statistics for a test, if it has any recorded outcomes.
+    pub fn stats(&self, binary_id: &str, test_name: &str) -> Option<FlakyStats> {
+        self.tests
+            .get(binary_id)
+            .and_then(|tests| tests.get(test_name))
+            .map(FlakyStats::new)
+    }
+
+    /// Iterates over all tests with recorded outcomes, returning their binary IDs, names and
+    /// statistics.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, FlakyStats)> + '_ {
+        self.tests.iter().flat_map(|(binary_id, tests)| {
+            tests.iter().map(move |(test_name, outcomes)| {
+                (
+                    binary_id.as_str(),
+                    test_name.as_str(),
+                    FlakyStats::new(outcomes),
+                )
+            })
+        })
+    }
+}
+
+/// Statistics about a test's recent outcomes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FlakyStats {
+    /// The number of runs recorded.
+    pub runs: usize,
+
+    /// The number of runs in which the test was flaky.
+    pub flaky_runs: usize,
+
+    /// The number of runs in which the test passed after failing in the previous run, or failed
+    /// after passing in the previous run.
+    ///
+    /// Unlike `flaky_runs`, this doesn't require tests to be retried.
+    pub transitions: usize,
+
+    /// The number of most recent runs in which the test passed on its first attempt.
+    pub stable_streak: usize,
+}
+
+impl FlakyStats {
+    fn new(outcomes: &VecDeque<TestOutcome>) -> Self {
+        Self {
+            runs: outcomes.len(),
+            flaky_runs: outcomes
+                .iter()
+                .filter(|outcome| **outcome == TestOutcome::Flaky)
+                .count(),
+            transitions: outcomes
+                .iter()
+                .zip(outcomes.iter().skip(1))
+                .filter(|(prev, next)| prev.is_pass() != next.is_pass())
+                .count(),
+            stable_streak: outcomes
+                .iter()
+                .rev()
+                .take_while(|outcome| **outcome == TestOutcome::Pass)
+                .count(),
+        }
+    }
+
+    /// Returns the fraction of recorded runs in which the test was flaky, or changed between
+    /// passing and failing.
+    pub fn flake_score(&self) -> f64 {
+        if self.runs == 0 {
+            0.0
+        } else {
+            (self.flaky_runs + self.transitions).min(self.runs) as f64 / self.runs as f64
+        }
+    }
+}
+
+/// A list of flaky tests, checked into the repository at [`Self::PATH`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FlakyTests {
+    // binary ID -> test names.
+    #[serde(default)]
+    tests: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl FlakyTests {
+    /// The location of the list within the workspace root.
+    pub const PATH: &'static str = ".config/flaky-tests.toml";
+
+    const HEADER: &'static str = "\
+# Flaky tests in this workspace, retried according to the `flaky-test-retries`
+# setting. This file is maintained by `cargo nextest flaky update`.
+
+";
+
+    /// Reads the list from `path`, returning an empty list if the file doesn't exist.
+    pub fn read(path: &Utf8Path) -> Result<Self, ConfigParseError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => {
+                return Err(ConfigParseError::new(
+                    path,
+                    None,
+                    ConfigParseErrorKind::FlakyTestsReadError(error),
+                ))
+            }
+        };
+
+        toml::from_str(&contents).map_err(|error| {
+            ConfigParseError::new(
+                path,
+                None,
+                ConfigParseErrorKind::FlakyTestsDeserializeError(Box::new(error)),
+            )
+        })
+    }
+
+    /// Writes the list out to `path`.
+    pub fn write(&self, path: &Utf8Path) -> Result<(), FlakyTestsWriteError> {
+        let contents = toml::to_string(self).expect("flaky tests are serializable");
+        let file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
+        file.write(|f| {
+            f.write_all(Self::HEADER.as_bytes())?;
+            f.write_all(contents.as_bytes())
+        })
+        .map_err(|error| FlakyTestsWriteError {
+            path: path.to_owned(),
+            error: match error {
+                atomicwrites::Error::Internal(error) | atomicwrites::Error::User(error) => error,
+            },
+        })
+    }
+
+    /// Returns true if the list contains the given test.
+    pub fn contains(&self, binary_id: &str, test_name: &str) -> bool {
+        self.tests
+            .get(binary_id)
+            .is_some_and(|tests| tests.contains(test_name))
+    }
+
+    /// Returns true if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.tests.is_empty()
+    }
+
+    /// Updates the list based on `history`.
+    ///
+    /// * Tests with at least `params.min_runs` recorded runs, and a flake score greater than
+    ///   `params.threshold`, are added.
+    /// * Tests that have passed on their first attempt for the last `params.stable_runs` runs are
+    ///   removed.
+    pub fn update(
+        &mut self,
+        history: &FlakyHistory,
+        params: &FlakyUpdateParams,
+    ) -> FlakyUpdateSummary {
+        let mut summary = FlakyUpdateSummary::default();
+
+        for (binary_id, test_name, stats) in history.iter() {
+            let listed = self.contains(binary_id, test_name);
+            if !listed && stats.runs >= params.min_runs && stats.flake_score() > params.threshold {
+                self.tests
+                    .entry(binary_id.to_owned())
+                    .or_default()
+                    .insert(test_name.to_owned());
+                summary
+                    .added
+                    .push((binary_id.to_owned(), test_name.to_owned()));
+            } else if listed && stats.stable_streak >= params.stable_runs {
+                if let Some(tests) = self.tests.get_mut(binary_id) {
+                    tests.remove(test_name);
+                    if tests.is_empty() {
+                        self.tests.remove(binary_id);
+                    }
+                }
+                summary
+                    .removed
+                    .push((binary_id.to_owned(), test_name.to_owned()));
+            }
+        }
+
+        summary
+    }
+}
+
+/// Parameters for [`FlakyTests::update`].
+#[derive(Clone, Debug)]
+pub struct FlakyUpdateParams {
+    /// Tests with a flake score above this threshold are added.
+    pub threshold: f64,
+
+    /// The minimum number of recorded runs before a test can be added.
+    pub min_runs: usize,
+
+    /// The number of consecutive stable runs after which a test is removed.
+    pub stable_runs: usize,
+}
+
+/// The changes made by [`FlakyTests::update`].
+#[derive(Clone, Debug, Default)]
+pub struct FlakyUpdateSummary {
+    /// Tests that were added, as (binary ID, test name) pairs.
+    pub added: Vec<(String, String)>,
+
+    /// Tests that were removed, as (binary ID, test name) pairs.
+    pub removed: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::tempdir;
+
+    #[test]
+    fn history_stats() {
+        let mut history = FlakyHistory::default();
+        for outcome in [
+            TestOutcome::Flaky,
+            TestOutcome::Fail,
+            TestOutcome::Pass,
+            TestOutcome::Pass,
+        ] {
+            history.record("my-binary", "tests::a", outcome);
+        }
+
+        let stats = history.stats("my-binary", "tests::a").unwrap();
+        assert_eq!(
+            stats,
+            FlakyStats {
+                runs: 4,
+                flaky_runs: 1,
+                transitions: 2,
+                stable_streak: 2,
+            }
+        );
+        assert_eq!(stats.flake_score(), 0.75);
+
+        for _ in 0..FlakyHistory::MAX_RUNS {
+            history.record("my-binary", "tests::a", TestOutcome::Pass);
+        }
+        let stats = history.stats("my-binary", "tests::a").unwrap();
+        assert_eq!(stats.runs, FlakyHistory::MAX_RUNS, "old runs are forgotten");
+        assert_eq!(stats.flaky_runs, 0);
+        assert_eq!(stats.transitions, 0);
+    }
+
+    #[test]
+    fn history_transitions_without_retries() {
+        // Without retries, a flaky test is never recorded as flaky, but alternates between
+        // passing and failing.
+        let mut history = FlakyHistory::default();
+        for outcome in [
+            TestOutcome::Pass,
+            TestOutcome::Fail,
+            TestOutcome::Pass,
+            TestOutcome::Pass,
+            TestOutcome::Fail,
+            TestOutcome::Pass,
+        ] {
+            history.record("my-binary", "tests::flaky", outcome);
+        }
+        // A test that broke once and stayed broken has a single transition.
+        for outcome in [TestOutcome::Pass, TestOutcome::Pass]
+            .into_iter()
+            .chain(std::iter::repeat(TestOutcome::Fail).take(4))
+        {
+            history.record("my-binary", "tests::broken", outcome);
+        }
+
+        let stats = history.stats("my-binary", "tests::flaky").unwrap();
+        assert_eq!(stats.flaky_runs, 0);
+        assert_eq!(stats.transitions, 4);
+        assert_eq!(stats.flake_score(), 4.0 / 6.0);
+
+        let stats = history.stats("my-binary", "tests::broken").unwrap();
+        assert_eq!(stats.transitions, 1);
+
+        let params = FlakyUpdateParams {
+            threshold: 0.2,
+            min_runs: 5,
+            stable_runs: 5,
+        };
+        let summary = FlakyTests::default().update(&history, &params);
+        assert_eq!(
+            summary.added,
+            vec![("my-binary".to_owned(), "tests::flaky".to_owned())],
+            "only the intermittently failing test is added"
+        );
+    }
+
+    #[test]
+    fn update_flaky_tests() {
+        let mut history = FlakyHistory::default();
+        for _ in 0..5 {
+            history.record("my-binary", "tests::flaky", TestOutcome::Flaky);
+            history.record("my-binary", "tests::stable", TestOutcome::Pass);
+            history.record("my-binary", "tests::new", TestOutcome::Flaky);
+        }
+        history.record("my-binary", "tests::few-runs", TestOutcome::Flaky);
+
+        let mut flaky_tests = FlakyTests::default();
+        flaky_tests
+            .tests
+            .entry("my-binary".to_owned())
+            .or_default()
+            .insert("tests::stable".to_owned());
+
+        let params = FlakyUpdateParams {
+            threshold: 0.1,
+            min_runs: 3,
+            stable_runs: 5,
+        };
+        let summary = flaky_tests.update(&history, &params);
+        assert_eq!(
+            summary.added,
+            vec![
+                ("my-binary".to_owned(), "tests::flaky".to_owned()),
+                ("my-binary".to_owned(), "tests::new".to_owned()),
+            ]
+        );
+        assert_eq!(
+            summary.removed,
+            vec![("my-binary".to_owned(), "tests::stable".to_owned())]
+        );
+
+        // Round-trip the list through a file.
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("flaky-tests.toml");
+        flaky_tests.write(&path).unwrap();
+        assert_eq!(FlakyTests::read(&path).unwrap(), flaky_tests);
+        assert!(flaky_tests.contains("my-binary", "tests::flaky"));
+        assert!(!flaky_tests.contains("my-binary", "tests::stable"));
+    }
+}
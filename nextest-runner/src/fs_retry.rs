@@ -0,0 +1,139 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Retrying file system operations that fail with transient errors.
+//!
+//! Some file system errors go away on their own: on Windows, a virus scanner or indexer can briefly
+//! hold a file open, and network file systems can return `EAGAIN` under load. Outputs written after
+//! all tests have run (such as JUnit reports) are retried a bounded number of times on these
+//! errors, so that a briefly locked file doesn't throw away the results of a long run.
+
+use camino::Utf8Path;
+use std::{io, thread, time::Duration};
+use tracing::warn;
+
+/// The maximum number of times an operation is attempted.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// The delay before the first retry. Each later retry doubles the delay.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Runs `op` against `path`, retrying it if it fails with a transient error.
+///
+/// A warning is logged for each retry. If the last attempt fails, its error is returned.
+pub(crate) fn retry_transient<T>(
+    path: &Utf8Path,
+    op: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    retry_transient_impl(path, INITIAL_BACKOFF, op)
+}
+
+fn retry_transient_impl<T>(
+    path: &Utf8Path,
+    initial_backoff: Duration,
+    mut op: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut backoff = initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(error) if attempt < MAX_ATTEMPTS && is_transient(&error) => {
+                warn!(
+                    "transient error operating on {path} \
+                     (attempt {attempt}/{MAX_ATTEMPTS}), retrying in {backoff:?}: {error}"
+                );
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Returns true if this error is likely to go away if the operation is retried.
+fn is_transient(error: &io::Error) -> bool {
+    if matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    ) {
+        return true;
+    }
+
+    error.raw_os_error().is_some_and(is_transient_os_error)
+}
+
+#[cfg(unix)]
+fn is_transient_os_error(code: i32) -> bool {
+    // EAGAIN is covered by WouldBlock above.
+    matches!(code, libc::EBUSY | libc::ETXTBSY)
+}
+
+#[cfg(windows)]
+fn is_transient_os_error(code: i32) -> bool {
+    use windows_sys::Win32::Foundation::{ERROR_LOCK_VIOLATION, ERROR_SHARING_VIOLATION};
+
+    // Another process (often a virus scanner or indexer) has the file open.
+    code == ERROR_SHARING_VIOLATION as i32 || code == ERROR_LOCK_VIOLATION as i32
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_transient_os_error(_code: i32) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&io::ErrorKind::WouldBlock.into()));
+        assert!(is_transient(&io::ErrorKind::Interrupted.into()));
+        assert!(!is_transient(&io::ErrorKind::NotFound.into()));
+        assert!(!is_transient(&io::ErrorKind::PermissionDenied.into()));
+
+        #[cfg(unix)]
+        {
+            assert!(is_transient(&io::Error::from_raw_os_error(libc::EBUSY)));
+            assert!(is_transient(&io::Error::from_raw_os_error(libc::EAGAIN)));
+            assert!(!is_transient(&io::Error::from_raw_os_error(libc::ENOSPC)));
+        }
+    }
+
+    #[test]
+    fn test_retry_transient() {
+        let path = Utf8Path::new("some/path");
+
+        // Succeeds after transient errors.
+        let mut calls = 0;
+        let res = retry_transient_impl(path, Duration::ZERO, || {
+            calls += 1;
+            if calls < 3 {
+                Err(io::ErrorKind::WouldBlock.into())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(res.unwrap(), 3);
+
+        // Gives up after MAX_ATTEMPTS.
+        let mut calls = 0;
+        let res: io::Result<()> = retry_transient_impl(path, Duration::ZERO, || {
+            calls += 1;
+            Err(io::ErrorKind::WouldBlock.into())
+        });
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(calls, MAX_ATTEMPTS);
+
+        // Non-transient errors aren't retried.
+        let mut calls = 0;
+        let res: io::Result<()> = retry_transient_impl(path, Duration::ZERO, || {
+            calls += 1;
+            Err(io::ErrorKind::NotFound.into())
+        });
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(calls, 1);
+    }
+}
@@ -9,7 +9,8 @@ use crate::{
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use owo_colors::{OwoColorize, Style};
-use std::{fmt, io, path::PathBuf, process::ExitStatus, time::Duration};
+use std::{borrow::Cow, fmt, io, path::PathBuf, process::ExitStatus, time::Duration};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 pub(crate) mod plural {
     pub(crate) fn were_plural_if(plural: bool) -> &'static str {
@@ -48,6 +49,14 @@ pub(crate) mod plural {
         }
     }
 
+    pub(crate) fn processes_str(count: usize) -> &'static str {
+        if count == 1 {
+            "process"
+        } else {
+            "processes"
+        }
+    }
+
     pub(crate) fn paths_str(count: usize) -> &'static str {
         if count == 1 {
             "path"
@@ -92,23 +101,82 @@ pub(crate) mod plural {
 pub(crate) struct DisplayTestInstance<'a> {
     instance: TestInstanceId<'a>,
     styles: &'a Styles,
+    // The maximum width in terminal columns, and the ellipsis to use when
+    // truncating.
+    max_width: Option<(usize, &'static str)>,
 }
 
 impl<'a> DisplayTestInstance<'a> {
     pub(crate) fn new(instance: TestInstanceId<'a>, styles: &'a Styles) -> Self {
-        Self { instance, styles }
+        Self {
+            instance,
+            styles,
+            max_width: None,
+        }
+    }
+
+    /// Truncates the test instance to at most `max_width` terminal columns,
+    /// replacing the removed text with `ellipsis`.
+    ///
+    /// Text is removed from the start of the test name, since the end is the
+    /// most specific part, and then from the start of the binary ID.
+    pub(crate) fn with_max_width(mut self, max_width: usize, ellipsis: &'static str) -> Self {
+        self.max_width = Some((max_width, ellipsis));
+        self
     }
 }
 
+/// Parts of the binary ID shorter than this are kept when truncating test
+/// instances, so that it's clear which binary a test is in.
+const MIN_BINARY_ID_WIDTH: usize = 8;
+
 impl fmt::Display for DisplayTestInstance<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{} ",
-            self.instance.binary_id.style(self.styles.binary_id),
-        )?;
-        fmt_write_test_name(self.instance.test_name, self.styles, f)
+        let binary_id = self.instance.binary_id.as_str();
+        let test_name = self.instance.test_name;
+        let (binary_id, test_name) = match self.max_width {
+            Some((max_width, ellipsis))
+                if binary_id.width() + 1 + test_name.width() > max_width =>
+            {
+                let name_budget =
+                    max_width.saturating_sub(binary_id.width().min(MIN_BINARY_ID_WIDTH) + 1);
+                let test_name = truncate_start(test_name, name_budget, ellipsis);
+                let binary_budget = max_width.saturating_sub(test_name.width() + 1);
+                (
+                    truncate_start(binary_id, binary_budget, ellipsis),
+                    test_name,
+                )
+            }
+            _ => (Cow::Borrowed(binary_id), Cow::Borrowed(test_name)),
+        };
+
+        write!(f, "{} ", binary_id.style(self.styles.binary_id))?;
+        fmt_write_test_name(&test_name, self.styles, f)
+    }
+}
+
+/// Truncates `s` to at most `max_width` terminal columns by removing
+/// characters from the start, and replacing them with `ellipsis`.
+///
+/// Wide characters, such as CJK ideographs and most emoji, take up two
+/// columns.
+pub(crate) fn truncate_start<'a>(s: &'a str, max_width: usize, ellipsis: &str) -> Cow<'a, str> {
+    if s.width() <= max_width {
+        return Cow::Borrowed(s);
+    }
+
+    let budget = max_width.saturating_sub(ellipsis.width());
+    let mut width = 0;
+    let mut start = s.len();
+    for (index, c) in s.char_indices().rev() {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width > budget {
+            break;
+        }
+        width += char_width;
+        start = index;
     }
+    Cow::Owned(format!("{ellipsis}{}", &s[start..]))
 }
 
 pub(crate) struct DisplayScriptInstance {
@@ -405,3 +473,44 @@ pub(crate) fn statically_unreachable() -> ! {
     }
     unreachable!("linker symbol above cannot be resolved")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nextest_metadata::RustBinaryId;
+
+    #[test]
+    fn test_truncate_start() {
+        assert_eq!(truncate_start("tests::foo", 10, "…"), "tests::foo");
+        assert_eq!(truncate_start("tests::foo", 8, "…"), "…ts::foo");
+        assert_eq!(truncate_start("tests::foo", 8, "..."), "...::foo");
+        // Each of these characters is two columns wide, so only two fit after
+        // the ellipsis.
+        assert_eq!(truncate_start("tests::テスト名", 6, "…"), "…ト名");
+        assert_eq!(truncate_start("tests::🦀🦀", 4, "…"), "…🦀");
+        assert_eq!(truncate_start("tests::foo", 0, "…"), "…");
+    }
+
+    #[test]
+    fn test_display_test_instance_max_width() {
+        let binary_id = RustBinaryId::new("my-crate::integration");
+        let instance = TestInstanceId {
+            binary_id: &binary_id,
+            test_name: "uploads::large::test_resume",
+        };
+        let styles = Styles::default();
+        let display = |max_width| {
+            DisplayTestInstance::new(instance, &styles)
+                .with_max_width(max_width, "…")
+                .to_string()
+        };
+
+        assert_eq!(
+            display(80),
+            "my-crate::integration uploads::large::test_resume"
+        );
+        // The test name is kept whole as long as part of the binary ID fits.
+        assert_eq!(display(40), "…integration uploads::large::test_resume");
+        assert_eq!(display(25), "…gration …ge::test_resume");
+    }
+}
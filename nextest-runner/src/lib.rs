@@ -13,8 +13,11 @@ pub mod cargo_config;
 pub mod config;
 #[cfg(feature = "experimental-tokio-console")]
 pub mod console;
+mod control;
 pub mod double_spawn;
 pub mod errors;
+pub mod flaky;
+mod fs_retry;
 mod helpers;
 pub mod indenter;
 pub mod input;
@@ -29,15 +32,19 @@ pub mod runner;
 // the tests in nextest-runner/tests/integration which depend on this to provide correct host and
 // target libdir.
 mod rustc_cli;
+pub mod safe_names;
 pub mod show_config;
 pub mod signal;
 pub mod target_runner;
 mod test_command;
 pub mod test_filter;
 pub mod test_output;
+pub mod theme;
 mod time;
 #[cfg(feature = "self-update")]
 pub mod update;
+pub mod user_config;
+pub mod warnings;
 pub mod write_str;
 
 pub use rustc_cli::RustcCli;
@@ -157,6 +157,38 @@ impl BinaryList {
     }
 }
 
+/// Computes the kind of test binary, and the platform it's built for, from a target's kinds.
+///
+/// `kind` must be non-empty.
+pub(super) fn test_binary_kind(kind: Vec<TargetKind>) -> (RustTestBinaryKind, BuildPlatform) {
+    if kind.iter().any(|k| {
+        // https://doc.rust-lang.org/nightly/cargo/reference/cargo-targets.html#the-crate-type-field
+        matches!(
+            k,
+            TargetKind::Lib
+                | TargetKind::RLib
+                | TargetKind::DyLib
+                | TargetKind::CDyLib
+                | TargetKind::StaticLib
+        )
+    }) {
+        (RustTestBinaryKind::LIB, BuildPlatform::Target)
+    } else if let Some(TargetKind::ProcMacro) = kind.first() {
+        (RustTestBinaryKind::PROC_MACRO, BuildPlatform::Host)
+    } else {
+        // Non-lib kinds should always have just one element. Grab the first one.
+        (
+            RustTestBinaryKind::new(
+                kind.into_iter()
+                    .next()
+                    .expect("caller checked that kind is non-empty")
+                    .to_string(),
+            ),
+            BuildPlatform::Target,
+        )
+    }
+}
+
 #[derive(Debug)]
 struct BinaryListBuildState<'g> {
     graph: &'g PackageGraph,
@@ -221,32 +253,7 @@ impl<'g> BinaryListBuildState<'g> {
                     });
                 }
 
-                let (computed_kind, platform) = if kind.iter().any(|k| {
-                    // https://doc.rust-lang.org/nightly/cargo/reference/cargo-targets.html#the-crate-type-field
-                    matches!(
-                        k,
-                        TargetKind::Lib
-                            | TargetKind::RLib
-                            | TargetKind::DyLib
-                            | TargetKind::CDyLib
-                            | TargetKind::StaticLib
-                    )
-                }) {
-                    (RustTestBinaryKind::LIB, BuildPlatform::Target)
-                } else if let Some(TargetKind::ProcMacro) = kind.first() {
-                    (RustTestBinaryKind::PROC_MACRO, BuildPlatform::Host)
-                } else {
-                    // Non-lib kinds should always have just one element. Grab the first one.
-                    (
-                        RustTestBinaryKind::new(
-                            kind.into_iter()
-                                .next()
-                                .expect("already checked that kind is non-empty")
-                                .to_string(),
-                        ),
-                        BuildPlatform::Target,
-                    )
-                };
+                let (computed_kind, platform) = test_binary_kind(kind);
 
                 // Construct the binary ID from the package and build target.
                 let id = RustBinaryId::from_parts(package.name(), &computed_kind, &name);
@@ -0,0 +1,734 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Listing tests by scanning source code, without linking test binaries.
+//!
+//! In large workspaces, linking test binaries can dominate the time it takes to list tests. This
+//! module supports a faster, inexact alternative: test targets are type-checked with `cargo check`,
+//! and their source files are scanned for `#[test]` functions.
+//!
+//! The scan doesn't expand macros or evaluate `cfg` attributes, so tests generated by macros (for
+//! example, by `test-case` or `rstest`) are missed, and tests disabled via `cfg` are included.
+
+use super::binary_list::test_binary_kind;
+use crate::{
+    errors::{FromMessagesError, WriteTestListError},
+    helpers::write_test_name,
+    indenter::indented,
+    list::{OutputFormat, Styles},
+    write_str::WriteStr,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use cargo_metadata::{Artifact, Message, TargetKind};
+use guppy::graph::PackageGraph;
+use nextest_metadata::{RustBinaryId, RustTestBinaryKind};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    io,
+};
+use tracing::warn;
+
+/// A list of tests obtained by scanning the source code of checked test targets.
+///
+/// Created by [`CheckedTestList::from_messages`].
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CheckedTestList {
+    /// The test suites found, keyed by binary ID.
+    pub rust_suites: BTreeMap<RustBinaryId, CheckedTestSuite>,
+}
+
+/// The tests found in the source code of a single test target.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CheckedTestSuite {
+    /// The package this target belongs to.
+    pub package_name: String,
+
+    /// The name of the target.
+    pub binary_name: String,
+
+    /// The kind of Rust test binary this target would be built into.
+    pub kind: RustTestBinaryKind,
+
+    /// The root source file of the target.
+    pub src_path: Utf8PathBuf,
+
+    /// The tests found, keyed by name.
+    pub test_cases: BTreeMap<String, CheckedTestCase>,
+}
+
+/// A test found by scanning source code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CheckedTestCase {
+    /// True if the test is marked `#[ignore]`.
+    pub ignored: bool,
+}
+
+impl CheckedTestList {
+    /// Parses Cargo messages from a `cargo check` invocation, and scans the source code of each
+    /// checked workspace target for tests.
+    pub fn from_messages(
+        reader: impl io::BufRead,
+        graph: &PackageGraph,
+    ) -> Result<Self, FromMessagesError> {
+        let mut list = Self::default();
+        for message in Message::parse_stream(reader) {
+            let message = message.map_err(FromMessagesError::ReadMessages)?;
+            if let Message::CompilerArtifact(artifact) = message {
+                list.process_artifact(artifact, graph)?;
+            }
+        }
+        Ok(list)
+    }
+
+    fn process_artifact(
+        &mut self,
+        artifact: Artifact,
+        graph: &PackageGraph,
+    ) -> Result<(), FromMessagesError> {
+        let package = graph
+            .metadata(&guppy::PackageId::new(artifact.package_id.repr))
+            .map_err(FromMessagesError::PackageGraph)?;
+        // Tests are only run for workspace packages.
+        if !package.in_workspace() {
+            return Ok(());
+        }
+
+        let name = artifact.target.name;
+        let kind = artifact.target.kind;
+        if kind.is_empty() {
+            return Err(FromMessagesError::MissingTargetKind {
+                package_name: package.name().to_owned(),
+                binary_name: name,
+            });
+        }
+        if kind.contains(&TargetKind::CustomBuild) {
+            return Ok(());
+        }
+
+        let (kind, _) = test_binary_kind(kind);
+        let binary_id = RustBinaryId::from_parts(package.name(), &kind, &name);
+        // Cargo may check a target both in test mode and as a dependency of other targets.
+        if self.rust_suites.contains_key(&binary_id) {
+            return Ok(());
+        }
+
+        let src_path = artifact.target.src_path;
+        let test_cases = scan_tests(&src_path);
+        self.rust_suites.insert(
+            binary_id,
+            CheckedTestSuite {
+                package_name: package.name().to_owned(),
+                binary_name: name,
+                kind,
+                src_path,
+                test_cases,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns the total number of tests across all suites.
+    pub fn test_count(&self) -> usize {
+        self.rust_suites
+            .values()
+            .map(|suite| suite.test_cases.len())
+            .sum()
+    }
+
+    /// Outputs this list to the given writer.
+    pub fn write(
+        &self,
+        output_format: OutputFormat,
+        writer: &mut dyn WriteStr,
+        colorize: bool,
+    ) -> Result<(), WriteTestListError> {
+        match output_format {
+            OutputFormat::Human { verbose } => self
+                .write_human(writer, verbose, colorize)
+                .map_err(WriteTestListError::Io),
+            OutputFormat::Serializable(format) => format.to_writer(self, writer),
+        }
+    }
+
+    fn write_human(
+        &self,
+        mut writer: &mut dyn WriteStr,
+        verbose: bool,
+        colorize: bool,
+    ) -> io::Result<()> {
+        let mut styles = Styles::default();
+        if colorize {
+            styles.colorize();
+        }
+
+        for (binary_id, suite) in &self.rust_suites {
+            if !verbose && suite.test_cases.is_empty() {
+                continue;
+            }
+
+            writeln!(writer, "{}:", binary_id.style(styles.binary_id))?;
+            if verbose {
+                writeln!(
+                    writer,
+                    "  {} {}",
+                    "src:".style(styles.field),
+                    suite.src_path
+                )?;
+            }
+
+            let mut indented = indented(writer).with_str("    ");
+            if suite.test_cases.is_empty() {
+                writeln!(indented, "(no tests)")?;
+            }
+            for (name, test_case) in &suite.test_cases {
+                write_test_name(name, &styles, &mut indented)?;
+                if verbose && test_case.ignored {
+                    write!(indented, " (ignored)")?;
+                }
+                writeln!(indented)?;
+            }
+            writer = indented.into_inner();
+        }
+        Ok(())
+    }
+}
+
+/// Scans the crate rooted at `src_path` for tests.
+///
+/// Modules that can't be read are skipped with a warning.
+fn scan_tests(src_path: &Utf8Path) -> BTreeMap<String, CheckedTestCase> {
+    let mut scanner = Scanner {
+        test_cases: BTreeMap::new(),
+        visited: HashSet::new(),
+    };
+    let mod_dir = src_path.parent().unwrap_or(Utf8Path::new("."));
+    scanner.scan_file(src_path, "", mod_dir);
+    scanner.test_cases
+}
+
+struct Scanner {
+    test_cases: BTreeMap<String, CheckedTestCase>,
+    // Guards against cycles introduced by #[path] attributes.
+    visited: HashSet<Utf8PathBuf>,
+}
+
+impl Scanner {
+    /// Scans the file at `path`, whose module path is `prefix` and whose child modules are looked
+    /// up in `mod_dir`.
+    fn scan_file(&mut self, path: &Utf8Path, prefix: &str, mod_dir: &Utf8Path) {
+        if !self.visited.insert(path.to_owned()) {
+            return;
+        }
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => {
+                warn!("skipping module at {path} while scanning for tests: {error}");
+                return;
+            }
+        };
+
+        let file_dir = path.parent().unwrap_or(Utf8Path::new("."));
+        for item in scan_source(&source) {
+            match item {
+                ScannedItem::Test {
+                    path: test_path,
+                    ignored,
+                } => {
+                    self.test_cases.insert(
+                        join_module_path(prefix, &test_path),
+                        CheckedTestCase { ignored },
+                    );
+                }
+                ScannedItem::ExternalModule {
+                    inline_path,
+                    name,
+                    path_attr,
+                } => {
+                    let module_path = join_module_path(prefix, &inline_path.join("::"));
+                    let module_path = join_module_path(&module_path, &name);
+                    let mut dir = mod_dir.to_owned();
+                    dir.extend(&inline_path);
+
+                    match path_attr {
+                        Some(path_attr) => {
+                            // Files included via #[path] are treated like mod.rs files.
+                            let base = if inline_path.is_empty() {
+                                file_dir
+                            } else {
+                                dir.as_path()
+                            };
+                            let file = base.join(path_attr);
+                            let child_dir = file.parent().unwrap_or(base).to_owned();
+                            self.scan_file(&file, &module_path, &child_dir);
+                        }
+                        None => {
+                            let child_dir = dir.join(&name);
+                            let candidates =
+                                [dir.join(format!("{name}.rs")), child_dir.join("mod.rs")];
+                            match candidates.iter().find(|file| file.is_file()) {
+                                Some(file) => self.scan_file(file, &module_path, &child_dir),
+                                None => warn!(
+                                    "skipping module `{module_path}` while scanning for tests: \
+                                     neither {} nor {} exists",
+                                    candidates[0], candidates[1],
+                                ),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn join_module_path(prefix: &str, rest: &str) -> String {
+    match (prefix.is_empty(), rest.is_empty()) {
+        (true, _) => rest.to_owned(),
+        (false, true) => prefix.to_owned(),
+        (false, false) => format!("{prefix}::{rest}"),
+    }
+}
+
+/// An item of interest found while scanning a source file.
+#[derive(Debug, Eq, PartialEq)]
+enum ScannedItem {
+    /// A test function, with its path relative to the file's module.
+    Test { path: String, ignored: bool },
+
+    /// A module declared as `mod name;`, within inline modules `inline_path`.
+    ExternalModule {
+        inline_path: Vec<String>,
+        name: String,
+        path_attr: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Punct(char),
+}
+
+/// Scans a single source file for test functions and out-of-line modules.
+fn scan_source(source: &str) -> Vec<ScannedItem> {
+    let tokens = tokenize(source);
+    let mut items = Vec::new();
+
+    // Each open brace is either an inline module, or some other block.
+    let mut blocks: Vec<Option<String>> = Vec::new();
+    // Outer attributes seen since the last item, as (path, tokens after the path).
+    let mut attrs: Vec<(Vec<String>, Vec<Token>)> = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Punct('#') => {
+                let inner = tokens.get(i + 1) == Some(&Token::Punct('!'));
+                let open = if inner { i + 2 } else { i + 1 };
+                if tokens.get(open) != Some(&Token::Punct('[')) {
+                    i += 1;
+                    continue;
+                }
+                let (attr, end) = parse_attr(&tokens, open + 1);
+                if !inner {
+                    attrs.push(attr);
+                }
+                i = end;
+                continue;
+            }
+            Token::Ident(ident) if ident == "mod" => {
+                if let Some(Token::Ident(name)) = tokens.get(i + 1) {
+                    match tokens.get(i + 2) {
+                        Some(Token::Punct(';')) => {
+                            if blocks.iter().all(Option::is_some) {
+                                let path_attr = attrs.iter().find_map(|(path, rest)| {
+                                    match (path.as_slice(), rest.as_slice()) {
+                                        ([path], [Token::Punct('='), Token::Str(value)])
+                                            if path == "path" =>
+                                        {
+                                            Some(value.clone())
+                                        }
+                                        _ => None,
+                                    }
+                                });
+                                items.push(ScannedItem::ExternalModule {
+                                    inline_path: blocks.iter().flatten().cloned().collect(),
+                                    name: name.clone(),
+                                    path_attr,
+                                });
+                            }
+                            attrs.clear();
+                            i += 3;
+                            continue;
+                        }
+                        Some(Token::Punct('{')) => {
+                            blocks.push(Some(name.clone()));
+                            attrs.clear();
+                            i += 3;
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Token::Ident(ident) if ident == "fn" => {
+                if let Some(Token::Ident(name)) = tokens.get(i + 1) {
+                    // Tests nested within functions or impl blocks aren't collected by libtest.
+                    let is_test = attrs
+                        .iter()
+                        .any(|(path, _)| path.last().is_some_and(|last| last == "test"));
+                    if is_test && blocks.iter().all(Option::is_some) {
+                        let ignored = attrs
+                            .iter()
+                            .any(|(path, _)| matches!(path.as_slice(), [path] if path == "ignore"));
+                        let mut path: Vec<&str> =
+                            blocks.iter().flatten().map(String::as_str).collect();
+                        path.push(name);
+                        items.push(ScannedItem::Test {
+                            path: path.join("::"),
+                            ignored,
+                        });
+                    }
+                    attrs.clear();
+                    i += 2;
+                    continue;
+                }
+            }
+            Token::Punct('{') => {
+                blocks.push(None);
+                attrs.clear();
+            }
+            Token::Punct('}') => {
+                blocks.pop();
+                attrs.clear();
+            }
+            Token::Punct(';') => {
+                attrs.clear();
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    items
+}
+
+/// Parses an attribute starting just after its opening `[`, returning the attribute and the index
+/// just past its closing `]`.
+fn parse_attr(tokens: &[Token], start: usize) -> ((Vec<String>, Vec<Token>), usize) {
+    let mut path = Vec::new();
+    let mut i = start;
+    while let Some(Token::Ident(segment)) = tokens.get(i) {
+        path.push(segment.clone());
+        i += 1;
+        if tokens.get(i) == Some(&Token::Punct(':'))
+            && tokens.get(i + 1) == Some(&Token::Punct(':'))
+        {
+            i += 2;
+        } else {
+            break;
+        }
+    }
+
+    let mut rest = Vec::new();
+    let mut depth = 1;
+    while let Some(token) = tokens.get(i) {
+        i += 1;
+        match token {
+            Token::Punct('[') => depth += 1,
+            Token::Punct(']') => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        rest.push(token.clone());
+    }
+
+    ((path, rest), i)
+}
+
+/// Splits source code into a simplified stream of tokens, skipping comments, numbers, lifetimes
+/// and character literals.
+fn tokenize(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            // Block comments can be nested.
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+        } else if c == '"' {
+            let (value, end) = read_str(&chars, i + 1);
+            tokens.push(Token::Str(value));
+            i = end;
+        } else if c == '\'' {
+            i = skip_char_or_lifetime(&chars, i);
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let ident: String = chars[start..i].iter().collect();
+
+            // Raw strings (r"..", br#".."#) and raw identifiers (r#ident).
+            if matches!(ident.as_str(), "r" | "br" | "cr") {
+                let hashes = chars[i..].iter().take_while(|&&c| c == '#').count();
+                if chars.get(i + hashes) == Some(&'"') {
+                    let (value, end) = read_raw_str(&chars, i + hashes + 1, hashes);
+                    tokens.push(Token::Str(value));
+                    i = end;
+                    continue;
+                }
+                if ident == "r" && hashes == 1 {
+                    let start = i + 1;
+                    i = start;
+                    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                        i += 1;
+                    }
+                    tokens.push(Token::Ident(chars[start..i].iter().collect()));
+                    continue;
+                }
+            }
+            // Byte and C strings (b"..", c"..") are treated like ordinary strings.
+            if matches!(ident.as_str(), "b" | "c") && chars.get(i) == Some(&'"') {
+                let (value, end) = read_str(&chars, i + 1);
+                tokens.push(Token::Str(value));
+                i = end;
+                continue;
+            }
+            tokens.push(Token::Ident(ident));
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+        } else {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// Reads a string literal starting just after the opening quote, returning its (unescaped only
+/// for simple escapes) value and the index just past the closing quote.
+fn read_str(chars: &[char], start: usize) -> (String, usize) {
+    let mut value = String::new();
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '"' => return (value, i + 1),
+            '\\' => {
+                if let Some(&escaped) = chars.get(i + 1) {
+                    value.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                }
+                i += 2;
+            }
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+    (value, i)
+}
+
+/// Reads a raw string literal starting just after the opening quote.
+fn read_raw_str(chars: &[char], start: usize, hashes: usize) -> (String, usize) {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '"'
+            && chars[i + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|&&c| c == '#')
+                .count()
+                == hashes
+        {
+            return (chars[start..i].iter().collect(), i + 1 + hashes);
+        }
+        i += 1;
+    }
+    (chars[start..].iter().collect(), i)
+}
+
+/// Skips a character literal or lifetime starting at the `'` at `start`.
+fn skip_char_or_lifetime(chars: &[char], start: usize) -> usize {
+    match (chars.get(start + 1), chars.get(start + 2)) {
+        // An escaped character literal, e.g. '\n' or '\u{1F600}'.
+        (Some('\\'), _) => {
+            let mut i = start + 3;
+            while i < chars.len() && chars[i] != '\'' {
+                i += 1;
+            }
+            i + 1
+        }
+        // A character literal, e.g. 'a' or '{'.
+        (Some(_), Some('\'')) => start + 3,
+        // A lifetime or label, e.g. 'a or 'static.
+        _ => {
+            let mut i = start + 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            i
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn test(path: &str, ignored: bool) -> ScannedItem {
+        ScannedItem::Test {
+            path: path.to_owned(),
+            ignored,
+        }
+    }
+
+    #[test]
+    fn test_scan_source() {
+        let source = indoc! {r##"
+            //! Crate docs with #[test] fn not_a_test() {}
+            #![cfg_attr(docsrs, feature(doc_cfg))]
+
+            mod external;
+            #[path = "other/file.rs"]
+            mod with_path;
+
+            #[test]
+            fn top_level() {
+                let _ = '{';
+                let _ = "}";
+                let _ = r#"" mod fake; "#;
+            }
+
+            /* #[test] fn commented_out() {} /* nested */ */
+
+            #[cfg(test)]
+            mod tests {
+                use super::*;
+
+                #[test]
+                #[ignore = "slow"]
+                fn ignored_test() {}
+
+                #[tokio::test]
+                async fn async_test<'a>() {}
+
+                fn helper() {
+                    #[test]
+                    fn nested_in_fn() {}
+                }
+
+                mod nested {
+                    mod deeper;
+
+                    #[test]
+                    pub(crate) fn in_nested() {}
+                }
+            }
+
+            impl Foo {
+                #[test]
+                fn in_impl() {}
+            }
+        "##};
+
+        assert_eq!(
+            scan_source(source),
+            vec![
+                ScannedItem::ExternalModule {
+                    inline_path: vec![],
+                    name: "external".to_owned(),
+                    path_attr: None,
+                },
+                ScannedItem::ExternalModule {
+                    inline_path: vec![],
+                    name: "with_path".to_owned(),
+                    path_attr: Some("other/file.rs".to_owned()),
+                },
+                test("top_level", false),
+                test("tests::ignored_test", true),
+                test("tests::async_test", false),
+                ScannedItem::ExternalModule {
+                    inline_path: vec!["tests".to_owned(), "nested".to_owned()],
+                    name: "deeper".to_owned(),
+                    path_attr: None,
+                },
+                test("tests::nested::in_nested", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_tests() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        let root = dir.path().join("lib.rs");
+        std::fs::write(&root, "mod a;\nmod b;\n#[test]\nfn root_test() {}\n").unwrap();
+        std::fs::write(
+            dir.path().join("a.rs"),
+            "mod child;\n#[test]\n#[ignore]\nfn a_test() {}\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.path().join("a")).unwrap();
+        std::fs::write(
+            dir.path().join("a/child.rs"),
+            "#[test]\nfn child_test() {}\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.path().join("b")).unwrap();
+        std::fs::write(dir.path().join("b/mod.rs"), "#[test]\nfn b_test() {}\n").unwrap();
+
+        let test_cases = scan_tests(&root);
+        assert_eq!(
+            test_cases.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("a::a_test".to_owned(), CheckedTestCase { ignored: true }),
+                (
+                    "a::child::child_test".to_owned(),
+                    CheckedTestCase { ignored: false }
+                ),
+                ("b::b_test".to_owned(), CheckedTestCase { ignored: false }),
+                ("root_test".to_owned(), CheckedTestCase { ignored: false }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,406 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Showing how Cargo features affect the set of test targets.
+//!
+//! Cargo skips test targets whose `required-features` aren't enabled, without a warning. This
+//! module computes, using only Cargo metadata, which features are enabled for each workspace
+//! package under a feature selection, and which test targets are built as a result -- both for the
+//! current selection and for the default features.
+
+use crate::{
+    errors::WriteTestListError,
+    list::{OutputFormat, Styles},
+    write_str::WriteStr,
+};
+use guppy::graph::{
+    feature::{named_feature_filter, StandardFeatures},
+    BuildTargetId, BuildTargetKind, PackageGraph, PackageMetadata,
+};
+use nextest_metadata::{RustBinaryId, RustTestBinaryKind};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+};
+
+/// A feature selection, as passed to Cargo with `--features`, `--all-features`, and
+/// `--no-default-features`.
+#[derive(Clone, Debug, Default)]
+pub struct FeatureSelection {
+    /// Features to activate. Each entry is either a feature name, or `package/feature`.
+    pub features: Vec<String>,
+
+    /// Whether all features are activated.
+    pub all_features: bool,
+
+    /// Whether the `default` feature is not activated.
+    pub no_default_features: bool,
+}
+
+impl FeatureSelection {
+    /// Returns true if this is the same as the default feature selection.
+    pub fn is_default(&self) -> bool {
+        self.features.is_empty() && !self.all_features && !self.no_default_features
+    }
+
+    fn base(&self) -> StandardFeatures {
+        if self.all_features {
+            StandardFeatures::All
+        } else if self.no_default_features {
+            StandardFeatures::None
+        } else {
+            StandardFeatures::Default
+        }
+    }
+
+    /// Returns the named features requested for the given package.
+    fn features_for<'a>(&'a self, package_name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        // Features can be separated by spaces or commas within a single argument.
+        self.features
+            .iter()
+            .flat_map(|features| features.split([' ', ',']))
+            .filter_map(move |feature| match feature.split_once('/') {
+                Some((package, feature)) => (package == package_name).then_some(feature),
+                None => (!feature.is_empty()).then_some(feature),
+            })
+    }
+}
+
+/// For each test target in a set of workspace packages, the features that affect it and whether
+/// it is built.
+///
+/// Created by [`FeatureMatrix::new`].
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FeatureMatrix {
+    /// The test targets, keyed by binary ID.
+    pub rust_suites: BTreeMap<RustBinaryId, FeatureMatrixSuite>,
+}
+
+/// Feature information for a single test target.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FeatureMatrixSuite {
+    /// The package this target belongs to.
+    pub package_name: String,
+
+    /// The name of the target.
+    pub binary_name: String,
+
+    /// The kind of Rust test binary this target would be built into.
+    pub kind: RustTestBinaryKind,
+
+    /// The features defined by the package, any of which can affect how this target is compiled.
+    pub features: BTreeSet<String>,
+
+    /// The features this target requires in order to be built.
+    pub required_features: Vec<String>,
+
+    /// The features of the package enabled by the current selection.
+    pub enabled_features: BTreeSet<String>,
+
+    /// The features of the package enabled by default.
+    pub default_features: BTreeSet<String>,
+
+    /// Required features that aren't enabled by the current selection.
+    pub missing_features: Vec<String>,
+
+    /// How the current selection changes whether this target is built, compared to the default
+    /// features.
+    pub status: FeatureMatrixStatus,
+}
+
+impl FeatureMatrixSuite {
+    /// Returns true if this target is built with the current feature selection.
+    pub fn is_built(&self) -> bool {
+        self.missing_features.is_empty()
+    }
+}
+
+/// Whether a test target is built with the current feature selection, compared to the default
+/// features.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeatureMatrixStatus {
+    /// The target is built with both the current selection and the default features.
+    Built,
+
+    /// The target is built with the current selection, but not with the default features.
+    Added,
+
+    /// The target is built with the default features, but not with the current selection.
+    Removed,
+
+    /// The target is built with neither the current selection nor the default features.
+    NotBuilt,
+}
+
+impl FeatureMatrixStatus {
+    fn new(built: bool, built_by_default: bool) -> Self {
+        match (built, built_by_default) {
+            (true, true) => Self::Built,
+            (true, false) => Self::Added,
+            (false, true) => Self::Removed,
+            (false, false) => Self::NotBuilt,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Built => "built",
+            Self::Added => "built (not built with default features)",
+            Self::Removed => "not built (built with default features)",
+            Self::NotBuilt => "not built",
+        }
+    }
+}
+
+impl FeatureMatrix {
+    /// Computes the feature matrix for the given workspace packages.
+    ///
+    /// If `packages` is empty, all workspace members are included. Packages in `exclude` are
+    /// skipped.
+    pub fn new(
+        graph: &PackageGraph,
+        packages: &[String],
+        exclude: &[String],
+        selection: &FeatureSelection,
+    ) -> Self {
+        let default_selection = FeatureSelection::default();
+        let mut rust_suites = BTreeMap::new();
+
+        for package in graph.workspace().iter() {
+            let name = package.name();
+            if (!packages.is_empty() && !packages.iter().any(|p| p == name))
+                || exclude.iter().any(|p| p == name)
+            {
+                continue;
+            }
+
+            let features: BTreeSet<_> = package.named_features().map(str::to_owned).collect();
+            let enabled_features = resolve_features(graph, package, selection);
+            // Avoid resolving features twice in the common case where the selection is the
+            // default.
+            let default_features = if selection.is_default() {
+                enabled_features.clone()
+            } else {
+                resolve_features(graph, package, &default_selection)
+            };
+
+            for target in package.build_targets() {
+                let (kind, target_name) = match target.id() {
+                    BuildTargetId::Library => {
+                        let kind = if matches!(target.kind(), BuildTargetKind::ProcMacro) {
+                            RustTestBinaryKind::PROC_MACRO
+                        } else {
+                            RustTestBinaryKind::LIB
+                        };
+                        (kind, target.name())
+                    }
+                    BuildTargetId::Binary(name) => (RustTestBinaryKind::BIN, name),
+                    BuildTargetId::Test(name) => (RustTestBinaryKind::TEST, name),
+                    // Other targets aren't tested by default.
+                    _ => continue,
+                };
+
+                let required_features = target.required_features().to_vec();
+                let missing_features = missing(&required_features, &enabled_features);
+                let built_by_default = missing(&required_features, &default_features).is_empty();
+                let status =
+                    FeatureMatrixStatus::new(missing_features.is_empty(), built_by_default);
+
+                rust_suites.insert(
+                    RustBinaryId::from_parts(name, &kind, target_name),
+                    FeatureMatrixSuite {
+                        package_name: name.to_owned(),
+                        binary_name: target_name.to_owned(),
+                        kind,
+                        features: features.clone(),
+                        required_features,
+                        enabled_features: enabled_features.clone(),
+                        default_features: default_features.clone(),
+                        missing_features,
+                        status,
+                    },
+                );
+            }
+        }
+
+        Self { rust_suites }
+    }
+
+    /// Outputs this matrix to the given writer.
+    pub fn write(
+        &self,
+        output_format: OutputFormat,
+        writer: &mut dyn WriteStr,
+        colorize: bool,
+    ) -> Result<(), WriteTestListError> {
+        match output_format {
+            OutputFormat::Human { verbose } => self
+                .write_human(writer, verbose, colorize)
+                .map_err(WriteTestListError::Io),
+            OutputFormat::Serializable(format) => format.to_writer(self, writer),
+        }
+    }
+
+    fn write_human(
+        &self,
+        writer: &mut dyn WriteStr,
+        verbose: bool,
+        colorize: bool,
+    ) -> io::Result<()> {
+        let mut styles = Styles::default();
+        if colorize {
+            styles.colorize();
+        }
+
+        for (binary_id, suite) in &self.rust_suites {
+            writeln!(
+                writer,
+                "{}: {}",
+                binary_id.style(styles.binary_id),
+                suite.status.as_str()
+            )?;
+            if !suite.required_features.is_empty() {
+                writeln!(
+                    writer,
+                    "    {} {}",
+                    "required features:".style(styles.field),
+                    suite.required_features.join(", ")
+                )?;
+            }
+            if !suite.missing_features.is_empty() {
+                writeln!(
+                    writer,
+                    "    {} {}",
+                    "missing features:".style(styles.field),
+                    suite.missing_features.join(", ")
+                )?;
+            }
+
+            let added: Vec<_> = suite
+                .enabled_features
+                .difference(&suite.default_features)
+                .map(|feature| format!("+{feature}"))
+                .collect();
+            let removed: Vec<_> = suite
+                .default_features
+                .difference(&suite.enabled_features)
+                .map(|feature| format!("-{feature}"))
+                .collect();
+            if !added.is_empty() || !removed.is_empty() {
+                writeln!(
+                    writer,
+                    "    {} {}",
+                    "compared to default features:".style(styles.field),
+                    added
+                        .into_iter()
+                        .chain(removed)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )?;
+            }
+
+            if verbose {
+                writeln!(
+                    writer,
+                    "    {} {}",
+                    "features:".style(styles.field),
+                    display_features(&suite.features, &suite.enabled_features)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the named features of `package` enabled by `selection`.
+fn resolve_features(
+    graph: &PackageGraph,
+    package: PackageMetadata<'_>,
+    selection: &FeatureSelection,
+) -> BTreeSet<String> {
+    let query = graph
+        .query_forward([package.id()])
+        .expect("package ID is from this graph");
+    let filter = named_feature_filter(selection.base(), selection.features_for(package.name()));
+    let feature_set = query.to_feature_query(filter).resolve();
+    feature_set
+        .features_for(package.id())
+        .expect("package ID is from this graph")
+        .map(|list| list.named_features().map(str::to_owned).collect())
+        .unwrap_or_default()
+}
+
+fn missing(required: &[String], enabled: &BTreeSet<String>) -> Vec<String> {
+    required
+        .iter()
+        .filter(|feature| !enabled.contains(*feature))
+        .cloned()
+        .collect()
+}
+
+// Marks enabled features with a `*`.
+fn display_features(features: &BTreeSet<String>, enabled: &BTreeSet<String>) -> String {
+    if features.is_empty() {
+        return "(none)".to_owned();
+    }
+    features
+        .iter()
+        .map(|feature| {
+            if enabled.contains(feature) {
+                format!("{feature}*")
+            } else {
+                feature.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features_for() {
+        let selection = FeatureSelection {
+            features: vec!["a,b".to_owned(), "c foo/d".to_owned(), "bar/e".to_owned()],
+            all_features: false,
+            no_default_features: false,
+        };
+        assert_eq!(
+            selection.features_for("foo").collect::<Vec<_>>(),
+            ["a", "b", "c", "d"]
+        );
+        assert_eq!(
+            selection.features_for("bar").collect::<Vec<_>>(),
+            ["a", "b", "c", "e"]
+        );
+        assert!(!selection.is_default());
+        assert!(FeatureSelection::default().is_default());
+    }
+
+    #[test]
+    fn test_status() {
+        assert_eq!(
+            FeatureMatrixStatus::new(true, true),
+            FeatureMatrixStatus::Built
+        );
+        assert_eq!(
+            FeatureMatrixStatus::new(true, false),
+            FeatureMatrixStatus::Added
+        );
+        assert_eq!(
+            FeatureMatrixStatus::new(false, true),
+            FeatureMatrixStatus::Removed
+        );
+        assert_eq!(
+            FeatureMatrixStatus::new(false, false),
+            FeatureMatrixStatus::NotBuilt
+        );
+    }
+}
@@ -8,13 +8,17 @@
 //! * [`BinaryList`] for test binaries
 
 mod binary_list;
+mod check_list;
 mod display_filter;
+mod feature_matrix;
 mod output_format;
 mod rust_build_meta;
 mod test_list;
 
 pub use binary_list::*;
+pub use check_list::*;
 pub(crate) use display_filter::*;
+pub use feature_matrix::*;
 pub use output_format::*;
 pub use rust_build_meta::*;
 pub use test_list::*;
@@ -7,6 +7,7 @@ use crate::{
     list::{BinaryListState, TestListState},
     platform::{BuildPlatforms, TargetPlatform},
     reuse_build::PathMapper,
+    warnings::{self, WarningCode},
 };
 use camino::Utf8PathBuf;
 use itertools::Itertools;
@@ -15,7 +16,6 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     marker::PhantomData,
 };
-use tracing::warn;
 
 /// Rust-related metadata used for builds and test runs.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -124,7 +124,10 @@ impl RustBuildMeta<TestListState> {
             .map(|libdir| libdir.to_path_buf())
             .collect::<Vec<_>>();
         if libdirs.is_empty() {
-            warn!("failed to detect the rustc libdir, may fail to list or run tests");
+            warnings::emit(
+                WarningCode::RustcLibdirNotFound,
+                format_args!("failed to detect the rustc libdir, may fail to list or run tests"),
+            );
         }
 
         // Cargo puts linked paths before base output directories.
@@ -4,11 +4,12 @@
 use super::{DisplayFilterMatcher, TestListDisplayFilter};
 use crate::{
     cargo_config::EnvironmentMap,
+    config::WrapperPreset,
     double_spawn::DoubleSpawnInfo,
-    errors::{CreateTestListError, FromMessagesError, WriteTestListError},
+    errors::{CreateTestListError, ErrorList, FromMessagesError, WriteTestListError},
     helpers::{convert_build_platform, dylib_path, dylib_path_envvar, write_test_name},
     indenter::indented,
-    list::{BinaryList, OutputFormat, RustBuildMeta, Styles, TestListState},
+    list::{BinaryList, OutputFormat, RustBuildMeta, SerializableFormat, Styles, TestListState},
     reuse_build::PathMapper,
     target_runner::{PlatformRunner, TargetRunner},
     test_command::{LocalExecuteContext, TestCommand},
@@ -21,22 +22,28 @@ use guppy::{
     graph::{PackageGraph, PackageMetadata},
     PackageId,
 };
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use newtype_uuid::{TypedUuid, TypedUuidKind, TypedUuidTag};
 use nextest_filtering::{BinaryQuery, EvalContext, TestQuery};
 use nextest_metadata::{
-    BuildPlatform, FilterMatch, MismatchReason, RustBinaryId, RustNonTestBinaryKind,
-    RustTestBinaryKind, RustTestBinarySummary, RustTestCaseSummary, RustTestSuiteStatusSummary,
-    RustTestSuiteSummary, TestListSummary,
+    BuildPlatform, FilterMatch, MismatchReason, RustBinaryId, RustBuildMetaSummary,
+    RustNonTestBinaryKind, RustTestBinaryKind, RustTestBinarySummary, RustTestCaseSummary,
+    RustTestSuiteStatusSummary, RustTestSuiteSummary, TestListSummary,
 };
 use owo_colors::OwoColorize;
+use quick_junit::ReportUuid;
+use serde::{Deserialize, Serialize, Serializer};
 use std::{
     collections::{BTreeMap, BTreeSet},
     ffi::{OsStr, OsString},
     fmt, io,
     path::PathBuf,
     sync::{Arc, OnceLock},
+    time::Duration,
 };
 use tokio::runtime::Runtime;
 use tracing::debug;
+use xxhash_rust::xxh64::xxh64;
 
 /// A Rust test binary built by Cargo. This artifact hasn't been run yet so there's no information
 /// about the tests within it.
@@ -231,6 +238,7 @@ impl<'g> TestList<'g> {
         ecx: &EvalContext<'_>,
         bound: FilterBound,
         list_threads: usize,
+        show_progress: bool,
     ) -> Result<Self, CreateTestListError>
     where
         I: IntoIterator<Item = RustTestArtifact<'g>>,
@@ -251,6 +259,9 @@ impl<'g> TestList<'g> {
 
         let runtime = Runtime::new().map_err(CreateTestListError::TokioRuntimeCreate)?;
 
+        let test_artifacts: Vec<_> = test_artifacts.into_iter().collect();
+        let progress = ListProgressBar::new(test_artifacts.len(), show_progress);
+
         let stream = futures::stream::iter(test_artifacts).map(|test_binary| {
             async {
                 let binary_match = filter.filter_binary_match(&test_binary, ecx, bound);
@@ -270,7 +281,7 @@ impl<'g> TestList<'g> {
                             ecx,
                             bound,
                             non_ignored.as_str(),
-                            ignored.as_str(),
+                            &ignored,
                         )?;
                         Ok::<_, CreateTestListError>((bin, info))
                     }
@@ -281,14 +292,37 @@ impl<'g> TestList<'g> {
                 }
             }
         });
-        let fut = stream.buffer_unordered(list_threads).try_collect();
+        // Binaries are listed to completion even if some of them fail, so that all failures can be
+        // reported at once.
+        let fut = stream
+            .buffer_unordered(list_threads)
+            .inspect(|_| progress.binary_listed())
+            .collect::<Vec<_>>();
 
-        let rust_suites: BTreeMap<_, _> = runtime.block_on(fut)?;
+        let results = runtime.block_on(fut);
+        progress.finish();
 
         // Ensure that the runtime doesn't stay hanging even if a custom test framework misbehaves
         // (can be an issue on Windows).
         runtime.shutdown_background();
 
+        let mut rust_suites = BTreeMap::new();
+        let mut errors = Vec::new();
+        for result in results {
+            match result {
+                Ok((binary_id, suite)) => {
+                    rust_suites.insert(binary_id, suite);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        if errors.len() == 1 {
+            return Err(errors.pop().expect("errors has one element"));
+        }
+        if let Some(errors) = ErrorList::new("while listing tests", errors) {
+            return Err(CreateTestListError::MultipleBinaries(errors));
+        }
+
         let test_count = rust_suites
             .values()
             .map(|suite| suite.status.test_count())
@@ -339,7 +373,7 @@ impl<'g> TestList<'g> {
                             ecx,
                             bound,
                             non_ignored.as_ref(),
-                            ignored.as_ref(),
+                            &ListedIgnored::Terse(ignored.as_ref().to_owned()),
                         )?;
                         test_count += info.status.test_count();
                         Ok((bin, info))
@@ -489,10 +523,36 @@ impl<'g> TestList<'g> {
             OutputFormat::Human { verbose } => self
                 .write_human(writer, verbose, colorize)
                 .map_err(WriteTestListError::Io),
-            OutputFormat::Serializable(format) => format.to_writer(&self.to_summary(), writer),
+            OutputFormat::Serializable(format) => self.write_serializable(format, false, writer),
         }
     }
 
+    /// Outputs this list to the given writer in a machine-readable format.
+    ///
+    /// The output matches the serialized form of [`Self::to_summary`], but is produced directly
+    /// from this list without building an intermediate summary. This matters for very large test
+    /// lists.
+    ///
+    /// If `compact` is true, the reasons that tests don't match filters are omitted. Compact
+    /// output can still be parsed as a [`TestListSummary`], with each omitted reason becoming
+    /// [`MismatchReason::Unknown`].
+    pub fn write_serializable(
+        &self,
+        format: SerializableFormat,
+        compact: bool,
+        writer: &mut dyn WriteStr,
+    ) -> Result<(), WriteTestListError> {
+        let summary = TestListSummaryRef {
+            rust_build_meta: self.rust_build_meta.to_summary(),
+            test_count: self.test_count,
+            rust_suites: RustSuitesRef {
+                rust_suites: &self.rust_suites,
+                compact,
+            },
+        };
+        format.to_writer(&summary, writer)
+    }
+
     /// Iterates over all the test suites.
     pub fn iter(&self) -> impl Iterator<Item = &RustTestSuite> + '_ {
         self.rust_suites.values()
@@ -573,7 +633,7 @@ impl<'g> TestList<'g> {
         ecx: &EvalContext<'_>,
         bound: FilterBound,
         non_ignored: impl AsRef<str>,
-        ignored: impl AsRef<str>,
+        ignored: &ListedIgnored,
     ) -> Result<(RustBinaryId, RustTestSuite<'g>), CreateTestListError> {
         let mut test_cases = BTreeMap::new();
 
@@ -585,6 +645,7 @@ impl<'g> TestList<'g> {
                 test_name.into(),
                 RustTestCaseSummary {
                     ignored: false,
+                    ignore_reason: None,
                     filter_match: non_ignored_filter.filter_match(
                         &test_binary,
                         test_name,
@@ -597,7 +658,21 @@ impl<'g> TestList<'g> {
         }
 
         let mut ignored_filter = filter.build();
-        for test_name in Self::parse(&test_binary.binary_id, ignored.as_ref())? {
+        let ignored_tests = match ignored {
+            ListedIgnored::Terse(output) => Self::parse(&test_binary.binary_id, output)?
+                .into_iter()
+                .map(|test_name| (test_name, None))
+                .collect(),
+            ListedIgnored::WithReasons(tests) => {
+                let mut tests: Vec<_> = tests
+                    .iter()
+                    .map(|(test_name, reason)| (test_name.as_str(), reason.as_deref()))
+                    .collect();
+                tests.sort_unstable();
+                tests
+            }
+        };
+        for (test_name, ignore_reason) in ignored_tests {
             // Note that libtest prints out:
             // * just ignored tests if --ignored is passed in
             // * all tests, both ignored and non-ignored, if --ignored is not passed in
@@ -606,6 +681,7 @@ impl<'g> TestList<'g> {
                 test_name.into(),
                 RustTestCaseSummary {
                     ignored: true,
+                    ignore_reason: ignore_reason.map(str::to_owned),
                     filter_match: ignored_filter.filter_match(
                         &test_binary,
                         test_name,
@@ -661,6 +737,45 @@ impl<'g> TestList<'g> {
         })
     }
 
+    /// Parses the output of `--list --format json` and returns the ignored
+    /// tests, along with the reasons they're ignored.
+    ///
+    /// Returns `None` if the output isn't a JSON listing, for example because
+    /// the test binary predates it and printed out a terse listing instead.
+    fn parse_json(list_output: &str) -> Option<Vec<(String, Option<String>)>> {
+        // The output is in the form:
+        // { "type": "suite", "event": "discovery" }
+        // { "type": "test", "event": "discovered", "name": "<test name>", "ignore": <bool>, "ignore_message": "<reason>", ... }
+        // ...
+        // { "type": "suite", "event": "completed", ... }
+        #[derive(Deserialize)]
+        struct ListEvent {
+            event: String,
+            name: Option<String>,
+            #[serde(default)]
+            ignore: bool,
+            ignore_message: Option<String>,
+        }
+
+        let mut discovery = false;
+        let mut ignored = Vec::new();
+        for line in list_output.lines().filter(|line| !line.trim().is_empty()) {
+            let event: ListEvent = serde_json::from_str(line).ok()?;
+            match (event.event.as_str(), event.name) {
+                ("discovery", _) => discovery = true,
+                ("discovered", Some(name)) if event.ignore => {
+                    // libtest prints an empty message for tests ignored
+                    // without a reason.
+                    let reason = event.ignore_message.filter(|message| !message.is_empty());
+                    ignored.push((name, reason));
+                }
+                _ => {}
+            }
+        }
+
+        discovery.then_some(ignored)
+    }
+
     /// Writes this test list out in a human-friendly format.
     pub fn write_human(
         &self,
@@ -750,7 +865,12 @@ impl<'g> TestList<'g> {
                                 }
                                 (true, false) => {
                                     write_test_name(name, &styles, &mut indented)?;
-                                    writeln!(indented, " (skipped)")?;
+                                    match info.skipped_ignore_reason() {
+                                        Some(reason) => {
+                                            writeln!(indented, " (skipped, ignored: {reason})")?
+                                        }
+                                        None => writeln!(indented, " (skipped)")?,
+                                    }
                                 }
                                 (false, false) => {
                                     // Skip printing this test entirely if it isn't a match.
@@ -810,7 +930,7 @@ impl RustTestArtifact<'_> {
         &self,
         lctx: &LocalExecuteContext<'_>,
         target_runner: &TargetRunner,
-    ) -> Result<(String, String), CreateTestListError> {
+    ) -> Result<(String, ListedIgnored), CreateTestListError> {
         // This error situation has been known to happen with reused builds. It produces
         // a really terrible and confusing "file not found" message if allowed to prceed.
         if !self.cwd.is_dir() {
@@ -821,16 +941,49 @@ impl RustTestArtifact<'_> {
         }
         let platform_runner = target_runner.for_build_platform(self.build_platform);
 
-        let non_ignored = self.exec_single(false, lctx, platform_runner);
-        let ignored = self.exec_single(true, lctx, platform_runner);
+        let non_ignored = self.exec_single(ListMode::NonIgnored, lctx, platform_runner);
+        let ignored = self.exec_ignored(lctx, platform_runner);
 
         let (non_ignored_out, ignored_out) = futures::future::join(non_ignored, ignored).await;
         Ok((non_ignored_out?, ignored_out?))
     }
 
+    /// Lists the ignored tests in this binary.
+    ///
+    /// Test binaries that can list tests as JSON, such as libtest and
+    /// libtest-mimic-style harnesses, also report why tests are ignored. Other
+    /// binaries are listed in the terse format.
+    async fn exec_ignored(
+        &self,
+        lctx: &LocalExecuteContext<'_>,
+        runner: Option<&PlatformRunner>,
+    ) -> Result<ListedIgnored, CreateTestListError> {
+        match self.exec_single(ListMode::Json, lctx, runner).await {
+            Ok(output) => {
+                if let Some(ignored) = TestList::parse_json(&output) {
+                    return Ok(ListedIgnored::WithReasons(ignored));
+                }
+                debug!(
+                    "JSON listing isn't supported, falling back to terse listing: {}",
+                    self.binary_id,
+                );
+            }
+            Err(error) => {
+                debug!(
+                    "JSON listing failed, falling back to terse listing: {}: {error}",
+                    self.binary_id,
+                );
+            }
+        }
+
+        self.exec_single(ListMode::Ignored, lctx, runner)
+            .await
+            .map(ListedIgnored::Terse)
+    }
+
     async fn exec_single(
         &self,
-        ignored: bool,
+        mode: ListMode,
         lctx: &LocalExecuteContext<'_>,
         runner: Option<&PlatformRunner>,
     ) -> Result<String, CreateTestListError> {
@@ -849,12 +1002,9 @@ impl RustTestArtifact<'_> {
             self.binary_path.clone().into()
         };
 
-        argv.extend(["--list", "--format", "terse"]);
-        if ignored {
-            argv.push("--ignored");
-        }
+        argv.extend(mode.args());
 
-        let cmd = TestCommand::new(
+        let mut cmd = TestCommand::new(
             lctx,
             program.clone(),
             &argv,
@@ -862,6 +1012,11 @@ impl RustTestArtifact<'_> {
             &self.package,
             &self.non_test_binaries,
         );
+        if mode == ListMode::Json {
+            // JSON listing is unstable in libtest, so this lets test binaries
+            // built with a stable toolchain accept `-Z unstable-options`.
+            cmd.command_mut().env("RUSTC_BOOTSTRAP", "1");
+        }
 
         let output =
             cmd.wait_with_output()
@@ -897,6 +1052,39 @@ impl RustTestArtifact<'_> {
     }
 }
 
+/// The way a test binary is run to list its tests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ListMode {
+    /// List tests that aren't ignored, in the terse format.
+    NonIgnored,
+
+    /// List ignored tests, in the terse format.
+    Ignored,
+
+    /// List all tests along with whether they're ignored, in the JSON format.
+    Json,
+}
+
+impl ListMode {
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            Self::NonIgnored => &["--list", "--format", "terse"],
+            Self::Ignored => &["--list", "--format", "terse", "--ignored"],
+            Self::Json => &["-Z", "unstable-options", "--list", "--format", "json"],
+        }
+    }
+}
+
+/// The ignored tests in a test binary, as obtained by listing it.
+#[derive(Clone, Debug)]
+enum ListedIgnored {
+    /// The output of listing ignored tests in the terse format.
+    Terse(String),
+
+    /// Ignored tests and the reasons they're ignored, from a JSON listing.
+    WithReasons(Vec<(String, Option<String>)>),
+}
+
 /// Serializable information about the status of and test cases within a test suite.
 ///
 /// Part of a [`RustTestSuiteSummary`].
@@ -945,17 +1133,22 @@ impl RustTestSuiteStatus {
         RustTestSuiteStatusSummary,
         BTreeMap<String, RustTestCaseSummary>,
     ) {
+        let test_cases = match self {
+            Self::Listed { test_cases } => test_cases.clone(),
+            Self::Skipped { .. } => BTreeMap::new(),
+        };
+        (self.status_summary(), test_cases)
+    }
+
+    fn status_summary(&self) -> RustTestSuiteStatusSummary {
         match self {
-            Self::Listed { test_cases } => (RustTestSuiteStatusSummary::LISTED, test_cases.clone()),
+            Self::Listed { .. } => RustTestSuiteStatusSummary::LISTED,
             Self::Skipped {
                 reason: BinaryMismatchReason::Expression,
-            } => (RustTestSuiteStatusSummary::SKIPPED, BTreeMap::new()),
+            } => RustTestSuiteStatusSummary::SKIPPED,
             Self::Skipped {
                 reason: BinaryMismatchReason::DefaultSet,
-            } => (
-                RustTestSuiteStatusSummary::SKIPPED_DEFAULT_FILTER,
-                BTreeMap::new(),
-            ),
+            } => RustTestSuiteStatusSummary::SKIPPED_DEFAULT_FILTER,
         }
     }
 }
@@ -1016,7 +1209,10 @@ impl<'a> TestInstance<'a> {
         &self,
         ctx: &TestExecuteContext<'_>,
         test_list: &TestList<'_>,
+        wrapper: WrapperPreset,
         extra_args: &[String],
+        libtest_json: bool,
+        audit_prefix: &[String],
     ) -> TestCommand {
         let platform_runner = ctx
             .target_runner
@@ -1028,18 +1224,48 @@ impl<'a> TestInstance<'a> {
         let program: String = match platform_runner {
             Some(runner) => {
                 args.extend(runner.args());
+                args.extend(wrapper.command_prefix());
                 args.push(self.suite_info.binary_path.as_str());
                 runner.binary().into()
             }
-            None => self.suite_info.binary_path.to_owned().into(),
+            None => match wrapper.command_prefix().split_first() {
+                Some((wrapper_program, wrapper_args)) => {
+                    args.extend(wrapper_args);
+                    args.push(self.suite_info.binary_path.as_str());
+                    (*wrapper_program).to_owned()
+                }
+                None => self.suite_info.binary_path.to_owned().into(),
+            },
         };
 
         args.extend(["--exact", self.name, "--nocapture"]);
         if self.test_info.ignored {
             args.push("--ignored");
         }
+        if libtest_json {
+            // The JSON format is unstable, so this relies on RUSTC_BOOTSTRAP,
+            // which is set by the executor.
+            args.extend([
+                "-Z",
+                "unstable-options",
+                "--format",
+                "json",
+                "--report-time",
+            ]);
+        }
         args.extend(extra_args.iter().map(String::as_str));
 
+        // Access auditing wraps the whole command, including any target runner or wrapper.
+        let (program, args) = match audit_prefix.split_first() {
+            Some((audit_program, audit_args)) => {
+                let mut wrapped_args: Vec<&str> = audit_args.iter().map(String::as_str).collect();
+                wrapped_args.push(&program);
+                wrapped_args.extend(args);
+                (audit_program.clone(), wrapped_args)
+            }
+            None => (program, args),
+        };
+
         let lctx = LocalExecuteContext {
             rust_build_meta: &test_list.rust_build_meta,
             double_spawn: ctx.double_spawn,
@@ -1076,6 +1302,42 @@ impl fmt::Display for TestInstanceId<'_> {
     }
 }
 
+impl TestInstanceId<'_> {
+    /// Returns a UUID identifying this test within the run `run_id`.
+    ///
+    /// The UUID is derived from the run ID, the binary ID and the test name, so it's the same across
+    /// attempts of the test, and can be recomputed by external tools that know those three values.
+    /// It is exposed to tests as `NEXTEST_TEST_ID`.
+    pub fn uuid(&self, run_id: ReportUuid) -> TestUuid {
+        let mut input = Vec::new();
+        input.extend_from_slice(run_id.to_string().as_bytes());
+        input.push(0);
+        input.extend_from_slice(self.binary_id.as_str().as_bytes());
+        input.push(0);
+        input.extend_from_slice(self.test_name.as_bytes());
+
+        let hash = (u128::from(xxh64(&input, 0)) << 64) | u128::from(xxh64(&input, 1));
+        // Mark this as a version 8 (custom) UUID with the RFC 9562 variant.
+        let hash = (hash & !(0xf << 76)) | (0x8 << 76);
+        let hash = (hash & !(0b11 << 62)) | (0b10 << 62);
+        TestUuid::from_u128(hash)
+    }
+}
+
+/// The kind of [`TestUuid`].
+pub enum TestKind {}
+
+impl TypedUuidKind for TestKind {
+    #[inline]
+    fn tag() -> TypedUuidTag {
+        const TAG: TypedUuidTag = TypedUuidTag::new("nextest_test");
+        TAG
+    }
+}
+
+/// A UUID identifying a test within a run, returned by [`TestInstanceId::uuid`].
+pub type TestUuid = TypedUuid<TestKind>;
+
 /// Context required for test execution.
 #[derive(Clone, Debug)]
 pub struct TestExecuteContext<'a> {
@@ -1086,12 +1348,176 @@ pub struct TestExecuteContext<'a> {
     pub target_runner: &'a TargetRunner,
 }
 
+// ---
+// Borrowed serialization
+// ---
+//
+// These types mirror the layout of `TestListSummary` and its children, but borrow from the test
+// list rather than cloning it. `test_parse_test_list` checks that the two produce identical
+// output.
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TestListSummaryRef<'a, 'g> {
+    rust_build_meta: RustBuildMetaSummary,
+    test_count: usize,
+    rust_suites: RustSuitesRef<'a, 'g>,
+}
+
+struct RustSuitesRef<'a, 'g> {
+    rust_suites: &'a BTreeMap<RustBinaryId, RustTestSuite<'g>>,
+    compact: bool,
+}
+
+impl Serialize for RustSuitesRef<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.rust_suites.iter().map(|(binary_id, test_suite)| {
+            (
+                binary_id,
+                RustTestSuiteSummaryRef {
+                    package_name: test_suite.package.name(),
+                    binary_id: &test_suite.binary_id,
+                    binary_name: &test_suite.binary_name,
+                    package_id: test_suite.package.id().repr(),
+                    kind: &test_suite.kind,
+                    binary_path: &test_suite.binary_path,
+                    build_platform: test_suite.build_platform,
+                    cwd: &test_suite.cwd,
+                    status: test_suite.status.status_summary(),
+                    test_cases: TestCasesRef {
+                        status: &test_suite.status,
+                        compact: self.compact,
+                    },
+                },
+            )
+        }))
+    }
+}
+
+// The binary fields are inlined here in the same order as `RustTestBinarySummary`, which is
+// flattened into `RustTestSuiteSummary`.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct RustTestSuiteSummaryRef<'a> {
+    package_name: &'a str,
+    binary_id: &'a RustBinaryId,
+    binary_name: &'a str,
+    package_id: &'a str,
+    kind: &'a RustTestBinaryKind,
+    binary_path: &'a Utf8Path,
+    build_platform: BuildPlatform,
+    cwd: &'a Utf8Path,
+    status: RustTestSuiteStatusSummary,
+    #[serde(rename = "testcases")]
+    test_cases: TestCasesRef<'a>,
+}
+
+struct TestCasesRef<'a> {
+    status: &'a RustTestSuiteStatus,
+    compact: bool,
+}
+
+impl Serialize for TestCasesRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.status.test_cases().map(|(name, case)| {
+            (
+                name,
+                RustTestCaseSummaryRef {
+                    ignored: case.ignored,
+                    ignore_reason: case.ignore_reason.as_deref(),
+                    filter_match: FilterMatchRef {
+                        filter_match: case.filter_match,
+                        compact: self.compact,
+                    },
+                },
+            )
+        }))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct RustTestCaseSummaryRef<'a> {
+    ignored: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ignore_reason: Option<&'a str>,
+    filter_match: FilterMatchRef,
+}
+
+struct FilterMatchRef {
+    filter_match: FilterMatch,
+    compact: bool,
+}
+
+impl Serialize for FilterMatchRef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // In compact mode, mismatches are written without a reason.
+        #[derive(Serialize)]
+        #[serde(rename_all = "kebab-case", tag = "status")]
+        enum CompactFilterMatch {
+            Matches,
+            Mismatch,
+        }
+
+        if !self.compact {
+            return self.filter_match.serialize(serializer);
+        }
+        match self.filter_match {
+            FilterMatch::Matches => CompactFilterMatch::Matches,
+            FilterMatch::Mismatch { .. } => CompactFilterMatch::Mismatch,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A progress bar shown on stderr while test binaries are listed.
+struct ListProgressBar {
+    bar: Option<ProgressBar>,
+}
+
+impl ListProgressBar {
+    fn new(binary_count: usize, show_progress: bool) -> Self {
+        // Some CI environments pretend to be a terminal, so don't show the progress bar there.
+        if !show_progress || binary_count == 0 || is_ci::uncached() {
+            return Self { bar: None };
+        }
+
+        let width = binary_count.to_string().len();
+        let bar = ProgressBar::with_draw_target(
+            Some(binary_count as u64),
+            ProgressDrawTarget::stderr_with_hz(20),
+        );
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template(&format!(
+                    "{{prefix:>12.green.bold}} [{{elapsed_precise:>9}}] {{wide_bar}} \
+                     {{pos:>{width}}}/{{len:{width}}} binaries listed"
+                ))
+                .expect("template is known to be valid"),
+        );
+        bar.set_prefix("Listing");
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Self { bar: Some(bar) }
+    }
+
+    fn binary_listed(&self) {
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+    }
+
+    fn finish(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         cargo_config::{TargetDefinitionLocation, TargetTriple, TargetTripleSource},
-        list::SerializableFormat,
         platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
         test_filter::{RunIgnored, TestFilterPatterns},
     };
@@ -1212,26 +1638,32 @@ mod tests {
                         test_cases: btreemap! {
                             "tests::foo::test_bar".to_owned() => RustTestCaseSummary {
                                 ignored: false,
+                                ignore_reason: None,
                                 filter_match: FilterMatch::Matches,
                             },
                             "tests::baz::test_quux".to_owned() => RustTestCaseSummary {
                                 ignored: false,
+                                ignore_reason: None,
                                 filter_match: FilterMatch::Matches,
                             },
                             "benches::bench_foo".to_owned() => RustTestCaseSummary {
                                 ignored: false,
+                                ignore_reason: None,
                                 filter_match: FilterMatch::Matches,
                             },
                             "tests::ignored::test_bar".to_owned() => RustTestCaseSummary {
                                 ignored: true,
+                                ignore_reason: None,
                                 filter_match: FilterMatch::Mismatch { reason: MismatchReason::Ignored },
                             },
                             "tests::baz::test_ignored".to_owned() => RustTestCaseSummary {
                                 ignored: true,
+                                ignore_reason: None,
                                 filter_match: FilterMatch::Mismatch { reason: MismatchReason::Ignored },
                             },
                             "benches::ignored_bench_foo".to_owned() => RustTestCaseSummary {
                                 ignored: true,
+                                ignore_reason: None,
                                 filter_match: FilterMatch::Mismatch { reason: MismatchReason::Ignored },
                             },
                         },
@@ -1418,6 +1850,195 @@ mod tests {
                 .expect("json-pretty succeeded"),
             EXPECTED_JSON_PRETTY
         );
+
+        // The borrowed serialization must match serializing the summary.
+        assert_eq!(
+            serde_json::to_string_pretty(&test_list.to_summary()).expect("summary serialized"),
+            EXPECTED_JSON_PRETTY
+        );
+
+        // Compact output drops mismatch reasons, but is still parseable.
+        let mut compact = String::new();
+        test_list
+            .write_serializable(SerializableFormat::Json, true, &mut compact)
+            .expect("compact json succeeded");
+        let summary = TestListSummary::parse_json(&compact).expect("compact json parsed");
+        let test_cases =
+            &summary.rust_suites[&RustBinaryId::new("fake-package::fake-binary")].test_cases;
+        assert_eq!(
+            test_cases["benches::bench_foo"].filter_match,
+            FilterMatch::Matches
+        );
+        assert_eq!(
+            test_cases["tests::ignored::test_bar"].filter_match,
+            FilterMatch::Mismatch {
+                reason: MismatchReason::Unknown
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_json_list() {
+        let json_output = indoc! {r#"
+            { "type": "suite", "event": "discovery" }
+            { "type": "test", "event": "discovered", "name": "tests::foo", "ignore": false, "ignore_message": "" }
+            { "type": "test", "event": "discovered", "name": "tests::slow", "ignore": true, "ignore_message": "takes an hour" }
+            { "type": "test", "event": "discovered", "name": "tests::bar", "ignore": true, "ignore_message": "" }
+            { "type": "bench", "event": "discovered", "name": "benches::baz", "ignore": true, "ignore_message": "" }
+            { "type": "suite", "event": "completed", "tests": 3, "benchmarks": 1, "total": 4, "ignored": 3 }
+        "#};
+        assert_eq!(
+            TestList::parse_json(json_output),
+            Some(vec![
+                ("tests::slow".to_owned(), Some("takes an hour".to_owned())),
+                ("tests::bar".to_owned(), None),
+                ("benches::baz".to_owned(), None),
+            ]),
+        );
+
+        // Older toolchains print a terse listing even if JSON is requested.
+        let terse_output = indoc! {"
+            tests::foo: test
+            tests::slow: test
+        "};
+        assert_eq!(TestList::parse_json(terse_output), None);
+    }
+
+    #[test]
+    fn test_instance_id_uuid() {
+        let binary_id = RustBinaryId::new("my-crate");
+        let id = TestInstanceId {
+            binary_id: &binary_id,
+            test_name: "tests::foo",
+        };
+        let other_id = TestInstanceId {
+            binary_id: &binary_id,
+            test_name: "tests::bar",
+        };
+        let run_id = ReportUuid::new_v4();
+        let other_run_id = ReportUuid::new_v4();
+
+        let uuid = id.uuid(run_id);
+        assert_eq!(uuid, id.uuid(run_id), "UUIDs are deterministic");
+        assert_ne!(uuid, other_id.uuid(run_id), "UUIDs differ across tests");
+        assert_ne!(uuid, id.uuid(other_run_id), "UUIDs differ across runs");
+
+        let uuid = uuid.to_string();
+        assert_eq!(&uuid[14..15], "8", "version 8 UUID: {uuid}");
+        assert!(
+            matches!(&uuid[19..20], "8" | "9" | "a" | "b"),
+            "RFC 9562 variant: {uuid}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_errors_are_aggregated() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A fake test binary that lists one test, and doesn't support JSON listing.
+        let dir = camino_tempfile::tempdir().unwrap();
+        let binary_path = dir.path().join("fake-binary");
+        std::fs::write(
+            &binary_path,
+            indoc! {r#"
+                #!/bin/sh
+                case "$*" in
+                    *json*) exit 1 ;;
+                    *--ignored*) ;;
+                    *) echo "tests::foo: test" ;;
+                esac
+            "#},
+        )
+        .unwrap();
+        std::fs::set_permissions(&binary_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let artifact = |name: &str, cwd: &Utf8Path| RustTestArtifact {
+            binary_path: binary_path.clone(),
+            cwd: cwd.to_owned(),
+            package: package_metadata(),
+            binary_name: name.to_owned(),
+            binary_id: RustBinaryId::new(&format!("fake-package::{name}")),
+            kind: RustTestBinaryKind::TEST,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        };
+        let missing_cwd = dir.path().join("missing");
+
+        let test_filter = TestFilterBuilder::default_set(RunIgnored::Default);
+        let ecx = EvalContext {
+            default_filter: &CompiledExpr::ALL,
+        };
+        let double_spawn = DoubleSpawnInfo::disabled();
+        let target_runner = TargetRunner::empty();
+        let ctx = TestExecuteContext {
+            double_spawn: &double_spawn,
+            target_runner: &target_runner,
+        };
+        let make_test_list = |artifacts: Vec<RustTestArtifact<'static>>| {
+            let build_platforms = BuildPlatforms {
+                host: HostPlatform {
+                    platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                    libdir: PlatformLibdir::Unavailable(PlatformLibdirUnavailable::new_const(
+                        "test",
+                    )),
+                },
+                target: None,
+            };
+            TestList::new(
+                &ctx,
+                artifacts,
+                RustBuildMeta::new("/fake", build_platforms).map_paths(&PathMapper::noop()),
+                &test_filter,
+                dir.path().to_owned(),
+                EnvironmentMap::empty(),
+                &ecx,
+                FilterBound::All,
+                2,
+                false,
+            )
+        };
+
+        let test_list = make_test_list(vec![
+            artifact("first", dir.path()),
+            artifact("second", dir.path()),
+        ])
+        .expect("binaries are listed");
+        assert_eq!(test_list.test_count(), 2, "one test in each binary");
+
+        // A single failure is returned as is.
+        let error = make_test_list(vec![
+            artifact("first", dir.path()),
+            artifact("broken", &missing_cwd),
+        ])
+        .expect_err("listing fails");
+        assert!(
+            matches!(error, CreateTestListError::CwdIsNotDir { .. }),
+            "single error is returned directly: {error:?}"
+        );
+
+        // Multiple failures are all reported, rather than just the first one.
+        let error = make_test_list(vec![
+            artifact("broken-1", &missing_cwd),
+            artifact("first", dir.path()),
+            artifact("broken-2", &missing_cwd),
+        ])
+        .expect_err("listing fails");
+        let CreateTestListError::MultipleBinaries(errors) = error else {
+            panic!("multiple errors are aggregated: {error:?}");
+        };
+        let mut binary_ids: Vec<_> = errors
+            .iter()
+            .map(|error| match error {
+                CreateTestListError::CwdIsNotDir { binary_id, .. } => binary_id.as_str(),
+                other => panic!("unexpected error: {other:?}"),
+            })
+            .collect();
+        binary_ids.sort_unstable();
+        assert_eq!(
+            binary_ids,
+            ["fake-package::broken-1", "fake-package::broken-2"]
+        );
     }
 
     static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
@@ -4,22 +4,27 @@
 //! Code to generate JUnit XML reports from test events.
 
 use crate::{
-    config::{JunitConfig, ScriptId},
+    config::{JunitConfig, JunitSplitBy, ScriptId},
     errors::{DisplayErrorChain, WriteEventError},
+    fs_retry::retry_transient,
     list::TestInstanceId,
     reporter::{
-        events::{ExecutionDescription, ExecutionResult, TestEvent, TestEventKind, UnitKind},
-        UnitErrorDescription,
+        events::{
+            ExecutionDescription, ExecutionResult, SkipCounts, TestEvent, TestEventKind, UnitKind,
+        },
+        AttemptOutputDiff, UnitErrorDescription,
     },
+    safe_names::safe_name_component,
     test_output::{ChildExecutionOutput, ChildOutput},
 };
+use camino::{Utf8Path, Utf8PathBuf};
 use debug_ignore::DebugIgnore;
 use indexmap::IndexMap;
 use nextest_metadata::RustBinaryId;
 use quick_junit::{
     NonSuccessKind, Report, TestCase, TestCaseStatus, TestRerun, TestSuite, XmlString,
 };
-use std::{fmt, fs::File};
+use std::{collections::HashMap, fmt};
 
 static STDOUT_STDERR_COMBINED: &str = "(stdout and stderr are combined)";
 static STDOUT_NOT_CAPTURED: &str = "(stdout not captured)";
@@ -30,6 +35,7 @@ static PROCESS_FAILED_TO_START: &str = "(process failed to start)";
 pub(super) struct MetadataJunit<'cfg> {
     config: JunitConfig<'cfg>,
     test_suites: DebugIgnore<IndexMap<SuiteKey<'cfg>, TestSuite>>,
+    skip_counts: HashMap<&'cfg RustBinaryId, SkipCounts>,
 }
 
 impl<'cfg> MetadataJunit<'cfg> {
@@ -37,6 +43,7 @@ impl<'cfg> MetadataJunit<'cfg> {
         Self {
             config,
             test_suites: DebugIgnore(IndexMap::new()),
+            skip_counts: HashMap::new(),
         }
     }
 
@@ -115,6 +122,7 @@ impl<'cfg> MetadataJunit<'cfg> {
                 run_statuses,
                 junit_store_success_output,
                 junit_store_failure_output,
+                tracking_issue,
                 ..
             } => {
                 let testsuite = self.testsuite_for_test(test_instance.id());
@@ -177,10 +185,49 @@ impl<'cfg> MetadataJunit<'cfg> {
                     store_stdout_stderr,
                     TestcaseOrRerun::Testcase(&mut testcase),
                 );
+                for (key, value) in &main_status.annotations {
+                    testcase.add_property((format!("annotation:{key}"), value.clone()));
+                }
 
                 testsuite.add_test_case(testcase);
+
+                // Record the failure class as a property of the suite, keyed by test name.
+                if let Some(failure_class) = &main_status.failure_class {
+                    testsuite.add_property((
+                        format!("failure-class:{}", test_instance.name),
+                        failure_class.clone(),
+                    ));
+                }
+                if !is_success {
+                    if let Some(tracking_issue) = tracking_issue {
+                        testsuite.add_property((
+                            format!("tracking-issue:{}", test_instance.name),
+                            tracking_issue.clone(),
+                        ));
+                    }
+                }
+                // Since the diff contains output, only record it if failure
+                // output is being stored.
+                if junit_store_failure_output {
+                    if let Some(diff) = AttemptOutputDiff::for_description(&run_statuses.describe())
+                    {
+                        testsuite.add_property((
+                            format!("attempt-diff:{}", test_instance.name),
+                            diff.diff().to_owned(),
+                        ));
+                    }
+                }
             }
-            TestEventKind::TestSkipped { .. } => {
+            TestEventKind::TestSkipped {
+                test_instance,
+                reason,
+            } => {
+                // Skipped tests are counted, by reason, as properties of their binary's suite.
+                self.skip_counts
+                    .entry(&test_instance.suite_info.binary_id)
+                    .or_default()
+                    .record(reason);
+
                 // TODO: report skipped tests? causes issues if we want to aggregate runs across
                 // skipped and non-skipped tests. Probably needs to be made configurable.
 
@@ -192,38 +239,60 @@ impl<'cfg> MetadataJunit<'cfg> {
                 //
                 // testsuite.add_testcase(testcase);
             }
-            TestEventKind::RunBeginCancel { .. } | TestEventKind::RunBeginKill { .. } => {}
+            TestEventKind::RunBeginCancel { .. }
+            | TestEventKind::ScopeBeginCancel { .. }
+            | TestEventKind::RunBeginKill { .. } => {}
+            TestEventKind::ConcurrencyReduced { .. }
+            | TestEventKind::ConcurrencyRestored { .. } => {}
             TestEventKind::RunFinished {
                 run_id,
                 start_time,
                 elapsed,
                 ..
             } => {
-                // Write out the report to the given file.
-                let mut report = Report::new(self.config.report_name());
-                report
-                    .set_report_uuid(run_id)
-                    .set_timestamp(start_time)
-                    .set_time(elapsed)
-                    .add_test_suites(self.test_suites.drain(..).map(|(_, testsuite)| testsuite));
+                let make_report = |test_suites: Vec<TestSuite>| {
+                    let mut report = Report::new(self.config.report_name());
+                    report
+                        .set_report_uuid(run_id)
+                        .set_timestamp(start_time)
+                        .set_time(elapsed)
+                        .add_test_suites(test_suites);
+                    report
+                };
+
+                // Suites are only created for binaries with tests that ran, so binaries where
+                // every test was skipped aren't reported.
+                for (key, suite) in self.test_suites.iter_mut() {
+                    if let SuiteKey::TestBinary(binary_id) = key {
+                        if let Some(skip_counts) = self.skip_counts.get(binary_id) {
+                            for (reason, count) in skip_counts.iter() {
+                                suite
+                                    .add_property((format!("skipped:{reason}"), count.to_string()));
+                            }
+                        }
+                    }
+                }
 
                 let junit_path = self.config.path();
-                let junit_dir = junit_path.parent().expect("junit path must have a parent");
-                std::fs::create_dir_all(junit_dir).map_err(|error| WriteEventError::Fs {
-                    file: junit_dir.to_path_buf(),
-                    error,
-                })?;
-
-                let f = File::create(junit_path).map_err(|error| WriteEventError::Fs {
-                    file: junit_path.to_path_buf(),
-                    error,
-                })?;
-                report
-                    .serialize(f)
-                    .map_err(|error| WriteEventError::Junit {
-                        file: junit_path.to_path_buf(),
-                        error,
-                    })?;
+                let split_by = self.config.split_by();
+                if split_by == JunitSplitBy::None {
+                    // Write out the report to the given file.
+                    let test_suites = self.test_suites.drain(..).map(|(_, suite)| suite);
+                    write_report(junit_path, &make_report(test_suites.collect()))?;
+                } else {
+                    // Write out one report per binary or package, next to the given file.
+                    let mut reports: IndexMap<String, Vec<TestSuite>> = IndexMap::new();
+                    for (key, suite) in self.test_suites.drain(..) {
+                        reports
+                            .entry(key.split_key(split_by))
+                            .or_default()
+                            .push(suite);
+                    }
+                    for (split_key, test_suites) in reports {
+                        let path = split_report_path(junit_path, &split_key);
+                        write_report(&path, &make_report(test_suites))?;
+                    }
+                }
             }
         }
 
@@ -252,6 +321,58 @@ enum SuiteKey<'cfg> {
     TestBinary(&'cfg RustBinaryId),
 }
 
+impl SuiteKey<'_> {
+    /// Returns the key for the split report this suite belongs to.
+    ///
+    /// Setup scripts aren't part of any binary or package, so each gets its own report.
+    fn split_key(&self, split_by: JunitSplitBy) -> String {
+        match (self, split_by) {
+            (SuiteKey::TestBinary(binary_id), JunitSplitBy::Package) => {
+                binary_id.components().package_name.to_owned()
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+fn split_report_path(junit_path: &Utf8Path, split_key: &str) -> Utf8PathBuf {
+    let stem = junit_path.file_stem().unwrap_or("junit");
+    // The stem and `-` come first, so the file name can't be a reserved device name on Windows.
+    let mut file_name = format!("{stem}-{}", safe_name_component(split_key));
+    if let Some(extension) = junit_path.extension() {
+        file_name.push('.');
+        file_name.push_str(extension);
+    }
+    junit_path.with_file_name(file_name)
+}
+
+fn write_report(junit_path: &Utf8Path, report: &Report) -> Result<(), WriteEventError> {
+    let junit_dir = junit_path.parent().expect("junit path must have a parent");
+
+    // Serialize the report up front, so that writing it out can be retried on transient errors
+    // without redoing any work.
+    let mut buf = Vec::new();
+    report
+        .serialize(&mut buf)
+        .map_err(|error| WriteEventError::Junit {
+            file: junit_path.to_path_buf(),
+            error,
+        })?;
+
+    retry_transient(junit_dir, || std::fs::create_dir_all(junit_dir)).map_err(|error| {
+        WriteEventError::Fs {
+            file: junit_dir.to_path_buf(),
+            error,
+        }
+    })?;
+    retry_transient(junit_path, || std::fs::write(junit_path, &buf)).map_err(|error| {
+        WriteEventError::Fs {
+            file: junit_path.to_path_buf(),
+            error,
+        }
+    })
+}
+
 impl fmt::Display for SuiteKey<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -286,6 +407,18 @@ fn non_success_kind_and_type(kind: UnitKind, result: ExecutionResult) -> (NonSuc
             leaked: false,
         } => (NonSuccessKind::Failure, format!("{kind} failure")),
         ExecutionResult::Timeout => (NonSuccessKind::Failure, format!("{kind} timeout")),
+        ExecutionResult::MemoryError { tool } => (
+            NonSuccessKind::Failure,
+            format!("{kind} memory error ({tool})"),
+        ),
+        ExecutionResult::CancelAcknowledged => (
+            NonSuccessKind::Failure,
+            format!("{kind} cancelled (cooperative)"),
+        ),
+        ExecutionResult::AccessViolation => (
+            NonSuccessKind::Failure,
+            format!("{kind} contacted disallowed hosts"),
+        ),
         ExecutionResult::ExecFail => (NonSuccessKind::Error, "execution failure".to_owned()),
         ExecutionResult::Leak => (
             NonSuccessKind::Error,
@@ -408,6 +541,27 @@ mod tests {
     use bytes::Bytes;
     use std::{io, sync::Arc};
 
+    #[test]
+    fn test_split_reports() {
+        let binary_id = RustBinaryId::new("my-crate::bin/foo");
+        let key = SuiteKey::TestBinary(&binary_id);
+        assert_eq!(key.split_key(JunitSplitBy::Package), "my-crate");
+        assert_eq!(key.split_key(JunitSplitBy::Binary), "my-crate::bin/foo");
+
+        let junit_path = Utf8Path::new("target/nextest/ci/junit.xml");
+        assert_eq!(
+            split_report_path(junit_path, "my-crate"),
+            "target/nextest/ci/junit-my-crate.xml"
+        );
+        let path = split_report_path(junit_path, "my-crate::bin/foo");
+        assert!(
+            path.as_str()
+                .starts_with("target/nextest/ci/junit-my-crate__bin_foo-"),
+            "binary ID is sanitized: {path}"
+        );
+        assert_eq!(path.extension(), Some("xml"));
+    }
+
     #[test]
     fn test_set_execute_status_props() {
         let cases = [
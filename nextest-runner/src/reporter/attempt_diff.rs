@@ -0,0 +1,202 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::events::{ExecuteStatus, ExecutionDescription};
+use crate::test_output::{ChildExecutionOutput, ChildOutput, ChildSingleOutput};
+use similar::TextDiff;
+
+/// A unified diff between the outputs of two failed attempts of a test.
+///
+/// If a retried test fails again, comparing its last two attempts helps tell
+/// deterministic failures, where the output is the same every time, apart from
+/// flaky ones.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AttemptOutputDiff {
+    from_attempt: usize,
+    to_attempt: usize,
+    diff: String,
+}
+
+impl AttemptOutputDiff {
+    /// Compares the last two attempts of a test that failed every time it was
+    /// run.
+    ///
+    /// Returns `None` if the test passed, wasn't retried, or if either
+    /// attempt's output wasn't captured.
+    pub fn for_description(describe: &ExecutionDescription<'_>) -> Option<Self> {
+        match describe {
+            ExecutionDescription::Failure {
+                first_status,
+                last_status,
+                retries,
+            } => {
+                let previous = match retries {
+                    [] => return None,
+                    [_] => *first_status,
+                    [.., previous, _] => previous,
+                };
+                Self::new(previous, last_status)
+            }
+            ExecutionDescription::Success { .. } | ExecutionDescription::Flaky { .. } => None,
+        }
+    }
+
+    /// Compares the outputs of two attempts.
+    ///
+    /// Returns `None` if either attempt's output wasn't captured, or if one
+    /// attempt's stdout and stderr were combined but the other's weren't.
+    pub fn new(old: &ExecuteStatus, new: &ExecuteStatus) -> Option<Self> {
+        let (old_output, new_output) = match (&old.output, &new.output) {
+            (
+                ChildExecutionOutput::Output {
+                    output: old_output, ..
+                },
+                ChildExecutionOutput::Output {
+                    output: new_output, ..
+                },
+            ) => (old_output, new_output),
+            _ => return None,
+        };
+
+        let from_attempt = old.retry_data.attempt;
+        let to_attempt = new.retry_data.attempt;
+        let mut diff = String::new();
+        let mut diff_stream = |name: &str, old: &ChildSingleOutput, new: &ChildSingleOutput| {
+            if old.buf != new.buf {
+                let text_diff = TextDiff::from_lines(old.as_str_lossy(), new.as_str_lossy());
+                diff.push_str(
+                    &text_diff
+                        .unified_diff()
+                        .header(
+                            &format!("try {from_attempt} {name}"),
+                            &format!("try {to_attempt} {name}"),
+                        )
+                        .to_string(),
+                );
+            }
+        };
+
+        match (old_output, new_output) {
+            (ChildOutput::Split(old_split), ChildOutput::Split(new_split)) => {
+                if let (Some(old), Some(new)) = (&old_split.stdout, &new_split.stdout) {
+                    diff_stream("stdout", old, new);
+                }
+                if let (Some(old), Some(new)) = (&old_split.stderr, &new_split.stderr) {
+                    diff_stream("stderr", old, new);
+                }
+            }
+            (ChildOutput::Combined { output: old }, ChildOutput::Combined { output: new }) => {
+                diff_stream("output", old, new);
+            }
+            _ => return None,
+        }
+
+        Some(Self {
+            from_attempt,
+            to_attempt,
+            diff,
+        })
+    }
+
+    /// Returns the attempt being compared against.
+    pub fn from_attempt(&self) -> usize {
+        self.from_attempt
+    }
+
+    /// Returns the attempt being compared.
+    pub fn to_attempt(&self) -> usize {
+        self.to_attempt
+    }
+
+    /// Returns true if the outputs of both attempts are the same.
+    pub fn is_identical(&self) -> bool {
+        self.diff.is_empty()
+    }
+
+    /// Returns the unified diff, one section per output stream that changed.
+    ///
+    /// This is empty if the outputs are the same.
+    pub fn diff(&self) -> &str {
+        &self.diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        reporter::events::{CgroupLimitsStatus, ExecutionResult, ExecutionStatuses, RetryData},
+        test_output::ChildSplitOutput,
+    };
+    use bytes::Bytes;
+    use chrono::Local;
+    use std::{collections::BTreeMap, time::Duration};
+
+    #[test]
+    fn attempt_output_diff() {
+        let statuses = ExecutionStatuses::new(vec![
+            make_status(1, "running\nleft: 4\n", "panicked\n"),
+            make_status(2, "running\nleft: 3\n", "panicked\n"),
+            make_status(3, "running\nleft: 3\n", "panicked\n"),
+        ]);
+        let diff = AttemptOutputDiff::for_description(&statuses.describe())
+            .expect("failing test with retries has a diff");
+        assert_eq!(diff.from_attempt(), 2);
+        assert_eq!(diff.to_attempt(), 3);
+        assert!(diff.is_identical(), "last two attempts are identical");
+
+        let old = make_status(1, "running\nleft: 4\n", "panicked\n");
+        let new = make_status(2, "running\nleft: 3\n", "panicked\n");
+        let diff = AttemptOutputDiff::new(&old, &new).expect("both outputs captured");
+        assert!(!diff.is_identical());
+        assert_eq!(
+            diff.diff(),
+            "--- try 1 stdout\n+++ try 2 stdout\n@@ -1,2 +1,2 @@\n running\n-left: 4\n+left: 3\n",
+        );
+
+        // Tests that weren't retried don't have a diff.
+        let statuses = ExecutionStatuses::new(vec![make_status(1, "", "")]);
+        assert_eq!(
+            AttemptOutputDiff::for_description(&statuses.describe()),
+            None
+        );
+    }
+
+    fn make_status(attempt: usize, stdout: &str, stderr: &str) -> ExecuteStatus {
+        ExecuteStatus {
+            retry_data: RetryData {
+                attempt,
+                total_attempts: 3,
+            },
+            output: ChildExecutionOutput::Output {
+                result: Some(ExecutionResult::Fail {
+                    abort_status: None,
+                    leaked: false,
+                }),
+                output: ChildOutput::Split(ChildSplitOutput {
+                    stdout: Some(Bytes::from(stdout.to_owned()).into()),
+                    stderr: Some(Bytes::from(stderr.to_owned()).into()),
+                }),
+                errors: None,
+            },
+            result: ExecutionResult::Fail {
+                abort_status: None,
+                leaked: false,
+            },
+            failure_class: None,
+            annotations: BTreeMap::new(),
+            exec_time: None,
+            startup_time: None,
+            start_time: Local::now().into(),
+            time_taken: Duration::from_secs(1),
+            is_slow: false,
+            delay_before_start: Duration::ZERO,
+            cgroup_limits: CgroupLimitsStatus::NotRequested,
+            kept_tmpdir: None,
+            kept_workdir: None,
+            access_report: None,
+            access_violations: Vec::new(),
+            crash_dump: None,
+        }
+    }
+}
@@ -0,0 +1,502 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Comparing the JUnit reports of two runs.
+//!
+//! [`ReportDiff`] lists tests that started failing or passing between two runs, tests that became
+//! significantly slower, and how the output of tests failing in both runs changed.
+
+use super::junit_xml::{parse_time, read_report, Element};
+use crate::{
+    errors::{JunitReportError, WriteTestListError},
+    list::OutputFormat,
+    write_str::WriteStr,
+};
+use camino::Utf8Path;
+use owo_colors::{OwoColorize, Style};
+use serde::Serialize;
+use similar::TextDiff;
+use std::{collections::BTreeMap, fmt, io};
+
+/// Parameters for [`ReportDiff`].
+#[derive(Clone, Copy, Debug)]
+pub struct ReportDiffParams {
+    /// Report tests that got slower by more than this fraction of their old time.
+    ///
+    /// For example, 0.5 reports tests that took more than 50% longer.
+    pub threshold: f64,
+}
+
+impl Default for ReportDiffParams {
+    fn default() -> Self {
+        Self { threshold: 0.5 }
+    }
+}
+
+/// Differences between the JUnit reports of two runs.
+///
+/// Setup scripts aren't compared.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ReportDiff {
+    /// Tests that fail in the new run, but passed, were skipped, or didn't run in the old one.
+    pub newly_failing: Vec<DiffTest>,
+
+    /// Tests that pass in the new run, but failed in the old one.
+    pub newly_passing: Vec<DiffTest>,
+
+    /// Tests that pass in both runs, but got slower by more than the threshold, slowest first.
+    pub duration_regressions: Vec<DurationRegression>,
+
+    /// Tests that fail in both runs.
+    pub still_failing: Vec<StillFailing>,
+}
+
+impl ReportDiff {
+    /// Tests must get slower by at least this many seconds to be reported as regressions, to avoid
+    /// noise from very fast tests.
+    pub const MIN_REGRESSION_SECS: f64 = 0.1;
+
+    /// Reads the JUnit reports at `old` and `new`, and compares them.
+    pub fn read(
+        old: &Utf8Path,
+        new: &Utf8Path,
+        params: ReportDiffParams,
+    ) -> Result<Self, JunitReportError> {
+        let old = read_report(old)?;
+        let new = read_report(new)?;
+        Ok(Self::new(&old, &new, params))
+    }
+
+    fn new(old: &Element, new: &Element, params: ReportDiffParams) -> Self {
+        let old = collect_tests(old);
+        let new = collect_tests(new);
+        let mut diff = Self::default();
+
+        for ((binary_id, name), new_test) in &new {
+            let old_test = old.get(&(*binary_id, *name));
+            let test = || DiffTest {
+                binary_id: (*binary_id).to_owned(),
+                name: (*name).to_owned(),
+            };
+
+            match (old_test.map(|old_test| old_test.status), new_test.status) {
+                (Some(TestStatus::Failed), TestStatus::Failed) => {
+                    let old_output = &old_test.expect("old test is present").output;
+                    let output_diff = (*old_output != new_test.output).then(|| {
+                        TextDiff::from_lines(old_output.as_str(), new_test.output.as_str())
+                            .unified_diff()
+                            .header("old", "new")
+                            .to_string()
+                    });
+                    diff.still_failing.push(StillFailing {
+                        binary_id: (*binary_id).to_owned(),
+                        name: (*name).to_owned(),
+                        output_diff,
+                    });
+                }
+                (_, TestStatus::Failed) => diff.newly_failing.push(test()),
+                (Some(TestStatus::Failed), TestStatus::Passed) => diff.newly_passing.push(test()),
+                (Some(TestStatus::Passed), TestStatus::Passed) => {
+                    let old_time = old_test.expect("old test is present").time;
+                    let new_time = new_test.time;
+                    if new_time - old_time >= Self::MIN_REGRESSION_SECS
+                        && new_time > old_time * (1.0 + params.threshold)
+                    {
+                        diff.duration_regressions.push(DurationRegression {
+                            binary_id: (*binary_id).to_owned(),
+                            name: (*name).to_owned(),
+                            old_time,
+                            new_time,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        diff.duration_regressions.sort_by(|a, b| {
+            (b.new_time - b.old_time)
+                .partial_cmp(&(a.new_time - a.old_time))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        diff
+    }
+
+    /// Returns true if there are no differences between the two runs.
+    pub fn is_empty(&self) -> bool {
+        self.newly_failing.is_empty()
+            && self.newly_passing.is_empty()
+            && self.duration_regressions.is_empty()
+            && self.still_failing.is_empty()
+    }
+
+    /// Outputs this diff in the given format.
+    pub fn write(
+        &self,
+        output_format: OutputFormat,
+        writer: &mut dyn WriteStr,
+        colorize: bool,
+    ) -> Result<(), WriteTestListError> {
+        match output_format {
+            OutputFormat::Human { verbose: _ } => self
+                .write_human(writer, colorize)
+                .map_err(WriteTestListError::Io),
+            OutputFormat::Serializable(format) => format.to_writer(self, writer),
+        }
+    }
+
+    fn write_human(&self, writer: &mut dyn WriteStr, colorize: bool) -> io::Result<()> {
+        let mut styles = Styles::default();
+        if colorize {
+            styles.colorize();
+        }
+
+        if self.is_empty() {
+            writeln!(writer, "no differences found")?;
+            return Ok(());
+        }
+
+        if !self.newly_failing.is_empty() {
+            write_header(
+                writer,
+                "newly failing",
+                styles.fail,
+                self.newly_failing.len(),
+            )?;
+            for test in &self.newly_failing {
+                writeln!(writer, "    {}", test.display(&styles))?;
+            }
+        }
+
+        if !self.newly_passing.is_empty() {
+            write_header(
+                writer,
+                "newly passing",
+                styles.pass,
+                self.newly_passing.len(),
+            )?;
+            for test in &self.newly_passing {
+                writeln!(writer, "    {}", test.display(&styles))?;
+            }
+        }
+
+        if !self.duration_regressions.is_empty() {
+            write_header(
+                writer,
+                "duration regressions",
+                styles.slow,
+                self.duration_regressions.len(),
+            )?;
+            for regression in &self.duration_regressions {
+                writeln!(
+                    writer,
+                    "    {}: {:.3}s -> {:.3}s",
+                    DisplayTest::new(&regression.binary_id, &regression.name, &styles),
+                    regression.old_time,
+                    regression.new_time,
+                )?;
+            }
+        }
+
+        if !self.still_failing.is_empty() {
+            write_header(
+                writer,
+                "still failing",
+                styles.fail,
+                self.still_failing.len(),
+            )?;
+            for still_failing in &self.still_failing {
+                let test = DisplayTest::new(&still_failing.binary_id, &still_failing.name, &styles);
+                match &still_failing.output_diff {
+                    Some(output_diff) => {
+                        writeln!(writer, "    {}: output changed", test)?;
+                        for line in output_diff.lines() {
+                            writeln!(writer, "        {line}")?;
+                        }
+                    }
+                    None => {
+                        writeln!(writer, "    {}: output unchanged", test)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A test within a [`ReportDiff`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DiffTest {
+    /// The binary ID of the test, from the `classname` attribute.
+    pub binary_id: String,
+
+    /// The name of the test.
+    pub name: String,
+}
+
+impl DiffTest {
+    fn display<'a>(&'a self, styles: &'a Styles) -> DisplayTest<'a> {
+        DisplayTest::new(&self.binary_id, &self.name, styles)
+    }
+}
+
+struct DisplayTest<'a> {
+    binary_id: &'a str,
+    name: &'a str,
+    styles: &'a Styles,
+}
+
+impl<'a> DisplayTest<'a> {
+    fn new(binary_id: &'a str, name: &'a str, styles: &'a Styles) -> Self {
+        Self {
+            binary_id,
+            name,
+            styles,
+        }
+    }
+}
+
+impl fmt::Display for DisplayTest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.binary_id.style(self.styles.binary_id),
+            self.name.style(self.styles.test_name),
+        )
+    }
+}
+
+/// A test that got slower between two runs.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DurationRegression {
+    /// The binary ID of the test, from the `classname` attribute.
+    pub binary_id: String,
+
+    /// The name of the test.
+    pub name: String,
+
+    /// The time taken by the test in the old run, in seconds.
+    pub old_time: f64,
+
+    /// The time taken by the test in the new run, in seconds.
+    pub new_time: f64,
+}
+
+/// A test that fails in both runs.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct StillFailing {
+    /// The binary ID of the test, from the `classname` attribute.
+    pub binary_id: String,
+
+    /// The name of the test.
+    pub name: String,
+
+    /// A unified diff of the failure message and output from the old run to the new one, or
+    /// `None` if they're the same.
+    pub output_diff: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug)]
+struct TestRecord {
+    status: TestStatus,
+    time: f64,
+    // The failure message and captured output, if the test failed.
+    output: String,
+}
+
+fn collect_tests(root: &Element) -> BTreeMap<(&str, &str), TestRecord> {
+    let mut tests = BTreeMap::new();
+    for suite in root.child_elements().filter(|e| e.name == "testsuite") {
+        if suite
+            .attr("name")
+            .is_some_and(|name| name.starts_with("@setup-script:"))
+        {
+            continue;
+        }
+        for testcase in suite.child_elements().filter(|e| e.name == "testcase") {
+            let binary_id = testcase.attr("classname").unwrap_or_default();
+            let name = testcase.attr("name").unwrap_or_default();
+
+            let failure = testcase
+                .child("failure")
+                .or_else(|| testcase.child("error"));
+            let (status, output) = match failure {
+                Some(failure) => {
+                    let mut output = failure.text();
+                    for stream in ["system-out", "system-err"] {
+                        if let Some(stream) = testcase.child(stream) {
+                            if !output.is_empty() && !output.ends_with('\n') {
+                                output.push('\n');
+                            }
+                            output.push_str(&stream.text());
+                        }
+                    }
+                    if !output.is_empty() && !output.ends_with('\n') {
+                        output.push('\n');
+                    }
+                    (TestStatus::Failed, output)
+                }
+                None if testcase.has_child("skipped") => (TestStatus::Skipped, String::new()),
+                None => (TestStatus::Passed, String::new()),
+            };
+
+            tests.insert(
+                (binary_id, name),
+                TestRecord {
+                    status,
+                    time: parse_time(testcase.attr("time")),
+                    output,
+                },
+            );
+        }
+    }
+    tests
+}
+
+fn write_header(
+    writer: &mut dyn WriteStr,
+    title: &str,
+    style: Style,
+    len: usize,
+) -> io::Result<()> {
+    writeln!(writer, "{} ({len}):", title.style(style))
+}
+
+#[derive(Clone, Debug, Default)]
+struct Styles {
+    binary_id: Style,
+    test_name: Style,
+    pass: Style,
+    fail: Style,
+    slow: Style,
+}
+
+impl Styles {
+    fn colorize(&mut self) {
+        self.binary_id = Style::new().magenta().bold();
+        self.test_name = Style::new().blue().bold();
+        self.pass = Style::new().green().bold();
+        self.fail = Style::new().red().bold();
+        self.slow = Style::new().yellow().bold();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::junit_xml::parse_report, *};
+    use indoc::indoc;
+
+    static OLD: &str = indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites name="nextest-run" tests="5" failures="2" errors="0">
+            <testsuite name="my-crate::basic" tests="5" disabled="0" errors="0" failures="2">
+                <testcase name="test_fixed" classname="my-crate::basic" time="0.100">
+                    <failure type="test failure">assertion failed</failure>
+                </testcase>
+                <testcase name="test_broken" classname="my-crate::basic" time="0.100">
+                </testcase>
+                <testcase name="test_slower" classname="my-crate::basic" time="0.200">
+                </testcase>
+                <testcase name="test_fast" classname="my-crate::basic" time="0.001">
+                </testcase>
+                <testcase name="test_still_failing" classname="my-crate::basic" time="0.100">
+                    <failure type="test failure">left: 4
+        right: 5</failure>
+                </testcase>
+            </testsuite>
+        </testsuites>
+    "#};
+
+    static NEW: &str = indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites name="nextest-run" tests="6" failures="3" errors="0">
+            <testsuite name="@setup-script:db" tests="1" disabled="0" errors="0" failures="1">
+                <testcase name="db" classname="@setup-script:db" time="0.100">
+                    <failure type="script failure">failed</failure>
+                </testcase>
+            </testsuite>
+            <testsuite name="my-crate::basic" tests="6" disabled="0" errors="0" failures="2">
+                <testcase name="test_fixed" classname="my-crate::basic" time="0.100">
+                </testcase>
+                <testcase name="test_broken" classname="my-crate::basic" time="0.100">
+                    <error type="execution failure">failed to exec</error>
+                </testcase>
+                <testcase name="test_slower" classname="my-crate::basic" time="0.900">
+                </testcase>
+                <testcase name="test_fast" classname="my-crate::basic" time="0.003">
+                </testcase>
+                <testcase name="test_still_failing" classname="my-crate::basic" time="0.100">
+                    <failure type="test failure">left: 4
+        right: 6</failure>
+                </testcase>
+                <testcase name="test_added" classname="my-crate::basic" time="0.100">
+                    <failure type="test failure">assertion failed</failure>
+                </testcase>
+            </testsuite>
+        </testsuites>
+    "#};
+
+    #[test]
+    fn test_report_diff() {
+        let old = parse_report(Utf8Path::new("old.xml"), OLD).expect("old report is valid");
+        let new = parse_report(Utf8Path::new("new.xml"), NEW).expect("new report is valid");
+        let diff = ReportDiff::new(&old, &new, ReportDiffParams::default());
+
+        let test = |name: &str| DiffTest {
+            binary_id: "my-crate::basic".to_owned(),
+            name: name.to_owned(),
+        };
+        assert_eq!(
+            diff,
+            ReportDiff {
+                newly_failing: vec![test("test_added"), test("test_broken")],
+                newly_passing: vec![test("test_fixed")],
+                duration_regressions: vec![DurationRegression {
+                    binary_id: "my-crate::basic".to_owned(),
+                    name: "test_slower".to_owned(),
+                    old_time: 0.2,
+                    new_time: 0.9,
+                }],
+                still_failing: vec![StillFailing {
+                    binary_id: "my-crate::basic".to_owned(),
+                    name: "test_still_failing".to_owned(),
+                    output_diff: Some(
+                        indoc! {"
+                            --- old
+                            +++ new
+                            @@ -1,2 +1,2 @@
+                             left: 4
+                            -right: 5
+                            +right: 6
+                        "}
+                        .to_owned()
+                    ),
+                }],
+            }
+        );
+
+        let same = ReportDiff::new(&new, &new, ReportDiffParams::default());
+        assert_eq!(
+            same.still_failing
+                .iter()
+                .map(|test| test.output_diff.as_deref())
+                .collect::<Vec<_>>(),
+            vec![None, None, None],
+        );
+        assert!(same.newly_failing.is_empty(), "no newly failing tests");
+    }
+}
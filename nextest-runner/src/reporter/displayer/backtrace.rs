@@ -0,0 +1,364 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Post-processing of Rust backtraces in test output.
+//!
+//! When a test panics with `RUST_BACKTRACE` set, the standard library prints a
+//! backtrace after a `stack backtrace:` line. Each frame is on its own line,
+//! optionally followed by the frame's source location:
+//!
+//! ```text
+//! stack backtrace:
+//!    0: rust_begin_unwind
+//!              at /rustc/.../library/std/src/panicking.rs:652:5
+//!    1: my_crate::tests::foo
+//!              at ./src/lib.rs:10:9
+//! ```
+//!
+//! With `RUST_BACKTRACE=full`, frames also have an address, as in
+//! `1:     0x55d5c7b1f0d5 - my_crate::tests::foo::h0123456789abcdef`.
+//!
+//! Frames from the standard library and the test harness are dimmed, and
+//! frames from workspace crates are highlighted, so that the frames that matter
+//! stand out. Symbols that are still mangled are demangled.
+
+use super::super::helpers::Styles;
+use bstr::ByteSlice;
+use camino::Utf8Path;
+use owo_colors::{OwoColorize, Style};
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+const STACK_BACKTRACE: &[u8] = b"stack backtrace:";
+
+/// Symbol prefixes for frames from the standard library, the test harness and
+/// the platform's process and thread startup code.
+const INTERNAL_SYMBOL_PREFIXES: &[&str] = &[
+    "std::",
+    "core::",
+    "alloc::",
+    "test::",
+    "<std::",
+    "<core::",
+    "<alloc::",
+    "<test::",
+    "__rust_",
+    "rust_begin_unwind",
+    "rust_panic",
+    "__libc_start",
+    "__GI___clone",
+    "__clone",
+    "start_thread",
+    "_start",
+    "__scrt_common_main",
+    "BaseThreadInitThunk",
+    "RtlUserThreadStart",
+    "_pthread_start",
+    "thread_start",
+];
+
+/// Styles the frames of any backtraces within test output.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct BacktraceFormatter<'a> {
+    workspace_root: &'a Utf8Path,
+    internal: Style,
+    workspace: Style,
+}
+
+impl<'a> BacktraceFormatter<'a> {
+    pub(super) fn new(workspace_root: &'a Utf8Path, styles: &Styles) -> Self {
+        Self {
+            workspace_root,
+            internal: styles.backtrace_internal,
+            workspace: styles.backtrace_workspace,
+        }
+    }
+
+    /// Writes out `output`, styling and demangling the frames of any
+    /// backtraces within it. Everything else is written out unchanged.
+    pub(super) fn write(&self, output: &[u8], writer: &mut dyn Write) -> io::Result<()> {
+        // Most output doesn't have backtraces, so avoid splitting it into lines.
+        if output.find(STACK_BACKTRACE).is_none() {
+            return writer.write_all(output);
+        }
+
+        let lines: Vec<&[u8]> = output.lines_with_terminator().collect();
+        let mut in_backtrace = false;
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            i += 1;
+            let content = line.trim_end_with(|c| c == '\n' || c == '\r');
+            let terminator = &line[content.len()..];
+
+            if !in_backtrace {
+                in_backtrace = content.trim_ascii() == STACK_BACKTRACE;
+                writer.write_all(line)?;
+                continue;
+            }
+
+            let Some((symbol_start, symbol)) = content.to_str().ok().and_then(parse_frame) else {
+                // Anything other than a frame, such as the "note: Some details
+                // are omitted" line, ends the backtrace.
+                in_backtrace = false;
+                writer.write_all(line)?;
+                continue;
+            };
+            let symbol = demangle(symbol);
+
+            // The frame's location, if any, is on the next line.
+            let location_line = lines.get(i).copied().and_then(|next| {
+                let next_content = next.trim_end_with(|c| c == '\n' || c == '\r');
+                let path = parse_location(next_content.to_str().ok()?)?;
+                Some((next, next_content, path))
+            });
+            let kind = classify_frame(
+                &symbol,
+                location_line.map(|(_, _, path)| path),
+                self.workspace_root,
+            );
+
+            writer.write_all(&content[..symbol_start])?;
+            match self.style(kind) {
+                Some(style) => write!(writer, "{}", symbol.style(style))?,
+                None => writer.write_all(symbol.as_bytes())?,
+            }
+            writer.write_all(terminator)?;
+
+            if let Some((next, next_content, _)) = location_line {
+                i += 1;
+                match self.style(kind) {
+                    // Style each line separately, since some CI systems reset
+                    // colors at the end of each line.
+                    Some(style) => {
+                        let next_content = next_content.to_str_lossy();
+                        write!(writer, "{}", next_content.style(style))?;
+                    }
+                    None => writer.write_all(next_content)?,
+                }
+                writer.write_all(&next[next_content.len()..])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn style(&self, kind: FrameKind) -> Option<Style> {
+        match kind {
+            FrameKind::Internal => Some(self.internal),
+            FrameKind::Workspace => Some(self.workspace),
+            FrameKind::Other => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FrameKind {
+    /// A frame from the standard library, the test harness or process startup.
+    Internal,
+
+    /// A frame from a crate in the workspace.
+    Workspace,
+
+    /// Any other frame, such as one from a dependency.
+    Other,
+}
+
+/// Parses a backtrace frame line, returning the offset of the symbol within the
+/// line along with the symbol.
+fn parse_frame(line: &str) -> Option<(usize, &str)> {
+    let (index, rest) = line.trim_start().split_once(':')?;
+    if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let rest = rest.trim_start();
+    // With RUST_BACKTRACE=full, the symbol is preceded by the frame's address.
+    let symbol = match rest.split_once(" - ") {
+        Some((address, symbol)) if address.starts_with("0x") => symbol,
+        _ => rest,
+    };
+    if symbol.is_empty() {
+        return None;
+    }
+    Some((line.len() - symbol.len(), symbol))
+}
+
+/// Parses a frame location line such as `at ./src/lib.rs:10:9`, returning the
+/// path.
+fn parse_location(line: &str) -> Option<&str> {
+    let location = line.trim_start().strip_prefix("at ")?;
+    // Split from the right, since Windows paths have colons in them.
+    let mut parts = location.rsplitn(3, ':');
+    let (_column, _line, path) = (parts.next()?, parts.next()?, parts.next()?);
+    Some(path)
+}
+
+/// Demangles symbols that the standard library didn't demangle, such as those
+/// from frames it couldn't symbolize fully.
+fn demangle(symbol: &str) -> Cow<'_, str> {
+    match rustc_demangle::try_demangle(symbol) {
+        // The alternate format omits the hash.
+        Ok(demangled) => format!("{demangled:#}").into(),
+        Err(_) => symbol.into(),
+    }
+}
+
+fn classify_frame(symbol: &str, location: Option<&str>, workspace_root: &Utf8Path) -> FrameKind {
+    if INTERNAL_SYMBOL_PREFIXES
+        .iter()
+        .any(|prefix| symbol.starts_with(prefix))
+    {
+        return FrameKind::Internal;
+    }
+    let Some(location) = location else {
+        return FrameKind::Other;
+    };
+    // The standard library's sources are remapped to /rustc/<commit hash>.
+    if location.starts_with("/rustc/") {
+        return FrameKind::Internal;
+    }
+    // Paths within the test's working directory, which is its crate's
+    // directory, are printed relative to it.
+    let path = Utf8Path::new(location);
+    if path.is_relative() || path.starts_with(workspace_root) {
+        FrameKind::Workspace
+    } else {
+        FrameKind::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case("   0: rust_begin_unwind", Some("rust_begin_unwind"); "basic")]
+    #[test_case(
+        "  12:     0x55d5c7b1f0d5 - my_crate::foo::h0123456789abcdef",
+        Some("my_crate::foo::h0123456789abcdef");
+        "full"
+    )]
+    #[test_case("  3: <F as my_crate::Tr>::f", Some("<F as my_crate::Tr>::f"); "trait impl")]
+    #[test_case("             at ./src/lib.rs:10:9", None; "location")]
+    #[test_case("note: Some details are omitted", None; "note")]
+    #[test_case("  4:", None; "no symbol")]
+    fn test_parse_frame(line: &str, expected: Option<&str>) {
+        let parsed = parse_frame(line);
+        assert_eq!(parsed.map(|(_, symbol)| symbol), expected);
+        if let Some((start, symbol)) = parsed {
+            assert_eq!(&line[start..], symbol, "offset points to the symbol");
+        }
+    }
+
+    #[test_case("             at ./src/lib.rs:10:9", Some("./src/lib.rs"); "relative")]
+    #[test_case(r"  at C:\ws\src\lib.rs:10:9", Some(r"C:\ws\src\lib.rs"); "windows")]
+    #[test_case("   1: foo", None; "frame")]
+    fn test_parse_location(line: &str, expected: Option<&str>) {
+        assert_eq!(parse_location(line), expected);
+    }
+
+    #[test]
+    fn test_classify_frame() {
+        let root = Utf8Path::new("/ws");
+        let classify = |symbol, location| classify_frame(symbol, location, root);
+
+        assert_eq!(
+            classify("core::panicking::panic_fmt", None),
+            FrameKind::Internal
+        );
+        assert_eq!(
+            classify(
+                "<alloc::boxed::Box<F> as core::ops::function::FnOnce<A>>::call_once",
+                None
+            ),
+            FrameKind::Internal,
+        );
+        assert_eq!(
+            classify("my_crate::foo", Some("/rustc/abc123/library/std/src/rt.rs")),
+            FrameKind::Internal,
+        );
+        assert_eq!(
+            classify("my_crate::foo", Some("./src/lib.rs")),
+            FrameKind::Workspace
+        );
+        assert_eq!(
+            classify("other_crate::bar", Some("/ws/other-crate/src/lib.rs")),
+            FrameKind::Workspace,
+        );
+        assert_eq!(
+            classify(
+                "serde::de::foo",
+                Some("/home/u/.cargo/registry/src/serde/de.rs")
+            ),
+            FrameKind::Other,
+        );
+        assert_eq!(classify("my_crate::foo", None), FrameKind::Other);
+    }
+
+    #[test]
+    fn test_demangle() {
+        assert_eq!(
+            demangle("_ZN8my_crate3foo17h0123456789abcdefE"),
+            "my_crate::foo"
+        );
+        assert_eq!(demangle("my_crate::foo"), "my_crate::foo");
+    }
+
+    #[test]
+    fn test_write() {
+        const DIM: &str = "\u{1b}[2m";
+        const BOLD: &str = "\u{1b}[1m";
+        const RESET: &str = "\u{1b}[0m";
+
+        let output = indoc! {"
+            thread 'foo' panicked at src/lib.rs:10:9:
+            oops
+            stack backtrace:
+               0: rust_begin_unwind
+                         at /rustc/abc123/library/std/src/panicking.rs:652:5
+               1: _ZN8my_crate3foo17h0123456789abcdefE
+                         at ./src/lib.rs:10:9
+               2: serde::de::foo
+            note: Some details are omitted.
+               3: not a frame
+        "};
+        let formatter = BacktraceFormatter {
+            workspace_root: Utf8Path::new("/ws"),
+            internal: Style::new().dimmed(),
+            workspace: Style::new().bold(),
+        };
+        let mut buf = Vec::new();
+        formatter.write(output.as_bytes(), &mut buf).unwrap();
+
+        let expected = format!(
+            "thread 'foo' panicked at src/lib.rs:10:9:\n\
+             oops\n\
+             stack backtrace:\n   \
+             0: {DIM}rust_begin_unwind{RESET}\n\
+             {DIM}             at /rustc/abc123/library/std/src/panicking.rs:652:5{RESET}\n   \
+             1: {BOLD}my_crate::foo{RESET}\n\
+             {BOLD}             at ./src/lib.rs:10:9{RESET}\n   \
+             2: serde::de::foo\n\
+             note: Some details are omitted.\n   \
+             3: not a frame\n"
+        );
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_without_backtrace() {
+        let formatter = BacktraceFormatter {
+            workspace_root: Utf8Path::new("/ws"),
+            internal: Style::new().dimmed(),
+            workspace: Style::new().bold(),
+        };
+        let mut buf = Vec::new();
+        formatter
+            .write(b"   0: looks like a frame\n", &mut buf)
+            .unwrap();
+        assert_eq!(buf, b"   0: looks like a frame\n");
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Formatting for durations and counts in the reporter output.
+
+use serde::Deserialize;
+use std::{fmt, time::Duration};
+
+/// How durations are displayed in the reporter output.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum DurationFormat {
+    /// Seconds with millisecond precision, for example `1.234s`.
+    #[default]
+    Seconds,
+
+    /// Whole milliseconds, for example `1234ms`.
+    Milliseconds,
+
+    /// ISO 8601 durations, for example `PT1.234S`.
+    Iso8601,
+}
+
+/// Formatting options for durations and counts.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct DisplayFormat {
+    pub(crate) duration_format: DurationFormat,
+    pub(crate) thousands_separator: bool,
+}
+
+impl DisplayFormat {
+    pub(super) fn duration(self, duration: Duration) -> DisplayDuration {
+        DisplayDuration {
+            duration,
+            format: self.duration_format,
+        }
+    }
+
+    pub(super) fn count(self, count: usize) -> DisplayCount {
+        DisplayCount {
+            count,
+            thousands_separator: self.thousands_separator,
+        }
+    }
+}
+
+/// A duration, displayed according to a [`DurationFormat`].
+///
+/// Width and alignment specifiers are honored, so this can be padded like a string.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct DisplayDuration {
+    duration: Duration,
+    format: DurationFormat,
+}
+
+impl fmt::Display for DisplayDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let out = match self.format {
+            DurationFormat::Seconds => format!("{:.3}s", self.duration.as_secs_f64()),
+            DurationFormat::Milliseconds => format!("{}ms", self.duration.as_millis()),
+            DurationFormat::Iso8601 => format!("PT{:.3}S", self.duration.as_secs_f64()),
+        };
+        f.pad(&out)
+    }
+}
+
+/// A count, optionally grouped with thousands separators.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct DisplayCount {
+    count: usize,
+    thousands_separator: bool,
+}
+
+impl fmt::Display for DisplayCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.count.to_string();
+        if !self.thousands_separator {
+            return f.pad(&digits);
+        }
+
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                out.push(',');
+            }
+            out.push(digit);
+        }
+        f.pad(&out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_duration() {
+        let duration = Duration::from_millis(1234);
+        let cases = [
+            (DurationFormat::Seconds, "1.234s"),
+            (DurationFormat::Milliseconds, "1234ms"),
+            (DurationFormat::Iso8601, "PT1.234S"),
+        ];
+        for (duration_format, expected) in cases {
+            let format = DisplayFormat {
+                duration_format,
+                thousands_separator: false,
+            };
+            assert_eq!(format.duration(duration).to_string(), expected);
+        }
+
+        let format = DisplayFormat::default();
+        assert_eq!(
+            format!("{:>9}", format.duration(duration)),
+            "   1.234s",
+            "padding is honored"
+        );
+    }
+
+    #[test]
+    fn test_display_count() {
+        let format = DisplayFormat {
+            duration_format: DurationFormat::Seconds,
+            thousands_separator: true,
+        };
+        let cases = [
+            (0, "0"),
+            (999, "999"),
+            (1000, "1,000"),
+            (12345, "12,345"),
+            (1234567, "1,234,567"),
+        ];
+        for (count, expected) in cases {
+            assert_eq!(format.count(count).to_string(), expected);
+        }
+
+        assert_eq!(DisplayFormat::default().count(12345).to_string(), "12345");
+    }
+}
@@ -3,6 +3,7 @@
 
 //! Display helpers for durations.
 
+use super::display_format::{DisplayDuration, DisplayFormat};
 use crate::{
     config::CompiledDefaultFilter,
     helpers::plural,
@@ -36,45 +37,43 @@ impl fmt::Display for DisplayBracketedHhMmSs {
     }
 }
 
-pub(super) struct DisplayBracketedDuration(pub(super) Duration);
+pub(super) struct DisplayBracketedDuration(pub(super) DisplayDuration);
 
 impl fmt::Display for DisplayBracketedDuration {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // * > means right-align.
-        // * 8 is the number of characters to pad to.
-        // * .3 means print three digits after the decimal point.
-        write!(f, "[{:>8.3?}s] ", self.0.as_secs_f64())
+        // * 9 is the number of characters to pad to.
+        write!(f, "[{:>9}] ", self.0)
     }
 }
 
-pub(super) struct DisplayDurationBy(pub(super) Duration);
+pub(super) struct DisplayDurationBy(pub(super) DisplayDuration);
 
 impl fmt::Display for DisplayDurationBy {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // * > means right-align.
-        // * 7 is the number of characters to pad to.
-        // * .3 means print three digits after the decimal point.
-        write!(f, "by {:>7.3?}s ", self.0.as_secs_f64())
+        // * 8 is the number of characters to pad to.
+        write!(f, "by {:>8} ", self.0)
     }
 }
 
-pub(super) struct DisplaySlowDuration(pub(super) Duration);
+pub(super) struct DisplaySlowDuration(pub(super) DisplayDuration);
 
 impl fmt::Display for DisplaySlowDuration {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Inside the curly braces:
         // * > means right-align.
-        // * 7 is the number of characters to pad to.
-        // * .3 means print three digits after the decimal point.
+        // * 8 is the number of characters to pad to.
         //
         // The > outside the curly braces is printed literally.
-        write!(f, "[>{:>7.3?}s] ", self.0.as_secs_f64())
+        write!(f, "[>{:>8}] ", self.0)
     }
 }
 
 pub(super) fn write_skip_counts(
     skip_counts: &SkipCounts,
     default_filter: &CompiledDefaultFilter,
+    format: DisplayFormat,
     styles: &Styles,
     writer: &mut dyn Write,
 ) -> io::Result<()> {
@@ -83,6 +82,7 @@ pub(super) fn write_skip_counts(
         write_skip_counts_impl(
             skip_counts.skipped_tests,
             skip_counts.skipped_binaries,
+            format,
             styles,
             writer,
         )?;
@@ -108,6 +108,7 @@ pub(super) fn write_skip_counts(
                 write_skip_counts_impl(
                     skip_counts.skipped_tests_default_filter,
                     skip_counts.skipped_binaries_default_filter,
+                    format,
                     styles,
                     writer,
                 )?;
@@ -127,6 +128,7 @@ pub(super) fn write_skip_counts(
 fn write_skip_counts_impl(
     skipped_tests: usize,
     skipped_binaries: usize,
+    format: DisplayFormat,
     styles: &Styles,
     writer: &mut dyn Write,
 ) -> io::Result<()> {
@@ -135,23 +137,23 @@ fn write_skip_counts_impl(
         write!(
             writer,
             "{} {} and {} {}",
-            skipped_tests.style(styles.count),
+            format.count(skipped_tests).style(styles.count),
             plural::tests_str(skipped_tests),
-            skipped_binaries.style(styles.count),
+            format.count(skipped_binaries).style(styles.count),
             plural::binaries_str(skipped_binaries),
         )?;
     } else if skipped_tests > 0 {
         write!(
             writer,
             "{} {}",
-            skipped_tests.style(styles.count),
+            format.count(skipped_tests).style(styles.count),
             plural::tests_str(skipped_tests),
         )?;
     } else if skipped_binaries > 0 {
         write!(
             writer,
             "{} {}",
-            skipped_binaries.style(styles.count),
+            format.count(skipped_binaries).style(styles.count),
             plural::binaries_str(skipped_binaries),
         )?;
     }
@@ -162,6 +164,7 @@ fn write_skip_counts_impl(
 pub(super) fn write_final_warnings(
     final_stats: FinalRunStats,
     cancel_status: Option<CancelReason>,
+    format: DisplayFormat,
     styles: &Styles,
     writer: &mut dyn Write,
 ) -> io::Result<()> {
@@ -179,8 +182,8 @@ pub(super) fn write_final_warnings(
                     writer,
                     "{}: {}/{} {} {} not run due to {} (run with {} to run all tests, or run with {})",
                     "warning".style(styles.skip),
-                    not_run.style(styles.count),
-                    initial_run_count.style(styles.count),
+                    format.count(not_run).style(styles.count),
+                    format.count(initial_run_count).style(styles.count),
                     plural::tests_plural_if(initial_run_count != 1 || not_run != 1),
                     plural::were_plural_if(initial_run_count != 1 || not_run != 1),
                     CancelReason::TestFailure.to_static_str().style(styles.skip),
@@ -198,8 +201,8 @@ pub(super) fn write_final_warnings(
                     writer,
                     "{}: {}/{} {} {} not run{}",
                     "warning".style(styles.skip),
-                    not_run.style(styles.count),
-                    initial_run_count.style(styles.count),
+                    format.count(not_run).style(styles.count),
+                    format.count(initial_run_count).style(styles.count),
                     plural::tests_plural_if(initial_run_count != 1 || not_run != 1),
                     plural::were_plural_if(initial_run_count != 1 || not_run != 1),
                     due_to_reason,
@@ -339,6 +342,7 @@ mod tests {
                     CompiledDefaultFilterSection::Profile
                 },
             },
+            DisplayFormat::default(),
             &Styles::default(),
             &mut buf,
         )
@@ -407,7 +411,14 @@ mod tests {
     fn final_warnings_for(stats: FinalRunStats, cancel_status: Option<CancelReason>) -> String {
         let mut buf: Vec<u8> = Vec::new();
         let styles = Styles::default();
-        write_final_warnings(stats, cancel_status, &styles, &mut buf).unwrap();
+        write_final_warnings(
+            stats,
+            cancel_status,
+            DisplayFormat::default(),
+            &styles,
+            &mut buf,
+        )
+        .unwrap();
         String::from_utf8(buf).unwrap()
     }
 }
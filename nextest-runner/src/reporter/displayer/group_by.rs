@@ -0,0 +1,144 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::formatters::DisplayBracketedDuration;
+use super::DisplayFormat;
+use crate::{
+    helpers::plural,
+    reporter::{events::ExecuteStatus, helpers::Styles},
+};
+use owo_colors::OwoColorize;
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+/// How test results are grouped in the reporter output.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum GroupBy {
+    /// Show results as tests finish, interleaving tests from different
+    /// binaries.
+    #[default]
+    None,
+
+    /// Buffer the results of each test binary, and show them together once
+    /// every test in the binary has finished or been skipped.
+    Binary,
+}
+
+/// The buffered output and counts for a test binary, with
+/// [`GroupBy::Binary`].
+#[derive(Debug, Default)]
+pub(super) struct BinaryGroup {
+    /// The number of tests in the binary that haven't finished or been skipped
+    /// yet.
+    pub(super) remaining: usize,
+    /// Lines written for the binary's tests so far.
+    pub(super) buf: Vec<u8>,
+    pub(super) passed: usize,
+    pub(super) failed: usize,
+    pub(super) skipped: usize,
+    /// The time taken by all attempts of the binary's tests, summed up.
+    pub(super) time_taken: Duration,
+}
+
+impl BinaryGroup {
+    pub(super) fn new(test_count: usize) -> Self {
+        Self {
+            remaining: test_count,
+            ..Default::default()
+        }
+    }
+
+    pub(super) fn on_finished(&mut self, last_status: &ExecuteStatus, time_taken: Duration) {
+        self.remaining = self.remaining.saturating_sub(1);
+        if last_status.result.is_success() {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.time_taken += time_taken;
+    }
+
+    pub(super) fn on_skipped(&mut self) {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.skipped += 1;
+    }
+
+    /// Writes out the header line for the binary, followed by the lines
+    /// buffered for its tests.
+    pub(super) fn write(
+        &self,
+        binary_id: &str,
+        format: DisplayFormat,
+        styles: &Styles,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let run = self.passed + self.failed;
+        let header_style = if self.failed > 0 {
+            styles.fail
+        } else {
+            styles.pass
+        };
+        write!(writer, "{:>12} ", "Binary".style(header_style))?;
+        write!(
+            writer,
+            "{}{}: {} {} run: {} {}",
+            DisplayBracketedDuration(format.duration(self.time_taken)),
+            binary_id.style(styles.list_styles.binary_id),
+            format.count(run).style(styles.count),
+            plural::tests_str(run),
+            format.count(self.passed).style(styles.count),
+            "passed".style(styles.pass),
+        )?;
+        if self.failed > 0 {
+            write!(
+                writer,
+                ", {} {}",
+                format.count(self.failed).style(styles.count),
+                "failed".style(styles.fail),
+            )?;
+        }
+        writeln!(
+            writer,
+            ", {} {}",
+            format.count(self.skipped).style(styles.count),
+            "skipped".style(styles.skip),
+        )?;
+        writer.write_all(&self.buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_group_write() {
+        let mut group = BinaryGroup::new(3);
+        group
+            .buf
+            .extend_from_slice(b"        FAIL [   1.000s] my-binary test_b\n");
+        group.passed = 1;
+        group.failed = 1;
+        group.skipped = 1;
+        group.remaining = 0;
+        group.time_taken = Duration::from_millis(1500);
+
+        let mut out = Vec::new();
+        group
+            .write(
+                "my-binary",
+                DisplayFormat::default(),
+                &Styles::default(),
+                &mut out,
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "      Binary [   1.500s] my-binary: 2 tests run: 1 passed, 1 failed, 1 skipped\n        \
+             FAIL [   1.000s] my-binary test_b\n",
+        );
+    }
+}
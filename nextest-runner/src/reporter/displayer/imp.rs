@@ -6,29 +6,44 @@
 //! The main structure in this module is [`TestReporter`].
 
 use super::{
+    display_format::DisplayFormat,
     formatters::{
         write_final_warnings, write_skip_counts, DisplayBracketedDuration, DisplayDurationBy,
         DisplaySlowDuration,
     },
-    progress::{progress_bar_msg, progress_str, write_summary_str, ProgressBarState},
+    group_by::BinaryGroup,
+    progress::{
+        progress_bar_msg, progress_str, write_skip_reasons_str, write_summary_str, ProgressBarState,
+    },
     unit_output::TestOutputDisplay,
     ChildOutputSpec, FinalStatusLevel, OutputStoreFinal, StatusLevel, StatusLevels,
     UnitOutputReporter,
 };
 use crate::{
-    config::{CompiledDefaultFilter, ScriptId},
+    config::{CompiledDefaultFilter, FinalSummary, MaxFailScope, ScriptId},
     errors::WriteEventError,
     helpers::{plural, DisplayScriptInstance, DisplayTestInstance},
     list::{TestInstance, TestInstanceId},
-    reporter::{events::*, helpers::Styles, imp::ReporterStderr},
+    reporter::{
+        error_description::{heuristic_is_should_panic, UnitErrorDescription},
+        events::*,
+        helpers::Styles,
+        imp::ReporterStderr,
+        AttemptOutputDiff,
+    },
+    theme::Theme,
+    user_config::Symbols,
 };
+use camino::{Utf8Path, Utf8PathBuf};
 use debug_ignore::DebugIgnore;
 use indent_write::io::IndentWriter;
-use nextest_metadata::MismatchReason;
+use indexmap::IndexMap;
+use nextest_metadata::{MismatchReason, RustBinaryId};
 use owo_colors::{OwoColorize, Style};
 use std::{
     borrow::Cow,
     cmp::Reverse,
+    collections::BTreeMap,
     io::{self, BufWriter, Write},
     time::Duration,
 };
@@ -37,63 +52,66 @@ use swrite::{swrite, SWrite};
 pub(crate) struct DisplayReporterBuilder {
     pub(crate) default_filter: CompiledDefaultFilter,
     pub(crate) status_levels: StatusLevels,
+    pub(crate) final_summary: FinalSummary,
     pub(crate) test_count: usize,
+    // The number of tests in each binary, only used when grouping by binary.
+    pub(crate) binary_test_counts: BTreeMap<RustBinaryId, usize>,
     pub(crate) success_output: Option<TestOutputDisplay>,
     pub(crate) failure_output: Option<TestOutputDisplay>,
     pub(crate) should_colorize: bool,
+    pub(crate) theme: Theme,
+    pub(crate) symbols: Option<Symbols>,
     pub(crate) no_capture: bool,
     pub(crate) hide_progress_bar: bool,
+    pub(crate) show_slots: bool,
+    pub(crate) format: DisplayFormat,
+    // Used to tell frames from workspace crates apart in backtraces.
+    pub(crate) workspace_root: Utf8PathBuf,
 }
 
 impl DisplayReporterBuilder {
     pub(crate) fn build(self, output: ReporterStderr<'_>) -> DisplayReporter<'_> {
         let mut styles: Box<Styles> = Box::default();
         if self.should_colorize {
-            styles.colorize();
+            styles.colorize(&self.theme);
         }
 
-        let status_level = match self.no_capture {
+        // Apart from the extra lines it hides, fail-only shows the same test
+        // statuses as fail.
+        let quiet = self.status_levels.status_level == StatusLevel::FailOnly;
+        let status_level = match (self.no_capture, quiet) {
             // In no-capture mode, the status level is treated as at least pass.
-            true => self.status_levels.status_level.max(StatusLevel::Pass),
-            false => self.status_levels.status_level,
+            (true, _) => self.status_levels.status_level.max(StatusLevel::Pass),
+            (false, true) => StatusLevel::Fail,
+            (false, false) => self.status_levels.status_level,
         };
 
         let mut theme_characters = ThemeCharacters::default();
-        match output {
-            ReporterStderr::Terminal => {
+        match (self.symbols, &output) {
+            (Some(Symbols::Unicode), _) => theme_characters.use_unicode(),
+            (Some(Symbols::Ascii), _) => {}
+            (None, ReporterStderr::Terminal) => {
                 if supports_unicode::on(supports_unicode::Stream::Stderr) {
                     theme_characters.use_unicode();
                 }
             }
-            ReporterStderr::Buffer(_) => {
+            (None, ReporterStderr::Buffer(_)) => {
                 // Always use Unicode for internal buffers.
                 theme_characters.use_unicode();
             }
         }
 
         let stderr = match output {
-            ReporterStderr::Terminal if self.no_capture => {
-                // Do not use a progress bar if --no-capture is passed in. This is required since we
-                // pass down stderr to the child process.
-                //
-                // In the future, we could potentially switch to using a pty, in which case we could
-                // still potentially use the progress bar as a status bar. However, that brings
-                // about its own complications: what if a test's output doesn't include a newline?
-                // We might have to use a curses-like UI which would be a lot of work for not much
-                // gain.
+            ReporterStderr::Terminal if self.progress_bar_hidden(is_ci::uncached()) => {
                 ReporterStderrImpl::TerminalWithoutBar
             }
-            ReporterStderr::Terminal if is_ci::uncached() => {
-                // Some CI environments appear to pretend to be a terminal. Disable the progress bar
-                // in these environments.
-                ReporterStderrImpl::TerminalWithoutBar
-            }
-            ReporterStderr::Terminal if self.hide_progress_bar => {
-                ReporterStderrImpl::TerminalWithoutBar
-            }
-
             ReporterStderr::Terminal => {
-                let state = ProgressBarState::new(self.test_count, theme_characters.progress_chars);
+                let state = ProgressBarState::new(
+                    self.test_count,
+                    theme_characters.progress_chars,
+                    theme_characters.ellipsis,
+                    self.show_slots,
+                );
                 ReporterStderrImpl::TerminalWithBar { state }
             }
             ReporterStderr::Buffer(buf) => ReporterStderrImpl::Buffer(buf),
@@ -118,15 +136,53 @@ impl DisplayReporterBuilder {
                     final_status_level: self.status_levels.final_status_level,
                 },
                 no_capture: self.no_capture,
+                quiet,
+                binary_groups: self
+                    .binary_test_counts
+                    .into_iter()
+                    .map(|(binary_id, test_count)| (binary_id, BinaryGroup::new(test_count)))
+                    .collect(),
+                final_summary: self.final_summary,
+                format: self.format,
                 styles,
                 theme_characters,
                 cancel_status: None,
-                unit_output: UnitOutputReporter::new(force_success_output, force_failure_output),
+                unit_output: UnitOutputReporter::new(
+                    force_success_output,
+                    force_failure_output,
+                    self.workspace_root,
+                ),
                 final_outputs: DebugIgnore(Vec::new()),
+                failure_classes: IndexMap::new(),
+                annotations: BTreeMap::new(),
+                startup_overheads: BTreeMap::new(),
+                test_times: Vec::new(),
+                retried_tests: Vec::new(),
             },
             stderr,
         }
     }
+
+    /// Returns true if the progress bar should be hidden when writing to a
+    /// terminal.
+    ///
+    /// This is the one place that decides whether to show the progress bar.
+    fn progress_bar_hidden(&self, is_ci: bool) -> bool {
+        // Do not use a progress bar if --no-capture is passed in. This is
+        // required since we pass down stderr to the child process.
+        //
+        // In the future, we could potentially switch to using a pty, in which
+        // case we could still potentially use the progress bar as a status
+        // bar. However, that brings about its own complications: what if a
+        // test's output doesn't include a newline? We might have to use a
+        // curses-like UI which would be a lot of work for not much gain.
+        self.no_capture
+            // Some CI environments appear to pretend to be a terminal.
+            || is_ci
+            || self.hide_progress_bar
+            // --status-level fail-only implies --hide-progress-bar.
+            || self.status_levels.status_level == StatusLevel::FailOnly
+    }
 }
 
 /// Functionality to report test results to stderr, JUnit, and/or structured,
@@ -146,7 +202,7 @@ impl<'a> DisplayReporter<'a> {
                     .write_event_impl(event, &mut buf)
                     .map_err(WriteEventError::Io)?;
 
-                state.update_progress_bar(event, &self.inner.styles);
+                state.update_progress_bar(event, self.inner.format, &self.inner.styles);
                 state.write_buf(&buf).map_err(WriteEventError::Io)
             }
             ReporterStderrImpl::TerminalWithoutBar => {
@@ -199,10 +255,11 @@ impl ReporterStderrImpl<'_> {
 
 #[derive(Debug)]
 enum FinalOutput {
-    Skipped(#[expect(dead_code)] MismatchReason),
+    Skipped(MismatchReason),
     Executed {
         run_statuses: ExecutionStatuses,
         display_output: bool,
+        tracking_issue: Option<String>,
     },
 }
 
@@ -219,20 +276,121 @@ struct DisplayReporterImpl<'a> {
     default_filter: CompiledDefaultFilter,
     status_levels: StatusLevels,
     no_capture: bool,
+    // Whether lines other than failures and the final summary are hidden, with
+    // the fail-only status level.
+    quiet: bool,
+    // Buffered output for each test binary. Empty unless grouping by binary.
+    binary_groups: BTreeMap<RustBinaryId, BinaryGroup>,
+    final_summary: FinalSummary,
+    format: DisplayFormat,
     styles: Box<Styles>,
     theme_characters: ThemeCharacters,
     cancel_status: Option<CancelReason>,
     unit_output: UnitOutputReporter,
     final_outputs: DebugIgnore<Vec<(TestInstance<'a>, FinalOutput)>>,
+    // Number of failed tests in each failure class, in the order first seen.
+    failure_classes: IndexMap<String, usize>,
+    // Annotations printed by finished tests, keyed by test instance.
+    annotations: BTreeMap<TestInstanceId<'a>, BTreeMap<String, String>>,
+    // Startup overhead of each test binary, summed across all attempts.
+    startup_overheads: BTreeMap<&'a RustBinaryId, StartupOverhead>,
+    // Time taken by the last attempt of each finished test, only tracked if the
+    // final summary lists the slowest tests.
+    test_times: Vec<(TestInstanceId<'a>, Duration)>,
+    // Tests that were run more than once, only tracked if the final summary
+    // lists retried tests.
+    retried_tests: Vec<(TestInstanceId<'a>, RetriedTest)>,
+}
+
+/// A test that was retried, as listed in the final summary.
+#[derive(Clone, Copy, Debug)]
+struct RetriedTest {
+    attempts: usize,
+    passed: bool,
+}
+
+/// The startup overhead of a test binary, summed across the test processes it
+/// was run in.
+#[derive(Clone, Copy, Debug, Default)]
+struct StartupOverhead {
+    total: Duration,
+    count: usize,
 }
 
+impl StartupOverhead {
+    fn mean(&self) -> Duration {
+        self.total / self.count.max(1) as u32
+    }
+}
+
+/// Test binaries that take at least this long to start up on average are
+/// listed in the final summary.
+const SLOW_STARTUP_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// The maximum number of test binaries listed as slow to start up.
+const MAX_SLOW_STARTUP_BINARIES: usize = 5;
+
 impl<'a> DisplayReporterImpl<'a> {
     fn write_event_impl(
         &mut self,
         event: &TestEvent<'a>,
         writer: &mut dyn Write,
     ) -> io::Result<()> {
+        if self.binary_groups.is_empty() {
+            return self.write_event_ungrouped(event, writer);
+        }
+
+        let test_instance = match &event.kind {
+            TestEventKind::TestStarted { test_instance, .. }
+            | TestEventKind::TestSlow { test_instance, .. }
+            | TestEventKind::TestAttemptFailedWillRetry { test_instance, .. }
+            | TestEventKind::TestRetryStarted { test_instance, .. }
+            | TestEventKind::TestFinished { test_instance, .. }
+            | TestEventKind::TestSkipped { test_instance, .. } => test_instance,
+            TestEventKind::RunFinished { .. } => {
+                // Show binaries that didn't complete, for example because the
+                // run was cancelled, before the summary.
+                for (binary_id, group) in std::mem::take(&mut self.binary_groups) {
+                    if group.passed + group.failed + group.skipped > 0 || !group.buf.is_empty() {
+                        group.write(binary_id.as_str(), self.format, &self.styles, writer)?;
+                    }
+                }
+                return self.write_event_ungrouped(event, writer);
+            }
+            _ => return self.write_event_ungrouped(event, writer),
+        };
+
+        let binary_id = &test_instance.suite_info.binary_id;
+        let Some(mut group) = self.binary_groups.remove(binary_id) else {
+            return self.write_event_ungrouped(event, writer);
+        };
+        let mut buf = std::mem::take(&mut group.buf);
+        let res = self.write_event_ungrouped(event, &mut buf);
+        group.buf = buf;
         match &event.kind {
+            TestEventKind::TestFinished { run_statuses, .. } => {
+                let time_taken = run_statuses.iter().map(|status| status.time_taken).sum();
+                group.on_finished(run_statuses.last_status(), time_taken);
+            }
+            TestEventKind::TestSkipped { .. } => group.on_skipped(),
+            _ => {}
+        }
+
+        if group.remaining == 0 {
+            group.write(binary_id.as_str(), self.format, &self.styles, writer)?;
+        } else {
+            self.binary_groups.insert(binary_id.clone(), group);
+        }
+        res
+    }
+
+    fn write_event_ungrouped(
+        &mut self,
+        event: &TestEvent<'a>,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        match &event.kind {
+            TestEventKind::RunStarted { .. } if self.quiet => {}
             TestEventKind::RunStarted {
                 test_list,
                 run_id,
@@ -258,19 +416,23 @@ impl<'a> DisplayReporterImpl<'a> {
                 write!(
                     writer,
                     "{} {tests_str} across {} {binaries_str}",
-                    test_list.run_count().style(count_style),
-                    test_list.listed_binary_count().style(count_style),
+                    self.format.count(test_list.run_count()).style(count_style),
+                    self.format
+                        .count(test_list.listed_binary_count())
+                        .style(count_style),
                 )?;
 
                 write_skip_counts(
                     test_list.skip_counts(),
                     &self.default_filter,
+                    self.format,
                     &self.styles,
                     writer,
                 )?;
 
                 writeln!(writer)?;
             }
+            TestEventKind::SetupScriptStarted { .. } if self.quiet => {}
             TestEventKind::SetupScriptStarted {
                 index,
                 total,
@@ -304,7 +466,7 @@ impl<'a> DisplayReporterImpl<'a> {
                 writeln!(
                     writer,
                     "{}{}",
-                    DisplaySlowDuration(*elapsed),
+                    DisplaySlowDuration(self.format.duration(*elapsed)),
                     self.display_script_instance(script_id.clone(), command, args)
                 )?;
             }
@@ -315,7 +477,11 @@ impl<'a> DisplayReporterImpl<'a> {
                 run_status,
                 ..
             } => {
-                self.write_setup_script_status_line(script_id, command, args, run_status, writer)?;
+                if !self.quiet || !run_status.result.is_success() {
+                    self.write_setup_script_status_line(
+                        script_id, command, args, run_status, writer,
+                    )?;
+                }
                 // Always display failing setup script output if it exists. We may change this in
                 // the future.
                 if !run_status.result.is_success() {
@@ -374,7 +540,7 @@ impl<'a> DisplayReporterImpl<'a> {
                 writeln!(
                     writer,
                     "{}{}",
-                    DisplaySlowDuration(*elapsed),
+                    DisplaySlowDuration(self.format.duration(*elapsed)),
                     self.display_test_instance(test_instance.id())
                 )?;
             }
@@ -397,11 +563,19 @@ impl<'a> DisplayReporterImpl<'a> {
                         writer,
                         "{:>12} {}",
                         try_status_string.style(self.styles.retry),
-                        DisplayBracketedDuration(run_status.time_taken),
+                        DisplayBracketedDuration(self.format.duration(run_status.time_taken)),
                     )?;
 
                     // Print the name of the test.
                     writeln!(writer, "{}", self.display_test_instance(test_instance.id()))?;
+                    if let Some(kept_tmpdir) = &run_status.kept_tmpdir {
+                        self.write_kept_tmpdir_line(kept_tmpdir, writer)?;
+                    }
+                    if let Some(kept_workdir) = &run_status.kept_workdir {
+                        self.write_kept_workdir_line(kept_workdir, writer)?;
+                    }
+                    self.write_access_audit_lines(run_status, writer)?;
+                    self.write_crash_dump_lines(run_status, writer)?;
 
                     // This test is guaranteed to have failed.
                     assert!(
@@ -430,7 +604,7 @@ impl<'a> DisplayReporterImpl<'a> {
                             writer,
                             "{:>12} {}",
                             delay_string.style(self.styles.retry),
-                            DisplayDurationBy(*delay_before_next_attempt)
+                            DisplayDurationBy(self.format.duration(*delay_before_next_attempt))
                         )?;
 
                         // Print the name of the test.
@@ -461,11 +635,45 @@ impl<'a> DisplayReporterImpl<'a> {
                 test_instance,
                 success_output,
                 failure_output,
+                tracking_issue,
                 run_statuses,
                 ..
             } => {
                 let describe = run_statuses.describe();
                 let last_status = run_statuses.last_status();
+                if !last_status.result.is_success() {
+                    if let Some(failure_class) = &last_status.failure_class {
+                        *self
+                            .failure_classes
+                            .entry(failure_class.clone())
+                            .or_default() += 1;
+                    }
+                }
+                if !last_status.annotations.is_empty() {
+                    self.annotations
+                        .insert(test_instance.id(), last_status.annotations.clone());
+                }
+                for startup_time in run_statuses.iter().filter_map(|status| status.startup_time) {
+                    let overhead = self
+                        .startup_overheads
+                        .entry(&test_instance.suite_info.binary_id)
+                        .or_default();
+                    overhead.total += startup_time;
+                    overhead.count += 1;
+                }
+                if self.final_summary.slowest > 0 {
+                    self.test_times
+                        .push((test_instance.id(), last_status.time_taken));
+                }
+                if self.final_summary.retries && run_statuses.len() > 1 {
+                    self.retried_tests.push((
+                        test_instance.id(),
+                        RetriedTest {
+                            attempts: run_statuses.len(),
+                            passed: last_status.result.is_success(),
+                        },
+                    ));
+                }
                 let test_output_display = match last_status.result.is_success() {
                     true => self.unit_output.success_output(*success_output),
                     false => self.unit_output.failure_output(*failure_output),
@@ -479,10 +687,24 @@ impl<'a> DisplayReporterImpl<'a> {
                 );
 
                 if output_on_test_finished.write_status_line {
-                    self.write_status_line(*test_instance, describe, writer)?;
+                    self.write_status_line(
+                        *test_instance,
+                        describe,
+                        failure_summary(last_status).as_deref(),
+                        writer,
+                    )?;
+                    if let Some(kept_tmpdir) = &last_status.kept_tmpdir {
+                        self.write_kept_tmpdir_line(kept_tmpdir, writer)?;
+                    }
+                    if let Some(kept_workdir) = &last_status.kept_workdir {
+                        self.write_kept_workdir_line(kept_workdir, writer)?;
+                    }
+                    self.write_access_audit_lines(last_status, writer)?;
+                    self.write_crash_dump_lines(last_status, writer)?;
                 }
                 if output_on_test_finished.show_immediate {
                     self.write_test_execute_status(test_instance, last_status, false, writer)?;
+                    self.write_attempt_diff(test_instance.id(), describe, writer)?;
                 }
                 if let OutputStoreFinal::Yes { display_output } =
                     output_on_test_finished.store_final
@@ -492,6 +714,7 @@ impl<'a> DisplayReporterImpl<'a> {
                         FinalOutput::Executed {
                             run_statuses: run_statuses.clone(),
                             display_output,
+                            tracking_issue: tracking_issue.clone(),
                         },
                     ));
                 }
@@ -501,7 +724,7 @@ impl<'a> DisplayReporterImpl<'a> {
                 reason,
             } => {
                 if self.status_levels.status_level >= StatusLevel::Skip {
-                    self.write_skip_line(test_instance.id(), writer)?;
+                    self.write_skip_line(test_instance, *reason, writer)?;
                 }
                 if self.status_levels.final_status_level >= FinalStatusLevel::Skip {
                     self.final_outputs
@@ -540,6 +763,62 @@ impl<'a> DisplayReporterImpl<'a> {
                 }
                 writeln!(writer)?;
             }
+            TestEventKind::ScopeBeginCancel {
+                scope,
+                test_instance,
+                running,
+            } => {
+                let suite_info = test_instance.suite_info;
+                // ScopeBeginCancel is never emitted for the run scope -- in
+                // that case, RunBeginCancel is emitted instead.
+                let scope_name = match scope {
+                    MaxFailScope::Package => suite_info.package.name(),
+                    MaxFailScope::Binary | MaxFailScope::Run => suite_info.binary_id.as_str(),
+                };
+
+                write!(
+                    writer,
+                    "{:>12} remaining tests in {scope} {} due to {}",
+                    "Cancelling".style(self.styles.fail),
+                    scope_name.style(self.styles.list_styles.binary_id),
+                    CancelReason::TestFailure
+                        .to_static_str()
+                        .style(self.styles.fail),
+                )?;
+                if *running > 0 {
+                    let tests_str = plural::tests_str(*running);
+                    write!(
+                        writer,
+                        ": {} {tests_str} still running",
+                        running.style(self.styles.count),
+                    )?;
+                }
+                writeln!(writer)?;
+            }
+            TestEventKind::ConcurrencyReduced { .. }
+            | TestEventKind::ConcurrencyRestored { .. }
+                if self.quiet => {}
+            TestEventKind::ConcurrencyReduced {
+                limit,
+                test_threads,
+                pressure,
+            } => {
+                writeln!(
+                    writer,
+                    "{:>12} to {} of {} test threads: {pressure}",
+                    "Throttling".style(self.styles.skip),
+                    limit.style(self.styles.count),
+                    test_threads.style(self.styles.count),
+                )?;
+            }
+            TestEventKind::ConcurrencyRestored { test_threads } => {
+                writeln!(
+                    writer,
+                    "{:>12} all {} test threads: system pressure subsided",
+                    "Restoring".style(self.styles.pass),
+                    test_threads.style(self.styles.count),
+                )?;
+            }
             TestEventKind::RunBeginKill {
                 setup_scripts_running,
                 running,
@@ -644,14 +923,14 @@ impl<'a> DisplayReporterImpl<'a> {
                 // TODO: display setup_scripts_running as well
                 writeln!(
                     writer,
-                    "{} in {:.3?}s",
+                    "{} in {}",
                     // Using "total" here for the number of running units is a
                     // slight fudge, but it prevents situations where (due to
                     // races with unit tasks exiting) the numbers don't exactly
                     // match up. It's also not dishonest -- there really are
                     // these many units currently running.
-                    progress_bar_msg(run_stats, *total, &self.styles),
-                    event.elapsed.as_secs_f64(),
+                    progress_bar_msg(run_stats, *total, self.format, &self.styles),
+                    self.format.duration(event.elapsed),
                 )?;
             }
             TestEventKind::InfoResponse {
@@ -692,6 +971,7 @@ impl<'a> DisplayReporterImpl<'a> {
                         current_stats,
                         *running,
                         *cancel_reason,
+                        self.format,
                         &self.styles,
                     )
                 )?;
@@ -716,21 +996,26 @@ impl<'a> DisplayReporterImpl<'a> {
                 )?;
 
                 // Next, print the total time taken.
-                // * > means right-align.
-                // * 8 is the number of characters to pad to.
-                // * .3 means print two digits after the decimal point.
-                write!(writer, "[{:>8.3?}s] ", elapsed.as_secs_f64())?;
+                write!(
+                    writer,
+                    "{}",
+                    DisplayBracketedDuration(self.format.duration(*elapsed))
+                )?;
 
                 write!(
                     writer,
                     "{}",
-                    run_stats.finished_count.style(self.styles.count)
+                    self.format
+                        .count(run_stats.finished_count)
+                        .style(self.styles.count)
                 )?;
                 if run_stats.finished_count != run_stats.initial_run_count {
                     write!(
                         writer,
                         "/{}",
-                        run_stats.initial_run_count.style(self.styles.count)
+                        self.format
+                            .count(run_stats.initial_run_count)
+                            .style(self.styles.count)
                     )?;
                 }
 
@@ -740,8 +1025,20 @@ impl<'a> DisplayReporterImpl<'a> {
                 );
 
                 let mut summary_str = String::new();
-                write_summary_str(run_stats, &self.styles, &mut summary_str);
+                write_summary_str(run_stats, self.format, &self.styles, &mut summary_str);
+                write_skip_reasons_str(
+                    &run_stats.skipped_by_reason,
+                    self.format,
+                    &self.styles,
+                    &mut summary_str,
+                );
                 writeln!(writer, " {tests_str} run: {summary_str}")?;
+                self.write_failure_classes(writer)?;
+                self.write_annotations(writer)?;
+                self.write_startup_overheads(writer)?;
+                self.write_cgroup_limits(run_stats, writer)?;
+                self.write_slowest_tests(writer)?;
+                self.write_retried_tests(writer)?;
 
                 // Don't print out test outputs after Ctrl-C, but *do* print them after SIGTERM or
                 // SIGHUP since those tend to be automated tasks performing kills.
@@ -759,20 +1056,36 @@ impl<'a> DisplayReporterImpl<'a> {
 
                     for (test_instance, final_output) in &*self.final_outputs {
                         match final_output {
-                            FinalOutput::Skipped(_) => {
-                                self.write_skip_line(test_instance.id(), writer)?;
+                            FinalOutput::Skipped(reason) => {
+                                self.write_skip_line(test_instance, *reason, writer)?;
                             }
                             FinalOutput::Executed {
                                 run_statuses,
                                 display_output,
+                                tracking_issue,
                             } => {
                                 let last_status = run_statuses.last_status();
-
+                                // Show why failed tests failed, so that users
+                                // don't have to scroll up to find out.
                                 self.write_final_status_line(
                                     test_instance.id(),
                                     run_statuses.describe(),
+                                    failure_summary(last_status).as_deref(),
                                     writer,
                                 )?;
+                                if !last_status.result.is_success() {
+                                    if let Some(tracking_issue) = tracking_issue {
+                                        self.write_tracking_issue_line(tracking_issue, writer)?;
+                                    }
+                                    if let Some(kept_tmpdir) = &last_status.kept_tmpdir {
+                                        self.write_kept_tmpdir_line(kept_tmpdir, writer)?;
+                                    }
+                                    if let Some(kept_workdir) = &last_status.kept_workdir {
+                                        self.write_kept_workdir_line(kept_workdir, writer)?;
+                                    }
+                                    self.write_access_audit_lines(last_status, writer)?;
+                                    self.write_crash_dump_lines(last_status, writer)?;
+                                }
                                 if *display_output {
                                     self.write_test_execute_status(
                                         test_instance,
@@ -780,6 +1093,11 @@ impl<'a> DisplayReporterImpl<'a> {
                                         false,
                                         writer,
                                     )?;
+                                    self.write_attempt_diff(
+                                        test_instance.id(),
+                                        run_statuses.describe(),
+                                        writer,
+                                    )?;
                                 }
                             }
                         }
@@ -787,25 +1105,292 @@ impl<'a> DisplayReporterImpl<'a> {
                 }
 
                 // Print out warnings at the end, if any.
-                write_final_warnings(stats_summary, self.cancel_status, &self.styles, writer)?;
+                write_final_warnings(
+                    stats_summary,
+                    self.cancel_status,
+                    self.format,
+                    &self.styles,
+                    writer,
+                )?;
             }
         }
 
         Ok(())
     }
 
+    fn write_tracking_issue_line(
+        &self,
+        tracking_issue: &str,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{:>12} {}",
+            "tracked by".style(self.styles.skip),
+            tracking_issue.style(self.styles.count),
+        )
+    }
+
+    fn write_kept_tmpdir_line(
+        &self,
+        kept_tmpdir: &Utf8Path,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{:>12} {}",
+            "tmpdir kept".style(self.styles.skip),
+            kept_tmpdir.style(self.styles.count),
+        )
+    }
+
+    fn write_kept_workdir_line(
+        &self,
+        kept_workdir: &Utf8Path,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{:>12} {}",
+            "workdir kept".style(self.styles.skip),
+            kept_workdir.style(self.styles.count),
+        )
+    }
+
+    fn write_crash_dump_lines(
+        &self,
+        status: &ExecuteStatus,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let Some(crash_dump) = &status.crash_dump else {
+            return Ok(());
+        };
+        writeln!(
+            writer,
+            "{:>12} {}",
+            "crash dump".style(self.styles.fail),
+            crash_dump.path.style(self.styles.count),
+        )?;
+        if let Some(backtrace) = &crash_dump.backtrace {
+            writeln!(
+                writer,
+                "{:>12} {}",
+                "backtrace".style(self.styles.skip),
+                backtrace.style(self.styles.count),
+            )?;
+        }
+        Ok(())
+    }
+
+    // Writes out the disallowed hosts a failed test contacted, and where its access report is.
+    fn write_access_audit_lines(
+        &self,
+        status: &ExecuteStatus,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        if status.result.is_success() {
+            return Ok(());
+        }
+        if !status.access_violations.is_empty() {
+            let hosts = status
+                .access_violations
+                .iter()
+                .map(|addr| addr.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(
+                writer,
+                "{:>12} {}",
+                "disallowed".style(self.styles.fail),
+                hosts.style(self.styles.count),
+            )?;
+        }
+        if let Some(access_report) = &status.access_report {
+            writeln!(
+                writer,
+                "{:>12} {}",
+                "audit report".style(self.styles.skip),
+                access_report.style(self.styles.count),
+            )?;
+        }
+        Ok(())
+    }
+
+    // Writes out the number of failed tests in each failure class, if any were classified.
+    fn write_failure_classes(&self, writer: &mut dyn Write) -> io::Result<()> {
+        if self.failure_classes.is_empty() {
+            return Ok(());
+        }
+
+        write!(writer, "{:>12} ", "Classes".style(self.styles.fail))?;
+        for (i, (failure_class, count)) in self.failure_classes.iter().enumerate() {
+            if i > 0 {
+                write!(writer, ", ")?;
+            }
+            write!(
+                writer,
+                "{} {}",
+                self.format.count(*count).style(self.styles.count),
+                failure_class.style(self.styles.fail)
+            )?;
+        }
+        writeln!(writer)
+    }
+
+    // Writes out the test binaries that are slowest to start up, if any are
+    // slow enough to be worth looking into.
+    fn write_startup_overheads(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let mut slow_binaries: Vec<_> = self
+            .startup_overheads
+            .iter()
+            .filter(|(_, overhead)| overhead.mean() >= SLOW_STARTUP_THRESHOLD)
+            .collect();
+        slow_binaries.sort_by_key(|(binary_id, overhead)| (Reverse(overhead.total), *binary_id));
+
+        for (i, (binary_id, overhead)) in slow_binaries
+            .into_iter()
+            .take(MAX_SLOW_STARTUP_BINARIES)
+            .enumerate()
+        {
+            if i == 0 {
+                write!(writer, "{:>12} ", "Startup".style(self.styles.skip))?;
+            } else {
+                write!(writer, "{:>12} ", "")?;
+            }
+            writeln!(
+                writer,
+                "{}: {} across {} {} ({} on average)",
+                binary_id.style(self.styles.list_styles.binary_id),
+                self.format.duration(overhead.total),
+                self.format.count(overhead.count).style(self.styles.count),
+                plural::processes_str(overhead.count),
+                self.format.duration(overhead.mean()),
+            )?;
+        }
+        Ok(())
+    }
+
+    // Writes out the annotations printed by each test, if any.
+    fn write_annotations(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for (i, (test_instance, annotations)) in self.annotations.iter().enumerate() {
+            if i == 0 {
+                write!(writer, "{:>12} ", "Annotations".style(self.styles.count))?;
+            } else {
+                write!(writer, "{:>12} ", "")?;
+            }
+            write!(writer, "{}:", self.display_test_instance(*test_instance))?;
+            for (key, value) in annotations {
+                write!(writer, " {}={value}", key.style(self.styles.count))?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    // Writes out the slowest tests, if requested in the final summary.
+    fn write_slowest_tests(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        self.test_times
+            .sort_by_key(|(test_instance, time_taken)| (Reverse(*time_taken), *test_instance));
+
+        for (i, (test_instance, time_taken)) in self
+            .test_times
+            .iter()
+            .take(self.final_summary.slowest)
+            .enumerate()
+        {
+            if i == 0 {
+                write!(writer, "{:>12} ", "Slowest".style(self.styles.skip))?;
+            } else {
+                write!(writer, "{:>12} ", "")?;
+            }
+            writeln!(
+                writer,
+                "{}{}",
+                DisplayBracketedDuration(self.format.duration(*time_taken)),
+                self.display_test_instance(*test_instance),
+            )?;
+        }
+        Ok(())
+    }
+
+    // Writes out the tests that were retried, if requested in the final
+    // summary.
+    fn write_retried_tests(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        self.retried_tests
+            .sort_by_key(|(test_instance, _)| *test_instance);
+
+        for (i, (test_instance, retried)) in self.retried_tests.iter().enumerate() {
+            if i == 0 {
+                write!(writer, "{:>12} ", "Retried".style(self.styles.retry))?;
+            } else {
+                write!(writer, "{:>12} ", "")?;
+            }
+            let outcome = if retried.passed {
+                "passed".style(self.styles.skip)
+            } else {
+                "failed".style(self.styles.fail)
+            };
+            writeln!(
+                writer,
+                "{}: {outcome} after {} attempts",
+                self.display_test_instance(*test_instance),
+                self.format.count(retried.attempts).style(self.styles.count),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_cgroup_limits(&self, run_stats: &RunStats, writer: &mut dyn Write) -> io::Result<()> {
+        let applied = run_stats.cgroup_limits_applied;
+        let unavailable = run_stats.cgroup_limits_unavailable;
+        if applied == 0 && unavailable == 0 {
+            return Ok(());
+        }
+
+        write!(writer, "{:>12} ", "Cgroups".style(self.styles.count))?;
+        write!(
+            writer,
+            "limits applied to {} {}",
+            self.format.count(applied).style(self.styles.count),
+            plural::tests_str(applied),
+        )?;
+        if unavailable > 0 {
+            write!(
+                writer,
+                ", {} for {} {}",
+                "unavailable".style(self.styles.skip),
+                self.format.count(unavailable).style(self.styles.count),
+                plural::tests_str(unavailable),
+            )?;
+        }
+        writeln!(writer)
+    }
+
     fn write_skip_line(
         &self,
-        test_instance: TestInstanceId<'a>,
+        test_instance: &TestInstance<'a>,
+        reason: MismatchReason,
         writer: &mut dyn Write,
     ) -> io::Result<()> {
         write!(writer, "{:>12} ", "SKIP".style(self.styles.skip))?;
         // same spacing   [   0.034s]
-        writeln!(
+        write!(
             writer,
             "[         ] {}",
-            self.display_test_instance(test_instance)
+            self.display_test_instance(test_instance.id())
         )?;
+        if reason == MismatchReason::ScopeCancelled {
+            writeln!(
+                writer,
+                " ({})",
+                "cancelled by max-fail scope".style(self.styles.skip)
+            )?;
+        } else {
+            match test_instance.test_info.skipped_ignore_reason() {
+                Some(reason) => writeln!(writer, " (ignored: {})", reason.style(self.styles.skip))?,
+                None => writeln!(writer)?,
+            }
+        }
 
         Ok(())
     }
@@ -838,7 +1423,7 @@ impl<'a> DisplayReporterImpl<'a> {
         writeln!(
             writer,
             "{}{}",
-            DisplayBracketedDuration(status.time_taken),
+            DisplayBracketedDuration(self.format.duration(status.time_taken)),
             self.display_script_instance(script_id.clone(), command, args)
         )?;
 
@@ -849,6 +1434,7 @@ impl<'a> DisplayReporterImpl<'a> {
         &self,
         test_instance: TestInstance<'a>,
         describe: ExecutionDescription<'_>,
+        summary: Option<&str>,
         writer: &mut dyn Write,
     ) -> io::Result<()> {
         let last_status = describe.last_status();
@@ -888,12 +1474,17 @@ impl<'a> DisplayReporterImpl<'a> {
         };
 
         // Print the time taken and the name of the test.
-        writeln!(
+        write!(
             writer,
             "{}{}",
-            DisplayBracketedDuration(last_status.time_taken),
+            DisplayBracketedDuration(self.format.duration(last_status.time_taken)),
             self.display_test_instance(test_instance.id())
         )?;
+        // Then, a summary of the failure, if any.
+        if let Some(summary) = summary {
+            write!(writer, ": {summary}")?;
+        }
+        writeln!(writer)?;
 
         // On Windows, also print out the exception if available.
         #[cfg(windows)]
@@ -912,6 +1503,7 @@ impl<'a> DisplayReporterImpl<'a> {
         &self,
         test_instance: TestInstanceId<'a>,
         describe: ExecutionDescription<'_>,
+        summary: Option<&str>,
         writer: &mut dyn Write,
     ) -> io::Result<()> {
         let last_status = describe.last_status();
@@ -964,12 +1556,17 @@ impl<'a> DisplayReporterImpl<'a> {
         };
 
         // Next, print the time taken and the name of the test.
-        writeln!(
+        write!(
             writer,
             "{}{}",
-            DisplayBracketedDuration(last_status.time_taken),
+            DisplayBracketedDuration(self.format.duration(last_status.time_taken)),
             self.display_test_instance(test_instance),
         )?;
+        // Then, a summary of the failure, if any.
+        if let Some(summary) = summary {
+            write!(writer, ": {summary}")?;
+        }
+        writeln!(writer)?;
 
         // On Windows, also print out the exception if available.
         #[cfg(windows)]
@@ -1142,16 +1739,16 @@ impl<'a> DisplayReporterImpl<'a> {
                 };
                 write!(
                     writer,
-                    "{status_str}: {attempt_str}{kind} {} for {:.3?}s as PID {}",
+                    "{status_str}: {attempt_str}{kind} {} for {} as PID {}",
                     "running".style(running_style),
-                    time_taken.as_secs_f64(),
+                    self.format.duration(*time_taken),
                     pid.style(self.styles.count),
                 )?;
                 if let Some(slow_after) = slow_after {
                     write!(
                         writer,
-                        " (marked slow after {:.3?}s)",
-                        slow_after.as_secs_f64()
+                        " (marked slow after {})",
+                        self.format.duration(*slow_after)
                     )?;
                 }
                 writeln!(writer)?;
@@ -1167,12 +1764,12 @@ impl<'a> DisplayReporterImpl<'a> {
                 write!(writer, "{status_str}: {attempt_str}{kind} ")?;
 
                 self.write_info_execution_result(*tentative_result, slow_after.is_some(), writer)?;
-                write!(writer, " after {:.3?}s", time_taken.as_secs_f64())?;
+                write!(writer, " after {}", self.format.duration(*time_taken))?;
                 if let Some(slow_after) = slow_after {
                     write!(
                         writer,
-                        " (marked slow after {:.3?}s)",
-                        slow_after.as_secs_f64()
+                        " (marked slow after {})",
+                        self.format.duration(*slow_after)
                     )?;
                 }
                 writeln!(writer)?;
@@ -1182,12 +1779,12 @@ impl<'a> DisplayReporterImpl<'a> {
                 if *waiting_duration >= Duration::from_secs(1) {
                     writeln!(
                         writer,
-                        "{}:   spent {:.3?}s waiting for {kind} PID {} to shut down, \
-                         will mark as leaky after another {:.3?}s",
+                        "{}:   spent {} waiting for {kind} PID {} to shut down, \
+                         will mark as leaky after another {}",
                         "note".style(self.styles.count),
-                        waiting_duration.as_secs_f64(),
+                        self.format.duration(*waiting_duration),
                         pid.style(self.styles.count),
-                        remaining.as_secs_f64(),
+                        self.format.duration(*remaining),
                     )?;
                 }
             }
@@ -1201,12 +1798,12 @@ impl<'a> DisplayReporterImpl<'a> {
             } => {
                 write!(writer, "{status_str}: {attempt_str}{kind} ")?;
                 self.write_info_execution_result(Some(*result), slow_after.is_some(), writer)?;
-                write!(writer, " after {:.3?}s", time_taken.as_secs_f64())?;
+                write!(writer, " after {}", self.format.duration(*time_taken))?;
                 if let Some(slow_after) = slow_after {
                     write!(
                         writer,
-                        " (marked slow after {:.3?}s)",
-                        slow_after.as_secs_f64()
+                        " (marked slow after {})",
+                        self.format.duration(*slow_after)
                     )?;
                 }
                 writeln!(writer)?;
@@ -1226,10 +1823,10 @@ impl<'a> DisplayReporterImpl<'a> {
                 )?;
                 writeln!(
                     writer,
-                    "{}:   waited {:.3?}s so far, will wait another {:.3?}s before retrying {kind}",
+                    "{}:   waited {} so far, will wait another {} before retrying {kind}",
                     "note".style(self.styles.count),
-                    waiting_duration.as_secs_f64(),
-                    remaining.as_secs_f64(),
+                    self.format.duration(*waiting_duration),
+                    self.format.duration(*remaining),
                 )?;
             }
         }
@@ -1255,13 +1852,13 @@ impl<'a> DisplayReporterImpl<'a> {
 
         writeln!(
             writer,
-            "{}: {attempt_str}{} {kind} PID {} due to {} ({} ran for {:.3?}s)",
+            "{}: {attempt_str}{} {kind} PID {} due to {} ({} ran for {})",
             "status".style(self.styles.count),
             "terminating".style(self.styles.fail),
             pid.style(self.styles.count),
             reason.style(self.styles.count),
             kind,
-            time_taken.as_secs_f64(),
+            self.format.duration(*time_taken),
         )?;
 
         match method {
@@ -1269,13 +1866,13 @@ impl<'a> DisplayReporterImpl<'a> {
             UnitTerminateMethod::Signal(signal) => {
                 writeln!(
                     writer,
-                    "{}:   sent {} to process group; spent {:.3?}s waiting for {} to exit, \
-                     will SIGKILL after another {:.3?}s",
+                    "{}:   sent {} to process group; spent {} waiting for {} to exit, \
+                     will SIGKILL after another {}",
                     "note".style(self.styles.count),
                     signal,
-                    waiting_duration.as_secs_f64(),
+                    self.format.duration(*waiting_duration),
                     kind,
-                    remaining.as_secs_f64(),
+                    self.format.duration(*remaining),
                 )?;
             }
             #[cfg(windows)]
@@ -1293,12 +1890,12 @@ impl<'a> DisplayReporterImpl<'a> {
             UnitTerminateMethod::Wait => {
                 writeln!(
                     writer,
-                    "{}:   waiting for {} to exit on its own; spent {:.3?}s, will terminate \
-                     job object after another {:.3?}s",
+                    "{}:   waiting for {} to exit on its own; spent {}, will terminate \
+                     job object after another {}",
                     "note".style(self.styles.count),
                     kind,
-                    waiting_duration.as_secs_f64(),
-                    remaining.as_secs_f64(),
+                    self.format.duration(*waiting_duration),
+                    self.format.duration(*remaining),
                 )?;
             }
             #[cfg(test)]
@@ -1306,12 +1903,12 @@ impl<'a> DisplayReporterImpl<'a> {
                 // This is only used in tests.
                 writeln!(
                     writer,
-                    "{}:   fake termination method; spent {:.3?}s waiting for {} to exit, \
-                     will kill after another {:.3?}s",
+                    "{}:   fake termination method; spent {} waiting for {} to exit, \
+                     will kill after another {}",
                     "note".style(self.styles.count),
-                    waiting_duration.as_secs_f64(),
+                    self.format.duration(*waiting_duration),
                     kind,
-                    remaining.as_secs_f64(),
+                    self.format.duration(*remaining),
                 )?;
             }
         }
@@ -1366,6 +1963,27 @@ impl<'a> DisplayReporterImpl<'a> {
             Some(ExecutionResult::ExecFail) => {
                 write!(writer, "{}", "failed to execute".style(self.styles.fail))
             }
+            Some(ExecutionResult::MemoryError { tool }) => {
+                write!(
+                    writer,
+                    "{} reported by {tool}",
+                    "memory error".style(self.styles.fail)
+                )
+            }
+            Some(ExecutionResult::CancelAcknowledged) => {
+                write!(
+                    writer,
+                    "{} (cooperative)",
+                    "cancelled".style(self.styles.fail)
+                )
+            }
+            Some(ExecutionResult::AccessViolation) => {
+                write!(
+                    writer,
+                    "{} disallowed hosts",
+                    "contacted".style(self.styles.fail)
+                )
+            }
             None => {
                 write!(
                     writer,
@@ -1409,6 +2027,51 @@ impl<'a> DisplayReporterImpl<'a> {
         )
     }
 
+    // For tests that failed on every attempt, writes out how the output
+    // changed since the previous attempt.
+    fn write_attempt_diff(
+        &self,
+        test_instance: TestInstanceId<'a>,
+        describe: ExecutionDescription<'_>,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let Some(diff) = AttemptOutputDiff::for_description(&describe) else {
+            return Ok(());
+        };
+
+        let style = self.styles.fail;
+        let mut header = String::new();
+        swrite!(header, "{} ", self.theme_characters.hbar(4).style(style));
+        let out_len = self.write_attempt(describe.last_status(), style, &mut header);
+        swrite!(
+            header,
+            "{:width$} {}",
+            "DIFF:".style(style),
+            self.display_test_instance(test_instance),
+            // The width is to align test instances.
+            width = (19 - out_len),
+        );
+        if diff.is_identical() {
+            swrite!(header, " (identical to try {})", diff.from_attempt());
+        } else {
+            swrite!(header, " (from try {})", diff.from_attempt());
+        }
+        writeln!(writer, "{header}")?;
+
+        for line in diff.diff().lines() {
+            if line.starts_with("+++") || line.starts_with("---") {
+                writeln!(writer, "{}", line.style(self.styles.count))?;
+            } else if line.starts_with('+') {
+                writeln!(writer, "{}", line.style(self.styles.pass))?;
+            } else if line.starts_with('-') {
+                writeln!(writer, "{}", line.style(self.styles.fail))?;
+            } else {
+                writeln!(writer, "{line}")?;
+            }
+        }
+        writeln!(writer)
+    }
+
     // Returns the number of characters written out to the screen.
     fn write_attempt(&self, run_status: &ExecuteStatus, style: Style, out: &mut String) -> usize {
         if run_status.retry_data.total_attempts > 1 {
@@ -1438,6 +2101,11 @@ impl<'a> DisplayReporterImpl<'a> {
 
         let hbar = self.theme_characters.hbar(4);
 
+        // Tests marked #[should_panic] print out a panic message even when
+        // they pass: call that out so the output isn't mistaken for a failure.
+        let panicked_as_expected =
+            run_status.result.is_success() && heuristic_is_should_panic(&run_status.output);
+
         let stdout_header = {
             let mut header = String::new();
             swrite!(header, "{} ", hbar.style(header_style));
@@ -1465,6 +2133,9 @@ impl<'a> DisplayReporterImpl<'a> {
                 // The width is to align test instances.
                 width = (19 - out_len),
             );
+            if panicked_as_expected {
+                swrite!(header, " {}", "(panicked as expected)".style(header_style));
+            }
             header
         };
 
@@ -1480,6 +2151,9 @@ impl<'a> DisplayReporterImpl<'a> {
                 // The width is to align test instances.
                 width = (19 - out_len),
             );
+            if panicked_as_expected {
+                swrite!(header, " {}", "(panicked as expected)".style(header_style));
+            }
             header
         };
 
@@ -1591,6 +2265,14 @@ impl<'a> DisplayReporterImpl<'a> {
     }
 }
 
+/// Returns a one-line extract of why a test failed, if it did.
+fn failure_summary(status: &ExecuteStatus) -> Option<String> {
+    if status.result.is_success() {
+        return None;
+    }
+    UnitErrorDescription::new(UnitKind::Test, &status.output).summary_line()
+}
+
 fn status_str(result: ExecutionResult) -> Cow<'static, str> {
     // Max 12 characters here.
     match result {
@@ -1623,6 +2305,9 @@ fn status_str(result: ExecutionResult) -> Cow<'static, str> {
         ExecutionResult::Pass => "PASS".into(),
         ExecutionResult::Leak => "LEAK".into(),
         ExecutionResult::Timeout => "TIMEOUT".into(),
+        ExecutionResult::MemoryError { .. } => "MEMORY ERROR".into(),
+        ExecutionResult::CancelAcknowledged => "CANCELLED".into(),
+        ExecutionResult::AccessViolation => "ACCESS ERROR".into(),
     }
 }
 
@@ -1654,6 +2339,9 @@ fn short_status_str(result: ExecutionResult) -> Cow<'static, str> {
         ExecutionResult::Pass => "PASS".into(),
         ExecutionResult::Leak => "LEAK".into(),
         ExecutionResult::Timeout => "TMT".into(),
+        ExecutionResult::MemoryError { .. } => "MEMERR".into(),
+        ExecutionResult::CancelAcknowledged => "CNCL".into(),
+        ExecutionResult::AccessViolation => "ACCESS".into(),
     }
 }
 
@@ -1694,6 +2382,7 @@ fn write_windows_message_line(
 struct ThemeCharacters {
     hbar: char,
     progress_chars: &'static str,
+    ellipsis: &'static str,
 }
 
 impl Default for ThemeCharacters {
@@ -1701,6 +2390,7 @@ impl Default for ThemeCharacters {
         Self {
             hbar: '-',
             progress_chars: "=> ",
+            ellipsis: "...",
         }
     }
 }
@@ -1710,6 +2400,7 @@ impl ThemeCharacters {
         self.hbar = '─';
         // https://mike42.me/blog/2018-06-make-better-cli-progress-bars-with-unicode-block-characters
         self.progress_chars = "█▉▊▋▌▍▎▏ ";
+        self.ellipsis = "…";
     }
 
     fn hbar(&self, width: usize) -> String {
@@ -1745,22 +2436,93 @@ mod tests {
     where
         F: FnOnce(DisplayReporter<'a>),
     {
-        let builder = DisplayReporterBuilder {
+        let output = ReporterStderr::Buffer(out);
+        let reporter = test_builder().build(output);
+        f(reporter);
+    }
+
+    /// Returns a reporter builder with default settings.
+    fn test_builder() -> DisplayReporterBuilder {
+        DisplayReporterBuilder {
             default_filter: CompiledDefaultFilter::for_default_config(),
             status_levels: StatusLevels {
                 status_level: StatusLevel::Fail,
                 final_status_level: FinalStatusLevel::Fail,
             },
+            final_summary: FinalSummary::default(),
             test_count: 0,
+            binary_test_counts: BTreeMap::new(),
             success_output: Some(TestOutputDisplay::Immediate),
             failure_output: Some(TestOutputDisplay::Immediate),
             should_colorize: false,
+            theme: Theme::default(),
+            symbols: None,
             no_capture: true,
             hide_progress_bar: false,
+            show_slots: false,
+            format: DisplayFormat::default(),
+            workspace_root: Utf8PathBuf::from("/fake/workspace"),
+        }
+    }
+
+    #[test]
+    fn progress_bar_hidden() {
+        let mut builder = test_builder();
+        builder.no_capture = false;
+        assert!(!builder.progress_bar_hidden(false), "shown by default");
+        assert!(builder.progress_bar_hidden(true), "hidden in CI");
+
+        builder.no_capture = true;
+        assert!(builder.progress_bar_hidden(false), "hidden with no-capture");
+        builder.no_capture = false;
+
+        builder.hide_progress_bar = true;
+        assert!(builder.progress_bar_hidden(false), "hidden when requested");
+        builder.hide_progress_bar = false;
+
+        builder.status_levels.status_level = StatusLevel::FailOnly;
+        assert!(builder.progress_bar_hidden(false), "hidden with fail-only");
+    }
+
+    #[test]
+    fn fail_only_status_level() {
+        let mut builder = test_builder();
+        builder.no_capture = false;
+        builder.status_levels.status_level = StatusLevel::FailOnly;
+        let mut out = Vec::new();
+        let reporter = builder.build(ReporterStderr::Buffer(&mut out));
+
+        // fail-only shows the same test statuses as fail, and hides
+        // everything else.
+        assert_eq!(reporter.inner.status_levels.status_level, StatusLevel::Fail);
+        assert!(reporter.inner.quiet);
+
+        // With --no-capture, the status level is still treated as at least
+        // pass.
+        let mut builder = test_builder();
+        builder.status_levels.status_level = StatusLevel::FailOnly;
+        let mut out = Vec::new();
+        let reporter = builder.build(ReporterStderr::Buffer(&mut out));
+        assert_eq!(reporter.inner.status_levels.status_level, StatusLevel::Pass);
+    }
+
+    #[test]
+    fn symbols() {
+        let hbar = |symbols: Option<Symbols>| {
+            let mut builder = test_builder();
+            builder.symbols = symbols;
+            let mut out = Vec::new();
+            let reporter = builder.build(ReporterStderr::Buffer(&mut out));
+            reporter.inner.theme_characters.hbar(4)
         };
-        let output = ReporterStderr::Buffer(out);
-        let reporter = builder.build(output);
-        f(reporter);
+
+        assert_eq!(hbar(None), "────", "Unicode is used for buffers by default");
+        assert_eq!(hbar(Some(Symbols::Unicode)), "────");
+        assert_eq!(
+            hbar(Some(Symbols::Ascii)),
+            "----",
+            "ASCII overrides the default"
+        );
     }
 
     #[test]
@@ -1784,10 +2546,20 @@ mod tests {
             // output is not relevant here.
             output: make_split_output(Some(fail_result), "", ""),
             result: fail_result,
+            failure_class: None,
+            annotations: BTreeMap::new(),
+            exec_time: None,
+            startup_time: None,
             start_time: Local::now().into(),
             time_taken: Duration::from_secs(1),
             is_slow: false,
             delay_before_start: Duration::ZERO,
+            cgroup_limits: CgroupLimitsStatus::NotRequested,
+            kept_tmpdir: None,
+            kept_workdir: None,
+            access_report: None,
+            access_violations: Vec::new(),
+            crash_dump: None,
         };
         let fail_describe = ExecutionDescription::Failure {
             first_status: &fail_status,
@@ -1803,16 +2575,53 @@ mod tests {
             // output is not relevant here.
             output: make_split_output(Some(fail_result), "", ""),
             result: ExecutionResult::Pass,
+            failure_class: None,
+            annotations: BTreeMap::new(),
+            exec_time: None,
+            startup_time: None,
             start_time: Local::now().into(),
             time_taken: Duration::from_secs(2),
             is_slow: false,
             delay_before_start: Duration::ZERO,
+            cgroup_limits: CgroupLimitsStatus::NotRequested,
+            kept_tmpdir: None,
+            kept_workdir: None,
+            access_report: None,
+            access_violations: Vec::new(),
+            crash_dump: None,
         };
 
         // Make an `ExecutionStatuses` with a failure and a success, indicating flakiness.
         let statuses = ExecutionStatuses::new(vec![fail_status.clone(), flaky_status]);
         let flaky_describe = statuses.describe();
 
+        let panic_status = ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 1,
+            },
+            output: make_split_output(
+                Some(fail_result),
+                "",
+                "thread 'test1' panicked at src/lib.rs:1:5:\n\
+                 assertion `left == right` failed\n  \
+                 left: 1\n \
+                 right: 2\n\
+                 note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n",
+            ),
+            ..fail_status.clone()
+        };
+        let panic_describe = ExecutionDescription::Failure {
+            first_status: &panic_status,
+            last_status: &panic_status,
+            retries: &[],
+        };
+        assert_eq!(
+            failure_summary(&fail_status),
+            None,
+            "no extract without any output"
+        );
+
         let mut out = Vec::new();
 
         with_reporter(
@@ -1823,6 +2632,7 @@ mod tests {
                     .write_final_status_line(
                         test_instance,
                         fail_describe,
+                        None,
                         reporter.stderr.buf_mut().unwrap(),
                     )
                     .unwrap();
@@ -1832,6 +2642,17 @@ mod tests {
                     .write_final_status_line(
                         test_instance,
                         flaky_describe,
+                        None,
+                        reporter.stderr.buf_mut().unwrap(),
+                    )
+                    .unwrap();
+
+                reporter
+                    .inner
+                    .write_final_status_line(
+                        test_instance,
+                        panic_describe,
+                        failure_summary(&panic_status).as_deref(),
                         reporter.stderr.buf_mut().unwrap(),
                     )
                     .unwrap();
@@ -1882,6 +2703,9 @@ mod tests {
                                 leaky: 1,
                                 exec_failed: 1,
                                 skipped: 5,
+                                skipped_by_reason: Default::default(),
+                                cgroup_limits_applied: 0,
+                                cgroup_limits_unavailable: 0,
                             },
                         },
                     })
@@ -2301,6 +3125,66 @@ mod tests {
             &mut out,
         );
     }
+
+    #[test]
+    fn startup_overheads() {
+        let slow_id = RustBinaryId::new("my-crate::slow");
+        let slowest_id = RustBinaryId::new("my-crate::slowest");
+        let fast_id = RustBinaryId::new("my-crate::fast");
+
+        let mut out = Vec::new();
+        with_reporter(
+            |mut reporter| {
+                // Nothing is written if no startup times were measured.
+                reporter
+                    .inner
+                    .write_startup_overheads(reporter.stderr.buf_mut().unwrap())
+                    .unwrap();
+                assert_eq!(reporter.stderr.buf_mut().unwrap(), b"");
+
+                // Binaries below the threshold aren't listed.
+                reporter.inner.startup_overheads.insert(
+                    &fast_id,
+                    StartupOverhead {
+                        total: Duration::from_millis(900),
+                        count: 10,
+                    },
+                );
+                reporter
+                    .inner
+                    .write_startup_overheads(reporter.stderr.buf_mut().unwrap())
+                    .unwrap();
+                assert_eq!(reporter.stderr.buf_mut().unwrap(), b"");
+
+                // Slow binaries are listed by total overhead, slowest first.
+                reporter.inner.startup_overheads.insert(
+                    &slow_id,
+                    StartupOverhead {
+                        total: Duration::from_millis(500),
+                        count: 2,
+                    },
+                );
+                reporter.inner.startup_overheads.insert(
+                    &slowest_id,
+                    StartupOverhead {
+                        total: Duration::from_millis(600),
+                        count: 1,
+                    },
+                );
+                reporter
+                    .inner
+                    .write_startup_overheads(reporter.stderr.buf_mut().unwrap())
+                    .unwrap();
+            },
+            &mut out,
+        );
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "     Startup my-crate::slowest: 0.600s across 1 process (0.600s on average)\n             \
+             my-crate::slow: 0.500s across 2 processes (0.250s on average)\n",
+        );
+    }
 }
 
 #[cfg(all(windows, test))]
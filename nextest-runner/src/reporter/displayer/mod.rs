@@ -3,12 +3,18 @@
 
 //! The displayer for human-friendly output.
 
+mod backtrace;
+mod display_format;
 mod formatters;
+mod group_by;
 mod imp;
 mod progress;
 mod status_level;
 mod unit_output;
 
+pub(crate) use display_format::DisplayFormat;
+pub use display_format::DurationFormat;
+pub use group_by::GroupBy;
 pub(crate) use imp::*;
 pub use status_level::*;
 pub use unit_output::*;
@@ -1,8 +1,16 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::reporter::{displayer::formatters::DisplayBracketedHhMmSs, events::*, helpers::Styles};
-use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use crate::{
+    helpers::DisplayTestInstance,
+    list::TestInstanceId,
+    reporter::{
+        displayer::{display_format::DisplayFormat, formatters::DisplayBracketedHhMmSs},
+        events::*,
+        helpers::Styles,
+    },
+};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use owo_colors::OwoColorize;
 use std::{
     io::{self, Write},
@@ -25,10 +33,17 @@ pub(super) struct ProgressBarState {
     hidden_no_capture: bool,
     hidden_run_paused: bool,
     hidden_info_response: bool,
+    // Per-slot status lines, shown below the progress bar with --show-slots.
+    slots: Option<SlotBars>,
 }
 
 impl ProgressBarState {
-    pub(super) fn new(test_count: usize, progress_chars: &str) -> Self {
+    pub(super) fn new(
+        test_count: usize,
+        progress_chars: &str,
+        ellipsis: &'static str,
+        show_slots: bool,
+    ) -> Self {
         let bar = ProgressBar::new(test_count as u64);
 
         let test_count_width = format!("{}", test_count).len();
@@ -49,7 +64,16 @@ impl ProgressBarState {
 
         // NOTE: set_draw_target must be called before enable_steady_tick to avoid a
         // spurious extra line from being printed as the draw target changes.
-        bar.set_draw_target(Self::stderr_target());
+        let (bar, slots) = if show_slots {
+            // The slot lines are drawn below the progress bar, so the progress
+            // bar must be part of the same MultiProgress.
+            let multi = MultiProgress::with_draw_target(Self::stderr_target());
+            let bar = multi.add(bar);
+            (bar, Some(SlotBars::new(multi, ellipsis)))
+        } else {
+            bar.set_draw_target(Self::stderr_target());
+            (bar, None)
+        };
         // Enable a steady tick 10 times a second.
         bar.enable_steady_tick(Duration::from_millis(100));
 
@@ -58,10 +82,16 @@ impl ProgressBarState {
             hidden_no_capture: false,
             hidden_run_paused: false,
             hidden_info_response: false,
+            slots,
         }
     }
 
-    pub(super) fn update_progress_bar(&mut self, event: &TestEvent<'_>, styles: &Styles) {
+    pub(super) fn update_progress_bar(
+        &mut self,
+        event: &TestEvent<'_>,
+        format: DisplayFormat,
+        styles: &Styles,
+    ) {
         let before_should_hide = self.should_hide();
 
         match &event.kind {
@@ -92,7 +122,7 @@ impl ProgressBarState {
                 self.bar
                     .set_prefix(progress_bar_prefix(current_stats, *cancel_state, styles));
                 self.bar
-                    .set_message(progress_bar_msg(current_stats, *running, styles));
+                    .set_message(progress_bar_msg(current_stats, *running, format, styles));
                 // If there are skipped tests, the initial run count will be lower than when constructed
                 // in ProgressBar::new.
                 self.bar.set_length(current_stats.initial_run_count as u64);
@@ -128,11 +158,23 @@ impl ProgressBarState {
             _ => {}
         }
 
+        if let Some(slots) = &mut self.slots {
+            match &event.kind {
+                TestEventKind::TestStarted { test_instance, .. } => {
+                    slots.start(test_instance.id(), styles);
+                }
+                TestEventKind::TestFinished { test_instance, .. } => {
+                    slots.finish(test_instance.id(), styles);
+                }
+                _ => {}
+            }
+        }
+
         let after_should_hide = self.should_hide();
 
         match (before_should_hide, after_should_hide) {
-            (false, true) => self.bar.set_draw_target(Self::hidden_target()),
-            (true, false) => self.bar.set_draw_target(Self::stderr_target()),
+            (false, true) => self.set_draw_target(Self::hidden_target()),
+            (true, false) => self.set_draw_target(Self::stderr_target()),
             _ => {}
         }
     }
@@ -140,14 +182,27 @@ impl ProgressBarState {
     pub(super) fn write_buf(&self, buf: &[u8]) -> io::Result<()> {
         // ProgressBar::println doesn't print status lines if the bar is
         // hidden. The suspend method prints it in all cases.
-        self.bar.suspend(|| std::io::stderr().write_all(buf))
+        match &self.slots {
+            Some(slots) => slots.multi.suspend(|| std::io::stderr().write_all(buf)),
+            None => self.bar.suspend(|| std::io::stderr().write_all(buf)),
+        }
     }
 
     #[inline]
     pub(super) fn finish_and_clear(&self) {
+        if let Some(slots) = &self.slots {
+            slots.finish_and_clear();
+        }
         self.bar.finish_and_clear();
     }
 
+    fn set_draw_target(&self, target: ProgressDrawTarget) {
+        match &self.slots {
+            Some(slots) => slots.multi.set_draw_target(target),
+            None => self.bar.set_draw_target(target),
+        }
+    }
+
     fn stderr_target() -> ProgressDrawTarget {
         // This used to be unbuffered, but that option went away from indicatif
         // 0.17.0. The refresh rate is now 20hz so that it's double the steady
@@ -164,12 +219,111 @@ impl ProgressBarState {
     }
 }
 
+/// Status lines showing the test running in each slot.
+///
+/// A test is assigned the lowest-numbered free slot when it starts, and keeps
+/// it across retries until it finishes.
+#[derive(Debug)]
+struct SlotBars {
+    multi: MultiProgress,
+    slots: Vec<Slot>,
+    // Used to truncate test names that don't fit on one line.
+    ellipsis: &'static str,
+}
+
+#[derive(Debug)]
+struct Slot {
+    bar: ProgressBar,
+    // The ID of the running test, formatted as a string.
+    running: Option<String>,
+}
+
+impl SlotBars {
+    fn new(multi: MultiProgress, ellipsis: &'static str) -> Self {
+        Self {
+            multi,
+            slots: Vec::new(),
+            ellipsis,
+        }
+    }
+
+    fn start(&mut self, id: TestInstanceId<'_>, styles: &Styles) -> usize {
+        let index = match self.slots.iter().position(|slot| slot.running.is_none()) {
+            Some(index) => index,
+            None => {
+                // Slots are added as concurrency grows, so the number of lines
+                // is the peak number of tests running at once.
+                let index = self.slots.len();
+                let bar = self.multi.add(ProgressBar::new_spinner());
+                bar.set_prefix(format!(
+                    "{:>12}",
+                    format!("slot {index}").style(styles.count)
+                ));
+                bar.enable_steady_tick(Duration::from_millis(100));
+                self.slots.push(Slot { bar, running: None });
+                index
+            }
+        };
+
+        // A line that's wider than the terminal wraps, which corrupts the
+        // progress bar. Truncate the test instance to fit instead.
+        let mut display = DisplayTestInstance::new(id, &styles.list_styles);
+        if let Ok((columns, _)) = crossterm::terminal::size() {
+            display = display.with_max_width(
+                usize::from(columns).saturating_sub(SLOT_PREFIX_WIDTH),
+                self.ellipsis,
+            );
+        }
+
+        let slot = &mut self.slots[index];
+        slot.bar.set_style(slot_running_style());
+        slot.bar.set_message(display.to_string());
+        slot.bar.reset_elapsed();
+        slot.running = Some(id.to_string());
+        index
+    }
+
+    fn finish(&mut self, id: TestInstanceId<'_>, styles: &Styles) -> Option<usize> {
+        let key = id.to_string();
+        let index = self
+            .slots
+            .iter()
+            .position(|slot| slot.running.as_ref() == Some(&key))?;
+
+        let slot = &mut self.slots[index];
+        slot.bar.set_style(slot_idle_style());
+        slot.bar.set_message("idle".style(styles.skip).to_string());
+        slot.running = None;
+        Some(index)
+    }
+
+    fn finish_and_clear(&self) {
+        for slot in &self.slots {
+            slot.bar.finish_and_clear();
+        }
+    }
+}
+
+/// The width of the part of a slot line before the test instance, as laid out
+/// by [`slot_running_style`].
+const SLOT_PREFIX_WIDTH: usize = 25;
+
+fn slot_running_style() -> ProgressStyle {
+    ProgressStyle::with_template("{prefix:>12} [{elapsed_precise:>9}] {msg}")
+        .expect("template is known to be valid")
+}
+
+fn slot_idle_style() -> ProgressStyle {
+    ProgressStyle::with_template("{prefix:>12} [{msg:>9}]").expect("template is known to be valid")
+}
+
 /// Returns a summary of current progress.
 pub(super) fn progress_str(
     elapsed: Duration,
     current_stats: &RunStats,
     running: usize,
     cancel_reason: Option<CancelReason>,
+    format: DisplayFormat,
     styles: &Styles,
 ) -> String {
     // First, show the prefix.
@@ -180,19 +334,24 @@ pub(super) fn progress_str(
         s,
         " {}{}/{}: {}",
         DisplayBracketedHhMmSs(elapsed),
-        current_stats.finished_count,
-        current_stats.initial_run_count,
-        progress_bar_msg(current_stats, running, styles)
+        format.count(current_stats.finished_count),
+        format.count(current_stats.initial_run_count),
+        progress_bar_msg(current_stats, running, format, styles)
     );
 
     s
 }
 
-pub(super) fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut String) {
+pub(super) fn write_summary_str(
+    run_stats: &RunStats,
+    format: DisplayFormat,
+    styles: &Styles,
+    out: &mut String,
+) {
     swrite!(
         out,
         "{} {}",
-        run_stats.passed.style(styles.count),
+        format.count(run_stats.passed).style(styles.count),
         "passed".style(styles.pass)
     );
 
@@ -201,21 +360,21 @@ pub(super) fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut
         if run_stats.passed_slow > 0 {
             text.push(format!(
                 "{} {}",
-                run_stats.passed_slow.style(styles.count),
+                format.count(run_stats.passed_slow).style(styles.count),
                 "slow".style(styles.skip),
             ));
         }
         if run_stats.flaky > 0 {
             text.push(format!(
                 "{} {}",
-                run_stats.flaky.style(styles.count),
+                format.count(run_stats.flaky).style(styles.count),
                 "flaky".style(styles.skip),
             ));
         }
         if run_stats.leaky > 0 {
             text.push(format!(
                 "{} {}",
-                run_stats.leaky.style(styles.count),
+                format.count(run_stats.leaky).style(styles.count),
                 "leaky".style(styles.skip),
             ));
         }
@@ -227,7 +386,7 @@ pub(super) fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut
         swrite!(
             out,
             "{} {}, ",
-            run_stats.failed.style(styles.count),
+            format.count(run_stats.failed).style(styles.count),
             "failed".style(styles.fail),
         );
     }
@@ -236,7 +395,7 @@ pub(super) fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut
         swrite!(
             out,
             "{} {}, ",
-            run_stats.exec_failed.style(styles.count),
+            format.count(run_stats.exec_failed).style(styles.count),
             "exec failed".style(styles.fail),
         );
     }
@@ -245,7 +404,7 @@ pub(super) fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut
         swrite!(
             out,
             "{} {}, ",
-            run_stats.timed_out.style(styles.count),
+            format.count(run_stats.timed_out).style(styles.count),
             "timed out".style(styles.fail),
         );
     }
@@ -253,16 +412,42 @@ pub(super) fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut
     swrite!(
         out,
         "{} {}",
-        run_stats.skipped.style(styles.count),
+        format.count(run_stats.skipped).style(styles.count),
         "skipped".style(styles.skip),
     );
 }
 
+/// Writes out why tests were skipped, for example ` (3 ignored, 2 default-filter)`.
+///
+/// Nothing is written if no tests were skipped.
+pub(super) fn write_skip_reasons_str(
+    skip_counts: &SkipCounts,
+    format: DisplayFormat,
+    styles: &Styles,
+    out: &mut String,
+) {
+    let text: Vec<_> = skip_counts
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(reason, count)| {
+            format!(
+                "{} {}",
+                format.count(count).style(styles.count),
+                reason.style(styles.skip)
+            )
+        })
+        .collect();
+    if !text.is_empty() {
+        swrite!(out, " ({})", text.join(", "));
+    }
+}
+
 fn progress_bar_cancel_prefix(reason: CancelReason, styles: &Styles) -> String {
     let status = match reason {
         CancelReason::SetupScriptFailure
         | CancelReason::TestFailure
         | CancelReason::ReportError
+        | CancelReason::ControlRequest
         | CancelReason::Signal
         | CancelReason::Interrupt => "Cancelling",
         CancelReason::SecondSignal => "Killing",
@@ -291,21 +476,24 @@ fn progress_bar_prefix(
 pub(super) fn progress_bar_msg(
     current_stats: &RunStats,
     running: usize,
+    format: DisplayFormat,
     styles: &Styles,
 ) -> String {
-    let mut s = format!("{} running, ", running.style(styles.count));
-    write_summary_str(current_stats, styles, &mut s);
+    let mut s = format!("{} running, ", format.count(running).style(styles.count));
+    write_summary_str(current_stats, format, styles, &mut s);
     s
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::theme::Theme;
+    use nextest_metadata::{MismatchReason, RustBinaryId};
 
     #[test]
     fn test_progress_bar_prefix() {
         let mut styles = Styles::default();
-        styles.colorize();
+        styles.colorize(&Theme::default());
 
         for (name, stats) in run_stats_test_failure_examples() {
             let prefix = progress_bar_prefix(&stats, Some(CancelReason::TestFailure), &styles);
@@ -349,10 +537,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_skip_reasons_str() {
+        let styles = Styles::default();
+
+        let mut s = String::new();
+        write_skip_reasons_str(
+            &SkipCounts::default(),
+            DisplayFormat::default(),
+            &styles,
+            &mut s,
+        );
+        assert_eq!(s, "", "nothing written if no tests were skipped");
+
+        let mut skip_counts = SkipCounts::default();
+        for reason in [
+            MismatchReason::DefaultFilter,
+            MismatchReason::Ignored,
+            MismatchReason::Ignored,
+            MismatchReason::DefaultFilter,
+            MismatchReason::Ignored,
+        ] {
+            skip_counts.record(reason);
+        }
+        let mut s = String::new();
+        write_skip_reasons_str(&skip_counts, DisplayFormat::default(), &styles, &mut s);
+        assert_eq!(s, " (3 ignored, 2 default-filter)");
+    }
+
+    #[test]
+    fn test_slot_assignment() {
+        let styles = Styles::default();
+        let mut slots = SlotBars::new(
+            MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+            "…",
+        );
+
+        let binary_id = RustBinaryId::new("my-binary");
+        let id = |test_name: &'static str| TestInstanceId {
+            binary_id: &binary_id,
+            test_name,
+        };
+
+        assert_eq!(slots.start(id("a"), &styles), 0);
+        assert_eq!(slots.start(id("b"), &styles), 1);
+        assert_eq!(slots.start(id("c"), &styles), 2);
+        assert_eq!(slots.finish(id("b"), &styles), Some(1));
+        assert_eq!(slots.finish(id("b"), &styles), None, "b already finished");
+        assert_eq!(slots.start(id("d"), &styles), 1, "lowest free slot reused");
+        assert_eq!(slots.finish(id("a"), &styles), Some(0));
+        assert_eq!(slots.finish(id("c"), &styles), Some(2));
+        assert_eq!(slots.start(id("e"), &styles), 0);
+        assert_eq!(slots.slots.len(), 3, "peak concurrency was 3");
+    }
+
     #[test]
     fn progress_str_snapshots() {
         let mut styles = Styles::default();
-        styles.colorize();
+        styles.colorize(&Theme::default());
 
         // This elapsed time is arbitrary but reasonably large.
         let elapsed = Duration::from_secs(123456);
@@ -364,11 +606,19 @@ mod tests {
                 &stats,
                 running,
                 Some(CancelReason::TestFailure),
+                DisplayFormat::default(),
                 &styles,
             );
             insta::assert_snapshot!(format!("{name}_with_cancel_reason"), s);
 
-            let s = progress_str(elapsed, &stats, running, None, &styles);
+            let s = progress_str(
+                elapsed,
+                &stats,
+                running,
+                None,
+                DisplayFormat::default(),
+                &styles,
+            );
             insta::assert_snapshot!(format!("{name}_without_cancel_reason"), s);
         }
 
@@ -378,11 +628,19 @@ mod tests {
                 &stats,
                 running,
                 Some(CancelReason::SetupScriptFailure),
+                DisplayFormat::default(),
                 &styles,
             );
             insta::assert_snapshot!(format!("{name}_with_cancel_reason"), s);
 
-            let s = progress_str(elapsed, &stats, running, None, &styles);
+            let s = progress_str(
+                elapsed,
+                &stats,
+                running,
+                None,
+                DisplayFormat::default(),
+                &styles,
+            );
             insta::assert_snapshot!(format!("{name}_without_cancel_reason"), s);
         }
     }
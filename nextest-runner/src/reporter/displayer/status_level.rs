@@ -21,6 +21,13 @@ pub enum StatusLevel {
     /// No output.
     None,
 
+    /// Only output test failures and the summary at the end of the run.
+    ///
+    /// Unlike [`Fail`](Self::Fail), this also hides the lines printed at the
+    /// start of the run, setup scripts that pass, and the progress bar. This
+    /// is useful for keeping CI logs small.
+    FailOnly,
+
     /// Only output test failures.
     Fail,
 
@@ -172,6 +179,19 @@ mod tests {
     use super::*;
     use test_strategy::proptest;
 
+    #[test]
+    fn fail_only_ordering() {
+        // fail-only is between none and fail, so that it isn't implied by any
+        // other status level.
+        assert!(StatusLevel::None < StatusLevel::FailOnly);
+        assert!(StatusLevel::FailOnly < StatusLevel::Fail);
+        assert_eq!(
+            StatusLevel::FailOnly.max(StatusLevel::Pass),
+            StatusLevel::Pass,
+            "no-capture treats fail-only as at least pass"
+        );
+    }
+
     // ---
     // The proptests here are probabilistically exhaustive, and it's just easier to express them
     // as property-based tests. We could also potentially use a model checker like Kani here.
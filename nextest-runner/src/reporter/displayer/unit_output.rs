@@ -3,6 +3,7 @@
 
 //! Code to write out test and script outputs to the displayer.
 
+use super::backtrace::BacktraceFormatter;
 use crate::{
     errors::DisplayErrorChain,
     reporter::{
@@ -13,6 +14,7 @@ use crate::{
     test_output::{ChildExecutionOutput, ChildOutput, ChildSingleOutput},
 };
 use bstr::ByteSlice;
+use camino::Utf8PathBuf;
 use indent_write::io::IndentWriter;
 use owo_colors::Style;
 use serde::Deserialize;
@@ -77,12 +79,14 @@ pub(super) struct UnitOutputReporter {
     force_success_output: Option<TestOutputDisplay>,
     force_failure_output: Option<TestOutputDisplay>,
     display_empty_outputs: bool,
+    workspace_root: Utf8PathBuf,
 }
 
 impl UnitOutputReporter {
     pub(super) fn new(
         force_success_output: Option<TestOutputDisplay>,
         force_failure_output: Option<TestOutputDisplay>,
+        workspace_root: Utf8PathBuf,
     ) -> Self {
         // Ordinarily, empty stdout and stderr are not displayed. This
         // environment variable is set in integration tests to ensure that they
@@ -94,6 +98,7 @@ impl UnitOutputReporter {
             force_success_output,
             force_failure_output,
             display_empty_outputs,
+            workspace_root,
         }
     }
 
@@ -236,7 +241,8 @@ impl UnitOutputReporter {
     /// Writes a test output to the writer, along with optionally a subslice of the output to
     /// highlight.
     ///
-    /// The description must be a subslice of the output.
+    /// The description must be a subslice of the output. Any backtraces after it are styled as
+    /// well.
     fn write_test_single_output_with_description(
         &self,
         styles: &Styles,
@@ -244,18 +250,26 @@ impl UnitOutputReporter {
         description: Option<ByteSubslice<'_>>,
         writer: &mut dyn Write,
     ) -> io::Result<()> {
+        let backtraces = BacktraceFormatter::new(&self.workspace_root, styles);
         if styles.is_colorized {
             if let Some(subslice) = description {
-                write_output_with_highlight(&output.buf, subslice, &styles.fail, writer)?;
+                write_output_with_highlight(
+                    &output.buf,
+                    subslice,
+                    &styles.fail,
+                    &backtraces,
+                    writer,
+                )?;
             } else {
                 // Output the text without stripping ANSI escapes, then reset the color afterwards
                 // in case the output is malformed.
-                write_output_with_trailing_newline(&output.buf, RESET_COLOR, writer)?;
+                write_output_with_trailing_newline(&output.buf, RESET_COLOR, &backtraces, writer)?;
             }
         } else {
-            // Strip ANSI escapes from the output if nextest itself isn't colorized.
+            // Strip ANSI escapes from the output if nextest itself isn't colorized. Backtraces
+            // are still demangled.
             let mut no_color = strip_ansi_escapes::Writer::new(writer);
-            write_output_with_trailing_newline(&output.buf, b"", &mut no_color)?;
+            write_output_with_trailing_newline(&output.buf, b"", &backtraces, &mut no_color)?;
         }
 
         Ok(())
@@ -268,6 +282,7 @@ fn write_output_with_highlight(
     output: &[u8],
     ByteSubslice { slice, start }: ByteSubslice,
     highlight_style: &Style,
+    backtraces: &BacktraceFormatter<'_>,
     mut writer: &mut dyn Write,
 ) -> io::Result<()> {
     let end = start + highlight_end(slice);
@@ -299,7 +314,7 @@ fn write_output_with_highlight(
     // `end` is guaranteed to be within the bounds of `output.buf`. (It is actually safe
     // for it to be equal to `output.buf.len()` -- it gets treated as an empty list in
     // that case.)
-    write_output_with_trailing_newline(&output[end..], RESET_COLOR, writer)?;
+    write_output_with_trailing_newline(&output[end..], RESET_COLOR, backtraces, writer)?;
 
     Ok(())
 }
@@ -311,6 +326,7 @@ fn write_output_with_highlight(
 fn write_output_with_trailing_newline(
     mut output: &[u8],
     trailer: &[u8],
+    backtraces: &BacktraceFormatter<'_>,
     writer: &mut dyn Write,
 ) -> io::Result<()> {
     // If there's a trailing newline in the output, insert the trailer right
@@ -319,7 +335,7 @@ fn write_output_with_trailing_newline(
         output = &output[..output.len() - 1];
     }
 
-    writer.write_all(output)?;
+    backtraces.write(output, writer)?;
     writer.write_all(trailer)?;
     writer.write_all(b"\n")
 }
@@ -343,6 +359,7 @@ impl fmt::Display for FmtSuffix<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use camino::Utf8Path;
 
     #[test]
     fn test_write_output_with_highlight() {
@@ -397,6 +414,7 @@ mod tests {
             output.as_bytes(),
             subslice,
             &Style::new().red().bold(),
+            &BacktraceFormatter::new(Utf8Path::new("/ws"), &Styles::default()),
             &mut buf,
         )
         .unwrap();
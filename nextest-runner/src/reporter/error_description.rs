@@ -114,6 +114,20 @@ impl<'a> UnitErrorDescription<'a> {
         self.output_slice
     }
 
+    /// Returns a one-line summary of the most relevant reason for the failure,
+    /// suitable for showing next to the name of the unit.
+    ///
+    /// The summary is stripped of ANSI escape codes and truncated if it's too
+    /// long.
+    pub fn summary_line(&self) -> Option<String> {
+        let summary = self
+            .output_slice
+            .and_then(|slice| slice.summary_line())
+            .or_else(|| self.abort.map(|abort| abort.to_string()))
+            .or_else(|| self.start_error.map(|error| error.to_string()))?;
+        Some(truncate_summary(&summary))
+    }
+
     /// Builds an iterator over all of the reasons for the error.
     fn all_errors(&self) -> impl Iterator<Item = &dyn std::error::Error> {
         self.exec_fail_errors().chain(self.child_process_errors())
@@ -183,13 +197,24 @@ pub enum TestOutputErrorSlice<'a> {
         stderr_subslice: ByteSubslice<'a>,
     },
 
-    /// A should-panic test did not panic.
+    /// A should-panic test did not panic, or panicked with a message that didn't
+    /// contain the expected string.
     ///
     /// The output is borrowed from standard output.
     ShouldPanic {
         /// The subslice of standard output that contains the should-panic message.
         stdout_subslice: ByteSubslice<'a>,
     },
+
+    /// A failure message was found in a `---- <name> ----` section, as printed
+    /// out by libtest without `--nocapture` and by custom harnesses that mimic
+    /// it.
+    ///
+    /// The output is borrowed from standard output.
+    HarnessFailure {
+        /// The subslice of standard output that contains the failure message.
+        stdout_subslice: ByteSubslice<'a>,
+    },
 }
 
 impl<'a> TestOutputErrorSlice<'a> {
@@ -199,7 +224,15 @@ impl<'a> TestOutputErrorSlice<'a> {
     /// `None` indicates that output wasn't captured -- in those cases there isn't
     /// much we can do.
     pub fn heuristic_extract(stdout: Option<&'a [u8]>, stderr: Option<&'a [u8]>) -> Option<Self> {
-        // Try the heuristic stack trace extraction first to try and grab more
+        // A should-panic test whose panic message didn't match still panics, so
+        // look for should-panic failures before the panic message.
+        if let Some(stdout) = stdout {
+            if let Some(stdout_subslice) = heuristic_should_panic(stdout) {
+                return Some(TestOutputErrorSlice::ShouldPanic { stdout_subslice });
+            }
+        }
+
+        // Try the heuristic stack trace extraction next to try and grab more
         // information first.
         if let Some(stderr) = stderr {
             if let Some(stderr_subslice) = heuristic_panic_message(stderr) {
@@ -211,14 +244,37 @@ impl<'a> TestOutputErrorSlice<'a> {
         }
 
         if let Some(stdout) = stdout {
-            if let Some(stdout_subslice) = heuristic_should_panic(stdout) {
-                return Some(TestOutputErrorSlice::ShouldPanic { stdout_subslice });
+            if let Some(stdout_subslice) = heuristic_harness_failure(stdout) {
+                return Some(TestOutputErrorSlice::HarnessFailure { stdout_subslice });
             }
         }
 
         None
     }
 
+    /// Returns a one-line summary of the failure, such as the panic message.
+    ///
+    /// Multi-line messages are condensed to their first line, along with the
+    /// values being compared for assertion failures.
+    pub fn summary_line(&self) -> Option<String> {
+        let slice = self.combined_subslice()?.slice;
+        let slice = strip_ansi_escapes::strip(slice);
+        let text = String::from_utf8_lossy(&slice);
+        match self {
+            Self::PanicMessage { .. } => summarize_panic_message(&text),
+            Self::ErrorStr { .. } => first_line(&text).map(ToOwned::to_owned),
+            Self::ShouldPanic { .. } => {
+                let mut lines = nonempty_lines(&text);
+                let first = lines.next()?;
+                let first = first.strip_prefix("note: ").unwrap_or(first);
+                // A message mismatch is followed by the panic message and the
+                // expected substring.
+                Some(with_operands(first, lines.collect()))
+            }
+            Self::HarnessFailure { .. } => summarize_message(&text),
+        }
+    }
+
     /// Returns the subslice of standard error that contains the description.
     pub fn stderr_subslice(&self) -> Option<ByteSubslice<'a>> {
         match self {
@@ -226,7 +282,7 @@ impl<'a> TestOutputErrorSlice<'a> {
             | Self::ErrorStr {
                 stderr_subslice, ..
             } => Some(*stderr_subslice),
-            Self::ShouldPanic { .. } => None,
+            Self::ShouldPanic { .. } | Self::HarnessFailure { .. } => None,
         }
     }
 
@@ -237,7 +293,8 @@ impl<'a> TestOutputErrorSlice<'a> {
             Self::ErrorStr { .. } => None,
             Self::ShouldPanic {
                 stdout_subslice, ..
-            } => Some(*stdout_subslice),
+            }
+            | Self::HarnessFailure { stdout_subslice } => Some(*stdout_subslice),
         }
     }
 
@@ -250,7 +307,8 @@ impl<'a> TestOutputErrorSlice<'a> {
             } => Some(*stderr_subslice),
             Self::ShouldPanic {
                 stdout_subslice, ..
-            } => Some(*stdout_subslice),
+            }
+            | Self::HarnessFailure { stdout_subslice } => Some(*stdout_subslice),
         }
     }
 }
@@ -264,7 +322,7 @@ impl fmt::Display for TestOutputErrorSlice<'_> {
             Self::ErrorStr { stderr_subslice } => {
                 write!(f, "{}", String::from_utf8_lossy(stderr_subslice.slice))
             }
-            Self::ShouldPanic { stdout_subslice } => {
+            Self::ShouldPanic { stdout_subslice } | Self::HarnessFailure { stdout_subslice } => {
                 write!(f, "{}", String::from_utf8_lossy(stdout_subslice.slice))
             }
         }
@@ -283,23 +341,88 @@ pub struct ByteSubslice<'a> {
     pub start: usize,
 }
 
+/// Returns true if the output of a test indicates that it was marked
+/// `#[should_panic]`.
+///
+/// libtest does not expose `should_panic` in its list output, so this can only
+/// be determined after the test has been run: libtest prints out
+/// `test <name> - should panic ...` in that case.
+pub(crate) fn heuristic_is_should_panic(output: &ChildExecutionOutput) -> bool {
+    let stdout = match output {
+        ChildExecutionOutput::Output {
+            output: ChildOutput::Split(output),
+            ..
+        } => output.stdout.as_ref(),
+        ChildExecutionOutput::Output {
+            output: ChildOutput::Combined { output },
+            ..
+        } => Some(output),
+        ChildExecutionOutput::StartError(_) => None,
+    };
+
+    stdout.is_some_and(|stdout| SHOULD_PANIC_REGEX.is_match(&stdout.buf))
+}
+
 fn heuristic_should_panic(stdout: &[u8]) -> Option<ByteSubslice<'_>> {
-    let line = stdout
-        .lines()
-        .find(|line| line.contains_str("note: test did not panic as expected"))?;
+    let mut lines = stdout.lines();
+    let line = lines.find(|line| {
+        line.contains_str("note: test did not panic as expected")
+            || line.contains_str("note: panic did not contain expected string")
+            || line.contains_str("note: expected panic with string value")
+    })?;
+    let start = subslice_start(stdout, line);
+
+    // Message mismatches are followed by indented lines with the panic message
+    // and the expected substring.
+    let mut end = start + line.len();
+    for next in lines {
+        if !next.starts_with(b" ") {
+            break;
+        }
+        end = subslice_start(stdout, next) + next.len();
+    }
+
+    Some(ByteSubslice {
+        slice: &stdout[start..end],
+        start,
+    })
+}
+
+fn heuristic_harness_failure(stdout: &[u8]) -> Option<ByteSubslice<'_>> {
+    HARNESS_FAILURE_REGEX.find_iter(stdout).find_map(|header| {
+        // The failure message continues until the next blank line.
+        let rest = &stdout[header.end()..];
+        let rest = rest.strip_prefix(b"\r").unwrap_or(rest);
+        let rest = rest.strip_prefix(b"\n")?;
+        let len = rest
+            .lines_with_terminator()
+            .take_while(|line| !line.trim_ascii().is_empty())
+            .map(|line| line.len())
+            .sum();
+        let slice = rest[..len].trim_end_with(|c| c.is_whitespace());
+        if slice.trim_ascii().is_empty() {
+            return None;
+        }
+        Some(ByteSubslice {
+            slice,
+            start: subslice_start(stdout, slice),
+        })
+    })
+}
 
-    // SAFETY: line is a subslice of stdout.
-    let start = unsafe { line.as_ptr().offset_from(stdout.as_ptr()) };
+/// Returns the start index of `subslice` within `parent`.
+fn subslice_start(parent: &[u8], subslice: &[u8]) -> usize {
+    // SAFETY: subslice is a subslice of parent.
+    let start = unsafe { subslice.as_ptr().offset_from(parent.as_ptr()) };
 
-    let start = usize::try_from(start).unwrap_or_else(|error| {
+    usize::try_from(start).unwrap_or_else(|error| {
         panic!(
-            "negative offset from stdout.as_ptr() ({:x}) to line.as_ptr() ({:x}): {}",
-            stdout.as_ptr() as usize,
-            line.as_ptr() as usize,
+            "negative offset from parent.as_ptr() ({:x}) to subslice.as_ptr() ({:x}): {}",
+            parent.as_ptr() as usize,
+            subslice.as_ptr() as usize,
             error
         )
-    });
-    Some(ByteSubslice { slice: line, start })
+    })
 }
 
 fn heuristic_panic_message(stderr: &[u8]) -> Option<ByteSubslice<'_>> {
@@ -339,6 +462,74 @@ fn heuristic_error_str(stderr: &[u8]) -> Option<ByteSubslice<'_>> {
     })
 }
 
+/// The maximum number of characters in a summary line.
+const MAX_SUMMARY_LEN: usize = 120;
+
+fn summarize_panic_message(text: &str) -> Option<String> {
+    // With result-based test failures, the error is more relevant than the
+    // panic that follows it.
+    if text.starts_with("Error:") {
+        return first_line(text).map(ToOwned::to_owned);
+    }
+
+    let (_, after) = text.split_once(" panicked at ")?;
+    if let Some(message) = after.strip_prefix('\'') {
+        // Before Rust 1.73, the message was quoted, and followed by the
+        // location: `thread 'main' panicked at 'message', src/lib.rs:1:5`.
+        let message = message.find("', ").map_or(message, |end| &message[..end]);
+        return summarize_message(message);
+    }
+
+    // Starting Rust 1.73, the message is on the lines after the location:
+    // `thread 'main' panicked at src/lib.rs:1:5:\nmessage`.
+    let (_, message) = after.split_once('\n')?;
+    let end = message
+        .split_inclusive('\n')
+        .take_while(|line| !line.starts_with("note: ") && !line.starts_with("stack backtrace:"))
+        .map(str::len)
+        .sum();
+    summarize_message(&message[..end])
+}
+
+fn summarize_message(message: &str) -> Option<String> {
+    let mut lines = nonempty_lines(message);
+    let first = lines.next()?;
+    // Assertion failures print out the values being compared on the following
+    // lines.
+    let operands = lines
+        .filter(|line| line.starts_with("left:") || line.starts_with("right:"))
+        .collect();
+    Some(with_operands(first, operands))
+}
+
+fn with_operands(first: &str, operands: Vec<&str>) -> String {
+    if operands.is_empty() {
+        return first.to_owned();
+    }
+    let operands: Vec<_> = operands
+        .into_iter()
+        .map(|operand| operand.trim_end_matches(','))
+        .collect();
+    format!("{first} ({})", operands.join(", "))
+}
+
+fn first_line(text: &str) -> Option<&str> {
+    nonempty_lines(text).next()
+}
+
+fn nonempty_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+fn truncate_summary(summary: &str) -> String {
+    if summary.chars().count() <= MAX_SUMMARY_LEN {
+        return summary.to_owned();
+    }
+    let mut truncated: String = summary.chars().take(MAX_SUMMARY_LEN - 3).collect();
+    truncated.push_str("...");
+    truncated
+}
+
 // This regex works for the default panic handler for Rust -- other panic handlers may not work,
 // which is why this is heuristic.
 static PANICKED_AT_REGEX_STR: &str = "^thread '([^']+)' panicked at ";
@@ -355,14 +546,32 @@ static ERROR_REGEX: Lazy<Regex> = Lazy::new(|| {
     builder.build().unwrap()
 });
 
+// libtest prints out captured output in a `---- <name> stdout ----` section,
+// and custom harnesses such as libtest-mimic print out failure messages in a
+// `---- <name> ----` section.
+static HARNESS_FAILURE_REGEX_STR: &str = r"^---- .+ ----\r?$";
+static HARNESS_FAILURE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    let mut builder = RegexBuilder::new(HARNESS_FAILURE_REGEX_STR);
+    builder.multi_line(true);
+    builder.build().unwrap()
+});
+
+static SHOULD_PANIC_REGEX_STR: &str = r"^test .+ - should panic \.\.\.";
+static SHOULD_PANIC_REGEX: Lazy<Regex> = Lazy::new(|| {
+    let mut builder = RegexBuilder::new(SHOULD_PANIC_REGEX_STR);
+    builder.multi_line(true);
+    builder.build().unwrap()
+});
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_heuristic_should_panic() {
-        let tests: &[(&str, &str)] = &[(
-            "running 1 test
+        let tests: &[(&str, &str)] = &[
+            (
+                "running 1 test
 test test_failure_should_panic - should panic ... FAILED
 
 failures:
@@ -374,8 +583,26 @@ failures:
     test_failure_should_panic
 
 test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 13 filtered out; finished in 0.00s",
-            "note: test did not panic as expected",
-        )];
+                "note: test did not panic as expected",
+            ),
+            (
+                "running 1 test
+test test_panic_mismatch - should panic ... FAILED
+
+failures:
+
+---- test_panic_mismatch stdout ----
+note: panic did not contain expected string
+      panic message: `\"bar\"`,
+ expected substring: `\"foo\"`
+
+failures:
+    test_panic_mismatch",
+                "note: panic did not contain expected string
+      panic message: `\"bar\"`,
+ expected substring: `\"foo\"`",
+            ),
+        ];
 
         for (input, output) in tests {
             let extracted = heuristic_should_panic(input.as_bytes())
@@ -391,6 +618,49 @@ test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 13 filtered out;
         }
     }
 
+    #[test]
+    fn test_heuristic_is_should_panic() {
+        let tests: &[(&str, bool)] = &[
+            (
+                "running 1 test
+test panics - should panic ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 1 filtered out; finished in 0.01s",
+                true,
+            ),
+            (
+                // With --nocapture, the panic message is printed out between
+                // the test name and the result.
+                "running 1 test
+test tests::panics - should panic ...
+thread 'tests::panics' panicked at src/lib.rs:3:15:
+boom
+ok",
+                true,
+            ),
+            (
+                "running 1 test
+test normal ... ok",
+                false,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let output = ChildExecutionOutput::Output {
+                result: Some(ExecutionResult::Pass),
+                output: ChildOutput::Combined {
+                    output: bytes::Bytes::copy_from_slice(input.as_bytes()).into(),
+                },
+                errors: None,
+            };
+            assert_eq!(
+                heuristic_is_should_panic(&output),
+                *expected,
+                "should-panic detection matches for input:\n{input}"
+            );
+        }
+    }
+
     #[test]
     fn test_heuristic_panic_message() {
         let tests: &[(&str, &str)] = &[
@@ -553,6 +823,112 @@ some more text at the end, followed by some newlines",
         }
     }
 
+    #[test]
+    fn test_heuristic_harness_failure() {
+        let tests: &[(&str, Option<&str>)] = &[
+            (
+                "running 1 test
+test check_input ... FAILED
+
+failures:
+
+---- check_input ----
+expected 3 items, found 2
+in input.txt
+
+failures:
+    check_input
+",
+                Some("expected 3 items, found 2\nin input.txt"),
+            ),
+            ("---- empty stdout ----\n\nfailures:\n", None),
+        ];
+
+        for (input, output) in tests {
+            let extracted = heuristic_harness_failure(input.as_bytes());
+            assert_eq!(
+                extracted.map(|extracted| DisplayWrapper(extracted.slice)),
+                output.map(|output| DisplayWrapper(output.as_bytes())),
+            );
+            if let Some(extracted) = extracted {
+                assert_eq!(
+                    extracted.start,
+                    extracted.slice.as_ptr() as usize - input.as_bytes().as_ptr() as usize
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_summary_line() {
+        let tests: &[(&str, &str, &str)] = &[
+            (
+                "",
+                "thread 'foo' panicked at src/lib.rs:2:5:\noops\nnote: run with `RUST_BACKTRACE=1`\n",
+                "oops",
+            ),
+            (
+                "",
+                "thread 'foo' panicked at src/lib.rs:2:5:
+assertion `left == right` failed: values differ
+  left: 1
+ right: 2
+stack backtrace:
+   0: rust_begin_unwind
+",
+                "assertion `left == right` failed: values differ (left: 1, right: 2)",
+            ),
+            (
+                "",
+                "thread 'main' panicked at 'assertion failed: `(left == right)`
+  left: `1`,
+ right: `0`', src/lib.rs:1:5",
+                "assertion failed: `(left == right)` (left: `1`, right: `0`)",
+            ),
+            (
+                "",
+                "text\nError: Custom { kind: InvalidData }
+thread 'foo' panicked at 'the test returned a termination value', src/lib.rs:1:5",
+                "Error: Custom { kind: InvalidData }",
+            ),
+            ("", "foobar\nError: \"this is an error\"\n", "Error: \"this is an error\""),
+            (
+                "---- foo stdout ----\nnote: panic did not contain expected string
+      panic message: `\"bar\"`,
+ expected substring: `\"foo\"`\n",
+                "thread 'foo' panicked at src/lib.rs:2:5:\nbar\n",
+                "panic did not contain expected string (panic message: `\"bar\"`, expected substring: `\"foo\"`)",
+            ),
+            (
+                "---- foo ----\n\u{1b}[31mmismatch\u{1b}[0m\nmore details\n\nfailures:\n",
+                "",
+                "mismatch",
+            ),
+        ];
+
+        for (stdout, stderr, expected) in tests {
+            let slice = TestOutputErrorSlice::heuristic_extract(
+                Some(stdout.as_bytes()),
+                Some(stderr.as_bytes()),
+            )
+            .expect("output slice should have been found");
+            assert_eq!(
+                slice.summary_line().as_deref(),
+                Some(*expected),
+                "summary matches for stdout {stdout:?} and stderr {stderr:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncate_summary() {
+        assert_eq!(truncate_summary("short"), "short");
+        let long = "x".repeat(MAX_SUMMARY_LEN + 1);
+        let truncated = truncate_summary(&long);
+        assert_eq!(truncated.chars().count(), MAX_SUMMARY_LEN);
+        assert!(truncated.ends_with("..."));
+    }
+
     // Wrapper so that panic messages show up nicely in the test output.
     #[derive(Eq, PartialEq)]
     struct DisplayWrapper<'a>(&'a [u8]);
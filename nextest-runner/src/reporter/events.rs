@@ -8,14 +8,15 @@
 
 use super::{FinalStatusLevel, StatusLevel, TestOutputDisplay};
 use crate::{
-    config::ScriptId,
+    config::{MaxFailScope, ScriptId},
     list::{TestInstance, TestInstanceId, TestList},
     test_output::ChildExecutionOutput,
 };
+use camino::Utf8PathBuf;
 use chrono::{DateTime, FixedOffset};
 use nextest_metadata::MismatchReason;
 use quick_junit::ReportUuid;
-use std::{collections::BTreeMap, fmt, process::ExitStatus, time::Duration};
+use std::{collections::BTreeMap, fmt, net::SocketAddr, process::ExitStatus, time::Duration};
 
 /// A test event.
 ///
@@ -200,6 +201,9 @@ pub enum TestEventKind<'a> {
         /// Whether the JUnit report should store failure output for this test.
         junit_store_failure_output: bool,
 
+        /// The issue tracking known failures of this test, if set via `tracking-issue`.
+        tracking_issue: Option<String>,
+
         /// Information about all the runs for this test.
         run_statuses: ExecutionStatuses,
 
@@ -276,6 +280,43 @@ pub enum TestEventKind<'a> {
         reason: CancelReason,
     },
 
+    /// Test failures exceeded the max-fail limit within a package or binary,
+    /// so remaining tests in that scope will not be started.
+    ///
+    /// Unlike [`Self::RunBeginCancel`], the rest of the run continues.
+    ScopeBeginCancel {
+        /// The scope within which tests are being cancelled.
+        scope: MaxFailScope,
+
+        /// The test whose failure caused the scope to be cancelled.
+        test_instance: TestInstance<'a>,
+
+        /// The number of tests still running.
+        running: usize,
+    },
+
+    /// The system came under pressure, so fewer tests will be run at the same
+    /// time.
+    ///
+    /// Tests that are already running are allowed to finish. This is only
+    /// emitted if adaptive concurrency is enabled.
+    ConcurrencyReduced {
+        /// The number of tests that can now run at the same time.
+        limit: usize,
+
+        /// The number of test threads the run was started with.
+        test_threads: usize,
+
+        /// What caused concurrency to be reduced.
+        pressure: SystemPressure,
+    },
+
+    /// System pressure subsided, and the run is back to its full concurrency.
+    ConcurrencyRestored {
+        /// The number of test threads the run was started with.
+        test_threads: usize,
+    },
+
     /// A forcible kill was requested due to receiving a signal.
     RunBeginKill {
         /// The number of setup scripts still running.
@@ -379,6 +420,16 @@ pub struct RunStats {
 
     /// The number of tests that were skipped.
     pub skipped: usize,
+
+    /// The number of tests that were skipped, broken down by why they were skipped.
+    pub skipped_by_reason: SkipCounts,
+
+    /// The number of tests that were run with cgroup limits applied.
+    pub cgroup_limits_applied: usize,
+
+    /// The number of tests that had cgroup limits configured, but were run without them because
+    /// they couldn't be applied.
+    pub cgroup_limits_unavailable: usize,
 }
 
 impl RunStats {
@@ -397,6 +448,19 @@ impl RunStats {
         self.failed + self.exec_failed + self.timed_out
     }
 
+    /// Returns the number of tests that were scheduled to run but haven't
+    /// finished, not counting tests skipped because their max-fail scope was
+    /// cancelled.
+    pub fn not_run_count(&self) -> usize {
+        self.initial_run_count
+            .saturating_sub(self.finished_count + self.skipped_by_reason.scope_cancelled)
+    }
+
+    /// Returns true if the run succeeded, but some tests only passed after being retried.
+    pub fn is_flaky_success(&self) -> bool {
+        self.flaky > 0 && self.summarize_final() == FinalRunStats::Success
+    }
+
     /// Summarizes the stats as an enum at the end of a test run.
     pub fn summarize_final(&self) -> FinalRunStats {
         // Check for failures first. The order of setup scripts vs tests should not be important,
@@ -408,12 +472,12 @@ impl RunStats {
         } else if self.failed_count() > 0 {
             FinalRunStats::Failed(RunStatsFailureKind::Test {
                 initial_run_count: self.initial_run_count,
-                not_run: self.initial_run_count.saturating_sub(self.finished_count),
+                not_run: self.not_run_count(),
             })
-        } else if self.initial_run_count > self.finished_count {
+        } else if self.not_run_count() > 0 {
             FinalRunStats::Cancelled(RunStatsFailureKind::Test {
                 initial_run_count: self.initial_run_count,
-                not_run: self.initial_run_count.saturating_sub(self.finished_count),
+                not_run: self.not_run_count(),
             })
         } else if self.finished_count == 0 {
             FinalRunStats::NoTestsRun
@@ -429,7 +493,10 @@ impl RunStats {
             ExecutionResult::Pass | ExecutionResult::Leak => {
                 self.setup_scripts_passed += 1;
             }
-            ExecutionResult::Fail { .. } => {
+            ExecutionResult::Fail { .. }
+            | ExecutionResult::MemoryError { .. }
+            | ExecutionResult::CancelAcknowledged
+            | ExecutionResult::AccessViolation => {
                 self.setup_scripts_failed += 1;
             }
             ExecutionResult::ExecFail => {
@@ -452,6 +519,11 @@ impl RunStats {
         // This is not likely to matter much in practice since failures are likely to be of the
         // same type.
         let last_status = run_statuses.last_status();
+        match last_status.cgroup_limits {
+            CgroupLimitsStatus::NotRequested => {}
+            CgroupLimitsStatus::Applied => self.cgroup_limits_applied += 1,
+            CgroupLimitsStatus::Unavailable => self.cgroup_limits_unavailable += 1,
+        }
         match last_status.result {
             ExecutionResult::Pass => {
                 self.passed += 1;
@@ -472,7 +544,10 @@ impl RunStats {
                     self.flaky += 1;
                 }
             }
-            ExecutionResult::Fail { .. } => {
+            ExecutionResult::Fail { .. }
+            | ExecutionResult::MemoryError { .. }
+            | ExecutionResult::CancelAcknowledged
+            | ExecutionResult::AccessViolation => {
                 self.failed += 1;
                 if last_status.is_slow {
                     self.failed_slow += 1;
@@ -482,6 +557,71 @@ impl RunStats {
             ExecutionResult::ExecFail => self.exec_failed += 1,
         }
     }
+
+    pub(crate) fn on_test_skipped(&mut self, reason: MismatchReason) {
+        self.skipped += 1;
+        self.skipped_by_reason.record(reason);
+    }
+}
+
+/// The number of tests that were skipped, broken down by why they were skipped.
+///
+/// The same names are used for these reasons across human-readable and
+/// machine-readable output. Part of [`RunStats`].
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+pub struct SkipCounts {
+    /// Tests skipped because of `#[ignore]` (or, with `--run-ignored only`,
+    /// because they weren't ignored). Named `ignored`.
+    pub ignored: usize,
+
+    /// Tests that didn't match the string filters passed in. Named `string-filter`.
+    pub string_filter: usize,
+
+    /// Tests that didn't match the filtersets passed in. Named `expression-filter`.
+    pub expression_filter: usize,
+
+    /// Tests in a different partition. Named `partition`.
+    pub partition: usize,
+
+    /// Tests excluded by the profile's default filter. Named `default-filter`.
+    pub default_filter: usize,
+
+    /// Tests that weren't started because their package or binary was cancelled
+    /// by `--max-fail-scope`. Named `scope-cancelled`.
+    ///
+    /// Unlike the other reasons, these tests are part of the initial run count.
+    pub scope_cancelled: usize,
+}
+
+impl SkipCounts {
+    /// Records a test skipped for the given reason.
+    pub fn record(&mut self, reason: MismatchReason) {
+        match reason {
+            MismatchReason::Ignored => self.ignored += 1,
+            MismatchReason::String => self.string_filter += 1,
+            MismatchReason::Expression => self.expression_filter += 1,
+            MismatchReason::Partition => self.partition += 1,
+            MismatchReason::DefaultFilter => self.default_filter += 1,
+            MismatchReason::ScopeCancelled => self.scope_cancelled += 1,
+            // MismatchReason is non-exhaustive, but every reason nextest
+            // produces is covered above.
+            _ => {}
+        }
+    }
+
+    /// Returns the name and count for each reason, including reasons with a
+    /// count of zero.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, usize)> {
+        [
+            ("ignored", self.ignored),
+            ("string-filter", self.string_filter),
+            ("expression-filter", self.expression_filter),
+            ("partition", self.partition),
+            ("default-filter", self.default_filter),
+            ("scope-cancelled", self.scope_cancelled),
+        ]
+        .into_iter()
+    }
 }
 
 /// A type summarizing the possible outcomes of a test run.
@@ -670,6 +810,23 @@ pub struct ExecuteStatus {
     pub output: ChildExecutionOutput,
     /// The execution result for this test: pass, fail or execution error.
     pub result: ExecutionResult,
+    /// The failure class this test was assigned, based on its output.
+    ///
+    /// This is `None` if the test passed, or if no `failure-classes` rule matched its output.
+    pub failure_class: Option<String>,
+    /// The key-value annotations the test printed, if it was run with `annotations` enabled.
+    pub annotations: BTreeMap<String, String>,
+    /// The time the test took to run as reported by libtest, if it was run with `libtest-json`
+    /// enabled.
+    ///
+    /// Unlike `time_taken`, this excludes the time taken to start and stop the test process.
+    pub exec_time: Option<Duration>,
+    /// An estimate of how long the test binary took to start up, if it could be measured.
+    ///
+    /// This is the time taken outside of `exec_time` if libtest reported it, and otherwise the
+    /// time until the test first produced output. It's `None` if output wasn't captured and
+    /// libtest didn't report a time.
+    pub startup_time: Option<Duration>,
     /// The time at which the test started.
     pub start_time: DateTime<FixedOffset>,
     /// The time it took for the test to run.
@@ -678,6 +835,57 @@ pub struct ExecuteStatus {
     pub is_slow: bool,
     /// The delay will be non-zero if this is a retry and delay was specified.
     pub delay_before_start: Duration,
+    /// Whether [cgroup limits](crate::config::CgroupLimits) were applied to this test.
+    pub cgroup_limits: CgroupLimitsStatus,
+    /// The temporary directory this test was run with, if it was kept after the test failed.
+    ///
+    /// This is set if the test was run with `isolate-tmpdir = "keep-on-failure"`.
+    pub kept_tmpdir: Option<Utf8PathBuf>,
+    /// The directory this test's on-disk state was kept in, if the test failed and
+    /// [`TestRunnerBuilder::set_keep_failed_workdirs`](crate::runner::TestRunnerBuilder::set_keep_failed_workdirs)
+    /// was set.
+    pub kept_workdir: Option<Utf8PathBuf>,
+    /// The path to the report of this test's network and file system accesses, if the test was
+    /// run with [`access-audit`](crate::config::AccessAudit) enabled.
+    pub access_report: Option<Utf8PathBuf>,
+    /// The network destinations this test contacted that the profile's
+    /// [access policy](crate::config::AccessPolicy) doesn't allow.
+    ///
+    /// This is only set if the test was run with `access-audit = "enforce"`. If it's non-empty and
+    /// the test otherwise passed, the result is [`ExecutionResult::AccessViolation`].
+    pub access_violations: Vec<SocketAddr>,
+    /// The crash dump collected from this test, if it crashed and was run with
+    /// [`crash-dumps`](crate::config::CrashDumps) enabled.
+    pub crash_dump: Option<CrashDump>,
+}
+
+/// A crash dump collected from a test that crashed.
+///
+/// Part of [`ExecuteStatus`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CrashDump {
+    /// The path to the crash dump, within the store directory.
+    pub path: Utf8PathBuf,
+
+    /// The path to the output of the profile's `crash-backtrace` command, if one is configured.
+    pub backtrace: Option<Utf8PathBuf>,
+}
+
+/// Whether cgroup limits were applied to a test.
+///
+/// Part of [`ExecuteStatus`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CgroupLimitsStatus {
+    /// No cgroup limits were configured for this test.
+    #[default]
+    NotRequested,
+
+    /// The test was run in its own cgroup, with the configured limits.
+    Applied,
+
+    /// Cgroup limits were configured for this test, but couldn't be applied, so the test was run
+    /// without them.
+    Unavailable,
 }
 
 /// Information about the execution of a setup script.
@@ -756,6 +964,23 @@ pub enum ExecutionResult {
     ExecFail,
     /// The test was terminated due to a timeout.
     Timeout,
+    /// The test failed, and an error-checking tool reported a memory error.
+    ///
+    /// This is only produced if the test was run with a `wrapper` preset.
+    MemoryError {
+        /// The tool that reported the error.
+        tool: MemoryTool,
+    },
+    /// The test was asked to stop, because it timed out or the run was cancelled, and acknowledged
+    /// the request before exiting.
+    ///
+    /// This is only produced if the test was run with `cooperative-cancel` enabled.
+    CancelAcknowledged,
+    /// The test passed, but contacted network destinations that the profile's access policy
+    /// doesn't allow.
+    ///
+    /// This is only produced if the test was run with `access-audit = "enforce"`.
+    AccessViolation,
 }
 
 impl ExecutionResult {
@@ -763,13 +988,55 @@ impl ExecutionResult {
     pub fn is_success(self) -> bool {
         match self {
             ExecutionResult::Pass | ExecutionResult::Leak => true,
-            ExecutionResult::Fail { .. } | ExecutionResult::ExecFail | ExecutionResult::Timeout => {
-                false
-            }
+            ExecutionResult::Fail { .. }
+            | ExecutionResult::ExecFail
+            | ExecutionResult::Timeout
+            | ExecutionResult::MemoryError { .. }
+            | ExecutionResult::CancelAcknowledged
+            | ExecutionResult::AccessViolation => false,
+        }
+    }
+}
+
+/// An error-checking tool that reported a memory error.
+///
+/// Returned as part of the [`ExecutionResult::MemoryError`] variant.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MemoryTool {
+    /// Valgrind's memcheck tool.
+    Valgrind,
+    /// AddressSanitizer.
+    AddressSanitizer,
+    /// LeakSanitizer.
+    LeakSanitizer,
+    /// MemorySanitizer.
+    MemorySanitizer,
+    /// ThreadSanitizer.
+    ThreadSanitizer,
+    /// UndefinedBehaviorSanitizer.
+    UndefinedBehaviorSanitizer,
+}
+
+impl MemoryTool {
+    /// Returns the name of the tool, as used in its reports.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Valgrind => "Valgrind",
+            Self::AddressSanitizer => "AddressSanitizer",
+            Self::LeakSanitizer => "LeakSanitizer",
+            Self::MemorySanitizer => "MemorySanitizer",
+            Self::ThreadSanitizer => "ThreadSanitizer",
+            Self::UndefinedBehaviorSanitizer => "UndefinedBehaviorSanitizer",
         }
     }
 }
 
+impl fmt::Display for MemoryTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// A regular exit code or Windows NT abort status for a test.
 ///
 /// Returned as part of the [`ExecutionResult::Fail`] variant.
@@ -821,6 +1088,9 @@ pub enum CancelReason {
     /// An error occurred while reporting results.
     ReportError,
 
+    /// A `cancel` command was received over the control socket.
+    ControlRequest,
+
     /// A termination signal (on Unix, SIGTERM or SIGHUP) was received.
     Signal,
 
@@ -837,12 +1107,45 @@ impl CancelReason {
             CancelReason::SetupScriptFailure => "setup script failure",
             CancelReason::TestFailure => "test failure",
             CancelReason::ReportError => "reporting error",
+            CancelReason::ControlRequest => "control request",
             CancelReason::Signal => "signal",
             CancelReason::Interrupt => "interrupt",
             CancelReason::SecondSignal => "second signal",
         }
     }
 }
+/// The kind of system pressure that caused adaptive concurrency to reduce the
+/// number of tests running at the same time.
+///
+/// Part of [`TestEventKind::ConcurrencyReduced`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SystemPressure {
+    /// The 1-minute load average per logical CPU was above `max-load`.
+    Load {
+        /// The load average per logical CPU.
+        load_per_cpu: f64,
+    },
+
+    /// Available memory was below `min-available-memory`.
+    Memory {
+        /// The amount of available memory, in bytes.
+        available: u64,
+    },
+}
+
+impl fmt::Display for SystemPressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load { load_per_cpu } => {
+                write!(f, "load average is {load_per_cpu:.2} per CPU")
+            }
+            Self::Memory { available } => {
+                write!(f, "{} MiB of memory available", available / (1024 * 1024))
+            }
+        }
+    }
+}
+
 /// The kind of unit of work that nextest is executing.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum UnitKind {
@@ -1272,4 +1575,31 @@ mod tests {
             "setup scripts passed => success, but no tests run"
         );
     }
+
+    #[test]
+    fn test_is_flaky_success() {
+        let stats = RunStats {
+            initial_run_count: 42,
+            finished_count: 42,
+            passed: 42,
+            ..RunStats::default()
+        };
+        assert!(
+            !stats.is_flaky_success(),
+            "no flaky tests => not a flaky success"
+        );
+        assert!(
+            RunStats { flaky: 1, ..stats }.is_flaky_success(),
+            "flaky tests and no failures => flaky success"
+        );
+        assert!(
+            !RunStats {
+                flaky: 1,
+                failed: 1,
+                ..stats
+            }
+            .is_flaky_success(),
+            "flaky tests with failures => not a flaky success"
+        );
+    }
 }
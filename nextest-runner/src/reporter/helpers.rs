@@ -1,6 +1,7 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::theme::Theme;
 use bstr::ByteSlice;
 use owo_colors::Style;
 
@@ -32,18 +33,22 @@ pub(super) struct Styles {
     pub(super) fail: Style,
     pub(super) skip: Style,
     pub(super) script_id: Style,
+    pub(super) backtrace_internal: Style,
+    pub(super) backtrace_workspace: Style,
     pub(super) list_styles: crate::list::Styles,
 }
 
 impl Styles {
-    pub(super) fn colorize(&mut self) {
+    pub(super) fn colorize(&mut self, theme: &Theme) {
         self.is_colorized = true;
-        self.count = Style::new().bold();
-        self.pass = Style::new().green().bold();
-        self.retry = Style::new().magenta().bold();
-        self.fail = Style::new().red().bold();
-        self.skip = Style::new().yellow().bold();
-        self.script_id = Style::new().blue().bold();
+        self.count = theme.emphasis();
+        self.pass = theme.pass();
+        self.retry = theme.retry();
+        self.fail = theme.fail();
+        self.skip = theme.skip();
+        self.script_id = theme.embolden(Style::new().blue());
+        self.backtrace_internal = Style::new().dimmed();
+        self.backtrace_workspace = theme.embolden(Style::new().cyan());
         self.list_styles.colorize();
     }
 }
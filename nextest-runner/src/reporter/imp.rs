@@ -6,15 +6,20 @@
 //! The main structure in this module is [`TestReporter`].
 
 use super::{
-    displayer::{DisplayReporter, DisplayReporterBuilder, StatusLevels},
-    FinalStatusLevel, StatusLevel, TestOutputDisplay,
+    displayer::{DisplayFormat, DisplayReporter, DisplayReporterBuilder, StatusLevels},
+    status_file::StatusFileReporter,
+    DurationFormat, FinalStatusLevel, GroupBy, StatusLevel, TestOutputDisplay,
 };
 use crate::{
-    config::EvaluatableProfile,
+    config::{EvaluatableProfile, FinalSummary},
     errors::WriteEventError,
     list::TestList,
     reporter::{aggregator::EventAggregator, events::*, structured::StructuredReporter},
+    theme::Theme,
+    user_config::Symbols,
 };
+use camino::Utf8PathBuf;
+use std::collections::BTreeMap;
 
 /// Standard error destination for the reporter.
 ///
@@ -34,13 +39,21 @@ pub enum ReporterStderr<'a> {
 pub struct ReporterBuilder {
     no_capture: bool,
     should_colorize: bool,
+    theme: Theme,
+    symbols: Option<Symbols>,
+    duration_format: DurationFormat,
+    thousands_separator: bool,
     failure_output: Option<TestOutputDisplay>,
     success_output: Option<TestOutputDisplay>,
     status_level: Option<StatusLevel>,
     final_status_level: Option<FinalStatusLevel>,
+    final_summary: Option<FinalSummary>,
+    group_by: GroupBy,
 
     verbose: bool,
     hide_progress_bar: bool,
+    show_slots: bool,
+    status_file: Option<Utf8PathBuf>,
 }
 
 impl ReporterBuilder {
@@ -59,6 +72,32 @@ impl ReporterBuilder {
         self
     }
 
+    /// Sets the color theme, used if the reporter colorizes output.
+    pub fn set_theme(&mut self, theme: Theme) -> &mut Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets the characters to use for progress bars and separators.
+    ///
+    /// By default, Unicode characters are used if the terminal supports them.
+    pub fn set_symbols(&mut self, symbols: Symbols) -> &mut Self {
+        self.symbols = Some(symbols);
+        self
+    }
+
+    /// Sets how durations are displayed.
+    pub fn set_duration_format(&mut self, duration_format: DurationFormat) -> &mut Self {
+        self.duration_format = duration_format;
+        self
+    }
+
+    /// Sets whether counts are grouped with thousands separators.
+    pub fn set_thousands_separator(&mut self, thousands_separator: bool) -> &mut Self {
+        self.thousands_separator = thousands_separator;
+        self
+    }
+
     /// Sets the conditions under which test failures are output.
     pub fn set_failure_output(&mut self, failure_output: TestOutputDisplay) -> &mut Self {
         self.failure_output = Some(failure_output);
@@ -83,6 +122,19 @@ impl ReporterBuilder {
         self
     }
 
+    /// Sets the extra sections to show in the final summary, overriding the
+    /// profile.
+    pub fn set_final_summary(&mut self, final_summary: FinalSummary) -> &mut Self {
+        self.final_summary = Some(final_summary);
+        self
+    }
+
+    /// Sets how test results are grouped in the output.
+    pub fn set_group_by(&mut self, group_by: GroupBy) -> &mut Self {
+        self.group_by = group_by;
+        self
+    }
+
     /// Sets verbose output.
     pub fn set_verbose(&mut self, verbose: bool) -> &mut Self {
         self.verbose = verbose;
@@ -95,6 +147,19 @@ impl ReporterBuilder {
         self.hide_progress_bar = hide_progress_bar;
         self
     }
+
+    /// Sets whether to show the test running in each slot below the progress bar.
+    /// This has no effect if the progress bar is hidden.
+    pub fn set_show_slots(&mut self, show_slots: bool) -> &mut Self {
+        self.show_slots = show_slots;
+        self
+    }
+
+    /// Sets a path to periodically write a JSON summary of the run's progress to.
+    pub fn set_status_file(&mut self, path: Utf8PathBuf) -> &mut Self {
+        self.status_file = Some(path);
+        self
+    }
 }
 
 impl ReporterBuilder {
@@ -112,6 +177,10 @@ impl ReporterBuilder {
         let final_status_level = self
             .final_status_level
             .unwrap_or_else(|| profile.final_status_level());
+        let final_summary = self
+            .final_summary
+            .or_else(|| profile.final_summary())
+            .unwrap_or_default();
 
         let display_reporter = DisplayReporterBuilder {
             default_filter: profile.default_filter().clone(),
@@ -119,19 +188,39 @@ impl ReporterBuilder {
                 status_level,
                 final_status_level,
             },
+            final_summary,
             test_count: test_list.test_count(),
+            binary_test_counts: match self.group_by {
+                GroupBy::None => BTreeMap::new(),
+                GroupBy::Binary => test_list
+                    .iter()
+                    .map(|suite| (suite.binary_id.clone(), suite.status.test_count()))
+                    .filter(|(_, test_count)| *test_count > 0)
+                    .collect(),
+            },
             success_output: self.success_output,
             failure_output: self.failure_output,
             should_colorize: self.should_colorize,
+            theme: self.theme,
+            symbols: self.symbols,
             no_capture: self.no_capture,
             hide_progress_bar: self.hide_progress_bar,
+            show_slots: self.show_slots,
+            format: DisplayFormat {
+                duration_format: self.duration_format,
+                thousands_separator: self.thousands_separator,
+            },
+            workspace_root: test_list.workspace_root().to_owned(),
         }
         .build(output);
 
+        let status_file = self.status_file.clone().map(StatusFileReporter::new);
+
         Reporter {
             display_reporter,
             structured_reporter,
             metadata_reporter: aggregator,
+            status_file,
         }
     }
 }
@@ -145,6 +234,8 @@ pub struct Reporter<'a> {
     metadata_reporter: EventAggregator<'a>,
     /// Used to emit test events in machine-readable format(s) to stdout
     structured_reporter: StructuredReporter<'a>,
+    /// Used to periodically write a summary of the run's progress to a file
+    status_file: Option<StatusFileReporter>,
 }
 
 impl<'a> Reporter<'a> {
@@ -156,6 +247,9 @@ impl<'a> Reporter<'a> {
     /// Mark the reporter done.
     pub fn finish(&mut self) {
         self.display_reporter.finish();
+        if let Some(status_file) = &mut self.status_file {
+            status_file.finish();
+        }
     }
 
     // ---
@@ -166,6 +260,9 @@ impl<'a> Reporter<'a> {
     fn write_event(&mut self, event: TestEvent<'a>) -> Result<(), WriteEventError> {
         // TODO: write to all of these even if one of them fails?
         self.display_reporter.write_event(&event)?;
+        if let Some(status_file) = &mut self.status_file {
+            status_file.write_event(&event);
+        }
         self.structured_reporter.write_event(&event)?;
         self.metadata_reporter.write_event(event)?;
         Ok(())
@@ -0,0 +1,247 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A minimal document model for reading and writing JUnit XML reports.
+//!
+//! quick-junit only supports writing reports, so reports produced by earlier runs are parsed into
+//! this model instead.
+
+use crate::errors::JunitReportError;
+use camino::Utf8Path;
+use quick_xml::{
+    events::{BytesCData, BytesDecl, BytesEnd, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+
+/// Reads the JUnit report at `path`, returning its `<testsuites>` root element.
+pub(super) fn read_report(path: &Utf8Path) -> Result<Element, JunitReportError> {
+    let contents = std::fs::read_to_string(path).map_err(|error| JunitReportError::Read {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    parse_report(path, &contents)
+}
+
+/// Parses the contents of a JUnit report, returning its `<testsuites>` root element.
+pub(super) fn parse_report(path: &Utf8Path, contents: &str) -> Result<Element, JunitReportError> {
+    let root = parse(contents).map_err(|error| JunitReportError::Parse {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    match root {
+        Some(root) if root.name == "testsuites" => Ok(root),
+        other => Err(JunitReportError::InvalidRoot {
+            path: path.to_path_buf(),
+            found: other.map(|root| root.name).unwrap_or_default(),
+        }),
+    }
+}
+
+/// Serializes a report with `root` as its root element.
+pub(super) fn to_xml_string(root: &Element) -> String {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 4);
+    write_event(
+        &mut writer,
+        Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)),
+    );
+    write_element(&mut writer, root);
+    let mut out =
+        String::from_utf8(writer.into_inner()).expect("elements and text are valid UTF-8");
+    out.push('\n');
+    out
+}
+
+pub(super) fn parse_time(time: Option<&str>) -> f64 {
+    time.and_then(|time| time.parse().ok()).unwrap_or(0.0)
+}
+
+pub(super) fn format_time(time: f64) -> String {
+    // This matches the precision used by quick-junit.
+    format!("{time:.3}")
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct Element {
+    pub(super) name: String,
+    pub(super) attrs: Vec<(String, String)>,
+    pub(super) children: Vec<Node>,
+}
+
+#[derive(Clone, Debug)]
+pub(super) enum Node {
+    Element(Element),
+    Text(String),
+    CData(String),
+}
+
+impl Element {
+    pub(super) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            attrs: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn from_start(start: &BytesStart<'_>) -> Result<Self, quick_xml::Error> {
+        let attrs = start
+            .attributes()
+            .map(|attr| {
+                let attr = attr?;
+                let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+                let value = attr.unescape_value()?.into_owned();
+                Ok((key, value))
+            })
+            .collect::<Result<_, quick_xml::Error>>()?;
+        Ok(Self {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            attrs,
+            children: Vec::new(),
+        })
+    }
+
+    pub(super) fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub(super) fn set_attr(&mut self, key: &str, value: String) {
+        match self.attrs.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.attrs.push((key.to_owned(), value)),
+        }
+    }
+
+    pub(super) fn add_count(&mut self, key: &str, value: Option<&str>) {
+        let count = |value: Option<&str>| -> usize {
+            value.and_then(|value| value.parse().ok()).unwrap_or(0)
+        };
+        let sum = count(self.attr(key)) + count(value);
+        self.set_attr(key, sum.to_string());
+    }
+
+    pub(super) fn child_elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            Node::Text(_) | Node::CData(_) => None,
+        })
+    }
+
+    pub(super) fn has_child(&self, name: &str) -> bool {
+        self.child_elements().any(|element| element.name == name)
+    }
+
+    pub(super) fn child(&self, name: &str) -> Option<&Element> {
+        self.child_elements().find(|element| element.name == name)
+    }
+
+    /// Returns the text directly within this element.
+    pub(super) fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|child| match child {
+                Node::Text(text) | Node::CData(text) => Some(text.as_str()),
+                Node::Element(_) => None,
+            })
+            .collect()
+    }
+}
+
+fn parse(contents: &str) -> Result<Option<Element>, quick_xml::Error> {
+    let mut reader = Reader::from_str(contents);
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => stack.push(Element::from_start(&start)?),
+            Event::Empty(start) => {
+                let element = Element::from_start(&start)?;
+                push_element(&mut stack, &mut root, element);
+            }
+            Event::End(_) => {
+                let element = stack
+                    .pop()
+                    .expect("reader checks that end tags match start tags");
+                push_element(&mut stack, &mut root, element);
+            }
+            Event::Text(text) => {
+                let text = text.unescape()?;
+                // Whitespace between elements is regenerated while writing the report.
+                if let Some(parent) = stack.last_mut() {
+                    if !text.trim().is_empty() {
+                        parent.children.push(Node::Text(text.into_owned()));
+                    }
+                }
+            }
+            Event::CData(cdata) => {
+                if let Some(parent) = stack.last_mut() {
+                    let cdata = String::from_utf8_lossy(&cdata).into_owned();
+                    parent.children.push(Node::CData(cdata));
+                }
+            }
+            Event::Eof => break,
+            // Declarations, comments, processing instructions and doctypes are not preserved.
+            _ => {}
+        }
+    }
+
+    Ok(root)
+}
+
+fn push_element(stack: &mut [Element], root: &mut Option<Element>, element: Element) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(Node::Element(element)),
+        None => {
+            root.get_or_insert(element);
+        }
+    }
+}
+
+fn write_element(writer: &mut Writer<Vec<u8>>, element: &Element) {
+    let mut start = BytesStart::new(element.name.as_str());
+    for (key, value) in &element.attrs {
+        start.push_attribute((key.as_str(), value.as_str()));
+    }
+
+    if element.children.is_empty() {
+        write_event(writer, Event::Empty(start));
+        return;
+    }
+
+    write_event(writer, Event::Start(start));
+    for child in &element.children {
+        match child {
+            Node::Element(child) => write_element(writer, child),
+            Node::Text(text) => write_event(writer, Event::Text(BytesText::new(text))),
+            Node::CData(cdata) => write_event(writer, Event::CData(BytesCData::new(cdata))),
+        }
+    }
+    write_event(writer, Event::End(BytesEnd::new(element.name.as_str())));
+}
+
+fn write_event(writer: &mut Writer<Vec<u8>>, event: Event<'_>) {
+    writer
+        .write_event(event)
+        .expect("writing to a Vec is infallible");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report_invalid_root() {
+        let error = parse_report(Utf8Path::new("shard.xml"), "<testsuite name=\"foo\"/>")
+            .expect_err("root is not testsuites");
+        assert!(
+            matches!(
+                &error,
+                JunitReportError::InvalidRoot { found, .. } if found == "testsuite"
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+}
@@ -0,0 +1,368 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Merging JUnit reports produced by partitioned runs.
+//!
+//! With `--partition`, each CI shard writes its own JUnit report. [`MergedReport`] combines these
+//! into a single JUnit report, and produces a [`MergeSummary`] with counts, flaky tests, and the
+//! slowest tests across all shards.
+
+use super::junit_xml::{format_time, parse_time, read_report, to_xml_string, Element, Node};
+use crate::errors::JunitReportError;
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use std::cmp::Ordering;
+
+/// A JUnit report merged from the reports of several shards.
+#[derive(Clone, Debug)]
+pub struct MergedReport {
+    root: Element,
+    summary: MergeSummary,
+}
+
+impl MergedReport {
+    /// The name of the merged JUnit report within the output directory.
+    pub const JUNIT_FILE_NAME: &'static str = "junit.xml";
+
+    /// The name of the summary within the output directory.
+    pub const SUMMARY_FILE_NAME: &'static str = "summary.json";
+
+    /// The number of tests listed in [`MergeSummary::slowest_tests`].
+    pub const SLOWEST_COUNT: usize = 10;
+
+    /// Reads and merges the JUnit reports at `paths`.
+    pub fn read(paths: &[Utf8PathBuf]) -> Result<Self, JunitReportError> {
+        let mut merger = Merger::default();
+        for path in paths {
+            merger.add(read_report(path)?);
+        }
+        Ok(merger.finish())
+    }
+
+    /// Returns the summary of the merged report.
+    pub fn summary(&self) -> &MergeSummary {
+        &self.summary
+    }
+
+    /// Serializes the merged JUnit report to a string.
+    pub fn to_junit_string(&self) -> String {
+        to_xml_string(&self.root)
+    }
+
+    /// Writes the merged JUnit report and the summary to `dir`, creating it if necessary.
+    pub fn write(&self, dir: &Utf8Path) -> Result<(), JunitReportError> {
+        std::fs::create_dir_all(dir).map_err(|error| JunitReportError::Write {
+            path: dir.to_path_buf(),
+            error,
+        })?;
+
+        let junit_path = dir.join(Self::JUNIT_FILE_NAME);
+        std::fs::write(&junit_path, self.to_junit_string()).map_err(|error| {
+            JunitReportError::Write {
+                path: junit_path.clone(),
+                error,
+            }
+        })?;
+
+        let summary_path = dir.join(Self::SUMMARY_FILE_NAME);
+        let mut summary = serde_json::to_string_pretty(&self.summary)
+            .expect("serializing the summary always succeeds");
+        summary.push('\n');
+        std::fs::write(&summary_path, summary).map_err(|error| JunitReportError::Write {
+            path: summary_path.clone(),
+            error,
+        })
+    }
+}
+
+/// Counts and notable tests across all shards of a merged report.
+///
+/// Setup scripts run once per shard, so they aren't included in the summary.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MergeSummary {
+    /// The number of reports that were merged.
+    pub shard_count: usize,
+
+    /// The number of tests across all shards.
+    pub test_count: usize,
+
+    /// The number of tests that passed, including flaky tests.
+    pub passed: usize,
+
+    /// The number of tests that failed or errored.
+    pub failed: usize,
+
+    /// The number of tests that were skipped.
+    pub skipped: usize,
+
+    /// Tests that failed or errored.
+    pub failed_tests: Vec<MergedTest>,
+
+    /// Tests that passed after being retried.
+    pub flaky_tests: Vec<MergedTest>,
+
+    /// The slowest tests, slowest first.
+    pub slowest_tests: Vec<MergedTest>,
+}
+
+/// A test within a [`MergeSummary`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MergedTest {
+    /// The binary ID of the test, from the `classname` attribute.
+    pub binary_id: String,
+
+    /// The name of the test.
+    pub name: String,
+
+    /// The time taken by the test's final attempt, in seconds.
+    pub time: f64,
+}
+
+#[derive(Debug, Default)]
+struct Merger {
+    shard_count: usize,
+    root: Option<Element>,
+    earliest_timestamp: Option<DateTime<FixedOffset>>,
+    suites: Vec<Element>,
+}
+
+impl Merger {
+    fn add(&mut self, mut root: Element) {
+        self.shard_count += 1;
+
+        let children = std::mem::take(&mut root.children);
+        match &mut self.root {
+            Some(merged) => {
+                for attr in ["tests", "failures", "errors"] {
+                    merged.add_count(attr, root.attr(attr));
+                }
+                // Shards run in parallel, so the longest shard is the best approximation of
+                // the time the whole run took.
+                let time = parse_time(merged.attr("time")).max(parse_time(root.attr("time")));
+                merged.set_attr("time", format_time(time));
+            }
+            None => {
+                // Each shard has its own run ID, none of which identify the merged report.
+                root.attrs.retain(|(key, _)| key != "uuid");
+                self.root = Some(root.clone());
+            }
+        }
+        if let Some(merged) = &mut self.root {
+            merge_timestamp(merged, &mut self.earliest_timestamp, root.attr("timestamp"));
+        }
+
+        for child in children {
+            let Node::Element(suite) = child else {
+                continue;
+            };
+            if suite.name != "testsuite" {
+                continue;
+            }
+            let existing = self
+                .suites
+                .iter_mut()
+                .find(|existing| existing.attr("name") == suite.attr("name"));
+            match existing {
+                Some(existing) => merge_suite(existing, suite),
+                None => self.suites.push(suite),
+            }
+        }
+    }
+
+    fn finish(self) -> MergedReport {
+        let mut summary = MergeSummary {
+            shard_count: self.shard_count,
+            ..Default::default()
+        };
+
+        let mut all_tests = Vec::new();
+        for suite in &self.suites {
+            if suite
+                .attr("name")
+                .is_some_and(|name| name.starts_with("@setup-script:"))
+            {
+                continue;
+            }
+            for testcase in suite.child_elements().filter(|e| e.name == "testcase") {
+                let test = MergedTest {
+                    binary_id: testcase.attr("classname").unwrap_or_default().to_owned(),
+                    name: testcase.attr("name").unwrap_or_default().to_owned(),
+                    time: parse_time(testcase.attr("time")),
+                };
+
+                summary.test_count += 1;
+                if testcase.has_child("failure") || testcase.has_child("error") {
+                    summary.failed += 1;
+                    summary.failed_tests.push(test.clone());
+                } else if testcase.has_child("skipped") {
+                    summary.skipped += 1;
+                } else {
+                    summary.passed += 1;
+                    if testcase.has_child("flakyFailure") || testcase.has_child("flakyError") {
+                        summary.flaky_tests.push(test.clone());
+                    }
+                }
+                all_tests.push(test);
+            }
+        }
+
+        all_tests.sort_by(|a, b| {
+            b.time
+                .partial_cmp(&a.time)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| (&a.binary_id, &a.name).cmp(&(&b.binary_id, &b.name)))
+        });
+        all_tests.truncate(MergedReport::SLOWEST_COUNT);
+        summary.slowest_tests = all_tests;
+
+        let mut root = self.root.unwrap_or_else(|| Element::new("testsuites"));
+        root.children = self.suites.into_iter().map(Node::Element).collect();
+
+        MergedReport { root, summary }
+    }
+}
+
+fn merge_suite(existing: &mut Element, suite: Element) {
+    for attr in ["tests", "disabled", "errors", "failures"] {
+        existing.add_count(attr, suite.attr(attr));
+    }
+    if suite.attr("time").is_some() {
+        let time = parse_time(existing.attr("time")) + parse_time(suite.attr("time"));
+        existing.set_attr("time", format_time(time));
+    }
+    let mut earliest = parse_timestamp(existing.attr("timestamp"));
+    merge_timestamp(existing, &mut earliest, suite.attr("timestamp"));
+
+    for child in suite.children {
+        match child {
+            Node::Element(element) if element.name == "testcase" => {
+                existing.children.push(Node::Element(element));
+            }
+            // Properties and suite-level output are the same for each shard, so keep the first
+            // shard's.
+            Node::Element(element) => {
+                if !existing.has_child(&element.name) {
+                    existing.children.push(Node::Element(element));
+                }
+            }
+            Node::Text(_) | Node::CData(_) => {}
+        }
+    }
+}
+
+fn merge_timestamp(
+    element: &mut Element,
+    earliest: &mut Option<DateTime<FixedOffset>>,
+    timestamp: Option<&str>,
+) {
+    let Some(timestamp) = timestamp else {
+        return;
+    };
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(timestamp) {
+        if !matches!(earliest, Some(current) if *current <= parsed) {
+            *earliest = Some(parsed);
+            element.set_attr("timestamp", timestamp.to_owned());
+        }
+    }
+}
+
+fn parse_timestamp(timestamp: Option<&str>) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(timestamp?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::junit_xml::parse_report, *};
+    use indoc::indoc;
+
+    static SHARD_1: &str = indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites name="nextest-run" tests="3" failures="1" errors="0" uuid="45c50042-482e-477e-88a2-60cfcc3eaf95" timestamp="2024-01-09T07:50:12.664+00:00" time="0.500">
+            <testsuite name="my-crate::basic" tests="2" disabled="0" errors="0" failures="1">
+                <testcase name="test_a" classname="my-crate::basic" time="0.100">
+                </testcase>
+                <testcase name="test_b" classname="my-crate::basic" time="0.300">
+                    <failure type="test failure">assertion &apos;left == right&apos; failed</failure>
+                </testcase>
+            </testsuite>
+            <testsuite name="my-crate" tests="1" disabled="0" errors="0" failures="0">
+                <testcase name="tests::flaky" classname="my-crate" time="0.050">
+                    <flakyFailure type="test failure"><![CDATA[panicked]]></flakyFailure>
+                </testcase>
+            </testsuite>
+        </testsuites>
+    "#};
+
+    static SHARD_2: &str = indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites name="nextest-run" tests="2" failures="0" errors="0" uuid="e9b5ea71-b92e-4ec4-a06b-3a1b5a6bb2f1" timestamp="2024-01-09T07:50:10.000+00:00" time="0.800">
+            <testsuite name="my-crate::basic" tests="2" disabled="0" errors="0" failures="0">
+                <testcase name="test_c" classname="my-crate::basic" time="0.700">
+                </testcase>
+                <testcase name="test_d" classname="my-crate::basic" time="0.010">
+                    <skipped/>
+                </testcase>
+            </testsuite>
+        </testsuites>
+    "#};
+
+    #[test]
+    fn test_merge_reports() {
+        let mut merger = Merger::default();
+        merger.add(parse_report(Utf8Path::new("shard-1.xml"), SHARD_1).expect("shard 1 is valid"));
+        merger.add(parse_report(Utf8Path::new("shard-2.xml"), SHARD_2).expect("shard 2 is valid"));
+        let report = merger.finish();
+
+        let test = |binary_id: &str, name: &str, time: f64| MergedTest {
+            binary_id: binary_id.to_owned(),
+            name: name.to_owned(),
+            time,
+        };
+        assert_eq!(
+            report.summary(),
+            &MergeSummary {
+                shard_count: 2,
+                test_count: 5,
+                passed: 3,
+                failed: 1,
+                skipped: 1,
+                failed_tests: vec![test("my-crate::basic", "test_b", 0.3)],
+                flaky_tests: vec![test("my-crate", "tests::flaky", 0.05)],
+                slowest_tests: vec![
+                    test("my-crate::basic", "test_c", 0.7),
+                    test("my-crate::basic", "test_b", 0.3),
+                    test("my-crate::basic", "test_a", 0.1),
+                    test("my-crate", "tests::flaky", 0.05),
+                    test("my-crate::basic", "test_d", 0.01),
+                ],
+            }
+        );
+
+        assert_eq!(
+            report.to_junit_string(),
+            indoc! {r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <testsuites name="nextest-run" tests="5" failures="1" errors="0" timestamp="2024-01-09T07:50:10.000+00:00" time="0.800">
+                    <testsuite name="my-crate::basic" tests="4" disabled="0" errors="0" failures="1">
+                        <testcase name="test_a" classname="my-crate::basic" time="0.100"/>
+                        <testcase name="test_b" classname="my-crate::basic" time="0.300">
+                            <failure type="test failure">assertion &apos;left == right&apos; failed</failure>
+                        </testcase>
+                        <testcase name="test_c" classname="my-crate::basic" time="0.700"/>
+                        <testcase name="test_d" classname="my-crate::basic" time="0.010">
+                            <skipped/>
+                        </testcase>
+                    </testsuite>
+                    <testsuite name="my-crate" tests="1" disabled="0" errors="0" failures="0">
+                        <testcase name="tests::flaky" classname="my-crate" time="0.050">
+                            <flakyFailure type="test failure"><![CDATA[panicked]]></flakyFailure>
+                        </testcase>
+                    </testsuite>
+                </testsuites>
+            "#}
+        );
+    }
+}
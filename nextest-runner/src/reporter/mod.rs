@@ -6,14 +6,21 @@
 //! The main type here is [`Reporter`], which is constructed via a [`ReporterBuilder`].
 
 mod aggregator;
+mod attempt_diff;
+pub mod diff;
 mod displayer;
 mod error_description;
 pub mod events;
 mod helpers;
 mod imp;
+mod junit_xml;
+pub mod merge;
+mod status_file;
 pub mod structured;
+pub mod timings;
 
-pub use displayer::{FinalStatusLevel, StatusLevel, TestOutputDisplay};
+pub use attempt_diff::*;
+pub use displayer::{DurationFormat, FinalStatusLevel, GroupBy, StatusLevel, TestOutputDisplay};
 pub use error_description::*;
 pub use helpers::highlight_end;
 pub use imp::*;
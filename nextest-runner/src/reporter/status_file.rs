@@ -0,0 +1,375 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A small JSON file describing the progress of a run, rewritten every few seconds.
+//!
+//! With `--status-file`, a background thread periodically replaces the file with the run's current
+//! counts, running tests and estimated time remaining. The file is replaced atomically, so that
+//! sidecar processes and health checks never observe a partially written file.
+
+use super::events::{RunStats, TestEvent, TestEventKind};
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::Local;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// How often the status file is rewritten.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Writes a status file for `--status-file`.
+pub(super) struct StatusFileReporter {
+    state: Arc<Mutex<StatusState>>,
+    // Dropping this stops the writer thread, after it writes out the file one last time.
+    stop_tx: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl StatusFileReporter {
+    pub(super) fn new(path: Utf8PathBuf) -> Self {
+        let state = Arc::new(Mutex::new(StatusState::default()));
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        let thread_state = state.clone();
+        let handle = thread::spawn(move || {
+            let mut failed = false;
+            let mut stop = false;
+            loop {
+                // The contents borrow from the state, so serialize them while the lock is held.
+                let bytes = {
+                    let state = thread_state
+                        .lock()
+                        .expect("status file lock is not poisoned");
+                    serde_json::to_vec_pretty(&state.to_contents(Instant::now()))
+                };
+                let result = bytes
+                    .map_err(io::Error::other)
+                    .and_then(|bytes| write_status_file(&path, &bytes));
+                if let Err(error) = result {
+                    // Only warn once, to avoid spamming the output every few seconds.
+                    if !failed {
+                        warn!("error writing status file to {path}: {error}");
+                        failed = true;
+                    }
+                }
+
+                if stop {
+                    break;
+                }
+                stop = match stop_rx.recv_timeout(UPDATE_INTERVAL) {
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => true,
+                };
+            }
+        });
+
+        Self {
+            state,
+            stop_tx: Some(stop_tx),
+            handle: Some(handle),
+        }
+    }
+
+    pub(super) fn write_event(&mut self, event: &TestEvent<'_>) {
+        let mut state = self.state.lock().expect("status file lock is not poisoned");
+        state.on_event(event);
+    }
+
+    /// Writes out the final status and stops the writer thread.
+    pub(super) fn finish(&mut self) {
+        self.stop_tx.take();
+        if let Some(handle) = self.handle.take() {
+            // The thread only panics if something has gone very wrong; ignore it here.
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for StatusFileReporter {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[derive(Debug, Default)]
+struct StatusState {
+    run_id: Option<String>,
+    profile_name: Option<String>,
+    phase: RunPhase,
+    start: Option<Instant>,
+    stats: RunStats,
+    // (binary ID, test name) -> when the test started, including any retries.
+    running: BTreeMap<(String, String), Instant>,
+}
+
+impl StatusState {
+    fn on_event(&mut self, event: &TestEvent<'_>) {
+        match &event.kind {
+            TestEventKind::RunStarted {
+                test_list,
+                run_id,
+                profile_name,
+                ..
+            } => {
+                self.run_id = Some(run_id.to_string());
+                self.profile_name = Some(profile_name.clone());
+                self.phase = RunPhase::Running;
+                self.start = Some(Instant::now());
+                self.stats.initial_run_count = test_list.run_count();
+            }
+            TestEventKind::TestStarted {
+                test_instance,
+                current_stats,
+                ..
+            } => {
+                let id = test_instance.id();
+                self.running.insert(
+                    (id.binary_id.to_string(), id.test_name.to_owned()),
+                    Instant::now(),
+                );
+                self.stats = *current_stats;
+            }
+            TestEventKind::TestFinished {
+                test_instance,
+                current_stats,
+                ..
+            } => {
+                let id = test_instance.id();
+                self.running
+                    .remove(&(id.binary_id.to_string(), id.test_name.to_owned()));
+                self.stats = *current_stats;
+            }
+            TestEventKind::RunBeginCancel { reason, .. } => {
+                self.phase = RunPhase::Cancelling {
+                    reason: reason.to_static_str(),
+                };
+            }
+            TestEventKind::RunFinished { run_stats, .. } => {
+                self.phase = RunPhase::Finished;
+                self.stats = *run_stats;
+                self.running.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn to_contents(&self, now: Instant) -> StatusFileContents<'_> {
+        let elapsed = self
+            .start
+            .map_or(Duration::ZERO, |start| now.saturating_duration_since(start));
+
+        // Estimate the time remaining from the average time per finished test so far.
+        let remaining = self.stats.not_run_count();
+        let eta_secs = match (self.phase, self.stats.finished_count) {
+            (RunPhase::Finished, _) => Some(0.0),
+            (_, 0) => None,
+            (_, finished) => Some(elapsed.as_secs_f64() * remaining as f64 / finished as f64),
+        };
+
+        StatusFileContents {
+            run_id: self.run_id.as_deref(),
+            profile: self.profile_name.as_deref(),
+            state: self.phase,
+            updated_at: Local::now().to_rfc3339(),
+            elapsed_secs: elapsed.as_secs_f64(),
+            eta_secs,
+            flaky_success: self.phase == RunPhase::Finished && self.stats.is_flaky_success(),
+            counts: StatusCounts {
+                total: self.stats.initial_run_count,
+                finished: self.stats.finished_count,
+                running: self.running.len(),
+                passed: self.stats.passed,
+                flaky: self.stats.flaky,
+                failed: self.stats.failed,
+                timed_out: self.stats.timed_out,
+                exec_failed: self.stats.exec_failed,
+                skipped: self.stats.skipped,
+            },
+            running: self
+                .running
+                .iter()
+                .map(|((binary_id, test_name), start)| RunningTest {
+                    binary_id,
+                    test_name,
+                    elapsed_secs: now.saturating_duration_since(*start).as_secs_f64(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", tag = "phase")]
+enum RunPhase {
+    #[default]
+    Starting,
+    Running,
+    Cancelling {
+        reason: &'static str,
+    },
+    Finished,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct StatusFileContents<'a> {
+    run_id: Option<&'a str>,
+    profile: Option<&'a str>,
+    state: RunPhase,
+    updated_at: String,
+    elapsed_secs: f64,
+    eta_secs: Option<f64>,
+    // Whether the run finished successfully, but some tests only passed after being retried.
+    flaky_success: bool,
+    counts: StatusCounts,
+    running: Vec<RunningTest<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct StatusCounts {
+    total: usize,
+    finished: usize,
+    running: usize,
+    passed: usize,
+    flaky: usize,
+    failed: usize,
+    timed_out: usize,
+    exec_failed: usize,
+    skipped: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct RunningTest<'a> {
+    binary_id: &'a str,
+    test_name: &'a str,
+    elapsed_secs: f64,
+}
+
+/// Atomically replaces the file at `path` with `bytes`.
+fn write_status_file(path: &Utf8Path, bytes: &[u8]) -> io::Result<()> {
+    // Write to a temporary file in the same directory, then rename it over the destination.
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_str().is_empty() => parent,
+        _ => Utf8Path::new("."),
+    };
+    crate::fs_retry::retry_transient(path, || {
+        std::fs::create_dir_all(dir)?;
+        let mut file = camino_tempfile::NamedUtf8TempFile::new_in(dir)?;
+        file.write_all(bytes)?;
+        file.persist(path).map_err(|error| error.error)?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::tempdir;
+
+    #[test]
+    fn status_file_contents() {
+        let start = Instant::now();
+        let mut state = StatusState {
+            run_id: Some("run-id".to_owned()),
+            profile_name: Some("default".to_owned()),
+            phase: RunPhase::Running,
+            start: Some(start),
+            stats: RunStats {
+                initial_run_count: 10,
+                finished_count: 4,
+                passed: 3,
+                failed: 1,
+                ..Default::default()
+            },
+            running: BTreeMap::new(),
+        };
+        state.running.insert(
+            ("my-crate".to_owned(), "tests::slow".to_owned()),
+            start + Duration::from_secs(5),
+        );
+
+        let contents = state.to_contents(start + Duration::from_secs(8));
+        let json = serde_json::to_value(&contents).unwrap();
+        assert_eq!(json["state"]["phase"], "running");
+        assert_eq!(json["counts"]["total"], 10);
+        assert_eq!(json["counts"]["finished"], 4);
+        assert_eq!(json["counts"]["running"], 1);
+        assert_eq!(json["elapsed-secs"], 8.0);
+        // 4 tests in 8 seconds, with 6 tests remaining.
+        assert_eq!(json["eta-secs"], 12.0);
+        assert_eq!(json["running"][0]["test-name"], "tests::slow");
+        assert_eq!(json["running"][0]["elapsed-secs"], 3.0);
+
+        // No estimate before any tests have finished.
+        state.stats.finished_count = 0;
+        let contents = state.to_contents(start + Duration::from_secs(8));
+        assert_eq!(contents.eta_secs, None);
+
+        state.phase = RunPhase::Cancelling { reason: "signal" };
+        let json = serde_json::to_value(state.to_contents(start)).unwrap();
+        assert_eq!(json["state"]["phase"], "cancelling");
+        assert_eq!(json["state"]["reason"], "signal");
+        assert_eq!(json["flaky-success"], false);
+    }
+
+    #[test]
+    fn status_file_flaky_success() {
+        let mut state = StatusState {
+            phase: RunPhase::Running,
+            stats: RunStats {
+                initial_run_count: 2,
+                finished_count: 2,
+                passed: 2,
+                flaky: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let json = serde_json::to_value(state.to_contents(Instant::now())).unwrap();
+        assert_eq!(
+            json["flaky-success"], false,
+            "not set until the run finishes"
+        );
+
+        state.phase = RunPhase::Finished;
+        let json = serde_json::to_value(state.to_contents(Instant::now())).unwrap();
+        assert_eq!(json["flaky-success"], true);
+
+        state.stats.passed = 1;
+        state.stats.failed = 1;
+        let json = serde_json::to_value(state.to_contents(Instant::now())).unwrap();
+        assert_eq!(json["flaky-success"], false, "not set if a test failed");
+    }
+
+    #[test]
+    fn write_status_file_replaces_contents() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("status").join("status.json");
+        let mut state = StatusState::default();
+
+        let bytes = serde_json::to_vec_pretty(&state.to_contents(Instant::now())).unwrap();
+        write_status_file(&path, &bytes).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(json["state"]["phase"], "starting");
+
+        state.phase = RunPhase::Finished;
+        let bytes = serde_json::to_vec_pretty(&state.to_contents(Instant::now())).unwrap();
+        write_status_file(&path, &bytes).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(json["state"]["phase"], "finished");
+        assert_eq!(json["eta-secs"], 0.0);
+    }
+}
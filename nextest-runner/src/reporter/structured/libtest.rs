@@ -25,11 +25,12 @@
 use crate::{
     errors::{DisplayErrorChain, FormatVersionError, FormatVersionErrorInner, WriteEventError},
     list::RustTestSuite,
-    reporter::events::{ExecutionResult, TestEvent, TestEventKind},
+    reporter::events::{ExecutionResult, SkipCounts, TestEvent, TestEventKind},
     test_output::{ChildExecutionOutput, ChildOutput, ChildSingleOutput},
 };
 use bstr::ByteSlice;
-use nextest_metadata::MismatchReason;
+use nextest_metadata::{FilterMatch, MismatchReason};
+use quick_junit::ReportUuid;
 use std::{collections::BTreeMap, fmt::Write as _};
 
 /// To support pinning the version of the output, we just use this simple enum
@@ -84,6 +85,9 @@ struct LibtestSuite<'cfg> {
     meta: &'cfg RustTestSuite<'cfg>,
     /// The accumulated duration of every test that has been executed
     total: std::time::Duration,
+    /// The estimated startup overhead of every test process that has been
+    /// executed
+    startup_overhead: std::time::Duration,
     /// Libtest outputs outputs a `started` event for every test that isn't
     /// filtered, including ignored tests, then outputs `ignored` events after
     /// all the started events, so we just mimic that with a temporary buffer
@@ -128,6 +132,8 @@ pub struct LibtestReporter<'cfg> {
     /// If true, we emit a `nextest` subobject with additional metadata in it
     /// that consumers can use for easier integration if they wish
     emit_nextest_obj: bool,
+    /// The ID of the current run, set once the run has started
+    run_id: Option<ReportUuid>,
 }
 
 impl<'cfg> LibtestReporter<'cfg> {
@@ -153,6 +159,7 @@ impl<'cfg> LibtestReporter<'cfg> {
                 _major: FormatMajorVersion::Unstable,
                 test_suites: BTreeMap::new(),
                 emit_nextest_obj,
+                run_id: None,
             });
         };
         let Some((major, minor)) = version.split_once('.') else {
@@ -214,15 +221,25 @@ impl<'cfg> LibtestReporter<'cfg> {
             _minor: minor,
             test_suites: BTreeMap::new(),
             emit_nextest_obj,
+            run_id: None,
         })
     }
 
     pub(crate) fn write_event(&mut self, event: &TestEvent<'cfg>) -> Result<(), WriteEventError> {
         let mut retries = None;
+        // The attempt number, for tests that were actually run
+        let mut attempt = None;
+        // Annotations printed by the last attempt, for tests that finished
+        let mut annotations = None;
 
         // Write the pieces of data that are the same across all events
         let (kind, eve, test_instance) = match &event.kind {
+            TestEventKind::RunStarted { run_id, .. } => {
+                self.run_id = Some(*run_id);
+                return Ok(());
+            }
             TestEventKind::TestStarted { test_instance, .. } => {
+                attempt = Some(1);
                 (KIND_TEST, EVENT_STARTED, test_instance)
             }
             TestEventKind::TestSkipped {
@@ -242,6 +259,8 @@ impl<'cfg> LibtestReporter<'cfg> {
                 if run_statuses.len() > 1 {
                     retries = Some(run_statuses.len());
                 }
+                attempt = Some(run_statuses.len());
+                annotations = Some(&run_statuses.last_status().annotations);
 
                 (
                     KIND_TEST,
@@ -249,7 +268,10 @@ impl<'cfg> LibtestReporter<'cfg> {
                         ExecutionResult::Pass | ExecutionResult::Leak => EVENT_OK,
                         ExecutionResult::Fail { .. }
                         | ExecutionResult::ExecFail
-                        | ExecutionResult::Timeout => EVENT_FAILED,
+                        | ExecutionResult::Timeout
+                        | ExecutionResult::MemoryError { .. }
+                        | ExecutionResult::CancelAcknowledged
+                        | ExecutionResult::AccessViolation => EVENT_FAILED,
                     },
                     test_instance,
                 )
@@ -309,6 +331,7 @@ impl<'cfg> LibtestReporter<'cfg> {
                     filtered,
                     meta: test_instance.suite_info,
                     total: std::time::Duration::new(0, 0),
+                    startup_overhead: std::time::Duration::new(0, 0),
                     ignore_block: None,
                     output_block: out,
                 })
@@ -349,26 +372,66 @@ impl<'cfg> LibtestReporter<'cfg> {
             out.extend_from_slice(b"\"");
         }
 
+        // These match the NEXTEST_RUN_ID, NEXTEST_TEST_ID and NEXTEST_ATTEMPT environment variables
+        // passed to the test, so consumers can associate the test's own telemetry with this event
+        if let (true, Some(run_id), Some(attempt)) = (self.emit_nextest_obj, self.run_id, attempt) {
+            write!(
+                out,
+                r#","nextest":{{"run_id":"{run_id}","test_id":"{}","attempt":{attempt}"#,
+                test_instance.id().uuid(run_id),
+            )
+            .map_err(fmt_err)?;
+
+            if let Some(annotations) = annotations.filter(|annotations| !annotations.is_empty()) {
+                out.extend_from_slice(br#","annotations":{"#);
+                for (i, (key, value)) in annotations.iter().enumerate() {
+                    if i > 0 {
+                        out.extend_from_slice(b",");
+                    }
+                    write!(
+                        out,
+                        r#""{}":"{}""#,
+                        EscapedString(key),
+                        EscapedString(value)
+                    )
+                    .map_err(fmt_err)?;
+                }
+                out.extend_from_slice(b"}");
+            }
+            out.extend_from_slice(b"}");
+        }
+
         match &event.kind {
             TestEventKind::TestFinished { run_statuses, .. } => {
                 let last_status = run_statuses.last_status();
 
                 test_suite.total += last_status.time_taken;
+                test_suite.startup_overhead += run_statuses
+                    .iter()
+                    .filter_map(|status| status.startup_time)
+                    .sum::<std::time::Duration>();
                 test_suite.running -= 1;
 
                 // libtest actually requires an additional `--report-time` flag to be
                 // passed for the exec_time information to be written. This doesn't
                 // really make sense when outputting structured output so we emit it
-                // unconditionally
+                // unconditionally. If the test reported its own time, prefer it.
                 write!(
                     out,
                     r#","exec_time":{}"#,
-                    last_status.time_taken.as_secs_f64()
+                    last_status
+                        .exec_time
+                        .unwrap_or(last_status.time_taken)
+                        .as_secs_f64()
                 )
                 .map_err(fmt_err)?;
 
                 match last_status.result {
-                    ExecutionResult::Fail { .. } | ExecutionResult::ExecFail => {
+                    ExecutionResult::Fail { .. }
+                    | ExecutionResult::ExecFail
+                    | ExecutionResult::MemoryError { .. }
+                    | ExecutionResult::CancelAcknowledged
+                    | ExecutionResult::AccessViolation => {
                         test_suite.failed += 1;
 
                         // Write the output from the test into the `stdout` (even
@@ -462,10 +525,32 @@ impl<'cfg> LibtestReporter<'cfg> {
             let binary_name = &suite_info.binary_name;
             write!(
                 out,
-                r#","nextest":{{"crate":"{crate_name}","test_binary":"{binary_name}","kind":"{}"}}"#,
+                r#","nextest":{{"crate":"{crate_name}","test_binary":"{binary_name}","kind":"{}""#,
                 suite_info.kind,
             )
             .map_err(fmt_err)?;
+
+            // Break down the tests that weren't run by why they were skipped, using the same names
+            // as the human-readable summary.
+            let mut skip_counts = SkipCounts::default();
+            for (_, test_case) in suite_info.status.test_cases() {
+                if let FilterMatch::Mismatch { reason } = test_case.filter_match {
+                    skip_counts.record(reason);
+                }
+            }
+            out.extend_from_slice(br#","skipped":{"#);
+            for (i, (reason, count)) in skip_counts.iter().enumerate() {
+                if i > 0 {
+                    out.extend_from_slice(b",");
+                }
+                write!(out, r#""{reason}":{count}"#).map_err(fmt_err)?;
+            }
+            write!(
+                out,
+                r#"}},"startup_overhead":{}}}"#,
+                test_suite.startup_overhead.as_secs_f64(),
+            )
+            .map_err(fmt_err)?;
         }
 
         out.extend_from_slice(b"}\n");
@@ -0,0 +1,497 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Analyzing the timings of a recorded run.
+//!
+//! [`TimingReport`] reads the JUnit report of a run and summarizes where the time went: the
+//! slowest tests and binaries, the longest chain of attempts that the run had to wait for, and how
+//! many tests were running at each point during the run.
+
+use super::junit_xml::{parse_time, read_report, Element};
+use crate::{
+    errors::{JunitReportError, WriteTestListError},
+    list::OutputFormat,
+    write_str::WriteStr,
+};
+use camino::Utf8Path;
+use chrono::{DateTime, FixedOffset};
+use owo_colors::{OwoColorize, Style};
+use serde::Serialize;
+use std::{collections::BTreeMap, io};
+
+/// Parameters for [`TimingReport`].
+#[derive(Clone, Copy, Debug)]
+pub struct TimingReportParams {
+    /// The number of slowest tests and binaries to report.
+    pub top: usize,
+
+    /// The number of intervals the run is split into for the parallelism graph.
+    pub intervals: usize,
+}
+
+impl Default for TimingReportParams {
+    fn default() -> Self {
+        Self {
+            top: 10,
+            intervals: 20,
+        }
+    }
+}
+
+/// A summary of the timings of a run, read from its JUnit report.
+///
+/// Setup scripts and skipped tests aren't included.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TimingReport {
+    /// The time from the first test starting to the last test finishing, in seconds.
+    ///
+    /// This is 0 if the report doesn't record when tests started.
+    pub wall_time: f64,
+
+    /// The time taken by all attempts of all tests, in seconds.
+    pub total_time: f64,
+
+    /// The average number of tests running at a time, over the wall time of the run.
+    pub mean_parallelism: f64,
+
+    /// The test whose attempts took the longest in total.
+    ///
+    /// Tests have no dependencies on each other, but a test's attempts run one after another, so
+    /// the run can't finish any sooner than this.
+    pub critical_path: Option<TimedTest>,
+
+    /// The slowest tests, slowest first.
+    pub slowest_tests: Vec<TimedTest>,
+
+    /// The binaries whose tests took the longest in total, slowest first.
+    pub slowest_binaries: Vec<TimedBinary>,
+
+    /// The average number of tests running during each interval of the run, in order.
+    pub parallelism: Vec<ParallelismInterval>,
+}
+
+impl TimingReport {
+    /// Reads the JUnit report at `path`, and summarizes its timings.
+    pub fn read(path: &Utf8Path, params: TimingReportParams) -> Result<Self, JunitReportError> {
+        let root = read_report(path)?;
+        Ok(Self::new(&root, params))
+    }
+
+    fn new(root: &Element, params: TimingReportParams) -> Self {
+        let tests = collect_tests(root);
+
+        let mut report = Self {
+            total_time: tests.iter().map(|test| test.time()).sum(),
+            ..Self::default()
+        };
+
+        let mut slowest_tests: Vec<_> = tests
+            .iter()
+            .map(|test| TimedTest {
+                binary_id: test.binary_id.to_owned(),
+                name: test.name.to_owned(),
+                time: test.time(),
+                attempts: test.attempts.len(),
+            })
+            .collect();
+        sort_by_time_desc(&mut slowest_tests, |test| test.time);
+        report.critical_path = slowest_tests.first().cloned();
+        slowest_tests.truncate(params.top);
+        report.slowest_tests = slowest_tests;
+
+        let mut binaries: BTreeMap<&str, TimedBinary> = BTreeMap::new();
+        for test in &tests {
+            let binary = binaries
+                .entry(test.binary_id)
+                .or_insert_with(|| TimedBinary {
+                    binary_id: test.binary_id.to_owned(),
+                    time: 0.0,
+                    test_count: 0,
+                });
+            binary.time += test.time();
+            binary.test_count += 1;
+        }
+        let mut slowest_binaries: Vec<_> = binaries.into_values().collect();
+        sort_by_time_desc(&mut slowest_binaries, |binary| binary.time);
+        slowest_binaries.truncate(params.top);
+        report.slowest_binaries = slowest_binaries;
+
+        // Attempts are placed on the timeline relative to the earliest one.
+        let intervals: Vec<_> = tests
+            .iter()
+            .flat_map(|test| &test.attempts)
+            .filter_map(|attempt| Some((attempt.start?, attempt.time)))
+            .collect();
+        let Some(run_start) = intervals.iter().map(|(start, _)| *start).min() else {
+            return report;
+        };
+        let intervals: Vec<(f64, f64)> = intervals
+            .into_iter()
+            .map(|(start, time)| {
+                let offset = (start - run_start).num_microseconds().unwrap_or(0) as f64 / 1e6;
+                (offset, offset + time)
+            })
+            .collect();
+
+        report.wall_time = intervals.iter().map(|(_, end)| *end).fold(0.0, f64::max);
+        if report.wall_time > 0.0 {
+            let timed_total: f64 = intervals.iter().map(|(start, end)| end - start).sum();
+            report.mean_parallelism = timed_total / report.wall_time;
+
+            let count = params.intervals.max(1);
+            let width = report.wall_time / count as f64;
+            report.parallelism = (0..count)
+                .map(|i| {
+                    let start = i as f64 * width;
+                    let end = start + width;
+                    let busy: f64 = intervals
+                        .iter()
+                        .map(|(test_start, test_end)| {
+                            (test_end.min(end) - test_start.max(start)).max(0.0)
+                        })
+                        .sum();
+                    ParallelismInterval {
+                        start,
+                        end,
+                        mean_running: busy / width,
+                    }
+                })
+                .collect();
+        }
+
+        report
+    }
+
+    /// Outputs this report in the given format.
+    pub fn write(
+        &self,
+        output_format: OutputFormat,
+        writer: &mut dyn WriteStr,
+        colorize: bool,
+    ) -> Result<(), WriteTestListError> {
+        match output_format {
+            OutputFormat::Human { verbose: _ } => self
+                .write_human(writer, colorize)
+                .map_err(WriteTestListError::Io),
+            OutputFormat::Serializable(format) => format.to_writer(self, writer),
+        }
+    }
+
+    fn write_human(&self, writer: &mut dyn WriteStr, colorize: bool) -> io::Result<()> {
+        let mut styles = Styles::default();
+        if colorize {
+            styles.colorize();
+        }
+
+        if self.slowest_tests.is_empty() {
+            writeln!(writer, "no timed tests found")?;
+            return Ok(());
+        }
+
+        writeln!(
+            writer,
+            "{}: {:.3}s, {}: {:.3}s, {}: {:.2}",
+            "wall time".style(styles.header),
+            self.wall_time,
+            "total test time".style(styles.header),
+            self.total_time,
+            "mean parallelism".style(styles.header),
+            self.mean_parallelism,
+        )?;
+        if let Some(test) = &self.critical_path {
+            writeln!(
+                writer,
+                "{}: {:.3}s ({} {})",
+                "critical path".style(styles.header),
+                test.time,
+                test.binary_id.style(styles.binary_id),
+                test.name.style(styles.test_name),
+            )?;
+        }
+
+        writeln!(
+            writer,
+            "\n{} ({}):",
+            "slowest tests".style(styles.header),
+            self.slowest_tests.len()
+        )?;
+        for test in &self.slowest_tests {
+            write!(
+                writer,
+                "    {} {}: {:.3}s",
+                test.binary_id.style(styles.binary_id),
+                test.name.style(styles.test_name),
+                test.time,
+            )?;
+            if test.attempts > 1 {
+                write!(writer, " ({} attempts)", test.attempts)?;
+            }
+            writeln!(writer)?;
+        }
+
+        writeln!(
+            writer,
+            "\n{} ({}):",
+            "slowest binaries".style(styles.header),
+            self.slowest_binaries.len()
+        )?;
+        for binary in &self.slowest_binaries {
+            writeln!(
+                writer,
+                "    {}: {:.3}s across {} {}",
+                binary.binary_id.style(styles.binary_id),
+                binary.time,
+                binary.test_count,
+                if binary.test_count == 1 {
+                    "test"
+                } else {
+                    "tests"
+                },
+            )?;
+        }
+
+        if !self.parallelism.is_empty() {
+            writeln!(writer, "\n{}:", "parallelism".style(styles.header))?;
+            let peak = self
+                .parallelism
+                .iter()
+                .map(|interval| interval.mean_running)
+                .fold(0.0, f64::max);
+            for interval in &self.parallelism {
+                let len = if peak > 0.0 {
+                    (interval.mean_running / peak * GRAPH_WIDTH as f64).round() as usize
+                } else {
+                    0
+                };
+                writeln!(
+                    writer,
+                    "    {:>9} |{:<GRAPH_WIDTH$}| {:.1}",
+                    format!("{:.3}s", interval.start),
+                    "#".repeat(len).style(styles.bar),
+                    interval.mean_running,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The width of the bars in the parallelism graph, at the busiest interval.
+const GRAPH_WIDTH: usize = 40;
+
+/// A test within a [`TimingReport`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TimedTest {
+    /// The binary ID of the test, from the `classname` attribute.
+    pub binary_id: String,
+
+    /// The name of the test.
+    pub name: String,
+
+    /// The time taken by all attempts of the test, in seconds.
+    pub time: f64,
+
+    /// The number of times the test was run.
+    pub attempts: usize,
+}
+
+/// A test binary within a [`TimingReport`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TimedBinary {
+    /// The binary ID.
+    pub binary_id: String,
+
+    /// The time taken by all attempts of all tests in the binary, in seconds.
+    pub time: f64,
+
+    /// The number of tests run in the binary.
+    pub test_count: usize,
+}
+
+/// An interval of a run within a [`TimingReport`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ParallelismInterval {
+    /// The start of the interval, in seconds since the first test started.
+    pub start: f64,
+
+    /// The end of the interval, in seconds since the first test started.
+    pub end: f64,
+
+    /// The average number of tests running during the interval.
+    pub mean_running: f64,
+}
+
+#[derive(Debug)]
+struct TestTimings<'a> {
+    binary_id: &'a str,
+    name: &'a str,
+    attempts: Vec<AttemptTiming>,
+}
+
+impl TestTimings<'_> {
+    fn time(&self) -> f64 {
+        self.attempts.iter().map(|attempt| attempt.time).sum()
+    }
+}
+
+#[derive(Debug)]
+struct AttemptTiming {
+    start: Option<DateTime<FixedOffset>>,
+    time: f64,
+}
+
+impl AttemptTiming {
+    fn new(element: &Element) -> Self {
+        Self {
+            start: element
+                .attr("timestamp")
+                .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok()),
+            time: parse_time(element.attr("time")),
+        }
+    }
+}
+
+/// The elements that record earlier attempts of a test.
+const RERUN_ELEMENTS: &[&str] = &["flakyFailure", "flakyError", "rerunFailure", "rerunError"];
+
+fn collect_tests(root: &Element) -> Vec<TestTimings<'_>> {
+    let mut tests = Vec::new();
+    for suite in root.child_elements().filter(|e| e.name == "testsuite") {
+        if suite
+            .attr("name")
+            .is_some_and(|name| name.starts_with("@setup-script:"))
+        {
+            continue;
+        }
+        for testcase in suite.child_elements().filter(|e| e.name == "testcase") {
+            if testcase.has_child("skipped") {
+                continue;
+            }
+            let mut attempts = vec![AttemptTiming::new(testcase)];
+            attempts.extend(
+                testcase
+                    .child_elements()
+                    .filter(|e| RERUN_ELEMENTS.contains(&e.name.as_str()))
+                    .map(AttemptTiming::new),
+            );
+            tests.push(TestTimings {
+                binary_id: testcase.attr("classname").unwrap_or_default(),
+                name: testcase.attr("name").unwrap_or_default(),
+                attempts,
+            });
+        }
+    }
+    tests
+}
+
+fn sort_by_time_desc<T>(items: &mut [T], time: impl Fn(&T) -> f64) {
+    items.sort_by(|a, b| time(b).total_cmp(&time(a)));
+}
+
+#[derive(Clone, Debug, Default)]
+struct Styles {
+    header: Style,
+    binary_id: Style,
+    test_name: Style,
+    bar: Style,
+}
+
+impl Styles {
+    fn colorize(&mut self) {
+        self.header = Style::new().bold();
+        self.binary_id = Style::new().magenta().bold();
+        self.test_name = Style::new().blue().bold();
+        self.bar = Style::new().green();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::junit_xml::parse_report, *};
+    use indoc::indoc;
+
+    static REPORT: &str = indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites name="nextest-run" tests="4" failures="0" errors="0">
+            <testsuite name="@setup-script:db" tests="1" disabled="0" errors="0" failures="0">
+                <testcase name="db" classname="@setup-script:db" timestamp="2024-01-01T00:00:00.000+00:00" time="5.000">
+                </testcase>
+            </testsuite>
+            <testsuite name="my-crate::basic" tests="3" disabled="0" errors="0" failures="0">
+                <testcase name="test_slow" classname="my-crate::basic" timestamp="2024-01-01T00:00:10.000+00:00" time="4.000">
+                </testcase>
+                <testcase name="test_fast" classname="my-crate::basic" timestamp="2024-01-01T00:00:10.000+00:00" time="1.000">
+                </testcase>
+                <testcase name="test_skipped" classname="my-crate::basic">
+                    <skipped/>
+                </testcase>
+            </testsuite>
+            <testsuite name="my-crate::flaky" tests="1" disabled="0" errors="0" failures="0">
+                <testcase name="test_flaky" classname="my-crate::flaky" timestamp="2024-01-01T00:00:12.000+00:00" time="2.000">
+                    <flakyFailure type="test failure" timestamp="2024-01-01T00:00:11.000+00:00" time="1.000">
+                    </flakyFailure>
+                </testcase>
+            </testsuite>
+        </testsuites>
+    "#};
+
+    #[test]
+    fn test_timing_report() {
+        let root = parse_report(Utf8Path::new("junit.xml"), REPORT).expect("report is valid");
+        let report = TimingReport::new(
+            &root,
+            TimingReportParams {
+                top: 2,
+                intervals: 4,
+            },
+        );
+
+        let test = |binary_id: &str, name: &str, time: f64, attempts: usize| TimedTest {
+            binary_id: binary_id.to_owned(),
+            name: name.to_owned(),
+            time,
+            attempts,
+        };
+        let interval = |start: f64, mean_running: f64| ParallelismInterval {
+            start,
+            end: start + 1.0,
+            mean_running,
+        };
+        assert_eq!(
+            report,
+            TimingReport {
+                wall_time: 4.0,
+                total_time: 8.0,
+                mean_parallelism: 2.0,
+                critical_path: Some(test("my-crate::basic", "test_slow", 4.0, 1)),
+                slowest_tests: vec![
+                    test("my-crate::basic", "test_slow", 4.0, 1),
+                    test("my-crate::flaky", "test_flaky", 3.0, 2),
+                ],
+                slowest_binaries: vec![
+                    TimedBinary {
+                        binary_id: "my-crate::basic".to_owned(),
+                        time: 5.0,
+                        test_count: 2,
+                    },
+                    TimedBinary {
+                        binary_id: "my-crate::flaky".to_owned(),
+                        time: 3.0,
+                        test_count: 1,
+                    },
+                ],
+                parallelism: vec![
+                    interval(0.0, 2.0),
+                    interval(1.0, 2.0),
+                    interval(2.0, 2.0),
+                    interval(3.0, 2.0),
+                ],
+            }
+        );
+    }
+}
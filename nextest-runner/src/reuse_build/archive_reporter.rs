@@ -2,7 +2,12 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::ArchiveStep;
-use crate::{helpers::plural, redact::Redactor};
+use crate::{
+    helpers::plural,
+    redact::Redactor,
+    theme::Theme,
+    warnings::{self, WarningCode},
+};
 use camino::Utf8Path;
 use owo_colors::{OwoColorize, Style};
 use std::{
@@ -35,7 +40,12 @@ impl ArchiveReporter {
 
     /// Colorizes output.
     pub fn colorize(&mut self) {
-        self.styles.colorize();
+        self.colorize_with_theme(&Theme::default());
+    }
+
+    /// Colorizes output using the given theme.
+    pub fn colorize_with_theme(&mut self, theme: &Theme) {
+        self.styles.colorize(theme);
     }
 
     /// Reports an archive event.
@@ -62,6 +72,7 @@ impl ArchiveReporter {
                 )?;
             }
             ArchiveEvent::StdlibPathError { error } => {
+                warnings::record(WarningCode::ArchiveInclude);
                 write!(writer, "{:>12} ", "Warning".style(self.styles.bold))?;
                 writeln!(
                     writer,
@@ -70,6 +81,7 @@ impl ArchiveReporter {
             }
             ArchiveEvent::ExtraPathMissing { path, warn } => {
                 if warn {
+                    warnings::record(WarningCode::ArchiveInclude);
                     write!(writer, "{:>12} ", "Warning".style(self.styles.warning))?;
                 } else if self.verbose {
                     write!(writer, "{:>12} ", "Skipped".style(self.styles.skipped))?;
@@ -84,6 +96,7 @@ impl ArchiveReporter {
                 )?;
             }
             ArchiveEvent::DirectoryAtDepthZero { path } => {
+                warnings::record(WarningCode::ArchiveInclude);
                 write!(writer, "{:>12} ", "Warning".style(self.styles.warning))?;
                 writeln!(
                     writer,
@@ -91,6 +104,34 @@ impl ArchiveReporter {
                     self.redactor.redact_path(path).style(self.styles.bold),
                 )?;
             }
+            ArchiveEvent::ExtraPathArchived {
+                path,
+                file_count,
+                excluded_count,
+            } => {
+                if !self.verbose {
+                    return Ok(());
+                }
+
+                write!(writer, "{:>12} ", "Included".style(self.styles.success))?;
+                write!(
+                    writer,
+                    "{} {} from extra path `{}`",
+                    self.redactor
+                        .redact_file_count(file_count)
+                        .style(self.styles.bold),
+                    plural::files_str(file_count),
+                    self.redactor.redact_path(path).style(self.styles.bold),
+                )?;
+                if excluded_count > 0 {
+                    write!(
+                        writer,
+                        " ({} excluded)",
+                        excluded_count.style(self.styles.bold)
+                    )?;
+                }
+                writeln!(writer)?;
+            }
             ArchiveEvent::RecursionDepthExceeded {
                 step,
                 path,
@@ -98,6 +139,7 @@ impl ArchiveReporter {
                 warn,
             } => {
                 if warn {
+                    warnings::record(WarningCode::ArchiveInclude);
                     write!(writer, "{:>12} ", "Warning".style(self.styles.warning))?;
                 } else if self.verbose {
                     write!(writer, "{:>12} ", "Skipped".style(self.styles.skipped))?;
@@ -112,6 +154,7 @@ impl ArchiveReporter {
                 )?;
             }
             ArchiveEvent::UnknownFileType { step, path } => {
+                warnings::record(WarningCode::ArchiveInclude);
                 write!(writer, "{:>12} ", "Warning".style(self.styles.warning))?;
                 writeln!(
                     writer,
@@ -121,6 +164,7 @@ impl ArchiveReporter {
                 )?;
             }
             ArchiveEvent::LinkedPathNotFound { path, requested_by } => {
+                warnings::record(WarningCode::ArchiveInclude);
                 write!(writer, "{:>12} ", "Warning".style(self.styles.warning))?;
                 writeln!(
                     writer,
@@ -288,11 +332,11 @@ struct Styles {
 }
 
 impl Styles {
-    fn colorize(&mut self) {
-        self.bold = Style::new().bold();
-        self.success = Style::new().green().bold();
-        self.warning = Style::new().yellow().bold();
-        self.skipped = Style::new().bold();
+    fn colorize(&mut self, theme: &Theme) {
+        self.bold = theme.emphasis();
+        self.success = theme.pass();
+        self.warning = theme.skip();
+        self.skipped = theme.emphasis();
     }
 }
 
@@ -332,6 +376,18 @@ pub enum ArchiveEvent<'a> {
         path: &'a Utf8Path,
     },
 
+    /// An extra path was archived.
+    ExtraPathArchived {
+        /// The path, which may contain glob patterns.
+        path: &'a Utf8Path,
+
+        /// The number of files archived from the path.
+        file_count: usize,
+
+        /// The number of files and directories excluded by `exclude` patterns.
+        excluded_count: usize,
+    },
+
     /// While performing the archive, the recursion depth was exceeded.
     RecursionDepthExceeded {
         /// The current step in the archive process.
@@ -1,16 +1,21 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{ArchiveCounts, ArchiveEvent, BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME};
+use super::{
+    ArchiveCounts, ArchiveEvent, SourceFingerprint, BINARIES_METADATA_FILE_NAME,
+    CARGO_METADATA_FILE_NAME, SOURCE_FINGERPRINT_FILE_NAME,
+};
 use crate::{
     config::{
-        get_num_cpus, ArchiveConfig, ArchiveIncludeOnMissing, EvaluatableProfile, RecursionDepth,
+        get_num_cpus, ArchiveConfig, ArchiveIncludeFilter, ArchiveIncludeOnMissing,
+        EvaluatableProfile, RecursionDepth,
     },
     errors::{ArchiveCreateError, UnknownArchiveFormat},
     helpers::{convert_rel_path_to_forward_slash, rel_path_join},
     list::{BinaryList, OutputFormat, SerializableFormat},
     redact::Redactor,
     reuse_build::{PathMapper, LIBDIRS_BASE_DIR},
+    warnings::{self, WarningCode},
 };
 use atomicwrites::{AtomicFile, OverwriteBehavior};
 use camino::{Utf8Path, Utf8PathBuf};
@@ -22,7 +27,7 @@ use std::{
     io::{self, BufWriter, Write},
     time::{Instant, SystemTime},
 };
-use tracing::{debug, trace, warn};
+use tracing::{debug, trace};
 use zstd::Encoder;
 
 /// Archive format.
@@ -55,12 +60,14 @@ impl ArchiveFormat {
 
 /// Archives test binaries along with metadata to the given file.
 ///
-/// The output file is a Zstandard-compressed tarball (`.tar.zst`).
+/// The output file is a Zstandard-compressed tarball (`.tar.zst`). `source_fingerprint` is stored in
+/// the archive so that runs against a different source tree can be detected.
 #[expect(clippy::too_many_arguments)]
 pub fn archive_to_file<'a, F>(
     profile: EvaluatableProfile<'a>,
     binary_list: &'a BinaryList,
     cargo_metadata: &'a str,
+    source_fingerprint: &'a SourceFingerprint,
     graph: &'a PackageGraph,
     path_mapper: &'a PathMapper,
     format: ArchiveFormat,
@@ -114,6 +121,7 @@ where
                 config,
                 binary_list,
                 cargo_metadata,
+                source_fingerprint,
                 graph,
                 path_mapper,
                 host_stdlib,
@@ -183,6 +191,7 @@ where
 struct Archiver<'a, W: Write> {
     binary_list: &'a BinaryList,
     cargo_metadata: &'a str,
+    source_fingerprint: &'a SourceFingerprint,
     graph: &'a PackageGraph,
     path_mapper: &'a PathMapper,
     host_stdlib: Option<Utf8PathBuf>,
@@ -200,6 +209,7 @@ impl<'a, W: Write> Archiver<'a, W> {
         config: &'a ArchiveConfig,
         binary_list: &'a BinaryList,
         cargo_metadata: &'a str,
+        source_fingerprint: &'a SourceFingerprint,
         graph: &'a PackageGraph,
         path_mapper: &'a PathMapper,
         host_stdlib: Option<Utf8PathBuf>,
@@ -235,6 +245,7 @@ impl<'a, W: Write> Archiver<'a, W> {
         Ok(Self {
             binary_list,
             cargo_metadata,
+            source_fingerprint,
             graph,
             path_mapper,
             host_stdlib,
@@ -261,6 +272,10 @@ impl<'a, W: Write> Archiver<'a, W> {
 
         self.append_from_memory(CARGO_METADATA_FILE_NAME, self.cargo_metadata)?;
 
+        let source_fingerprint = serde_json::to_string_pretty(self.source_fingerprint)
+            .expect("source fingerprint is serializable");
+        self.append_from_memory(SOURCE_FINGERPRINT_FILE_NAME, &source_fingerprint)?;
+
         let target_dir = &self.binary_list.rust_build_meta.target_directory;
 
         fn filter_map_err<T>(result: io::Result<()>) -> Option<Result<T, ArchiveCreateError>> {
@@ -276,7 +291,7 @@ impl<'a, W: Write> Archiver<'a, W> {
             .include
             .iter()
             .filter_map(|include| {
-                let src_path = include.join_path(target_dir);
+                let src_path = include.join_base_path(target_dir);
                 let src_path = self.path_mapper.map_binary(src_path);
 
                 match src_path.symlink_metadata() {
@@ -394,12 +409,19 @@ impl<'a, W: Write> Archiver<'a, W> {
                 &rel_path,
                 RecursionDepth::Finite(1),
                 false,
+                None,
                 callback,
             )?;
 
             // Archive build script output in order to set environment variables from there
             let Some(out_dir_parent) = build_script_out_dir.parent() else {
-                warn!("could not determine parent directory of output directory {build_script_out_dir}");
+                warnings::emit(
+                    WarningCode::BuildScriptOutput,
+                    format_args!(
+                        "could not determine parent directory of output directory \
+                         {build_script_out_dir}"
+                    ),
+                );
                 continue;
             };
             let out_file_path = out_dir_parent.join("output");
@@ -464,25 +486,62 @@ impl<'a, W: Write> Archiver<'a, W> {
                 &rel_path,
                 RecursionDepth::Finite(1),
                 false,
+                None,
                 callback,
             )?;
         }
 
         // Also include extra paths.
         for (include, src_path) in archive_include_paths {
-            let rel_path = include.join_path(Utf8Path::new("target"));
+            let rel_path = include.join_base_path(Utf8Path::new("target"));
             let rel_path = convert_rel_path_to_forward_slash(&rel_path);
 
             if src_path.exists() {
-                self.append_path_recursive(
+                let filter = include.filter();
+                let counts = self.append_path_recursive(
                     ArchiveStep::ExtraPaths,
                     &src_path,
                     &rel_path,
                     include.depth(),
                     // Warn if the implicit depth limit for these paths is in use.
                     true,
+                    Some(&filter),
                     callback,
                 )?;
+
+                let path = self.path_mapper.map_binary(include.join_path(target_dir));
+                callback(ArchiveEvent::ExtraPathArchived {
+                    path: &path,
+                    file_count: counts.file_count,
+                    excluded_count: counts.excluded_count,
+                })
+                .map_err(ArchiveCreateError::ReporterIo)?;
+
+                // A glob that matches nothing is treated the same as a missing path.
+                if include.is_glob() && counts.file_count == 0 {
+                    match include.on_missing() {
+                        ArchiveIncludeOnMissing::Error => {
+                            return Err(ArchiveCreateError::MissingExtraPath {
+                                path,
+                                redactor: self.redactor.clone(),
+                            });
+                        }
+                        ArchiveIncludeOnMissing::Warn => {
+                            callback(ArchiveEvent::ExtraPathMissing {
+                                path: &path,
+                                warn: true,
+                            })
+                            .map_err(ArchiveCreateError::ReporterIo)?;
+                        }
+                        ArchiveIncludeOnMissing::Ignore => {
+                            callback(ArchiveEvent::ExtraPathMissing {
+                                path: &path,
+                                warn: false,
+                            })
+                            .map_err(ArchiveCreateError::ReporterIo)?;
+                        }
+                    }
+                }
             }
         }
 
@@ -542,6 +601,11 @@ impl<'a, W: Write> Archiver<'a, W> {
         Ok(())
     }
 
+    /// Appends `src_path` to the archive as `rel_path`, recursing into directories up to `limit`.
+    ///
+    /// Entries are visited in sorted order so that archives are deterministic. If `filter` is
+    /// specified, only the files it matches are archived.
+    #[expect(clippy::too_many_arguments)]
     fn append_path_recursive<F>(
         &mut self,
         step: ArchiveStep,
@@ -549,11 +613,14 @@ impl<'a, W: Write> Archiver<'a, W> {
         rel_path: &Utf8Path,
         limit: RecursionDepth,
         warn_on_exceed_depth: bool,
+        filter: Option<&ArchiveIncludeFilter>,
         callback: &mut F,
-    ) -> Result<(), ArchiveCreateError>
+    ) -> Result<AppendCounts, ArchiveCreateError>
     where
         F: for<'b> FnMut(ArchiveEvent<'b>) -> io::Result<()>,
     {
+        let mut counts = AppendCounts::default();
+
         // Within the loop, the metadata will be part of the directory entry.
         let metadata =
             fs::symlink_metadata(src_path).map_err(|error| ArchiveCreateError::InputFileRead {
@@ -573,6 +640,19 @@ impl<'a, W: Write> Archiver<'a, W> {
                  (depth: {depth})",
             );
 
+            // Filters match against paths relative to the target directory.
+            let filter_path = rel_path
+                .strip_prefix("target")
+                .expect("archived paths are always within the target directory");
+            if filter.is_some_and(|filter| filter.is_excluded(filter_path)) {
+                debug!(
+                    target: "nextest-runner",
+                    "excluding `{src_path}` from archive",
+                );
+                counts.excluded_count += 1;
+                continue;
+            }
+
             if metadata.is_dir() {
                 // Check the recursion limit.
                 if depth.is_zero() {
@@ -600,11 +680,15 @@ impl<'a, W: Write> Archiver<'a, W> {
                         error,
                     }
                 })?;
-                for entry in entries {
-                    let entry = entry.map_err(|error| ArchiveCreateError::DirEntryRead {
+                let mut entries = entries.collect::<Result<Vec<_>, _>>().map_err(|error| {
+                    ArchiveCreateError::DirEntryRead {
                         path: src_path.to_owned(),
                         error,
-                    })?;
+                    }
+                })?;
+                // Entries are popped off the stack in reverse order, so sort them in reverse.
+                entries.sort_by(|a, b| b.file_name().cmp(a.file_name()));
+                for entry in entries {
                     let metadata =
                         entry
                             .metadata()
@@ -623,7 +707,10 @@ impl<'a, W: Write> Archiver<'a, W> {
                     ));
                 }
             } else if metadata.is_file() || metadata.is_symlink() {
-                self.append_file(step, &src_path, &rel_path)?;
+                if filter.map_or(true, |filter| filter.is_match(filter_path)) {
+                    self.append_file(step, &src_path, &rel_path)?;
+                    counts.file_count += 1;
+                }
             } else {
                 // Don't archive other kinds of files.
                 callback(ArchiveEvent::UnknownFileType {
@@ -634,7 +721,7 @@ impl<'a, W: Write> Archiver<'a, W> {
             }
         }
 
-        Ok(())
+        Ok(counts)
     }
 
     fn append_file(
@@ -663,6 +750,16 @@ impl<'a, W: Write> Archiver<'a, W> {
     }
 }
 
+/// Counts of files visited by [`Archiver::append_path_recursive`].
+#[derive(Clone, Copy, Debug, Default)]
+struct AppendCounts {
+    /// The number of files archived.
+    file_count: usize,
+
+    /// The number of files and directories excluded.
+    excluded_count: usize,
+}
+
 fn find_std(libdir: &Utf8Path) -> io::Result<Utf8PathBuf> {
     for path in libdir.read_dir_utf8()? {
         let path = path?;
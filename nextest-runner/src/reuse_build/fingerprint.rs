@@ -0,0 +1,310 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::config::NextestConfig;
+use camino::Utf8Path;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::{debug, trace};
+use xxhash_rust::xxh64::xxh64;
+
+/// A fingerprint of the source tree an archive was built from.
+///
+/// Stored in archives so that a run against a different checkout of the workspace can be
+/// detected. Each component is optional: it's absent if it couldn't be determined, for example if
+/// the workspace isn't in a Git repository, or if the archive was created by an older version of
+/// nextest.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SourceFingerprint {
+    /// The commit SHA of `HEAD`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+
+    /// Whether tracked files had uncommitted changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dirty: Option<bool>,
+
+    /// A hash of the workspace's `Cargo.lock`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cargo_lock_hash: Option<String>,
+
+    /// A hash of the nextest config file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_hash: Option<String>,
+
+    /// The version of nextest that created the archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nextest_version: Option<String>,
+}
+
+impl SourceFingerprint {
+    /// Computes the fingerprint for the workspace at `workspace_root`.
+    ///
+    /// `config_file` is the nextest config file in use, defaulting to
+    /// [`NextestConfig::CONFIG_PATH`] within the workspace. The nextest version is not set.
+    pub fn compute(workspace_root: &Utf8Path, config_file: Option<&Utf8Path>) -> Self {
+        let commit = git_output(workspace_root, &["rev-parse", "HEAD"])
+            .map(|stdout| stdout.trim().to_owned());
+        let dirty = git_output(
+            workspace_root,
+            &["status", "--porcelain", "--untracked-files=no"],
+        )
+        .map(|stdout| !stdout.trim().is_empty());
+
+        let cargo_lock_hash = hash_file(&workspace_root.join("Cargo.lock"));
+        let config_hash = match config_file {
+            Some(config_file) => hash_file(config_file),
+            None => hash_file(&workspace_root.join(NextestConfig::CONFIG_PATH)),
+        };
+
+        Self {
+            commit,
+            dirty,
+            cargo_lock_hash,
+            config_hash,
+            nextest_version: None,
+        }
+    }
+
+    /// Sets the version of nextest that created the archive.
+    pub fn with_nextest_version(mut self, version: &Version) -> Self {
+        self.nextest_version = Some(version.to_string());
+        self
+    }
+
+    /// Compares `self`, the fingerprint recorded at archive time, to `current`.
+    ///
+    /// Components that are missing on either side are not compared.
+    pub fn mismatches(&self, current: &Self) -> Vec<SourceFingerprintMismatch> {
+        let mut mismatches = Vec::new();
+        if let (Some(archived), Some(current)) = (&self.commit, &current.commit) {
+            if archived != current {
+                mismatches.push(SourceFingerprintMismatch::Commit {
+                    archived: archived.clone(),
+                    current: current.clone(),
+                });
+            }
+        }
+        if let (Some(archived), Some(current)) = (self.dirty, current.dirty) {
+            // An archive built from a dirty tree can't be matched to any checkout, so report it
+            // whenever either side is dirty.
+            if archived || current {
+                mismatches.push(SourceFingerprintMismatch::Dirty { archived, current });
+            }
+        }
+        if let (Some(archived), Some(current)) = (&self.cargo_lock_hash, &current.cargo_lock_hash) {
+            if archived != current {
+                mismatches.push(SourceFingerprintMismatch::CargoLock);
+            }
+        }
+        if let (Some(archived), Some(current)) = (&self.config_hash, &current.config_hash) {
+            if archived != current {
+                mismatches.push(SourceFingerprintMismatch::Config);
+            }
+        }
+        mismatches
+    }
+}
+
+/// A difference between an archived [`SourceFingerprint`] and the current workspace.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SourceFingerprintMismatch {
+    /// The commit differs.
+    Commit {
+        /// The commit the archive was built from.
+        archived: String,
+
+        /// The commit currently checked out.
+        current: String,
+    },
+
+    /// The archive or the current workspace had uncommitted changes.
+    Dirty {
+        /// Whether the archive was built from a dirty tree.
+        archived: bool,
+
+        /// Whether the current workspace is dirty.
+        current: bool,
+    },
+
+    /// `Cargo.lock` differs.
+    CargoLock,
+
+    /// The nextest config file differs.
+    Config,
+}
+
+impl fmt::Display for SourceFingerprintMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Commit { archived, current } => {
+                write!(
+                    f,
+                    "archive was built from commit {archived}, but {current} is checked out"
+                )
+            }
+            Self::Dirty { archived, current } => {
+                let describe = |dirty: bool| if dirty { "dirty" } else { "clean" };
+                write!(
+                    f,
+                    "archive was built from a {} tree, and the current tree is {}",
+                    describe(*archived),
+                    describe(*current),
+                )
+            }
+            Self::CargoLock => write!(
+                f,
+                "Cargo.lock differs from the one the archive was built with"
+            ),
+            Self::Config => write!(
+                f,
+                "nextest config differs from the one the archive was built with"
+            ),
+        }
+    }
+}
+
+fn hash_file(path: &Utf8Path) -> Option<String> {
+    match std::fs::read(path) {
+        Ok(contents) => Some(format!("{:016x}", xxh64(&contents, 0))),
+        Err(error) => {
+            debug!("unable to read {path} for source fingerprint: {error}");
+            None
+        }
+    }
+}
+
+fn git_output(workspace_root: &Utf8Path, args: &[&str]) -> Option<String> {
+    let expression = duct::cmd(
+        "git",
+        ["-C", workspace_root.as_str()].iter().chain(args).copied(),
+    );
+    trace!("Executing command: {:?}", expression);
+    let output = match expression
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+    {
+        Ok(output) => output,
+        Err(error) => {
+            debug!("failed to run git for source fingerprint: {error}");
+            return None;
+        }
+    };
+    if !output.status.success() {
+        debug!(
+            "git {} failed with {}: {}",
+            args.join(" "),
+            output.status,
+            String::from_utf8_lossy(&output.stderr),
+        );
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_mismatches() {
+        let archived = SourceFingerprint {
+            commit: Some("abc".to_owned()),
+            dirty: Some(false),
+            cargo_lock_hash: Some("0123".to_owned()),
+            config_hash: Some("89ab".to_owned()),
+            nextest_version: Some("0.9.89".to_owned()),
+        };
+
+        assert_eq!(archived.mismatches(&archived), vec![], "identical");
+        assert_eq!(
+            archived.mismatches(&SourceFingerprint::default()),
+            vec![],
+            "missing components are not compared"
+        );
+
+        let current = SourceFingerprint {
+            commit: Some("def".to_owned()),
+            dirty: Some(true),
+            cargo_lock_hash: Some("4567".to_owned()),
+            config_hash: Some("cdef".to_owned()),
+            // The nextest version is informational, and isn't compared.
+            nextest_version: Some("0.9.90".to_owned()),
+        };
+        assert_eq!(
+            archived.mismatches(&current),
+            vec![
+                SourceFingerprintMismatch::Commit {
+                    archived: "abc".to_owned(),
+                    current: "def".to_owned(),
+                },
+                SourceFingerprintMismatch::Dirty {
+                    archived: false,
+                    current: true,
+                },
+                SourceFingerprintMismatch::CargoLock,
+                SourceFingerprintMismatch::Config,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_config_hash() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        let workspace_root = dir.path();
+
+        let fingerprint = SourceFingerprint::compute(workspace_root, None);
+        assert_eq!(fingerprint.config_hash, None, "no config file");
+        assert_eq!(fingerprint.nextest_version, None, "version is not set");
+
+        let default_config = workspace_root.join(NextestConfig::CONFIG_PATH);
+        std::fs::create_dir_all(default_config.parent().unwrap()).unwrap();
+        std::fs::write(&default_config, "[profile.default]\nretries = 1\n").unwrap();
+        let default_hash = SourceFingerprint::compute(workspace_root, None).config_hash;
+        assert!(default_hash.is_some(), "default config file is hashed");
+
+        let custom_config = workspace_root.join("custom.toml");
+        std::fs::write(&custom_config, "[profile.default]\nretries = 2\n").unwrap();
+        let custom_hash =
+            SourceFingerprint::compute(workspace_root, Some(&custom_config)).config_hash;
+        assert!(custom_hash.is_some(), "custom config file is hashed");
+        assert_ne!(
+            default_hash, custom_hash,
+            "the custom config file is used instead of the default one"
+        );
+
+        let missing_config = workspace_root.join("missing.toml");
+        assert_eq!(
+            SourceFingerprint::compute(workspace_root, Some(&missing_config)).config_hash,
+            None,
+            "missing config file is not hashed"
+        );
+
+        let fingerprint = SourceFingerprint::compute(workspace_root, None)
+            .with_nextest_version(&Version::new(0, 9, 89));
+        assert_eq!(fingerprint.nextest_version.as_deref(), Some("0.9.89"));
+    }
+
+    #[test]
+    fn test_fingerprint_deserialize() {
+        // Archives created by older versions of nextest don't record the config hash or version.
+        let fingerprint: SourceFingerprint =
+            serde_json::from_str(r#"{ "commit": "abc", "dirty": false }"#)
+                .expect("old fingerprint deserialized");
+        assert_eq!(
+            fingerprint,
+            SourceFingerprint {
+                commit: Some("abc".to_owned()),
+                dirty: Some(false),
+                ..Default::default()
+            }
+        );
+
+        serde_json::from_str::<SourceFingerprint>(r#"{ "config-hash": 123 }"#)
+            .expect_err("config hash must be a string");
+    }
+}
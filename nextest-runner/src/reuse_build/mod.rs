@@ -23,10 +23,12 @@ use std::{fmt, fs, io, sync::Arc};
 
 mod archive_reporter;
 mod archiver;
+mod fingerprint;
 mod unarchiver;
 
 pub use archive_reporter::*;
 pub use archiver::*;
+pub use fingerprint::*;
 pub use unarchiver::*;
 
 /// The name of the file in which Cargo metadata is stored.
@@ -35,6 +37,9 @@ pub const CARGO_METADATA_FILE_NAME: &str = "target/nextest/cargo-metadata.json";
 /// The name of the file in which binaries metadata is stored.
 pub const BINARIES_METADATA_FILE_NAME: &str = "target/nextest/binaries-metadata.json";
 
+/// The name of the file in which the source fingerprint is stored.
+pub const SOURCE_FINGERPRINT_FILE_NAME: &str = "target/nextest/source-fingerprint.json";
+
 /// The name of the directory in which libdirs are stored.
 pub const LIBDIRS_BASE_DIR: &str = "target/nextest/libdirs";
 
@@ -50,6 +55,9 @@ pub struct ReuseBuildInfo {
     /// A remapper for libdirs.
     pub libdir_mapper: LibdirMapper,
 
+    /// The fingerprint of the source tree the archive was built from, if available.
+    pub source_fingerprint: Option<SourceFingerprint>,
+
     /// Optional temporary directory used for cleanup.
     _temp_dir: Option<Utf8TempDir>,
 }
@@ -65,6 +73,7 @@ impl ReuseBuildInfo {
             cargo_metadata,
             binaries_metadata,
             libdir_mapper: LibdirMapper::default(),
+            source_fingerprint: None,
             _temp_dir: None,
         }
     }
@@ -91,6 +100,7 @@ impl ReuseBuildInfo {
             cargo_metadata_json,
             graph,
             libdir_mapper,
+            source_fingerprint,
         } = unarchiver.extract(dest, callback)?;
 
         let cargo_metadata = MetadataWithRemap {
@@ -106,6 +116,7 @@ impl ReuseBuildInfo {
             cargo_metadata: Some(cargo_metadata),
             binaries_metadata: Some(binaries_metadata),
             libdir_mapper,
+            source_fingerprint,
             _temp_dir: temp_dir,
         })
     }
@@ -120,6 +131,14 @@ impl ReuseBuildInfo {
         self.binaries_metadata.as_ref().map(|m| &m.metadata)
     }
 
+    /// Returns the fingerprint of the source tree the archive was built from.
+    ///
+    /// This is `None` if the build isn't being reused from an archive, or if the archive was
+    /// created by an older version of nextest.
+    pub fn source_fingerprint(&self) -> Option<&SourceFingerprint> {
+        self.source_fingerprint.as_ref()
+    }
+
     /// Returns true if any component of the build is being reused.
     #[inline]
     pub fn is_active(&self) -> bool {
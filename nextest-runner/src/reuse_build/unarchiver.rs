@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    ArchiveEvent, ArchiveFormat, LibdirMapper, PlatformLibdirMapper, BINARIES_METADATA_FILE_NAME,
-    CARGO_METADATA_FILE_NAME, LIBDIRS_BASE_DIR,
+    ArchiveEvent, ArchiveFormat, LibdirMapper, PlatformLibdirMapper, SourceFingerprint,
+    BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME, LIBDIRS_BASE_DIR,
+    SOURCE_FINGERPRINT_FILE_NAME,
 };
 use crate::{
     errors::{ArchiveExtractError, ArchiveReadError},
@@ -92,10 +93,12 @@ impl<'a> Unarchiver<'a> {
         // Will be filled out by the for loop below.
         let mut binary_list = None;
         let mut graph_data = None;
+        let mut source_fingerprint = None;
         let mut host_libdir = PlatformLibdirMapper::Unavailable;
         let mut target_libdir = PlatformLibdirMapper::Unavailable;
         let binaries_metadata_path = Utf8Path::new(BINARIES_METADATA_FILE_NAME);
         let cargo_metadata_path = Utf8Path::new(CARGO_METADATA_FILE_NAME);
+        let source_fingerprint_path = Utf8Path::new(SOURCE_FINGERPRINT_FILE_NAME);
 
         let mut file_count = 0;
 
@@ -171,6 +174,19 @@ impl<'a> Unarchiver<'a> {
                 })?;
                 graph_data = Some((json, package_graph));
                 continue;
+            } else if path == source_fingerprint_path {
+                // Archives created by older versions of nextest don't have this file, so it's
+                // optional.
+                let json = fs::read_to_string(dest_dir.join(source_fingerprint_path))
+                    .map_err(|error| ArchiveExtractError::WriteFile { path, error })?;
+                let fingerprint: SourceFingerprint =
+                    serde_json::from_str(&json).map_err(|error| {
+                        ArchiveExtractError::Read(ArchiveReadError::MetadataDeserializeError {
+                            path: source_fingerprint_path,
+                            error,
+                        })
+                    })?;
+                source_fingerprint = Some(fingerprint);
             } else if let Ok(suffix) = path.strip_prefix(LIBDIRS_BASE_DIR) {
                 if suffix.starts_with("host") {
                     host_libdir = PlatformLibdirMapper::Path(dest_dir.join(
@@ -223,6 +239,7 @@ impl<'a> Unarchiver<'a> {
                 host: host_libdir,
                 target: target_libdir,
             },
+            source_fingerprint,
         })
     }
 }
@@ -246,6 +263,9 @@ pub(crate) struct ExtractInfo {
 
     /// A remapper for the Rust libdir.
     pub libdir_mapper: LibdirMapper,
+
+    /// The source fingerprint, if the archive has one.
+    pub source_fingerprint: Option<SourceFingerprint>,
 }
 
 struct ArchiveReader<'a> {
@@ -0,0 +1,393 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Auditing tests' network and file system access, set via `access-audit`.
+//!
+//! On Linux, audited tests are run under `strace`, which logs file system and network system
+//! calls to a temporary file. Once the test exits, the log is summarized into an
+//! [`AccessReport`], which is written out to the store directory as JSON:
+//!
+//! * `network`: the sockets the test connected or sent data to, as `address:port` for IP
+//!   destinations and `unix:path` for Unix domain sockets
+//! * `files`: the paths the test successfully opened or modified, mapped to `read` or `write`
+//!
+//! Paths are recorded as the test passed them in, so relative paths are relative to the test's
+//! working directory at the time.
+
+use crate::{
+    config::AccessPolicy, errors::SafeNamesManifestWriteError, list::TestInstanceId,
+    safe_names::SafeNamesDir,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempPath;
+use quick_junit::ReportUuid;
+use serde::{Serialize, Serializer};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt, io,
+    net::{IpAddr, SocketAddr},
+};
+use tracing::warn;
+
+/// The directory within the store that access reports are written to.
+const ACCESS_REPORTS_DIR: &str = "access-reports";
+
+/// The system calls that are logged. Only the ones handled by
+/// [`AccessReport::add_syscall`] are recorded.
+const STRACE_TRACE: &str = "trace=%file,%network";
+
+/// Runs a single test attempt under `strace`.
+#[derive(Debug)]
+pub(super) struct AccessAuditor {
+    log_path: Utf8TempPath,
+    command_prefix: Vec<String>,
+}
+
+impl AccessAuditor {
+    /// Creates a new auditor, or returns `None` if auditing isn't supported on this platform.
+    pub(super) fn new() -> io::Result<Option<Self>> {
+        if !cfg!(target_os = "linux") {
+            static WARN_ONCE: std::sync::Once = std::sync::Once::new();
+            WARN_ONCE.call_once(|| {
+                warn!("access-audit is only supported on Linux, running tests without it")
+            });
+            return Ok(None);
+        }
+
+        let log_path = camino_tempfile::Builder::new()
+            .prefix("nextest-access-audit")
+            .tempfile()?
+            .into_temp_path();
+        let command_prefix = [
+            "strace",
+            // Follow child processes and threads.
+            "-f",
+            // Don't log process attach, detach and exit messages.
+            "-qq",
+            "-e",
+            "signal=none",
+            "-e",
+            STRACE_TRACE,
+            // Paths are logged as strings, so make sure they aren't truncated.
+            "-s",
+            "4096",
+            "-o",
+            log_path.as_str(),
+            "--",
+        ]
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+
+        Ok(Some(Self {
+            log_path,
+            command_prefix,
+        }))
+    }
+
+    /// Returns the program and arguments to insert before the test command.
+    pub(super) fn command_prefix(&self) -> &[String] {
+        &self.command_prefix
+    }
+
+    /// Reads the log written while the test ran, and summarizes it.
+    pub(super) fn finish(self) -> io::Result<AccessReport> {
+        let log = std::fs::read(&self.log_path)?;
+        Ok(AccessReport::parse(&String::from_utf8_lossy(&log)))
+    }
+}
+
+/// Returns the directory access reports are written to for a run.
+pub(super) fn access_reports_dir(store_dir: &Utf8Path, run_id: ReportUuid) -> SafeNamesDir {
+    SafeNamesDir::new(store_dir.join(ACCESS_REPORTS_DIR).join(run_id.to_string()))
+}
+
+/// Returns the path an attempt's access report is written to.
+pub(super) fn access_report_path(
+    dir: &SafeNamesDir,
+    test_id: TestInstanceId<'_>,
+    attempt: usize,
+) -> Result<Utf8PathBuf, SafeNamesManifestWriteError> {
+    Ok(dir
+        .path_for(test_id)?
+        .join(format!("attempt-{attempt}.json")))
+}
+
+/// The network destinations and files a test attempt accessed.
+#[derive(Debug, Default, Eq, PartialEq, Serialize)]
+pub(super) struct AccessReport {
+    network: BTreeSet<NetworkDestination>,
+    files: BTreeMap<String, FileAccess>,
+}
+
+impl AccessReport {
+    fn parse(log: &str) -> Self {
+        let mut report = Self::default();
+        // With -f, system calls interrupted by other threads or processes are split across two
+        // lines: "openat(AT_FDCWD, "foo", <unfinished ...>" and later "<... openat resumed>) = 3".
+        let mut unfinished: HashMap<&str, String> = HashMap::new();
+        for line in log.lines() {
+            let (pid, call) = split_pid(line);
+            if let Some(head) = call.strip_suffix(" <unfinished ...>") {
+                unfinished.insert(pid, head.to_owned());
+                continue;
+            }
+            let call = match call.strip_prefix("<... ") {
+                Some(resumed) => {
+                    let Some((_, tail)) = resumed.split_once(" resumed>") else {
+                        continue;
+                    };
+                    let Some(mut head) = unfinished.remove(pid) else {
+                        continue;
+                    };
+                    head.push_str(tail);
+                    Cow::Owned(head)
+                }
+                None => Cow::Borrowed(call),
+            };
+            report.add_syscall(&call);
+        }
+        report
+    }
+
+    fn add_syscall(&mut self, call: &str) {
+        let Some((name, rest)) = call.split_once('(') else {
+            return;
+        };
+        let Some((args, result)) = rest.rsplit_once(") = ") else {
+            return;
+        };
+        let succeeded = !result.starts_with('-');
+
+        match name {
+            "open" | "openat" | "openat2" => {
+                if !succeeded {
+                    return;
+                }
+                let access = if ["O_WRONLY", "O_RDWR", "O_CREAT", "O_TRUNC"]
+                    .iter()
+                    .any(|flag| args.contains(flag))
+                {
+                    FileAccess::Write
+                } else {
+                    FileAccess::Read
+                };
+                if let Some(path) = string_args(args).into_iter().next() {
+                    self.add_file(path, access);
+                }
+            }
+            "creat" | "mkdir" | "mkdirat" | "rmdir" | "unlink" | "unlinkat" | "truncate"
+            | "rename" | "renameat" | "renameat2" | "link" | "linkat" | "symlink" | "symlinkat" => {
+                if !succeeded {
+                    return;
+                }
+                for path in string_args(args) {
+                    self.add_file(path, FileAccess::Write);
+                }
+            }
+            "connect" | "sendto" | "sendmsg" => {
+                let Some(destination) = NetworkDestination::parse(args) else {
+                    return;
+                };
+                // Contacting an IP address counts even if the connection failed, or is still in
+                // progress for non-blocking sockets. Unix sockets only count if they exist.
+                if succeeded || matches!(destination, NetworkDestination::Inet(_)) {
+                    self.network.insert(destination);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_file(&mut self, path: String, access: FileAccess) {
+        let entry = self.files.entry(path).or_insert(access);
+        *entry = (*entry).max(access);
+    }
+
+    /// Returns the IP destinations the policy doesn't allow.
+    pub(super) fn violations(&self, policy: &AccessPolicy) -> Vec<SocketAddr> {
+        self.network
+            .iter()
+            .filter_map(|destination| match destination {
+                NetworkDestination::Inet(addr) if !policy.allows(addr.ip()) => Some(*addr),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Writes this report out as JSON.
+    pub(super) fn write(&self, path: &Utf8Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// Splits the process ID logged with -f from the rest of the line.
+fn split_pid(line: &str) -> (&str, &str) {
+    if let Some(rest) = line.strip_prefix("[pid ") {
+        if let Some((pid, call)) = rest.split_once(']') {
+            return (pid.trim(), call.trim_start());
+        }
+    }
+    match line.split_once(' ') {
+        Some((pid, call)) if !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit()) => {
+            (pid, call.trim_start())
+        }
+        _ => ("", line),
+    }
+}
+
+/// Returns the string arguments to a system call, unescaping quotes and backslashes. Other
+/// escapes are left as they are.
+fn string_args(args: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = args.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut s = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some(escaped @ ('"' | '\\')) => s.push(escaped),
+                    Some(other) => {
+                        s.push('\\');
+                        s.push(other);
+                    }
+                    None => s.push('\\'),
+                },
+                c => s.push(c),
+            }
+        }
+        strings.push(s);
+    }
+    strings
+}
+
+/// How a file was accessed. Writes take precedence over reads.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum FileAccess {
+    Read,
+    Write,
+}
+
+/// A socket a test connected or sent data to.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum NetworkDestination {
+    Inet(SocketAddr),
+    Unix(String),
+}
+
+impl NetworkDestination {
+    /// Parses the socket address strace logs for `connect`, `sendto` and `sendmsg`, for example
+    /// `{sa_family=AF_INET, sin_port=htons(443), sin_addr=inet_addr("93.184.216.34")}`.
+    fn parse(args: &str) -> Option<Self> {
+        if args.contains("sa_family=AF_INET,") {
+            let port = between(args, "sin_port=htons(", ")")?.parse().ok()?;
+            let addr: IpAddr = between(args, "inet_addr(\"", "\"")?.parse().ok()?;
+            Some(Self::Inet(SocketAddr::new(addr, port)))
+        } else if args.contains("sa_family=AF_INET6,") {
+            let port = between(args, "sin6_port=htons(", ")")?.parse().ok()?;
+            let addr: IpAddr = between(args, "inet_pton(AF_INET6, \"", "\"")?
+                .parse()
+                .ok()?;
+            Some(Self::Inet(SocketAddr::new(addr, port)))
+        } else if args.contains("sa_family=AF_UNIX,") {
+            // Abstract sockets are shown as sun_path=@"name".
+            let (_, rest) = args.split_once("sun_path=")?;
+            let abstract_prefix = if rest.starts_with('@') { "@" } else { "" };
+            let path = string_args(rest).into_iter().next()?;
+            Some(Self::Unix(format!("{abstract_prefix}{path}")))
+        } else {
+            None
+        }
+    }
+}
+
+fn between<'a>(s: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let (_, rest) = s.split_once(start)?;
+    let (value, _) = rest.split_once(end)?;
+    Some(value)
+}
+
+impl fmt::Display for NetworkDestination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inet(addr) => write!(f, "{addr}"),
+            Self::Unix(path) => write!(f, "unix:{path}"),
+        }
+    }
+}
+
+impl Serialize for NetworkDestination {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_parse_strace_log() {
+        let log = indoc! {r#"
+            4242  openat(AT_FDCWD, "/etc/ld.so.cache", O_RDONLY|O_CLOEXEC) = 3
+            4242  openat(AT_FDCWD, "/nonexistent", O_RDONLY) = -1 ENOENT (No such file or directory)
+            4243  openat(AT_FDCWD, "target/out \"quoted\".txt", O_WRONLY|O_CREAT|O_TRUNC, 0666 <unfinished ...>
+            4242  connect(5, {sa_family=AF_INET, sin_port=htons(443), sin_addr=inet_addr("93.184.216.34")}, 16) = -1 EINPROGRESS (Operation now in progress)
+            4243  <... openat resumed>) = 4
+            4242  openat(AT_FDCWD, "/etc/ld.so.cache", O_RDWR) = 3
+            4242  sendto(6, "\x12\x34", 2, MSG_NOSIGNAL, {sa_family=AF_INET6, sin6_port=htons(53), sin6_flowinfo=htonl(0), inet_pton(AF_INET6, "2001:db8::1", &sin6_addr), sin6_scope_id=0}, 28) = 2
+            [pid  4244] connect(7, {sa_family=AF_UNIX, sun_path="/var/run/nscd/socket"}, 110) = -1 ENOENT (No such file or directory)
+            [pid  4244] connect(7, {sa_family=AF_UNIX, sun_path=@"abstract"}, 12) = 0
+            4244  connect(8, {sa_family=AF_INET, sin_port=htons(8080), sin_addr=inet_addr("127.0.0.1")}, 16) = 0
+            4242  renameat2(AT_FDCWD, "a.tmp", AT_FDCWD, "a", RENAME_NOREPLACE) = 0
+            4242  newfstatat(AT_FDCWD, "/etc/hosts", {st_mode=S_IFREG|0644, st_size=1}, 0) = 0
+            4242  +++ exited with 0 +++
+        "#};
+        let report = AccessReport::parse(log);
+
+        assert_eq!(
+            report
+                .network
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "93.184.216.34:443",
+                "127.0.0.1:8080",
+                "[2001:db8::1]:53",
+                "unix:@abstract",
+            ],
+        );
+        assert_eq!(
+            report.files,
+            BTreeMap::from([
+                ("/etc/ld.so.cache".to_owned(), FileAccess::Write),
+                ("a".to_owned(), FileAccess::Write),
+                ("a.tmp".to_owned(), FileAccess::Write),
+                ("target/out \"quoted\".txt".to_owned(), FileAccess::Write),
+            ]),
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["network"][0], "93.184.216.34:443");
+        assert_eq!(json["files"]["a"], "write");
+    }
+
+    #[test]
+    fn test_split_pid() {
+        assert_eq!(split_pid("123  open()"), ("123", "open()"));
+        assert_eq!(split_pid("[pid  123] open()"), ("123", "open()"));
+        assert_eq!(split_pid("open(\"a b\")"), ("", "open(\"a b\")"));
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Adaptive concurrency: running fewer tests at the same time while the system
+//! is under pressure.
+//!
+//! A sampler task periodically checks system load and available memory (see
+//! [`SystemLoad`]). While either is past its configured threshold, the number
+//! of tests allowed to run at the same time is reduced; once the pressure
+//! subsides, it's gradually raised back to the number of test threads.
+//!
+//! Tests that are already running are never stopped. Instead,
+//! [`ConcurrencyThrottle`] holds new tests back until enough running tests
+//! have finished.
+
+use super::{ExecutorEvent, SystemLoad};
+use crate::{
+    config::{get_num_cpus, AdaptiveConcurrency},
+    reporter::events::SystemPressure,
+};
+use std::sync::Mutex;
+use tokio::{
+    sync::{mpsc::UnboundedSender, oneshot, Notify},
+    time::MissedTickBehavior,
+};
+
+/// Limits the number of tests running at the same time to a value that can be
+/// changed while the run is in progress.
+#[derive(Debug)]
+pub(super) struct ConcurrencyThrottle {
+    state: Mutex<ThrottleState>,
+    notify: Notify,
+}
+
+#[derive(Debug)]
+struct ThrottleState {
+    limit: usize,
+    running: usize,
+}
+
+impl ConcurrencyThrottle {
+    pub(super) fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(ThrottleState { limit, running: 0 }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Waits until fewer tests than the current limit are running, returning
+    /// a guard that counts as a running test until it's dropped.
+    pub(super) async fn acquire(&self) -> ThrottleGuard<'_> {
+        loop {
+            // Create the notification future before checking the state, so
+            // that a wakeup between the check and the await isn't lost.
+            let notified = self.notify.notified();
+            {
+                let mut state = self.lock();
+                if state.running < state.limit {
+                    state.running += 1;
+                    return ThrottleGuard { throttle: self };
+                }
+            }
+            notified.await;
+        }
+    }
+
+    fn set_limit(&self, limit: usize) {
+        self.lock().limit = limit;
+        self.notify.notify_waiters();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ThrottleState> {
+        self.state.lock().expect("throttle lock is not poisoned")
+    }
+}
+
+/// Counts as a running test in a [`ConcurrencyThrottle`] until dropped.
+#[derive(Debug)]
+pub(super) struct ThrottleGuard<'a> {
+    throttle: &'a ConcurrencyThrottle,
+}
+
+impl Drop for ThrottleGuard<'_> {
+    fn drop(&mut self) {
+        self.throttle.lock().running -= 1;
+        self.throttle.notify.notify_waiters();
+    }
+}
+
+/// Samples system load until `done_rx` resolves, adjusting the throttle's
+/// limit and reporting changes over `resp_tx`.
+pub(super) async fn run_sampler<'a>(
+    throttle: &ConcurrencyThrottle,
+    config: AdaptiveConcurrency,
+    test_threads: usize,
+    resp_tx: UnboundedSender<ExecutorEvent<'a>>,
+    mut done_rx: oneshot::Receiver<()>,
+) {
+    let cpus = get_num_cpus();
+    let min = config.min_threads().min(test_threads);
+    let mut limit = test_threads;
+    let mut interval = tokio::time::interval(config.sample_interval());
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = &mut done_rx => break,
+            _ = interval.tick() => {}
+        }
+
+        let pressure = system_pressure(SystemLoad::sample(), &config, cpus);
+        let new_limit = next_limit(limit, test_threads, min, pressure.is_some());
+        if new_limit == limit {
+            continue;
+        }
+        throttle.set_limit(new_limit);
+        let event = match pressure {
+            Some(pressure) => ExecutorEvent::ConcurrencyReduced {
+                limit: new_limit,
+                test_threads,
+                pressure,
+            },
+            None if new_limit == test_threads => {
+                ExecutorEvent::ConcurrencyRestored { test_threads }
+            }
+            // Concurrency is being raised, but isn't fully restored yet.
+            None => {
+                limit = new_limit;
+                continue;
+            }
+        };
+        limit = new_limit;
+        if resp_tx.send(event).is_err() {
+            // The dispatcher has shut down, so we should too.
+            break;
+        }
+    }
+}
+
+fn system_pressure(
+    load: SystemLoad,
+    config: &AdaptiveConcurrency,
+    cpus: usize,
+) -> Option<SystemPressure> {
+    if let (Some(min), Some(available)) = (config.min_available_memory, load.available_memory) {
+        if available < min {
+            return Some(SystemPressure::Memory { available });
+        }
+    }
+    if let (Some(max), Some(load_average)) = (config.max_load, load.load_average) {
+        let load_per_cpu = load_average / cpus as f64;
+        if load_per_cpu > max {
+            return Some(SystemPressure::Load { load_per_cpu });
+        }
+    }
+    None
+}
+
+/// Returns the next limit: under pressure, the limit is cut by a quarter, and
+/// otherwise it's raised by an eighth of the number of test threads.
+fn next_limit(limit: usize, max: usize, min: usize, under_pressure: bool) -> usize {
+    if under_pressure {
+        (limit * 3 / 4).max(min)
+    } else {
+        (limit + max.div_ceil(8)).min(max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_next_limit() {
+        // Reductions stop at the minimum.
+        assert_eq!(next_limit(16, 16, 2, true), 12);
+        assert_eq!(next_limit(12, 16, 2, true), 9);
+        assert_eq!(next_limit(2, 16, 2, true), 2);
+        assert_eq!(next_limit(1, 1, 1, true), 1);
+
+        // Increases stop at the maximum.
+        assert_eq!(next_limit(9, 16, 2, false), 11);
+        assert_eq!(next_limit(15, 16, 2, false), 16);
+        assert_eq!(next_limit(16, 16, 2, false), 16);
+        assert_eq!(next_limit(1, 4, 1, false), 2);
+    }
+
+    #[test]
+    fn test_system_pressure() {
+        let config = AdaptiveConcurrency {
+            max_load: Some(1.5),
+            min_available_memory: Some(1024),
+            ..Default::default()
+        };
+        let load = |load_average, available_memory| SystemLoad {
+            load_average,
+            available_memory,
+        };
+
+        assert_eq!(
+            system_pressure(load(Some(4.0), Some(2048)), &config, 4),
+            None
+        );
+        assert_eq!(
+            system_pressure(load(Some(8.0), Some(2048)), &config, 4),
+            Some(SystemPressure::Load { load_per_cpu: 2.0 })
+        );
+        // Memory pressure is reported first.
+        assert_eq!(
+            system_pressure(load(Some(8.0), Some(512)), &config, 4),
+            Some(SystemPressure::Memory { available: 512 })
+        );
+        // Unsupported measurements are never considered to be under pressure.
+        assert_eq!(system_pressure(load(None, None), &config, 4), None);
+    }
+
+    #[tokio::test]
+    async fn throttle_waits_for_limit() {
+        let throttle = ConcurrencyThrottle::new(2);
+        let first = throttle.acquire().await;
+        let _second = throttle.acquire().await;
+
+        let mut third = std::pin::pin!(throttle.acquire());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut third)
+                .await
+                .is_err(),
+            "third test waits while two are running"
+        );
+
+        // Raising the limit lets the waiting test start.
+        throttle.set_limit(3);
+        let _third = tokio::time::timeout(Duration::from_secs(5), third)
+            .await
+            .expect("third test started after the limit was raised");
+
+        // Lowering the limit holds new tests back until enough have finished.
+        throttle.set_limit(2);
+        let mut fourth = std::pin::pin!(throttle.acquire());
+        drop(first);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut fourth)
+                .await
+                .is_err(),
+            "fourth test waits while two are still running"
+        );
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Collecting key-value annotations from test output.
+//!
+//! Tests can export values such as seeds or iteration counts by printing lines
+//! of the form `::nextest::annotation key=value` to stdout or stderr.
+
+use crate::test_output::ChildOutput;
+use bstr::ByteSlice;
+use std::collections::BTreeMap;
+
+/// The prefix that marks a line of output as an annotation.
+const ANNOTATION_PREFIX: &[u8] = b"::nextest::annotation ";
+
+/// Collects the annotations printed by a test.
+///
+/// If a key is printed more than once, the last value wins. Lines that start
+/// with the prefix but don't contain a `key=value` pair are ignored.
+pub(super) fn collect_annotations(output: &ChildOutput) -> BTreeMap<String, String> {
+    let mut annotations = BTreeMap::new();
+    match output {
+        ChildOutput::Split(output) => {
+            for single in [&output.stdout, &output.stderr].into_iter().flatten() {
+                collect_from(&single.buf, &mut annotations);
+            }
+        }
+        ChildOutput::Combined { output } => collect_from(&output.buf, &mut annotations),
+    }
+    annotations
+}
+
+fn collect_from(buf: &[u8], annotations: &mut BTreeMap<String, String>) {
+    for line in buf.lines() {
+        let Some(rest) = line.trim_ascii_start().strip_prefix(ANNOTATION_PREFIX) else {
+            continue;
+        };
+        let Some((key, value)) = rest.split_once_str(b"=") else {
+            continue;
+        };
+        let key = key.trim_ascii();
+        if key.is_empty() || key.iter().any(u8::is_ascii_whitespace) {
+            continue;
+        }
+        annotations.insert(
+            key.to_str_lossy().into_owned(),
+            value.trim_ascii().to_str_lossy().into_owned(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_output::ChildSplitOutput;
+    use bytes::Bytes;
+
+    #[test]
+    fn collect_annotations_from_output() {
+        let output = ChildOutput::Split(ChildSplitOutput {
+            stdout: Some(
+                Bytes::from_static(
+                    b"running 1 test\n\
+                      ::nextest::annotation seed=1234\n\
+                      ::nextest::annotation iterations = 1000\r\n\
+                      ::nextest::annotation no-value\n\
+                      ::nextest::annotation bad key=1\n\
+                      ::nextest::annotation =empty-key\n\
+                      not an ::nextest::annotation key=value\n",
+                )
+                .into(),
+            ),
+            stderr: Some(Bytes::from_static(b"  ::nextest::annotation seed=5678\n").into()),
+        });
+
+        let annotations = collect_annotations(&output);
+        assert_eq!(
+            annotations.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("iterations".to_owned(), "1000".to_owned()),
+                // stderr is read after stdout, so its value wins.
+                ("seed".to_owned(), "5678".to_owned()),
+            ],
+        );
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Limiting the number of test binaries with tests in flight.
+//!
+//! With very large test binaries, running tests from many binaries at once
+//! means that all of them have to be loaded at the same time, which can cause
+//! the page cache to thrash. [`BinaryLimiter`] bounds the number of distinct
+//! binaries that have tests running, independent of the number of test
+//! threads.
+
+use crate::config::MaxConcurrentBinaries;
+use futures::{
+    prelude::*,
+    stream::{Fuse, FusedStream},
+};
+use nextest_metadata::RustBinaryId;
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+/// Hands out slots for test binaries.
+///
+/// Tests from a binary that already has a slot share it, so a binary only
+/// waits for a slot when it has no tests running.
+#[derive(Debug)]
+pub(super) struct BinaryLimiter {
+    semaphore: Option<Arc<Semaphore>>,
+    in_flight: Arc<Mutex<HashMap<RustBinaryId, InFlightBinary>>>,
+    released: Arc<Notify>,
+}
+
+#[derive(Debug)]
+struct InFlightBinary {
+    test_count: usize,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl BinaryLimiter {
+    pub(super) fn new(max: MaxConcurrentBinaries) -> Self {
+        Self {
+            semaphore: max
+                .count()
+                .map(|count| Arc::new(Semaphore::new(count.get()))),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            released: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Admits items from `work` to run as their test binaries get slots.
+    ///
+    /// Items are admitted in order, except that an item whose binary can't
+    /// get a slot yet is set aside rather than holding up the items after it.
+    /// This way, tests and retries from binaries that are already running
+    /// keep being admitted while another binary waits for a slot.
+    pub(super) fn admit<'l, T, S>(
+        &'l self,
+        work: S,
+        binary_id: fn(&T) -> &RustBinaryId,
+    ) -> impl Stream<Item = (T, Option<BinaryGuard>)> + 'l
+    where
+        T: 'l,
+        S: Stream<Item = T> + 'l,
+    {
+        let state = AdmitState {
+            work: Box::pin(work.fuse()),
+            pending: VecDeque::new(),
+        };
+        futures::stream::unfold(state, move |mut state| async move {
+            loop {
+                // Set aside everything that's ready, without waiting for more.
+                while let Some(Some(item)) = state.work.next().now_or_never() {
+                    state.pending.push_back(item);
+                }
+
+                if self.semaphore.is_none() {
+                    if let Some(item) = state.pending.pop_front() {
+                        return Some(((item, None), state));
+                    }
+                } else if let Some((index, guard)) = self.first_admissible(&state, binary_id) {
+                    let item = state.pending.remove(index).expect("index is valid");
+                    return Some(((item, Some(guard)), state));
+                }
+
+                match (state.pending.is_empty(), state.work.is_terminated()) {
+                    (true, true) => return None,
+                    (false, true) => self.released.notified().await,
+                    (true, false) => match state.work.next().await {
+                        Some(item) => state.pending.push_back(item),
+                        None => return None,
+                    },
+                    (false, false) => {
+                        // Wait for either a slot to be released, or for more
+                        // work that might be from a binary that's running.
+                        tokio::select! {
+                            () = self.released.notified() => {}
+                            item = state.work.next() => {
+                                state.pending.extend(item);
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn first_admissible<T, S>(
+        &self,
+        state: &AdmitState<T, S>,
+        binary_id: fn(&T) -> &RustBinaryId,
+    ) -> Option<(usize, BinaryGuard)> {
+        // Each binary only needs to be tried once per pass.
+        let mut waiting = HashSet::new();
+        state.pending.iter().enumerate().find_map(|(index, item)| {
+            let id = binary_id(item);
+            if waiting.contains(id) {
+                return None;
+            }
+            match self.try_acquire(id) {
+                Some(guard) => Some((index, guard)),
+                None => {
+                    waiting.insert(id);
+                    None
+                }
+            }
+        })
+    }
+
+    /// Returns a guard that holds the slot for `binary_id` until it's
+    /// dropped, or `None` if the binary has to wait for a slot.
+    fn try_acquire(&self, binary_id: &RustBinaryId) -> Option<BinaryGuard> {
+        let semaphore = self.semaphore.as_ref()?;
+
+        match self.lock().entry(binary_id.clone()) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().test_count += 1;
+            }
+            Entry::Vacant(entry) => {
+                let permit = semaphore.clone().try_acquire_owned().ok()?;
+                entry.insert(InFlightBinary {
+                    test_count: 1,
+                    _permit: permit,
+                });
+            }
+        }
+        Some(self.guard(binary_id))
+    }
+
+    fn guard(&self, binary_id: &RustBinaryId) -> BinaryGuard {
+        BinaryGuard {
+            binary_id: binary_id.clone(),
+            in_flight: self.in_flight.clone(),
+            released: self.released.clone(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<RustBinaryId, InFlightBinary>> {
+        self.in_flight
+            .lock()
+            .expect("in-flight lock is not poisoned")
+    }
+}
+
+struct AdmitState<T, S> {
+    work: Pin<Box<Fuse<S>>>,
+    pending: VecDeque<T>,
+}
+
+/// Holds a test binary's slot in a [`BinaryLimiter`] while a test from it is
+/// running.
+#[derive(Debug)]
+pub(super) struct BinaryGuard {
+    binary_id: RustBinaryId,
+    in_flight: Arc<Mutex<HashMap<RustBinaryId, InFlightBinary>>>,
+    released: Arc<Notify>,
+}
+
+impl Drop for BinaryGuard {
+    fn drop(&mut self) {
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .expect("in-flight lock is not poisoned");
+        if let Entry::Occupied(mut entry) = in_flight.entry(self.binary_id.clone()) {
+            entry.get_mut().test_count -= 1;
+            if entry.get().test_count == 0 {
+                // Dropping the entry releases the binary's permit. There's a
+                // single admission stream, so notify_one wakes it up (or lets
+                // its next wait return immediately).
+                entry.remove();
+                self.released.notify_one();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{num::NonZeroUsize, time::Duration};
+    use tokio::sync::mpsc::unbounded_channel;
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+
+    fn limited(count: usize) -> BinaryLimiter {
+        BinaryLimiter::new(MaxConcurrentBinaries::Count(
+            NonZeroUsize::new(count).unwrap(),
+        ))
+    }
+
+    async fn next_within<S: Stream + Unpin>(
+        stream: &mut S,
+    ) -> Result<Option<S::Item>, tokio::time::error::Elapsed> {
+        tokio::time::timeout(Duration::from_millis(50), stream.next()).await
+    }
+
+    #[test]
+    fn limits_distinct_binaries() {
+        let limiter = limited(1);
+        let a = RustBinaryId::new("a");
+        let b = RustBinaryId::new("b");
+
+        // Tests from the same binary share a slot.
+        let a1 = limiter.try_acquire(&a).expect("a gets a slot");
+        let a2 = limiter.try_acquire(&a).expect("a shares its slot");
+
+        // A different binary has to wait until all of a's tests are done.
+        assert!(limiter.try_acquire(&b).is_none(), "b waits while a runs");
+        drop(a1);
+        assert!(
+            limiter.try_acquire(&b).is_none(),
+            "b waits while a still has a test running"
+        );
+        drop(a2);
+        let _b1 = limiter
+            .try_acquire(&b)
+            .expect("b gets a slot once a is done");
+    }
+
+    #[tokio::test]
+    async fn admitted_binary_runs_while_another_waits() {
+        let limiter = limited(1);
+        let (tx, rx) = unbounded_channel();
+        let mut admitted =
+            std::pin::pin!(limiter.admit(UnboundedReceiverStream::new(rx), |(id, _)| id));
+
+        for item in [("a", 1), ("b", 1), ("a", 2)] {
+            tx.send((RustBinaryId::new(item.0), item.1)).unwrap();
+        }
+
+        // a gets the slot, and b has to wait -- but a's second test doesn't
+        // have to wait behind b.
+        let (a1, a1_guard) = next_within(&mut admitted).await.unwrap().unwrap();
+        assert_eq!(a1, (RustBinaryId::new("a"), 1));
+        let (a2, a2_guard) = next_within(&mut admitted).await.unwrap().unwrap();
+        assert_eq!(a2, (RustBinaryId::new("a"), 2));
+
+        // The same goes for work that shows up later, such as retries.
+        tx.send((RustBinaryId::new("a"), 3)).unwrap();
+        let (a3, a3_guard) = next_within(&mut admitted).await.unwrap().unwrap();
+        assert_eq!(a3, (RustBinaryId::new("a"), 3));
+
+        assert!(
+            next_within(&mut admitted).await.is_err(),
+            "b waits while a has tests running"
+        );
+        drop((a1_guard, a2_guard, a3_guard));
+        let (b1, _b1_guard) = next_within(&mut admitted)
+            .await
+            .expect("b is admitted once a is done")
+            .unwrap();
+        assert_eq!(b1, (RustBinaryId::new("b"), 1));
+
+        drop(tx);
+        assert!(next_within(&mut admitted).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn unlimited_is_a_no_op() {
+        let limiter = BinaryLimiter::new(MaxConcurrentBinaries::Unlimited);
+        let work = futures::stream::iter([RustBinaryId::new("a"), RustBinaryId::new("b")]);
+        let admitted: Vec<_> = limiter.admit(work, |id| id).collect().await;
+        assert_eq!(admitted.len(), 2);
+        assert!(admitted.iter().all(|(_, guard)| guard.is_none()));
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-test cgroups, used to apply `cgroup-limits`.
+//!
+//! On Linux, each test with cgroup limits is run in its own cgroup v2, created under the cgroup
+//! that nextest is running in. The test process moves itself into the cgroup just before it
+//! starts, so any processes it spawns are subject to the same limits.
+//!
+//! Setting up cgroups can fail for many reasons: cgroup v2 might not be mounted, nextest might not
+//! have permission to create cgroups, or nextest's cgroup might contain other processes (in which
+//! case the kernel doesn't allow controllers to be enabled for child cgroups). In all of these
+//! cases, tests are run without limits, and a warning is printed once per run.
+
+use crate::{config::CgroupLimits, reporter::events::CgroupLimitsStatus};
+use quick_junit::ReportUuid;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::warn;
+
+/// Creates cgroups for tests over the course of a run.
+#[derive(Debug)]
+pub(super) struct CgroupManager {
+    #[cfg_attr(not(target_os = "linux"), expect(dead_code))]
+    run_id: ReportUuid,
+    #[cfg(target_os = "linux")]
+    parent: once_cell::sync::OnceCell<Result<camino::Utf8PathBuf, String>>,
+    #[cfg(target_os = "linux")]
+    next_id: std::sync::atomic::AtomicUsize,
+    warned: AtomicBool,
+}
+
+impl CgroupManager {
+    pub(super) fn new(run_id: ReportUuid) -> Self {
+        Self {
+            run_id,
+            #[cfg(target_os = "linux")]
+            parent: once_cell::sync::OnceCell::new(),
+            #[cfg(target_os = "linux")]
+            next_id: std::sync::atomic::AtomicUsize::new(0),
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// Sets up `cmd` to run in a new cgroup with the given limits.
+    ///
+    /// The returned [`TestCgroup`], if any, must be kept alive until the test has exited.
+    pub(super) fn apply(
+        &self,
+        cmd: &mut std::process::Command,
+        limits: CgroupLimits,
+    ) -> (CgroupLimitsStatus, Option<TestCgroup>) {
+        if limits.is_empty() {
+            return (CgroupLimitsStatus::NotRequested, None);
+        }
+
+        match self.create(cmd, limits) {
+            Ok(cgroup) => (CgroupLimitsStatus::Applied, Some(cgroup)),
+            Err(reason) => {
+                // Only warn once, since the same problem likely applies to every test.
+                if !self.warned.swap(true, Ordering::Relaxed) {
+                    warn!(
+                        "cgroup limits could not be applied, running tests without them: {reason}"
+                    );
+                }
+                (CgroupLimitsStatus::Unavailable, None)
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn create(
+        &self,
+        cmd: &mut std::process::Command,
+        limits: CgroupLimits,
+    ) -> Result<TestCgroup, String> {
+        use crate::config::{CgroupCpuMax, ResourceLimit};
+        use std::os::{fd::AsRawFd, unix::process::CommandExt};
+
+        let parent = self
+            .parent
+            .get_or_init(linux::init_parent)
+            .as_ref()
+            .map_err(Clone::clone)?;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let path = parent.join(format!("nextest-{}-{id}", self.run_id));
+        std::fs::create_dir(&path).map_err(|error| format!("error creating {path}: {error}"))?;
+        // From here on, the cgroup is removed on drop if anything fails.
+        let mut cgroup = TestCgroup { procs: None, path };
+
+        let limit_str = |limit: ResourceLimit| match limit {
+            ResourceLimit::Unlimited => "max".to_owned(),
+            ResourceLimit::Value(value) => value.to_string(),
+        };
+        if let Some(memory_max) = limits.memory_max {
+            cgroup.write("memory.max", &limit_str(memory_max))?;
+        }
+        if let Some(pids_max) = limits.pids_max {
+            cgroup.write("pids.max", &limit_str(pids_max))?;
+        }
+        if let Some(cpu_max) = limits.cpu_max {
+            cgroup.write(
+                "cpu.max",
+                &format!("{} {}", cpu_max.quota_micros(), CgroupCpuMax::PERIOD_MICROS),
+            )?;
+        }
+
+        let procs_path = cgroup.path.join("cgroup.procs");
+        let procs = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&procs_path)
+            .map_err(|error| format!("error opening {procs_path}: {error}"))?;
+        let fd = procs.as_raw_fd();
+        // The file is opened with O_CLOEXEC, so it isn't inherited by the test itself.
+        cgroup.procs = Some(procs);
+
+        // SAFETY: write is async-signal-safe, and the closure doesn't allocate. Writing "0" to
+        // cgroup.procs moves the writing process into the cgroup.
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::write(fd, b"0".as_ptr().cast(), 1) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        Ok(cgroup)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn create(
+        &self,
+        _cmd: &mut std::process::Command,
+        _limits: CgroupLimits,
+    ) -> Result<TestCgroup, String> {
+        Err("cgroups are only supported on Linux".to_owned())
+    }
+}
+
+/// A cgroup that a single test is run in. The cgroup is removed on drop.
+#[derive(Debug)]
+#[cfg_attr(not(target_os = "linux"), expect(dead_code))]
+pub(super) struct TestCgroup {
+    // Kept open until the test has been spawned, and then closed before the cgroup is removed.
+    #[cfg(target_os = "linux")]
+    procs: Option<std::fs::File>,
+    #[cfg(target_os = "linux")]
+    path: camino::Utf8PathBuf,
+}
+
+#[cfg(target_os = "linux")]
+impl TestCgroup {
+    fn write(&self, file_name: &str, contents: &str) -> Result<(), String> {
+        let path = self.path.join(file_name);
+        std::fs::write(&path, contents)
+            .map_err(|error| format!("error writing `{contents}` to {path}: {error}"))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for TestCgroup {
+    fn drop(&mut self) {
+        self.procs.take();
+        // This fails if processes are still in the cgroup, for example if the test leaked
+        // processes. The kernel keeps the (empty) directory around in that case.
+        if let Err(error) = std::fs::remove_dir(&self.path) {
+            tracing::debug!("error removing cgroup {}: {error}", self.path);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use camino::{Utf8Path, Utf8PathBuf};
+
+    /// The location cgroup v2 is conventionally mounted at.
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+    /// The controllers needed to apply limits.
+    const CONTROLLERS: &[&str] = &["memory", "pids", "cpu"];
+
+    /// Finds the cgroup nextest is running in, and enables controllers for its child cgroups.
+    pub(super) fn init_parent() -> Result<Utf8PathBuf, String> {
+        let contents = std::fs::read_to_string("/proc/self/cgroup")
+            .map_err(|error| format!("error reading /proc/self/cgroup: {error}"))?;
+        // With cgroup v2, the line for the unified hierarchy looks like "0::/path/to/cgroup".
+        let relative = parse_unified_cgroup(&contents)
+            .ok_or_else(|| "nextest is not running in a cgroup v2 hierarchy".to_owned())?;
+        let parent = Utf8Path::new(CGROUP_ROOT).join(relative.trim_start_matches('/'));
+
+        let controllers_path = parent.join("cgroup.controllers");
+        let available = std::fs::read_to_string(&controllers_path)
+            .map_err(|error| format!("error reading {controllers_path}: {error}"))?;
+        let enable: Vec<_> = CONTROLLERS
+            .iter()
+            .filter(|controller| available.split_whitespace().any(|c| c == **controller))
+            .map(|controller| format!("+{controller}"))
+            .collect();
+        if !enable.is_empty() {
+            let subtree_control = parent.join("cgroup.subtree_control");
+            std::fs::write(&subtree_control, enable.join(" ")).map_err(|error| {
+                let hint = if error.raw_os_error() == Some(libc::EBUSY) {
+                    " (nextest's cgroup contains other processes; \
+                     run nextest in a dedicated cgroup to use cgroup limits)"
+                } else {
+                    ""
+                };
+                format!("error enabling controllers in {subtree_control}: {error}{hint}")
+            })?;
+        }
+
+        Ok(parent)
+    }
+
+    pub(super) fn parse_unified_cgroup(contents: &str) -> Option<&str> {
+        contents.lines().find_map(|line| line.strip_prefix("0::"))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_unified_cgroup() {
+            assert_eq!(
+                parse_unified_cgroup("0::/user.slice/user-1000.slice/session-2.scope\n"),
+                Some("/user.slice/user-1000.slice/session-2.scope"),
+            );
+            // Hybrid setups list v1 hierarchies as well.
+            assert_eq!(
+                parse_unified_cgroup("12:pids:/user.slice\n1:name=systemd:/init.scope\n0::/\n"),
+                Some("/"),
+            );
+            // cgroup v1 only.
+            assert_eq!(parse_unified_cgroup("12:pids:/user.slice\n"), None);
+        }
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Collecting crash dumps from tests that crash, set via `crash-dumps`.
+//!
+//! Crash dumps are written by the operating system, not by nextest, so once a
+//! test crashes, its dump is looked for wherever the system puts it:
+//!
+//! * On Linux, according to `/proc/sys/kernel/core_pattern`. If cores are piped
+//!   to `systemd-coredump`, the dump is retrieved with `coredumpctl`.
+//! * On macOS, in `/cores`.
+//! * On other Unix platforms, in the test's working directory.
+//! * On Windows, in the default `DumpFolder` that Windows Error Reporting's
+//!   `LocalDumps` feature writes to, `%LOCALAPPDATA%\CrashDumps`.
+//!
+//! A dump that's found is moved to
+//! `<store-dir>/crash-dumps/<run-id>/<test>/attempt-<n>/`, along with the
+//! output of the profile's `crash-backtrace` command, if one is configured.
+//! `<test>` is the test's [safe name](crate::safe_names::safe_name).
+
+use crate::{
+    errors::SafeNamesManifestWriteError, list::TestInstanceId, reporter::events::CrashDump,
+    safe_names::SafeNamesDir,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use quick_junit::ReportUuid;
+use std::{io, process::ExitStatus};
+use tracing::{debug, warn};
+
+/// The directory within the store that crash dumps are collected into.
+const CRASH_DUMPS_DIR: &str = "crash-dumps";
+
+/// Returns the directory crash dumps are collected into for a run.
+pub(super) fn crash_dumps_dir(store_dir: &Utf8Path, run_id: ReportUuid) -> SafeNamesDir {
+    SafeNamesDir::new(store_dir.join(CRASH_DUMPS_DIR).join(run_id.to_string()))
+}
+
+/// Returns the directory an attempt's crash dump is collected into.
+pub(super) fn crash_dump_dir(
+    dir: &SafeNamesDir,
+    test_id: TestInstanceId<'_>,
+    attempt: usize,
+) -> Result<Utf8PathBuf, SafeNamesManifestWriteError> {
+    Ok(dir.path_for(test_id)?.join(format!("attempt-{attempt}")))
+}
+
+/// Returns true if a process that exited with this status might have left a
+/// crash dump behind.
+pub(super) fn may_have_crash_dump(exit_status: ExitStatus) -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            use std::os::unix::process::ExitStatusExt;
+            exit_status.core_dumped()
+        } else if #[cfg(windows)] {
+            // Crashes are reported as NTSTATUS error codes, which have the high
+            // bit set.
+            exit_status.code().is_some_and(|code| code < 0)
+        } else {
+            let _ = exit_status;
+            false
+        }
+    }
+}
+
+/// What's needed to find a test's crash dump, captured before the test is
+/// spawned.
+#[derive(Debug)]
+pub(super) struct CrashDumpCollector {
+    binary_path: Utf8PathBuf,
+    cwd: Utf8PathBuf,
+}
+
+impl CrashDumpCollector {
+    pub(super) fn capture(cmd: &std::process::Command, binary_path: &Utf8Path) -> Self {
+        // Relative core patterns are relative to the working directory of the
+        // process that crashed.
+        let cwd = cmd
+            .get_current_dir()
+            .map(|dir| dir.to_owned())
+            .or_else(|| std::env::current_dir().ok())
+            .and_then(|dir| Utf8PathBuf::try_from(dir).ok())
+            .unwrap_or_default();
+        Self {
+            binary_path: binary_path.to_owned(),
+            cwd,
+        }
+    }
+
+    /// Looks for the crash dump of the process with the given PID, moving it
+    /// to `dir` if it's found.
+    ///
+    /// If `backtrace_command` isn't empty, it's run on the dump, and its output
+    /// is written out next to it.
+    pub(super) async fn collect(
+        self,
+        pid: u32,
+        dir: &Utf8Path,
+        backtrace_command: &[String],
+    ) -> io::Result<Option<CrashDump>> {
+        let exe_name = self.binary_path.file_name().unwrap_or_default();
+        let Some(location) = os::dump_location(pid, exe_name, &self.cwd) else {
+            debug!(
+                pid,
+                "crash dumps aren't written to a known location, not collecting"
+            );
+            return Ok(None);
+        };
+
+        let path = match location {
+            DumpLocation::Files(candidates) => {
+                let Some(source) = candidates.into_iter().find(|path| path.is_file()) else {
+                    debug!(pid, "no crash dump found");
+                    return Ok(None);
+                };
+                std::fs::create_dir_all(dir)?;
+                let path = dir.join(source.file_name().unwrap_or("core"));
+                move_file(&source, &path)?;
+                path
+            }
+            #[cfg(target_os = "linux")]
+            DumpLocation::Coredumpctl => {
+                std::fs::create_dir_all(dir)?;
+                let path = dir.join("core");
+                if !os::coredumpctl_dump(pid, &path).await? {
+                    debug!(pid, "coredumpctl didn't find a crash dump");
+                    // Don't leave an empty directory behind.
+                    let _ = std::fs::remove_dir(dir);
+                    return Ok(None);
+                }
+                path
+            }
+        };
+
+        // A failure to produce a backtrace shouldn't lose the dump itself.
+        let backtrace = if backtrace_command.is_empty() {
+            None
+        } else {
+            let backtrace = dir.join("backtrace.txt");
+            match write_backtrace(backtrace_command, &self.binary_path, &path, &backtrace).await {
+                Ok(()) => Some(backtrace),
+                Err(error) => {
+                    warn!("error running crash-backtrace command on {path}: {error}");
+                    None
+                }
+            }
+        };
+
+        Ok(Some(CrashDump { path, backtrace }))
+    }
+}
+
+/// Where the operating system writes crash dumps.
+#[derive(Debug)]
+enum DumpLocation {
+    /// The dump is written to the first of these files that exists.
+    Files(Vec<Utf8PathBuf>),
+
+    /// Dumps are handled by `systemd-coredump`, and can be retrieved with
+    /// `coredumpctl`.
+    #[cfg(target_os = "linux")]
+    Coredumpctl,
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::DumpLocation;
+    use camino::Utf8Path;
+    use std::{io, process::Stdio, time::Duration};
+
+    /// `systemd-coredump` processes dumps asynchronously, so a dump might not
+    /// be available as soon as the test exits.
+    const COREDUMPCTL_ATTEMPTS: usize = 10;
+    const COREDUMPCTL_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+    pub(super) fn dump_location(pid: u32, exe_name: &str, cwd: &Utf8Path) -> Option<DumpLocation> {
+        let pattern = std::fs::read_to_string("/proc/sys/kernel/core_pattern").ok()?;
+        let pattern = pattern.trim_end();
+        if let Some(helper) = pattern.strip_prefix('|') {
+            return helper
+                .contains("systemd-coredump")
+                .then_some(DumpLocation::Coredumpctl);
+        }
+
+        let uses_pid = std::fs::read_to_string("/proc/sys/kernel/core_uses_pid")
+            .is_ok_and(|value| value.trim() == "1");
+        let path = super::expand_core_pattern(pattern, pid, exe_name, uses_pid)?;
+        Some(DumpLocation::Files(vec![cwd.join(path)]))
+    }
+
+    /// Writes out the dump of the process with the given PID, returning false
+    /// if `coredumpctl` didn't find it.
+    pub(super) async fn coredumpctl_dump(pid: u32, dest: &Utf8Path) -> io::Result<bool> {
+        for attempt in 1..=COREDUMPCTL_ATTEMPTS {
+            let status = tokio::process::Command::new("coredumpctl")
+                .args(["--no-pager", "--quiet", "dump", "--output", dest.as_str()])
+                .arg(pid.to_string())
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await?;
+            if status.success() {
+                return Ok(true);
+            }
+            if attempt < COREDUMPCTL_ATTEMPTS {
+                tokio::time::sleep(COREDUMPCTL_RETRY_DELAY).await;
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod os {
+    use super::DumpLocation;
+    use camino::{Utf8Path, Utf8PathBuf};
+
+    pub(super) fn dump_location(
+        pid: u32,
+        _exe_name: &str,
+        _cwd: &Utf8Path,
+    ) -> Option<DumpLocation> {
+        Some(DumpLocation::Files(vec![Utf8PathBuf::from(format!(
+            "/cores/core.{pid}"
+        ))]))
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+mod os {
+    use super::DumpLocation;
+    use camino::Utf8Path;
+
+    pub(super) fn dump_location(_pid: u32, exe_name: &str, cwd: &Utf8Path) -> Option<DumpLocation> {
+        // The BSDs default to "%N.core", and other platforms to "core".
+        Some(DumpLocation::Files(vec![
+            cwd.join(format!("{exe_name}.core")),
+            cwd.join("core"),
+        ]))
+    }
+}
+
+#[cfg(windows)]
+mod os {
+    use super::DumpLocation;
+    use camino::{Utf8Path, Utf8PathBuf};
+
+    pub(super) fn dump_location(pid: u32, exe_name: &str, _cwd: &Utf8Path) -> Option<DumpLocation> {
+        // This is where LocalDumps writes dumps if no DumpFolder is configured.
+        let local_app_data = Utf8PathBuf::try_from(std::env::var_os("LOCALAPPDATA")?).ok()?;
+        Some(DumpLocation::Files(vec![local_app_data
+            .join("CrashDumps")
+            .join(format!("{exe_name}.{pid}.dmp"))]))
+    }
+}
+
+/// Expands a Linux core pattern (see `core(5)`) for a process that crashed.
+///
+/// Returns `None` if the pattern uses specifiers other than `%p`, `%P`, `%e`
+/// and `%%`, since their values aren't known to nextest.
+#[cfg_attr(not(any(target_os = "linux", test)), expect(dead_code))]
+fn expand_core_pattern(pattern: &str, pid: u32, exe_name: &str, uses_pid: bool) -> Option<String> {
+    // %e is the process's command name, which the kernel truncates to 15
+    // bytes.
+    let comm = exe_name.get(..15).unwrap_or(exe_name);
+
+    let mut expanded = String::with_capacity(pattern.len());
+    let mut has_pid = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '%' => expanded.push('%'),
+            'p' | 'P' => {
+                has_pid = true;
+                expanded.push_str(&pid.to_string());
+            }
+            'e' => expanded.push_str(comm),
+            _ => return None,
+        }
+    }
+
+    // With core_uses_pid set, the PID is appended if the pattern doesn't
+    // already include it.
+    if uses_pid && !has_pid {
+        expanded.push_str(&format!(".{pid}"));
+    }
+    Some(expanded)
+}
+
+/// Replaces the `{binary}` and `{dump}` placeholders in a `crash-backtrace`
+/// command.
+fn backtrace_args(command: &[String], binary: &Utf8Path, dump: &Utf8Path) -> Vec<String> {
+    command
+        .iter()
+        .map(|arg| {
+            arg.replace("{binary}", binary.as_str())
+                .replace("{dump}", dump.as_str())
+        })
+        .collect()
+}
+
+async fn write_backtrace(
+    command: &[String],
+    binary: &Utf8Path,
+    dump: &Utf8Path,
+    dest: &Utf8Path,
+) -> io::Result<()> {
+    let args = backtrace_args(command, binary, dump);
+    let (program, args) = args
+        .split_first()
+        .expect("crash-backtrace command is non-empty");
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await?;
+
+    let mut contents = output.stdout;
+    contents.extend_from_slice(&output.stderr);
+    std::fs::write(dest, contents)
+}
+
+/// Moves a file, falling back to copying it if it's on a different file system
+/// than the destination.
+fn move_file(from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_core_pattern() {
+        assert_eq!(
+            expand_core_pattern("core", 1234, "my_crate-0123456789abcdef", false).as_deref(),
+            Some("core")
+        );
+        assert_eq!(
+            expand_core_pattern("core", 1234, "my_crate-0123456789abcdef", true).as_deref(),
+            Some("core.1234")
+        );
+        assert_eq!(
+            expand_core_pattern(
+                "/var/cores/%e.%p.100%%",
+                1234,
+                "my_crate-0123456789abcdef",
+                true,
+            )
+            .as_deref(),
+            Some("/var/cores/my_crate-012345.1234.100%"),
+        );
+        // Timestamps, signal numbers and the like can't be reproduced.
+        assert_eq!(expand_core_pattern("core.%t", 1234, "t", false), None);
+        assert_eq!(expand_core_pattern("core%", 1234, "t", false), None);
+    }
+
+    #[test]
+    fn test_backtrace_args() {
+        let command =
+            ["gdb", "--batch", "-ex", "bt", "{binary}", "--core={dump}"].map(str::to_owned);
+        assert_eq!(
+            backtrace_args(
+                &command,
+                Utf8Path::new("/target/debug/deps/t-abc"),
+                Utf8Path::new("/store/core"),
+            ),
+            [
+                "gdb",
+                "--batch",
+                "-ex",
+                "bt",
+                "/target/debug/deps/t-abc",
+                "--core=/store/core"
+            ],
+        );
+    }
+}
@@ -5,16 +5,18 @@
 //!
 //! This module interfaces with the external world and the test executor. It
 //! receives events from the executor and from other inputs (e.g. signal and
-//! input handling), and sends events to the reporter.
+//! input handling, and the control socket), and sends events to the reporter.
 
-use super::{RunUnitRequest, RunnerTaskState, ShutdownRequest};
+use super::{RunUnitRequest, RunnerTaskState, ShutdownRequest, TestDurations};
 use crate::{
-    config::{MaxFail, ScriptConfig, ScriptId},
+    config::{MaxFail, MaxFailScope, ScriptConfig, ScriptId},
+    control::{ControlCommand, ControlRequest, ControlResponse, ControlServer},
+    flaky::{FlakyHistory, TestOutcome},
     input::{InputEvent, InputHandler},
     list::{TestInstance, TestInstanceId, TestList},
     reporter::events::{
-        CancelReason, ExecuteStatus, ExecutionStatuses, InfoResponse, RunStats, TestEvent,
-        TestEventKind,
+        CancelReason, ExecuteStatus, ExecutionDescription, ExecutionStatuses, InfoResponse,
+        RunStats, TestEvent, TestEventKind,
     },
     runner::{ExecutorEvent, RunUnitQuery, SignalRequest},
     signal::{JobControlEvent, ShutdownEvent, SignalEvent, SignalHandler, SignalInfoEvent},
@@ -22,8 +24,12 @@ use crate::{
 };
 use chrono::Local;
 use debug_ignore::DebugIgnore;
+use nextest_metadata::MismatchReason;
 use quick_junit::ReportUuid;
-use std::{collections::BTreeMap, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
 use tokio::sync::{
     mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
     oneshot,
@@ -44,6 +50,16 @@ pub(super) struct DispatcherContext<'a, F> {
     stopwatch: StopwatchStart,
     run_stats: RunStats,
     max_fail: MaxFail,
+    max_fail_scope: MaxFailScope,
+    // Failure counts and cancelled scopes, keyed by package ID or binary ID
+    // depending on max_fail_scope. Unused if the scope is the whole run.
+    scope_failures: BTreeMap<&'a str, usize>,
+    cancelled_scopes: BTreeSet<&'a str>,
+    // Durations of finished tests, if they're being recorded.
+    durations: Option<TestDurations>,
+    // Outcomes of finished tests, used to track flaky tests across runs, if
+    // they're being recorded.
+    flaky_history: Option<FlakyHistory>,
     running_setup_script: Option<ContextSetupScript<'a>>,
     running_tests: BTreeMap<TestInstanceId<'a>, ContextTestInstance<'a>>,
     cancel_state: Option<CancelReason>,
@@ -63,6 +79,9 @@ where
         cli_args: Vec<String>,
         initial_run_count: usize,
         max_fail: MaxFail,
+        max_fail_scope: MaxFailScope,
+        durations: Option<TestDurations>,
+        flaky_history: Option<FlakyHistory>,
     ) -> Self {
         Self {
             callback: DebugIgnore(callback),
@@ -75,6 +94,11 @@ where
                 ..RunStats::default()
             },
             max_fail,
+            max_fail_scope,
+            scope_failures: BTreeMap::new(),
+            cancelled_scopes: BTreeSet::new(),
+            durations,
+            flaky_history,
             running_setup_script: None,
             running_tests: BTreeMap::new(),
             cancel_state: None,
@@ -96,12 +120,14 @@ where
         mut executor_rx: UnboundedReceiver<ExecutorEvent<'a>>,
         signal_handler: &mut SignalHandler,
         input_handler: &mut InputHandler,
+        control_server: &mut ControlServer,
         report_cancel_rx: oneshot::Receiver<()>,
     ) -> RunnerTaskState {
         let mut report_cancel_rx = std::pin::pin!(report_cancel_rx);
 
         let mut signals_done = false;
         let mut inputs_done = false;
+        let mut control_done = false;
         let mut report_cancel_rx_done = false;
 
         loop {
@@ -133,6 +159,15 @@ where
                         }
                     }
                 }
+                internal_event = control_server.recv(), if !control_done => {
+                    match internal_event {
+                        Some(request) => InternalEvent::Control(request),
+                        None => {
+                            control_done = true;
+                            continue;
+                        }
+                    }
+                }
                 res = &mut report_cancel_rx, if !report_cancel_rx_done => {
                     report_cancel_rx_done = true;
                     match res {
@@ -210,6 +245,12 @@ where
                     input_handler.resume();
                     self.broadcast_request(RunUnitRequest::Signal(SignalRequest::Continue));
                 }
+                #[cfg(unix)]
+                HandleEventResponse::Forward(signal) => {
+                    let count = self
+                        .broadcast_request(RunUnitRequest::Signal(SignalRequest::Forward(signal)));
+                    debug!(?signal, count, "forwarded signal to running units");
+                }
                 #[cfg(not(unix))]
                 HandleEventResponse::JobControl(e) => {
                     // On platforms other than Unix this enum is expected to be
@@ -418,6 +459,20 @@ where
                     // The run has been cancelled: don't start any new units.
                     return HandleEventResponse::None;
                 }
+                if self
+                    .max_fail_scope_key(&test_instance)
+                    .is_some_and(|key| self.cancelled_scopes.contains(key))
+                {
+                    // The package or binary this test belongs to has been
+                    // cancelled: don't start this test, and report it as
+                    // skipped. Dropping req_rx_tx tells the executor to exit.
+                    let reason = MismatchReason::ScopeCancelled;
+                    self.run_stats.on_test_skipped(reason);
+                    return self.callback_none_response(TestEventKind::TestSkipped {
+                        test_instance,
+                        reason,
+                    });
+                }
 
                 let (req_tx, req_rx) = unbounded_channel();
                 match req_rx_tx.send(req_rx) {
@@ -493,13 +548,42 @@ where
                 failure_output,
                 junit_store_success_output,
                 junit_store_failure_output,
+                tracking_issue,
                 last_run_status,
             }) => {
                 let run_statuses = self.finish_test(test_instance.id(), last_run_status);
+                let failed_before = self.run_stats.failed_count();
                 self.run_stats.on_test_finished(&run_statuses);
+                let newly_failed = self.run_stats.failed_count() > failed_before;
+                if let Some(durations) = &mut self.durations {
+                    durations.record(&test_instance, run_statuses.last_status().time_taken);
+                }
+                if let Some(flaky_history) = &mut self.flaky_history {
+                    let outcome = match run_statuses.describe() {
+                        ExecutionDescription::Success { .. } => TestOutcome::Pass,
+                        ExecutionDescription::Flaky { .. } => TestOutcome::Flaky,
+                        ExecutionDescription::Failure { .. } => TestOutcome::Fail,
+                    };
+                    let id = test_instance.id();
+                    flaky_history.record(id.binary_id.as_str(), id.test_name, outcome);
+                }
 
-                // should this run be cancelled because of a failure?
-                let fail_cancel = self.max_fail.is_exceeded(self.run_stats.failed_count());
+                // should this run (or this test's scope) be cancelled because
+                // of a failure?
+                let (fail_cancel, scope_cancel) = match self.max_fail_scope_key(&test_instance) {
+                    None => (
+                        self.max_fail.is_exceeded(self.run_stats.failed_count()),
+                        false,
+                    ),
+                    Some(key) if newly_failed => {
+                        let failures = self.scope_failures.entry(key).or_default();
+                        *failures += 1;
+                        let scope_cancel = self.max_fail.is_exceeded(*failures)
+                            && self.cancelled_scopes.insert(key);
+                        (false, scope_cancel)
+                    }
+                    Some(_) => (false, false),
+                };
 
                 self.basic_callback(TestEventKind::TestFinished {
                     test_instance,
@@ -507,6 +591,7 @@ where
                     failure_output,
                     junit_store_success_output,
                     junit_store_failure_output,
+                    tracking_issue,
                     run_statuses,
                     current_stats: self.run_stats,
                     running: self.running(),
@@ -516,6 +601,14 @@ where
                 if fail_cancel {
                     // A test failed: start cancellation if required.
                     self.begin_cancel(CancelReason::TestFailure, CancelEvent::TestFailure)
+                } else if scope_cancel {
+                    // Tests already running in this scope are allowed to
+                    // finish, but no new ones will be started.
+                    self.callback_none_response(TestEventKind::ScopeBeginCancel {
+                        scope: self.max_fail_scope,
+                        test_instance,
+                        running: self.running(),
+                    })
                 } else {
                     HandleEventResponse::None
                 }
@@ -524,12 +617,24 @@ where
                 test_instance,
                 reason,
             }) => {
-                self.run_stats.skipped += 1;
+                self.run_stats.on_test_skipped(reason);
                 self.callback_none_response(TestEventKind::TestSkipped {
                     test_instance,
                     reason,
                 })
             }
+            InternalEvent::Executor(ExecutorEvent::ConcurrencyReduced {
+                limit,
+                test_threads,
+                pressure,
+            }) => self.callback_none_response(TestEventKind::ConcurrencyReduced {
+                limit,
+                test_threads,
+                pressure,
+            }),
+            InternalEvent::Executor(ExecutorEvent::ConcurrencyRestored { test_threads }) => {
+                self.callback_none_response(TestEventKind::ConcurrencyRestored { test_threads })
+            }
             InternalEvent::Signal(event) => self.handle_signal_event(event),
             InternalEvent::Input(InputEvent::Info) => {
                 // Print current statistics.
@@ -545,9 +650,72 @@ where
             InternalEvent::ReportCancel => {
                 self.begin_cancel(CancelReason::ReportError, CancelEvent::Report)
             }
+            InternalEvent::Control(request) => self.handle_control_request(request),
         }
     }
 
+    fn handle_control_request(&mut self, request: ControlRequest) -> HandleEventResponse {
+        let ControlRequest { command, responder } = request;
+        debug!(?command, "received control command");
+
+        let (response, event_response) = match command {
+            ControlCommand::Status => {
+                let response = ControlResponse::Status {
+                    run_id: self.run_id.to_string(),
+                    initial_run_count: self.run_stats.initial_run_count,
+                    finished_count: self.run_stats.finished_count,
+                    running: self.running_tests.keys().map(|id| id.to_string()).collect(),
+                    cancel_reason: self.cancel_state.map(|reason| reason.to_static_str()),
+                };
+                (response, HandleEventResponse::None)
+            }
+            ControlCommand::Cancel => {
+                let event_response = self.begin_cancel(
+                    CancelReason::ControlRequest,
+                    CancelEvent::Signal(ShutdownRequest::Control),
+                );
+                let response = if event_response == HandleEventResponse::None {
+                    ControlResponse::ok("the run is already being cancelled")
+                } else {
+                    ControlResponse::ok("cancelling the run")
+                };
+                (response, event_response)
+            }
+            ControlCommand::CancelTest {
+                binary_id,
+                test_name,
+            } => {
+                let instance = self.running_tests.iter().find(|(id, _)| {
+                    id.binary_id.as_str() == binary_id && id.test_name == test_name
+                });
+                let response = match instance {
+                    Some((_, instance)) => {
+                        let req = RunUnitRequest::Signal(SignalRequest::Shutdown(
+                            ShutdownRequest::Control,
+                        ));
+                        if instance.req_tx.send(req).is_ok() {
+                            ControlResponse::ok(format!("terminating {binary_id} {test_name}"))
+                        } else {
+                            // The test has exited, but we haven't processed
+                            // the finish event yet.
+                            ControlResponse::error(format!(
+                                "{binary_id} {test_name} is already finishing"
+                            ))
+                        }
+                    }
+                    None => ControlResponse::error(format!(
+                        "{binary_id} {test_name} isn't currently running"
+                    )),
+                };
+                (response, HandleEventResponse::None)
+            }
+        };
+
+        // The client going away isn't really important.
+        _ = responder.send(response);
+        event_response
+    }
+
     fn new_setup_script(
         &mut self,
         id: ScriptId,
@@ -698,6 +866,8 @@ where
                     HandleEventResponse::None
                 }
             }
+            #[cfg(unix)]
+            SignalEvent::Forward(signal) => HandleEventResponse::Forward(signal),
             SignalEvent::Info(event) => HandleEventResponse::Info(InfoEvent::Signal(event)),
         }
     }
@@ -749,6 +919,16 @@ where
         new_count
     }
 
+    /// Returns the key identifying the max-fail scope this test belongs to, or
+    /// `None` if failures are counted across the whole run.
+    fn max_fail_scope_key(&self, test_instance: &TestInstance<'a>) -> Option<&'a str> {
+        match self.max_fail_scope {
+            MaxFailScope::Run => None,
+            MaxFailScope::Package => Some(test_instance.suite_info.package.id().repr()),
+            MaxFailScope::Binary => Some(test_instance.suite_info.binary_id.as_str()),
+        }
+    }
+
     /// Begin cancellation of a test run. Report it if the current cancel state
     /// is less than the required one.
     ///
@@ -792,6 +972,18 @@ where
     pub(super) fn run_stats(&self) -> RunStats {
         self.run_stats
     }
+
+    /// Returns the durations recorded during this run, if they're being
+    /// recorded.
+    pub(super) fn durations(&self) -> Option<&TestDurations> {
+        self.durations.as_ref()
+    }
+
+    /// Returns the flaky test history, including outcomes recorded during
+    /// this run, if it's being recorded.
+    pub(super) fn flaky_history(&self) -> Option<&FlakyHistory> {
+        self.flaky_history.as_ref()
+    }
 }
 
 fn event_to_cancel_reason(event: ShutdownEvent) -> CancelReason {
@@ -842,6 +1034,7 @@ enum InternalEvent<'a> {
     Signal(SignalEvent),
     Input(InputEvent),
     ReportCancel,
+    Control(ControlRequest),
 }
 
 /// The return result of `handle_event`.
@@ -852,6 +1045,10 @@ enum HandleEventResponse {
     #[cfg_attr(not(unix), expect(dead_code))]
     JobControl(JobControlEvent),
 
+    /// Forward a signal to running units.
+    #[cfg(unix)]
+    Forward(crate::signal::ForwardedSignal),
+
     /// Request information from running units.
     Info(InfoEvent),
 
@@ -911,6 +1108,9 @@ mod tests {
             vec![],
             0,
             MaxFail::All,
+            MaxFailScope::Run,
+            None,
+            None,
         );
         cx.disable_signal_3_times_panic = true;
 
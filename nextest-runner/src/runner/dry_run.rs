@@ -0,0 +1,223 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Dry runs, which show what a test run would do without running any tests.
+//!
+//! A dry run goes through the same steps as a real run up to the point where
+//! tests are started: tests are filtered, settings are resolved for each test,
+//! setup scripts are matched against tests, and tests are put in the order the
+//! runner would start them in.
+
+use super::tests_in_start_order;
+use crate::{
+    config::{EvaluatableProfile, RetryPolicy, ScriptId, TestGroup},
+    helpers::plural,
+    list::{TestInstance, TestList},
+    write_str::WriteStr,
+};
+use owo_colors::{OwoColorize, Style};
+use std::io;
+
+/// What a test run would do, as worked out by [`TestRunnerBuilder::dry_run`].
+///
+/// [`TestRunnerBuilder::dry_run`]: super::TestRunnerBuilder::dry_run
+#[derive(Debug)]
+pub struct DryRun<'a> {
+    profile_name: &'a str,
+    test_threads: usize,
+    setup_scripts: Vec<DryRunScript>,
+    tests: Vec<DryRunTest<'a>>,
+    skip_count: usize,
+}
+
+#[derive(Debug)]
+struct DryRunScript {
+    id: ScriptId,
+    lazy: bool,
+    test_count: usize,
+}
+
+#[derive(Debug)]
+struct DryRunTest<'a> {
+    instance: TestInstance<'a>,
+    test_group: TestGroup,
+    threads_required: usize,
+    retries: usize,
+    banned_reason: Option<&'a str>,
+    // Indexes into `DryRun::setup_scripts`.
+    setup_scripts: Vec<usize>,
+}
+
+impl<'a> DryRun<'a> {
+    pub(super) fn new(
+        test_list: &'a TestList<'a>,
+        profile: &'a EvaluatableProfile<'a>,
+        test_threads: usize,
+        force_retries: Option<RetryPolicy>,
+        allow_banned: bool,
+    ) -> Self {
+        let ecx = profile.filterset_ecx();
+        let scripts: Vec<_> = profile.setup_scripts(test_list).into_iter().collect();
+        let mut setup_scripts: Vec<_> = scripts
+            .iter()
+            .map(|script| DryRunScript {
+                id: script.id.clone(),
+                lazy: script.config.lazy,
+                test_count: 0,
+            })
+            .collect();
+
+        let (start_order, _) = tests_in_start_order(profile, test_list);
+        let mut tests = Vec::new();
+        let mut skip_count = 0;
+        for instance in start_order {
+            if !instance.test_info.filter_match.is_match() {
+                skip_count += 1;
+                continue;
+            }
+
+            let query = instance.to_test_query();
+            let settings = profile.settings_for(&query);
+            let test_scripts: Vec<_> = scripts
+                .iter()
+                .enumerate()
+                .filter(|(_, script)| script.is_enabled(&query, &ecx))
+                .map(|(index, _)| index)
+                .collect();
+            for &index in &test_scripts {
+                setup_scripts[index].test_count += 1;
+            }
+
+            tests.push(DryRunTest {
+                instance,
+                test_group: settings.test_group().clone(),
+                threads_required: settings.threads_required().compute(test_threads),
+                retries: force_retries.unwrap_or_else(|| settings.retries()).count(),
+                banned_reason: if allow_banned {
+                    None
+                } else {
+                    profile.banned_reason(&query)
+                },
+                setup_scripts: test_scripts,
+            });
+        }
+
+        Self {
+            profile_name: profile.name(),
+            test_threads,
+            setup_scripts,
+            tests,
+            skip_count,
+        }
+    }
+
+    /// Returns the number of tests that would be run.
+    pub fn test_count(&self) -> usize {
+        self.tests.len()
+    }
+
+    /// Writes out the dry run in a human-friendly format.
+    pub fn write_human(&self, writer: &mut dyn WriteStr, colorize: bool) -> io::Result<()> {
+        let mut styles = Styles::default();
+        if colorize {
+            styles.colorize();
+        }
+
+        writeln!(
+            writer,
+            "dry run for profile {} with {} test {}",
+            self.profile_name.style(styles.profile),
+            self.test_threads.style(styles.count),
+            if self.test_threads == 1 {
+                "thread"
+            } else {
+                "threads"
+            },
+        )?;
+
+        if !self.setup_scripts.is_empty() {
+            writeln!(writer, "\nsetup scripts, in the order they'd be run:")?;
+            for script in &self.setup_scripts {
+                write!(writer, "  {}", script.id.style(styles.script_id))?;
+                if script.lazy {
+                    write!(writer, " (lazy)")?;
+                }
+                writeln!(
+                    writer,
+                    ": required by {} {}",
+                    script.test_count.style(styles.count),
+                    plural::tests_str(script.test_count),
+                )?;
+            }
+        }
+
+        writeln!(writer, "\ntests, in the order they'd be started:")?;
+        let index_width = self.tests.len().to_string().len();
+        for (index, test) in self.tests.iter().enumerate() {
+            write!(
+                writer,
+                "  {:>index_width$}. {} {}",
+                index + 1,
+                test.instance.suite_info.binary_id.style(styles.binary_id),
+                test.instance.name.style(styles.test_name),
+            )?;
+
+            let mut notes = Vec::new();
+            if let TestGroup::Custom(group) = &test.test_group {
+                notes.push(format!("group: {group}"));
+            }
+            if test.threads_required != 1 {
+                notes.push(format!("threads: {}", test.threads_required));
+            }
+            if test.retries > 0 {
+                notes.push(format!("retries: {}", test.retries));
+            }
+            if !test.setup_scripts.is_empty() {
+                let ids: Vec<_> = test
+                    .setup_scripts
+                    .iter()
+                    .map(|&index| self.setup_scripts[index].id.to_string())
+                    .collect();
+                notes.push(format!("setup scripts: {}", ids.join(", ")));
+            }
+            if !notes.is_empty() {
+                write!(writer, " ({})", notes.join("; "))?;
+            }
+            if let Some(reason) = test.banned_reason {
+                write!(writer, " {}: {reason}", "banned".style(styles.banned))?;
+            }
+            writeln!(writer)?;
+        }
+
+        writeln!(
+            writer,
+            "\n{} {} would be run, {} skipped",
+            self.tests.len().style(styles.count),
+            plural::tests_str(self.tests.len()),
+            self.skip_count.style(styles.count),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Styles {
+    profile: Style,
+    count: Style,
+    script_id: Style,
+    binary_id: Style,
+    test_name: Style,
+    banned: Style,
+}
+
+impl Styles {
+    fn colorize(&mut self) {
+        self.profile = Style::new().bold();
+        self.count = Style::new().bold();
+        self.script_id = Style::new().blue().bold();
+        self.binary_id = Style::new().magenta().bold();
+        self.test_name = Style::new().blue().bold();
+        self.banned = Style::new().red().bold();
+    }
+}
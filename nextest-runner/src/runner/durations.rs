@@ -0,0 +1,256 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Durations of tests from previous runs.
+//!
+//! These are used by [`SchedulingPolicy::LongestFirst`] to start the
+//! longest-running tests as early as possible, so that the end of a run isn't
+//! spent waiting on a few slow tests that happened to be scheduled last.
+
+use crate::{
+    config::SchedulingPolicy,
+    list::{RustTestSuiteStatus, TestInstance, TestList},
+};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    io,
+    time::Duration,
+};
+use tracing::{debug, warn};
+
+/// The name of the file, within the profile's store directory, that durations
+/// are stored in.
+pub(super) const DURATIONS_FILE_NAME: &str = "test-durations.json";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(super) struct TestDurations {
+    // binary ID -> test name -> duration of the last attempt, in milliseconds.
+    tests: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+impl TestDurations {
+    /// Loads durations from `path`.
+    ///
+    /// Durations are only a scheduling hint, so if the file doesn't exist or
+    /// can't be read, this returns an empty set of durations.
+    pub(super) fn load(path: &Utf8Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                debug!("no test durations found at {path}");
+                return Self::default();
+            }
+            Err(error) => {
+                warn!("error reading test durations from {path}: {error}");
+                return Self::default();
+            }
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(durations) => durations,
+            Err(error) => {
+                warn!("error parsing test durations from {path}, ignoring: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes durations out to `path`, creating parent directories as needed.
+    ///
+    /// Durations for tests that no longer exist in `test_list` are left out.
+    pub(super) fn save(&self, test_list: &TestList<'_>, path: &Utf8Path) {
+        let listed = test_list.iter().filter_map(|suite| match &suite.status {
+            RustTestSuiteStatus::Listed { test_cases } => Some((
+                suite.binary_id.as_str(),
+                test_cases.keys().map(String::as_str).collect(),
+            )),
+            RustTestSuiteStatus::Skipped { .. } => None,
+        });
+        let mut durations = self.clone();
+        durations.prune(listed);
+
+        if let Some(parent) = path.parent() {
+            if let Err(error) = std::fs::create_dir_all(parent) {
+                warn!("error creating directory {parent} for test durations: {error}");
+                return;
+            }
+        }
+
+        let file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
+        if let Err(error) = file.write(|f| serde_json::to_writer(f, &durations)) {
+            warn!("error writing test durations to {path}: {error}");
+        }
+    }
+
+    /// Returns the duration recorded for a test, if any.
+    pub(super) fn get(&self, test_instance: &TestInstance<'_>) -> Option<Duration> {
+        let id = test_instance.id();
+        self.get_by_name(id.binary_id.as_str(), id.test_name)
+    }
+
+    /// Records the duration for a test, replacing any earlier value.
+    pub(super) fn record(&mut self, test_instance: &TestInstance<'_>, duration: Duration) {
+        let id = test_instance.id();
+        self.record_by_name(id.binary_id.as_str(), id.test_name, duration);
+    }
+
+    /// Orders tests according to the scheduling policy.
+    pub(super) fn sort_tests(&self, policy: SchedulingPolicy, tests: &mut [TestInstance<'_>]) {
+        sort_by_duration(policy, tests, |test_instance| self.get(test_instance));
+    }
+
+    fn get_by_name(&self, binary_id: &str, test_name: &str) -> Option<Duration> {
+        self.tests
+            .get(binary_id)
+            .and_then(|tests| tests.get(test_name))
+            .map(|&ms| Duration::from_millis(ms))
+    }
+
+    fn record_by_name(&mut self, binary_id: &str, test_name: &str, duration: Duration) {
+        self.tests.entry(binary_id.to_owned()).or_default().insert(
+            test_name.to_owned(),
+            u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+        );
+    }
+
+    /// Removes durations for tests that are no longer in their binary.
+    ///
+    /// `listed` has the tests in each binary that was listed. Binaries that
+    /// weren't listed are left alone, since they might just not have been built
+    /// this time.
+    fn prune<'t>(&mut self, listed: impl IntoIterator<Item = (&'t str, BTreeSet<&'t str>)>) {
+        for (binary_id, test_names) in listed {
+            let Some(tests) = self.tests.get_mut(binary_id) else {
+                continue;
+            };
+            tests.retain(|test_name, _| test_names.contains(test_name.as_str()));
+            if tests.is_empty() {
+                self.tests.remove(binary_id);
+            }
+        }
+    }
+}
+
+fn sort_by_duration<T>(
+    policy: SchedulingPolicy,
+    items: &mut [T],
+    duration: impl Fn(&T) -> Option<Duration>,
+) {
+    match policy {
+        SchedulingPolicy::ListOrder => {}
+        SchedulingPolicy::LongestFirst => {
+            // Tests without a recorded duration may well be slow, so start
+            // them first. The sort is stable, so ties retain list order.
+            items.sort_by_key(|item| Reverse(duration(item).unwrap_or(Duration::MAX)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::tempdir;
+
+    #[test]
+    fn durations_missing_or_invalid() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(DURATIONS_FILE_NAME);
+
+        let durations = TestDurations::load(&path);
+        assert!(durations.tests.is_empty(), "missing file is empty");
+
+        std::fs::write(&path, "not json").unwrap();
+        let durations = TestDurations::load(&path);
+        assert!(durations.tests.is_empty(), "invalid file is empty");
+    }
+
+    #[test]
+    fn durations_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested").join(DURATIONS_FILE_NAME);
+
+        let mut durations = TestDurations::default();
+        durations
+            .tests
+            .entry("my-binary".to_owned())
+            .or_default()
+            .insert("tests::slow".to_owned(), 12_000);
+        durations.save(&TestList::empty(), &path);
+
+        let loaded = TestDurations::load(&path);
+        assert_eq!(loaded.tests, durations.tests, "durations roundtrip");
+    }
+
+    #[test]
+    fn durations_sort() {
+        let mut durations = TestDurations::default();
+        durations.record_by_name("my-binary", "fast", Duration::from_millis(10));
+        durations.record_by_name("my-binary", "slow", Duration::from_secs(5));
+        durations.record_by_name("my-binary", "medium", Duration::from_secs(1));
+        durations.record_by_name("my-binary", "medium-2", Duration::from_secs(1));
+        assert_eq!(
+            durations.get_by_name("my-binary", "slow"),
+            Some(Duration::from_secs(5)),
+            "duration recorded"
+        );
+        assert_eq!(
+            durations.get_by_name("other-binary", "slow"),
+            None,
+            "durations are per binary"
+        );
+
+        let tests = ["fast", "medium", "new", "slow", "medium-2", "new-2"];
+        let sorted = |policy| {
+            let mut order = tests;
+            sort_by_duration(policy, &mut order, |test_name| {
+                durations.get_by_name("my-binary", test_name)
+            });
+            order
+        };
+
+        assert_eq!(
+            sorted(SchedulingPolicy::ListOrder),
+            tests,
+            "list order is unchanged"
+        );
+        assert_eq!(
+            sorted(SchedulingPolicy::LongestFirst),
+            ["new", "new-2", "slow", "medium", "medium-2", "fast"],
+            "unknown tests first, then longest first, with ties in list order"
+        );
+    }
+
+    #[test]
+    fn durations_prune() {
+        let mut durations = TestDurations::default();
+        durations.record_by_name("my-binary", "kept", Duration::from_secs(1));
+        durations.record_by_name("my-binary", "removed", Duration::from_secs(1));
+        durations.record_by_name("removed-binary", "test", Duration::from_secs(1));
+        durations.record_by_name("unlisted-binary", "test", Duration::from_secs(1));
+
+        durations.prune([
+            ("my-binary", BTreeSet::from(["kept", "new"])),
+            ("removed-binary", BTreeSet::new()),
+        ]);
+
+        let remaining: Vec<_> = durations
+            .tests
+            .iter()
+            .flat_map(|(binary_id, tests)| {
+                tests
+                    .keys()
+                    .map(move |test_name| (binary_id.as_str(), test_name.as_str()))
+            })
+            .collect();
+        assert_eq!(
+            remaining,
+            [("my-binary", "kept"), ("unlisted-binary", "test")],
+            "durations for tests that no longer exist are removed"
+        );
+    }
+}
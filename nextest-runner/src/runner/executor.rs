@@ -14,20 +14,27 @@
 use super::HandleSignalResult;
 use crate::{
     config::{
-        EvaluatableProfile, RetryPolicy, ScriptConfig, ScriptId, SetupScriptCommand,
-        SetupScriptExecuteData, SlowTimeout, TestSettings,
+        AccessAudit, EvaluatableProfile, NetworkAccess, Redactions, RetryPolicy, ScriptConfig,
+        ScriptId, SetupScript, SetupScriptCommand, SetupScriptExecuteData, SlowTimeout,
+        TestSettings,
     },
     double_spawn::DoubleSpawnInfo,
-    errors::{ChildError, ChildFdError, ChildStartError, ErrorList},
+    errors::{ChildError, ChildFdError, ChildStartError, DisplayErrorChain, ErrorList},
     list::{TestExecuteContext, TestInstance, TestList},
     reporter::events::{
-        AbortStatus, ExecutionResult, InfoResponse, RetryData, SetupScriptInfoResponse,
-        TestInfoResponse, UnitKind, UnitState,
+        AbortStatus, CgroupLimitsStatus, ExecutionResult, InfoResponse, RetryData,
+        SetupScriptEnvMap, SetupScriptInfoResponse, TestInfoResponse, UnitKind, UnitState,
     },
     runner::{
-        parse_env_file, ExecutorEvent, InternalExecuteStatus, InternalSetupScriptExecuteStatus,
-        InternalTerminateReason, RunUnitQuery, RunUnitRequest, SignalRequest, UnitExecuteStatus,
+        access_report_path, access_reports_dir, annotations::collect_annotations,
+        apply_output_filters, crash_dump_dir, crash_dumps_dir, failed_workdir_path,
+        failed_workdirs_dir, libtest_json::ingest_libtest_json, may_have_crash_dump,
+        parse_cleanup_file, parse_env_file, redact_output, tool_output::detect_memory_error,
+        AccessAuditor, CgroupManager, CrashDumpCollector, ExecutorEvent, InternalExecuteStatus,
+        InternalSetupScriptExecuteStatus, InternalTerminateReason, RunUnitQuery, RunUnitRequest,
+        ScriptCleanup, SignalRequest, UnitExecuteStatus, WorkdirSnapshot, DEFAULT_CLEANUP_TIMEOUT,
     },
+    safe_names::SafeNamesDir,
     target_runner::TargetRunner,
     test_command::{ChildAccumulator, ChildFds},
     test_output::{CaptureStrategy, ChildExecutionOutput, ChildOutput, ChildSplitOutput},
@@ -37,6 +44,7 @@ use nextest_metadata::FilterMatch;
 use quick_junit::ReportUuid;
 use rand::{distributions::OpenClosed01, thread_rng, Rng};
 use std::{
+    collections::BTreeMap,
     num::NonZeroUsize,
     pin::Pin,
     process::{ExitStatus, Stdio},
@@ -50,7 +58,7 @@ use tokio::{
         oneshot,
     },
 };
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 
 #[derive(Debug)]
 pub(super) struct ExecutorContext<'a> {
@@ -62,9 +70,20 @@ pub(super) struct ExecutorContext<'a> {
     capture_strategy: CaptureStrategy,
     // This is Some if the user specifies a retry policy over the command-line.
     force_retries: Option<RetryPolicy>,
+    allow_banned: bool,
+    keep_failed_workdirs: bool,
+    script_cleanup: ScriptCleanup,
+    cgroups: CgroupManager,
+    redactions: Redactions<'a>,
+    // Per-test artifacts for this run, each in a directory named after the
+    // test.
+    failed_workdirs: SafeNamesDir,
+    crash_dumps: SafeNamesDir,
+    access_reports: SafeNamesDir,
 }
 
 impl<'a> ExecutorContext<'a> {
+    #[expect(clippy::too_many_arguments)]
     pub(super) fn new(
         run_id: ReportUuid,
         profile: &'a EvaluatableProfile<'a>,
@@ -73,6 +92,8 @@ impl<'a> ExecutorContext<'a> {
         target_runner: TargetRunner,
         capture_strategy: CaptureStrategy,
         force_retries: Option<RetryPolicy>,
+        allow_banned: bool,
+        keep_failed_workdirs: bool,
     ) -> Self {
         Self {
             run_id,
@@ -82,10 +103,22 @@ impl<'a> ExecutorContext<'a> {
             target_runner,
             capture_strategy,
             force_retries,
+            allow_banned,
+            keep_failed_workdirs,
+            script_cleanup: ScriptCleanup::new(),
+            cgroups: CgroupManager::new(run_id),
+            redactions: profile.redactions(),
+            failed_workdirs: failed_workdirs_dir(profile.store_dir(), run_id),
+            crash_dumps: crash_dumps_dir(profile.store_dir(), run_id),
+            access_reports: access_reports_dir(profile.store_dir(), run_id),
         }
     }
 
     /// Run scripts, returning data about each successfully executed script.
+    ///
+    /// Scripts with `lazy = true` aren't run here. Instead, they're started by
+    /// [`Self::run_test_instance`] just before the first test that requires
+    /// them.
     pub(super) async fn run_setup_scripts(
         &self,
         resp_tx: UnboundedSender<ExecutorEvent<'a>>,
@@ -94,84 +127,191 @@ impl<'a> ExecutorContext<'a> {
         let total = setup_scripts.len();
         debug!("running {} setup scripts", total);
 
-        let mut setup_script_data = SetupScriptExecuteData::new();
+        let mut setup_script_data = SetupScriptExecuteData::new(total);
+        let mut index = 0;
 
         // Run setup scripts one by one.
-        for (index, script) in setup_scripts.into_iter().enumerate() {
-            let this_resp_tx = resp_tx.clone();
-
-            let script_id = script.id.clone();
-            let config = script.config;
-
-            let script_fut = async move {
-                let (req_rx_tx, req_rx_rx) = oneshot::channel();
-                let _ = this_resp_tx.send(ExecutorEvent::SetupScriptStarted {
-                    script_id: script_id.clone(),
-                    config,
-                    index,
-                    total,
-                    req_rx_tx,
-                });
-                let mut req_rx = match req_rx_rx.await {
-                    Ok(req_rx) => req_rx,
-                    Err(_) => {
-                        // The receiver was dropped -- the dispatcher has
-                        // signaled that this unit should exit.
-                        return None;
-                    }
-                };
+        for script in setup_scripts.into_iter() {
+            if script.config.lazy {
+                let remaining = self.tests_requiring(&script);
+                debug!(
+                    script_id = %script.id,
+                    remaining,
+                    "deferring lazy setup script"
+                );
+                setup_script_data.add_lazy_script(script, remaining);
+                continue;
+            }
 
-                let packet = SetupScriptPacket {
-                    script_id: script_id.clone(),
-                    config,
-                };
+            // Run this setup script to completion.
+            if let Some(env_map) = self
+                .run_setup_script_unit(&script, index, total, resp_tx.clone())
+                .await
+            {
+                setup_script_data.add_script(script, env_map);
+            }
+            index += 1;
+        }
 
-                let status = self
-                    .run_setup_script(packet, &this_resp_tx, &mut req_rx)
-                    .await;
+        // Lazy scripts are numbered after the ones run here, in the order
+        // they're started.
+        *setup_script_data.lock_next_lazy_index().await = index;
 
-                // Drain the request receiver, responding to any final requests
-                // that may have been sent.
-                drain_req_rx(req_rx, UnitExecuteStatus::SetupScript(&status));
+        setup_script_data
+    }
 
-                let status = status.into_external();
-                let env_map = status.env_map.clone();
+    /// Runs a single setup script, returning its environment map if it
+    /// succeeded.
+    async fn run_setup_script_unit(
+        &self,
+        script: &SetupScript<'a>,
+        index: usize,
+        total: usize,
+        resp_tx: UnboundedSender<ExecutorEvent<'a>>,
+    ) -> Option<SetupScriptEnvMap> {
+        let script_id = script.id.clone();
+        let config = script.config;
 
-                let _ = this_resp_tx.send(ExecutorEvent::SetupScriptFinished {
-                    script_id,
-                    config,
-                    index,
-                    total,
-                    status,
-                });
+        let (req_rx_tx, req_rx_rx) = oneshot::channel();
+        let _ = resp_tx.send(ExecutorEvent::SetupScriptStarted {
+            script_id: script_id.clone(),
+            config,
+            index,
+            total,
+            req_rx_tx,
+        });
+        let mut req_rx = match req_rx_rx.await {
+            Ok(req_rx) => req_rx,
+            Err(_) => {
+                // The receiver was dropped -- the dispatcher has
+                // signaled that this unit should exit.
+                return None;
+            }
+        };
 
-                env_map.map(|env_map| (script, env_map))
-            };
+        let packet = SetupScriptPacket {
+            script_id: script_id.clone(),
+            config,
+        };
 
-            // Run this setup script to completion.
-            if let Some((script, env_map)) = script_fut.await {
-                setup_script_data.add_script(script, env_map);
+        let status = self.run_setup_script(packet, &resp_tx, &mut req_rx).await;
+
+        // Drain the request receiver, responding to any final requests
+        // that may have been sent.
+        drain_req_rx(req_rx, UnitExecuteStatus::SetupScript(&status));
+
+        let status = status.into_external();
+        let env_map = status.env_map.clone();
+
+        let _ = resp_tx.send(ExecutorEvent::SetupScriptFinished {
+            script_id,
+            config,
+            index,
+            total,
+            status,
+        });
+
+        env_map
+    }
+
+    /// Returns the number of tests in this run that require the given setup
+    /// script.
+    fn tests_requiring(&self, script: &SetupScript<'_>) -> usize {
+        let ecx = self.profile.filterset_ecx();
+        self.test_list
+            .iter_tests()
+            .filter(|test| test.test_info.filter_match.is_match())
+            .filter(|test| script.is_enabled(&test.to_test_query(), &ecx))
+            .count()
+    }
+
+    /// Starts any lazy setup scripts required by this test that haven't been
+    /// started yet, and waits for them to finish.
+    ///
+    /// If a lazy script fails, the dispatcher cancels the run, so the test
+    /// itself won't be started.
+    async fn start_lazy_setup_scripts(
+        &self,
+        test_instance: TestInstance<'a>,
+        setup_script_data: &SetupScriptExecuteData<'a>,
+        resp_tx: &UnboundedSender<ExecutorEvent<'a>>,
+    ) {
+        let query = test_instance.to_test_query();
+        let ecx = self.profile.filterset_ecx();
+        for lazy in setup_script_data.lazy_scripts() {
+            if !lazy.script.is_enabled(&query, &ecx) {
+                continue;
             }
+            lazy.env_map
+                .get_or_init(|| async {
+                    let mut next_index = setup_script_data.lock_next_lazy_index().await;
+                    let index = *next_index;
+                    *next_index += 1;
+                    debug!(
+                        script_id = %lazy.script.id,
+                        test = %test_instance.id(),
+                        "starting lazy setup script"
+                    );
+                    self.run_setup_script_unit(
+                        &lazy.script,
+                        index,
+                        setup_script_data.total(),
+                        resp_tx.clone(),
+                    )
+                    .await
+                })
+                .await;
         }
+    }
 
-        setup_script_data
+    /// Records that a test has finished, running the cleanup commands for any
+    /// lazy setup scripts that no remaining tests require.
+    async fn finish_lazy_setup_scripts(
+        &self,
+        test_instance: TestInstance<'a>,
+        setup_script_data: &SetupScriptExecuteData<'a>,
+    ) {
+        let query = test_instance.to_test_query();
+        let ecx = self.profile.filterset_ecx();
+        for lazy in setup_script_data.lazy_scripts() {
+            if lazy.script.is_enabled(&query, &ecx) && lazy.test_finished() {
+                debug!(
+                    script_id = %lazy.script.id,
+                    "last test requiring lazy setup script finished, cleaning up"
+                );
+                self.script_cleanup
+                    .run_for_script(&lazy.script.id, self.test_list, self.run_id)
+                    .await;
+            }
+        }
+    }
+
+    /// Run the cleanup commands registered by setup scripts.
+    ///
+    /// This must be called once the run is over, on every exit path.
+    pub(super) async fn run_script_cleanup(&self) {
+        self.script_cleanup.run(self.test_list, self.run_id).await;
     }
 
-    /// Returns a future that runs all attempts of a single test instance.
+    /// Returns a future that runs the first attempt of a single test instance.
+    ///
+    /// If the attempt fails and the test has retries remaining, a
+    /// [`PendingRetry`] is sent over `retry_tx`. The scheduler waits out the
+    /// retry delay without holding on to the test's slot, then calls
+    /// [`Self::run_pending_retry`].
     pub(super) async fn run_test_instance(
         &self,
         test_instance: TestInstance<'a>,
-        settings: TestSettings<'a>,
+        settings: Arc<TestSettings<'a>>,
         resp_tx: UnboundedSender<ExecutorEvent<'a>>,
         setup_script_data: Arc<SetupScriptExecuteData<'a>>,
+        retry_tx: UnboundedSender<PendingRetry<'a>>,
     ) {
         debug!(test_name = test_instance.name, "running test");
 
-        let settings = Arc::new(settings);
-
         let retry_policy = self.force_retries.unwrap_or_else(|| settings.retries());
         let total_attempts = retry_policy.count() + 1;
-        let mut backoff_iter = BackoffIter::new(retry_policy);
+        let backoff_iter = BackoffIter::new(retry_policy);
 
         if let FilterMatch::Mismatch { reason } = test_instance.test_info.filter_match {
             // Failure to send means the receiver was dropped.
@@ -182,6 +322,9 @@ impl<'a> ExecutorContext<'a> {
             return;
         }
 
+        self.start_lazy_setup_scripts(test_instance, &setup_script_data, &resp_tx)
+            .await;
+
         let (req_rx_tx, req_rx_rx) = oneshot::channel();
 
         // Wait for the Started event to be processed by the
@@ -190,7 +333,7 @@ impl<'a> ExecutorContext<'a> {
             test_instance,
             req_rx_tx,
         });
-        let mut req_rx = match req_rx_rx.await {
+        let req_rx = match req_rx_rx.await {
             Ok(rx) => rx,
             Err(_) => {
                 // The receiver was dropped -- the dispatcher has signaled that this unit should
@@ -199,98 +342,131 @@ impl<'a> ExecutorContext<'a> {
             }
         };
 
-        let mut attempt = 0;
-        let mut delay = Duration::ZERO;
-        let last_run_status = loop {
-            attempt += 1;
-            let retry_data = RetryData {
-                attempt,
+        let attempt = TestAttempt {
+            test_instance,
+            retry_data: RetryData {
+                attempt: 1,
                 total_attempts,
-            };
+            },
+            settings,
+            setup_script_data,
+            backoff_iter,
+            delay: Duration::ZERO,
+            req_rx,
+            retry_tx,
+        };
 
-            if retry_data.attempt > 1 {
-                // Ensure that the dispatcher believes the run is still ongoing.
-                // If the run is cancelled, the dispatcher will let us know by
-                // dropping the receiver.
-                let (tx, rx) = oneshot::channel();
-                _ = resp_tx.send(ExecutorEvent::RetryStarted {
-                    test_instance,
-                    retry_data,
-                    tx,
-                });
+        self.run_attempt(attempt, resp_tx).await;
+    }
 
-                match rx.await {
-                    Ok(()) => {}
-                    Err(_) => {
-                        // The receiver was dropped -- the dispatcher has
-                        // signaled that this unit should exit.
-                        return;
-                    }
-                }
+    /// Returns a future that runs a retry of a test instance, once the delay
+    /// before the retry has elapsed.
+    pub(super) async fn run_pending_retry(
+        &self,
+        retry: PendingRetry<'a>,
+        resp_tx: UnboundedSender<ExecutorEvent<'a>>,
+    ) {
+        let attempt = retry.attempt;
+
+        // Ensure that the dispatcher believes the run is still ongoing. If the
+        // run is cancelled, the dispatcher will let us know by dropping the
+        // receiver.
+        let (tx, rx) = oneshot::channel();
+        _ = resp_tx.send(ExecutorEvent::RetryStarted {
+            test_instance: attempt.test_instance,
+            retry_data: attempt.retry_data,
+            tx,
+        });
+
+        match rx.await {
+            Ok(()) => {}
+            Err(_) => {
+                // The receiver was dropped -- the dispatcher has signaled that
+                // this unit should exit.
+                return;
             }
+        }
 
-            // Some of this information is only useful for event reporting, but
-            // it's a lot easier to pass it in than to try and hook on
-            // additional information later.
-            let packet = TestPacket {
-                test_instance,
-                retry_data,
-                settings: settings.clone(),
-                setup_script_data: setup_script_data.clone(),
-                delay_before_start: delay,
-            };
+        self.run_attempt(attempt, resp_tx).await;
+    }
 
-            let run_status = self.run_test(packet.clone(), &resp_tx, &mut req_rx).await;
-
-            if run_status.result.is_success() {
-                // The test succeeded.
-                break run_status;
-            } else if retry_data.attempt < retry_data.total_attempts {
-                // Retry this test: send a retry event, then retry the loop.
-                delay = backoff_iter
-                    .next()
-                    .expect("backoff delay must be non-empty");
-
-                let run_status = run_status.into_external();
-                let previous_result = run_status.result;
-                let previous_slow = run_status.is_slow;
-
-                let _ = resp_tx.send(ExecutorEvent::AttemptFailedWillRetry {
-                    test_instance,
-                    failure_output: settings.failure_output(),
-                    run_status,
-                    delay_before_next_attempt: delay,
-                });
+    async fn run_attempt(
+        &self,
+        mut attempt: TestAttempt<'a>,
+        resp_tx: UnboundedSender<ExecutorEvent<'a>>,
+    ) {
+        // Some of this information is only useful for event reporting, but
+        // it's a lot easier to pass it in than to try and hook on additional
+        // information later.
+        let packet = TestPacket {
+            test_instance: attempt.test_instance,
+            retry_data: attempt.retry_data,
+            settings: attempt.settings.clone(),
+            setup_script_data: attempt.setup_script_data.clone(),
+            delay_before_start: attempt.delay,
+        };
 
-                handle_delay_between_attempts(
-                    &packet,
-                    previous_result,
-                    previous_slow,
-                    delay,
-                    &mut req_rx,
-                )
-                .await;
-            } else {
-                // This test failed and is out of retries.
-                break run_status;
+        let run_status = self
+            .run_test(packet.clone(), &resp_tx, &mut attempt.req_rx)
+            .await;
+
+        // Banned tests would be banned on every attempt, so don't retry them.
+        let banned = matches!(
+            run_status.output,
+            ChildExecutionOutput::StartError(ChildStartError::Banned { .. })
+        );
+        if !run_status.result.is_success() && !attempt.retry_data.is_last_attempt() && !banned {
+            // Retry this test: send a retry event, then hand the test back to
+            // the scheduler.
+            let delay = attempt
+                .backoff_iter
+                .next()
+                .expect("backoff delay must be non-empty");
+
+            let run_status = run_status.into_external();
+            let previous_result = run_status.result;
+            let previous_slow = run_status.is_slow;
+
+            let _ = resp_tx.send(ExecutorEvent::AttemptFailedWillRetry {
+                test_instance: attempt.test_instance,
+                failure_output: attempt.settings.failure_output(),
+                run_status,
+                delay_before_next_attempt: delay,
+            });
+
+            let retry_tx = attempt.retry_tx.clone();
+            let retry = PendingRetry {
+                packet,
+                previous_result,
+                previous_slow,
+                attempt: attempt.next_attempt(delay),
+            };
+            if retry_tx.send(retry).is_err() {
+                // The scheduler has shut down, so we should too.
+                debug!("retry_tx.send failed, shutting down");
             }
-        };
+            return;
+        }
 
-        drain_req_rx(req_rx, UnitExecuteStatus::Test(&last_run_status));
+        drain_req_rx(attempt.req_rx, UnitExecuteStatus::Test(&run_status));
 
         // At this point, either:
         // * the test has succeeded, or
         // * the test has failed and we've run out of retries.
         // In either case, the test is finished.
-        let last_run_status = last_run_status.into_external();
+        let last_run_status = run_status.into_external();
         let _ = resp_tx.send(ExecutorEvent::Finished {
-            test_instance,
-            success_output: settings.success_output(),
-            failure_output: settings.failure_output(),
-            junit_store_success_output: settings.junit_store_success_output(),
-            junit_store_failure_output: settings.junit_store_failure_output(),
+            test_instance: attempt.test_instance,
+            success_output: attempt.settings.success_output(),
+            failure_output: attempt.settings.failure_output(),
+            junit_store_success_output: attempt.settings.junit_store_success_output(),
+            junit_store_failure_output: attempt.settings.junit_store_failure_output(),
+            tracking_issue: attempt.settings.tracking_issue().map(str::to_owned),
             last_run_status,
         });
+
+        self.finish_lazy_setup_scripts(attempt.test_instance, &attempt.setup_script_data)
+            .await;
     }
 
     // ---
@@ -352,7 +528,7 @@ impl<'a> ExecutorContext<'a> {
             }
         }
 
-        let (mut child, env_path) = cmd
+        let (mut child, paths) = cmd
             .spawn()
             .map_err(|error| ChildStartError::Spawn(Arc::new(error)))?;
         let child_pid = child
@@ -387,6 +563,7 @@ impl<'a> ExecutorContext<'a> {
         let mut cx = UnitContext {
             packet: UnitPacket::SetupScript(script.clone()),
             slow_after: None,
+            redactions: self.redactions.clone(),
         };
 
         let (res, leaked) = {
@@ -497,7 +674,7 @@ impl<'a> ExecutorContext<'a> {
                                 // let the script finish.
                             }
                             RunUnitRequest::Query(RunUnitQuery::GetInfo(sender)) => {
-                                _ = sender.send(script.info_response(
+                                _ = sender.send(cx.info_response(
                                     UnitState::Running {
                                         pid: child_pid,
                                         time_taken:             stopwatch.snapshot().active,
@@ -545,10 +722,25 @@ impl<'a> ExecutorContext<'a> {
         let exec_result = status
             .unwrap_or_else(|| create_execution_result(exit_status, &child_acc.errors, leaked));
 
-        // Read from the environment map. If there's an error here, add it to the list of child errors.
         let mut errors: Vec<_> = child_acc.errors.into_iter().map(ChildError::from).collect();
+
+        // Register cleanup commands even if the script failed, since it may have created some
+        // resources before failing.
+        match parse_cleanup_file(&paths.cleanup_path).await {
+            Ok(commands) => {
+                let timeout = script
+                    .config
+                    .cleanup_timeout
+                    .unwrap_or(DEFAULT_CLEANUP_TIMEOUT);
+                self.script_cleanup
+                    .register(&script.script_id, timeout, commands);
+            }
+            Err(error) => errors.push(ChildError::SetupScriptOutput(error)),
+        }
+
+        // Read from the environment map. If there's an error here, add it to the list of child errors.
         let env_map = if exec_result.is_success() {
-            match parse_env_file(&env_path).await {
+            match parse_env_file(&paths.env_path).await {
                 Ok(env_map) => Some(env_map),
                 Err(error) => {
                     errors.push(ChildError::SetupScriptOutput(error));
@@ -564,7 +756,7 @@ impl<'a> ExecutorContext<'a> {
             slow_after: cx.slow_after,
             output: ChildExecutionOutput::Output {
                 result: Some(exec_result),
-                output: child_acc.output.freeze(),
+                output: redact_output(&cx.redactions, child_acc.output.freeze()),
                 errors: ErrorList::new(UnitKind::WAITING_ON_SCRIPT_MESSAGE, errors),
             },
             result: exec_result,
@@ -593,7 +785,17 @@ impl<'a> ExecutorContext<'a> {
                 slow_after: None,
                 output: ChildExecutionOutput::StartError(error),
                 result: ExecutionResult::ExecFail,
+                failure_class: None,
+                annotations: BTreeMap::new(),
+                exec_time: None,
+                startup_time: None,
                 stopwatch_end: stopwatch.snapshot(),
+                cgroup_limits: CgroupLimitsStatus::NotRequested,
+                kept_tmpdir: None,
+                kept_workdir: None,
+                access_report: None,
+                access_violations: Vec::new(),
+                crash_dump: None,
             },
         }
     }
@@ -606,36 +808,170 @@ impl<'a> ExecutorContext<'a> {
         resp_tx: &UnboundedSender<ExecutorEvent<'a>>,
         req_rx: &mut UnboundedReceiver<RunUnitRequest<'a>>,
     ) -> Result<InternalExecuteStatus<'a>, ChildStartError> {
+        if !self.allow_banned {
+            if let Some(reason) = self
+                .profile
+                .banned_reason(&test.test_instance.to_test_query())
+            {
+                return Err(ChildStartError::Banned {
+                    reason: reason.to_owned(),
+                });
+            }
+        }
+
+        let access_audit = test.settings.access_audit();
+        let auditor = if access_audit.is_enabled() {
+            AccessAuditor::new().map_err(|error| ChildStartError::AccessAudit(Arc::new(error)))?
+        } else {
+            None
+        };
+
         let ctx = TestExecuteContext {
             double_spawn: &self.double_spawn,
             target_runner: &self.target_runner,
         };
-        let mut cmd =
-            test.test_instance
-                .make_command(&ctx, self.test_list, test.settings.run_extra_args());
+        let mut cmd = test.test_instance.make_command(
+            &ctx,
+            self.test_list,
+            test.settings.wrapper(),
+            test.settings.run_extra_args(),
+            test.settings.libtest_json(),
+            auditor
+                .as_ref()
+                .map_or(&[][..], |auditor| auditor.command_prefix()),
+        );
         let command_mut = cmd.command_mut();
 
         // Debug environment variable for testing.
         command_mut.env("__NEXTEST_ATTEMPT", format!("{}", test.retry_data.attempt));
         command_mut.env("NEXTEST_RUN_ID", format!("{}", self.run_id));
-        command_mut.stdin(Stdio::null());
+        command_mut.env(
+            "NEXTEST_TEST_ID",
+            format!("{}", test.test_instance.id().uuid(self.run_id)),
+        );
+        command_mut.env("NEXTEST_ATTEMPT", format!("{}", test.retry_data.attempt));
+        if test.settings.libtest_json() {
+            // Lets test binaries built with a stable toolchain accept
+            // `-Z unstable-options`.
+            command_mut.env("RUSTC_BOOTSTRAP", "1");
+        }
+        crate::test_command::apply_stdin(
+            command_mut,
+            test.settings.stdin(),
+            self.test_list.workspace_root(),
+        )
+        .map_err(|(path, error)| ChildStartError::StdinFile {
+            path,
+            error: Arc::new(error),
+        })?;
         test.setup_script_data.apply(
             &test.test_instance.to_test_query(),
             &self.profile.filterset_ecx(),
             command_mut,
         );
+
+        // This is set after setup script environment variables are applied,
+        // so that isolation takes precedence. The directory is removed once
+        // `isolated_home` is dropped at the end of this function, after the
+        // test (and any leaked processes we waited for) have exited.
+        let mut isolated_home = if test.settings.isolate_home() {
+            let dir = crate::test_command::create_isolated_home(command_mut, self.run_id)
+                .map_err(|error| ChildStartError::TempDir(Arc::new(error)))?;
+            Some(dir)
+        } else {
+            None
+        };
+
+        // Like `isolated_home`, this is cleaned up at the end of this
+        // function.
+        let _isolated_desktop = crate::test_command::create_isolated_desktop(
+            command_mut,
+            self.run_id,
+            test.settings.isolate_desktop(),
+        )
+        .await
+        .map_err(|error| ChildStartError::IsolateDesktop(Arc::new(error)))?;
+
+        // This is either deleted or kept at the end of this function, once the
+        // test's result is known.
+        let isolate_tmpdir = test.settings.isolate_tmpdir();
+        let mut isolated_tmpdir = if isolate_tmpdir.is_isolated() {
+            let dir = crate::test_command::create_isolated_tmpdir(command_mut, self.run_id)
+                .map_err(|error| ChildStartError::TempDir(Arc::new(error)))?;
+            Some(dir)
+        } else {
+            None
+        };
+
+        // This is checked once the test exits, and cleaned up at the end of
+        // this function.
+        let cancel_ack = if test.settings.cooperative_cancel() {
+            let cancel_ack = crate::test_command::create_cancel_ack(command_mut, self.run_id)
+                .map_err(|error| ChildStartError::TempDir(Arc::new(error)))?;
+            Some(cancel_ack)
+        } else {
+            None
+        };
+
+        // Suppressed variables are removed last, so that they're removed
+        // regardless of where they were set.
+        crate::test_command::apply_suppress_env(command_mut, test.settings.suppress_env());
+
         super::os::set_process_group(command_mut);
+        let crash_dumps = test.settings.crash_dumps();
+        if crash_dumps.is_enabled() {
+            super::os::enable_core_dumps(command_mut);
+        }
+        let rlimits = test.settings.rlimits();
+        if !rlimits.is_empty() {
+            debug!(test = %test.test_instance.id(), %rlimits, "applying resource limits");
+        }
+        super::os::set_rlimits(command_mut, rlimits);
+        if let Some(priority) = test.settings.priority() {
+            debug!(test = %test.test_instance.id(), %priority, "setting process priority");
+            super::os::set_priority(command_mut, priority);
+        }
+        let cgroup_limits = test.settings.cgroup_limits();
+        if !cgroup_limits.is_empty() {
+            debug!(test = %test.test_instance.id(), %cgroup_limits, "applying cgroup limits");
+        }
+        // Like `isolated_home`, the cgroup is removed at the end of this function.
+        let (cgroup_limits, _cgroup) = self.cgroups.apply(command_mut, cgroup_limits);
+        // This is applied after cgroups, since moving into a cgroup from a new user namespace can
+        // fail.
+        let network = test.settings.network();
+        if network == NetworkAccess::Deny {
+            debug!(test = %test.test_instance.id(), "denying network access");
+        }
+        super::os::set_network_access(command_mut, network);
+        // Pre-exec hooks are run by the double-spawned child, after everything above has been
+        // applied to it.
+        crate::double_spawn::set_pre_exec_hooks(command_mut, self.profile.pre_exec());
 
         // If creating a job fails, we might be on an old system. Ignore this -- job objects are a
         // best-effort thing.
         let job = super::os::Job::create().ok();
 
+        if let Some(cpu_set) = test.settings.cpu_set() {
+            debug!(test = %test.test_instance.id(), %cpu_set, "pinning test to CPUs");
+            super::os::set_cpu_set(command_mut, job.as_ref(), cpu_set);
+        }
+
+        // The command is consumed by spawning it, so capture what's needed to
+        // reproduce a failure up front.
+        let workdir_snapshot = self
+            .keep_failed_workdirs
+            .then(|| WorkdirSnapshot::capture(command_mut));
+        let crash_dump_collector = crash_dumps.is_enabled().then(|| {
+            CrashDumpCollector::capture(command_mut, &test.test_instance.suite_info.binary_path)
+        });
+
         let crate::test_command::Child {
             mut child,
             child_fds,
         } = cmd
             .spawn(self.capture_strategy)
-            .map_err(|error| ChildStartError::Spawn(Arc::new(error)))?;
+            .map_err(|error| super::os::spawn_error(network, error))?;
 
         // Note: The PID stored here must be used with care -- it might be
         // outdated and have been reused by the kernel in case the process
@@ -660,16 +996,24 @@ impl<'a> ExecutorContext<'a> {
         let mut interval_sleep = std::pin::pin!(crate::time::pausable_sleep(slow_timeout.period));
 
         let mut timeout_hit = 0;
+        // The time at which the test first produced output, used to estimate
+        // how long the test binary took to start up.
+        let mut first_output_after = None;
 
         let mut cx = UnitContext {
             packet: UnitPacket::Test(test.clone()),
             slow_after: None,
+            redactions: self.redactions.clone(),
         };
 
         let (res, leaked) = {
             let res = loop {
                 tokio::select! {
-                    () = child_acc.fill_buf(), if !child_acc.fds.is_done() => {}
+                    () = child_acc.fill_buf(), if !child_acc.fds.is_done() => {
+                        if first_output_after.is_none() && !child_acc.output.is_empty() {
+                            first_output_after = Some(stopwatch.snapshot().active);
+                        }
+                    }
                     res = child.wait() => {
                         // The test finished executing.
                         break res;
@@ -773,7 +1117,7 @@ impl<'a> ExecutorContext<'a> {
                                 // let the test finish.
                             }
                             RunUnitRequest::Query(RunUnitQuery::GetInfo(tx)) => {
-                                _ = tx.send(test.info_response(
+                                _ = tx.send(cx.info_response(
                                     UnitState::Running {
                                         pid: child_pid,
                                         time_taken: stopwatch.snapshot().active,
@@ -819,17 +1163,228 @@ impl<'a> ExecutorContext<'a> {
         let exit_status = exit_status.expect("None always results in early return");
         let exec_result = status
             .unwrap_or_else(|| create_execution_result(exit_status, &child_acc.errors, leaked));
+        // Some runners, such as WebAssembly runtimes, report aborts through exit codes.
+        let exec_result = match exec_result {
+            ExecutionResult::Fail {
+                abort_status: None,
+                leaked,
+            } => ExecutionResult::Fail {
+                abort_status: self
+                    .target_runner
+                    .for_build_platform(test.test_instance.suite_info.build_platform)
+                    .and_then(|runner| runner.abort_status(exit_status)),
+                leaked,
+            },
+            other => other,
+        };
+
+        // Redact sensitive data before anything else sees the output.
+        let output = redact_output(&cx.redactions, child_acc.output.freeze());
+        // libtest's events are removed before anything else looks at the
+        // output.
+        let (output, exec_time) = if test.settings.libtest_json() {
+            ingest_libtest_json(output, test.test_instance.name)
+        } else {
+            (output, None)
+        };
+        // If libtest reported how long the test itself took, the rest of the
+        // process's lifetime is overhead. Otherwise, use the time until the
+        // harness first printed anything, such as libtest's "running 1 test".
+        let startup_time = match exec_time {
+            Some(exec_time) => Some(stopwatch.snapshot().active.saturating_sub(exec_time)),
+            None => first_output_after,
+        };
+        // If the test ran under an error-checking tool, distinguish memory errors from ordinary
+        // failures.
+        let exec_result = match exec_result {
+            ExecutionResult::Fail { .. } => {
+                match detect_memory_error(test.settings.wrapper(), exit_status, &output) {
+                    Some(tool) => ExecutionResult::MemoryError { tool },
+                    None => exec_result,
+                }
+            }
+            other => other,
+        };
+        // Tests only acknowledge being stopped if they were asked to, whether
+        // due to a timeout or to the run being cancelled.
+        let exec_result = match &cancel_ack {
+            Some(cancel_ack) if !exec_result.is_success() && cancel_ack.is_acknowledged() => {
+                ExecutionResult::CancelAcknowledged
+            }
+            _ => exec_result,
+        };
+
+        // Write out the access report, and check it against the profile's
+        // policy if requested. Tests that contacted hosts outside the policy
+        // fail even if they otherwise passed.
+        let mut access_report = None;
+        let mut access_violations = Vec::new();
+        if let Some(auditor) = auditor {
+            match auditor.finish() {
+                Ok(report) => {
+                    if access_audit == AccessAudit::Enforce {
+                        access_violations = report.violations(self.profile.access_policy());
+                    }
+                    match access_report_path(
+                        &self.access_reports,
+                        test.test_instance.id(),
+                        test.retry_data.attempt,
+                    ) {
+                        Ok(path) => match report.write(&path) {
+                            Ok(()) => access_report = Some(path),
+                            Err(error) => warn!(
+                                "error writing access report for {} to {path}: {error}",
+                                test.test_instance.id(),
+                            ),
+                        },
+                        Err(error) => warn!(
+                            "error writing access report for {}: {}",
+                            test.test_instance.id(),
+                            DisplayErrorChain::new(error),
+                        ),
+                    }
+                }
+                Err(error) => warn!(
+                    "error reading access audit log for {}: {error}",
+                    test.test_instance.id(),
+                ),
+            }
+        }
+        let exec_result = if exec_result.is_success() && !access_violations.is_empty() {
+            ExecutionResult::AccessViolation
+        } else {
+            exec_result
+        };
+
+        let failure_class = if exec_result.is_success() {
+            None
+        } else {
+            self.profile
+                .failure_classes()
+                .classify(&output)
+                .map(str::to_owned)
+        };
+
+        // Like failure classes, annotations are collected before filters are
+        // applied.
+        let annotations = if test.settings.annotations() {
+            collect_annotations(&output)
+        } else {
+            BTreeMap::new()
+        };
+
+        // A test that crashed has always failed, so this doesn't need to check
+        // the result.
+        let crash_dump = match crash_dump_collector {
+            Some(collector) if may_have_crash_dump(exit_status) => {
+                match crash_dump_dir(
+                    &self.crash_dumps,
+                    test.test_instance.id(),
+                    test.retry_data.attempt,
+                ) {
+                    Ok(dir) => match collector
+                        .collect(child_pid, &dir, self.profile.crash_backtrace())
+                        .await
+                    {
+                        Ok(crash_dump) => crash_dump,
+                        Err(error) => {
+                            warn!(
+                                "error collecting crash dump for {} to {dir}: {error}",
+                                test.test_instance.id(),
+                            );
+                            None
+                        }
+                    },
+                    Err(error) => {
+                        warn!(
+                            "error collecting crash dump for {}: {}",
+                            test.test_instance.id(),
+                            DisplayErrorChain::new(error),
+                        );
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // Keep the failed attempt's on-disk state around for inspection. This
+        // takes the temporary and home directories, and uses the unfiltered
+        // output.
+        let kept_workdir = match workdir_snapshot {
+            Some(snapshot) if !exec_result.is_success() => {
+                match failed_workdir_path(
+                    &self.failed_workdirs,
+                    test.test_instance.id(),
+                    test.retry_data.attempt,
+                ) {
+                    Ok(dir) => match snapshot.write(
+                        &dir,
+                        &output,
+                        isolated_tmpdir.take(),
+                        isolated_home.take(),
+                    ) {
+                        Ok(()) => Some(dir),
+                        Err(error) => {
+                            warn!(
+                                "error keeping workdir for {} at {dir}: {error}",
+                                test.test_instance.id(),
+                            );
+                            None
+                        }
+                    },
+                    Err(error) => {
+                        warn!(
+                            "error keeping workdir for {}: {}",
+                            test.test_instance.id(),
+                            DisplayErrorChain::new(error),
+                        );
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // Filters are applied after classifying the failure, so that failure
+        // classes match what the test actually printed.
+        let output = apply_output_filters(
+            test.settings.output_filters(),
+            output,
+            self.test_list.workspace_root(),
+            test.test_instance.id(),
+        )
+        .await;
+
+        // Keep the temporary directory around for debugging if requested.
+        // Otherwise, it's deleted when dropped here.
+        let kept_tmpdir = match isolated_tmpdir {
+            Some(dir) if isolate_tmpdir.keeps_on_failure() && !exec_result.is_success() => {
+                Some(dir.into_path())
+            }
+            _ => None,
+        };
 
         Ok(InternalExecuteStatus {
             test,
             slow_after: cx.slow_after,
             output: ChildExecutionOutput::Output {
                 result: Some(exec_result),
-                output: child_acc.output.freeze(),
+                output,
                 errors: ErrorList::new(UnitKind::WAITING_ON_TEST_MESSAGE, child_acc.errors),
             },
             result: exec_result,
+            failure_class,
+            annotations,
+            exec_time,
+            startup_time,
             stopwatch_end: stopwatch.snapshot(),
+            cgroup_limits,
+            kept_tmpdir,
+            kept_workdir,
+            access_report,
+            access_violations,
+            crash_dump,
         })
     }
 }
@@ -903,6 +1458,93 @@ impl Iterator for BackoffIter {
     }
 }
 
+/// A unit of work for the test scheduler.
+pub(super) enum TestWork<'a> {
+    /// The first attempt of a test.
+    Initial {
+        test_instance: TestInstance<'a>,
+        retry_tx: UnboundedSender<PendingRetry<'a>>,
+    },
+
+    /// A retry of a test whose previous attempt failed.
+    Retry(Box<PendingRetry<'a>>),
+}
+
+impl<'a> TestWork<'a> {
+    pub(super) fn test_instance(&self) -> TestInstance<'a> {
+        match self {
+            Self::Initial { test_instance, .. } => *test_instance,
+            Self::Retry(retry) => retry.attempt.test_instance,
+        }
+    }
+
+    /// Returns the attempt number of this unit of work, starting from 1.
+    pub(super) fn attempt(&self) -> usize {
+        match self {
+            Self::Initial { .. } => 1,
+            Self::Retry(retry) => retry.attempt.retry_data.attempt,
+        }
+    }
+}
+
+/// State carried across the attempts of a single test.
+struct TestAttempt<'a> {
+    test_instance: TestInstance<'a>,
+    retry_data: RetryData,
+    settings: Arc<TestSettings<'a>>,
+    setup_script_data: Arc<SetupScriptExecuteData<'a>>,
+    backoff_iter: BackoffIter,
+    delay: Duration,
+    req_rx: UnboundedReceiver<RunUnitRequest<'a>>,
+    retry_tx: UnboundedSender<PendingRetry<'a>>,
+}
+
+impl TestAttempt<'_> {
+    fn next_attempt(self, delay: Duration) -> Self {
+        Self {
+            retry_data: RetryData {
+                attempt: self.retry_data.attempt + 1,
+                total_attempts: self.retry_data.total_attempts,
+            },
+            delay,
+            ..self
+        }
+    }
+}
+
+/// A test that failed and is waiting to be retried.
+///
+/// The delay before the next attempt is waited out by the scheduler's input
+/// stream rather than by the test's own task, so a test that's backing off
+/// doesn't occupy a slot that another test could use.
+pub(super) struct PendingRetry<'a> {
+    // The packet for the previous attempt, used to respond to info queries.
+    packet: TestPacket<'a>,
+    previous_result: ExecutionResult,
+    previous_slow: bool,
+    attempt: TestAttempt<'a>,
+}
+
+impl<'a> PendingRetry<'a> {
+    pub(super) fn settings(&self) -> Arc<TestSettings<'a>> {
+        self.attempt.settings.clone()
+    }
+
+    /// Waits for the delay before the next attempt, responding to requests from
+    /// the dispatcher in the meantime.
+    pub(super) async fn wait_for_delay(mut self) -> Self {
+        handle_delay_between_attempts(
+            &self.packet,
+            self.previous_result,
+            self.previous_slow,
+            self.attempt.delay,
+            &mut self.attempt.req_rx,
+        )
+        .await;
+        self
+    }
+}
+
 /// Either a test or a setup script, along with information about how long the
 /// test took.
 pub(super) struct UnitContext<'a> {
@@ -911,6 +1553,7 @@ pub(super) struct UnitContext<'a> {
     // should live -- many parts of the request-response system need various
     // pieces of this code.
     slow_after: Option<Duration>,
+    redactions: Redactions<'a>,
 }
 
 impl<'a> UnitContext<'a> {
@@ -923,6 +1566,19 @@ impl<'a> UnitContext<'a> {
         state: UnitState,
         output: ChildExecutionOutput,
     ) -> InfoResponse<'a> {
+        // In-progress output is shown too, so it needs to be redacted as well.
+        let output = match output {
+            ChildExecutionOutput::Output {
+                result,
+                output,
+                errors,
+            } => ChildExecutionOutput::Output {
+                result,
+                output: redact_output(&self.redactions, output),
+                errors,
+            },
+            ChildExecutionOutput::StartError(error) => ChildExecutionOutput::StartError(error),
+        };
         match &self.packet {
             UnitPacket::SetupScript(packet) => packet.info_response(state, output),
             UnitPacket::Test(packet) => packet.info_response(state, output),
@@ -1081,6 +1737,11 @@ async fn handle_delay_between_attempts<'a>(
                             waiting_stopwatch.resume();
                         }
                     }
+                    #[cfg(unix)]
+                    RunUnitRequest::Signal(SignalRequest::Forward(_)) => {
+                        // There's no test process to forward the signal to
+                        // between attempts.
+                    }
                     RunUnitRequest::Signal(SignalRequest::Shutdown(_)) => {
                         // The run was cancelled, so go ahead and perform a
                         // shutdown.
@@ -1138,12 +1799,11 @@ async fn detect_fd_leaks<'a>(
     stopwatch: &mut StopwatchStart,
     req_rx: &mut UnboundedReceiver<RunUnitRequest<'a>>,
 ) -> bool {
-    loop {
-        // Ignore stop and continue events here since the leak timeout should be very small.
-        // TODO: we may want to consider them.
-        let mut sleep = std::pin::pin!(tokio::time::sleep(leak_timeout));
-        let waiting_stopwatch = crate::time::stopwatch();
+    let mut sleep = std::pin::pin!(crate::time::pausable_sleep(leak_timeout));
+    #[cfg_attr(not(unix), expect(unused_mut))]
+    let mut waiting_stopwatch = crate::time::stopwatch();
 
+    loop {
         tokio::select! {
             // All of the branches here need to check for
             // `!child_acc.fds.is_done()`, because if child_fds is done we want
@@ -1159,8 +1819,38 @@ async fn detect_fd_leaks<'a>(
                 let req = recv.expect("a RecvError should never happen here");
 
                 match req {
+                    #[cfg(unix)]
+                    RunUnitRequest::Signal(SignalRequest::Stop(sender)) => {
+                        // The process is done executing, but it may have
+                        // leaked processes in its process group. Stop them,
+                        // and don't let the leak timeout elapse while
+                        // nextest is stopped.
+                        stopwatch.pause();
+                        sleep.as_mut().pause();
+                        waiting_stopwatch.pause();
+                        super::os::job_control_process_group(
+                            child_pid,
+                            crate::signal::JobControlEvent::Stop,
+                        );
+                        _ = sender.send(());
+                    }
+                    #[cfg(unix)]
+                    RunUnitRequest::Signal(SignalRequest::Continue) => {
+                        // Possible to receive a Continue at the beginning of
+                        // this wait.
+                        if sleep.is_paused() {
+                            stopwatch.resume();
+                            sleep.as_mut().resume();
+                            waiting_stopwatch.resume();
+                            super::os::job_control_process_group(
+                                child_pid,
+                                crate::signal::JobControlEvent::Continue,
+                            );
+                        }
+                    }
                     RunUnitRequest::Signal(_) => {
-                        // The process is done executing, so signals are moot.
+                        // The process is done executing, so other signals are
+                        // moot.
                     }
                     RunUnitRequest::OtherCancel => {
                         // Ignore non-signal cancellation requests -- let the
@@ -1244,6 +1934,11 @@ async fn handle_signal_request<'a>(
             }
             HandleSignalResult::JobControl
         }
+        #[cfg(unix)]
+        SignalRequest::Forward(signal) => {
+            super::os::forward_signal_to_child(child, signal);
+            HandleSignalResult::Forwarded
+        }
         SignalRequest::Shutdown(event) => {
             let res = super::os::terminate_child(
                 cx,
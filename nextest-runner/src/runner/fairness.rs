@@ -0,0 +1,74 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Interleaving tests from different packages or binaries.
+//!
+//! By default, tests are started in list order, which means that all the tests
+//! in a binary are started before any tests in the next one. In large
+//! workspaces, this can mean that failures in packages listed late aren't seen
+//! until most of the run is over. With [`SchedulingFairness`], tests from each
+//! package or binary are started in turn instead.
+
+use crate::{config::SchedulingFairness, list::TestInstance};
+use indexmap::IndexMap;
+use std::{collections::VecDeque, hash::Hash};
+
+/// Reorders tests according to the fairness setting.
+///
+/// The relative order of tests within each package or binary is preserved, so
+/// this composes with the order set by the scheduling policy.
+pub(super) fn apply_fairness<'a>(
+    fairness: SchedulingFairness,
+    tests: Vec<TestInstance<'a>>,
+) -> Vec<TestInstance<'a>> {
+    match fairness {
+        SchedulingFairness::None => tests,
+        SchedulingFairness::RoundRobinPackage => {
+            interleave_by_key(tests, |test| test.suite_info.package.id())
+        }
+        SchedulingFairness::RoundRobinBinary => {
+            interleave_by_key(tests, |test| &test.suite_info.binary_id)
+        }
+    }
+}
+
+/// Takes one item from each group in turn, where groups are ordered by where
+/// their first item appears.
+fn interleave_by_key<T, K: Eq + Hash>(items: Vec<T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    let len = items.len();
+    let mut groups: IndexMap<K, VecDeque<T>> = IndexMap::new();
+    for item in items {
+        groups.entry(key(&item)).or_default().push_back(item);
+    }
+
+    let mut groups: Vec<_> = groups.into_values().collect();
+    let mut out = Vec::with_capacity(len);
+    while !groups.is_empty() {
+        groups.retain_mut(|group| match group.pop_front() {
+            Some(item) => {
+                out.push(item);
+                !group.is_empty()
+            }
+            None => false,
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleave() {
+        let items = vec![("a", 1), ("a", 2), ("a", 3), ("b", 1), ("c", 1), ("c", 2)];
+        let interleaved = interleave_by_key(items, |(key, _)| *key);
+        assert_eq!(
+            interleaved,
+            vec![("a", 1), ("b", 1), ("c", 1), ("a", 2), ("c", 2), ("a", 3)],
+        );
+
+        let empty: Vec<(&str, i32)> = interleave_by_key(Vec::new(), |(key, _)| *key);
+        assert!(empty.is_empty());
+    }
+}
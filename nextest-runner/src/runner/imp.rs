@@ -1,13 +1,20 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{DispatcherContext, ExecutorContext, RunnerTaskState};
+use super::{
+    apply_fairness, run_sampler, BinaryLimiter, ConcurrencyThrottle, DispatcherContext, DryRun,
+    ExecutorContext, PendingRetry, RunnerTaskState, ScheduleLog, TestDurations, TestWork,
+    DURATIONS_FILE_NAME,
+};
 use crate::{
     config::{
-        EvaluatableProfile, MaxFail, RetryPolicy, SetupScriptExecuteData, TestGroup, TestThreads,
+        EvaluatableProfile, MaxFail, MaxFailScope, RetryPolicy, SchedulingPolicy,
+        SetupScriptExecuteData, TestGroup, TestThreads,
     },
+    control::ControlServer,
     double_spawn::DoubleSpawnInfo,
     errors::{ConfigureHandleInheritanceError, TestRunnerBuildError, TestRunnerExecuteErrors},
+    flaky::FlakyHistory,
     input::{InputHandler, InputHandlerKind, InputHandlerStatus},
     list::{TestInstance, TestList},
     reporter::events::{RunStats, TestEvent},
@@ -17,6 +24,7 @@ use crate::{
     test_output::CaptureStrategy,
 };
 use async_scoped::TokioScope;
+use camino::Utf8PathBuf;
 use future_queue::StreamExt;
 use futures::prelude::*;
 use quick_junit::ReportUuid;
@@ -26,6 +34,7 @@ use tokio::{
     sync::{mpsc::unbounded_channel, oneshot},
     task::JoinError,
 };
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, warn};
 
 /// Test runner options.
@@ -34,7 +43,12 @@ pub struct TestRunnerBuilder {
     capture_strategy: CaptureStrategy,
     retries: Option<RetryPolicy>,
     max_fail: Option<MaxFail>,
+    max_fail_scope: Option<MaxFailScope>,
     test_threads: Option<TestThreads>,
+    debug_schedule: Option<Utf8PathBuf>,
+    control_socket: Option<Utf8PathBuf>,
+    allow_banned: bool,
+    keep_failed_workdirs: bool,
 }
 
 impl TestRunnerBuilder {
@@ -65,12 +79,71 @@ impl TestRunnerBuilder {
         self
     }
 
+    /// Sets the scope within which failures are counted against max-fail.
+    pub fn set_max_fail_scope(&mut self, max_fail_scope: MaxFailScope) -> &mut Self {
+        self.max_fail_scope = Some(max_fail_scope);
+        self
+    }
+
     /// Sets the number of tests to run simultaneously.
     pub fn set_test_threads(&mut self, test_threads: TestThreads) -> &mut Self {
         self.test_threads = Some(test_threads);
         self
     }
 
+    /// Records the runner's scheduling decisions to a JSON Lines file at `path`.
+    ///
+    /// This is meant for debugging why tests started when they did.
+    pub fn set_debug_schedule(&mut self, path: Utf8PathBuf) -> &mut Self {
+        self.debug_schedule = Some(path);
+        self
+    }
+
+    /// Listens for control commands on a Unix domain socket (on Unix) or a named pipe (on Windows)
+    /// at `path` for the duration of the run.
+    ///
+    /// Clients can send `status`, `cancel` and `cancel-test <binary-id> <test-name>` commands, one
+    /// per line, and receive a line of JSON in response to each.
+    pub fn set_control_socket(&mut self, path: Utf8PathBuf) -> &mut Self {
+        self.control_socket = Some(path);
+        self
+    }
+
+    /// Runs tests even if they match a `[[profile.<name>.banned]]` entry.
+    ///
+    /// By default, banned tests aren't run, and are reported as failing to start.
+    pub fn set_allow_banned(&mut self, allow_banned: bool) -> &mut Self {
+        self.allow_banned = allow_banned;
+        self
+    }
+
+    /// Keeps the on-disk state of failed test attempts within the profile's store directory.
+    ///
+    /// This includes each attempt's command line, environment and captured output, along with its
+    /// temporary and home directories if they're isolated.
+    pub fn set_keep_failed_workdirs(&mut self, keep_failed_workdirs: bool) -> &mut Self {
+        self.keep_failed_workdirs = keep_failed_workdirs;
+        self
+    }
+
+    /// Works out what a test run would do, without running any tests.
+    ///
+    /// Settings set on this builder, such as the number of test threads and retries, are taken
+    /// into account.
+    pub fn dry_run<'a>(
+        &self,
+        test_list: &'a TestList,
+        profile: &'a EvaluatableProfile<'a>,
+    ) -> DryRun<'a> {
+        DryRun::new(
+            test_list,
+            profile,
+            self.compute_test_threads(profile),
+            self.retries,
+            self.allow_banned,
+        )
+    }
+
     /// Creates a new test runner.
     #[expect(clippy::too_many_arguments)]
     pub fn build<'a>(
@@ -83,16 +156,14 @@ impl TestRunnerBuilder {
         double_spawn: DoubleSpawnInfo,
         target_runner: TargetRunner,
     ) -> Result<TestRunner<'a>, TestRunnerBuildError> {
-        let test_threads = match self.capture_strategy {
-            CaptureStrategy::None => 1,
-            CaptureStrategy::Combined | CaptureStrategy::Split => self
-                .test_threads
-                .unwrap_or_else(|| profile.test_threads())
-                .compute(),
-        };
+        let test_threads = self.compute_test_threads(profile);
         let max_fail = self
             .max_fail
             .unwrap_or_else(|| MaxFail::from_fail_fast(profile.fail_fast()));
+        let max_fail_scope = self
+            .max_fail_scope
+            .unwrap_or_else(|| profile.max_fail_scope());
+        double_spawn.check_pre_exec_hooks(profile.pre_exec())?;
 
         let runtime = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
@@ -102,10 +173,25 @@ impl TestRunnerBuilder {
         let _guard = runtime.enter();
 
         // signal_handler.build() must be called from within the guard.
-        let signal_handler = signal_handler.build()?;
+        let signal_handler = signal_handler.build(profile.signals())?;
 
         let input_handler = input_handler.build();
 
+        // The control server must also be started from within the guard.
+        let control_server = match self.control_socket {
+            Some(path) => ControlServer::bind(&path)
+                .map_err(|error| TestRunnerBuildError::ControlSocketBind { path, error })?,
+            None => ControlServer::noop(),
+        };
+
+        let schedule_log = self
+            .debug_schedule
+            .map(|path| {
+                ScheduleLog::create(&path)
+                    .map_err(|error| TestRunnerBuildError::DebugScheduleCreate { path, error })
+            })
+            .transpose()?;
+
         Ok(TestRunner {
             inner: TestRunnerInner {
                 run_id: ReportUuid::new_v4(),
@@ -118,12 +204,27 @@ impl TestRunnerBuilder {
                 force_retries: self.retries,
                 cli_args,
                 max_fail,
+                max_fail_scope,
+                schedule_log,
+                allow_banned: self.allow_banned,
+                keep_failed_workdirs: self.keep_failed_workdirs,
                 runtime,
             },
             signal_handler,
             input_handler,
+            control_server,
         })
     }
+
+    fn compute_test_threads(&self, profile: &EvaluatableProfile<'_>) -> usize {
+        match self.capture_strategy {
+            CaptureStrategy::None => 1,
+            CaptureStrategy::Combined | CaptureStrategy::Split => self
+                .test_threads
+                .unwrap_or_else(|| profile.test_threads())
+                .compute(),
+        }
+    }
 }
 
 /// Context for running tests.
@@ -134,6 +235,7 @@ pub struct TestRunner<'a> {
     inner: TestRunnerInner<'a>,
     signal_handler: SignalHandler,
     input_handler: InputHandler,
+    control_server: ControlServer,
 }
 
 impl<'a> TestRunner<'a> {
@@ -185,6 +287,7 @@ impl<'a> TestRunner<'a> {
         let res = self.inner.execute(
             &mut self.signal_handler,
             &mut self.input_handler,
+            &mut self.control_server,
             report_cancel_rx,
             |event| {
                 match callback(event) {
@@ -221,6 +324,26 @@ impl<'a> TestRunner<'a> {
     }
 }
 
+/// Returns tests in the order the runner starts them in, along with the recorded test durations
+/// if the scheduling policy uses them.
+pub(super) fn tests_in_start_order<'a>(
+    profile: &EvaluatableProfile<'_>,
+    test_list: &'a TestList<'a>,
+) -> (Vec<TestInstance<'a>>, Option<TestDurations>) {
+    let scheduling = profile.scheduling();
+    let durations = match scheduling {
+        SchedulingPolicy::ListOrder => None,
+        SchedulingPolicy::LongestFirst => Some(TestDurations::load(
+            &profile.store_dir().join(DURATIONS_FILE_NAME),
+        )),
+    };
+    let mut tests: Vec<_> = test_list.iter_tests().collect();
+    if let Some(durations) = &durations {
+        durations.sort_tests(scheduling, &mut tests);
+    }
+    (apply_fairness(profile.fairness(), tests), durations)
+}
+
 #[derive(Debug)]
 struct TestRunnerInner<'a> {
     run_id: ReportUuid,
@@ -233,6 +356,10 @@ struct TestRunnerInner<'a> {
     force_retries: Option<RetryPolicy>,
     cli_args: Vec<String>,
     max_fail: MaxFail,
+    max_fail_scope: MaxFailScope,
+    schedule_log: Option<ScheduleLog>,
+    allow_banned: bool,
+    keep_failed_workdirs: bool,
     runtime: Runtime,
 }
 
@@ -241,6 +368,7 @@ impl<'a> TestRunnerInner<'a> {
         &self,
         signal_handler: &mut SignalHandler,
         input_handler: &mut InputHandler,
+        control_server: &mut ControlServer,
         report_cancel_rx: oneshot::Receiver<()>,
         callback: F,
     ) -> Result<RunStats, Vec<JoinError>>
@@ -249,6 +377,9 @@ impl<'a> TestRunnerInner<'a> {
     {
         // TODO: add support for other test-running approaches, measure performance.
 
+        let (tests, durations) = tests_in_start_order(self.profile, self.test_list);
+        let flaky_history_path = self.profile.store_dir().join(FlakyHistory::FILE_NAME);
+
         let mut dispatcher_cx = DispatcherContext::new(
             callback,
             self.run_id,
@@ -256,6 +387,11 @@ impl<'a> TestRunnerInner<'a> {
             self.cli_args.clone(),
             self.test_list.run_count(),
             self.max_fail,
+            self.max_fail_scope,
+            durations,
+            self.profile
+                .record_flaky_history()
+                .then(|| FlakyHistory::load(&flaky_history_path)),
         );
 
         let executor_cx = ExecutorContext::new(
@@ -266,6 +402,8 @@ impl<'a> TestRunnerInner<'a> {
             self.target_runner.clone(),
             self.capture_strategy,
             self.force_retries,
+            self.allow_banned,
+            self.keep_failed_workdirs,
         );
 
         // Send the initial event.
@@ -273,8 +411,16 @@ impl<'a> TestRunnerInner<'a> {
         // yet.)
         dispatcher_cx.run_started(self.test_list);
 
+        let binary_limiter = BinaryLimiter::new(self.profile.max_concurrent_binaries());
+        let adaptive_concurrency = self.profile.adaptive_concurrency();
+        let throttle = adaptive_concurrency
+            .is_enabled()
+            .then(|| ConcurrencyThrottle::new(self.test_threads));
+
         let executor_cx_ref = &executor_cx;
         let dispatcher_cx_mut = &mut dispatcher_cx;
+        let binary_limiter_ref = &binary_limiter;
+        let throttle_ref = throttle.as_ref();
 
         let _guard = self.runtime.enter();
 
@@ -282,8 +428,13 @@ impl<'a> TestRunnerInner<'a> {
             let (resp_tx, resp_rx) = unbounded_channel::<ExecutorEvent<'a>>();
 
             // Run the dispatcher to completion in a task.
-            let dispatcher_fut =
-                dispatcher_cx_mut.run(resp_rx, signal_handler, input_handler, report_cancel_rx);
+            let dispatcher_fut = dispatcher_cx_mut.run(
+                resp_rx,
+                signal_handler,
+                input_handler,
+                control_server,
+                report_cancel_rx,
+            );
             scope.spawn_cancellable(dispatcher_fut, || RunnerTaskState::Cancelled);
 
             let (script_tx, mut script_rx) = unbounded_channel::<SetupScriptExecuteData<'a>>();
@@ -312,18 +463,94 @@ impl<'a> TestRunnerInner<'a> {
                 .test_group_config()
                 .iter()
                 .map(|(group_name, config)| (group_name, config.max_threads.compute()));
+            if let Some(schedule_log) = &self.schedule_log {
+                schedule_log.run_started(self.test_threads, groups.clone());
+            }
 
             let setup_script_data = Arc::new(script_data);
 
-            let run_tests_fut = futures::stream::iter(self.test_list.iter_tests())
-                .map(move |test_instance: TestInstance<'a>| {
-                    let query = test_instance.to_test_query();
-                    let settings = self.profile.settings_for(&query);
+            // If adaptive concurrency is enabled, sample system load until all
+            // tests are done. Dropping sampler_done_tx stops the sampler.
+            let (sampler_done_tx, sampler_done_rx) = oneshot::channel::<()>();
+            if let Some(throttle) = throttle_ref {
+                let sampler_fut = run_sampler(
+                    throttle,
+                    adaptive_concurrency,
+                    self.test_threads,
+                    resp_tx.clone(),
+                    sampler_done_rx,
+                )
+                .map(|()| RunnerTaskState::finished_no_children());
+                scope.spawn_cancellable(sampler_fut, || RunnerTaskState::Cancelled);
+            }
+
+            // Tests are fed to the scheduler from two sources: the list of
+            // tests, and retries of tests whose previous attempt failed.
+            //
+            // Each unit of work holds a sender for the retry channel, and the
+            // initial stream holds one until it's exhausted. This means that
+            // the retry stream ends once all tests have been started and no
+            // test is in flight or waiting to be retried.
+            let (retry_tx, retry_rx) = unbounded_channel::<PendingRetry<'a>>();
+            let initial_tests = futures::stream::unfold(
+                (tests.into_iter(), retry_tx),
+                |(mut iter, retry_tx)| async move {
+                    let test_instance = iter.next()?;
+                    let work = TestWork::Initial {
+                        test_instance,
+                        retry_tx: retry_tx.clone(),
+                    };
+                    Some((work, (iter, retry_tx)))
+                },
+            );
+            // Retry delays are waited out here, concurrently, so that tests
+            // which are backing off don't hold on to a slot.
+            let retries = UnboundedReceiverStream::new(retry_rx)
+                .map(|retry| retry.wait_for_delay())
+                .buffer_unordered(usize::MAX)
+                .map(|retry| TestWork::Retry(Box::new(retry)));
+
+            let work = futures::stream::select(initial_tests, retries);
+            let run_tests_fut = binary_limiter_ref
+                // Only hand tests to the scheduler once their binary has a
+                // slot, so that tests waiting on a slot don't hold on to test
+                // threads. Tests from binaries that are already running are
+                // admitted ahead of tests waiting on a slot.
+                .admit(work, |work: &TestWork<'a>| {
+                    &work.test_instance().suite_info.binary_id
+                })
+                .then(move |(work, binary_guard)| async move {
+                    // Under system pressure, also wait until enough running
+                    // tests have finished.
+                    let throttle_guard = match throttle_ref {
+                        Some(throttle) => Some(throttle.acquire().await),
+                        None => None,
+                    };
+                    (work, binary_guard, throttle_guard)
+                })
+                .map(move |(work, binary_guard, throttle_guard)| {
+                    let test_instance = work.test_instance();
+                    let settings = match &work {
+                        TestWork::Initial { .. } => {
+                            let query = test_instance.to_test_query();
+                            Arc::new(self.profile.settings_for(&query))
+                        }
+                        TestWork::Retry(retry) => retry.settings(),
+                    };
                     let threads_required = settings.threads_required().compute(self.test_threads);
                     let test_group = match settings.test_group() {
                         TestGroup::Global => None,
                         TestGroup::Custom(name) => Some(name.clone()),
                     };
+                    let scheduled_unit = self.schedule_log.as_ref().map(|schedule_log| {
+                        let unit = schedule_log.unit_queued(
+                            test_instance,
+                            work.attempt(),
+                            threads_required,
+                            test_group.as_ref(),
+                        );
+                        (schedule_log, unit)
+                    });
                     let resp_tx = resp_tx.clone();
                     let setup_script_data = setup_script_data.clone();
 
@@ -333,6 +560,14 @@ impl<'a> TestRunnerInner<'a> {
                     // used to do). It also provides some degree of per-test
                     // isolation.
                     let fut = async move {
+                        // Hold on to the binary's slot, and count as running
+                        // for adaptive concurrency, until the test is done.
+                        let _binary_guard = binary_guard;
+                        let _throttle_guard = throttle_guard;
+                        if let Some((schedule_log, unit)) = &scheduled_unit {
+                            schedule_log.unit_started(unit);
+                        }
+
                         // SAFETY: Within an outer scope_and_block (which we
                         // have here), scope_and_collect is safe as long as the
                         // returned future isn't forgotten. We're not forgetting
@@ -347,17 +582,28 @@ impl<'a> TestRunnerInner<'a> {
                         // solvable via channels. And we could likely do our own
                         // channels here.)
                         let ((), mut ret) = unsafe {
-                            TokioScope::scope_and_collect(move |scope| {
-                                scope.spawn(executor_cx_ref.run_test_instance(
+                            TokioScope::scope_and_collect(move |scope| match work {
+                                TestWork::Initial {
+                                    test_instance,
+                                    retry_tx,
+                                } => scope.spawn(executor_cx_ref.run_test_instance(
                                     test_instance,
                                     settings,
                                     resp_tx.clone(),
                                     setup_script_data,
-                                ))
+                                    retry_tx,
+                                )),
+                                TestWork::Retry(retry) => scope.spawn(
+                                    executor_cx_ref.run_pending_retry(*retry, resp_tx.clone()),
+                                ),
                             })
                         }
                         .await;
 
+                        if let Some((schedule_log, unit)) = &scheduled_unit {
+                            schedule_log.unit_finished(unit);
+                        }
+
                         // If no future was started, that's really strange.
                         // Worth at least logging.
                         let Some(result) = ret.pop() else {
@@ -385,13 +631,34 @@ impl<'a> TestRunnerInner<'a> {
                 // run_tests_fut.await ... }` block causes Rust 1.83 to complain
                 // about a weird lifetime mismatch. FutureExt::map as used below
                 // does not.
-                .map(|child_join_errors| RunnerTaskState::Finished { child_join_errors });
+                .map(|child_join_errors| {
+                    drop(sampler_done_tx);
+                    RunnerTaskState::Finished { child_join_errors }
+                });
 
             scope.spawn_cancellable(run_tests_fut, || RunnerTaskState::Cancelled);
         });
 
+        // Clean up after setup scripts before reporting that the run is finished. This happens
+        // whether the run succeeded, failed, or was cancelled.
+        self.runtime.block_on(executor_cx.run_script_cleanup());
+
         dispatcher_cx.run_finished();
 
+        if let Some(schedule_log) = &self.schedule_log {
+            schedule_log.finish();
+        }
+
+        if let Some(durations) = dispatcher_cx.durations() {
+            durations.save(
+                self.test_list,
+                &self.profile.store_dir().join(DURATIONS_FILE_NAME),
+            );
+        }
+        if let Some(flaky_history) = dispatcher_cx.flaky_history() {
+            flaky_history.save(&flaky_history_path);
+        }
+
         // Were there any join errors in tasks?
         //
         // If one of the tasks panics, we likely end up stuck because the
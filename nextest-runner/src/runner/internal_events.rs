@@ -13,8 +13,8 @@ use crate::{
     list::TestInstance,
     reporter::{
         events::{
-            ExecuteStatus, ExecutionResult, InfoResponse, RetryData, SetupScriptEnvMap,
-            SetupScriptExecuteStatus, UnitState,
+            CgroupLimitsStatus, CrashDump, ExecuteStatus, ExecutionResult, InfoResponse, RetryData,
+            SetupScriptEnvMap, SetupScriptExecuteStatus, SystemPressure, UnitState,
         },
         TestOutputDisplay,
     },
@@ -22,8 +22,9 @@ use crate::{
     test_output::ChildExecutionOutput,
     time::StopwatchSnapshot,
 };
+use camino::Utf8PathBuf;
 use nextest_metadata::MismatchReason;
-use std::time::Duration;
+use std::{collections::BTreeMap, net::SocketAddr, time::Duration};
 use tokio::{
     sync::{
         mpsc::{UnboundedReceiver, UnboundedSender},
@@ -98,12 +99,22 @@ pub(super) enum ExecutorEvent<'a> {
         failure_output: TestOutputDisplay,
         junit_store_success_output: bool,
         junit_store_failure_output: bool,
+        tracking_issue: Option<String>,
         last_run_status: ExecuteStatus,
     },
     Skipped {
         test_instance: TestInstance<'a>,
         reason: MismatchReason,
     },
+    // Sent by the adaptive concurrency sampler rather than the executor.
+    ConcurrencyReduced {
+        limit: usize,
+        test_threads: usize,
+        pressure: SystemPressure,
+    },
+    ConcurrencyRestored {
+        test_threads: usize,
+    },
 }
 
 #[derive(Clone, Copy)]
@@ -140,7 +151,17 @@ pub(super) struct InternalExecuteStatus<'a> {
     pub(super) slow_after: Option<Duration>,
     pub(super) output: ChildExecutionOutput,
     pub(super) result: ExecutionResult,
+    pub(super) failure_class: Option<String>,
+    pub(super) annotations: BTreeMap<String, String>,
+    pub(super) exec_time: Option<Duration>,
+    pub(super) startup_time: Option<Duration>,
     pub(super) stopwatch_end: StopwatchSnapshot,
+    pub(super) cgroup_limits: CgroupLimitsStatus,
+    pub(super) kept_tmpdir: Option<Utf8PathBuf>,
+    pub(super) kept_workdir: Option<Utf8PathBuf>,
+    pub(super) access_report: Option<Utf8PathBuf>,
+    pub(super) access_violations: Vec<SocketAddr>,
+    pub(super) crash_dump: Option<CrashDump>,
 }
 
 impl InternalExecuteStatus<'_> {
@@ -149,10 +170,20 @@ impl InternalExecuteStatus<'_> {
             retry_data: self.test.retry_data(),
             output: self.output,
             result: self.result,
+            failure_class: self.failure_class,
+            annotations: self.annotations,
+            exec_time: self.exec_time,
+            startup_time: self.startup_time,
             start_time: self.stopwatch_end.start_time.fixed_offset(),
             time_taken: self.stopwatch_end.active,
             is_slow: self.slow_after.is_some(),
             delay_before_start: self.test.delay_before_start(),
+            cgroup_limits: self.cgroup_limits,
+            kept_tmpdir: self.kept_tmpdir,
+            kept_workdir: self.kept_workdir,
+            access_report: self.access_report,
+            access_violations: self.access_violations,
+            crash_dump: self.crash_dump,
         }
     }
 }
@@ -199,6 +230,8 @@ impl<'a> RunUnitRequest<'a> {
             }
             #[cfg(unix)]
             Self::Signal(SignalRequest::Continue) => {}
+            #[cfg(unix)]
+            Self::Signal(SignalRequest::Forward(_)) => {}
             Self::Signal(SignalRequest::Shutdown(_)) => {}
             Self::OtherCancel => {}
             Self::Query(RunUnitQuery::GetInfo(tx)) => {
@@ -216,6 +249,8 @@ pub(super) enum SignalRequest {
     Stop(UnboundedSender<()>),
     #[cfg(unix)]
     Continue,
+    #[cfg(unix)]
+    Forward(crate::signal::ForwardedSignal),
     Shutdown(ShutdownRequest),
 }
 
@@ -223,6 +258,9 @@ pub(super) enum SignalRequest {
 pub(super) enum ShutdownRequest {
     Once(ShutdownEvent),
     Twice,
+    /// A `cancel` or `cancel-test` command was received over the control
+    /// socket.
+    Control,
 }
 
 #[derive(Clone, Debug)]
@@ -257,6 +295,10 @@ pub(super) enum HandleSignalResult {
     #[cfg(unix)]
     JobControl,
 
+    /// A signal was forwarded to the child.
+    #[cfg(unix)]
+    Forwarded,
+
     /// The child was terminated.
     #[cfg_attr(not(windows), expect(dead_code))]
     Terminated(TerminateChildResult),
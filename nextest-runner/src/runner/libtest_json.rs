@@ -0,0 +1,156 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Ingesting libtest's JSON output.
+//!
+//! With `libtest-json` enabled, test binaries are run with
+//! `-Z unstable-options --format json --report-time` alongside `--nocapture`.
+//! libtest then prints one JSON event per line to stdout, interleaved with
+//! whatever the test prints. The events are removed from the output, and the
+//! ones for the test being run provide its execution time and any failure
+//! messages libtest would otherwise only print in its summary.
+
+use crate::test_output::{ChildOutput, ChildSingleOutput};
+use bstr::ByteSlice;
+use bytes::Bytes;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// The start of every event libtest prints.
+const EVENT_PREFIX: &[u8] = br#"{ "type": ""#;
+
+/// The fields of a libtest event that nextest uses.
+#[derive(Deserialize)]
+struct LibtestEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    name: Option<String>,
+    exec_time: Option<f64>,
+    stdout: Option<String>,
+    message: Option<String>,
+    reason: Option<String>,
+}
+
+/// Removes libtest's events from a test's output.
+///
+/// Returns the output along with the execution time reported by libtest. If
+/// the output doesn't contain any events for `test_name`, for example because
+/// the binary doesn't use libtest, it's returned unchanged.
+pub(super) fn ingest_libtest_json(
+    output: ChildOutput,
+    test_name: &str,
+) -> (ChildOutput, Option<Duration>) {
+    match output {
+        ChildOutput::Split(mut split) => {
+            let mut exec_time = None;
+            if let Some(stdout) = &split.stdout {
+                if let Some((buf, time)) = ingest_buf(&stdout.buf, test_name) {
+                    split.stdout = Some(ChildSingleOutput::from(Bytes::from(buf)));
+                    exec_time = time;
+                }
+            }
+            (ChildOutput::Split(split), exec_time)
+        }
+        ChildOutput::Combined { output } => match ingest_buf(&output.buf, test_name) {
+            Some((buf, exec_time)) => (
+                ChildOutput::Combined {
+                    output: ChildSingleOutput::from(Bytes::from(buf)),
+                },
+                exec_time,
+            ),
+            None => (ChildOutput::Combined { output }, None),
+        },
+    }
+}
+
+fn ingest_buf(buf: &[u8], test_name: &str) -> Option<(Vec<u8>, Option<Duration>)> {
+    let mut out = Vec::with_capacity(buf.len());
+    let mut messages = Vec::new();
+    let mut exec_time = None;
+    let mut found = false;
+
+    for line in buf.lines_with_terminator() {
+        // A test that prints without a trailing newline leaves the event at
+        // the end of the line.
+        let Some(start) = line.rfind(EVENT_PREFIX) else {
+            out.extend_from_slice(line);
+            continue;
+        };
+        let (before, event) = line.split_at(start);
+        let Ok(event) = serde_json::from_slice::<LibtestEvent>(event.trim_ascii_end()) else {
+            out.extend_from_slice(line);
+            continue;
+        };
+        if !before.is_empty() {
+            out.extend_from_slice(before);
+            out.push(b'\n');
+        }
+
+        if event.kind == "test" && event.name.as_deref() == Some(test_name) {
+            found = true;
+            if let Some(time) = event.exec_time {
+                exec_time = Duration::try_from_secs_f64(time).ok();
+            }
+            messages.extend(
+                [event.stdout, event.message, event.reason]
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+    }
+
+    if !found {
+        return None;
+    }
+    for message in messages {
+        out.extend_from_slice(message.as_bytes());
+        if !message.ends_with('\n') {
+            out.push(b'\n');
+        }
+    }
+    Some((out, exec_time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_output::ChildSplitOutput;
+
+    #[test]
+    fn ingest_libtest_json_output() {
+        let stdout = b"{ \"type\": \"suite\", \"event\": \"started\", \"test_count\": 1 }\n\
+            { \"type\": \"test\", \"event\": \"started\", \"name\": \"tests::foo\" }\n\
+            hello from foo\n\
+            no newline{ \"type\": \"test\", \"name\": \"tests::foo\", \"event\": \"failed\", \"exec_time\": 0.25, \"message\": \"panic did not contain expected string\" }\n\
+            { \"type\": \"suite\", \"event\": \"failed\", \"passed\": 0, \"failed\": 1, \"ignored\": 0, \"measured\": 0, \"filtered_out\": 0, \"exec_time\": 0.26 }\n";
+        let output = ChildOutput::Split(ChildSplitOutput {
+            stdout: Some(Bytes::from_static(stdout).into()),
+            stderr: Some(Bytes::from_static(b"thread panicked\n").into()),
+        });
+
+        let (output, exec_time) = ingest_libtest_json(output, "tests::foo");
+        assert_eq!(exec_time, Some(Duration::from_millis(250)));
+        let ChildOutput::Split(split) = output else {
+            panic!("output is still split");
+        };
+        assert_eq!(
+            split.stdout.expect("stdout is present").as_str_lossy(),
+            "hello from foo\nno newline\npanic did not contain expected string\n",
+        );
+        assert_eq!(
+            split.stderr.expect("stderr is present").as_str_lossy(),
+            "thread panicked\n",
+        );
+
+        // Output without events for the test is left alone.
+        let output = ChildOutput::Combined {
+            output: Bytes::from_static(b"custom harness output\n").into(),
+        };
+        let (output, exec_time) = ingest_libtest_json(output, "tests::foo");
+        assert_eq!(exec_time, None);
+        let ChildOutput::Combined { output } = output else {
+            panic!("output is still combined");
+        };
+        assert_eq!(output.as_str_lossy(), "custom harness output\n");
+    }
+}
@@ -10,11 +10,27 @@
 //!
 //! [_The runner loop_]: https://nexte.st/docs/design/architecture/runner-loop/
 
+mod access_audit;
+mod adaptive;
+mod annotations;
+mod binary_limit;
+mod cgroup;
+mod crash_dump;
 mod dispatcher;
+mod dry_run;
+mod durations;
 mod executor;
+mod fairness;
 mod imp;
 mod internal_events;
+mod libtest_json;
+mod output_filters;
+mod schedule_log;
+mod script_cleanup;
 mod script_helpers;
+mod system_load;
+mod tool_output;
+mod workdir;
 
 #[cfg(unix)]
 #[path = "unix.rs"]
@@ -24,8 +40,21 @@ mod os;
 #[path = "windows.rs"]
 mod os;
 
+use access_audit::*;
+use adaptive::*;
+use binary_limit::*;
+use cgroup::*;
+use crash_dump::*;
 use dispatcher::*;
+pub use dry_run::*;
+use durations::*;
 use executor::*;
+use fairness::*;
 pub use imp::*;
 use internal_events::*;
+use output_filters::*;
+use schedule_log::*;
+use script_cleanup::*;
 use script_helpers::*;
+use system_load::*;
+use workdir::*;
@@ -0,0 +1,427 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Filters applied to tests' captured output, set via `output-filters`.
+//!
+//! Filters run once a test has finished, after its failure class (if any) has
+//! been determined from the unfiltered output. The filtered output is what's
+//! displayed and stored in JUnit reports.
+//!
+//! Redactions, set via `reporter.redact`, are applied to all captured output
+//! as soon as it's read, before anything else sees it.
+
+use crate::{
+    config::{OutputFilter, Redactions},
+    list::TestInstanceId,
+    test_output::{ChildOutput, ChildSingleOutput, ChildSplitOutput},
+};
+use bstr::ByteSlice;
+use bytes::Bytes;
+use camino::Utf8Path;
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+use serde_json::Value;
+use std::{borrow::Cow, process::Stdio, time::Duration};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// How long an output filter command can run for before it's killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Applies `filters` to the output of a test.
+///
+/// Command filters are run in `cwd`. If a command fails, a warning is printed
+/// and its input is passed on unchanged.
+pub(super) async fn apply_output_filters(
+    filters: &[OutputFilter],
+    output: ChildOutput,
+    cwd: &Utf8Path,
+    test_id: TestInstanceId<'_>,
+) -> ChildOutput {
+    if filters.is_empty() {
+        return output;
+    }
+
+    match output {
+        ChildOutput::Split(ChildSplitOutput { stdout, stderr }) => {
+            let stdout = match stdout {
+                Some(stdout) => Some(filter_single(filters, stdout, cwd, test_id).await),
+                None => None,
+            };
+            let stderr = match stderr {
+                Some(stderr) => Some(filter_single(filters, stderr, cwd, test_id).await),
+                None => None,
+            };
+            ChildOutput::Split(ChildSplitOutput { stdout, stderr })
+        }
+        ChildOutput::Combined { output } => ChildOutput::Combined {
+            output: filter_single(filters, output, cwd, test_id).await,
+        },
+    }
+}
+
+async fn filter_single(
+    filters: &[OutputFilter],
+    output: ChildSingleOutput,
+    cwd: &Utf8Path,
+    test_id: TestInstanceId<'_>,
+) -> ChildSingleOutput {
+    let mut buf = output.buf;
+    for filter in filters {
+        buf = match filter {
+            OutputFilter::StripTimestamps => strip_timestamps(&buf),
+            OutputFilter::CollapseRepeatedLines => collapse_repeated_lines(&buf),
+            OutputFilter::DecodeJsonLogs => decode_json_logs(&buf),
+            OutputFilter::Command { program, args } => {
+                match run_command(program, args, cwd, buf.clone()).await {
+                    Ok(filtered) => filtered,
+                    Err(error) => {
+                        warn!(
+                            "for {test_id}, output filter `{filter}` failed, \
+                             leaving output unfiltered: {error}"
+                        );
+                        buf
+                    }
+                }
+            }
+        };
+    }
+    ChildSingleOutput::from(buf)
+}
+
+/// Applies `redactions` to the output of a test or setup script.
+pub(super) fn redact_output(redactions: &Redactions<'_>, output: ChildOutput) -> ChildOutput {
+    if redactions.is_empty() {
+        return output;
+    }
+
+    match output {
+        ChildOutput::Split(ChildSplitOutput { stdout, stderr }) => {
+            ChildOutput::Split(ChildSplitOutput {
+                stdout: stdout.map(|stdout| redact_single(redactions, stdout)),
+                stderr: stderr.map(|stderr| redact_single(redactions, stderr)),
+            })
+        }
+        ChildOutput::Combined { output } => ChildOutput::Combined {
+            output: redact_single(redactions, output),
+        },
+    }
+}
+
+fn redact_single(redactions: &Redactions<'_>, output: ChildSingleOutput) -> ChildSingleOutput {
+    let mut buf: Option<Bytes> = None;
+    for redaction in redactions.iter() {
+        let input = buf.as_deref().unwrap_or(&output.buf);
+        if let Cow::Owned(redacted) = redaction
+            .pattern()
+            .replace_all(input, redaction.replace().as_bytes())
+        {
+            buf = Some(redacted.into());
+        }
+    }
+    // Only rebuild the output if something was redacted.
+    match buf {
+        Some(buf) => ChildSingleOutput::from(buf),
+        None => output,
+    }
+}
+
+// For example, "2024-05-01T12:34:56.789Z", "[2024-05-01 12:34:56,789]" or "12:34:56.789".
+static TIMESTAMP_REGEX_STR: &str =
+    r"^\[?(?:\d{4}-\d{2}-\d{2}[T ])?\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?\]?[ \t]*";
+static TIMESTAMP_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(TIMESTAMP_REGEX_STR).unwrap());
+
+fn strip_timestamps(buf: &[u8]) -> Bytes {
+    let mut out = Vec::with_capacity(buf.len());
+    for line in buf.lines_with_terminator() {
+        let start = TIMESTAMP_REGEX.find(line).map_or(0, |m| m.end());
+        out.extend_from_slice(&line[start..]);
+    }
+    out.into()
+}
+
+fn collapse_repeated_lines(buf: &[u8]) -> Bytes {
+    fn flush(out: &mut Vec<u8>, line: &[u8], count: usize) {
+        out.extend_from_slice(line);
+        if count > 1 {
+            if !line.ends_with(b"\n") {
+                out.push(b'\n');
+            }
+            let repeats = count - 1;
+            let times = if repeats == 1 { "time" } else { "times" };
+            out.extend_from_slice(
+                format!("[previous line repeated {repeats} more {times}]\n").as_bytes(),
+            );
+        }
+    }
+
+    let mut out = Vec::with_capacity(buf.len());
+    let mut current: Option<(&[u8], usize)> = None;
+    for line in buf.lines_with_terminator() {
+        match &mut current {
+            Some((prev, count)) if trim_terminator(prev) == trim_terminator(line) => *count += 1,
+            _ => {
+                if let Some((prev, count)) = current {
+                    flush(&mut out, prev, count);
+                }
+                current = Some((line, 1));
+            }
+        }
+    }
+    if let Some((prev, count)) = current {
+        flush(&mut out, prev, count);
+    }
+    out.into()
+}
+
+fn decode_json_logs(buf: &[u8]) -> Bytes {
+    let mut out = Vec::with_capacity(buf.len());
+    for line in buf.lines_with_terminator() {
+        let content = trim_terminator(line);
+        match serde_json::from_slice::<serde_json::Map<String, Value>>(content) {
+            Ok(object) => {
+                out.extend_from_slice(render_json_log(object).as_bytes());
+                out.extend_from_slice(&line[content.len()..]);
+            }
+            Err(_) => out.extend_from_slice(line),
+        }
+    }
+    out.into()
+}
+
+/// Renders a JSON log line as `<timestamp> <LEVEL> <target>: <message> key=value...`.
+///
+/// Common field names from `tracing-subscriber`, `slog`, `bunyan` and similar
+/// loggers are recognized. Anything missing is left out.
+fn render_json_log(mut object: serde_json::Map<String, Value>) -> String {
+    // tracing-subscriber nests the message and other fields under "fields".
+    if let Some(Value::Object(fields)) = object.remove("fields") {
+        object.extend(fields);
+    }
+
+    let mut take = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| object.remove(*key))
+            .map(|value| match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            })
+    };
+    let timestamp = take(&["timestamp", "time", "ts"]);
+    let level = take(&["level", "severity", "lvl"]);
+    let target = take(&["target", "logger"]);
+    let message = take(&["message", "msg"]);
+
+    let mut out = String::new();
+    let mut push = |part: &str| {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(part);
+    };
+    if let Some(timestamp) = timestamp {
+        push(&timestamp);
+    }
+    if let Some(level) = level {
+        push(&level.to_uppercase());
+    }
+    if let Some(target) = target {
+        push(&format!("{target}:"));
+    }
+    if let Some(message) = message {
+        push(&message);
+    }
+    for (key, value) in object {
+        let value = match value {
+            Value::String(s) if !s.is_empty() && !s.contains(char::is_whitespace) => s,
+            other => other.to_string(),
+        };
+        push(&format!("{key}={value}"));
+    }
+    out
+}
+
+fn trim_terminator(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+async fn run_command(
+    program: &str,
+    args: &[String],
+    cwd: &Utf8Path,
+    input: Bytes,
+) -> Result<Bytes, String> {
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|error| format!("error spawning command: {error}"))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let write = async move {
+        // The command might exit without reading all of its input, so ignore
+        // errors here. Dropping stdin at the end closes it.
+        _ = stdin.write_all(&input).await;
+    };
+    let wait = async {
+        let ((), output) = tokio::join!(write, child.wait_with_output());
+        output
+    };
+
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, wait)
+        .await
+        .map_err(|_| format!("command timed out after {COMMAND_TIMEOUT:?}"))?
+        .map_err(|error| format!("error waiting for command: {error}"))?;
+    if !output.status.success() {
+        return Err(format!("command exited with {}", output.status));
+    }
+    Ok(output.stdout.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Redaction;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "2024-05-01T12:34:56.789Z INFO starting\n",
+        "INFO starting\n"
+        ; "rfc 3339"
+    )]
+    #[test_case(
+        "[2024-05-01 12:34:56,789] WARN slow\r\n12:34:57 done",
+        "WARN slow\r\ndone"
+        ; "bracketed and time only"
+    )]
+    #[test_case(
+        "2024-05-01T12:34:56+05:30\tmessage\n",
+        "message\n"
+        ; "offset"
+    )]
+    #[test_case(
+        "test result: 12 passed\n",
+        "test result: 12 passed\n"
+        ; "no timestamp"
+    )]
+    fn test_strip_timestamps(input: &str, expected: &str) {
+        assert_eq!(strip_timestamps(input.as_bytes()), expected.as_bytes());
+    }
+
+    #[test]
+    fn test_collapse_repeated_lines() {
+        let input = indoc! {"
+            retrying
+            retrying
+            retrying
+            connected
+            ok
+            ok
+        "};
+        let expected = indoc! {"
+            retrying
+            [previous line repeated 2 more times]
+            connected
+            ok
+            [previous line repeated 1 more time]
+        "};
+        assert_eq!(
+            collapse_repeated_lines(input.as_bytes()),
+            expected.as_bytes()
+        );
+
+        // The last line doesn't have a terminator.
+        assert_eq!(
+            collapse_repeated_lines(b"a\r\na"),
+            "a\r\n[previous line repeated 1 more time]\n".as_bytes(),
+        );
+    }
+
+    #[test]
+    fn test_decode_json_logs() {
+        let input = indoc! {r#"
+            {"timestamp":"2024-05-01T12:34:56Z","level":"WARN","fields":{"message":"pool exhausted","size":8},"target":"db::pool"}
+            {"time":1714566896000,"level":"info","msg":"request handled","path":"/api/v1"}
+            not json
+            {"message":"with spaces","user":"a b"}
+            [1, 2, 3]
+        "#};
+        let expected = indoc! {r#"
+            2024-05-01T12:34:56Z WARN db::pool: pool exhausted size=8
+            1714566896000 INFO request handled path=/api/v1
+            not json
+            with spaces user="a b"
+            [1, 2, 3]
+        "#};
+        assert_eq!(
+            decode_json_logs(input.as_bytes()).to_str().unwrap(),
+            expected
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_command() {
+        let cwd = Utf8Path::new(env!("CARGO_MANIFEST_DIR"));
+        let output = run_command(
+            "tr",
+            &["a-z".to_owned(), "A-Z".to_owned()],
+            cwd,
+            Bytes::from_static(b"hello\n"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output, b"HELLO\n".as_slice());
+
+        run_command("false", &[], cwd, Bytes::new())
+            .await
+            .expect_err("non-zero exit is an error");
+    }
+
+    #[test]
+    fn test_redact_output() {
+        #[derive(serde::Deserialize)]
+        struct Wrapper {
+            redact: Vec<Redaction>,
+        }
+
+        let wrapper: Wrapper = toml::from_str(indoc! {r#"
+            redact = [
+                { pattern = 'ghp_[A-Za-z0-9]+' },
+                { pattern = '(password=)\S+', replace = '${1}***' },
+            ]
+        "#})
+        .unwrap();
+        let redactions = Redactions::new(&wrapper.redact);
+
+        let output = ChildOutput::Split(ChildSplitOutput {
+            stdout: Some(Bytes::from_static(b"token ghp_abc123 and ghp_def456\n").into()),
+            stderr: Some(Bytes::from_static(b"nothing to see here\n").into()),
+        });
+        let ChildOutput::Split(output) = redact_output(&redactions, output) else {
+            panic!("output is still split");
+        };
+        assert_eq!(
+            output.stdout.unwrap().buf,
+            b"token [REDACTED] and [REDACTED]\n".as_slice()
+        );
+        assert_eq!(
+            output.stderr.unwrap().buf,
+            b"nothing to see here\n".as_slice()
+        );
+
+        let output = ChildOutput::Combined {
+            output: Bytes::from_static(b"password=hunter2 ghp_abc\n").into(),
+        };
+        let ChildOutput::Combined { output } = redact_output(&redactions, output) else {
+            panic!("output is still combined");
+        };
+        assert_eq!(output.buf, b"password=*** [REDACTED]\n".as_slice());
+    }
+}
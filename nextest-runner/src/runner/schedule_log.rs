@@ -0,0 +1,357 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A debug log of the scheduling decisions made by the runner.
+//!
+//! With `--debug-schedule`, the runner writes a [JSON Lines](https://jsonlines.org/) file with an
+//! entry for each time a test is queued, started, and finished. Each entry records how many slots
+//! were in use, so that runs with idle cores but queued tests can be diagnosed afterwards.
+
+use crate::{config::CustomTestGroup, list::TestInstance};
+use camino::Utf8Path;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufWriter, Write},
+    sync::Mutex,
+    time::Instant,
+};
+use tracing::warn;
+
+/// Records scheduling decisions to a file.
+#[derive(Debug)]
+pub(super) struct ScheduleLog {
+    inner: Mutex<ScheduleLogInner>,
+}
+
+#[derive(Debug)]
+struct ScheduleLogInner {
+    writer: BufWriter<File>,
+    start: Instant,
+    test_threads: usize,
+    threads_in_use: usize,
+    group_limits: HashMap<CustomTestGroup, usize>,
+    group_threads_in_use: HashMap<CustomTestGroup, usize>,
+    // Set after the first write error, to avoid warning about every event.
+    failed: bool,
+}
+
+impl ScheduleLog {
+    pub(super) fn create(path: &Utf8Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let file = File::create(path)?;
+        Ok(Self {
+            inner: Mutex::new(ScheduleLogInner {
+                writer: BufWriter::new(file),
+                start: Instant::now(),
+                test_threads: 0,
+                threads_in_use: 0,
+                group_limits: HashMap::new(),
+                group_threads_in_use: HashMap::new(),
+                failed: false,
+            }),
+        })
+    }
+
+    /// Records the start of the run, along with the limits that tests are scheduled against.
+    pub(super) fn run_started<'g>(
+        &self,
+        test_threads: usize,
+        groups: impl IntoIterator<Item = (&'g CustomTestGroup, usize)>,
+    ) {
+        let mut inner = self.lock();
+        inner.start = Instant::now();
+        inner.test_threads = test_threads;
+        inner.group_limits = groups
+            .into_iter()
+            .map(|(group, max_threads)| (group.clone(), max_threads))
+            .collect();
+
+        let groups = inner
+            .group_limits
+            .iter()
+            .map(|(group, max_threads)| (group.as_str().to_owned(), *max_threads))
+            .collect();
+        inner.write(ScheduleEvent::RunStarted {
+            test_threads,
+            groups,
+        });
+    }
+
+    /// Records that a unit of work was picked up by the scheduler, and returns the unit to pass
+    /// into [`Self::unit_started`] and [`Self::unit_finished`].
+    pub(super) fn unit_queued<'a>(
+        &self,
+        test_instance: TestInstance<'a>,
+        attempt: usize,
+        threads_required: usize,
+        test_group: Option<&CustomTestGroup>,
+    ) -> ScheduledUnit<'a> {
+        let unit = ScheduledUnit {
+            test_instance,
+            attempt,
+            threads_required,
+            test_group: test_group.cloned(),
+            queued_at: Instant::now(),
+        };
+
+        let mut inner = self.lock();
+        let blocked_on = inner.blocked_on(unit.threads_required, unit.test_group.as_ref());
+        let group_threads_in_use = inner.group_threads_in_use(&unit);
+        let threads_in_use = inner.threads_in_use;
+        inner.write(ScheduleEvent::Queued {
+            unit: unit.info(),
+            threads_in_use,
+            group_threads_in_use,
+            blocked_on,
+        });
+
+        unit
+    }
+
+    /// Records that a unit of work acquired its slots and started running.
+    pub(super) fn unit_started(&self, unit: &ScheduledUnit<'_>) {
+        let mut inner = self.lock();
+        inner.threads_in_use += inner.slot_weight(unit);
+        if let Some(group) = &unit.test_group {
+            *inner.group_threads_in_use.entry(group.clone()).or_default() += 1;
+        }
+
+        let group_threads_in_use = inner.group_threads_in_use(unit);
+        let threads_in_use = inner.threads_in_use;
+        inner.write(ScheduleEvent::Started {
+            unit: unit.info(),
+            waited: unit.queued_at.elapsed().as_secs_f64(),
+            threads_in_use,
+            group_threads_in_use,
+        });
+    }
+
+    /// Records that a unit of work finished and released its slots.
+    pub(super) fn unit_finished(&self, unit: &ScheduledUnit<'_>) {
+        let mut inner = self.lock();
+        inner.threads_in_use = inner.threads_in_use.saturating_sub(inner.slot_weight(unit));
+        if let Some(group) = &unit.test_group {
+            if let Some(count) = inner.group_threads_in_use.get_mut(group) {
+                *count = count.saturating_sub(1);
+            }
+        }
+
+        let group_threads_in_use = inner.group_threads_in_use(unit);
+        let threads_in_use = inner.threads_in_use;
+        inner.write(ScheduleEvent::Finished {
+            unit: unit.info(),
+            threads_in_use,
+            group_threads_in_use,
+        });
+    }
+
+    /// Flushes the log to disk.
+    pub(super) fn finish(&self) {
+        let mut inner = self.lock();
+        if let Err(error) = inner.writer.flush() {
+            warn!("error writing schedule log: {error}");
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ScheduleLogInner> {
+        // The lock is only held while writing out an event, so poisoning can only happen if
+        // serialization panics -- recover the data in that case.
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ScheduleLogInner {
+    fn blocked_on(
+        &self,
+        threads_required: usize,
+        test_group: Option<&CustomTestGroup>,
+    ) -> BlockedOn {
+        if let Some(group) = test_group {
+            let limit = self.group_limits.get(group).copied().unwrap_or(usize::MAX);
+            if self.group_threads_in_use.get(group).copied().unwrap_or(0) >= limit {
+                return BlockedOn::Group;
+            }
+        }
+        // A test that requires more threads than are available is run once all other tests have
+        // finished, so compare against the lesser of the two.
+        let required = threads_required.min(self.test_threads);
+        if self.threads_in_use + required > self.test_threads {
+            BlockedOn::Threads
+        } else {
+            BlockedOn::Nothing
+        }
+    }
+
+    // The scheduler caps the threads a unit requires at the total number of test threads.
+    fn slot_weight(&self, unit: &ScheduledUnit<'_>) -> usize {
+        unit.threads_required.min(self.test_threads)
+    }
+
+    fn group_threads_in_use(&self, unit: &ScheduledUnit<'_>) -> Option<usize> {
+        unit.test_group
+            .as_ref()
+            .map(|group| self.group_threads_in_use.get(group).copied().unwrap_or(0))
+    }
+
+    fn write(&mut self, event: ScheduleEvent<'_>) {
+        if self.failed {
+            return;
+        }
+        let record = ScheduleRecord {
+            elapsed: self.start.elapsed().as_secs_f64(),
+            event,
+        };
+        let res = serde_json::to_writer(&mut self.writer, &record)
+            .map_err(io::Error::from)
+            .and_then(|()| self.writer.write_all(b"\n"));
+        if let Err(error) = res {
+            warn!("error writing schedule log, further events will not be recorded: {error}");
+            self.failed = true;
+        }
+    }
+}
+
+/// A unit of work tracked by the [`ScheduleLog`].
+#[derive(Debug)]
+pub(super) struct ScheduledUnit<'a> {
+    test_instance: TestInstance<'a>,
+    attempt: usize,
+    threads_required: usize,
+    test_group: Option<CustomTestGroup>,
+    queued_at: Instant,
+}
+
+impl ScheduledUnit<'_> {
+    fn info(&self) -> UnitInfo<'_> {
+        UnitInfo {
+            binary_id: self.test_instance.suite_info.binary_id.as_str(),
+            test_name: self.test_instance.name,
+            attempt: self.attempt,
+            threads_required: self.threads_required,
+            test_group: self.test_group.as_ref().map(|group| group.as_str()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ScheduleRecord<'a> {
+    /// Seconds since the start of the run.
+    elapsed: f64,
+    #[serde(flatten)]
+    event: ScheduleEvent<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum ScheduleEvent<'a> {
+    #[serde(rename_all = "kebab-case")]
+    RunStarted {
+        test_threads: usize,
+        groups: BTreeMap<String, usize>,
+    },
+    #[serde(rename_all = "kebab-case")]
+    Queued {
+        #[serde(flatten)]
+        unit: UnitInfo<'a>,
+        threads_in_use: usize,
+        group_threads_in_use: Option<usize>,
+        blocked_on: BlockedOn,
+    },
+    #[serde(rename_all = "kebab-case")]
+    Started {
+        #[serde(flatten)]
+        unit: UnitInfo<'a>,
+        /// Seconds between the unit being queued and it starting.
+        waited: f64,
+        threads_in_use: usize,
+        group_threads_in_use: Option<usize>,
+    },
+    #[serde(rename_all = "kebab-case")]
+    Finished {
+        #[serde(flatten)]
+        unit: UnitInfo<'a>,
+        threads_in_use: usize,
+        group_threads_in_use: Option<usize>,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct UnitInfo<'a> {
+    binary_id: &'a str,
+    test_name: &'a str,
+    attempt: usize,
+    threads_required: usize,
+    test_group: Option<&'a str>,
+}
+
+/// Why a unit of work couldn't start as soon as it was queued.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum BlockedOn {
+    /// Enough slots were available for the unit to start immediately.
+    Nothing,
+
+    /// Not enough test threads were free.
+    Threads,
+
+    /// The unit's test group was at its `max-threads` limit.
+    Group,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::Utf8TempDir;
+
+    #[test]
+    fn test_blocked_on() {
+        let dir = Utf8TempDir::new().expect("created temp dir");
+        let log = ScheduleLog::create(&dir.path().join("schedule.jsonl")).expect("created log");
+        let serial = CustomTestGroup::new("serial".into()).expect("valid group name");
+        log.run_started(4, [(&serial, 1)]);
+
+        let mut inner = log.lock();
+        assert_eq!(inner.blocked_on(1, None), BlockedOn::Nothing);
+        assert_eq!(inner.blocked_on(1, Some(&serial)), BlockedOn::Nothing);
+
+        inner.threads_in_use = 3;
+        assert_eq!(inner.blocked_on(1, None), BlockedOn::Nothing);
+        assert_eq!(inner.blocked_on(2, None), BlockedOn::Threads);
+        // Tests requiring more threads than are available wait for all slots to be free.
+        assert_eq!(inner.blocked_on(8, None), BlockedOn::Threads);
+        inner.threads_in_use = 0;
+        assert_eq!(inner.blocked_on(8, None), BlockedOn::Nothing);
+
+        inner.group_threads_in_use.insert(serial.clone(), 1);
+        assert_eq!(inner.blocked_on(1, Some(&serial)), BlockedOn::Group);
+        assert_eq!(inner.blocked_on(1, None), BlockedOn::Nothing);
+    }
+
+    #[test]
+    fn test_run_started_record() {
+        let dir = Utf8TempDir::new().expect("created temp dir");
+        let path = dir.path().join("schedule.jsonl");
+        let log = ScheduleLog::create(&path).expect("created log");
+        let serial = CustomTestGroup::new("serial".into()).expect("valid group name");
+        log.run_started(4, [(&serial, 1)]);
+        log.finish();
+
+        let contents = std::fs::read_to_string(&path).expect("read log");
+        let record: serde_json::Value =
+            serde_json::from_str(contents.trim_end()).expect("record is valid JSON");
+        assert_eq!(record["type"], "run-started");
+        assert_eq!(record["test-threads"], 4);
+        assert_eq!(record["groups"]["serial"], 1);
+        assert!(record["elapsed"].is_f64(), "elapsed is a float: {record}");
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Cleanup commands registered by setup scripts.
+//!
+//! Setup scripts can write commands to the file pointed to by `NEXTEST_CLEANUP`. Once the run is
+//! over -- whether tests passed, failed, or the run was cancelled -- these commands are run in
+//! reverse order of registration, each with a timeout.
+//!
+//! Commands registered by lazy setup scripts are run earlier, as soon as the last test requiring
+//! the script has finished.
+
+use crate::{config::ScriptId, list::TestList, test_command::apply_ld_dyld_env};
+use quick_junit::ReportUuid;
+use std::{
+    process::{Output, Stdio},
+    sync::Mutex,
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+/// The default timeout for each cleanup command.
+pub(super) const DEFAULT_CLEANUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Cleanup commands registered by setup scripts over the course of a run.
+#[derive(Debug, Default)]
+pub(super) struct ScriptCleanup {
+    commands: Mutex<Vec<CleanupCommand>>,
+}
+
+#[derive(Debug)]
+struct CleanupCommand {
+    script_id: ScriptId,
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl CleanupCommand {
+    fn display(&self) -> String {
+        shell_words::join(std::iter::once(&self.program).chain(&self.args))
+    }
+}
+
+impl ScriptCleanup {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the cleanup commands written out by a setup script.
+    pub(super) fn register(
+        &self,
+        script_id: &ScriptId,
+        timeout: Duration,
+        commands: Vec<(String, Vec<String>)>,
+    ) {
+        let mut registered = self.lock();
+        registered.extend(commands.into_iter().map(|(program, args)| CleanupCommand {
+            script_id: script_id.clone(),
+            program,
+            args,
+            timeout,
+        }));
+    }
+
+    /// Runs all registered cleanup commands, most recently registered first.
+    ///
+    /// Failures are reported but don't stop later cleanup commands from running.
+    pub(super) async fn run(&self, test_list: &TestList<'_>, run_id: ReportUuid) {
+        let commands = std::mem::take(&mut *self.lock());
+        run_commands(commands, test_list, run_id).await;
+    }
+
+    /// Runs the cleanup commands registered by a single setup script, most recently registered
+    /// first.
+    ///
+    /// The commands are removed from the list, so they aren't run again at the end of the run.
+    pub(super) async fn run_for_script(
+        &self,
+        script_id: &ScriptId,
+        test_list: &TestList<'_>,
+        run_id: ReportUuid,
+    ) {
+        let commands = {
+            let mut registered = self.lock();
+            let (commands, rest) = std::mem::take(&mut *registered)
+                .into_iter()
+                .partition(|command| &command.script_id == script_id);
+            *registered = rest;
+            commands
+        };
+        run_commands(commands, test_list, run_id).await;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<CleanupCommand>> {
+        // The lock is never held across a panic point, but recover the data anyway -- running
+        // cleanup commands is more important than propagating the poison.
+        self.commands
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+async fn run_commands(commands: Vec<CleanupCommand>, test_list: &TestList<'_>, run_id: ReportUuid) {
+    if commands.is_empty() {
+        return;
+    }
+
+    info!("running {} setup script cleanup commands", commands.len());
+    let mut failed = 0;
+    for command in commands.iter().rev() {
+        if let Err(message) = run_one(command, test_list, run_id).await {
+            warn!(
+                "cleanup command `{}` for setup script `{}` {message}",
+                command.display(),
+                command.script_id,
+            );
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        warn!(
+            "{failed}/{} setup script cleanup commands failed",
+            commands.len()
+        );
+    }
+}
+
+async fn run_one(
+    command: &CleanupCommand,
+    test_list: &TestList<'_>,
+    run_id: ReportUuid,
+) -> Result<(), String> {
+    let mut cmd = std::process::Command::new(&command.program);
+    cmd.args(&command.args);
+    test_list.cargo_env().apply_env(&mut cmd);
+    cmd.current_dir(test_list.workspace_root())
+        .env("NEXTEST", "1")
+        .env("NEXTEST_RUN_ID", format!("{run_id}"))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    apply_ld_dyld_env(&mut cmd, test_list.updated_dylib_path());
+
+    let mut cmd = tokio::process::Command::from(cmd);
+    // If the timeout is hit, the output future is dropped -- make sure the child is killed.
+    cmd.kill_on_drop(true);
+
+    debug!(
+        "running cleanup command `{}` for setup script `{}`",
+        command.display(),
+        command.script_id,
+    );
+    match tokio::time::timeout(command.timeout, cmd.output()).await {
+        Ok(Ok(output)) if output.status.success() => Ok(()),
+        Ok(Ok(output)) => Err(format!(
+            "failed with {}{}",
+            output.status,
+            stderr_suffix(&output)
+        )),
+        Ok(Err(error)) => Err(format!("could not be run: {error}")),
+        Err(_) => Err(format!(
+            "timed out after {:?} and was killed",
+            command.timeout
+        )),
+    }
+}
+
+fn stderr_suffix(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stderr = stderr.trim_end();
+    if stderr.is_empty() {
+        String::new()
+    } else {
+        format!(", stderr:\n{stderr}")
+    }
+}
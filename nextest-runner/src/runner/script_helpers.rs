@@ -53,3 +53,81 @@ pub(super) async fn parse_env_file(
 
     Ok(SetupScriptEnvMap { env_map })
 }
+
+/// Parses a cleanup file generated by a setup script.
+///
+/// Each non-empty line is a command, split using Unix shell rules.
+pub(super) async fn parse_cleanup_file(
+    cleanup_path: &Utf8Path,
+) -> Result<Vec<(String, Vec<String>)>, SetupScriptOutputError> {
+    let contents = tokio::fs::read_to_string(cleanup_path)
+        .await
+        .map_err(|error| SetupScriptOutputError::CleanupFileRead {
+            path: cleanup_path.to_owned(),
+            error: Arc::new(error),
+        })?;
+    parse_cleanup_commands(cleanup_path, &contents)
+}
+
+fn parse_cleanup_commands(
+    cleanup_path: &Utf8Path,
+    contents: &str,
+) -> Result<Vec<(String, Vec<String>)>, SetupScriptOutputError> {
+    let mut commands = Vec::new();
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parse_err = || SetupScriptOutputError::CleanupFileParse {
+            path: cleanup_path.to_owned(),
+            line: line.to_owned(),
+        };
+        let mut args = shell_words::split(line).map_err(|_| parse_err())?;
+        if args.is_empty() {
+            return Err(parse_err());
+        }
+        let program = args.remove(0);
+        commands.push((program, args));
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cleanup_commands() {
+        let path = Utf8Path::new("cleanup");
+        let commands = parse_cleanup_commands(
+            path,
+            "docker rm -f my-container\n\n  \nrm -rf 'dir with spaces'\n",
+        )
+        .expect("valid cleanup file");
+        assert_eq!(
+            commands,
+            vec![
+                (
+                    "docker".to_owned(),
+                    vec!["rm".to_owned(), "-f".to_owned(), "my-container".to_owned()]
+                ),
+                (
+                    "rm".to_owned(),
+                    vec!["-rf".to_owned(), "dir with spaces".to_owned()]
+                ),
+            ]
+        );
+
+        let error = parse_cleanup_commands(path, "echo 'unterminated\n")
+            .expect_err("unterminated quote is invalid");
+        assert!(
+            matches!(
+                &error,
+                SetupScriptOutputError::CleanupFileParse { line, .. } if line == "echo 'unterminated"
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+}
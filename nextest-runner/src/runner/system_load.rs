@@ -0,0 +1,116 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Sampling system load and available memory, for adaptive concurrency.
+//!
+//! Load averages are read from `/proc/loadavg` on Linux and via
+//! `getloadavg(3)` on macOS and the BSDs. Available memory is only supported
+//! on Linux, where it's read from `/proc/meminfo`. On other platforms, the
+//! corresponding values are `None`.
+
+/// A sample of system load.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct SystemLoad {
+    /// The 1-minute load average.
+    pub(super) load_average: Option<f64>,
+
+    /// The amount of available memory, in bytes.
+    pub(super) available_memory: Option<u64>,
+}
+
+impl SystemLoad {
+    pub(super) fn sample() -> Self {
+        Self {
+            load_average: os::load_average(),
+            available_memory: os::available_memory(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    pub(super) fn load_average() -> Option<f64> {
+        let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+        super::parse_loadavg(&loadavg)
+    }
+
+    pub(super) fn available_memory() -> Option<u64> {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        super::parse_mem_available(&meminfo)
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod os {
+    pub(super) fn load_average() -> Option<f64> {
+        let mut loads = [0.0f64; 1];
+        // SAFETY: getloadavg writes at most `nelem` (here 1) values to the
+        // buffer, which has room for that many.
+        let ret = unsafe { libc::getloadavg(loads.as_mut_ptr(), 1) };
+        (ret == 1).then_some(loads[0])
+    }
+
+    pub(super) fn available_memory() -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+mod os {
+    pub(super) fn load_average() -> Option<f64> {
+        None
+    }
+
+    pub(super) fn available_memory() -> Option<u64> {
+        None
+    }
+}
+
+/// Parses the 1-minute load average out of the contents of `/proc/loadavg`.
+#[cfg_attr(not(any(target_os = "linux", test)), expect(dead_code))]
+fn parse_loadavg(loadavg: &str) -> Option<f64> {
+    loadavg.split_whitespace().next()?.parse().ok()
+}
+
+/// Parses the `MemAvailable` line out of the contents of `/proc/meminfo`,
+/// returning the value in bytes.
+#[cfg_attr(not(any(target_os = "linux", test)), expect(dead_code))]
+fn parse_mem_available(meminfo: &str) -> Option<u64> {
+    let line = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?;
+    let kib: u64 = line.trim().strip_suffix("kB")?.trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_loadavg() {
+        assert_eq!(parse_loadavg("0.52 0.58 0.59 1/467 12345\n"), Some(0.52));
+        assert_eq!(parse_loadavg(""), None);
+    }
+
+    #[test]
+    fn test_parse_mem_available() {
+        let meminfo = "MemTotal:       16303428 kB\n\
+                       MemFree:         1034096 kB\n\
+                       MemAvailable:    8144032 kB\n\
+                       Buffers:          446392 kB\n";
+        assert_eq!(parse_mem_available(meminfo), Some(8144032 * 1024));
+        assert_eq!(parse_mem_available("MemTotal: 16303428 kB\n"), None);
+    }
+}
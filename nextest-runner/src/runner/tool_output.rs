@@ -0,0 +1,167 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parsing the output of error-checking tools such as Valgrind and sanitizers.
+//!
+//! This is used to classify test failures caused by memory errors separately from ordinary test
+//! failures, for tests run with a `wrapper` preset.
+
+use crate::{config::WrapperPreset, reporter::events::MemoryTool, test_output::ChildOutput};
+use once_cell::sync::Lazy;
+use regex::bytes::{Regex, RegexBuilder};
+use std::process::ExitStatus;
+
+/// Returns the tool that reported a memory error for a failed test, if any.
+///
+/// `exit_status` and `output` are those of the failed test. If output wasn't captured, only the
+/// exit status is considered.
+pub(super) fn detect_memory_error(
+    wrapper: WrapperPreset,
+    exit_status: ExitStatus,
+    output: &ChildOutput,
+) -> Option<MemoryTool> {
+    let stderr = match output {
+        ChildOutput::Split(output) => output.stderr.as_ref().map(|stderr| &stderr.buf[..]),
+        // Sanitizer and Valgrind reports go to stderr, but combined output contains it too.
+        ChildOutput::Combined { output } => Some(&output.buf[..]),
+    };
+    detect_memory_error_impl(wrapper, exit_status.code(), stderr)
+}
+
+fn detect_memory_error_impl(
+    wrapper: WrapperPreset,
+    exit_code: Option<i32>,
+    stderr: Option<&[u8]>,
+) -> Option<MemoryTool> {
+    match wrapper {
+        WrapperPreset::None => None,
+        WrapperPreset::Valgrind => {
+            let found = exit_code == Some(WrapperPreset::VALGRIND_ERROR_EXIT_CODE)
+                || stderr.is_some_and(|stderr| VALGRIND_ERROR_REGEX.is_match(stderr));
+            found.then_some(MemoryTool::Valgrind)
+        }
+        WrapperPreset::Sanitizer => {
+            let stderr = stderr?;
+            if let Some(captures) = SANITIZER_ERROR_REGEX.captures(stderr) {
+                return Some(match &captures[1] {
+                    b"AddressSanitizer" => MemoryTool::AddressSanitizer,
+                    b"LeakSanitizer" => MemoryTool::LeakSanitizer,
+                    b"MemorySanitizer" => MemoryTool::MemorySanitizer,
+                    b"ThreadSanitizer" => MemoryTool::ThreadSanitizer,
+                    other => unreachable!(
+                        "regex only matches known sanitizers, found {}",
+                        String::from_utf8_lossy(other)
+                    ),
+                });
+            }
+            UBSAN_ERROR_REGEX
+                .is_match(stderr)
+                .then_some(MemoryTool::UndefinedBehaviorSanitizer)
+        }
+    }
+}
+
+// Valgrind prefixes each line with the process ID, e.g. "==1234== ERROR SUMMARY: 2 errors from 2
+// contexts". The summary isn't printed with --quiet, but may be if users pass in their own options
+// via VALGRIND_OPTS.
+static VALGRIND_ERROR_REGEX_STR: &str = r"^==\d+== ERROR SUMMARY: [1-9]";
+static VALGRIND_ERROR_REGEX: Lazy<Regex> = Lazy::new(|| {
+    let mut builder = RegexBuilder::new(VALGRIND_ERROR_REGEX_STR);
+    builder.multi_line(true);
+    builder.build().unwrap()
+});
+
+// For example, "==1234==ERROR: AddressSanitizer: heap-use-after-free on address ..." or
+// "WARNING: ThreadSanitizer: data race (pid=1234)".
+static SANITIZER_ERROR_REGEX_STR: &str = r"^(?:==\d+==)?(?:ERROR|WARNING): (AddressSanitizer|LeakSanitizer|MemorySanitizer|ThreadSanitizer): ";
+static SANITIZER_ERROR_REGEX: Lazy<Regex> = Lazy::new(|| {
+    let mut builder = RegexBuilder::new(SANITIZER_ERROR_REGEX_STR);
+    builder.multi_line(true);
+    builder.build().unwrap()
+});
+
+// For example, "src/lib.rs:10:5: runtime error: shift exponent 40 is too large".
+static UBSAN_ERROR_REGEX_STR: &str = r":\d+:\d+: runtime error: ";
+static UBSAN_ERROR_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(UBSAN_ERROR_REGEX_STR).unwrap());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(
+        WrapperPreset::Valgrind,
+        Some(97),
+        "",
+        Some(MemoryTool::Valgrind);
+        "valgrind error exit code"
+    )]
+    #[test_case(
+        WrapperPreset::Valgrind,
+        Some(101),
+        "==1234== Invalid read of size 4\n==1234== ERROR SUMMARY: 1 errors from 1 contexts\n",
+        Some(MemoryTool::Valgrind);
+        "valgrind error summary"
+    )]
+    #[test_case(
+        WrapperPreset::Valgrind,
+        Some(101),
+        "==1234== ERROR SUMMARY: 0 errors from 0 contexts\n",
+        None;
+        "valgrind without errors"
+    )]
+    #[test_case(
+        WrapperPreset::Sanitizer,
+        Some(1),
+        "=================================================================\n\
+         ==5678==ERROR: AddressSanitizer: heap-use-after-free on address 0x602000000010\n",
+        Some(MemoryTool::AddressSanitizer);
+        "address sanitizer"
+    )]
+    #[test_case(
+        WrapperPreset::Sanitizer,
+        Some(23),
+        "==5678==ERROR: LeakSanitizer: detected memory leaks\n",
+        Some(MemoryTool::LeakSanitizer);
+        "leak sanitizer"
+    )]
+    #[test_case(
+        WrapperPreset::Sanitizer,
+        Some(66),
+        "==================\nWARNING: ThreadSanitizer: data race (pid=5678)\n",
+        Some(MemoryTool::ThreadSanitizer);
+        "thread sanitizer"
+    )]
+    #[test_case(
+        WrapperPreset::Sanitizer,
+        Some(1),
+        "src/lib.rs:10:5: runtime error: shift exponent 40 is too large for 32-bit type 'int'\n",
+        Some(MemoryTool::UndefinedBehaviorSanitizer);
+        "undefined behavior sanitizer"
+    )]
+    #[test_case(
+        WrapperPreset::Sanitizer,
+        Some(101),
+        "thread 'tests::foo' panicked at src/lib.rs:10:5:\nassertion failed\n",
+        None;
+        "sanitizer with panic"
+    )]
+    #[test_case(
+        WrapperPreset::None,
+        Some(97),
+        "==5678==ERROR: AddressSanitizer: heap-use-after-free\n",
+        None;
+        "no wrapper"
+    )]
+    fn test_detect_memory_error(
+        wrapper: WrapperPreset,
+        exit_code: Option<i32>,
+        stderr: &str,
+        expected: Option<MemoryTool>,
+    ) {
+        assert_eq!(
+            detect_memory_error_impl(wrapper, exit_code, Some(stderr.as_bytes())),
+            expected
+        );
+    }
+}
@@ -3,18 +3,22 @@
 
 use super::{InternalTerminateReason, ShutdownRequest, TerminateChildResult, UnitContext};
 use crate::{
-    errors::ConfigureHandleInheritanceError,
+    config::{
+        CpuSet, IoPriority, NetworkAccess, ProcessPriority, ResourceLimit, ResourceLimitKind,
+        ResourceLimits,
+    },
+    errors::{ChildStartError, ConfigureHandleInheritanceError},
     reporter::events::{
         UnitState, UnitTerminateMethod, UnitTerminateReason, UnitTerminateSignal,
         UnitTerminatingState,
     },
     runner::{RunUnitQuery, RunUnitRequest, SignalRequest},
-    signal::{JobControlEvent, ShutdownEvent},
+    signal::{ForwardedSignal, JobControlEvent, ShutdownEvent},
     test_command::ChildAccumulator,
     time::StopwatchStart,
 };
 use libc::{SIGCONT, SIGHUP, SIGINT, SIGKILL, SIGQUIT, SIGSTOP, SIGTERM, SIGTSTP};
-use std::{convert::Infallible, os::unix::process::CommandExt, time::Duration};
+use std::{convert::Infallible, os::unix::process::CommandExt, sync::Arc, time::Duration};
 use tokio::{process::Child, sync::mpsc::UnboundedReceiver};
 
 // This is a no-op on non-windows platforms.
@@ -31,6 +35,298 @@ pub(super) fn set_process_group(cmd: &mut std::process::Command) {
     cmd.process_group(0);
 }
 
+// The type of resource arguments to getrlimit and setrlimit differs across libcs.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type RlimitResource = libc::c_int;
+
+/// Enables core dumps for the child process, by raising its soft `RLIMIT_CORE` to the hard limit.
+///
+/// This is applied before [`set_rlimits`], so a `core` limit set through `rlimits` takes
+/// precedence.
+pub(super) fn enable_core_dumps(cmd: &mut std::process::Command) {
+    // SAFETY: getrlimit and setrlimit are async-signal-safe, and the closure doesn't allocate.
+    unsafe {
+        cmd.pre_exec(|| {
+            let mut rlimit = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            if libc::getrlimit(libc::RLIMIT_CORE, &mut rlimit) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            rlimit.rlim_cur = rlimit.rlim_max;
+            if libc::setrlimit(libc::RLIMIT_CORE, &rlimit) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Sets the soft resource limits for the child process.
+///
+/// If a limit can't be set (for example, because it's above the hard limit), spawning the child
+/// fails with the corresponding error.
+pub(super) fn set_rlimits(cmd: &mut std::process::Command, limits: ResourceLimits) {
+    if limits.is_empty() {
+        return;
+    }
+
+    // Compute the limits before forking -- pre_exec closures must not allocate.
+    let limits: Vec<(RlimitResource, libc::rlim_t)> = limits
+        .iter()
+        .map(|(kind, limit)| {
+            let resource = match kind {
+                ResourceLimitKind::Nofile => libc::RLIMIT_NOFILE,
+                ResourceLimitKind::Core => libc::RLIMIT_CORE,
+                ResourceLimitKind::Stack => libc::RLIMIT_STACK,
+                ResourceLimitKind::AddressSpace => libc::RLIMIT_AS,
+            };
+            let value = match limit {
+                ResourceLimit::Unlimited => libc::RLIM_INFINITY,
+                // rlim_t is u64 on most, but not all, platforms.
+                #[allow(clippy::unnecessary_cast)]
+                ResourceLimit::Value(value) => value as libc::rlim_t,
+            };
+            (resource, value)
+        })
+        .collect();
+
+    // SAFETY: getrlimit and setrlimit are async-signal-safe, and the closure doesn't allocate.
+    unsafe {
+        cmd.pre_exec(move || {
+            for &(resource, value) in &limits {
+                let mut rlimit = libc::rlimit {
+                    rlim_cur: 0,
+                    rlim_max: 0,
+                };
+                if libc::getrlimit(resource, &mut rlimit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                // Only the soft limit is changed, so that tests can't raise limits above what
+                // nextest itself was run with.
+                rlimit.rlim_cur = value;
+                if libc::setrlimit(resource, &rlimit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Sets the scheduling priorities for the child process.
+///
+/// If a priority can't be set (for example, because lowering the niceness requires privileges),
+/// spawning the child fails with the corresponding error.
+pub(super) fn set_priority(cmd: &mut std::process::Command, priority: ProcessPriority) {
+    let nice = priority.nice;
+    let ioprio = priority.io.and_then(ioprio_value);
+    if nice.is_none() && ioprio.is_none() {
+        return;
+    }
+
+    // SAFETY: setpriority and syscall(SYS_ioprio_set) are async-signal-safe, and the closure
+    // doesn't allocate.
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(nice) = nice {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            if let Some(ioprio) = ioprio {
+                set_ioprio(ioprio)?;
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Returns the value to pass to `ioprio_set` for the given I/O priority, or `None` if I/O
+/// priorities aren't supported on this platform.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn ioprio_value(io: IoPriority) -> Option<libc::c_int> {
+    // These constants are from linux/ioprio.h, which libc doesn't expose.
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    const IOPRIO_CLASS_BE: libc::c_int = 2;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+
+    Some(match io {
+        IoPriority::Idle => IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        IoPriority::BestEffort(level) => {
+            (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | libc::c_int::from(level)
+        }
+    })
+}
+
+/// Sets the I/O priority of the current process.
+///
+/// # Safety
+///
+/// This is called from a `pre_exec` closure, so it must not allocate.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+unsafe fn set_ioprio(ioprio: libc::c_int) -> std::io::Result<()> {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    if libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn ioprio_value(_io: IoPriority) -> Option<libc::c_int> {
+    // Other Unix platforms don't have per-process I/O priorities. Setting them is a no-op there.
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+unsafe fn set_ioprio(_ioprio: libc::c_int) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Denies the child process network access, if requested.
+///
+/// On Linux, the child is moved into a new network namespace. This is done without privileges by
+/// also creating a user namespace, with the current user and group mapped to themselves.
+#[cfg(target_os = "linux")]
+pub(super) fn set_network_access(cmd: &mut std::process::Command, network: NetworkAccess) {
+    if network == NetworkAccess::Allow {
+        return;
+    }
+
+    // Compute the mappings before forking -- pre_exec closures must not allocate.
+    // SAFETY: getuid and getgid always succeed.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let uid_map = format!("{uid} {uid} 1");
+    let gid_map = format!("{gid} {gid} 1");
+
+    // SAFETY: unshare, open, write, close, socket and ioctl are all async-signal-safe, and the
+    // closure doesn't allocate.
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            // setgroups must be denied before an unprivileged process can write gid_map.
+            write_proc_file(c"/proc/self/setgroups", b"deny")?;
+            write_proc_file(c"/proc/self/uid_map", uid_map.as_bytes())?;
+            write_proc_file(c"/proc/self/gid_map", gid_map.as_bytes())?;
+            // New network namespaces start with the loopback interface down. Bring it up so
+            // that tests can still talk to servers they start on localhost.
+            bring_up_loopback()
+        });
+    }
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn write_proc_file(path: &std::ffi::CStr, contents: &[u8]) -> std::io::Result<()> {
+    let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let ret = libc::write(fd, contents.as_ptr().cast(), contents.len());
+    let result = if ret < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    };
+    libc::close(fd);
+    result
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn bring_up_loopback() -> std::io::Result<()> {
+    let sock = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+    if sock < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut ifr: libc::ifreq = std::mem::zeroed();
+    for (dest, src) in ifr.ifr_name.iter_mut().zip(b"lo") {
+        *dest = *src as libc::c_char;
+    }
+    // The type of the ioctl request differs across libcs.
+    let mut ret = libc::ioctl(sock, libc::SIOCGIFFLAGS as _, &mut ifr);
+    if ret == 0 {
+        ifr.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+        ret = libc::ioctl(sock, libc::SIOCSIFFLAGS as _, &ifr);
+    }
+    let result = if ret < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    };
+    libc::close(sock);
+    result
+}
+
+/// Denies the child process network access, if requested.
+///
+/// Without network namespaces, this is best-effort: proxy environment variables are pointed at an
+/// address that refuses connections.
+#[cfg(not(target_os = "linux"))]
+pub(super) fn set_network_access(cmd: &mut std::process::Command, network: NetworkAccess) {
+    if network == NetworkAccess::Deny {
+        crate::test_command::apply_deny_network_env(cmd);
+    }
+}
+
+/// Converts an error spawning a test into a [`ChildStartError`].
+///
+/// If the test was denied network access, errors that creating the user and network namespaces can
+/// fail with are reported as such. They're almost always caused by unprivileged user namespaces
+/// being disabled.
+#[cfg(target_os = "linux")]
+pub(super) fn spawn_error(network: NetworkAccess, error: std::io::Error) -> ChildStartError {
+    let namespace_error = matches!(
+        error.raw_os_error(),
+        Some(libc::EPERM | libc::EINVAL | libc::ENOSPC | libc::EUSERS)
+    );
+    if network == NetworkAccess::Deny && namespace_error {
+        ChildStartError::DenyNetwork(Arc::new(error))
+    } else {
+        ChildStartError::Spawn(Arc::new(error))
+    }
+}
+
+/// Converts an error spawning a test into a [`ChildStartError`].
+#[cfg(not(target_os = "linux"))]
+pub(super) fn spawn_error(_network: NetworkAccess, error: std::io::Error) -> ChildStartError {
+    ChildStartError::Spawn(Arc::new(error))
+}
+
+/// Pins the child process to the given set of CPUs.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(super) fn set_cpu_set(cmd: &mut std::process::Command, _job: Option<&Job>, cpu_set: &CpuSet) {
+    // Build the CPU set before forking -- pre_exec closures must not allocate.
+    // SAFETY: cpu_set_t is a plain bitmask, for which all zeroes is a valid (empty) value.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for cpu in cpu_set.iter() {
+        // SAFETY: CpuSet::MAX_CPU is within CPU_SETSIZE, so this is always in bounds.
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+
+    // SAFETY: sched_setaffinity is async-signal-safe, and the closure doesn't allocate.
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Pins the child process to the given set of CPUs.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(super) fn set_cpu_set(_cmd: &mut std::process::Command, _job: Option<&Job>, _cpu_set: &CpuSet) {
+    // Other Unix platforms either don't support CPU affinity at all (macOS only has affinity
+    // hints), or use incompatible APIs. Pinning is a no-op there.
+}
+
 #[derive(Debug)]
 pub(super) struct Job(());
 
@@ -49,22 +345,38 @@ pub(super) fn assign_process_to_job(
 
 pub(super) fn job_control_child(child: &Child, event: JobControlEvent) {
     if let Some(pid) = child.id() {
-        let pid = pid as i32;
-        // Send the signal to the process group.
-        let signal = match event {
-            JobControlEvent::Stop => SIGTSTP,
-            JobControlEvent::Continue => SIGCONT,
-        };
-        unsafe {
-            // We set up a process group while starting the test -- now send a signal to that
-            // group.
-            libc::kill(-pid, signal);
-        }
+        job_control_process_group(pid, event);
     } else {
         // The child exited already -- don't send a signal.
     }
 }
 
+/// Sends a job control signal to the process group led by `pid`.
+///
+/// This is used after the child has exited, to stop and continue any processes it leaked.
+pub(super) fn job_control_process_group(pid: u32, event: JobControlEvent) {
+    let pid = pid as i32;
+    // Send the signal to the process group.
+    let signal = match event {
+        JobControlEvent::Stop => SIGTSTP,
+        JobControlEvent::Continue => SIGCONT,
+    };
+    unsafe {
+        // We set up a process group while starting the test -- now send a signal to that
+        // group. If no processes are left in the group, this fails with ESRCH, which is fine.
+        libc::kill(-pid, signal);
+    }
+}
+
+pub(super) fn forward_signal_to_child(child: &Child, signal: ForwardedSignal) {
+    if let Some(pid) = child.id() {
+        unsafe {
+            // Send the signal to the process group, like job control signals.
+            libc::kill(-(pid as i32), signal.signal());
+        }
+    }
+}
+
 // Note this is SIGSTOP rather than SIGTSTP to avoid triggering our signal handler.
 pub(super) fn raise_stop() {
     // This can never error out because SIGSTOP is a valid signal.
@@ -149,6 +461,9 @@ pub(super) async fn terminate_child<'a>(
                         }
                         job_control_child(child, JobControlEvent::Continue);
                     }
+                    RunUnitRequest::Signal(SignalRequest::Forward(signal)) => {
+                        forward_signal_to_child(child, signal);
+                    }
                     RunUnitRequest::Signal(SignalRequest::Shutdown(_)) => {
                         // Receiving a shutdown signal while in this state always means kill
                         // immediately.
@@ -237,6 +552,8 @@ fn shutdown_terminate_method(req: ShutdownRequest, grace_period: Duration) -> Un
             UnitTerminateMethod::Signal(UnitTerminateSignal::Interrupt)
         }
         ShutdownRequest::Twice => UnitTerminateMethod::Signal(UnitTerminateSignal::Kill),
+        // Treat control requests like a termination signal.
+        ShutdownRequest::Control => UnitTerminateMethod::Signal(UnitTerminateSignal::Term),
     }
 }
 
@@ -251,3 +568,114 @@ impl UnitTerminateSignal {
         }
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+
+    /// Tells `network_deny_child` that it was started by `network_deny`.
+    const NETWORK_DENY_CHILD_ENV: &str = "__NEXTEST_TEST_NETWORK_DENY_CHILD";
+
+    #[test]
+    fn network_deny() {
+        // Run the checks in a copy of this test binary, started the same way as a test with
+        // `network = "deny"`.
+        let mut cmd = std::process::Command::new(std::env::current_exe().unwrap());
+        cmd.args([
+            "--exact",
+            "runner::os::tests::network_deny_child",
+            "--ignored",
+            "--nocapture",
+        ])
+        .env(NETWORK_DENY_CHILD_ENV, "1");
+        set_network_access(&mut cmd, NetworkAccess::Deny);
+
+        let output = match cmd.output() {
+            Ok(output) => output,
+            Err(error) => match spawn_error(NetworkAccess::Deny, error) {
+                // Some environments, such as unprivileged containers, don't allow creating user
+                // namespaces.
+                ChildStartError::DenyNetwork(error) => {
+                    eprintln!("skipping test, user namespaces are unavailable: {error}");
+                    return;
+                }
+                error => panic!("error spawning child: {error:?}"),
+            },
+        };
+        assert!(
+            output.status.success(),
+            "child failed with {}\n--- stdout:\n{}\n--- stderr:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains("1 passed"),
+            "network_deny_child ran, stdout:\n{stdout}"
+        );
+    }
+
+    #[test]
+    #[ignore = "run by network_deny"]
+    fn network_deny_child() {
+        if std::env::var_os(NETWORK_DENY_CHILD_ENV).is_none() {
+            return;
+        }
+
+        // 192.0.2.1 is reserved for documentation (RFC 5737). With only a loopback interface, there's
+        // no route to it, so connecting fails immediately rather than timing out.
+        let addr = SocketAddr::from(([192, 0, 2, 1], 80));
+        let error = TcpStream::connect_timeout(&addr, Duration::from_secs(5))
+            .expect_err("connecting to a non-loopback address fails");
+        assert_eq!(
+            error.raw_os_error(),
+            Some(libc::ENETUNREACH),
+            "network is unreachable: {error}"
+        );
+
+        // Tests can still start servers on localhost and connect to them.
+        let listener =
+            TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).expect("binding to loopback works");
+        let local_addr = listener.local_addr().unwrap();
+        TcpStream::connect(local_addr).expect("connecting to loopback works");
+        listener.accept().expect("accepting on loopback works");
+    }
+
+    #[test]
+    fn network_deny_spawn_error() {
+        let error = spawn_error(
+            NetworkAccess::Deny,
+            std::io::Error::from_raw_os_error(libc::EPERM),
+        );
+        assert!(
+            matches!(error, ChildStartError::DenyNetwork(_)),
+            "EPERM with network denied is a namespace error: {error:?}"
+        );
+        assert_eq!(
+            error.to_string(),
+            "error denying network access to test (`network = \"deny\"` requires \
+             unprivileged user namespaces to be enabled)",
+        );
+
+        // Errors unrelated to namespaces, and errors for tests with network access, are reported
+        // as regular spawn errors.
+        let error = spawn_error(
+            NetworkAccess::Deny,
+            std::io::Error::from_raw_os_error(libc::ENOENT),
+        );
+        assert!(
+            matches!(error, ChildStartError::Spawn(_)),
+            "ENOENT is a spawn error: {error:?}"
+        );
+        let error = spawn_error(
+            NetworkAccess::Allow,
+            std::io::Error::from_raw_os_error(libc::EPERM),
+        );
+        assert!(
+            matches!(error, ChildStartError::Spawn(_)),
+            "EPERM with network allowed is a spawn error: {error:?}"
+        );
+    }
+}
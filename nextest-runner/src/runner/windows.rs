@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    errors::ConfigureHandleInheritanceError,
+    config::{CpuSet, NetworkAccess, ProcessPriority, ResourceLimits},
+    errors::{ChildStartError, ConfigureHandleInheritanceError},
     reporter::events::{UnitState, UnitTerminateMethod, UnitTerminateReason, UnitTerminatingState},
     runner::{
         InternalTerminateReason, RunUnitQuery, RunUnitRequest, ShutdownRequest, SignalRequest,
@@ -12,8 +13,9 @@ use crate::{
     test_command::ChildAccumulator,
     time::StopwatchStart,
 };
-use std::time::Duration;
+use std::{os::windows::process::CommandExt, sync::Arc, time::Duration};
 use tokio::{process::Child, sync::mpsc::UnboundedReceiver};
+use tracing::warn;
 pub(super) use win32job::Job;
 use win32job::JobError;
 use windows_sys::Win32::{
@@ -21,6 +23,10 @@ use windows_sys::Win32::{
     System::{
         Console::{GetStdHandle, STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
         JobObjects::TerminateJobObject,
+        Threading::{
+            ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+            IDLE_PRIORITY_CLASS,
+        },
     },
 };
 
@@ -56,6 +62,73 @@ pub(super) fn set_process_group(_cmd: &mut std::process::Command) {
     // TODO: set process group on Windows for better ctrl-C handling.
 }
 
+pub(super) fn enable_core_dumps(_cmd: &mut std::process::Command) {
+    // Crash dumps are written by Windows Error Reporting, which is configured system-wide through
+    // the LocalDumps registry key.
+}
+
+pub(super) fn set_rlimits(_cmd: &mut std::process::Command, _limits: ResourceLimits) {
+    // Resource limits are Unix-only. (Job objects support some similar limits, but with different
+    // semantics.)
+}
+
+pub(super) fn set_priority(cmd: &mut std::process::Command, priority: ProcessPriority) {
+    // Windows has priority classes rather than niceness, so map the niceness to the closest
+    // class. I/O priorities can't be set for a process before it starts, so they're ignored.
+    let class = match priority.nice {
+        Some(15..) => IDLE_PRIORITY_CLASS,
+        Some(5..=14) => BELOW_NORMAL_PRIORITY_CLASS,
+        Some(-4..=4) | None => return,
+        Some(-14..=-5) => ABOVE_NORMAL_PRIORITY_CLASS,
+        Some(..=-15) => HIGH_PRIORITY_CLASS,
+    };
+    cmd.creation_flags(class);
+}
+
+pub(super) fn set_network_access(cmd: &mut std::process::Command, network: NetworkAccess) {
+    // Windows has no unprivileged equivalent to network namespaces, so this is best-effort.
+    if network == NetworkAccess::Deny {
+        crate::test_command::apply_deny_network_env(cmd);
+    }
+}
+
+pub(super) fn spawn_error(_network: NetworkAccess, error: std::io::Error) -> ChildStartError {
+    ChildStartError::Spawn(Arc::new(error))
+}
+
+pub(super) fn set_cpu_set(_cmd: &mut std::process::Command, job: Option<&Job>, cpu_set: &CpuSet) {
+    // On Windows, CPU affinity is set as a limit on the job object that the test process is
+    // assigned to.
+    let Some(job) = job else {
+        warn!("unable to pin test to CPUs {cpu_set}: no job object available");
+        return;
+    };
+
+    // Affinity masks can only represent as many CPUs as there are bits in a usize.
+    let mut mask: usize = 0;
+    for cpu in cpu_set.iter() {
+        if cpu >= usize::BITS as usize {
+            warn!(
+                "ignoring CPU {cpu} in cpu-set: CPU indexes above {} are not supported on Windows",
+                usize::BITS - 1
+            );
+            continue;
+        }
+        mask |= 1 << cpu;
+    }
+    if mask == 0 {
+        return;
+    }
+
+    let result = job.query_extended_limit_info().and_then(|mut info| {
+        info.limit_affinity(mask);
+        job.set_extended_limit_info(&mut info)
+    });
+    if let Err(error) = result {
+        warn!("unable to pin test to CPUs {cpu_set}: {error}");
+    }
+}
+
 pub(super) fn assign_process_to_job(
     child: &tokio::process::Child,
     job: Option<&Job>,
@@ -95,9 +168,10 @@ pub(super) async fn terminate_child<'a>(
 
     let child_exited = match term_method {
         UnitTerminateMethod::Wait => {
-            // Unlike Unix, this doesn't need to be a pausable sleep -- we don't
-            // have a notion of pausing nextest on Windows at the moment.
-            let mut sleep = std::pin::pin!(tokio::time::sleep(grace_period));
+            // There's no notion of pausing nextest on Windows at the moment, but
+            // a pausable sleep still excludes time the system spends suspended
+            // from the grace period.
+            let mut sleep = std::pin::pin!(crate::time::pausable_sleep(grace_period));
             let waiting_stopwatch = crate::time::stopwatch();
 
             loop {
@@ -212,5 +286,8 @@ fn shutdown_terminate_method(req: ShutdownRequest, grace_period: Duration) -> Un
         // interrupt, child processes did as well.
         ShutdownRequest::Once(ShutdownEvent::Interrupt) => UnitTerminateMethod::Wait,
         ShutdownRequest::Twice => UnitTerminateMethod::JobObject,
+        // Unlike with interrupts, child processes don't receive anything for
+        // control requests, so there's nothing to wait for.
+        ShutdownRequest::Control => UnitTerminateMethod::JobObject,
     }
 }
@@ -0,0 +1,134 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Preserving failed tests' on-disk state, with `--keep-failed-workdirs`.
+//!
+//! For each failed attempt, a directory named after the test's
+//! [safe name](crate::safe_names::safe_name) is created within the profile's
+//! store directory, containing:
+//!
+//! * `command.txt`: the command line and working directory the test was run with
+//! * `env.txt`: the test's environment
+//! * `stdout` and `stderr` (or `output`, if they were combined): the unfiltered
+//!   captured output
+//! * `tmp` and `home`: the test's temporary and home directories, if
+//!   `isolate-tmpdir` and `isolate-home` were enabled
+
+use crate::{
+    errors::SafeNamesManifestWriteError,
+    list::TestInstanceId,
+    safe_names::SafeNamesDir,
+    test_output::{ChildOutput, ChildSingleOutput},
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempDir;
+use quick_junit::ReportUuid;
+use std::{collections::BTreeMap, ffi::OsString, fmt::Write as _, io};
+
+/// The directory within the store that failed tests' workdirs are kept in.
+const FAILED_WORKDIRS_DIR: &str = "failed-workdirs";
+
+/// Information about how a test was started, captured before it's spawned in
+/// case it fails.
+#[derive(Debug)]
+pub(super) struct WorkdirSnapshot {
+    command: String,
+    env: BTreeMap<OsString, OsString>,
+}
+
+impl WorkdirSnapshot {
+    pub(super) fn capture(cmd: &std::process::Command) -> Self {
+        let mut command = shell_words::join(
+            std::iter::once(cmd.get_program())
+                .chain(cmd.get_args())
+                .map(|arg| arg.to_string_lossy()),
+        );
+        if let Some(dir) = cmd.get_current_dir() {
+            write!(command, "\n(in {})", dir.display()).expect("writing to a String is infallible");
+        }
+        command.push('\n');
+
+        // The test inherits nextest's environment, with the command's changes
+        // applied on top.
+        let mut env: BTreeMap<_, _> = std::env::vars_os().collect();
+        for (key, value) in cmd.get_envs() {
+            match value {
+                Some(value) => {
+                    env.insert(key.to_owned(), value.to_owned());
+                }
+                None => {
+                    env.remove(key);
+                }
+            }
+        }
+
+        Self { command, env }
+    }
+
+    /// Writes out the snapshot, along with the test's output and directories,
+    /// to `dir`.
+    pub(super) fn write(
+        self,
+        dir: &Utf8Path,
+        output: &ChildOutput,
+        tmpdir: Option<Utf8TempDir>,
+        home: Option<Utf8TempDir>,
+    ) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join("command.txt"), &self.command)?;
+
+        let mut env = String::new();
+        for (key, value) in &self.env {
+            writeln!(env, "{}={}", key.to_string_lossy(), value.to_string_lossy())
+                .expect("writing to a String is infallible");
+        }
+        std::fs::write(dir.join("env.txt"), env)?;
+
+        let write_output = |name: &str, output: Option<&ChildSingleOutput>| match output {
+            Some(output) => std::fs::write(dir.join(name), &output.buf),
+            None => Ok(()),
+        };
+        match output {
+            ChildOutput::Split(output) => {
+                write_output("stdout", output.stdout.as_ref())?;
+                write_output("stderr", output.stderr.as_ref())?;
+            }
+            ChildOutput::Combined { output } => write_output("output", Some(output))?,
+        }
+
+        for (name, temp_dir) in [("tmp", tmpdir), ("home", home)] {
+            if let Some(temp_dir) = temp_dir {
+                move_dir(temp_dir, &dir.join(name))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the directory that failed tests' workdirs are kept in for a run.
+pub(super) fn failed_workdirs_dir(store_dir: &Utf8Path, run_id: ReportUuid) -> SafeNamesDir {
+    SafeNamesDir::new(store_dir.join(FAILED_WORKDIRS_DIR).join(run_id.to_string()))
+}
+
+/// Returns the directory a failed attempt's state is kept in.
+pub(super) fn failed_workdir_path(
+    dir: &SafeNamesDir,
+    test_id: TestInstanceId<'_>,
+    attempt: usize,
+) -> Result<Utf8PathBuf, SafeNamesManifestWriteError> {
+    Ok(dir.path_for(test_id)?.join(format!("attempt-{attempt}")))
+}
+
+/// Moves a temporary directory to `dest`, keeping it around.
+///
+/// If the directory can't be renamed, for example because the store is on a
+/// different file system, it's kept in place and its original path is recorded
+/// in `dest.txt` instead.
+fn move_dir(temp_dir: Utf8TempDir, dest: &Utf8Path) -> io::Result<()> {
+    let path = temp_dir.into_path();
+    if std::fs::rename(&path, dest).is_err() {
+        std::fs::write(dest.with_extension("txt"), format!("{path}\n"))?;
+    }
+    Ok(())
+}
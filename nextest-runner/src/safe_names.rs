@@ -0,0 +1,292 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Filesystem-safe names for tests.
+//!
+//! Test names can be arbitrarily long and contain characters like `:`, `<` and `>` that aren't
+//! valid in file names on Windows. Anything that writes files or directories named after tests
+//! should use [`SafeNames`] to obtain a name that works on every platform, and write out its
+//! [manifest](SafeNames::write_manifest) so that names can be mapped back to tests.
+//!
+//! Within nextest, per-test artifacts (failed workdirs, crash dumps and access reports) are kept
+//! in directories named this way, and JUnit reports split by binary or package use
+//! `safe_name_component` for their file names.
+
+use crate::{errors::SafeNamesManifestWriteError, list::TestInstanceId};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Serialize;
+use std::{collections::BTreeMap, io::Write, sync::Mutex};
+use xxhash_rust::xxh64::xxh64;
+
+/// The maximum length of a safe name, in bytes.
+///
+/// This is well below the limit of 255 bytes for a path component on common filesystems, to leave
+/// room for parent directories within the 260-character `MAX_PATH` limit on Windows.
+pub const MAX_SAFE_NAME_LEN: usize = 64;
+
+/// Returns a name for `id` that is safe to use as a file or directory name on all platforms.
+///
+/// Names that are already safe and at most [`MAX_SAFE_NAME_LEN`] bytes long are returned as-is,
+/// with the binary ID and test name separated by `-`. Otherwise, disallowed characters are
+/// replaced with `_`, the name is truncated, and a hash of the original binary ID and test name is
+/// appended so that distinct tests get distinct names.
+pub fn safe_name(id: TestInstanceId<'_>) -> String {
+    let original = format!("{}-{}", id.binary_id, id.test_name);
+
+    // Since the name always contains a `-` before any `.`, it can never be a reserved device name
+    // like `CON` or `NUL` on Windows.
+    sanitize(&original, || {
+        // The hash is computed over the binary ID and test name separately so that, for example,
+        // binary `a-b` with test `c` doesn't collide with binary `a` with test `b-c`.
+        let mut input = Vec::with_capacity(id.binary_id.as_str().len() + id.test_name.len() + 1);
+        input.extend_from_slice(id.binary_id.as_str().as_bytes());
+        input.push(0);
+        input.extend_from_slice(id.test_name.as_bytes());
+        xxh64(&input, 0)
+    })
+}
+
+/// Returns a version of `original` that is safe to use as part of a file name on all platforms.
+///
+/// This follows the same rules as [`safe_name`], with the hash computed over `original`. The caller
+/// is responsible for ensuring that the full file name isn't a reserved device name on Windows,
+/// for example by adding a prefix.
+pub(crate) fn safe_name_component(original: &str) -> String {
+    sanitize(original, || xxh64(original.as_bytes(), 0))
+}
+
+fn sanitize(original: &str, hash: impl FnOnce() -> u64) -> String {
+    let mut sanitized: String = original
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // Windows doesn't allow names to end with a period.
+    while sanitized.ends_with('.') {
+        sanitized.pop();
+    }
+    if sanitized == original && sanitized.len() <= MAX_SAFE_NAME_LEN {
+        return sanitized;
+    }
+
+    let suffix = format!("-{:016x}", hash());
+
+    // The sanitized string is ASCII, so truncating at any byte offset is valid.
+    sanitized.truncate(MAX_SAFE_NAME_LEN - suffix.len());
+    sanitized.push_str(&suffix);
+    sanitized
+}
+
+/// Safe names assigned to tests, along with the reverse mapping.
+#[derive(Clone, Debug, Default)]
+pub struct SafeNames {
+    // safe name -> test
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+impl SafeNames {
+    /// The conventional file name for the manifest, within the directory containing the named
+    /// files.
+    pub const MANIFEST_FILE_NAME: &'static str = "test-names.json";
+
+    /// Creates a new, empty set of names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the safe name for `id`, recording it in the manifest.
+    pub fn name_for(&mut self, id: TestInstanceId<'_>) -> String {
+        let name = safe_name(id);
+        self.entries
+            .entry(name.clone())
+            .or_insert_with(|| ManifestEntry {
+                binary_id: id.binary_id.to_string(),
+                test_name: id.test_name.to_owned(),
+            });
+        name
+    }
+
+    /// Writes the mapping from safe names to tests to `path` as JSON.
+    pub fn write_manifest(&self, path: &Utf8Path) -> Result<(), SafeNamesManifestWriteError> {
+        let contents =
+            serde_json::to_vec_pretty(&self.entries).expect("serializing a map always succeeds");
+        let file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
+        file.write(|f| f.write_all(&contents))
+            .map_err(|error| SafeNamesManifestWriteError {
+                path: path.to_owned(),
+                error: match error {
+                    atomicwrites::Error::Internal(error) | atomicwrites::Error::User(error) => {
+                        error
+                    }
+                },
+            })
+    }
+}
+
+/// A directory with an entry for each of a set of tests, named using [`safe_name`].
+///
+/// The directory's manifest is rewritten each time a new test is added, so that names can be
+/// mapped back to tests even if nextest exits partway through a run.
+#[derive(Debug)]
+pub(crate) struct SafeNamesDir {
+    dir: Utf8PathBuf,
+    names: Mutex<SafeNames>,
+}
+
+impl SafeNamesDir {
+    /// Creates a new `SafeNamesDir` for `dir`. The directory is created lazily.
+    pub(crate) fn new(dir: Utf8PathBuf) -> Self {
+        Self {
+            dir,
+            names: Mutex::new(SafeNames::new()),
+        }
+    }
+
+    /// Returns the path of the entry for `id` within this directory.
+    ///
+    /// The first time this is called for a test, the directory is created if necessary and the
+    /// manifest is updated.
+    pub(crate) fn path_for(
+        &self,
+        id: TestInstanceId<'_>,
+    ) -> Result<Utf8PathBuf, SafeNamesManifestWriteError> {
+        let name = safe_name(id);
+        let mut names = self.names.lock().expect("safe names mutex is not poisoned");
+        if !names.entries.contains_key(&name) {
+            names.name_for(id);
+            let manifest_path = self.dir.join(SafeNames::MANIFEST_FILE_NAME);
+            std::fs::create_dir_all(&self.dir).map_err(|error| SafeNamesManifestWriteError {
+                path: manifest_path.clone(),
+                error,
+            })?;
+            names.write_manifest(&manifest_path)?;
+        }
+        Ok(self.dir.join(name))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ManifestEntry {
+    binary_id: String,
+    test_name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nextest_metadata::RustBinaryId;
+
+    fn id<'a>(binary_id: &'a RustBinaryId, test_name: &'a str) -> TestInstanceId<'a> {
+        TestInstanceId {
+            binary_id,
+            test_name,
+        }
+    }
+
+    #[test]
+    fn test_safe_name() {
+        let binary_id = RustBinaryId::new("my-crate");
+        assert_eq!(
+            safe_name(id(&binary_id, "simple_test")),
+            "my-crate-simple_test",
+            "safe names are unchanged"
+        );
+
+        let name = safe_name(id(&binary_id, "tests::generic<Vec<u8>>"));
+        assert!(
+            name.starts_with("my-crate-tests__generic_Vec_u8__-"),
+            "invalid characters replaced: {name}"
+        );
+        assert_eq!(name.len(), "my-crate-tests__generic_Vec_u8__".len() + 17);
+        assert_ne!(
+            name,
+            safe_name(id(&binary_id, "tests::generic<Vec<u16>>")),
+            "names that sanitize similarly get different hashes"
+        );
+
+        let long_name = "a".repeat(500);
+        let name = safe_name(id(&binary_id, &long_name));
+        assert_eq!(name.len(), MAX_SAFE_NAME_LEN, "long names are truncated");
+
+        let name = safe_name(id(&binary_id, "trailing."));
+        assert!(
+            name.starts_with("my-crate-trailing-"),
+            "trailing periods are removed: {name}"
+        );
+    }
+
+    #[test]
+    fn test_safe_name_component() {
+        assert_eq!(safe_name_component("my-crate"), "my-crate");
+
+        let name = safe_name_component("my-crate::bin/foo");
+        assert!(
+            name.starts_with("my-crate__bin_foo-"),
+            "invalid characters replaced: {name}"
+        );
+        assert_ne!(
+            name,
+            safe_name_component("my-crate__bin_foo"),
+            "names that sanitize similarly get different hashes"
+        );
+    }
+
+    #[test]
+    fn test_safe_names_manifest() {
+        let binary_id = RustBinaryId::new("my-crate::tests");
+        let mut names = SafeNames::new();
+        let name = names.name_for(id(&binary_id, "mod::test"));
+
+        let dir = camino_tempfile::tempdir().unwrap();
+        let path = dir.path().join(SafeNames::MANIFEST_FILE_NAME);
+        names.write_manifest(&path).unwrap();
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            manifest[&name],
+            serde_json::json!({
+                "binary-id": "my-crate::tests",
+                "test-name": "mod::test",
+            }),
+        );
+    }
+
+    #[test]
+    fn test_safe_names_dir() {
+        let temp_dir = camino_tempfile::tempdir().unwrap();
+        let dir = SafeNamesDir::new(temp_dir.path().join("run"));
+        let binary_id = RustBinaryId::new("my-crate::tests");
+        let manifest_path = dir.dir.join(SafeNames::MANIFEST_FILE_NAME);
+        assert!(!manifest_path.exists(), "manifest is written lazily");
+
+        let path = dir.path_for(id(&binary_id, "mod::test")).unwrap();
+        let other_path = dir.path_for(id(&binary_id, "mod__test")).unwrap();
+        assert_eq!(path.parent(), Some(dir.dir.as_path()));
+        assert_ne!(path, other_path, "similar names get distinct paths");
+        assert_eq!(
+            dir.path_for(id(&binary_id, "mod::test")).unwrap(),
+            path,
+            "paths are stable"
+        );
+
+        let manifest: BTreeMap<String, serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        let mut expected = vec![path.file_name().unwrap(), other_path.file_name().unwrap()];
+        expected.sort_unstable();
+        assert_eq!(
+            manifest
+                .keys()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>(),
+            expected,
+            "manifest has an entry for each test"
+        );
+    }
+}
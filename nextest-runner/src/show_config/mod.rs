@@ -6,6 +6,8 @@
 // mod overrides;
 mod nextest_version;
 mod test_groups;
+mod test_settings;
 
 pub use nextest_version::*;
 pub use test_groups::*;
+pub use test_settings::*;
@@ -83,7 +83,9 @@ impl<'a> ShowTestGroups<'a> {
                             .or_insert_with(|| ShowTestGroupsData::new(source));
                         data.matching_tests.insert(&suite.binary_id, test_name);
                     }
-                    SettingSource::Profile => {
+                    // Test groups can't be set by the flaky tests list, but
+                    // treat it like the profile for completeness.
+                    SettingSource::Profile | SettingSource::FlakyTests => {
                         if let Some(non_overrides) = non_overrides.as_mut() {
                             if settings.mode.matches_group(&TestGroup::Global) {
                                 non_overrides.insert(&suite.binary_id, test_name);
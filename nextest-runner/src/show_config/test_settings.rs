@@ -0,0 +1,137 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    config::{EvaluatableProfile, MaybeTargetSpec, SettingSource},
+    flaky::FlakyTests,
+    helpers::QuotedDisplay,
+    list::TestInstance,
+    write_str::WriteStr,
+};
+use nextest_metadata::BuildPlatform;
+use owo_colors::{OwoColorize, Style};
+use std::io;
+
+/// Shows the resolved settings for a single test, along with where each setting came from.
+#[derive(Debug)]
+pub struct ShowTestSettings<'a> {
+    profile_name: &'a str,
+    test_instance: TestInstance<'a>,
+    entries: Vec<(&'static str, String, SettingSource<'a>)>,
+}
+
+impl<'a> ShowTestSettings<'a> {
+    /// Resolves the settings for `test_instance` under the given profile.
+    pub fn new(profile: &'a EvaluatableProfile<'a>, test_instance: TestInstance<'a>) -> Self {
+        let query = test_instance.to_test_query();
+        let entries = profile.settings_with_source_for(&query).entries();
+        Self {
+            profile_name: profile.name(),
+            test_instance,
+            entries,
+        }
+    }
+
+    /// Writes the settings to the given writer in a human-friendly format.
+    pub fn write_human(&self, writer: &mut dyn WriteStr, colorize: bool) -> io::Result<()> {
+        let mut styles = Styles::default();
+        if colorize {
+            styles.colorize();
+        }
+
+        let platform = match self.test_instance.suite_info.build_platform {
+            BuildPlatform::Host => "host",
+            BuildPlatform::Target => "target",
+        };
+        writeln!(
+            writer,
+            "test: {} {} (profile {}, {} platform)",
+            self.test_instance
+                .suite_info
+                .binary_id
+                .style(styles.binary_id),
+            self.test_instance.name.style(styles.test_name),
+            self.profile_name.style(styles.profile),
+            platform,
+        )?;
+
+        let key_width = self
+            .entries
+            .iter()
+            .map(|(key, _, _)| key.len())
+            .max()
+            .unwrap_or_default();
+        for (key, value, source) in &self.entries {
+            write!(
+                writer,
+                "  {:key_width$} = {}  ",
+                key.style(styles.key),
+                value,
+            )?;
+            match source {
+                SettingSource::Profile => {
+                    writeln!(
+                        writer,
+                        "(from profile {})",
+                        self.profile_name.style(styles.profile)
+                    )?;
+                }
+                SettingSource::FlakyTests => {
+                    writeln!(
+                        writer,
+                        "(from flaky-test-retries in profile {}, since the test is listed in {})",
+                        self.profile_name.style(styles.profile),
+                        FlakyTests::PATH,
+                    )?;
+                }
+                SettingSource::Override(override_) => {
+                    let id = override_.id();
+                    write!(
+                        writer,
+                        "(from override {} in profile {}",
+                        id.index + 1,
+                        id.profile_name.style(styles.profile),
+                    )?;
+                    if let Some(expr) = override_.filter() {
+                        write!(
+                            writer,
+                            " with filter {}",
+                            QuotedDisplay(&expr.parsed).style(styles.filter)
+                        )?;
+                    }
+                    if let MaybeTargetSpec::Provided(target_spec) = override_.target_spec() {
+                        write!(
+                            writer,
+                            " on platform {}",
+                            QuotedDisplay(target_spec).style(styles.platform)
+                        )?;
+                    }
+                    writeln!(writer, ")")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Styles {
+    binary_id: Style,
+    test_name: Style,
+    profile: Style,
+    key: Style,
+    filter: Style,
+    platform: Style,
+}
+
+impl Styles {
+    fn colorize(&mut self) {
+        self.binary_id = Style::new().magenta().bold();
+        self.test_name = Style::new().blue().bold();
+        self.profile = Style::new().bold();
+        self.key = Style::new().bold();
+        self.filter = Style::new().yellow();
+        self.platform = Style::new().yellow();
+    }
+}
@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 //! Support for handling signals in nextest.
+//!
+//! How interrupt, termination, hangup and user-defined signals are handled is configurable per
+//! profile via [`SignalConfig`].
 
-use crate::errors::SignalHandlerSetupError;
+use crate::{config::SignalConfig, errors::SignalHandlerSetupError};
 
 /// The kind of signal handling to set up for a test run.
 ///
@@ -12,7 +15,7 @@ use crate::errors::SignalHandlerSetupError;
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
 pub enum SignalHandlerKind {
     /// The standard signal handler. Capture interrupt and termination signals depending on the
-    /// platform.
+    /// platform, and handle them according to the profile's [`SignalConfig`].
     Standard,
 
     /// A no-op signal handler. Useful for tests.
@@ -20,9 +23,12 @@ pub enum SignalHandlerKind {
 }
 
 impl SignalHandlerKind {
-    pub(crate) fn build(self) -> Result<SignalHandler, SignalHandlerSetupError> {
+    pub(crate) fn build(
+        self,
+        config: SignalConfig,
+    ) -> Result<SignalHandler, SignalHandlerSetupError> {
         match self {
-            Self::Standard => SignalHandler::new(),
+            Self::Standard => SignalHandler::new(config),
             Self::Noop => Ok(SignalHandler::noop()),
         }
     }
@@ -37,8 +43,8 @@ pub(crate) struct SignalHandler {
 impl SignalHandler {
     /// Creates a new `SignalHandler` that handles Ctrl-C and other signals.
     #[cfg(any(unix, windows))]
-    pub(crate) fn new() -> Result<Self, SignalHandlerSetupError> {
-        let signals = imp::Signals::new()?;
+    pub(crate) fn new(config: SignalConfig) -> Result<Self, SignalHandlerSetupError> {
+        let signals = imp::Signals::new(config)?;
         Ok(Self {
             signals: Some(signals),
         })
@@ -60,6 +66,7 @@ impl SignalHandler {
 #[cfg(unix)]
 mod imp {
     use super::*;
+    use crate::config::SignalAction;
     use std::io;
     use tokio::signal::unix::{signal, SignalKind};
     use tokio_stream::{wrappers::SignalStream, StreamExt, StreamMap};
@@ -74,6 +81,7 @@ mod imp {
         Cont,
         Info,
         Usr1,
+        Usr2,
     }
 
     /// Signals for SIGINT, SIGTERM and SIGHUP on Unix.
@@ -82,11 +90,12 @@ mod imp {
         // The number of streams is quite small, so a StreamMap (backed by a
         // Vec) is a good option to store the list of streams to poll.
         map: StreamMap<SignalId, SignalStream>,
+        config: SignalConfig,
         sigquit_as_info: bool,
     }
 
     impl Signals {
-        pub(super) fn new() -> io::Result<Self> {
+        pub(super) fn new(config: SignalConfig) -> io::Result<Self> {
             let mut map = StreamMap::new();
 
             // Set up basic signals.
@@ -98,6 +107,7 @@ mod imp {
                 (SignalId::Tstp, signal_stream(tstp_kind())?),
                 (SignalId::Cont, signal_stream(cont_kind())?),
                 (SignalId::Usr1, signal_stream(SignalKind::user_defined1())?),
+                (SignalId::Usr2, signal_stream(SignalKind::user_defined2())?),
             ]);
 
             if let Some(info_kind) = info_kind() {
@@ -112,27 +122,74 @@ mod imp {
 
             Ok(Self {
                 map,
+                config,
                 sigquit_as_info,
             })
         }
 
         pub(super) async fn recv(&mut self) -> Option<SignalEvent> {
-            self.map.next().await.map(|(id, _)| match id {
-                SignalId::Int => SignalEvent::Shutdown(ShutdownEvent::Interrupt),
-                SignalId::Hup => SignalEvent::Shutdown(ShutdownEvent::Hangup),
-                SignalId::Term => SignalEvent::Shutdown(ShutdownEvent::Term),
-                SignalId::Quit => {
-                    if self.sigquit_as_info {
-                        SignalEvent::Info(SignalInfoEvent::Info)
-                    } else {
-                        SignalEvent::Shutdown(ShutdownEvent::Quit)
+            loop {
+                let (id, _) = self.map.next().await?;
+                let event = match id {
+                    SignalId::Int => self.configured_event(
+                        self.config.int,
+                        ShutdownEvent::Interrupt,
+                        ForwardedSignal::Int,
+                    ),
+                    SignalId::Hup => self.configured_event(
+                        self.config.hup,
+                        ShutdownEvent::Hangup,
+                        ForwardedSignal::Hup,
+                    ),
+                    SignalId::Term => self.configured_event(
+                        self.config.term,
+                        ShutdownEvent::Term,
+                        ForwardedSignal::Term,
+                    ),
+                    SignalId::Quit => {
+                        if self.sigquit_as_info {
+                            Some(SignalEvent::Info(SignalInfoEvent::Info))
+                        } else {
+                            Some(SignalEvent::Shutdown(ShutdownEvent::Quit))
+                        }
                     }
+                    SignalId::Tstp => Some(SignalEvent::JobControl(JobControlEvent::Stop)),
+                    SignalId::Cont => Some(SignalEvent::JobControl(JobControlEvent::Continue)),
+                    SignalId::Info => Some(SignalEvent::Info(SignalInfoEvent::Info)),
+                    // User-defined signals don't have a corresponding termination signal, so
+                    // tests are sent SIGTERM on cancellation.
+                    SignalId::Usr1 => self.configured_event(
+                        self.config.usr1,
+                        ShutdownEvent::Term,
+                        ForwardedSignal::Usr1,
+                    ),
+                    SignalId::Usr2 => self.configured_event(
+                        self.config.usr2,
+                        ShutdownEvent::Term,
+                        ForwardedSignal::Usr2,
+                    ),
+                };
+                if let Some(event) = event {
+                    return Some(event);
                 }
-                SignalId::Tstp => SignalEvent::JobControl(JobControlEvent::Stop),
-                SignalId::Cont => SignalEvent::JobControl(JobControlEvent::Continue),
-                SignalId::Info => SignalEvent::Info(SignalInfoEvent::Info),
-                SignalId::Usr1 => SignalEvent::Info(SignalInfoEvent::Usr1),
-            })
+            }
+        }
+
+        fn configured_event(
+            &self,
+            action: SignalAction,
+            shutdown: ShutdownEvent,
+            forward: ForwardedSignal,
+        ) -> Option<SignalEvent> {
+            match action {
+                SignalAction::Cancel => Some(SignalEvent::Shutdown(shutdown)),
+                SignalAction::Forward => Some(SignalEvent::Forward(forward)),
+                SignalAction::Info => Some(SignalEvent::Info(match forward {
+                    ForwardedSignal::Usr1 => SignalInfoEvent::Usr1,
+                    _ => SignalInfoEvent::Info,
+                })),
+                SignalAction::Ignore => None,
+            }
         }
     }
 
@@ -173,33 +230,39 @@ mod imp {
 #[cfg(windows)]
 mod imp {
     use super::*;
+    use crate::config::SignalAction;
     use tokio::signal::windows::{ctrl_c, CtrlC};
 
     #[derive(Debug)]
     pub(super) struct Signals {
         ctrl_c: CtrlC,
         ctrl_c_done: bool,
+        ignore_ctrl_c: bool,
     }
 
     impl Signals {
-        pub(super) fn new() -> std::io::Result<Self> {
+        pub(super) fn new(config: SignalConfig) -> std::io::Result<Self> {
             let ctrl_c = ctrl_c()?;
             Ok(Self {
                 ctrl_c,
                 ctrl_c_done: false,
+                ignore_ctrl_c: config.int == SignalAction::Ignore,
             })
         }
 
         pub(super) async fn recv(&mut self) -> Option<SignalEvent> {
-            if self.ctrl_c_done {
-                return None;
-            }
+            loop {
+                if self.ctrl_c_done {
+                    return None;
+                }
 
-            match self.ctrl_c.recv().await {
-                Some(()) => Some(SignalEvent::Shutdown(ShutdownEvent::Interrupt)),
-                None => {
-                    self.ctrl_c_done = true;
-                    None
+                match self.ctrl_c.recv().await {
+                    Some(()) if self.ignore_ctrl_c => {}
+                    Some(()) => return Some(SignalEvent::Shutdown(ShutdownEvent::Interrupt)),
+                    None => {
+                        self.ctrl_c_done = true;
+                        return None;
+                    }
                 }
             }
         }
@@ -211,10 +274,36 @@ pub(crate) enum SignalEvent {
     #[cfg(unix)]
     JobControl(JobControlEvent),
     Shutdown(ShutdownEvent),
+    #[cfg(unix)]
+    Forward(ForwardedSignal),
     #[cfg_attr(not(unix), expect(dead_code))]
     Info(SignalInfoEvent),
 }
 
+// A signal to be forwarded to running tests.
+#[cfg(unix)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ForwardedSignal {
+    Int,
+    Term,
+    Hup,
+    Usr1,
+    Usr2,
+}
+
+#[cfg(unix)]
+impl ForwardedSignal {
+    pub(crate) fn signal(self) -> libc::c_int {
+        match self {
+            Self::Int => libc::SIGINT,
+            Self::Term => libc::SIGTERM,
+            Self::Hup => libc::SIGHUP,
+            Self::Usr1 => libc::SIGUSR1,
+            Self::Usr2 => libc::SIGUSR2,
+        }
+    }
+}
+
 // A job-control related signal event.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) enum JobControlEvent {
@@ -6,11 +6,12 @@
 use crate::{
     cargo_config::{CargoConfig, CargoConfigSource, CargoConfigs, DiscoveredConfig, Runner},
     errors::TargetRunnerError,
-    platform::BuildPlatforms,
+    platform::{BuildPlatforms, PlatformLibdir},
+    reporter::events::AbortStatus,
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use nextest_metadata::BuildPlatform;
-use std::fmt;
+use std::{fmt, process::ExitStatus};
 use target_spec::Platform;
 
 /// A [target runner](https://doc.rust-lang.org/cargo/reference/config.html#targettriplerunner)
@@ -25,6 +26,9 @@ impl TargetRunner {
     /// Acquires the [target runner](https://doc.rust-lang.org/cargo/reference/config.html#targettriplerunner)
     /// which can be set in a [.cargo/config.toml](https://doc.rust-lang.org/cargo/reference/config.html#hierarchical-structure)
     /// or via a `CARGO_TARGET_{TRIPLE}_RUNNER` environment variable
+    ///
+    /// WASI test binaries can't be executed directly, so if no runner is configured for a WASI
+    /// platform, [`PlatformRunnerSource::WasiDefault`] is used.
     pub fn new(
         configs: &CargoConfigs,
         build_platforms: &BuildPlatforms,
@@ -67,6 +71,37 @@ impl TargetRunner {
         }
     }
 
+    /// Resolves placeholders in runner arguments, and checks that runner binaries exist.
+    ///
+    /// Runner arguments can refer to the Rust libdirs for the host and target platforms with
+    /// `{host-libdir}` and `{target-libdir}` respectively. For archived builds, these point to
+    /// the libdirs extracted from the archive. This allows runners like
+    /// `qemu-aarch64 -L {target-libdir}` to be configured without a wrapper script.
+    ///
+    /// `{workspace-root}` is replaced with `workspace_root`, which is used to give WebAssembly
+    /// runtimes access to the workspace.
+    ///
+    /// Checking runners before the run starts means that a misconfigured runner produces a single
+    /// error, rather than a failure for every test.
+    pub fn resolve(
+        &self,
+        build_platforms: &BuildPlatforms,
+        workspace_root: &Utf8Path,
+    ) -> Result<Self, TargetRunnerError> {
+        let host = self
+            .host
+            .as_ref()
+            .map(|runner| runner.resolve(build_platforms, workspace_root))
+            .transpose()?;
+        let target = self
+            .target
+            .as_ref()
+            .map(|runner| runner.resolve(build_platforms, workspace_root))
+            .transpose()?;
+
+        Ok(Self { host, target })
+    }
+
     /// Returns the platform runners for all build platforms.
     #[inline]
     pub fn all_build_platforms(&self) -> [(BuildPlatform, Option<&PlatformRunner>); 2] {
@@ -92,7 +127,32 @@ impl PlatformRunner {
         configs: &CargoConfigs,
         platform: &Platform,
     ) -> Result<Option<Self>, TargetRunnerError> {
-        Self::find_config(configs, platform)
+        if let Some(runner) = Self::find_config(configs, platform)? {
+            return Ok(Some(runner));
+        }
+
+        Ok(is_wasi(platform).then(Self::wasi_default))
+    }
+
+    /// The runner used for WASI platforms if none is configured.
+    fn wasi_default() -> Self {
+        Self {
+            runner_binary: WASMTIME.into(),
+            args: [
+                "run",
+                // Tests read NEXTEST_* and CARGO_* variables, among others.
+                "-S",
+                "inherit-env",
+                // Tests are run with the package directory as the working directory.
+                "--dir=.",
+                // Paths derived from CARGO_MANIFEST_DIR and similar are absolute.
+                "--dir={workspace-root}",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            source: PlatformRunnerSource::WasiDefault,
+        }
     }
 
     /// Attempts to find a target runner for the specified target from a
@@ -264,6 +324,57 @@ impl PlatformRunner {
         }
     }
 
+    fn resolve(
+        &self,
+        build_platforms: &BuildPlatforms,
+        workspace_root: &Utf8Path,
+    ) -> Result<Self, TargetRunnerError> {
+        let target_libdir = match &build_platforms.target {
+            Some(target) => &target.libdir,
+            None => &build_platforms.host.libdir,
+        };
+        let placeholders = [
+            ("{host-libdir}", &build_platforms.host.libdir),
+            ("{target-libdir}", target_libdir),
+        ];
+
+        let mut args = Vec::with_capacity(self.args.len());
+        for arg in &self.args {
+            let mut arg = arg.replace("{workspace-root}", workspace_root.as_str());
+            for (placeholder, libdir) in placeholders {
+                if !arg.contains(placeholder) {
+                    continue;
+                }
+                match libdir {
+                    PlatformLibdir::Available(path) => {
+                        arg = arg.replace(placeholder, path.as_str());
+                    }
+                    PlatformLibdir::Unavailable(reason) => {
+                        return Err(TargetRunnerError::LibdirUnavailable {
+                            key: self.source.clone(),
+                            placeholder,
+                            reason: reason.clone(),
+                        });
+                    }
+                }
+            }
+            args.push(arg);
+        }
+
+        if !runner_binary_exists(&self.runner_binary) {
+            return Err(TargetRunnerError::BinaryNotFound {
+                key: self.source.clone(),
+                binary: self.runner_binary.clone(),
+            });
+        }
+
+        Ok(Self {
+            runner_binary: self.runner_binary.clone(),
+            args,
+            source: self.source.clone(),
+        })
+    }
+
     /// Gets the runner binary path.
     ///
     /// Note that this is returned as a `str` specifically to avoid duct's
@@ -286,6 +397,75 @@ impl PlatformRunner {
     pub fn source(&self) -> &PlatformRunnerSource {
         &self.source
     }
+
+    /// Returns the abort status for a test that exited with `exit_status` under this runner, if
+    /// the runner reports aborts through its exit code.
+    ///
+    /// wasmtime exits with code 134 (128 + SIGABRT) on Unix if the module traps, which is how a
+    /// panic in a WASI test surfaces since WASI targets use `panic = "abort"`.
+    pub(crate) fn abort_status(&self, exit_status: ExitStatus) -> Option<AbortStatus> {
+        cfg_if::cfg_if! {
+            if #[cfg(unix)] {
+                let is_wasmtime_trap = self.runner_binary.file_stem() == Some(WASMTIME)
+                    && exit_status.code() == Some(128 + libc::SIGABRT);
+                is_wasmtime_trap.then_some(AbortStatus::UnixSignal(libc::SIGABRT))
+            } else {
+                let _ = exit_status;
+                None
+            }
+        }
+    }
+}
+
+const WASMTIME: &str = "wasmtime";
+
+fn is_wasi(platform: &Platform) -> bool {
+    // wasm32-wasi, wasm32-wasip1, wasm32-wasip1-threads, wasm32-wasip2 etc.
+    platform
+        .triple_str()
+        .split('-')
+        .any(|component| component.starts_with("wasi"))
+}
+
+fn runner_binary_exists(runner_binary: &Utf8Path) -> bool {
+    // Runners specified as paths were made absolute by normalize_runner, while pathless names
+    // are looked up in PATH.
+    if runner_binary
+        .parent()
+        .is_some_and(|parent| !parent.as_str().is_empty())
+    {
+        return is_executable_file(runner_binary.as_std_path());
+    }
+
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&paths).any(|dir| is_executable_file(&dir.join(runner_binary)))
+}
+
+/// Returns true if `path`, or on Windows `path` with one of the extensions in `PATHEXT`
+/// appended, is a file.
+fn is_executable_file(path: &std::path::Path) -> bool {
+    if path.is_file() {
+        return true;
+    }
+    if !cfg!(windows) {
+        return false;
+    }
+
+    // This is the default value of PATHEXT on Windows.
+    let path_ext = std::env::var_os("PATHEXT")
+        .unwrap_or_else(|| ".COM;.EXE;.BAT;.CMD".into())
+        .to_string_lossy()
+        .into_owned();
+    path_ext
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .any(|ext| {
+            let mut candidate = path.as_os_str().to_owned();
+            candidate.push(ext);
+            std::path::Path::new(&candidate).is_file()
+        })
 }
 
 /// The place where a platform runner's configuration was picked up from.
@@ -309,13 +489,16 @@ pub enum PlatformRunnerSource {
         /// If `target.'cfg(target_os = "linux")'.runner` is used, this is `cfg(target_os = "linux")`.
         target_table: String,
     },
+
+    /// No runner was configured for a WASI platform, so nextest's default of `wasmtime` was used.
+    WasiDefault,
 }
 
 impl PlatformRunnerSource {
     // https://github.com/rust-lang/cargo/blob/3959f87158ea4f8733e2fcbe032b8a50ae0b6834/src/cargo/util/config/value.rs#L66-L75
     fn resolve_dir<'a>(&'a self, cwd: &'a Utf8Path) -> &'a Utf8Path {
         match self {
-            Self::Env(_) => cwd,
+            Self::Env(_) | Self::WasiDefault => cwd,
             Self::CargoConfig { source, .. } => source.resolve_dir(cwd),
         }
     }
@@ -339,6 +522,9 @@ impl fmt::Display for PlatformRunnerSource {
             } => {
                 write!(f, "`target.{target_table}.runner` within `{path}`")
             }
+            Self::WasiDefault => {
+                write!(f, "nextest's default for WASI targets")
+            }
         }
     }
 }
@@ -348,6 +534,7 @@ mod tests {
     use super::*;
     use camino_tempfile::Utf8TempDir;
     use color_eyre::eyre::{Context, Result};
+    use nextest_metadata::PlatformLibdirUnavailable;
     use target_spec::TargetFeatures;
 
     #[test]
@@ -578,6 +765,116 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_placeholders() {
+        let dir = setup_temp_dir().unwrap();
+        let dir_path = dir.path().canonicalize_utf8().unwrap();
+        let runner_binary = dir_path.join("qemu-aarch64");
+        std::fs::write(&runner_binary, "").unwrap();
+
+        let source =
+            PlatformRunnerSource::Env("CARGO_TARGET_AARCH64_UNKNOWN_LINUX_GNU_RUNNER".to_owned());
+        let runner = PlatformRunner {
+            runner_binary: runner_binary.clone(),
+            args: vec!["-L".to_owned(), "{target-libdir}/..".to_owned()],
+            source: source.clone(),
+        };
+        let target_runner = TargetRunner {
+            host: None,
+            target: Some(runner),
+        };
+
+        let mut build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+        build_platforms.host.libdir = PlatformLibdir::Available("/fake/libdir".into());
+        let resolved = target_runner.resolve(&build_platforms, &dir_path).unwrap();
+        let target = resolved.target().unwrap();
+        assert_eq!(target.binary(), runner_binary.as_str());
+        assert_eq!(
+            target.args().collect::<Vec<_>>(),
+            vec!["-L", "/fake/libdir/.."],
+            "target libdir falls back to host libdir without a target"
+        );
+
+        build_platforms.host.libdir =
+            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::NOT_IN_ARCHIVE);
+        let error = target_runner
+            .resolve(&build_platforms, &dir_path)
+            .unwrap_err();
+        assert!(
+            matches!(
+                error,
+                TargetRunnerError::LibdirUnavailable {
+                    placeholder: "{target-libdir}",
+                    ..
+                }
+            ),
+            "unavailable libdir produces error: {error}"
+        );
+
+        let missing_runner = TargetRunner {
+            host: None,
+            target: Some(PlatformRunner {
+                runner_binary: dir_path.join("does-not-exist"),
+                args: Vec::new(),
+                source,
+            }),
+        };
+        let error = missing_runner
+            .resolve(&build_platforms, &dir_path)
+            .unwrap_err();
+        assert!(
+            matches!(error, TargetRunnerError::BinaryNotFound { .. }),
+            "missing binary produces error: {error}"
+        );
+    }
+
+    #[test]
+    fn test_wasi_default() {
+        for (triple, expected) in [
+            ("wasm32-wasip1", true),
+            ("wasm32-wasip1-threads", true),
+            ("wasm32-wasip2", true),
+            ("wasm32-unknown-unknown", false),
+            ("x86_64-unknown-linux-gnu", false),
+        ] {
+            let platform = Platform::new(triple, TargetFeatures::Unknown).unwrap();
+            assert_eq!(is_wasi(&platform), expected, "for triple {triple}");
+        }
+
+        let runner = PlatformRunner::wasi_default();
+        assert_eq!(runner.binary(), "wasmtime");
+        assert_eq!(runner.source(), &PlatformRunnerSource::WasiDefault);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+
+            // Exit codes are stored in the high byte of the wait status.
+            let trapped = ExitStatus::from_raw(134 << 8);
+            let failed = ExitStatus::from_raw(101 << 8);
+            assert_eq!(
+                runner.abort_status(trapped),
+                Some(AbortStatus::UnixSignal(libc::SIGABRT)),
+            );
+            assert_eq!(runner.abort_status(failed), None);
+        }
+    }
+
+    #[test]
+    fn test_runner_binary_exists() {
+        let dir = Utf8TempDir::new().unwrap();
+        std::fs::write(dir.path().join("runner"), "").unwrap();
+        assert!(runner_binary_exists(&dir.path().join("runner")));
+        assert!(!runner_binary_exists(&dir.path().join("missing")));
+
+        // On Windows, runners can be batch files that are named without their extension.
+        std::fs::write(dir.path().join("batch-runner.cmd"), "").unwrap();
+        assert_eq!(
+            runner_binary_exists(&dir.path().join("batch-runner")),
+            cfg!(windows),
+        );
+    }
+
     fn setup_temp_dir() -> Result<Utf8TempDir> {
         let dir = camino_tempfile::Builder::new()
             .tempdir()
@@ -3,21 +3,25 @@
 
 use crate::{
     cargo_config::EnvironmentMap,
+    config::{DesktopIsolation, EnvVarPattern, TestStdin},
     double_spawn::{DoubleSpawnContext, DoubleSpawnInfo},
     helpers::dylib_path_envvar,
     list::{RustBuildMeta, TestListState},
     test_output::CaptureStrategy,
+    warnings::{self, WarningCode},
 };
 use camino::{Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempDir;
 use guppy::graph::PackageMetadata;
 use once_cell::sync::Lazy;
+use quick_junit::ReportUuid;
 use std::{
     collections::{BTreeSet, HashMap},
     ffi::{OsStr, OsString},
     fs::File,
     io::{BufRead, BufReader},
 };
-use tracing::warn;
+use tracing::debug;
 
 mod imp;
 pub(crate) use imp::{Child, ChildAccumulator, ChildFds};
@@ -195,11 +199,17 @@ fn apply_package_env(cmd: &mut std::process::Command, package: &PackageMetadata<
 /// Applies environment variables spcified by the build script via `cargo::rustc-env`
 fn apply_build_script_env(cmd: &mut std::process::Command, out_dir: &Utf8Path) {
     let Some(out_dir_parent) = out_dir.parent() else {
-        warn!("could not determine parent directory of output directory {out_dir}");
+        warnings::emit(
+            WarningCode::BuildScriptOutput,
+            format_args!("could not determine parent directory of output directory {out_dir}"),
+        );
         return;
     };
     let Ok(out_file) = File::open(out_dir_parent.join("output")) else {
-        warn!("could not find build script output file at {out_dir_parent}/output");
+        warnings::emit(
+            WarningCode::BuildScriptOutput,
+            format_args!("could not find build script output file at {out_dir_parent}/output"),
+        );
         return;
     };
     parse_build_script_output(
@@ -222,7 +232,12 @@ where
 {
     for line in out_file.lines() {
         let Ok(line) = line else {
-            warn!("in build script output `{out_file_path}`, found line with invalid UTF-8");
+            warnings::emit(
+                WarningCode::BuildScriptOutput,
+                format_args!(
+                    "in build script output `{out_file_path}`, found line with invalid UTF-8"
+                ),
+            );
             continue;
         };
         // `cargo::rustc-env` is the official syntax since `cargo` 1.77, `cargo:rustc-env` is
@@ -234,13 +249,277 @@ where
             continue;
         };
         let Some((k, v)) = key_val.split_once('=') else {
-            warn!("rustc-env variable '{key_val}' has no value in {out_file_path}, skipping");
+            warnings::emit(
+                WarningCode::BuildScriptOutput,
+                format_args!(
+                    "rustc-env variable '{key_val}' has no value in {out_file_path}, skipping"
+                ),
+            );
             continue;
         };
         callback(k, v);
     }
 }
 
+/// Creates a temporary home directory for a single test attempt, and points the
+/// command's home-related environment variables at it.
+///
+/// On Windows, `USERPROFILE`, `APPDATA` and `LOCALAPPDATA` are redirected as
+/// well, since most programs use those rather than `HOME`.
+///
+/// The directory is deleted when the returned [`Utf8TempDir`] is dropped.
+pub(crate) fn create_isolated_home(
+    cmd: &mut std::process::Command,
+    run_id: ReportUuid,
+) -> std::io::Result<Utf8TempDir> {
+    let dir = camino_tempfile::Builder::new()
+        .prefix(&format!("nextest-home-{run_id}-"))
+        .tempdir()?;
+    let home = dir.path();
+    cmd.env("HOME", home);
+
+    #[cfg(windows)]
+    {
+        let app_data = home.join("AppData").join("Roaming");
+        let local_app_data = home.join("AppData").join("Local");
+        std::fs::create_dir_all(&app_data)?;
+        std::fs::create_dir_all(&local_app_data)?;
+        cmd.env("USERPROFILE", home)
+            .env("APPDATA", &app_data)
+            .env("LOCALAPPDATA", &local_app_data);
+    }
+
+    Ok(dir)
+}
+
+/// Creates a temporary directory for a single test attempt, and points the
+/// command's `TMPDIR` and `NEXTEST_TMPDIR` at it.
+///
+/// On Windows, `TEMP` and `TMP` are redirected as well, since that's where the
+/// temporary directory is looked up there.
+///
+/// The directory is created within nextest's own temporary directory, and is
+/// deleted when the returned [`Utf8TempDir`] is dropped.
+pub(crate) fn create_isolated_tmpdir(
+    cmd: &mut std::process::Command,
+    run_id: ReportUuid,
+) -> std::io::Result<Utf8TempDir> {
+    let dir = camino_tempfile::Builder::new()
+        .prefix(&format!("nextest-tmp-{run_id}-"))
+        .tempdir()?;
+    let tmpdir = dir.path();
+    cmd.env("TMPDIR", tmpdir).env("NEXTEST_TMPDIR", tmpdir);
+
+    #[cfg(windows)]
+    {
+        cmd.env("TEMP", tmpdir).env("TMP", tmpdir);
+    }
+
+    Ok(dir)
+}
+
+/// Connects the command's standard input as specified by `stdin`.
+///
+/// Files are resolved relative to `workspace_root`, and opened afresh for each attempt so that
+/// retries see the same input. On error, returns the path that couldn't be opened.
+pub(crate) fn apply_stdin(
+    cmd: &mut std::process::Command,
+    stdin: &TestStdin,
+    workspace_root: &Utf8Path,
+) -> Result<(), (Utf8PathBuf, std::io::Error)> {
+    let stdin = match stdin {
+        TestStdin::Null => std::process::Stdio::null(),
+        TestStdin::Inherit => std::process::Stdio::inherit(),
+        TestStdin::File(path) => {
+            let path = workspace_root.join(path);
+            let file = File::open(&path).map_err(|error| (path, error))?;
+            std::process::Stdio::from(file)
+        }
+    };
+    cmd.stdin(stdin);
+    Ok(())
+}
+
+/// Per-attempt isolation from the desktop session, created by [`create_isolated_desktop`].
+///
+/// The D-Bus session bus, if any, is stopped and the XDG directories are deleted when this is
+/// dropped.
+pub(crate) struct IsolatedDesktop {
+    // Fields are dropped in declaration order, so the bus is stopped before its socket's directory
+    // is deleted.
+    _dbus: Option<tokio::process::Child>,
+    _dir: Utf8TempDir,
+}
+
+/// Creates temporary XDG directories for a single test attempt, points the command's XDG
+/// environment variables at them, and optionally starts a private D-Bus session bus.
+///
+/// Returns `None` if `isolation` is [`DesktopIsolation::None`], or on platforms without XDG
+/// desktop sessions.
+pub(crate) async fn create_isolated_desktop(
+    cmd: &mut std::process::Command,
+    run_id: ReportUuid,
+    isolation: DesktopIsolation,
+) -> std::io::Result<Option<IsolatedDesktop>> {
+    if !isolation.isolates_xdg() || cfg!(any(windows, target_os = "macos")) {
+        return Ok(None);
+    }
+
+    let dir = camino_tempfile::Builder::new()
+        .prefix(&format!("nextest-xdg-{run_id}-"))
+        .tempdir()?;
+    let runtime_dir = dir.path().join("runtime");
+    let cache_home = dir.path().join("cache");
+    let config_home = dir.path().join("config");
+    for path in [&runtime_dir, &cache_home, &config_home] {
+        std::fs::create_dir(path)?;
+    }
+    // The XDG base directory specification requires the runtime directory to be accessible only
+    // by its owner.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&runtime_dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    cmd.env("XDG_RUNTIME_DIR", &runtime_dir)
+        .env("XDG_CACHE_HOME", &cache_home)
+        .env("XDG_CONFIG_HOME", &config_home);
+
+    let dbus = if isolation.isolates_dbus() {
+        Some(start_dbus_session(cmd, &runtime_dir).await?)
+    } else {
+        None
+    };
+
+    Ok(Some(IsolatedDesktop {
+        _dbus: dbus,
+        _dir: dir,
+    }))
+}
+
+/// Starts a D-Bus session bus listening within `runtime_dir`, and points the command at it.
+async fn start_dbus_session(
+    cmd: &mut std::process::Command,
+    runtime_dir: &Utf8Path,
+) -> std::io::Result<tokio::process::Child> {
+    use tokio::io::AsyncBufReadExt;
+
+    let mut child = tokio::process::Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--nopidfile", "--print-address=1"])
+        .arg(format!("--address=unix:path={}", runtime_dir.join("bus")))
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // dbus-daemon prints its address once it's ready to accept connections.
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let mut address = String::new();
+    tokio::io::BufReader::new(stdout)
+        .read_line(&mut address)
+        .await?;
+    let address = address.trim();
+    if address.is_empty() {
+        return Err(std::io::Error::other(
+            "dbus-daemon exited without printing its address",
+        ));
+    }
+    debug!("started private D-Bus session at {address}");
+    cmd.env("DBUS_SESSION_BUS_ADDRESS", address);
+
+    Ok(child)
+}
+
+/// The message a test writes to `NEXTEST_CANCEL_ACK_PATH` to acknowledge being stopped.
+pub(crate) const CANCEL_ACK_MESSAGE: &str = "acknowledged";
+
+/// A file that a single test attempt can write to, to acknowledge that it's been asked to stop.
+///
+/// The file is created by the test, not by nextest. It's deleted along with its directory when
+/// this is dropped.
+pub(crate) struct CancelAck {
+    dir: Utf8TempDir,
+}
+
+impl CancelAck {
+    const FILE_NAME: &'static str = "cancel-ack";
+
+    /// Returns true if the test wrote [`CANCEL_ACK_MESSAGE`] to the file.
+    pub(crate) fn is_acknowledged(&self) -> bool {
+        // Surrounding whitespace, such as a trailing newline, is ignored.
+        std::fs::read_to_string(self.dir.path().join(Self::FILE_NAME))
+            .is_ok_and(|contents| contents.trim() == CANCEL_ACK_MESSAGE)
+    }
+}
+
+/// Creates a temporary directory for a single test attempt, and points
+/// `NEXTEST_CANCEL_ACK_PATH` at a file within it.
+pub(crate) fn create_cancel_ack(
+    cmd: &mut std::process::Command,
+    run_id: ReportUuid,
+) -> std::io::Result<CancelAck> {
+    let dir = camino_tempfile::Builder::new()
+        .prefix(&format!("nextest-cancel-{run_id}-"))
+        .tempdir()?;
+    cmd.env(
+        "NEXTEST_CANCEL_ACK_PATH",
+        dir.path().join(CancelAck::FILE_NAME),
+    );
+    Ok(CancelAck { dir })
+}
+
+/// The proxy that network access is sent to with `network = "deny"`, on
+/// platforms without network namespaces.
+///
+/// Nothing listens on the discard port in practice, so connections through the
+/// proxy are refused.
+#[cfg(not(target_os = "linux"))]
+pub(crate) const DENY_NETWORK_PROXY: &str = "http://127.0.0.1:9";
+
+/// Points proxy environment variables at [`DENY_NETWORK_PROXY`], as a
+/// best-effort way to deny network access to clients that honor them.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn apply_deny_network_env(cmd: &mut std::process::Command) {
+    for var in [
+        "http_proxy",
+        "https_proxy",
+        "all_proxy",
+        "HTTP_PROXY",
+        "HTTPS_PROXY",
+        "ALL_PROXY",
+    ] {
+        cmd.env(var, DENY_NETWORK_PROXY);
+    }
+    // Don't let any hosts bypass the proxy.
+    cmd.env_remove("no_proxy");
+    cmd.env_remove("NO_PROXY");
+}
+
+/// Removes environment variables matching any of `patterns` from the command's
+/// environment, whether they were set on the command or would be inherited from
+/// nextest's own environment.
+pub(crate) fn apply_suppress_env(cmd: &mut std::process::Command, patterns: &[EnvVarPattern]) {
+    if patterns.is_empty() {
+        return;
+    }
+
+    let names: BTreeSet<OsString> = cmd
+        .get_envs()
+        .map(|(k, _)| k.to_owned())
+        .chain(std::env::vars_os().map(|(k, _)| k))
+        .collect();
+    for name in names {
+        let Some(name_str) = name.to_str() else {
+            continue;
+        };
+        if patterns.iter().any(|pattern| pattern.matches(name_str)) {
+            debug!("suppressing environment variable {name_str}");
+            cmd.env_remove(&name);
+        }
+    }
+}
+
 /// This is a workaround for a macOS SIP issue:
 /// https://github.com/nextest-rs/nextest/pull/84
 ///
@@ -320,4 +599,153 @@ mod tests {
             "parsed key-value pairs match"
         );
     }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn isolated_desktop_xdg() {
+        let mut cmd = std::process::Command::new("test-binary");
+        let isolated =
+            create_isolated_desktop(&mut cmd, ReportUuid::new_v4(), DesktopIsolation::Xdg)
+                .await
+                .expect("isolation succeeded")
+                .expect("XDG isolation is supported on Linux");
+
+        let envs: HashMap<_, _> = cmd
+            .get_envs()
+            .filter_map(|(k, v)| Some((k.to_str()?, Utf8PathBuf::from(v?.to_str()?))))
+            .collect();
+        for var in ["XDG_RUNTIME_DIR", "XDG_CACHE_HOME", "XDG_CONFIG_HOME"] {
+            assert!(envs[var].is_dir(), "{var} points to a directory");
+        }
+        assert!(
+            !envs.contains_key("DBUS_SESSION_BUS_ADDRESS"),
+            "no D-Bus session started"
+        );
+
+        let runtime_dir = envs["XDG_RUNTIME_DIR"].clone();
+        drop(isolated);
+        assert!(!runtime_dir.exists(), "directories removed on drop");
+    }
+
+    #[tokio::test]
+    async fn isolated_desktop_none() {
+        let mut cmd = std::process::Command::new("test-binary");
+        let isolated =
+            create_isolated_desktop(&mut cmd, ReportUuid::new_v4(), DesktopIsolation::None)
+                .await
+                .expect("isolation succeeded");
+        assert!(isolated.is_none(), "no isolation performed");
+        assert_eq!(cmd.get_envs().count(), 0, "no variables set");
+    }
+
+    #[test]
+    fn isolated_home() {
+        let mut cmd = std::process::Command::new("test-binary");
+        let home = create_isolated_home(&mut cmd, ReportUuid::new_v4()).unwrap();
+
+        let envs: HashMap<_, _> = cmd
+            .get_envs()
+            .filter_map(|(k, v)| Some((k.to_str()?, Utf8PathBuf::from(v?.to_str()?))))
+            .collect();
+        assert_eq!(envs["HOME"], home.path(), "HOME is set");
+        assert!(home.path().is_dir(), "directory created");
+
+        #[cfg(windows)]
+        {
+            assert_eq!(envs["USERPROFILE"], home.path(), "USERPROFILE is set");
+            for var in ["APPDATA", "LOCALAPPDATA"] {
+                assert!(
+                    envs[var].starts_with(home.path()),
+                    "{var} is within the home directory"
+                );
+                assert!(envs[var].is_dir(), "{var} points to a directory");
+            }
+        }
+
+        let path = home.path().to_owned();
+        drop(home);
+        assert!(!path.exists(), "directory removed on drop");
+    }
+
+    #[test]
+    fn isolated_tmpdir() {
+        let mut cmd = std::process::Command::new("test-binary");
+        let tmpdir = create_isolated_tmpdir(&mut cmd, ReportUuid::new_v4()).unwrap();
+
+        let envs: HashMap<_, _> = cmd
+            .get_envs()
+            .filter_map(|(k, v)| Some((k.to_str()?, Utf8PathBuf::from(v?.to_str()?))))
+            .collect();
+        assert_eq!(envs["TMPDIR"], tmpdir.path(), "TMPDIR is set");
+        assert_eq!(
+            envs["NEXTEST_TMPDIR"],
+            tmpdir.path(),
+            "NEXTEST_TMPDIR is set"
+        );
+        assert!(tmpdir.path().is_dir(), "directory created");
+
+        let path = tmpdir.path().to_owned();
+        drop(tmpdir);
+        assert!(!path.exists(), "directory removed on drop");
+    }
+
+    #[test]
+    fn cancel_ack() {
+        let mut cmd = std::process::Command::new("true");
+        let cancel_ack = create_cancel_ack(&mut cmd, ReportUuid::new_v4()).unwrap();
+        let path = cmd
+            .get_envs()
+            .find_map(|(key, value)| (key == "NEXTEST_CANCEL_ACK_PATH").then_some(value))
+            .flatten()
+            .expect("NEXTEST_CANCEL_ACK_PATH is set");
+        assert!(!cancel_ack.is_acknowledged(), "file not written yet");
+
+        std::fs::write(path, "something else").unwrap();
+        assert!(!cancel_ack.is_acknowledged(), "wrong message");
+
+        std::fs::write(path, format!("{CANCEL_ACK_MESSAGE}\n")).unwrap();
+        assert!(cancel_ack.is_acknowledged(), "message written");
+    }
+
+    #[test]
+    fn suppress_env() {
+        let mut cmd = std::process::Command::new("test-binary");
+        cmd.env("NEXTEST", "1")
+            .env("NEXTEST_EXECUTION_MODE", "process-per-test")
+            .env("NEXTEST_LD_LIBRARY_PATH", "/lib")
+            .env("NEXTEST_DYLD_FALLBACK_LIBRARY_PATH", "/lib");
+
+        let patterns = [
+            EnvVarPattern::new("NEXTEST_LD_*").unwrap(),
+            EnvVarPattern::new("NEXTEST_EXECUTION_MODE").unwrap(),
+        ];
+        apply_suppress_env(&mut cmd, &patterns);
+
+        let envs: BTreeSet<_> = cmd
+            .get_envs()
+            .filter_map(|(k, v)| Some((k.to_str()?, v.is_some())))
+            // Ignore variables inherited from the environment the test is running in.
+            .filter(|(k, _)| {
+                [
+                    "NEXTEST",
+                    "NEXTEST_EXECUTION_MODE",
+                    "NEXTEST_LD_LIBRARY_PATH",
+                    "NEXTEST_DYLD_FALLBACK_LIBRARY_PATH",
+                ]
+                .contains(k)
+            })
+            .collect();
+        assert_eq!(
+            envs,
+            [
+                ("NEXTEST", true),
+                ("NEXTEST_DYLD_FALLBACK_LIBRARY_PATH", true),
+                // Removed variables are reported with a value of None.
+                ("NEXTEST_EXECUTION_MODE", false),
+                ("NEXTEST_LD_LIBRARY_PATH", false),
+            ]
+            .into_iter()
+            .collect(),
+        );
+    }
 }
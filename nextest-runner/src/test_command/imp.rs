@@ -287,6 +287,17 @@ impl ChildOutputMut {
         }
     }
 
+    /// Returns true if no output has been collected yet.
+    pub(crate) fn is_empty(&self) -> bool {
+        match self {
+            Self::Split { stdout, stderr } => {
+                stdout.as_ref().map_or(true, |x| x.is_empty())
+                    && stderr.as_ref().map_or(true, |x| x.is_empty())
+            }
+            Self::Combined(combined) => combined.is_empty(),
+        }
+    }
+
     /// Makes a snapshot of the current output, returning a [`TestOutput`].
     ///
     /// This requires cloning the output so it's more expensive than [`Self::freeze`].
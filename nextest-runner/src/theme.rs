@@ -0,0 +1,188 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Color themes for nextest's output.
+//!
+//! A [`Theme`] maps the semantic styles nextest uses, such as the one for
+//! passing tests, to colors. It's set in the `[theme]` section of the
+//! [user config](crate::user_config::UserConfig), and only takes effect if
+//! output is colorized in the first place.
+
+use owo_colors::{AnsiColors, Style};
+use serde::Deserialize;
+
+/// A color theme, set in the `[theme]` section of the user config.
+///
+/// Colors that aren't set use nextest's defaults.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Theme {
+    /// The color for passing tests and successful operations.
+    #[serde(default)]
+    pub pass: Option<ThemeColor>,
+
+    /// The color for failing tests and errors.
+    #[serde(default)]
+    pub fail: Option<ThemeColor>,
+
+    /// The color for skipped, slow, flaky and leaky tests, and for warnings.
+    #[serde(default)]
+    pub skip: Option<ThemeColor>,
+
+    /// The color for retried tests.
+    #[serde(default)]
+    pub retry: Option<ThemeColor>,
+
+    /// Whether to use bold text. Defaults to true.
+    #[serde(default)]
+    pub bold: Option<bool>,
+}
+
+impl Theme {
+    /// Returns the style for passing tests and successful operations.
+    pub fn pass(&self) -> Style {
+        self.color_style(self.pass, ThemeColor::Green)
+    }
+
+    /// Returns the style for failing tests and errors.
+    pub fn fail(&self) -> Style {
+        self.color_style(self.fail, ThemeColor::Red)
+    }
+
+    /// Returns the style for skipped, slow, flaky and leaky tests, and for
+    /// warnings.
+    pub fn skip(&self) -> Style {
+        self.color_style(self.skip, ThemeColor::Yellow)
+    }
+
+    /// Returns the style for retried tests.
+    pub fn retry(&self) -> Style {
+        self.color_style(self.retry, ThemeColor::Magenta)
+    }
+
+    /// Returns the style for text that's emphasized without a color, such as
+    /// counts.
+    pub fn emphasis(&self) -> Style {
+        self.embolden(Style::new())
+    }
+
+    /// Returns `style` made bold, unless bold text is disabled.
+    ///
+    /// This is used for styles that don't have a configurable color.
+    pub fn embolden(&self, style: Style) -> Style {
+        if self.bold.unwrap_or(true) {
+            style.bold()
+        } else {
+            style
+        }
+    }
+
+    fn color_style(&self, color: Option<ThemeColor>, default: ThemeColor) -> Style {
+        let style = match color.unwrap_or(default).to_ansi() {
+            Some(color) => Style::new().color(color),
+            None => Style::new(),
+        };
+        self.embolden(style)
+    }
+}
+
+/// A color in a [`Theme`].
+///
+/// These are the 16 ANSI colors, whose exact appearance is determined by the
+/// terminal, plus `default` for the terminal's default text color.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum ThemeColor {
+    /// The terminal's default text color.
+    Default,
+    /// Black.
+    Black,
+    /// Red.
+    Red,
+    /// Green.
+    Green,
+    /// Yellow.
+    Yellow,
+    /// Blue.
+    Blue,
+    /// Magenta.
+    Magenta,
+    /// Cyan.
+    Cyan,
+    /// White.
+    White,
+    /// Bright black, often displayed as gray.
+    BrightBlack,
+    /// Bright red.
+    BrightRed,
+    /// Bright green.
+    BrightGreen,
+    /// Bright yellow.
+    BrightYellow,
+    /// Bright blue.
+    BrightBlue,
+    /// Bright magenta.
+    BrightMagenta,
+    /// Bright cyan.
+    BrightCyan,
+    /// Bright white.
+    BrightWhite,
+}
+
+impl ThemeColor {
+    fn to_ansi(self) -> Option<AnsiColors> {
+        let color = match self {
+            Self::Default => return None,
+            Self::Black => AnsiColors::Black,
+            Self::Red => AnsiColors::Red,
+            Self::Green => AnsiColors::Green,
+            Self::Yellow => AnsiColors::Yellow,
+            Self::Blue => AnsiColors::Blue,
+            Self::Magenta => AnsiColors::Magenta,
+            Self::Cyan => AnsiColors::Cyan,
+            Self::White => AnsiColors::White,
+            Self::BrightBlack => AnsiColors::BrightBlack,
+            Self::BrightRed => AnsiColors::BrightRed,
+            Self::BrightGreen => AnsiColors::BrightGreen,
+            Self::BrightYellow => AnsiColors::BrightYellow,
+            Self::BrightBlue => AnsiColors::BrightBlue,
+            Self::BrightMagenta => AnsiColors::BrightMagenta,
+            Self::BrightCyan => AnsiColors::BrightCyan,
+            Self::BrightWhite => AnsiColors::BrightWhite,
+        };
+        Some(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use owo_colors::OwoColorize;
+
+    #[test]
+    fn theme_styles() {
+        let theme = Theme::default();
+        assert_eq!(
+            "x".style(theme.pass()).to_string(),
+            "x".style(Style::new().green().bold()).to_string(),
+        );
+
+        let theme: Theme = toml::from_str(
+            r#"
+            fail = "bright-red"
+            skip = "default"
+            bold = false
+            "#,
+        )
+        .expect("theme is valid");
+        assert_eq!(
+            "x".style(theme.fail()).to_string(),
+            "x".style(Style::new().bright_red()).to_string(),
+        );
+        assert_eq!("x".style(theme.skip()).to_string(), "x");
+        assert_eq!("x".style(theme.emphasis()).to_string(), "x");
+
+        toml::from_str::<Theme>(r#"pass = "chartreuse""#).expect_err("unknown color");
+    }
+}
@@ -5,6 +5,8 @@
 
 mod pausable_sleep;
 mod stopwatch;
+mod suspend;
 
 pub(crate) use pausable_sleep::*;
 pub(crate) use stopwatch::*;
+pub(crate) use suspend::*;
@@ -1,6 +1,7 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::SuspendMark;
 use pin_project_lite::pin_project;
 use std::{future::Future, pin::Pin, task::Poll, time::Duration};
 use tokio::time::{Instant, Sleep};
@@ -11,12 +12,16 @@ pub(crate) fn pausable_sleep(duration: Duration) -> PausableSleep {
 
 pin_project! {
     /// A wrapper around `tokio::time::Sleep` that can also be paused, resumed and reset.
+    ///
+    /// Time spent with the system suspended doesn't count towards the sleep: if the system was
+    /// suspended while the sleep was running, its deadline is pushed back accordingly.
     #[derive(Debug)]
     pub(crate) struct PausableSleep {
         #[pin]
         sleep: Sleep,
         duration: Duration,
         pause_state: SleepPauseState,
+        suspend_mark: SuspendMark,
     }
 }
 
@@ -26,6 +31,7 @@ impl PausableSleep {
             sleep: tokio::time::sleep(duration),
             duration,
             pause_state: SleepPauseState::Running,
+            suspend_mark: SuspendMark::now(),
         }
     }
 
@@ -43,7 +49,9 @@ impl PausableSleep {
                 this.sleep.reset(far_future());
                 // This will return 0 if the deadline has passed. That's fine because we'll just
                 // reset the timer back to 0 in resume, which will behave correctly.
-                let remaining = deadline.duration_since(Instant::now());
+                //
+                // Time spent suspended while the sleep was running is added back.
+                let remaining = deadline.duration_since(Instant::now()) + this.suspend_mark.take();
                 *this.pause_state = SleepPauseState::Paused { remaining };
             }
             SleepPauseState::Paused { remaining } => {
@@ -58,6 +66,8 @@ impl PausableSleep {
             SleepPauseState::Paused { remaining } => {
                 this.sleep.reset(Instant::now() + *remaining);
                 *this.pause_state = SleepPauseState::Running;
+                // Don't count time spent suspended while paused.
+                *this.suspend_mark = SuspendMark::now();
             }
             SleepPauseState::Running => {
                 panic!("illegal state transition: resume() called while sleep was running");
@@ -79,6 +89,7 @@ impl PausableSleep {
         match this.pause_state {
             SleepPauseState::Running => {
                 this.sleep.reset(Instant::now() + duration);
+                *this.suspend_mark = SuspendMark::now();
             }
             SleepPauseState::Paused { remaining } => {
                 *remaining = duration;
@@ -104,7 +115,7 @@ impl Future for PausableSleep {
     type Output = ();
 
     fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
-        let this = self.project();
+        let mut this = self.project();
         // Always call into this.sleep.
         //
         // We don't do anything special for paused sleeps here. That's because
@@ -112,7 +123,23 @@ impl Future for PausableSleep {
         // will mean that the future gets registered with the time driver (so is
         // not going to be stuck without a waker, even though the waker will
         // never end up waking the task in practice).
-        this.sleep.poll(cx)
+        loop {
+            match this.sleep.as_mut().poll(cx) {
+                Poll::Ready(()) => {
+                    // If the system was suspended while the sleep was running,
+                    // the deadline may have been hit because of that. Push the
+                    // deadline back by the time spent suspended, and poll
+                    // again so the sleep is registered with the time driver.
+                    let suspended = this.suspend_mark.take();
+                    if suspended.is_zero() {
+                        return Poll::Ready(());
+                    }
+                    let deadline = this.sleep.deadline() + suspended;
+                    this.sleep.as_mut().reset(deadline);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
     }
 }
 
@@ -6,7 +6,10 @@
 //! Tests need to track a start time and a duration. For that we use a combination of a `SystemTime`
 //! (realtime clock) and an `Instant` (monotonic clock). Once the stopwatch transitions to the "end"
 //! state, we can report the elapsed time using the monotonic clock.
+//!
+//! Time spent paused (via job control) or with the system suspended is not counted as active time.
 
+use super::suspended_time;
 use chrono::{DateTime, Local};
 use std::time::{Duration, Instant};
 
@@ -20,6 +23,8 @@ pub(crate) struct StopwatchStart {
     start_time: DateTime<Local>,
     instant: Instant,
     paused_time: Duration,
+    // The value of `suspended_time()` when the stopwatch was started.
+    suspended_at_start: Duration,
     pause_state: StopwatchPauseState,
 }
 
@@ -31,6 +36,7 @@ impl StopwatchStart {
             start_time: Local::now(),
             instant: Instant::now(),
             paused_time: Duration::ZERO,
+            suspended_at_start: suspended_time(),
             pause_state: StopwatchPauseState::Running,
         }
     }
@@ -44,6 +50,7 @@ impl StopwatchStart {
             StopwatchPauseState::Running => {
                 self.pause_state = StopwatchPauseState::Paused {
                     paused_at: Instant::now(),
+                    suspended_at: suspended_time(),
                 };
             }
             StopwatchPauseState::Paused { .. } => {
@@ -54,8 +61,14 @@ impl StopwatchStart {
 
     pub(crate) fn resume(&mut self) {
         match &self.pause_state {
-            StopwatchPauseState::Paused { paused_at } => {
-                self.paused_time += paused_at.elapsed();
+            StopwatchPauseState::Paused {
+                paused_at,
+                suspended_at,
+            } => {
+                // Time spent suspended while paused is subtracted out in snapshot(), so don't
+                // count it twice.
+                let suspended = suspended_time().saturating_sub(*suspended_at);
+                self.paused_time += paused_at.elapsed().saturating_sub(suspended);
                 self.pause_state = StopwatchPauseState::Running;
             }
             StopwatchPauseState::Running => {
@@ -65,11 +78,16 @@ impl StopwatchStart {
     }
 
     pub(crate) fn snapshot(&self) -> StopwatchSnapshot {
+        let suspended = suspended_time().saturating_sub(self.suspended_at_start);
         StopwatchSnapshot {
             start_time: self.start_time,
             // self.instant is supposed to be monotonic but might not be so on
             // some weird systems. If the duration underflows, just return 0.
-            active: self.instant.elapsed().saturating_sub(self.paused_time),
+            active: self
+                .instant
+                .elapsed()
+                .saturating_sub(self.paused_time)
+                .saturating_sub(suspended),
             paused: self.paused_time,
         }
     }
@@ -92,7 +110,10 @@ pub(crate) struct StopwatchSnapshot {
 #[derive(Clone, Debug)]
 enum StopwatchPauseState {
     Running,
-    Paused { paused_at: Instant },
+    Paused {
+        paused_at: Instant,
+        suspended_at: Duration,
+    },
 }
 
 #[cfg(test)]
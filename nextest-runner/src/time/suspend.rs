@@ -0,0 +1,108 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tracking of time spent with the system suspended.
+//!
+//! If a machine is suspended in the middle of a test run (for example, a laptop's lid is closed),
+//! the time spent suspended should not count towards test durations and timeouts. Otherwise, every
+//! running test would be marked slow or timed out as soon as the machine wakes up.
+//!
+//! On Unix platforms, `Instant` is backed by a clock that doesn't advance while the system is
+//! suspended (`CLOCK_MONOTONIC` on Linux and `CLOCK_UPTIME_RAW` on macOS), so no correction is
+//! required. On Windows, `Instant` is backed by `QueryPerformanceCounter`, which does advance while
+//! the system is suspended, so the time spent suspended is measured and subtracted out.
+
+use std::time::Duration;
+
+/// Returns the total amount of time the system has spent suspended that is counted by `Instant`.
+///
+/// This is only meaningful as a difference between two calls. It is always zero on platforms where
+/// `Instant` doesn't advance while the system is suspended.
+pub(crate) fn suspended_time() -> Duration {
+    imp::suspended_time()
+}
+
+/// Tracks how much suspended time has elapsed since a point in time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct SuspendMark {
+    at: Duration,
+}
+
+impl SuspendMark {
+    /// Creates a new mark at the current point in time.
+    pub(crate) fn now() -> Self {
+        Self::at(suspended_time())
+    }
+
+    fn at(at: Duration) -> Self {
+        Self { at }
+    }
+
+    /// Returns the time spent suspended since this mark was last updated, and moves the mark
+    /// forward to the current point in time.
+    pub(crate) fn take(&mut self) -> Duration {
+        self.take_at(suspended_time())
+    }
+
+    fn take_at(&mut self, now: Duration) -> Duration {
+        // Clock readings may be slightly noisy. Never let the mark move backwards.
+        let elapsed = now.saturating_sub(self.at);
+        self.at = self.at.max(now);
+        elapsed
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::time::Duration;
+    use windows_sys::Win32::System::{
+        SystemInformation::GetTickCount64, WindowsProgramming::QueryUnbiasedInterruptTime,
+    };
+
+    pub(super) fn suspended_time() -> Duration {
+        // GetTickCount64 includes time spent suspended, while the unbiased interrupt time (in
+        // units of 100ns) does not. The difference between the two is the total time spent
+        // suspended since boot, with millisecond precision.
+        let mut unbiased = 0u64;
+        // SAFETY: the pointer is valid for writes.
+        if unsafe { QueryUnbiasedInterruptTime(&mut unbiased) } == 0 {
+            return Duration::ZERO;
+        }
+        // SAFETY: GetTickCount64 has no preconditions.
+        let ticks = unsafe { GetTickCount64() };
+        Duration::from_millis(ticks.saturating_sub(unbiased / 10_000))
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use std::time::Duration;
+
+    #[inline]
+    pub(super) fn suspended_time() -> Duration {
+        Duration::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suspend_mark_take() {
+        let mut mark = SuspendMark::at(Duration::from_secs(10));
+        assert_eq!(mark.take_at(Duration::from_secs(10)), Duration::ZERO);
+        assert_eq!(
+            mark.take_at(Duration::from_secs(75)),
+            Duration::from_secs(65)
+        );
+        // The mark has moved forward, so the same suspension isn't counted twice.
+        assert_eq!(mark.take_at(Duration::from_secs(75)), Duration::ZERO);
+        // Noise in the readings doesn't move the mark backwards.
+        assert_eq!(mark.take_at(Duration::from_millis(74_999)), Duration::ZERO);
+        assert_eq!(
+            mark.take_at(Duration::from_secs(76)),
+            Duration::from_secs(1)
+        );
+    }
+}
@@ -3,18 +3,23 @@
 
 //! Self-updates for nextest.
 
-use crate::errors::{UpdateError, UpdateVersionParseError};
+use crate::{
+    errors::{UpdateError, UpdateVersionParseError},
+    user_config::HttpConfig,
+};
 use camino::{Utf8Path, Utf8PathBuf};
+use indicatif::{ProgressBar, ProgressStyle};
 use mukti_metadata::{
     DigestAlgorithm, MuktiProject, MuktiReleasesJson, ReleaseLocation, ReleaseStatus,
 };
-use self_update::{ArchiveKind, Compression, Download, Extract};
-use semver::{Version, VersionReq};
+use reqwest::blocking::Client;
+use self_update::{ArchiveKind, Compression, Extract};
+use semver::{Prerelease, Version, VersionReq};
 use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use std::{
-    fs,
-    io::{self, BufWriter},
+    fmt, fs,
+    io::{self, BufWriter, Write},
     str::FromStr,
 };
 use target_spec::Platform;
@@ -28,12 +33,25 @@ pub struct MuktiBackend {
 
     /// The package name.
     pub package_name: String,
+
+    /// A mirror to download release archives from, instead of the locations in the release
+    /// metadata.
+    ///
+    /// This is either a base URL or a local directory. Archives are fetched from
+    /// `<mirror>/<file name>`, where the file name is the last path component of the location in
+    /// the release metadata. Checksums from the release metadata are still verified.
+    pub mirror: Option<String>,
+
+    /// Configuration for the HTTP client used to fetch releases.
+    pub http: HttpConfig,
 }
 
 impl MuktiBackend {
     /// Fetch releases.
     pub fn fetch_releases(&self, current_version: Version) -> Result<NextestReleases, UpdateError> {
         info!(target: "nextest-runner::update", "checking for self-updates");
+        let client = self.http.client().map_err(UpdateError::HttpClient)?;
+
         // Is the URL a file that exists on disk? If so, use that.
         let as_path = Utf8Path::new(&self.url);
         let releases_buf = if as_path.exists() {
@@ -43,9 +61,7 @@ impl MuktiBackend {
             })?
         } else {
             let mut releases_buf: Vec<u8> = Vec::new();
-            Download::from_url(&self.url)
-                .download_to(&mut releases_buf)
-                .map_err(UpdateError::SelfUpdate)?;
+            download(&client, &self.url, None, &mut releases_buf)?;
             releases_buf
         };
 
@@ -62,7 +78,13 @@ impl MuktiBackend {
             }
         };
 
-        NextestReleases::new(&self.package_name, project, current_version)
+        NextestReleases::new(
+            &self.package_name,
+            project,
+            current_version,
+            self.mirror.clone(),
+            client,
+        )
     }
 }
 
@@ -83,6 +105,11 @@ pub struct NextestReleases {
 
     /// The install path.
     pub bin_install_path: Utf8PathBuf,
+
+    /// The mirror to download archives from, if any. See [`MuktiBackend::mirror`].
+    pub mirror: Option<String>,
+
+    client: Client,
 }
 
 impl NextestReleases {
@@ -90,19 +117,16 @@ impl NextestReleases {
         package_name: &str,
         project: MuktiProject,
         current_version: Version,
+        mirror: Option<String>,
+        client: Client,
     ) -> Result<Self, UpdateError> {
-        let bin_install_path = std::env::current_exe()
-            .and_then(|exe| {
-                Utf8PathBuf::try_from(exe)
-                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
-            })
-            .map_err(UpdateError::CurrentExe)?;
-
         Ok(Self {
             package_name: package_name.to_owned(),
             project,
             current_version,
-            bin_install_path,
+            bin_install_path: current_exe()?,
+            mirror,
+            client,
         })
     }
 
@@ -110,11 +134,12 @@ impl NextestReleases {
     pub fn check<'a>(
         &'a self,
         version: &UpdateVersion,
+        channel: UpdateChannel,
         force: bool,
         bin_path_in_archive: &'a Utf8Path,
         perform_setup_fn: impl FnOnce(&Version) -> bool,
     ) -> Result<CheckStatus<'a>, UpdateError> {
-        let (version, version_data) = self.get_version_data(version)?;
+        let (version, version_data) = self.get_version_data(version, channel)?;
         debug!(
             target: "nextest-runner::update",
             "current version is {}, update version is {version}",
@@ -153,6 +178,12 @@ impl NextestReleases {
                 }
             })?;
 
+        let mut location = location.clone();
+        if let Some(mirror) = &self.mirror {
+            location.url = mirror_url(mirror, &location.url);
+            debug!(target: "nextest-runner::update", "using mirror location: {}", location.url);
+        }
+
         let force_disable_setup = version_data
             .metadata
             .is_some_and(|metadata| metadata.force_disable_setup);
@@ -161,7 +192,7 @@ impl NextestReleases {
         Ok(CheckStatus::Success(MuktiUpdateContext {
             context: self,
             version: version.clone(),
-            location: location.clone(),
+            location,
             bin_path_in_archive,
             perform_setup,
         }))
@@ -174,6 +205,7 @@ impl NextestReleases {
     fn get_version_data(
         &self,
         version: &UpdateVersion,
+        channel: UpdateChannel,
     ) -> Result<(&Version, ReleaseVersionData), UpdateError> {
         let (version, release_data) = match version {
             UpdateVersion::Exact(version) => {
@@ -189,10 +221,17 @@ impl NextestReleases {
                     }
                 })?
             }
-            UpdateVersion::Req(req) => self
-                .project
-                .get_latest_matching(req)
-                .ok_or_else(|| UpdateError::NoMatchForVersionReq { req: req.clone() })?,
+            UpdateVersion::Req(req) => match channel {
+                UpdateChannel::Stable => self.project.get_latest_matching(req),
+                UpdateChannel::Beta => self
+                    .project
+                    .all_versions()
+                    .filter(|(v, release_data)| {
+                        release_data.status == ReleaseStatus::Active && channel.matches(req, v)
+                    })
+                    .max_by(|(a, _), (b, _)| a.cmp(b)),
+            }
+            .ok_or_else(|| UpdateError::NoMatchForVersionReq { req: req.clone() })?,
         };
 
         // Parse the metadata into our custom format.
@@ -300,18 +339,120 @@ pub struct MuktiUpdateContext<'a> {
 impl MuktiUpdateContext<'_> {
     /// Performs the update.
     pub fn do_update(&self) -> Result<(), UpdateError> {
+        Installer {
+            package_name: &self.context.package_name,
+            bin_install_path: &self.context.bin_install_path,
+            source: ArchiveSource::from_location(&self.location.url, &self.context.client),
+            expected_sha256: self
+                .location
+                .checksums
+                .get(&DigestAlgorithm::SHA256)
+                .map(|checksum| checksum.0.as_str()),
+            bin_path_in_archive: self.bin_path_in_archive,
+            perform_setup: self.perform_setup,
+        }
+        .install()
+    }
+}
+
+/// An update from a local archive, for environments without access to the release metadata.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct LocalArchiveUpdate {
+    /// The package name.
+    pub package_name: String,
+
+    /// The path to the `.tar.gz` archive.
+    pub archive_path: Utf8PathBuf,
+
+    /// The expected SHA-256 checksum of the archive, as a hex string.
+    ///
+    /// If this is `None`, the checksum is not verified and a warning is printed.
+    pub sha256: Option<String>,
+
+    /// The path to the binary within the archive.
+    pub bin_path_in_archive: Utf8PathBuf,
+
+    /// The install path.
+    pub bin_install_path: Utf8PathBuf,
+}
+
+impl LocalArchiveUpdate {
+    /// Creates a new local archive update, replacing the currently running executable.
+    pub fn new(
+        package_name: &str,
+        archive_path: Utf8PathBuf,
+        sha256: Option<String>,
+        bin_path_in_archive: Utf8PathBuf,
+    ) -> Result<Self, UpdateError> {
+        Ok(Self {
+            package_name: package_name.to_owned(),
+            archive_path,
+            sha256,
+            bin_path_in_archive,
+            bin_install_path: current_exe()?,
+        })
+    }
+
+    /// Performs the update.
+    pub fn do_update(&self) -> Result<(), UpdateError> {
+        Installer {
+            package_name: &self.package_name,
+            bin_install_path: &self.bin_install_path,
+            source: ArchiveSource::Path(&self.archive_path),
+            expected_sha256: self.sha256.as_deref(),
+            bin_path_in_archive: &self.bin_path_in_archive,
+            // The version in the archive isn't known, and it may predate `self setup`.
+            perform_setup: false,
+        }
+        .install()
+    }
+}
+
+/// Downloads or copies an archive, verifies it, and replaces the current binary with the one in
+/// it.
+struct Installer<'a> {
+    package_name: &'a str,
+    bin_install_path: &'a Utf8Path,
+    source: ArchiveSource<'a>,
+    expected_sha256: Option<&'a str>,
+    bin_path_in_archive: &'a Utf8Path,
+    perform_setup: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ArchiveSource<'a> {
+    Url(&'a str, &'a Client),
+    Path(&'a Utf8Path),
+}
+
+impl<'a> ArchiveSource<'a> {
+    /// Returns the source for a location in release metadata, which may be a local path if a
+    /// mirror directory is in use.
+    fn from_location(location: &'a str, client: &'a Client) -> Self {
+        let as_path = Utf8Path::new(location);
+        if as_path.is_file() {
+            Self::Path(as_path)
+        } else {
+            Self::Url(location, client)
+        }
+    }
+}
+
+impl Installer<'_> {
+    fn install(&self) -> Result<(), UpdateError> {
         // This method is adapted from self_update's update_extended.
 
-        let tmp_dir_parent = self.context.bin_install_path.parent().ok_or_else(|| {
+        let tmp_dir_parent = self.bin_install_path.parent().ok_or_else(|| {
             UpdateError::CurrentExe(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!(
                     "parent directory of current exe `{}` could not be determined",
-                    self.context.bin_install_path
+                    self.bin_install_path
                 ),
             ))
         })?;
-        let tmp_backup_dir_prefix = format!("__{}_backup", self.context.package_name);
+        let tmp_backup_dir_prefix = format!("__{}_backup", self.package_name);
         #[expect(clippy::redundant_clone)]
         let tmp_backup_filename = tmp_backup_dir_prefix.clone();
 
@@ -327,7 +468,7 @@ impl MuktiUpdateContext<'_> {
             );
         }
 
-        let tmp_archive_dir_prefix = format!("{}_download", self.context.package_name);
+        let tmp_archive_dir_prefix = format!("{}_download", self.package_name);
         let tmp_archive_dir = camino_tempfile::Builder::new()
             .prefix(&tmp_archive_dir_prefix)
             .tempdir_in(tmp_dir_parent)
@@ -336,8 +477,7 @@ impl MuktiUpdateContext<'_> {
                 error,
             })?;
         let tmp_dir_path: &Utf8Path = tmp_archive_dir.path();
-        let tmp_archive_path =
-            tmp_dir_path.join(format!("{}.{TAR_GZ_SUFFIX}", self.context.package_name));
+        let tmp_archive_path = tmp_dir_path.join(format!("{}.{TAR_GZ_SUFFIX}", self.package_name));
         let tmp_archive = fs::File::create(&tmp_archive_path).map_err(|error| {
             UpdateError::TempArchiveCreate {
                 archive_path: tmp_archive_path.clone(),
@@ -346,21 +486,32 @@ impl MuktiUpdateContext<'_> {
         })?;
         let mut tmp_archive_buf = BufWriter::new(tmp_archive);
 
-        let mut download = Download::from_url(&self.location.url);
-        let mut headers = http::header::HeaderMap::new();
-        headers.insert(
-            http::header::ACCEPT,
-            "application/octet-stream".parse().unwrap(),
-        );
-        download.set_headers(headers);
-        download.show_progress(true);
-        // TODO: set progress style
-
-        download
-            .download_to(&mut tmp_archive_buf)
-            .map_err(UpdateError::SelfUpdate)?;
-
-        debug!(target: "nextest-runner::update", "downloaded to {tmp_archive_path}");
+        match self.source {
+            ArchiveSource::Path(path) => {
+                let mut source =
+                    fs::File::open(path).map_err(|error| UpdateError::ReadLocalArchive {
+                        path: path.to_owned(),
+                        error,
+                    })?;
+                io::copy(&mut source, &mut tmp_archive_buf).map_err(|error| {
+                    UpdateError::TempArchiveWrite {
+                        archive_path: tmp_archive_path.clone(),
+                        error,
+                    }
+                })?;
+                debug!(target: "nextest-runner::update", "copied {path} to {tmp_archive_path}");
+            }
+            ArchiveSource::Url(url, client) => {
+                download(
+                    client,
+                    url,
+                    Some("application/octet-stream"),
+                    &mut tmp_archive_buf,
+                )?;
+
+                debug!(target: "nextest-runner::update", "downloaded to {tmp_archive_path}");
+            }
+        }
 
         let tmp_archive =
             tmp_archive_buf
@@ -394,18 +545,18 @@ impl MuktiUpdateContext<'_> {
         let hash = hasher.finalize();
         let hash_str = hex::encode(hash);
 
-        match self.location.checksums.get(&DigestAlgorithm::SHA256) {
-            Some(checksum) => {
-                if checksum.0 != hash_str {
+        match self.expected_sha256 {
+            Some(expected) => {
+                if !expected.eq_ignore_ascii_case(&hash_str) {
                     return Err(UpdateError::ChecksumMismatch {
-                        expected: checksum.0.clone(),
+                        expected: expected.to_owned(),
                         actual: hash_str,
                     });
                 }
                 debug!(target: "nextest-runner::update", "SHA-256 checksum verified: {hash_str}");
             }
             None => {
-                warn!(target: "nextest-runner::update", "unable to verify SHA-256 checksum of downloaded archive ({hash_str})");
+                warn!(target: "nextest-runner::update", "unable to verify SHA-256 checksum of archive ({hash_str})");
             }
         }
 
@@ -437,12 +588,12 @@ impl MuktiUpdateContext<'_> {
 
         Move::from_source(&new_exe)
             .replace_using_temp(&tmp_file_path)
-            .to_dest(&self.context.bin_install_path)?;
+            .to_dest(self.bin_install_path)?;
 
         // Finally, run `cargo nextest self setup` if requested.
         if self.perform_setup {
             info!(target: "nextest-runner::update", "running `cargo nextest self setup`");
-            let mut cmd = std::process::Command::new(&self.context.bin_install_path);
+            let mut cmd = std::process::Command::new(self.bin_install_path);
             cmd.args(["nextest", "self", "setup", "--source", "self-update"]);
             let status = cmd.status().map_err(UpdateError::SelfSetup)?;
             if !status.success() {
@@ -569,6 +720,107 @@ fn cleanup_backup_temp_directories(
 
 const TAR_GZ_SUFFIX: &str = "tar.gz";
 
+/// Downloads `url` to `writer`.
+///
+/// If `accept` is specified, it's sent as the `Accept` header and a progress bar is shown.
+fn download(
+    client: &Client,
+    url: &str,
+    accept: Option<&str>,
+    writer: &mut impl Write,
+) -> Result<(), UpdateError> {
+    let mut request = client.get(url);
+    if let Some(accept) = accept {
+        request = request.header(http::header::ACCEPT, accept);
+    }
+    let mut response = request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|error| UpdateError::Download {
+            url: url.to_owned(),
+            error,
+        })?;
+
+    let result = if accept.is_some() {
+        let progress_bar = ProgressBar::new(response.content_length().unwrap_or(0)).with_style(
+            ProgressStyle::with_template(
+                "[{elapsed_precise}] [{bar:40}] {bytes}/{total_bytes} ({eta})",
+            )
+            .expect("progress bar template is valid")
+            .progress_chars("=> "),
+        );
+        let result = io::copy(&mut response, &mut progress_bar.wrap_write(writer));
+        progress_bar.finish_and_clear();
+        result
+    } else {
+        io::copy(&mut response, writer)
+    };
+    result.map_err(|error| UpdateError::DownloadRead {
+        url: url.to_owned(),
+        error,
+    })?;
+
+    Ok(())
+}
+
+fn current_exe() -> Result<Utf8PathBuf, UpdateError> {
+    std::env::current_exe()
+        .and_then(|exe| {
+            Utf8PathBuf::try_from(exe)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .map_err(UpdateError::CurrentExe)
+}
+
+/// Returns the location of an archive within a mirror.
+fn mirror_url(mirror: &str, url: &str) -> String {
+    let file_name = url.rsplit('/').next().unwrap_or(url);
+    let mirror_path = Utf8Path::new(mirror);
+    if mirror_path.is_dir() {
+        mirror_path.join(file_name).into_string()
+    } else {
+        format!("{}/{file_name}", mirror.trim_end_matches('/'))
+    }
+}
+
+/// The release channel to update from.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum UpdateChannel {
+    /// Only update to stable releases.
+    #[default]
+    Stable,
+
+    /// Also update to pre-releases, such as `0.9.90-b.1`.
+    Beta,
+}
+
+impl UpdateChannel {
+    /// Returns true if `version` is on this channel and matches `req`.
+    ///
+    /// [`VersionReq::matches`] only matches pre-releases if the requirement names one for the
+    /// same version. For the beta channel, pre-releases are matched as if they were the version
+    /// they precede, so that `^0.9.89` matches `0.9.90-b.1`.
+    pub fn matches(self, req: &VersionReq, version: &Version) -> bool {
+        match self {
+            Self::Stable => version.pre.is_empty() && req.matches(version),
+            Self::Beta => {
+                let mut release = version.clone();
+                release.pre = Prerelease::EMPTY;
+                req.matches(&release)
+            }
+        }
+    }
+}
+
+impl fmt::Display for UpdateChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stable => write!(f, "stable"),
+            Self::Beta => write!(f, "beta"),
+        }
+    }
+}
+
 /// Represents the version this project is being updated to.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum UpdateVersion {
@@ -620,3 +872,38 @@ impl FromStr for UpdateVersion {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(UpdateChannel::Stable, "*", "0.9.89", true ; "stable release")]
+    #[test_case(UpdateChannel::Stable, "*", "0.9.90-b.1", false ; "stable skips pre-release")]
+    #[test_case(UpdateChannel::Beta, "*", "0.9.90-b.1", true ; "beta pre-release")]
+    #[test_case(UpdateChannel::Beta, "*", "0.9.89", true ; "beta release")]
+    #[test_case(UpdateChannel::Beta, "~0.9.85", "0.9.90-b.1", true ; "beta pre-release in range")]
+    #[test_case(UpdateChannel::Beta, "~0.9.85", "0.10.0-b.1", false ; "beta pre-release out of range")]
+    fn test_channel_matches(channel: UpdateChannel, req: &str, version: &str, expected: bool) {
+        let req: VersionReq = req.parse().unwrap();
+        let version: Version = version.parse().unwrap();
+        assert_eq!(channel.matches(&req, &version), expected);
+    }
+
+    #[test]
+    fn test_mirror_url() {
+        let url = "https://github.com/nextest-rs/nextest/releases/download/cargo-nextest-0.9.90/cargo-nextest-0.9.90-x86_64-unknown-linux-gnu.tar.gz";
+        assert_eq!(
+            mirror_url("https://mirror.example.com/nextest/", url),
+            "https://mirror.example.com/nextest/cargo-nextest-0.9.90-x86_64-unknown-linux-gnu.tar.gz",
+        );
+
+        let dir = camino_tempfile::tempdir().unwrap();
+        assert_eq!(
+            mirror_url(dir.path().as_str(), url),
+            dir.path()
+                .join("cargo-nextest-0.9.90-x86_64-unknown-linux-gnu.tar.gz")
+                .into_string(),
+        );
+    }
+}
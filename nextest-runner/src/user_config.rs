@@ -0,0 +1,389 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-user configuration for nextest.
+//!
+//! Unlike the repository configuration in `.config/nextest.toml`, user configuration applies to
+//! every invocation of nextest by a user, and contains settings specific to their environment,
+//! such as how to reach the network.
+
+use crate::{
+    config::ReporterConfig, errors::UserConfigError, reporter::DurationFormat, theme::Theme,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Per-user configuration for nextest.
+///
+/// This is read from `$XDG_CONFIG_HOME/nextest/config.toml`, defaulting to
+/// `~/.config/nextest/config.toml` on all platforms. The path can be overridden with the
+/// `NEXTEST_USER_CONFIG_FILE` environment variable.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct UserConfig {
+    #[serde(default)]
+    http: HttpConfig,
+    #[serde(default)]
+    reporter: ReporterConfig,
+    #[serde(default)]
+    theme: Theme,
+    #[serde(default)]
+    ui: UiConfig,
+}
+
+impl UserConfig {
+    /// The environment variable used to override the path to the user config file.
+    pub const PATH_ENV: &'static str = "NEXTEST_USER_CONFIG_FILE";
+
+    /// Reads the user config from its default location, or from the path in
+    /// [`Self::PATH_ENV`] if set.
+    ///
+    /// If the file doesn't exist, the default configuration is returned. (If the path is set via
+    /// the environment variable, the file must exist.)
+    pub fn from_default_location() -> Result<Self, UserConfigError> {
+        if let Some(path) = std::env::var_os(Self::PATH_ENV) {
+            let path = Utf8PathBuf::try_from(std::path::PathBuf::from(path))
+                .map_err(|error| UserConfigError::PathNotUtf8 { error })?;
+            return Self::from_path(&path);
+        }
+
+        match default_path() {
+            Some(path) if path.exists() => Self::from_path(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Reads the user config from the given path.
+    pub fn from_path(path: &Utf8Path) -> Result<Self, UserConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|error| UserConfigError::Read {
+            path: path.to_owned(),
+            error,
+        })?;
+        toml::from_str(&contents).map_err(|error| UserConfigError::Parse {
+            path: path.to_owned(),
+            error,
+        })
+    }
+
+    /// Returns the HTTP client configuration.
+    pub fn http(&self) -> &HttpConfig {
+        &self.http
+    }
+
+    /// Returns the reporter configuration.
+    ///
+    /// Settings here apply unless the profile in use sets them too.
+    pub fn reporter(&self) -> &ReporterConfig {
+        &self.reporter
+    }
+
+    /// Returns the color theme.
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Returns the terminal UI configuration.
+    pub fn ui(&self) -> &UiConfig {
+        &self.ui
+    }
+}
+
+fn default_path() -> Option<Utf8PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+        _ => home::home_dir()?.join(".config"),
+    };
+    let path = Utf8PathBuf::try_from(config_dir).ok()?;
+    Some(path.join("nextest").join("config.toml"))
+}
+
+/// Configuration for HTTP requests made by nextest, set in the `[http]` section of the user
+/// config.
+///
+/// This is shared by all nextest features that access the network, such as self-updates.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HttpConfig {
+    /// The proxy to use for all requests, e.g. `http://proxy.example.com:3128`.
+    ///
+    /// If unset, the `HTTPS_PROXY`, `HTTP_PROXY` and `ALL_PROXY` environment variables are used.
+    #[serde(default)]
+    pub proxy: Option<String>,
+
+    /// Hosts to not use a proxy for, as a comma-separated list in the same format as the
+    /// `NO_PROXY` environment variable. This also applies to proxies set through environment
+    /// variables.
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+
+    /// A PEM file with additional root certificates to trust.
+    #[serde(default)]
+    pub ca_bundle: Option<Utf8PathBuf>,
+
+    /// The timeout for each request.
+    #[serde(default, with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+
+    /// The timeout for connecting to a host.
+    #[serde(default, with = "humantime_serde")]
+    pub connect_timeout: Option<Duration>,
+}
+
+/// Terminal UI configuration, set in the `[ui]` section of the user config.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct UiConfig {
+    /// The characters to use for progress bars and separators.
+    ///
+    /// If unset, Unicode characters are used if the terminal supports them.
+    #[serde(default)]
+    pub symbols: Option<Symbols>,
+
+    /// How durations are displayed in the reporter output.
+    #[serde(default)]
+    pub duration_format: DurationFormat,
+
+    /// Whether to group the digits of test counts with thousands separators, e.g. `12,345 passed`,
+    /// in the reporter output.
+    #[serde(default)]
+    pub thousands_separator: bool,
+}
+
+/// The characters nextest uses for progress bars and separators.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Symbols {
+    /// Use Unicode box-drawing and block characters.
+    Unicode,
+
+    /// Only use ASCII characters, for terminals and log viewers that don't
+    /// display Unicode correctly.
+    Ascii,
+}
+
+#[cfg(feature = "self-update")]
+impl HttpConfig {
+    /// Builds a blocking HTTP client with this configuration.
+    pub fn client(&self) -> Result<reqwest::blocking::Client, crate::errors::HttpClientError> {
+        use crate::errors::HttpClientError;
+
+        let mut builder = reqwest::blocking::Client::builder()
+            .user_agent(concat!("cargo-nextest/", env!("CARGO_PKG_VERSION")));
+
+        let no_proxy = self
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest::NoProxy::from_string);
+        for (kind, proxy) in self.proxies(|name| std::env::var(name).ok()) {
+            let proxy = kind
+                .to_proxy(&proxy)
+                .map_err(|error| HttpClientError::InvalidProxy {
+                    proxy: proxy.clone(),
+                    error,
+                })?
+                .no_proxy(no_proxy.clone());
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path).map_err(|error| HttpClientError::CaBundleRead {
+                path: path.clone(),
+                error,
+            })?;
+            let certs = reqwest::Certificate::from_pem_bundle(&pem).map_err(|error| {
+                HttpClientError::CaBundleParse {
+                    path: path.clone(),
+                    error,
+                }
+            })?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        builder.build().map_err(HttpClientError::Build)
+    }
+
+    /// Returns the proxies to set up on the client, looking up environment variables with
+    /// `env_var`.
+    ///
+    /// If no proxies are returned, reqwest picks up proxies from the environment itself.
+    fn proxies(&self, env_var: impl Fn(&str) -> Option<String>) -> Vec<(ProxyKind, String)> {
+        if let Some(proxy) = &self.proxy {
+            return vec![(ProxyKind::All, proxy.clone())];
+        }
+        // reqwest only applies the NO_PROXY environment variable to proxies it picks up from the
+        // environment itself. So if no-proxy is set, set up proxies from the environment here, in
+        // the order reqwest checks them, so that it can be applied to them.
+        let has_no_proxy = self
+            .no_proxy
+            .as_deref()
+            .is_some_and(|no_proxy| !no_proxy.trim().is_empty());
+        if !has_no_proxy {
+            return Vec::new();
+        }
+        [
+            (ProxyKind::Https, ["HTTPS_PROXY", "https_proxy"]),
+            (ProxyKind::Http, ["HTTP_PROXY", "http_proxy"]),
+            (ProxyKind::All, ["ALL_PROXY", "all_proxy"]),
+        ]
+        .into_iter()
+        .filter_map(|(kind, names)| {
+            let proxy = names
+                .iter()
+                .find_map(|name| env_var(name).filter(|value| !value.is_empty()))?;
+            Some((kind, proxy))
+        })
+        .collect()
+    }
+}
+
+/// The requests a proxy is used for.
+#[cfg(feature = "self-update")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProxyKind {
+    All,
+    Http,
+    Https,
+}
+
+#[cfg(feature = "self-update")]
+impl ProxyKind {
+    fn to_proxy(self, url: &str) -> reqwest::Result<reqwest::Proxy> {
+        match self {
+            Self::All => reqwest::Proxy::all(url),
+            Self::Http => reqwest::Proxy::http(url),
+            Self::Https => reqwest::Proxy::https(url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FinalSummary;
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_user_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            indoc! {r#"
+                [http]
+                proxy = "http://proxy.example.com:3128"
+                no-proxy = "localhost,.internal.example.com"
+                ca-bundle = "/etc/ssl/corp-ca.pem"
+                timeout = "2m"
+                connect-timeout = "10s"
+
+                [reporter]
+                final-summary = { slowest = 3 }
+
+                [ui]
+                symbols = "ascii"
+                duration-format = "iso8601"
+                thousands-separator = true
+            "#},
+        )
+        .unwrap();
+
+        let config = UserConfig::from_path(&path).expect("user config is valid");
+        let http = config.http();
+        assert_eq!(http.proxy.as_deref(), Some("http://proxy.example.com:3128"));
+        assert_eq!(
+            http.no_proxy.as_deref(),
+            Some("localhost,.internal.example.com")
+        );
+        assert_eq!(
+            http.ca_bundle.as_deref(),
+            Some(Utf8Path::new("/etc/ssl/corp-ca.pem"))
+        );
+        assert_eq!(http.timeout, Some(Duration::from_secs(120)));
+        assert_eq!(http.connect_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(
+            config.reporter().final_summary,
+            Some(FinalSummary {
+                slowest: 3,
+                retries: false,
+            }),
+        );
+        assert_eq!(
+            *config.ui(),
+            UiConfig {
+                symbols: Some(Symbols::Ascii),
+                duration_format: DurationFormat::Iso8601,
+                thousands_separator: true,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_user_config_unknown_key() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[http]\nproxxy = \"http://proxy\"\n").unwrap();
+
+        let error = UserConfig::from_path(&path).expect_err("unknown key is rejected");
+        let source = std::error::Error::source(&error).unwrap().to_string();
+        assert!(source.contains("unknown field `proxxy`"), "{source}");
+    }
+
+    #[test]
+    fn parse_user_config_invalid_symbols() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[ui]\nsymbols = \"emoji\"\n").unwrap();
+
+        let error = UserConfig::from_path(&path).expect_err("unknown symbols are rejected");
+        let source = std::error::Error::source(&error).unwrap().to_string();
+        assert!(source.contains("unknown variant `emoji`"), "{source}");
+    }
+
+    #[cfg(feature = "self-update")]
+    #[test]
+    fn http_proxies() {
+        let env_var = |name: &str| match name {
+            "https_proxy" => Some("http://env-proxy.example.com:3128".to_owned()),
+            "HTTP_PROXY" => Some(String::new()),
+            _ => None,
+        };
+
+        let mut http = HttpConfig {
+            no_proxy: Some("internal.example.com".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            http.proxies(env_var),
+            vec![(
+                ProxyKind::Https,
+                "http://env-proxy.example.com:3128".to_owned()
+            )],
+            "with no-proxy set, proxies from the environment are set up so it applies to them"
+        );
+        http.client().expect("client with no-proxy is built");
+
+        http.no_proxy = None;
+        assert!(
+            http.proxies(env_var).is_empty(),
+            "without no-proxy, reqwest picks up proxies from the environment itself"
+        );
+
+        http.proxy = Some("http://proxy.example.com:3128".to_owned());
+        assert_eq!(
+            http.proxies(env_var),
+            vec![(ProxyKind::All, "http://proxy.example.com:3128".to_owned())],
+            "the configured proxy takes precedence over the environment"
+        );
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Structured warnings.
+//!
+//! Nextest produces warnings for situations that are likely to be mistakes but
+//! don't prevent it from doing its job, such as unknown configuration keys.
+//! Each kind of warning has a [`WarningCode`], which is shown alongside the
+//! warning.
+//!
+//! Warning codes are recorded for the lifetime of the process as they're
+//! emitted, so that `cargo nextest --deny-warnings` can turn them into errors
+//! at the end of a command.
+
+use crate::errors::UnknownWarningCode;
+use std::{collections::BTreeSet, fmt, str::FromStr, sync::Mutex};
+use tracing::warn;
+
+/// A code identifying a kind of warning produced by nextest.
+///
+/// Codes are displayed and parsed in kebab-case, e.g. `unknown-config-keys`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum WarningCode {
+    /// A config file contained keys nextest doesn't know about.
+    UnknownConfigKeys,
+
+    /// A config file defined a profile in the reserved `default-` namespace.
+    UnknownDefaultProfiles,
+
+    /// A `suppress-env` pattern can't match any variable nextest sets.
+    UnmatchedSuppressEnv,
+
+    /// An environment variable that no longer has any effect was set.
+    ObsoleteEnvVar,
+
+    /// The current version of nextest is older than the recommended version.
+    RecommendedVersionNotMet,
+
+    /// Paths couldn't be included while creating an archive.
+    ArchiveInclude,
+
+    /// An archive was built from a different source tree than the current
+    /// workspace.
+    ArchiveSourceMismatch,
+
+    /// A build script's output directory or output couldn't be read.
+    BuildScriptOutput,
+
+    /// The rustc libdir for a platform couldn't be found.
+    RustcLibdirNotFound,
+}
+
+impl WarningCode {
+    /// All known warning codes.
+    pub const ALL: &'static [Self] = &[
+        Self::UnknownConfigKeys,
+        Self::UnknownDefaultProfiles,
+        Self::UnmatchedSuppressEnv,
+        Self::ObsoleteEnvVar,
+        Self::RecommendedVersionNotMet,
+        Self::ArchiveInclude,
+        Self::ArchiveSourceMismatch,
+        Self::BuildScriptOutput,
+        Self::RustcLibdirNotFound,
+    ];
+
+    /// Returns the kebab-case string for this code.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UnknownConfigKeys => "unknown-config-keys",
+            Self::UnknownDefaultProfiles => "unknown-default-profiles",
+            Self::UnmatchedSuppressEnv => "unmatched-suppress-env",
+            Self::ObsoleteEnvVar => "obsolete-env-var",
+            Self::RecommendedVersionNotMet => "recommended-version-not-met",
+            Self::ArchiveInclude => "archive-include",
+            Self::ArchiveSourceMismatch => "archive-source-mismatch",
+            Self::BuildScriptOutput => "build-script-output",
+            Self::RustcLibdirNotFound => "rustc-libdir-not-found",
+        }
+    }
+}
+
+impl fmt::Display for WarningCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WarningCode {
+    type Err = UnknownWarningCode;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|code| code.as_str() == s)
+            .ok_or_else(|| UnknownWarningCode::new(s))
+    }
+}
+
+static EMITTED: Mutex<BTreeSet<WarningCode>> = Mutex::new(BTreeSet::new());
+
+/// Logs a warning with the given code, and records the code as emitted.
+///
+/// The code is shown in brackets after the message.
+pub fn emit(code: WarningCode, message: fmt::Arguments<'_>) {
+    record(code);
+    warn!("{message} [{code}]");
+}
+
+/// Records a warning code as emitted, without logging anything.
+///
+/// This is for warnings that are written out by a reporter rather than
+/// through [`tracing`].
+pub fn record(code: WarningCode) {
+    EMITTED
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .insert(code);
+}
+
+/// Returns the set of warning codes emitted so far by this process.
+pub fn emitted() -> BTreeSet<WarningCode> {
+    EMITTED
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warning_code_roundtrip() {
+        for &code in WarningCode::ALL {
+            assert_eq!(code.to_string().parse::<WarningCode>().unwrap(), code);
+        }
+
+        let error = "unknown-keys".parse::<WarningCode>().unwrap_err();
+        assert_eq!(error.input, "unknown-keys");
+    }
+}
@@ -11,7 +11,7 @@ use fixture_data::{
 use nextest_filtering::{Filterset, FiltersetKind, ParseContext};
 use nextest_metadata::{FilterMatch, MismatchReason};
 use nextest_runner::{
-    config::{NextestConfig, RetryPolicy},
+    config::{MaxFail, MaxFailScope, NextestConfig, RetryPolicy, TestThreads},
     double_spawn::DoubleSpawnInfo,
     input::InputHandlerKind,
     list::BinaryList,
@@ -26,10 +26,13 @@ use nextest_runner::{
     signal::SignalHandlerKind,
     target_runner::TargetRunner,
     test_filter::{RunIgnored, TestFilterBuilder, TestFilterPatterns},
-    test_output::{ChildExecutionOutput, ChildOutput},
+    test_output::{CaptureStrategy, ChildExecutionOutput, ChildOutput},
 };
 use pretty_assertions::assert_eq;
-use std::{io::Cursor, time::Duration};
+use std::{
+    io::Cursor,
+    time::{Duration, Instant},
+};
 use test_case::test_case;
 
 #[test]
@@ -310,6 +313,107 @@ fn test_run_ignored() -> Result<()> {
     Ok(())
 }
 
+/// Test that once the max-fail limit is hit, only the remaining tests in the failing scope are
+/// skipped.
+#[test_case(MaxFailScope::Binary ; "binary scope")]
+#[test_case(MaxFailScope::Package ; "package scope")]
+fn test_max_fail_scope(scope: MaxFailScope) -> Result<()> {
+    set_env_vars();
+
+    // nextest-tests::basic has several failing tests, while nextest-tests::other only has a
+    // passing one. Both binaries are in the same package.
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse(
+        "binary_id(nextest-tests::basic) | binary_id(nextest-tests::other)".to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    // With a single test thread, each test's result is processed before the next test is started,
+    // so every test scheduled after the first failure in the scope is skipped.
+    let mut builder = TestRunnerBuilder::default();
+    builder
+        .set_max_fail(MaxFail::Count(1))
+        .set_max_fail_scope(scope)
+        .set_test_threads(TestThreads::Count(1));
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+
+    let mut failed = 0;
+    let mut scope_cancelled = 0;
+    for (&(_, name), instance_value) in &instance_statuses {
+        match &instance_value.status {
+            InstanceStatus::Skipped(MismatchReason::ScopeCancelled) => {
+                // With a package scope, nextest-tests::other is cancelled along with
+                // nextest-tests::basic.
+                if scope == MaxFailScope::Binary {
+                    assert_eq!(
+                        instance_value.binary_id, "nextest-tests::basic",
+                        "{name} is in the cancelled scope"
+                    );
+                }
+                scope_cancelled += 1;
+            }
+            InstanceStatus::Skipped(_) => {}
+            InstanceStatus::Finished(run_statuses) => {
+                if !run_statuses.last_status().result.is_success() {
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    assert_eq!(failed, 1, "only the first failure in the scope is run");
+    assert!(scope_cancelled > 0, "tests after the failure were skipped");
+    assert_eq!(
+        run_stats.skipped_by_reason.scope_cancelled, scope_cancelled,
+        "skipped tests are counted by reason"
+    );
+    assert_eq!(
+        run_stats.finished_count + scope_cancelled,
+        run_stats.initial_run_count,
+        "every scheduled test either finished or was skipped"
+    );
+    assert_eq!(
+        run_stats.summarize_final(),
+        FinalRunStats::Failed(RunStatsFailureKind::Test {
+            initial_run_count: run_stats.initial_run_count,
+            not_run: 0,
+        }),
+        "tests skipped because of the scope aren't counted as not run"
+    );
+
+    Ok(())
+}
+
 /// Test that filtersets with regular substring filters behave as expected.
 #[test]
 fn test_filter_expr_with_string_filters() -> Result<()> {
@@ -612,6 +716,249 @@ fn test_retries(retries: Option<RetryPolicy>) -> Result<()> {
     Ok(())
 }
 
+/// Test that a test waiting out the delay before a retry doesn't hold on to a test slot.
+#[test]
+fn test_retry_delay_releases_slot() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    // test_flaky_mod_4 is sorted (and so started) before test_success.
+    let expr = Filterset::parse(
+        "binary_id(nextest-tests::basic) & (test(=test_flaky_mod_4) | test(=test_success))"
+            .to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let delay = Duration::from_secs(2);
+    let mut builder = TestRunnerBuilder::default();
+    builder
+        .set_retries(RetryPolicy::Fixed {
+            count: 1,
+            delay,
+            jitter: false,
+        })
+        .set_test_threads(TestThreads::Count(1));
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+
+    let finished = |test_name: &str| {
+        let (_, instance_value) = instance_statuses
+            .iter()
+            .find(|(&(_, name), _)| name == test_name)
+            .unwrap_or_else(|| panic!("{test_name} should be present"));
+        match &instance_value.status {
+            InstanceStatus::Finished(run_statuses) => run_statuses.clone(),
+            InstanceStatus::Skipped(reason) => panic!("{test_name} was skipped: {reason}"),
+        }
+    };
+
+    let flaky_statuses = finished("test_flaky_mod_4");
+    assert_eq!(flaky_statuses.len(), 2, "test_flaky_mod_4 was retried once");
+    let mut flaky_iter = flaky_statuses.iter();
+    let first_attempt = flaky_iter.next().unwrap();
+    let retry = flaky_iter.next().unwrap();
+    assert_eq!(
+        retry.delay_before_start, delay,
+        "retry waited for the delay"
+    );
+
+    let success_statuses = finished("test_success");
+    let success = success_statuses.last_status();
+    assert!(
+        success.start_time >= first_attempt.start_time + first_attempt.time_taken,
+        "with one test thread, test_success started after the first attempt finished"
+    );
+    assert!(
+        success.start_time + success.time_taken <= retry.start_time,
+        "test_success ran while the retry was waiting out its delay, \
+         so the retry didn't hold on to the only test slot"
+    );
+
+    assert_eq!(run_stats.finished_count, 2, "both tests finished");
+    Ok(())
+}
+
+/// Test that cancelling the run ends it promptly, even if a retry is waiting out its delay.
+#[test]
+fn test_cancel_during_retry_delay() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    // test_flaky_mod_4 fails and waits 30 seconds before being retried. In the meantime,
+    // test_result_failure fails, which triggers fail-fast.
+    let expr = Filterset::parse(
+        "binary_id(nextest-tests::basic) & (test(=test_flaky_mod_4) | test(=test_result_failure))"
+            .to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-retry-delay")
+        .expect("with-retry-delay config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_test_threads(TestThreads::Count(1));
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let start = Instant::now();
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    let elapsed = start.elapsed();
+
+    assert!(
+        elapsed < Duration::from_secs(20),
+        "run should end without waiting for the retry delay, but took {elapsed:?}"
+    );
+
+    for (&(_, name), instance_value) in &instance_statuses {
+        if let InstanceStatus::Finished(run_statuses) = &instance_value.status {
+            assert_eq!(
+                run_statuses.len(),
+                1,
+                "{name} should not have been retried after the run was cancelled"
+            );
+        }
+    }
+    assert!(
+        matches!(
+            run_stats.summarize_final(),
+            FinalRunStats::Failed(RunStatsFailureKind::Test { .. })
+        ),
+        "run should be marked failed, but got {:?}",
+        run_stats.summarize_final(),
+    );
+
+    Ok(())
+}
+
+#[test_case(CaptureStrategy::Split, true ; "split")]
+#[test_case(CaptureStrategy::None, false ; "no capture")]
+fn test_startup_time(capture_strategy: CaptureStrategy, expect_measured: bool) -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse(
+        "binary_id(nextest-tests::basic) & test(=test_success)".to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_capture_strategy(capture_strategy);
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, _) = execute_collect(runner);
+
+    let (_, instance_value) = instance_statuses
+        .iter()
+        .find(|(&(_, name), _)| name == "test_success")
+        .expect("test_success should be present");
+    let InstanceStatus::Finished(run_statuses) = &instance_value.status else {
+        panic!("test_success should have been run");
+    };
+    let status = run_statuses.last_status();
+    match status.startup_time {
+        Some(startup_time) => {
+            assert!(
+                expect_measured,
+                "startup time is not measured without capture"
+            );
+            assert!(
+                startup_time <= status.time_taken,
+                "startup time {startup_time:?} is within the test's time taken {:?}",
+                status.time_taken,
+            );
+        }
+        None => {
+            assert!(
+                !expect_measured,
+                "startup time is measured from the test's first output"
+            );
+        }
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_termination() -> Result<()> {
     set_env_vars();
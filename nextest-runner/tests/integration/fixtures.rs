@@ -267,6 +267,7 @@ impl FixtureTargets {
             &ecx,
             FilterBound::All,
             get_num_cpus(),
+            false,
         )
         .context("Failed to make test list")
     }